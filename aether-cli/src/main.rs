@@ -0,0 +1,882 @@
+//! Aether CLI - emit, tail, and inspect waves from the command line
+//!
+//! Uses the same config files as the services (`config/default.toml`, `config/aether-cli.toml`)
+
+use aether_core::{
+    control_channel, doctor, load_config, tail_channel, Aether, AppConfig, AuditLog, AuditQuery,
+    CausalTree, Channel, ControlResponse, EmitTransport, Keyring, RecoveryMode, Wave,
+    WaveComparison, WaveQuery, WaveRecorder, WaveStore, WaveType,
+};
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand};
+use std::time::Duration;
+
+/// Lease asked for per `tail_waves`; renewed three times per lease while following
+const TAIL_LEASE: Duration = Duration::from_secs(30);
+
+/// How long the service holding a store's lease gets to checkpoint it
+const CHECKPOINT_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Parser)]
+#[command(name = "aether-cli", about = "Emit, tail, and inspect Aether waves")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Emit a wave with a JSON payload
+    Emit {
+        channel: String,
+        payload: String,
+        /// Wave type (event, command, query, response, broadcast)
+        #[arg(long, default_value = "event")]
+        wave_type: String,
+        #[arg(long)]
+        amplitude: Option<f64>,
+    },
+    /// Print waves matching a channel pattern as NDJSON
+    Tail { pattern: String },
+    /// Record waves matching a pattern to NDJSON (or a WaveStore with --store)
+    Record {
+        pattern: String,
+        path: String,
+        /// Fraction of waves to keep (0.0..=1.0)
+        #[arg(long, default_value_t = 1.0)]
+        sample_rate: f64,
+        /// Write a WaveStore directory instead of NDJSON
+        #[arg(long)]
+        store: bool,
+    },
+    /// Search the waves a service (the gateway) indexed, printed as NDJSON
+    ///
+    /// `--follow` keeps printing new matches, like `tail -f`.
+    Search {
+        /// Channel name or pattern
+        #[arg(long)]
+        channel: Option<String>,
+        /// Emitting service
+        #[arg(long)]
+        source: Option<String>,
+        /// Substring of the payload
+        #[arg(long)]
+        contains: Option<String>,
+        /// RFC 3339 timestamp of the earliest wave
+        #[arg(long)]
+        since: Option<String>,
+        /// Only the newest N matches
+        #[arg(long)]
+        limit: Option<usize>,
+        #[arg(long)]
+        follow: bool,
+        /// Service whose wave index is searched
+        #[arg(long, default_value = "aether-gateway")]
+        service: String,
+        #[arg(long, default_value_t = 2000)]
+        timeout_ms: u64,
+    },
+    /// Request live statistics from a service via its control channel
+    Stats {
+        service: String,
+        #[arg(long, default_value_t = 2000)]
+        timeout_ms: u64,
+    },
+    /// Route a share of a service's channel to instances of one version
+    ///
+    /// Every instance of the service answers; `--clear` removes the rule.
+    Rollout {
+        service: String,
+        channel: String,
+        /// Version label receiving the share (`service.version` of those instances)
+        #[arg(long, required_unless_present = "clear")]
+        version: Option<String>,
+        /// Share of waves in percent
+        #[arg(long, default_value_t = 0.0)]
+        percent: f64,
+        #[arg(long)]
+        clear: bool,
+        #[arg(long, default_value_t = 2000)]
+        timeout_ms: u64,
+    },
+    /// Stop a service's wave intake; subscriptions stay open and waves are buffered
+    ///
+    /// Every instance of the service answers.
+    Pause {
+        service: String,
+        #[arg(long, default_value_t = 2000)]
+        timeout_ms: u64,
+    },
+    /// Resume wave intake paused with `pause`
+    Resume {
+        service: String,
+        #[arg(long, default_value_t = 2000)]
+        timeout_ms: u64,
+    },
+    /// Drain a service before a deploy: readiness turns false, in-flight work
+    /// finishes, subscriptions are dropped and pending writes flushed, then it exits
+    Drain {
+        service: String,
+        #[arg(long, default_value_t = 2000)]
+        timeout_ms: u64,
+    },
+    /// Re-emit persisted waves recorded at or after a timestamp
+    Replay {
+        /// RFC 3339 timestamp (e.g. 2024-05-01T14:00:00Z)
+        #[arg(long)]
+        from: String,
+        /// Only replay waves matching this channel pattern
+        #[arg(long)]
+        channel: Option<String>,
+        /// Store path (defaults to `aether.persistence_path`)
+        #[arg(long)]
+        store: Option<String>,
+        /// Print waves instead of emitting them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show the tree of waves one wave triggered, or compare two waves
+    ///
+    /// With one ID, follows causation links through the log; with two, prints
+    /// their payload diff, shared causes and the time between them as JSON.
+    Inspect {
+        wave_id: String,
+        other_id: Option<String>,
+        /// Store path (defaults to `aether.persistence_path`)
+        #[arg(long)]
+        store: Option<String>,
+        /// Print the tree as JSON instead of indented lines
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check a service's config against this host and print the report as JSON
+    ///
+    /// Run it where the service will start, before starting it: the port
+    /// checks fail while the service holds them. Exits non-zero if any check failed.
+    Doctor {
+        /// Service whose config is checked (defaults to the CLI's own)
+        #[arg(long)]
+        service: Option<String>,
+    },
+    /// Inspect a persistence store
+    Store {
+        #[command(subcommand)]
+        command: StoreCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum StoreCommand {
+    /// Show log size, last index, snapshot, and per-channel counts
+    Inspect { path: String },
+    /// Check every log entry's checksum and report corrupt ones
+    Verify {
+        path: String,
+        /// Move corrupt entries out of the log into the quarantine tree
+        #[arg(long)]
+        quarantine: bool,
+    },
+    /// Rebuild the wave ID, channel and correlation indices from the log
+    Reindex { path: String },
+    /// Write the log, audit trail, snapshot and cursors to a checksummed archive,
+    /// e.g. before an upgrade; waves of an encrypted store stay sealed
+    Export {
+        path: String,
+        archive: String,
+        /// Write waves in the clear so the archive restores without the store's keys
+        #[arg(long)]
+        decrypt: bool,
+    },
+    /// Restore an archive into an empty store, verifying its checksum first
+    Import { path: String, archive: String },
+    /// Seal every log entry with the active `aether.persistence_encryption` key
+    ///
+    /// Run after rotating keys; older keys can be removed once it finishes.
+    Reencrypt { path: String },
+    /// Print audit events as NDJSON and check the hash chain
+    Audit {
+        path: String,
+        /// Only this kind (auth_failure, acl_denial, config_reload, control_command, ...)
+        #[arg(long)]
+        kind: Option<String>,
+        /// RFC 3339 timestamp of the earliest event
+        #[arg(long)]
+        since: Option<String>,
+        /// Only the newest N events
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("warn")),
+        )
+        .init();
+
+    let cli = Cli::parse();
+    let app_config = load_config("aether-cli").context("failed to load config")?;
+
+    match cli.command {
+        Command::Emit {
+            channel,
+            payload,
+            wave_type,
+            amplitude,
+        } => emit(&app_config, channel, &payload, &wave_type, amplitude).await,
+        Command::Tail { pattern } => tail(&app_config, pattern).await,
+        Command::Record {
+            pattern,
+            path,
+            sample_rate,
+            store,
+        } => record(&app_config, pattern, &path, sample_rate, store).await,
+        Command::Search {
+            channel,
+            source,
+            contains,
+            since,
+            limit,
+            follow,
+            service,
+            timeout_ms,
+        } => {
+            let query = WaveQuery {
+                channel,
+                source,
+                contains,
+                since: parse_since(since.as_deref())?,
+                limit,
+            };
+            let timeout = Duration::from_millis(timeout_ms);
+            search(&app_config, &service, query, follow, timeout).await
+        }
+        Command::Stats {
+            service,
+            timeout_ms,
+        } => stats(&app_config, &service, Duration::from_millis(timeout_ms)).await,
+        Command::Rollout {
+            service,
+            channel,
+            version,
+            percent,
+            clear,
+            timeout_ms,
+        } => {
+            let command = match version.filter(|_| !clear) {
+                Some(version) => serde_json::json!({
+                    "command": "set_rollout",
+                    "channel": channel,
+                    "version": version,
+                    "percent": percent,
+                }),
+                None => serde_json::json!({"command": "clear_rollout", "channel": channel}),
+            };
+            broadcast(
+                &app_config,
+                &service,
+                command,
+                Duration::from_millis(timeout_ms),
+            )
+            .await
+        }
+        Command::Pause {
+            service,
+            timeout_ms,
+        } => {
+            let command = serde_json::json!({"command": "pause"});
+            broadcast(
+                &app_config,
+                &service,
+                command,
+                Duration::from_millis(timeout_ms),
+            )
+            .await
+        }
+        Command::Resume {
+            service,
+            timeout_ms,
+        } => {
+            let command = serde_json::json!({"command": "resume"});
+            broadcast(
+                &app_config,
+                &service,
+                command,
+                Duration::from_millis(timeout_ms),
+            )
+            .await
+        }
+        Command::Drain {
+            service,
+            timeout_ms,
+        } => {
+            let command = serde_json::json!({"command": "drain"});
+            broadcast(
+                &app_config,
+                &service,
+                command,
+                Duration::from_millis(timeout_ms),
+            )
+            .await
+        }
+        Command::Replay {
+            from,
+            channel,
+            store,
+            dry_run,
+        } => replay(&app_config, &from, channel, store, dry_run).await,
+        Command::Inspect {
+            wave_id,
+            other_id,
+            store,
+            json,
+        } => inspect(&app_config, &wave_id, other_id.as_deref(), store, json).await,
+        Command::Doctor { service } => run_doctor(app_config, service.as_deref()).await,
+        Command::Store {
+            command: StoreCommand::Inspect { path },
+        } => inspect_store(&app_config, &path).await,
+        Command::Store {
+            command: StoreCommand::Verify { path, quarantine },
+        } => verify_store(&app_config, &path, quarantine).await,
+        Command::Store {
+            command: StoreCommand::Reindex { path },
+        } => reindex_store(&app_config, &path).await,
+        Command::Store {
+            command:
+                StoreCommand::Export {
+                    path,
+                    archive,
+                    decrypt,
+                },
+        } => export_store(&app_config, &path, &archive, decrypt).await,
+        Command::Store {
+            command: StoreCommand::Import { path, archive },
+        } => import_store(&app_config, &path, &archive).await,
+        Command::Store {
+            command: StoreCommand::Reencrypt { path },
+        } => reencrypt_store(&app_config, &path).await,
+        Command::Store {
+            command:
+                StoreCommand::Audit {
+                    path,
+                    kind,
+                    since,
+                    limit,
+                },
+        } => audit_store(&app_config, &path, kind, since, limit).await,
+    }
+}
+
+async fn emit(
+    app_config: &AppConfig,
+    channel: String,
+    payload: &str,
+    wave_type: &str,
+    amplitude: Option<f64>,
+) -> anyhow::Result<()> {
+    let payload: serde_json::Value =
+        serde_json::from_str(payload).context("payload must be valid JSON")?;
+    let aether = Aether::new(app_config.aether_config());
+
+    let mut builder = Wave::builder(Channel::new(channel))
+        .payload(payload)
+        .wave_type(parse_wave_type(wave_type)?)
+        .source(app_config.service.name.clone());
+    if let Some(amplitude) = amplitude {
+        builder = builder.amplitude(amplitude);
+    }
+    let wave = authenticated(app_config, builder.build());
+    let id = *wave.id();
+
+    aether.emit(wave).await?;
+    aether.flush().await?;
+    println!("{}", id);
+    Ok(())
+}
+
+async fn tail(app_config: &AppConfig, pattern: String) -> anyhow::Result<()> {
+    let aether = Aether::new(app_config.aether_config());
+    let mut receiver = aether.subscribe(&Channel::new(pattern)).await;
+
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            wave = receiver.recv() => match wave {
+                Ok(wave) => {
+                    let wave = aether.redactor().redact(&wave);
+                    println!("{}", serde_json::to_string(&wave)?);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    eprintln!("-- skipped {} waves --", skipped);
+                }
+                Err(_) => break,
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn record(
+    app_config: &AppConfig,
+    pattern: String,
+    path: &str,
+    sample_rate: f64,
+    store: bool,
+) -> anyhow::Result<()> {
+    let aether = Aether::new(app_config.aether_config());
+    let recorder = if store {
+        WaveRecorder::wave_store(path)?
+    } else {
+        WaveRecorder::ndjson(path)?
+    }
+    .with_sample_rate(sample_rate)
+    .with_redactor(std::sync::Arc::clone(aether.redactor()));
+
+    let task = recorder.spawn(&aether, Channel::new(pattern)).await;
+    tokio::signal::ctrl_c().await?;
+    task.abort();
+    let _ = task.await;
+    eprintln!("recording saved to {}", path);
+    Ok(())
+}
+
+/// Send a control command; responses arrive on the returned receiver
+async fn send_control(
+    app_config: &AppConfig,
+    service: &str,
+    command: serde_json::Value,
+) -> anyhow::Result<tokio::sync::broadcast::Receiver<Wave>> {
+    let aether = Aether::new(app_config.aether_config());
+    let reply_to = control_channel(service).child(&format!("cli{}", uuid::Uuid::new_v4().simple()));
+    let replies = aether.subscribe(&reply_to).await;
+
+    let mut wave = Wave::builder(control_channel(service))
+        .wave_type(WaveType::Command)
+        .payload(command)
+        .metadata(serde_json::json!({"reply_to": reply_to.name()}))
+        .source(app_config.service.name.clone())
+        .build();
+    if let Some(token) = &app_config.aether.auth_token {
+        wave.set_auth_token(token.clone());
+    }
+    if let Some(token) = app_config
+        .control
+        .auth_token
+        .clone()
+        .or_else(|| app_config.aether.auth_token.clone())
+    {
+        wave.set_control_token(token);
+    }
+    aether.emit(wave).await?;
+    aether.flush().await?;
+    Ok(replies)
+}
+
+/// Send a control command and wait for the first instance's successful reply
+async fn request(
+    app_config: &AppConfig,
+    service: &str,
+    command: serde_json::Value,
+    timeout: Duration,
+) -> anyhow::Result<serde_json::Value> {
+    let name = command["command"].as_str().unwrap_or("control").to_string();
+    let mut replies = send_control(app_config, service, command).await?;
+    let reply = tokio::time::timeout(timeout, replies.recv())
+        .await
+        .map_err(|_| anyhow!("no reply from {} within {:?}", service, timeout))??;
+    let response: ControlResponse = serde_json::from_value(reply.payload().clone())?;
+    if !response.ok {
+        return Err(anyhow!(
+            "{} rejected {} request: {}",
+            service,
+            name,
+            response.detail
+        ));
+    }
+    Ok(response.detail)
+}
+
+async fn stats(app_config: &AppConfig, service: &str, timeout: Duration) -> anyhow::Result<()> {
+    let command = serde_json::json!({"command": "dump_stats"});
+    let detail = request(app_config, service, command, timeout).await?;
+    println!("{}", serde_json::to_string_pretty(&detail)?);
+    Ok(())
+}
+
+async fn search(
+    app_config: &AppConfig,
+    service: &str,
+    query: WaveQuery,
+    follow: bool,
+    timeout: Duration,
+) -> anyhow::Result<()> {
+    let command = serde_json::json!({"command": "search_waves", "query": query});
+    let detail = request(app_config, service, command, timeout).await?;
+    for wave in detail["waves"].as_array().into_iter().flatten() {
+        println!("{}", serde_json::to_string(wave)?);
+    }
+    if !follow {
+        return Ok(());
+    }
+
+    let aether = Aether::new(app_config.aether_config());
+    let stream = tail_channel(&format!("cli{}", uuid::Uuid::new_v4().simple()));
+    let mut tailed = aether.subscribe(&stream).await;
+    let command = serde_json::json!({
+        "command": "tail_waves",
+        "query": WaveQuery { limit: None, ..query },
+        "stream": stream.name(),
+        "duration_ms": TAIL_LEASE.as_millis() as u64,
+    });
+    let mut renew = tokio::time::interval(TAIL_LEASE / 3);
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            _ = renew.tick() => {
+                request(app_config, service, command.clone(), timeout).await?;
+            }
+            wave = tailed.recv() => match wave {
+                Ok(wave) => println!("{}", serde_json::to_string(wave.payload())?),
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    eprintln!("-- skipped {} waves --", skipped);
+                }
+                Err(_) => break,
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Send a control command to every instance and print each reply
+async fn broadcast(
+    app_config: &AppConfig,
+    service: &str,
+    command: serde_json::Value,
+    timeout: Duration,
+) -> anyhow::Result<()> {
+    let mut replies = send_control(app_config, service, command).await?;
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut answered = 0;
+    let mut failed = 0;
+    while let Ok(Ok(reply)) = tokio::time::timeout_at(deadline, replies.recv()).await {
+        let response: ControlResponse = serde_json::from_value(reply.payload().clone())?;
+        answered += 1;
+        if !response.ok {
+            failed += 1;
+        }
+        println!("{}", serde_json::to_string(&response)?);
+    }
+    match (answered, failed) {
+        (0, _) => Err(anyhow!("no reply from {} within {:?}", service, timeout)),
+        (_, 0) => Ok(()),
+        (answered, failed) => Err(anyhow!("{} of {} instances rejected", failed, answered)),
+    }
+}
+
+async fn replay(
+    app_config: &AppConfig,
+    from: &str,
+    channel: Option<String>,
+    store: Option<String>,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let from: DateTime<Utc> = DateTime::parse_from_rfc3339(from)
+        .context("--from must be an RFC 3339 timestamp")?
+        .with_timezone(&Utc);
+    let path = store.unwrap_or_else(|| app_config.aether.persistence_path.clone());
+    let store = open_store(app_config, StoreAccess::ReadOnly, &path).await?;
+    let pattern = channel.map(Channel::new);
+
+    // Persistence is disabled for the replaying layer so waves are not logged twice
+    let mut aether_config = app_config.aether_config();
+    aether_config.persistence_enabled = false;
+    let aether = Aether::new(aether_config);
+
+    // A concrete channel is read through the store's channel index
+    let waves = match &pattern {
+        Some(channel) if !channel.is_wildcard() => store
+            .read_channel(channel.name(), 0)?
+            .into_iter()
+            .map(|(_, wave)| wave)
+            .collect(),
+        _ => store.read_from(0)?,
+    };
+    let (mut replayed, mut dropped) = (0, 0);
+    for mut wave in waves {
+        if wave.timestamp() < &from {
+            continue;
+        }
+        if let Some(pattern) = &pattern {
+            if !wave.channel().matches(pattern) {
+                continue;
+            }
+        }
+
+        // Stored hop counts and trails belong to the original delivery; keep
+        // them from dead-lettering the replay or reading as loops
+        wave.reset_propagation();
+        wave.clear_breadcrumbs();
+        if dry_run {
+            println!("{}", serde_json::to_string(&wave)?);
+        } else {
+            let receipt = aether.emit(authenticated(app_config, wave)).await?;
+            if receipt.transport == EmitTransport::Dropped {
+                dropped += 1;
+                continue;
+            }
+        }
+        replayed += 1;
+    }
+    aether.flush().await?;
+    eprintln!("replayed {} waves", replayed);
+    if dropped > 0 {
+        eprintln!("{} waves were dropped by the layer", dropped);
+    }
+    Ok(())
+}
+
+async fn inspect(
+    app_config: &AppConfig,
+    wave_id: &str,
+    other_id: Option<&str>,
+    store: Option<String>,
+    json: bool,
+) -> anyhow::Result<()> {
+    let parse = |id: &str| {
+        id.parse()
+            .with_context(|| format!("invalid wave ID {}", id))
+    };
+    let wave_id = parse(wave_id)?;
+    let path = store.unwrap_or_else(|| app_config.aether.persistence_path.clone());
+    let store = open_store(app_config, StoreAccess::ReadOnly, &path).await?;
+
+    if let Some(other_id) = other_id {
+        let comparison = WaveComparison::between(&store, &wave_id, &parse(other_id)?)?;
+        println!("{}", serde_json::to_string_pretty(&comparison)?);
+        return Ok(());
+    }
+    let tree = CausalTree::build(&store, &wave_id)?
+        .ok_or_else(|| anyhow!("wave {} is not in the log", wave_id))?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&tree)?);
+    } else {
+        print!("{}", tree.render());
+    }
+    Ok(())
+}
+
+async fn inspect_store(app_config: &AppConfig, path: &str) -> anyhow::Result<()> {
+    let store = open_store(app_config, StoreAccess::ReadOnly, path).await?;
+
+    let mut channels = std::collections::BTreeMap::<String, u64>::new();
+    for wave in store.read_from(0)? {
+        *channels
+            .entry(wave.channel().name().to_string())
+            .or_default() += 1;
+    }
+
+    let report = serde_json::json!({
+        "path": path,
+        "waves": store.len(),
+        "last_index": store.last_index()?,
+        "snapshot": store.load_snapshot()?,
+        "channels": channels,
+    });
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+async fn run_doctor(app_config: AppConfig, service: Option<&str>) -> anyhow::Result<()> {
+    let app_config = match service {
+        Some(service) => load_config(service)
+            .with_context(|| format!("failed to load config for {}", service))?,
+        None => app_config,
+    };
+    let report = doctor(&app_config).await;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    if !report.passed() {
+        return Err(anyhow!("startup self-test failed"));
+    }
+    Ok(())
+}
+
+async fn verify_store(app_config: &AppConfig, path: &str, quarantine: bool) -> anyhow::Result<()> {
+    let access = if quarantine {
+        StoreAccess::Write
+    } else {
+        StoreAccess::ReadOnly
+    };
+    let store = open_store(app_config, access, path).await?;
+    let report = if quarantine {
+        store.recover_from(0, RecoveryMode::Quarantine)?.1
+    } else {
+        store.verify()?
+    };
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    store.flush()?;
+    if !report.is_clean() && !quarantine {
+        return Err(anyhow!("{} corrupt entries", report.corrupt.len()));
+    }
+    Ok(())
+}
+
+async fn reindex_store(app_config: &AppConfig, path: &str) -> anyhow::Result<()> {
+    let store = open_store(app_config, StoreAccess::Write, path).await?;
+    let indexed = store.reindex()?;
+    store.flush()?;
+    eprintln!("indexed {} waves", indexed);
+    Ok(())
+}
+
+async fn export_store(
+    app_config: &AppConfig,
+    path: &str,
+    archive: &str,
+    decrypt: bool,
+) -> anyhow::Result<()> {
+    let store = open_store(app_config, StoreAccess::ReadOnly, path).await?;
+    let summary = if decrypt {
+        store.export_decrypted(archive)
+    } else {
+        store.export(archive)
+    };
+    let summary = summary.with_context(|| format!("failed to export {} to {}", path, archive))?;
+    println!("{}", serde_json::to_string_pretty(&summary)?);
+    if summary.skipped > 0 {
+        eprintln!("left {} corrupt entries out of the archive", summary.skipped);
+    }
+    Ok(())
+}
+
+async fn import_store(app_config: &AppConfig, path: &str, archive: &str) -> anyhow::Result<()> {
+    let store = open_store(app_config, StoreAccess::Write, path).await?;
+    let summary = store
+        .import(archive)
+        .with_context(|| format!("failed to import {} into {}", archive, path))?;
+    println!("{}", serde_json::to_string_pretty(&summary)?);
+    Ok(())
+}
+
+async fn reencrypt_store(app_config: &AppConfig, path: &str) -> anyhow::Result<()> {
+    let store = open_store(app_config, StoreAccess::Write, path).await?;
+    let rewritten = store.reencrypt()?;
+    store.flush()?;
+    eprintln!("re-encrypted {} entries", rewritten);
+    Ok(())
+}
+
+/// How a command opens a wave store
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StoreAccess {
+    /// In place, or a checkpoint from the service holding the writer lease
+    ReadOnly,
+    /// The store itself, taking the writer lease
+    Write,
+}
+
+/// Open a store with the keys from `aether.persistence_encryption`, if any
+async fn open_store(
+    app_config: &AppConfig,
+    access: StoreAccess,
+    path: &str,
+) -> anyhow::Result<WaveStore> {
+    let keyring = app_config
+        .aether
+        .persistence_encryption
+        .as_ref()
+        .map(Keyring::from_config)
+        .transpose()
+        .context("failed to load store keys")?;
+    let store = match (access, keyring) {
+        (StoreAccess::ReadOnly, keyring) => match WaveStore::lease_holder(path)? {
+            Some(holder) => {
+                let service = holder.service.clone().ok_or_else(|| {
+                    anyhow!(
+                        "store {} is open for writing by {}, which has no control plane \
+                         to checkpoint it",
+                        path,
+                        holder
+                    )
+                })?;
+                let checkpoint = std::env::temp_dir()
+                    .join(format!("aether-checkpoint-{}", uuid::Uuid::new_v4()));
+                let command = serde_json::json!({
+                    "command": "checkpoint_store",
+                    "path": checkpoint.to_string_lossy(),
+                });
+                request(app_config, &service, command, CHECKPOINT_TIMEOUT)
+                    .await
+                    .with_context(|| format!("failed to checkpoint store {}", path))?;
+                WaveStore::open_checkpoint(&checkpoint, keyring)
+            }
+            None => WaveStore::open_read_only(path, keyring),
+        },
+        (StoreAccess::Write, Some(keyring)) => {
+            let flush_interval =
+                Duration::from_millis(app_config.aether.persistence_flush_interval_ms);
+            WaveStore::open_encrypted(path, flush_interval, keyring)
+        }
+        (StoreAccess::Write, None) => WaveStore::open(path),
+    };
+    store.with_context(|| format!("failed to open store {}", path))
+}
+
+async fn audit_store(
+    app_config: &AppConfig,
+    path: &str,
+    kind: Option<String>,
+    since: Option<String>,
+    limit: Option<usize>,
+) -> anyhow::Result<()> {
+    let store = open_store(app_config, StoreAccess::ReadOnly, path).await?;
+    let audit = AuditLog::open(&store, app_config.aether.audit.load_key()?)?;
+    let query = AuditQuery {
+        kind: kind.as_deref().map(str::parse).transpose()?,
+        since: parse_since(since.as_deref())?,
+        limit,
+        ..AuditQuery::default()
+    };
+    for event in audit.query(&query)? {
+        println!("{}", serde_json::to_string(&event)?);
+    }
+    match audit.verify()? {
+        None => {
+            eprintln!("audit chain intact ({} events)", audit.len());
+            Ok(())
+        }
+        Some(index) => Err(anyhow!("audit chain broken at event {}", index)),
+    }
+}
+
+fn parse_since(since: Option<&str>) -> anyhow::Result<Option<DateTime<Utc>>> {
+    since
+        .map(|since| {
+            DateTime::parse_from_rfc3339(since)
+                .map(|since| since.with_timezone(&Utc))
+                .with_context(|| format!("invalid --since timestamp {}", since))
+        })
+        .transpose()
+}
+
+fn authenticated(app_config: &AppConfig, mut wave: Wave) -> Wave {
+    if let Some(token) = &app_config.aether.auth_token {
+        wave.set_auth_token(token.clone());
+    }
+    wave
+}
+
+fn parse_wave_type(value: &str) -> anyhow::Result<WaveType> {
+    match value.to_ascii_lowercase().as_str() {
+        "event" => Ok(WaveType::Event),
+        "command" => Ok(WaveType::Command),
+        "query" => Ok(WaveType::Query),
+        "response" => Ok(WaveType::Response),
+        "broadcast" => Ok(WaveType::Broadcast),
+        other => Err(anyhow!("unknown wave type: {}", other)),
+    }
+}
@@ -1,24 +1,365 @@
-use aether_core::{Aether, AetherConfig, Channel, Wave};
-use criterion::{criterion_group, criterion_main, Criterion};
+//! Aether benchmark suite
+//!
+//! Compare against a saved baseline with
+//! `cargo bench -p aether-core -- --save-baseline main` and later
+//! `cargo bench -p aether-core -- --baseline main`. The NATS group runs
+//! against `AETHER_BENCH_NATS_URL` (default `nats://127.0.0.1:4222`) and is
+//! skipped when no server answers.
 
-fn bench_emit(c: &mut Criterion) {
-    let rt = tokio::runtime::Runtime::new().unwrap();
-    let aether = Aether::new(AetherConfig {
+use aether_core::{
+    Aether, AetherConfig, AetherShardSet, Channel, Durability, Vibrator, VibratorConfig, Wave,
+    WaveCodec,
+};
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+fn memory_layer() -> Aether {
+    Aether::new(AetherConfig {
         use_nats: false,
         ..AetherConfig::default()
+    })
+}
+
+fn bench_wave(channel: impl Into<Channel>) -> Wave {
+    Wave::builder(channel)
+        .payload(serde_json::json!({"data": "x"}))
+        .build()
+}
+
+/// Read whatever is queued so receivers never lag
+fn drain(receiver: &mut broadcast::Receiver<Wave>) {
+    while !matches!(
+        receiver.try_recv(),
+        Err(broadcast::error::TryRecvError::Empty | broadcast::error::TryRecvError::Closed)
+    ) {}
+}
+
+/// One emit fanned out to a growing number of subscribers
+fn bench_emit_subscribers(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let mut group = c.benchmark_group("emit_subscribers");
+    group.throughput(Throughput::Elements(1));
+
+    for subscribers in [0usize, 1, 8, 64] {
+        let aether = memory_layer();
+        let channel = Channel::new("bench.emit");
+        let mut receivers = rt.block_on(aether.subscribe_many(vec![channel.clone(); subscribers]));
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(subscribers),
+            &subscribers,
+            |b, _| {
+                b.iter(|| {
+                    rt.block_on(async {
+                        let _ = aether.emit(bench_wave(channel.clone())).await;
+                    });
+                    receivers.iter_mut().for_each(drain);
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+/// Emit cost without persistence and with each durability mode
+fn bench_emit_persistence(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let mut group = c.benchmark_group("emit_persistence");
+    group.throughput(Throughput::Elements(1));
+
+    for (name, durability) in [
+        ("off", None),
+        ("buffered", Some(Durability::Buffered)),
+        ("fsync", Some(Durability::Fsync)),
+    ] {
+        let path = std::env::temp_dir().join(format!("aether-bench-{}", uuid::Uuid::new_v4()));
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            persistence_enabled: durability.is_some(),
+            persistence_path: path.to_string_lossy().into_owned(),
+            persistence_durability: durability.unwrap_or_default(),
+            ..AetherConfig::default()
+        });
+
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                rt.block_on(async {
+                    let _ = aether.emit(bench_wave("bench.emit")).await;
+                })
+            })
+        });
+
+        drop(aether);
+        let _ = std::fs::remove_dir_all(path);
+    }
+    group.finish();
+}
+
+/// Encode and decode of a typical wave with each codec
+fn bench_codecs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("codec");
+    let wave = Wave::builder("orders.created")
+        .payload(serde_json::json!({
+            "order_id": "8f0c6a52-3c1e-4b8e-9d7a-1f2e3d4c5b6a",
+            "items": [{"sku": "ItemA", "quantity": 3}, {"sku": "ItemB", "quantity": 1}],
+            "total": 129.5,
+        }))
+        .source("service-alpha")
+        .build();
+
+    for (name, codec) in [
+        ("json", WaveCodec::Json),
+        ("msgpack", WaveCodec::MessagePack),
+    ] {
+        let encoded = codec.encode(&wave).unwrap();
+        group.throughput(Throughput::Bytes(encoded.len() as u64));
+        group.bench_function(BenchmarkId::new("encode", name), |b| {
+            b.iter(|| codec.encode(&wave).unwrap())
+        });
+        group.bench_function(BenchmarkId::new("decode", name), |b| {
+            b.iter(|| codec.decode(&encoded).unwrap())
+        });
+    }
+    group.finish();
+}
+
+/// Byte payloads delivered to several receivers, which each get a clone
+fn bench_bytes_fanout(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let aether = memory_layer();
+    let channel = Channel::new("bench.fanout.bytes");
+    let payload = Bytes::from(vec![0u8; 256]);
+    let mut receivers: Vec<_> =
+        rt.block_on(async { aether.subscribe_many(vec![channel.clone(); 8]).await });
+
+    c.bench_function("wave_clone_bytes", |b| {
+        let wave = Wave::builder(channel.clone())
+            .payload_bytes(payload.clone())
+            .source("bench-vibrator")
+            .build();
+        b.iter(|| wave.clone())
     });
 
-    c.bench_function("aether_emit", |b| {
+    c.bench_function("aether_emit_bytes_8_receivers", |b| {
         b.iter(|| {
             rt.block_on(async {
-                let wave = Wave::builder(Channel::new("bench.emit"))
-                    .payload(serde_json::json!({"data": "x"}))
+                let wave = Wave::builder("bench.fanout.bytes")
+                    .payload_bytes(payload.clone())
+                    .source("bench-vibrator")
                     .build();
                 let _ = aether.emit(wave).await;
+                for receiver in &mut receivers {
+                    let _ = receiver.recv().await;
+                }
             })
         })
     });
 }
 
-criterion_group!(benches, bench_emit);
+/// Emit `channels` once each from `producers` concurrent tasks
+async fn emit_concurrently<F, Fut>(channels: &[Channel], producers: usize, emit: F)
+where
+    F: Fn(Wave) -> Fut + Clone + Send + 'static,
+    Fut: std::future::Future<Output = ()> + Send,
+{
+    let tasks: Vec<_> = (0..producers)
+        .map(|producer| {
+            let channels: Vec<Channel> = channels
+                .iter()
+                .skip(producer)
+                .step_by(producers)
+                .cloned()
+                .collect();
+            let emit = emit.clone();
+            tokio::spawn(async move {
+                for channel in channels {
+                    emit(bench_wave(channel)).await;
+                }
+            })
+        })
+        .collect();
+    for task in tasks {
+        let _ = task.await;
+    }
+}
+
+/// Many producers on many channels, one layer versus a shard set
+fn bench_sharded_emit(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let channels: Vec<Channel> = (0..64)
+        .map(|n| Channel::new(format!("bench.shard.{}", n)))
+        .collect();
+
+    for shard_count in [1, 8] {
+        let shards = AetherShardSet::new(
+            AetherConfig {
+                use_nats: false,
+                ..AetherConfig::default()
+            },
+            shard_count,
+        );
+        let receivers: Vec<_> = rt.block_on(async {
+            let mut receivers = Vec::new();
+            for channel in &channels {
+                receivers.push(shards.subscribe(channel).await);
+            }
+            receivers
+        });
+
+        let name = format!("aether_emit_8_producers_{}_shards", shard_count);
+        c.bench_function(&name, |b| {
+            b.iter(|| {
+                rt.block_on(emit_concurrently(&channels, 8, {
+                    let shards = shards.clone();
+                    move |wave| {
+                        let shards = shards.clone();
+                        async move {
+                            let _ = shards.emit(wave).await;
+                        }
+                    }
+                }))
+            })
+        });
+        drop(receivers);
+    }
+}
+
+fn bench_contended_emit(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let channels: Vec<Channel> = (0..512)
+        .map(|n| Channel::new(format!("bench.contended.{}", n)))
+        .collect();
+    let aether = memory_layer();
+    let receivers = rt.block_on(aether.subscribe_many(channels.clone()));
+
+    for producer_count in [4, 16] {
+        let name = format!("aether_emit_contended_{}_producers", producer_count);
+        c.bench_function(&name, |b| {
+            b.iter(|| {
+                rt.block_on(emit_concurrently(&channels, producer_count, {
+                    let aether = aether.clone();
+                    move |wave| {
+                        let aether = aether.clone();
+                        async move {
+                            let _ = aether.emit(wave).await;
+                        }
+                    }
+                }))
+            })
+        });
+    }
+    drop(receivers);
+}
+
+/// Emit and receive back through a NATS server
+fn bench_nats_loopback(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let nats_url = std::env::var("AETHER_BENCH_NATS_URL")
+        .unwrap_or_else(|_| "nats://127.0.0.1:4222".to_string());
+    let aether = Aether::new(AetherConfig {
+        nats_url: nats_url.clone(),
+        ..AetherConfig::default()
+    });
+    let channel = Channel::new(format!("bench.loopback.{}", uuid::Uuid::new_v4().simple()));
+
+    let receiver = rt.block_on(async {
+        let mut receiver = aether.subscribe(&channel).await;
+        // The bridge subscribes in the background; wait until a wave makes it round
+        for _ in 0..20 {
+            aether.emit(bench_wave(channel.clone())).await.ok()?;
+            let echoed = tokio::time::timeout(Duration::from_millis(100), receiver.recv()).await;
+            if matches!(echoed, Ok(Ok(_))) {
+                return Some(receiver);
+            }
+        }
+        None
+    });
+    let Some(mut receiver) = receiver else {
+        eprintln!("Skipping nats_loopback: no NATS server at {}", nats_url);
+        return;
+    };
+
+    let mut group = c.benchmark_group("nats_loopback");
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("emit_receive", |b| {
+        b.iter(|| {
+            rt.block_on(async {
+                aether.emit(bench_wave(channel.clone())).await.unwrap();
+                receiver.recv().await.unwrap()
+            })
+        })
+    });
+    group.finish();
+}
+
+/// End-to-end latency from one vibrator's emit to another's receive
+///
+/// Criterion reports the mean; the percentiles of every measured wave are
+/// printed after the run.
+fn bench_vibrator_latency(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let aether = memory_layer();
+    let (sender, mut receiver) = rt.block_on(async {
+        let sender = Vibrator::new(VibratorConfig::new("bench-sender"), &aether)
+            .await
+            .unwrap();
+        let receiver = Vibrator::new(
+            VibratorConfig::new("bench-receiver")
+                .with_channels(vec![Channel::new("bench.latency")]),
+            &aether,
+        )
+        .await
+        .unwrap();
+        (sender, receiver)
+    });
+
+    let mut samples: Vec<Duration> = Vec::new();
+    c.bench_function("vibrator_to_vibrator_latency", |b| {
+        b.iter_custom(|iters| {
+            rt.block_on(async {
+                let mut total = Duration::ZERO;
+                for _ in 0..iters {
+                    let start = Instant::now();
+                    let _ = sender
+                        .emit_wave("bench.latency", serde_json::json!({"data": "x"}))
+                        .await;
+                    receiver.receive().await;
+                    let elapsed = start.elapsed();
+                    samples.push(elapsed);
+                    total += elapsed;
+                }
+                total
+            })
+        })
+    });
+
+    if !samples.is_empty() {
+        samples.sort();
+        let percentile = |p: f64| samples[((samples.len() - 1) as f64 * p).round() as usize];
+        println!(
+            "vibrator_to_vibrator_latency over {} waves: p50 {:?}, p90 {:?}, p99 {:?}, \
+             p99.9 {:?}, max {:?}",
+            samples.len(),
+            percentile(0.50),
+            percentile(0.90),
+            percentile(0.99),
+            percentile(0.999),
+            samples[samples.len() - 1]
+        );
+    }
+}
+
+criterion_group!(
+    benches,
+    bench_emit_subscribers,
+    bench_emit_persistence,
+    bench_codecs,
+    bench_bytes_fanout,
+    bench_sharded_emit,
+    bench_contended_emit,
+    bench_nats_loopback,
+    bench_vibrator_latency
+);
 criterion_main!(benches);
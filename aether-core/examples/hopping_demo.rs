@@ -11,32 +11,38 @@ async fn main() -> anyhow::Result<()> {
 
     let hop_count = 4;
     let hop_interval_ms = 200;
+    // Also listen on the neighbouring hop this close to a slot boundary
+    let grace = Duration::from_millis(50);
+    let base = Channel::new("orders");
 
     let mut receiver = Vibrator::new(
         VibratorConfig::new("receiver").with_noise_floor(0.05),
         &aether,
     )
-    .await;
+    .await?;
     receiver
-        .resonate_hopping(Channel::new("orders"), hop_count)
-        .await;
+        .follow_time_hopping(&base, hop_count, hop_interval_ms, grace)
+        .await?;
 
-    let sender = Vibrator::new(VibratorConfig::new("sender"), &aether).await;
+    let sender = Vibrator::new(VibratorConfig::new("sender"), &aether).await?;
 
     // Low amplitude wave (filtered by noise floor)
-    let low_channel = Channel::new("orders").hop_now(hop_count, hop_interval_ms);
+    let low_channel = base.hop_now(hop_count, hop_interval_ms);
     let low_wave = Wave::builder(low_channel)
         .payload(json!({"msg": "low"}))
         .amplitude(0.01)
         .build();
     sender.emit(low_wave).await?;
 
-    // Wait briefly, then send a visible wave
+    // Wait briefly, then move to the live hop and send a visible wave
     sleep(Duration::from_millis(50)).await;
+    receiver
+        .follow_time_hopping(&base, hop_count, hop_interval_ms, grace)
+        .await?;
 
     sender
         .emit_time_hopping_wave(
-            Channel::new("orders"),
+            base.clone(),
             hop_count,
             hop_interval_ms,
             json!({"msg": "hop"}),
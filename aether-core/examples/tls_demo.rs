@@ -14,10 +14,10 @@ async fn main() -> anyhow::Result<()> {
     });
 
     let channel = Channel::new("tls.demo");
-    let mut receiver = Vibrator::create("tls-receiver", &aether).await;
+    let mut receiver = Vibrator::create("tls-receiver", &aether).await?;
     receiver.resonate_on(channel.clone()).await;
 
-    let sender = Vibrator::create("tls-sender", &aether).await;
+    let sender = Vibrator::create("tls-sender", &aether).await?;
     sender
         .emit_wave(channel.clone(), serde_json::json!({"msg": "tls-ok"}))
         .await?;
@@ -1,12 +1,46 @@
 //! Aether - Aether layer implementation
 
-use crate::{channel::Channel, wave::Wave, AetherError, Result};
+use crate::{
+    amplitude_policy::AmplitudePolicy,
+    audit::{AuditConfig, AuditKind, AuditLog},
+    buffer_pool::BytePool,
+    channel::Channel,
+    chaos::{Chaos, ChaosConfig},
+    clock::{SharedClock, SystemClock},
+    cluster::PeerTable,
+    codec::WaveCodec,
+    connection::{self, ConnectionTracker, NatsServer},
+    encryption::{EncryptionConfig, Keyring},
+    events::{AetherEvent, EventBus},
+    flow_trace::{Breadcrumb, FlowTraceConfig, LoopCheck, LoopGuard},
+    labels::{LabelBudget, DEFAULT_LABEL_BUDGET},
+    last_value::LastValueCache,
+    log_writer::LogWriter,
+    persistence::Durability,
+    rate_limit::{ChannelQuota, ChannelQuotas, RateLimitConfig, SourceRateLimiter},
+    receipt::{EmitOptions, EmitReceipt, EmitTransport},
+    redaction::{RedactionRule, Redactor},
+    registry::{ChannelRegistry, RegistryConfig},
+    sampling,
+    sketch::{WaveSample, WaveSketches},
+    source_stats::{SourceTable, ANONYMOUS_SOURCE},
+    transform::TransformPipeline,
+    validation::{RequiredFields, WaveValidators},
+    wave::Wave,
+    AetherError, Result,
+};
+use arc_swap::ArcSwap;
 use async_nats::ConnectOptions;
-use futures::StreamExt;
-use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use dashmap::{mapref::entry::Entry, DashMap};
+use futures::{Stream, StreamExt};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
 use std::sync::Arc;
 use tokio::sync::{broadcast, OnceCell, RwLock};
-use tracing::{debug, info, warn};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, info_span, warn, Instrument};
+use uuid::Uuid;
 
 /// Aether layer configuration
 #[derive(Debug, Clone)]
@@ -17,6 +51,9 @@ pub struct AetherConfig {
     /// Maximum propagation count for waves
     pub max_propagation: u32,
 
+    /// What happens to a wave that reached `max_propagation`
+    pub propagation_limit_policy: PropagationLimitPolicy,
+
     /// Attenuation factor
     pub attenuation_factor: f64,
 
@@ -29,9 +66,12 @@ pub struct AetherConfig {
     /// Use NATS as the transport backend
     pub use_nats: bool,
 
-    /// NATS server URL
+    /// NATS server URL, used when `nats_servers` is empty
     pub nats_url: String,
 
+    /// NATS servers to spread across and fail over between
+    pub nats_servers: Vec<NatsServer>,
+
     /// Require TLS for NATS connection
     pub nats_tls_required: bool,
 
@@ -64,6 +104,103 @@ pub struct AetherConfig {
 
     /// Snapshot interval (in waves)
     pub snapshot_interval: u64,
+
+    /// Also snapshot this often (in seconds) when waves were logged since the
+    /// last snapshot; 0 disables
+    pub snapshot_every_secs: u64,
+
+    /// Whether emit waits for the log write to be fsynced
+    pub persistence_durability: Durability,
+
+    /// Waves queued for the log writer before emit waits
+    pub persistence_queue_size: usize,
+
+    /// Background flush interval for buffered writes (milliseconds)
+    pub persistence_flush_interval_ms: u64,
+
+    /// Seal log entries at rest; persistence stays off if the keys do not load
+    pub persistence_encryption: Option<EncryptionConfig>,
+
+    /// Fault injection applied before transmission (testing only)
+    pub chaos: Option<ChaosConfig>,
+
+    /// Tenant namespace prefixed to every channel and NATS subject
+    pub namespace: Option<String>,
+
+    /// Other namespaces this layer may emit into
+    pub bridges: Vec<NamespaceBridge>,
+
+    /// Per-source emission limits
+    pub rate_limit: Option<RateLimitConfig>,
+
+    /// Per-channel throughput quotas (first matching pattern wins)
+    pub channel_quotas: Vec<ChannelQuota>,
+
+    /// Declared channels checked on emit
+    pub registry: Option<RegistryConfig>,
+
+    /// Channel patterns whose most recent wave is kept for late subscribers
+    ///
+    /// Kept per layer: under NATS other instances do not see it.
+    pub retained_channels: Vec<String>,
+
+    /// Payload fields masked in logs, recordings and exports
+    pub redaction: Vec<RedactionRule>,
+
+    /// Channels without receivers are removed after this long without traffic
+    pub channel_idle_timeout_ms: Option<u64>,
+
+    /// Append hop-by-hop breadcrumbs to every emitted wave
+    pub flow_trace: Option<FlowTraceConfig>,
+
+    /// Per-type amplitude floors enforced on emit
+    pub amplitude_policy: Option<AmplitudePolicy>,
+
+    /// Payload fields required per channel, checked on emit
+    pub required_fields: Vec<RequiredFields>,
+
+    /// Audit chain key and how many rejected emits are recorded
+    pub audit: AuditConfig,
+}
+
+/// Channel prefix for waves dead-lettered at the propagation limit
+pub const DEAD_LETTER_CHANNEL_PREFIX: &str = "aether.deadletter";
+
+/// What happens to a wave that reached `max_propagation`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PropagationLimitPolicy {
+    /// Drop it quietly
+    Drop,
+    /// Drop it with a warning naming the wave and channel
+    #[default]
+    Log,
+    /// Emit it, wrapped, on `aether.deadletter.<channel>`
+    DeadLetter,
+    /// Fail the emit
+    Error,
+}
+
+impl PropagationLimitPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PropagationLimitPolicy::Drop => "drop",
+            PropagationLimitPolicy::Log => "log",
+            PropagationLimitPolicy::DeadLetter => "dead_letter",
+            PropagationLimitPolicy::Error => "error",
+        }
+    }
+}
+
+/// Permission to emit into another tenant namespace
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct NamespaceBridge {
+    /// Target namespace
+    pub namespace: String,
+
+    /// Auth token expected by the target namespace
+    #[serde(default)]
+    pub auth_token: Option<String>,
 }
 
 impl Default for AetherConfig {
@@ -71,11 +208,13 @@ impl Default for AetherConfig {
         Self {
             channel_buffer_size: 1000,
             max_propagation: 10,
+            propagation_limit_policy: PropagationLimitPolicy::default(),
             attenuation_factor: 0.95,
             min_amplitude: 0.01,
             enable_physics: true,
             use_nats: true,
             nats_url: "nats://127.0.0.1:4222".to_string(),
+            nats_servers: Vec::new(),
             nats_tls_required: false,
             auth_token: None,
             allowed_sources: Vec::new(),
@@ -87,67 +226,528 @@ impl Default for AetherConfig {
             persistence_enabled: false,
             persistence_path: "./data/aether".to_string(),
             snapshot_interval: 1000,
+            snapshot_every_secs: 300,
+            persistence_durability: Durability::Buffered,
+            persistence_queue_size: 10_000,
+            persistence_flush_interval_ms: 500,
+            persistence_encryption: None,
+            chaos: None,
+            namespace: None,
+            bridges: Vec::new(),
+            rate_limit: None,
+            channel_quotas: Vec::new(),
+            registry: None,
+            retained_channels: Vec::new(),
+            redaction: Vec::new(),
+            channel_idle_timeout_ms: None,
+            flow_trace: None,
+            amplitude_policy: None,
+            required_fields: Vec::new(),
+            audit: AuditConfig::default(),
         }
     }
 }
 
+/// Keys for the configured store encryption; `None` when it is off
+fn load_keyring(config: &AetherConfig) -> anyhow::Result<Option<Keyring>> {
+    if !config.persistence_enabled {
+        return Ok(None);
+    }
+    config
+        .persistence_encryption
+        .as_ref()
+        .map(Keyring::from_config)
+        .transpose()
+}
+
+/// The configured wave store; `None` with persistence disabled
+fn open_store(
+    config: &AetherConfig,
+    keyring: Option<Keyring>,
+) -> anyhow::Result<Option<crate::persistence::WaveStore>> {
+    if !config.persistence_enabled {
+        return Ok(None);
+    }
+    if config.persistence_durability == Durability::Unlogged {
+        anyhow::bail!(
+            "persistence_durability = \"unlogged\" would log nothing; set \
+             persistence_enabled = false instead, or mark single waves unlogged \
+             with EmitOptions"
+        );
+    }
+    let flush_interval = std::time::Duration::from_millis(config.persistence_flush_interval_ms);
+    let store = match keyring {
+        Some(keyring) => crate::persistence::WaveStore::open_encrypted(
+            &config.persistence_path,
+            flush_interval,
+            keyring,
+        ),
+        None => crate::persistence::WaveStore::open_with_flush_interval(
+            &config.persistence_path,
+            flush_interval,
+        ),
+    }?;
+    store.reindex_in_background()?;
+    Ok(Some(store))
+}
+
+/// Initial size of a NATS publish buffer
+const PUBLISH_BUFFER_CAPACITY: usize = 4096;
+
+/// Publish buffers kept for reuse
+const PUBLISH_BUFFERS: usize = 64;
+
+/// Waves between refreshes of the sketch gauges
+const SKETCH_METRICS_EVERY: u64 = 1000;
+
+/// Channels labelled individually in `aether_propagation_limit_total`
+static PROPAGATION_LIMIT_CHANNELS: LabelBudget = LabelBudget::new(DEFAULT_LABEL_BUDGET);
+
 /// Aether layer - communication medium encompassing all services
 pub struct Aether {
     /// Configuration
     config: AetherConfig,
 
-    /// Broadcast channels per channel
-    channels: Arc<RwLock<HashMap<String, broadcast::Sender<Wave>>>>,
+    /// Broadcast channels per channel; emit only takes a shard read lock once
+    /// the channel exists
+    channels: Arc<DashMap<String, ChannelEntry>>,
+
+    /// Wildcard entries of `channels`, so emit finds them without a full scan
+    wildcards: Arc<WildcardRoutes>,
 
     /// Statistics
     stats: Arc<RwLock<AetherStats>>,
 
+    /// Top channels, sources, payload sizes and types of transmitted waves
+    sketches: Arc<std::sync::Mutex<WaveSketches>>,
+
+    /// Emit and rejection totals per source
+    sources: Arc<SourceTable>,
+
+    /// Channel pairs quarantined after a flow-trace loop
+    loops: Arc<LoopGuard>,
+
+    /// Lifecycle events for `Aether::events`
+    events: EventBus,
+
+    /// NATS link status for `Aether::connection_state`
+    connection: Arc<ConnectionTracker>,
+
     /// NATS client
     nats_client: Arc<OnceCell<async_nats::Client>>,
 
-    /// Persistence store
+    /// Encode buffers for NATS publishes
+    publish_buffers: BytePool,
+
+    /// Persistence store (read side)
     store: Option<crate::persistence::WaveStore>,
+
+    /// Queue to the thread that appends to the store
+    writer: Option<Arc<LogWriter>>,
+
+    /// Security-relevant events, kept next to the wave log
+    audit: Option<AuditLog>,
+
+    /// Copy of every locally emitted wave for taps
+    taps: broadcast::Sender<Wave>,
+
+    /// Fault injection state
+    chaos: Option<Arc<Chaos>>,
+
+    /// Per-source token buckets
+    rate_limiter: Option<Arc<SourceRateLimiter>>,
+
+    /// Per-channel wave and byte budgets
+    quotas: Option<Arc<ChannelQuotas>>,
+
+    /// Declared channels
+    registry: Option<Arc<ChannelRegistry>>,
+
+    /// Most recent wave on retained channels
+    last_values: Option<Arc<LastValueCache>>,
+
+    /// Masks payload fields for observers
+    redactor: Arc<Redactor>,
+
+    /// Attached vibrators by name (several may share a name)
+    vibrators: Arc<std::sync::Mutex<HashMap<String, usize>>>,
+
+    /// Last sequence number assigned per channel and source; see `sequence_key`
+    sequences: Arc<std::sync::Mutex<HashMap<String, u64>>>,
+
+    /// Waves transmitted per channel name, carried across restarts by snapshots
+    channel_totals: Arc<std::sync::Mutex<BTreeMap<String, u64>>>,
+
+    /// Identity stamped on emitted waves; see `Aether::instance_id`
+    instance: Uuid,
+
+    /// Start count stamped on emitted waves; see `Aether::epoch`
+    epoch: u64,
+
+    /// Last sequence number assigned per source in this epoch
+    source_sequences: Arc<std::sync::Mutex<HashMap<String, u64>>>,
+
+    /// Time source for snapshots and clock-aware callers
+    clock: SharedClock,
+
+    /// Cluster members heard on the membership channel
+    cluster: Arc<PeerTable>,
+
+    /// Payload rewrites applied before a wave is published
+    transforms: Arc<TransformPipeline>,
+
+    /// Application checks run on emit after authorization
+    validators: Arc<WaveValidators>,
+}
+
+/// A local channel and the NATS subscription feeding it
+struct ChannelEntry {
+    sender: broadcast::Sender<Wave>,
+    /// Parsed name when the channel is a wildcard subscription
+    pattern: Option<Channel>,
+    /// Last emit or subscribe in Unix milliseconds, for idle collection
+    last_active_ms: AtomicI64,
+    bridge: Option<JoinHandle<()>>,
+}
+
+impl ChannelEntry {
+    fn new(name: &str, buffer_size: usize, now: DateTime<Utc>) -> Self {
+        let (sender, _) = broadcast::channel(buffer_size);
+        let pattern = Some(Channel::new(name)).filter(Channel::is_wildcard);
+        Self {
+            sender,
+            pattern,
+            last_active_ms: AtomicI64::new(now.timestamp_millis()),
+            bridge: None,
+        }
+    }
+
+    fn touch(&self, now: DateTime<Utc>) {
+        self.last_active_ms
+            .fetch_max(now.timestamp_millis(), Ordering::Relaxed);
+    }
+
+    fn is_idle(&self, now: DateTime<Utc>, timeout: chrono::Duration) -> bool {
+        let idle_ms = now.timestamp_millis() - self.last_active_ms.load(Ordering::Relaxed);
+        self.sender.receiver_count() == 0 && idle_ms >= timeout.num_milliseconds()
+    }
+
+    fn has_running_bridge(&self) -> bool {
+        self.bridge
+            .as_ref()
+            .is_some_and(|bridge| !bridge.is_finished())
+    }
+}
+
+/// Removing a channel cancels its bridge, which drops the NATS subscription
+impl Drop for ChannelEntry {
+    fn drop(&mut self) {
+        if let Some(bridge) = self.bridge.take() {
+            bridge.abort();
+        }
+    }
+}
+
+/// Sender of a wildcard channel, matched against every emitted channel name
+#[derive(Clone)]
+struct WildcardRoute {
+    name: String,
+    pattern: Channel,
+    sender: broadcast::Sender<Wave>,
+}
+
+type WildcardRoutes = ArcSwap<Vec<WildcardRoute>>;
+
+/// Route matching waves to `entry` if it is a wildcard channel
+///
+/// Called under the entry's shard lock, so a removal of the same entry cannot
+/// run its `remove_wildcard_route` first.
+fn add_wildcard_route(routes: &WildcardRoutes, name: &str, entry: &ChannelEntry) {
+    let Some(pattern) = &entry.pattern else {
+        return;
+    };
+    routes.rcu(|current| {
+        let mut next = Vec::clone(current);
+        next.push(WildcardRoute {
+            name: name.to_string(),
+            pattern: pattern.clone(),
+            sender: entry.sender.clone(),
+        });
+        next
+    });
+}
+
+/// Stop routing to a removed channel entry
+fn remove_wildcard_route(routes: &WildcardRoutes, entry: &ChannelEntry) {
+    if entry.pattern.is_none() {
+        return;
+    }
+    routes.rcu(|current| {
+        current
+            .iter()
+            .filter(|route| !route.sender.same_channel(&entry.sender))
+            .cloned()
+            .collect::<Vec<_>>()
+    });
+}
+
+fn record_channel_gauges(channels: &DashMap<String, ChannelEntry>) {
+    metrics::gauge!("aether_active_channels").set(channels.len() as f64);
+    let bridges = channels
+        .iter()
+        .filter(|entry| entry.has_running_bridge())
+        .count();
+    metrics::gauge!("aether_nats_bridge_tasks").set(bridges as f64);
 }
 
 /// Aether layer statistics
-#[derive(Debug, Default, Clone, Copy, serde::Serialize, serde::Deserialize)]
+///
+/// Volume, source and size figures come from bounded sketches, so they are
+/// estimates; `total_waves` is exact.
+#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
 pub struct AetherStats {
     pub total_waves: u64,
     pub active_channels: usize,
     pub total_vibrators: usize,
+    /// Busiest channels by waves sent, largest first
+    #[serde(default)]
+    pub top_channels: Vec<(String, u64)>,
+    /// Distinct wave sources seen
+    #[serde(default)]
+    pub distinct_sources: u64,
+    #[serde(default)]
+    pub payload_bytes_p50: u64,
+    #[serde(default)]
+    pub payload_bytes_p99: u64,
+    #[serde(default)]
+    pub waves_by_type: BTreeMap<String, u64>,
+}
+
+impl AetherStats {
+    /// Publish the sketch figures as gauges
+    pub(crate) fn publish_metrics(&self) {
+        for (channel, waves) in &self.top_channels {
+            metrics::gauge!("aether_top_channel_waves", "channel" => channel.clone())
+                .set(*waves as f64);
+        }
+        metrics::gauge!("aether_distinct_sources").set(self.distinct_sources as f64);
+        metrics::gauge!("aether_payload_bytes", "quantile" => "0.5")
+            .set(self.payload_bytes_p50 as f64);
+        metrics::gauge!("aether_payload_bytes", "quantile" => "0.99")
+            .set(self.payload_bytes_p99 as f64);
+        for (wave_type, waves) in &self.waves_by_type {
+            metrics::gauge!("aether_waves_by_type", "type" => wave_type.clone()).set(*waves as f64);
+        }
+    }
 }
 
 impl Aether {
-    /// Create a new Aether layer
+    /// Create a layer; if the wave store cannot be opened (e.g. another
+    /// process holds its writer lease) persistence is disabled with a warning
+    ///
+    /// # Panics
+    ///
+    /// If `persistence_encryption` is configured and its keys fail to load;
+    /// the layer never logs waves the config asked to keep encrypted in the
+    /// clear or drops them silently. Use `try_new` to handle the error.
     pub fn new(config: AetherConfig) -> Self {
         info!("Initializing Aether layer...");
-        let store = if config.persistence_enabled {
-            match crate::persistence::WaveStore::open(&config.persistence_path) {
-                Ok(store) => Some(store),
+        let keyring = load_keyring(&config)
+            .unwrap_or_else(|err| panic!("Failed to load persistence encryption keys: {:#}", err));
+        let store = match open_store(&config, keyring) {
+            Ok(store) => store,
+            Err(err) => {
+                warn!("Failed to open persistence store: {:#}", err);
+                None
+            }
+        };
+        Self::with_store(config, store)
+    }
+
+    /// Like `new`, but fails instead of running without the configured wave store
+    pub fn try_new(config: AetherConfig) -> Result<Self> {
+        info!("Initializing Aether layer...");
+        let store = load_keyring(&config)
+            .and_then(|keyring| open_store(&config, keyring))
+            .map_err(|err| AetherError::PersistenceError(format!("{:#}", err)))?;
+        Ok(Self::with_store(config, store))
+    }
+
+    fn with_store(config: AetherConfig, store: Option<crate::persistence::WaveStore>) -> Self {
+        let audit = store.as_ref().and_then(|store| {
+            let opened = config
+                .audit
+                .load_key()
+                .and_then(|key| AuditLog::open(store, key));
+            match opened {
+                Ok(audit) => Some(audit.with_rejection_rate(config.audit.rejections_per_sec)),
                 Err(err) => {
-                    warn!("Failed to open persistence store: {}", err);
+                    warn!("Failed to open audit log: {:#}", err);
                     None
                 }
             }
-        } else {
-            None
+        });
+        let start = store
+            .as_ref()
+            .map(|store| Ok::<_, anyhow::Error>((store.instance_id()?, store.advance_epoch()?)));
+        let (instance, epoch) = match start {
+            Some(Ok(start)) => start,
+            Some(Err(err)) => {
+                warn!("Failed to advance source epoch: {}", err);
+                (Uuid::new_v4(), 1)
+            }
+            None => (Uuid::new_v4(), 1),
         };
+        let events = EventBus::default();
+        let writer = store.clone().map(|store| {
+            Arc::new(LogWriter::spawn(
+                store,
+                config.persistence_queue_size,
+                events.clone(),
+            ))
+        });
+        let (taps, _) = broadcast::channel(config.channel_buffer_size);
+        let chaos = config.chaos.clone().map(|chaos| Arc::new(Chaos::new(chaos)));
+        let rate_limiter = config
+            .rate_limit
+            .clone()
+            .map(|limits| Arc::new(SourceRateLimiter::new(limits)));
+        let quotas = (!config.channel_quotas.is_empty())
+            .then(|| Arc::new(ChannelQuotas::new(config.channel_quotas.clone())));
+        let registry = config
+            .registry
+            .clone()
+            .map(|registry| Arc::new(ChannelRegistry::from_config(registry)));
+        let last_values = (!config.retained_channels.is_empty())
+            .then(|| Arc::new(LastValueCache::new(&config.retained_channels)));
+        let redactor = Arc::new(Redactor::new(&config.redaction));
+        let validators = Arc::new(WaveValidators::from_config(&config.required_fields));
+        let connection = Arc::new(ConnectionTracker::new(config.use_nats, Utc::now()));
         Self {
             config,
-            channels: Arc::new(RwLock::new(HashMap::new())),
+            channels: Arc::new(DashMap::new()),
+            wildcards: Arc::new(ArcSwap::from_pointee(Vec::new())),
             stats: Arc::new(RwLock::new(AetherStats::default())),
+            sketches: Arc::new(std::sync::Mutex::new(WaveSketches::new())),
+            sources: Arc::new(SourceTable::default()),
+            loops: Arc::new(LoopGuard::default()),
+            events,
+            connection,
             nats_client: Arc::new(OnceCell::new()),
+            publish_buffers: BytePool::new(PUBLISH_BUFFER_CAPACITY, PUBLISH_BUFFERS),
             store,
+            writer,
+            audit,
+            taps,
+            chaos,
+            rate_limiter,
+            quotas,
+            registry,
+            last_values,
+            redactor,
+            vibrators: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            sequences: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            channel_totals: Arc::new(std::sync::Mutex::new(BTreeMap::new())),
+            instance,
+            epoch,
+            source_sequences: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            clock: Arc::new(SystemClock),
+            cluster: Arc::new(PeerTable::new()),
+            transforms: Arc::new(TransformPipeline::default()),
+            validators,
         }
     }
 
-    /// Create an Aether layer with default configuration
-    pub fn default() -> Self {
-        Self::new(AetherConfig::default())
+    /// Replace the channel registry (call before cloning or creating vibrators)
+    pub fn with_registry(mut self, registry: ChannelRegistry) -> Self {
+        self.registry = Some(Arc::new(registry));
+        self
+    }
+
+    /// Declared channels, if a registry is configured
+    pub fn registry(&self) -> Option<&ChannelRegistry> {
+        self.registry.as_deref()
+    }
+
+    /// Set publish-side transformers (call before cloning or creating vibrators)
+    pub fn with_transforms(mut self, transforms: TransformPipeline) -> Self {
+        self.transforms = Arc::new(transforms);
+        self
+    }
+
+    /// Add validators after the configured `required_fields` (call before
+    /// cloning or creating vibrators)
+    pub fn with_validators(mut self, validators: WaveValidators) -> Self {
+        self.validators = Arc::new(WaveValidators::clone(&self.validators).then(validators));
+        self
+    }
+
+    /// Replace the time source (call before cloning or creating vibrators)
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.connection = Arc::new(ConnectionTracker::new(self.config.use_nats, clock.now()));
+        self.clock = clock;
+        self
     }
 
     /// Emit a wave into the Aether layer
-    pub async fn emit(&self, mut wave: Wave) -> Result<()> {
+    ///
+    /// Runs in an `aether.emit` span; failures set `error = true` on it so the
+    /// trace sampler keeps them.
+    pub async fn emit(&self, wave: Wave) -> Result<EmitReceipt> {
+        self.emit_with(wave, EmitOptions::default()).await
+    }
+
+    /// Emit with per-wave options, such as a stronger durability than the
+    /// layer's `persistence_durability` for a payment
+    pub async fn emit_with(&self, wave: Wave, options: EmitOptions) -> Result<EmitReceipt> {
+        self.emit_from(wave, None, options).await
+    }
+
+    /// Emit on behalf of `emitter`, named in flow-trace breadcrumbs instead
+    /// of the wave's source
+    pub(crate) async fn emit_from(
+        &self,
+        wave: Wave,
+        emitter: Option<&str>,
+        options: EmitOptions,
+    ) -> Result<EmitReceipt> {
+        let span = info_span!(
+            "aether.emit",
+            aether.channel = wave.channel().name(),
+            wave.id = %wave.id(),
+            error = tracing::field::Empty,
+        );
+        let source = wave.source_arc().cloned();
+        let result = self
+            .emit_wave(wave, emitter, options)
+            .instrument(span.clone())
+            .await;
+        if let Err(err) = &result {
+            span.record(sampling::ERROR_ATTRIBUTE, true);
+            self.sources.reject(source.as_ref(), err);
+        }
+        result
+    }
+
+    async fn emit_wave(
+        &self,
+        mut wave: Wave,
+        emitter: Option<&str>,
+        options: EmitOptions,
+    ) -> Result<EmitReceipt> {
+        wave.inherit_context();
+        // Later checks, persistence and NATS all see the transformed wave
+        if !self.transforms.is_empty() {
+            let id = *wave.id();
+            match self.transforms.apply(wave)? {
+                Some(transformed) => wave = transformed,
+                None => {
+                    debug!("Wave {} dropped by a transformer", id);
+                    return Ok(EmitReceipt::dropped(id));
+                }
+            }
+        }
+
         // Validate channel name
         let channel_name = wave.channel().name();
         if !is_valid_channel_name(channel_name, self.config.max_channel_length) {
@@ -156,6 +756,12 @@ impl Aether {
                 channel_name
             )));
         }
+        if wave.channel().is_wildcard() {
+            return Err(AetherError::ValidationFailed(format!(
+                "cannot emit to wildcard channel: {}",
+                channel_name
+            )));
+        }
 
         // Validate payload size
         let payload_size = if let Some(bytes) = wave.payload_bytes() {
@@ -173,14 +779,34 @@ impl Aether {
             )));
         }
 
+        // Declared channels and payload schema
+        if let Some(registry) = &self.registry {
+            registry.check(&wave)?;
+        }
+
+        // Namespace isolation; tokens are scoped to the target namespace
+        let namespace = wave
+            .namespace()
+            .map(str::to_string)
+            .or_else(|| self.config.namespace.clone());
+        let expected_token = match self.namespace_token(namespace.as_deref()) {
+            Ok(expected_token) => expected_token,
+            Err(err) => {
+                self.audit_rejection(AuditKind::AclDenial, &wave, &err);
+                return Err(err);
+            }
+        };
+
         // Auth token validation
-        if let Some(expected) = &self.config.auth_token {
+        if let Some(expected) = expected_token {
             match wave.auth_token() {
                 Some(token) if token == expected => {}
                 _ => {
-                    return Err(AetherError::AuthorizationFailed(
+                    let err = AetherError::AuthorizationFailed(
                         "missing or invalid auth token".to_string(),
-                    ));
+                    );
+                    self.audit_rejection(AuditKind::AuthFailure, &wave, &err);
+                    return Err(err);
                 }
             }
         }
@@ -190,162 +816,520 @@ impl Aether {
             match wave.source() {
                 Some(source) if self.config.allowed_sources.contains(&source.to_string()) => {}
                 _ => {
-                    return Err(AetherError::AuthorizationFailed(
-                        "source not allowed".to_string(),
-                    ));
+                    let err = AetherError::AuthorizationFailed("source not allowed".to_string());
+                    self.audit_rejection(AuditKind::AclDenial, &wave, &err);
+                    return Err(err);
                 }
             }
         }
 
+        // Application validators
+        if !self.validators.is_empty() {
+            self.validators.check(&wave).await?;
+        }
+
+        // Per-source rate limit
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire(wave.source()).await?;
+        }
+
+        // Per-channel quota
+        if let Some(quotas) = &self.quotas {
+            quotas.charge(wave.channel(), payload_size)?;
+        }
+
         // Check propagation count
         if wave.propagation_count() >= self.config.max_propagation {
-            warn!("Wave {} reached max propagation count", wave.id());
-            return Ok(());
+            return self.propagation_limit_reached(wave).await;
+        }
+
+        self.publish(wave, emitter, options, namespace, payload_size)
+            .await
+    }
+
+    /// Stamp, trace, persist and transmit a wave that passed admission
+    async fn publish(
+        &self,
+        mut wave: Wave,
+        emitter: Option<&str>,
+        options: EmitOptions,
+        namespace: Option<String>,
+        payload_size: usize,
+    ) -> Result<EmitReceipt> {
+        if let Some(policy) = &self.config.amplitude_policy {
+            wave.default_amplitude(policy.defaults.get(wave.wave_type()));
+            if let Some(floor) = policy.floors.get(wave.wave_type()) {
+                wave.raise_amplitude(floor);
+            }
         }
 
         // Validity check
         if !wave.is_valid_with_threshold(self.config.min_amplitude) {
             debug!("Skipping invalid wave {}", wave.id());
-            return Ok(());
+            return Ok(EmitReceipt::dropped(*wave.id()));
+        }
+
+        if let Some(namespace) = namespace {
+            if wave.namespace().is_none() {
+                wave.set_namespace(namespace);
+            }
         }
 
         wave.propagate();
+        if let Some(flow_trace) = &self.config.flow_trace {
+            let service = emitter.or(wave.source()).unwrap_or(ANONYMOUS_SOURCE);
+            let breadcrumb = Breadcrumb {
+                service: service.to_string(),
+                channel: wave.channel().name().to_string(),
+                at: self.clock.now(),
+                hop: wave.propagation_count(),
+            };
+            let trail = wave.breadcrumbs();
+            match self.loops.check(&trail, &breadcrumb, flow_trace) {
+                LoopCheck::Clear => {}
+                LoopCheck::Loop { cycle } => {
+                    warn!("Wave {} looped back to {}; dropped", wave.id(), service);
+                    metrics::counter!("aether_wave_loops_total", "service" => service.to_string())
+                        .increment(1);
+                    self.events
+                        .publish(AetherEvent::loop_detected(&wave, cycle));
+                    return Ok(EmitReceipt::dropped(*wave.id()));
+                }
+                LoopCheck::Quarantined { from, to } => {
+                    debug!(
+                        "Wave {} dropped: {} -> {} is quarantined",
+                        wave.id(),
+                        from,
+                        to
+                    );
+                    metrics::counter!("aether_loop_quarantine_drops_total").increment(1);
+                    return Ok(EmitReceipt::dropped(*wave.id()));
+                }
+            }
+            wave.push_breadcrumb(breadcrumb, flow_trace.max_breadcrumbs);
+        }
+
+        // Sequenced only once past loop detection, so drops leave no gaps
+        wave.set_sequence(self.next_sequence(&sequence_key(
+            &scoped_name(wave.namespace(), wave.channel().name()),
+            wave.source(),
+        )));
+        let source_sequence =
+            self.next_source_sequence(emitter.or(wave.source()).unwrap_or(ANONYMOUS_SOURCE));
+        wave.set_source_position(self.instance, self.epoch, source_sequence);
+
+        if sampling::should_log_wave(&wave) {
+            info!(
+                target: "aether::waves",
+                channel = wave.channel().name(),
+                wave_id = %wave.id(),
+                payload = %self.redactor.payload(&wave),
+                "Wave emitted"
+            );
+        }
+
+        if self.taps.receiver_count() > 0 {
+            let _ = self.taps.send(wave.clone());
+        }
 
-        let channel_name = wave.channel().name().to_string();
+        // Buffered writes fail in the writer; sync writes fail the emit
+        let durability = options
+            .durability
+            .unwrap_or(self.config.persistence_durability);
+        let (persisted, persisted_index) = match &self.writer {
+            Some(writer) if durability != Durability::Unlogged => {
+                match writer.append(&wave, durability).await {
+                    Ok(index) => (true, index),
+                    Err(err) if durability == Durability::Fsync => {
+                        return Err(AetherError::PersistenceError(err.to_string()));
+                    }
+                    Err(err) => {
+                        warn!("Failed to persist wave: {}", err);
+                        (false, None)
+                    }
+                }
+            }
+            _ => (false, None),
+        };
+        let wave_id = *wave.id();
+
+        // Cached before transmit so a subscriber woken by the wave finds it,
+        // and taken back out if the wave never goes out
+        let displaced = self.last_values.as_ref().and_then(|last_values| {
+            last_values.store(scoped_name(wave.namespace(), wave.channel().name()), &wave)
+        });
 
-        let persisted_index = if let Some(store) = &self.store {
-            match store.append_wave(&wave) {
-                Ok(index) => Some(index),
+        let sample = WaveSample {
+            channel: wave.channel().clone(),
+            source: wave.source_arc().cloned(),
+            wave_type: wave.wave_type().clone(),
+            payload_bytes: payload_size,
+        };
+        // Charged even when nobody is listening; it is the source being measured
+        self.sources.record(sample.source.as_ref(), payload_size);
+        let waves = match &self.chaos {
+            Some(chaos) => chaos.apply(wave).await?,
+            None => vec![wave],
+        };
+
+        let mut receivers = Some(0);
+        for wave in waves {
+            let reached = match self.transmit(wave).await {
+                Ok(reached) => reached,
                 Err(err) => {
-                    warn!("Failed to persist wave: {}", err);
-                    None
+                    if let (Some(last_values), Some(displaced)) = (&self.last_values, displaced) {
+                        last_values.undo(displaced);
+                    }
+                    return Err(err);
                 }
+            };
+            receivers = match (receivers, reached) {
+                (Some(total), Some(reached)) => Some(total + reached),
+                _ => None,
+            };
+        }
+
+        if receivers != Some(0) {
+            self.record_emit(persisted, &sample).await;
+        }
+
+        Ok(EmitReceipt {
+            wave_id,
+            receivers,
+            persisted_index,
+            transport: if self.config.use_nats {
+                EmitTransport::Nats
+            } else {
+                EmitTransport::Local
+            },
+        })
+    }
+
+    async fn propagation_limit_reached(&self, wave: Wave) -> Result<EmitReceipt> {
+        let policy = self.config.propagation_limit_policy;
+        metrics::counter!(
+            "aether_propagation_limit_total",
+            "channel" => PROPAGATION_LIMIT_CHANNELS.label(wave.channel().name()),
+            "policy" => policy.as_str()
+        )
+        .increment(1);
+        match policy {
+            PropagationLimitPolicy::Drop => Ok(EmitReceipt::dropped(*wave.id())),
+            PropagationLimitPolicy::Log => {
+                warn!(
+                    "Wave {} on {} reached max propagation count {}; dropped",
+                    wave.id(),
+                    wave.channel().name(),
+                    self.config.max_propagation
+                );
+                Ok(EmitReceipt::dropped(*wave.id()))
             }
-        } else {
-            None
+            PropagationLimitPolicy::Error => Err(AetherError::PropagationLimitReached(format!(
+                "wave {} on {} after {} hops",
+                wave.id(),
+                wave.channel().name(),
+                wave.propagation_count()
+            ))),
+            // A dead letter over the limit itself is dropped, not re-dead-lettered
+            PropagationLimitPolicy::DeadLetter
+                if wave
+                    .channel()
+                    .name()
+                    .starts_with(DEAD_LETTER_CHANNEL_PREFIX) =>
+            {
+                Ok(EmitReceipt::dropped(*wave.id()))
+            }
+            PropagationLimitPolicy::DeadLetter => {
+                let channel = format!("{}.{}", DEAD_LETTER_CHANNEL_PREFIX, wave.channel().name());
+                warn!(
+                    "Wave {} reached max propagation count; dead-lettered to {}",
+                    wave.id(),
+                    channel
+                );
+                let mut builder = Wave::builder(channel)
+                    .payload(serde_json::json!({
+                        "reason": "max_propagation",
+                        "wave_id": wave.id(),
+                        "channel": wave.channel().name(),
+                        "source": wave.source(),
+                        "propagation_count": wave.propagation_count(),
+                        "wave": wave,
+                    }))
+                    .timestamp(self.clock.now());
+                // Keeps the original source, and goes through the same checks
+                // as any other emit (name length, size, namespace, rate limit)
+                if let Some(source) = wave.source_arc() {
+                    builder = builder.source(Arc::clone(source));
+                }
+                let mut dead_letter = builder.build();
+                if let Some(namespace) = wave.namespace() {
+                    dead_letter.set_namespace(namespace);
+                }
+                if let Some(token) = wave.auth_token() {
+                    dead_letter.set_auth_token(token);
+                }
+                // The original wave is dropped either way; a rejected dead
+                // letter is reported here rather than failing its emit
+                if let Err(err) = Box::pin(self.emit(dead_letter)).await {
+                    warn!("Dead letter for wave {} was rejected: {}", wave.id(), err);
+                    metrics::counter!("aether_dead_letters_rejected_total").increment(1);
+                }
+                Ok(EmitReceipt::dropped(*wave.id()))
+            }
+        }
+    }
+
+    fn audit_rejection(&self, kind: AuditKind, wave: &Wave, err: &AetherError) {
+        let Some(audit) = &self.audit else {
+            return;
         };
+        let detail = serde_json::json!({
+            "wave_id": wave.id(),
+            "channel": wave.channel().name(),
+            "namespace": wave.namespace(),
+            "reason": err.to_string(),
+        });
+        if let Err(err) = audit.record_rejection(kind, wave.source(), detail) {
+            warn!("Failed to record audit event: {}", err);
+        }
+    }
+
+    /// Append to the audit log; a no-op when persistence is disabled
+    pub fn record_audit(&self, kind: AuditKind, actor: Option<&str>, detail: serde_json::Value) {
+        if let Some(audit) = &self.audit {
+            if let Err(err) = audit.record(kind, actor, detail) {
+                warn!("Failed to record audit event: {}", err);
+            }
+        }
+    }
+
+    /// Audit log, if persistence is enabled
+    pub fn audit(&self) -> Option<&AuditLog> {
+        self.audit.as_ref()
+    }
+
+    fn next_sequence(&self, key: &str) -> u64 {
+        let mut sequences = self.sequences.lock().expect("sequence lock poisoned");
+        let sequence = sequences.entry(key.to_string()).or_insert(0);
+        *sequence += 1;
+        *sequence
+    }
+
+    fn next_source_sequence(&self, source: &str) -> u64 {
+        let mut sequences = self
+            .source_sequences
+            .lock()
+            .expect("sequence lock poisoned");
+        let sequence = sequences.entry(source.to_string()).or_insert(0);
+        *sequence += 1;
+        *sequence
+    }
+
+    /// Identity stamped on every wave this layer emits
+    ///
+    /// Kept in the store, so it survives restarts; without persistence every
+    /// process is a new instance. Replicas sharing a service name have
+    /// different instances, and their epochs are never compared.
+    pub fn instance_id(&self) -> Uuid {
+        self.instance
+    }
+
+    /// Start count stamped on every wave this layer emits
+    ///
+    /// Counts the starts recorded in the store, so a restarted producer emits
+    /// under a higher epoch of the same instance and receivers can tell its
+    /// new waves from stale in-flight ones. Without persistence it is always 1.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Auth token required to emit into a namespace, or an error if not bridged
+    fn namespace_token(&self, namespace: Option<&str>) -> Result<Option<&String>> {
+        if namespace == self.config.namespace.as_deref() {
+            return Ok(self.config.auth_token.as_ref());
+        }
+        self.config
+            .bridges
+            .iter()
+            .find(|bridge| Some(bridge.namespace.as_str()) == namespace)
+            .map(|bridge| bridge.auth_token.as_ref())
+            .ok_or_else(|| {
+                AetherError::AuthorizationFailed(format!(
+                    "namespace {} is not bridged",
+                    namespace.unwrap_or("<none>")
+                ))
+            })
+    }
+
+    /// Hand a wave to the transport; returns the receivers it reached, or
+    /// `None` over NATS, which does not say
+    async fn transmit(&self, wave: Wave) -> Result<Option<usize>> {
+        let channel_name = scoped_name(wave.namespace(), wave.channel().name());
 
         if self.config.use_nats {
-            let subject = nats_subject(&channel_name);
-            let payload = serde_json::to_vec(&wave)
-                .map_err(|e| AetherError::TransmissionFailed(e.to_string()))?;
+            let subject = nats_subject(wave.namespace(), wave.channel().name());
+            // Once NATS drops the published bytes, the next encode reclaims their allocation
+            let mut buffer = self.publish_buffers.acquire().await;
+            let encoded = WaveCodec::Json.encode_into(&wave, buffer.as_mut());
+            let payload = buffer.as_mut().split().freeze();
+            buffer.release().await;
+            encoded?;
             let client = self.nats_client().await?;
 
-            if let Err(e) = client.publish(subject, payload.into()).await {
+            if let Err(e) = client.publish(subject, payload).await {
                 return Err(AetherError::TransmissionFailed(e.to_string()));
             }
 
-            // Update statistics
-            let mut stats = self.stats.write().await;
-            stats.total_waves += 1;
-
-            if let (Some(index), Some(store)) = (persisted_index, &self.store) {
-                if self.config.snapshot_interval > 0
-                    && stats.total_waves % self.config.snapshot_interval == 0
-                {
-                    let snapshot = crate::persistence::AetherSnapshot {
-                        last_index: index,
-                        stats: *stats,
-                        timestamp: chrono::Utc::now(),
-                    };
-                    if let Err(err) = store.save_snapshot(&snapshot) {
-                        warn!("Failed to save snapshot: {}", err);
-                    }
+            debug!("Published wave {} to NATS", wave.id());
+            return Ok(None);
+        }
+
+        // Create channel if it does not exist, and collect wildcard subscriptions it falls under
+        let senders = {
+            let now = self.clock.now();
+            let existing = self.channels.get(&channel_name).map(|entry| {
+                entry.touch(now);
+                entry.sender.clone()
+            });
+            let sender = existing.unwrap_or_else(|| {
+                let mut created = false;
+                let entry = self
+                    .channels
+                    .entry(channel_name.clone())
+                    .or_insert_with(|| {
+                        debug!("Creating new channel: {}", channel_name);
+                        created = true;
+                        let entry =
+                            ChannelEntry::new(&channel_name, self.config.channel_buffer_size, now);
+                        add_wildcard_route(&self.wildcards, &channel_name, &entry);
+                        entry
+                    });
+                entry.touch(now);
+                let sender = entry.sender.clone();
+                drop(entry);
+                if created {
+                    self.events.publish(AetherEvent::ChannelCreated {
+                        channel: channel_name.clone(),
+                    });
                 }
+                sender
+            });
+
+            let mut senders = vec![sender];
+            let wildcards = self.wildcards.load();
+            if !wildcards.is_empty() {
+                let scoped = Channel::new(channel_name.clone());
+                senders.extend(
+                    wildcards
+                        .iter()
+                        .filter(|route| {
+                            route.name != channel_name && scoped.matches(&route.pattern)
+                        })
+                        .map(|route| route.sender.clone()),
+                );
             }
-
-            metrics::counter!("aether_waves_total").increment(1);
-
-            debug!("Published wave {} to NATS", wave.id());
-            return Ok(());
-        }
-
-        // Create channel if it does not exist
-        let sender = {
-            let mut channels = self.channels.write().await;
-            channels
-                .entry(channel_name.clone())
-                .or_insert_with(|| {
-                    debug!("Creating new channel: {}", channel_name);
-                    let (tx, _) = broadcast::channel(self.config.channel_buffer_size);
-                    tx
-                })
-                .clone()
+            senders
         };
 
-        // Send wave
-        match sender.send(wave.clone()) {
-            Ok(receiver_count) => {
-                debug!(
-                    "Sent wave {} to channel {} ({} receivers)",
-                    wave.id(),
-                    channel_name,
-                    receiver_count
-                );
+        // Send wave; the last sender takes it without a clone
+        let wave_id = *wave.id();
+        let (last, rest) = senders.split_last().expect("the channel's own sender");
+        let receiver_count: usize = rest
+            .iter()
+            .filter_map(|sender| sender.send(wave.clone()).ok())
+            .sum::<usize>()
+            + last.send(wave).unwrap_or(0);
+        if receiver_count == 0 {
+            warn!("No receivers for wave {} on {}", wave_id, channel_name);
+            return Ok(Some(0));
+        }
+        debug!(
+            "Sent wave {} to channel {} ({} receivers)",
+            wave_id, channel_name, receiver_count
+        );
+        Ok(Some(receiver_count))
+    }
 
-                // Update statistics
-                let mut stats = self.stats.write().await;
-                stats.total_waves += 1;
-
-                if let (Some(index), Some(store)) = (persisted_index, &self.store) {
-                    if self.config.snapshot_interval > 0
-                        && stats.total_waves % self.config.snapshot_interval == 0
-                    {
-                        let snapshot = crate::persistence::AetherSnapshot {
-                            last_index: index,
-                            stats: *stats,
-                            timestamp: chrono::Utc::now(),
-                        };
-                        if let Err(err) = store.save_snapshot(&snapshot) {
-                            warn!("Failed to save snapshot: {}", err);
-                        }
-                    }
-                }
+    /// Update statistics and take a snapshot when the interval is reached
+    async fn record_emit(&self, persisted: bool, sample: &WaveSample) {
+        self.sketches
+            .lock()
+            .expect("sketch lock poisoned")
+            .record(sample);
+        *self
+            .channel_totals
+            .lock()
+            .expect("channel totals lock poisoned")
+            .entry(sample.channel.name().to_string())
+            .or_default() += 1;
+        let mut stats = self.stats.write().await;
+        stats.total_waves += 1;
+        if stats.total_waves % SKETCH_METRICS_EVERY == 0 {
+            self.current_stats(&stats).publish_metrics();
+        }
 
-                metrics::counter!("aether_waves_total").increment(1);
-            }
-            Err(e) => {
-                warn!("Failed to send wave: {:?}", e);
+        if let (true, Some(writer)) = (persisted, &self.writer) {
+            if self.config.snapshot_interval > 0
+                && stats.total_waves % self.config.snapshot_interval == 0
+            {
+                let current = self.current_stats(&stats);
+                // The writer fills in the index of the last wave written before it
+                let snapshot = self.build_snapshot(0, current);
+                drop(stats);
+                if let Err(err) = writer.snapshot(snapshot).await {
+                    warn!("Failed to save snapshot: {}", err);
+                }
             }
         }
 
-        Ok(())
+        metrics::counter!("aether_waves_total").increment(1);
     }
 
     /// Get a receiver to listen on a specific channel
     pub async fn subscribe(&self, channel: &Channel) -> broadcast::Receiver<Wave> {
-        let channel_name = channel.name().to_string();
-
-        let mut channels = self.channels.write().await;
-        let mut created = false;
-        let sender = channels
-            .entry(channel_name.clone())
-            .or_insert_with(|| {
-                created = true;
+        let namespace = self.config.namespace.as_deref();
+        let channel_name = scoped_name(namespace, channel.name());
+
+        let now = self.clock.now();
+        let (sender, created) = match self.channels.entry(channel_name.clone()) {
+            Entry::Occupied(entry) => {
+                entry.get().touch(now);
+                (entry.get().sender.clone(), false)
+            }
+            Entry::Vacant(entry) => {
                 debug!("Creating channel {} (subscribe)", channel_name);
-                let (tx, _) = broadcast::channel(self.config.channel_buffer_size);
-                tx
-            })
-            .clone();
-
-        if created {
-            metrics::gauge!("aether_active_channels").set(channels.len() as f64);
-        }
+                let entry = entry.insert(ChannelEntry::new(
+                    &channel_name,
+                    self.config.channel_buffer_size,
+                    now,
+                ));
+                add_wildcard_route(&self.wildcards, &channel_name, &entry);
+                (entry.sender.clone(), true)
+            }
+        };
 
         if self.config.use_nats && created {
-            let subject = nats_subject(&channel_name);
+            let subject = nats_subject(namespace, channel.name());
             let sender_clone = sender.clone();
+            let last_values = self.last_values.clone();
             let client_result = self.nats_client().await;
 
             match client_result {
                 Ok(client) => {
-                    tokio::spawn(async move {
+                    let bridge = tokio::spawn(async move {
                         let subject_for_log = subject.clone();
                         match client.subscribe(subject).await {
                             Ok(mut subscriber) => {
                                 while let Some(message) = subscriber.next().await {
-                                    match serde_json::from_slice::<Wave>(&message.payload) {
+                                    match Wave::from_bytes(&message.payload, WaveCodec::Json) {
                                         Ok(wave) => {
+                                            if let Some(last_values) = &last_values {
+                                                let name = scoped_name(
+                                                    wave.namespace(),
+                                                    wave.channel().name(),
+                                                );
+                                                last_values.store(name, &wave);
+                                            }
                                             let _ = sender_clone.send(wave);
                                         }
                                         Err(err) => {
@@ -362,6 +1346,15 @@ impl Aether {
                             }
                         }
                     });
+                    // The channel may have been removed or recreated while connecting
+                    match self.channels.get_mut(&channel_name) {
+                        Some(mut entry)
+                            if entry.bridge.is_none() && entry.sender.same_channel(&sender) =>
+                        {
+                            entry.bridge = Some(bridge);
+                        }
+                        _ => bridge.abort(),
+                    }
                 }
                 Err(err) => {
                     warn!("Failed to connect to NATS: {}", err);
@@ -369,9 +1362,42 @@ impl Aether {
             }
         }
 
+        if created {
+            record_channel_gauges(&self.channels);
+            self.events.publish(AetherEvent::ChannelCreated {
+                channel: channel_name,
+            });
+        }
+
         sender.subscribe()
     }
 
+    /// Most recent wave on a retained channel
+    pub fn last_wave(&self, channel: &Channel) -> Option<Wave> {
+        let last_values = self.last_values.as_ref()?;
+        last_values.get(&scoped_name(self.config.namespace.as_deref(), channel.name()))
+    }
+
+    /// Most recent wave on every retained channel matching a pattern, oldest first
+    pub fn last_waves(&self, pattern: &Channel) -> Vec<Wave> {
+        match &self.last_values {
+            Some(last_values) => last_values.matching(self.config.namespace.as_deref(), pattern),
+            None => Vec::new(),
+        }
+    }
+
+    /// Subscribe and also get the retained waves already on the channel
+    ///
+    /// A wave emitted while subscribing can show up both in the returned list
+    /// and on the receiver.
+    pub async fn subscribe_retained(
+        &self,
+        channel: &Channel,
+    ) -> (Vec<Wave>, broadcast::Receiver<Wave>) {
+        let receiver = self.subscribe(channel).await;
+        (self.last_waves(channel), receiver)
+    }
+
     /// Listen on multiple channels
     pub async fn subscribe_many(&self, channels: Vec<Channel>) -> Vec<broadcast::Receiver<Wave>> {
         let mut receivers = Vec::new();
@@ -381,41 +1407,262 @@ impl Aether {
         receivers
     }
 
+    /// Observe every wave matching a pattern without joining the channel
+    ///
+    /// With NATS this subscribes to the pattern on the broker; in memory it
+    /// sees every wave emitted through this layer. Lagging taps skip waves.
+    pub async fn tap(&self, pattern: Channel) -> impl Stream<Item = Wave> + Send + 'static {
+        let receiver = if self.config.use_nats {
+            self.subscribe(&pattern).await
+        } else {
+            self.taps.subscribe()
+        };
+
+        let namespace = self.config.namespace.clone();
+        let events = self.events.clone();
+        futures::stream::unfold(receiver, move |mut receiver| {
+            let pattern = pattern.clone();
+            let namespace = namespace.clone();
+            let events = events.clone();
+            async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(wave)
+                            if wave.namespace() == namespace.as_deref()
+                                && wave.channel().matches(&pattern) =>
+                        {
+                            return Some((wave, receiver))
+                        }
+                        Ok(_) => continue,
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            warn!("Tap on {} skipped {} waves", pattern, skipped);
+                            events.publish(AetherEvent::SubscriberLagged {
+                                subscriber: "tap".to_string(),
+                                channel: pattern.name().to_string(),
+                                skipped,
+                            });
+                        }
+                        Err(broadcast::error::RecvError::Closed) => return None,
+                    }
+                }
+            }
+        })
+    }
+
     /// Get Aether layer statistics
     pub async fn stats(&self) -> AetherStats {
         let stats = self.stats.read().await;
-        let channels = self.channels.read().await;
+        let current = self.current_stats(&stats);
+        current.publish_metrics();
+        current
+    }
 
-        AetherStats {
+    fn current_stats(&self, stats: &AetherStats) -> AetherStats {
+        let mut current = AetherStats {
             total_waves: stats.total_waves,
-            active_channels: channels.len(),
-            total_vibrators: stats.total_vibrators,
+            active_channels: self.channels.len(),
+            total_vibrators: self.vibrators.lock().expect("vibrator lock poisoned").values().sum(),
+            ..AetherStats::default()
+        };
+        self.sketches
+            .lock()
+            .expect("sketch lock poisoned")
+            .fill(&mut current);
+        current
+    }
+
+    /// Copy of the layer's sketches, for merging across shards
+    pub(crate) fn sketches(&self) -> WaveSketches {
+        self.sketches.lock().expect("sketch lock poisoned").clone()
+    }
+
+    pub(crate) fn source_table(&self) -> &SourceTable {
+        &self.sources
+    }
+
+    pub(crate) fn event_bus(&self) -> &EventBus {
+        &self.events
+    }
+
+    pub(crate) fn connection(&self) -> &ConnectionTracker {
+        &self.connection
+    }
+
+    /// Names of the vibrators attached to this layer
+    pub fn vibrators(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .vibrators
+            .lock()
+            .expect("vibrator lock poisoned")
+            .keys()
+            .cloned()
+            .collect();
+        names.sort();
+        names
+    }
+
+    pub(crate) fn register_vibrator(&self, name: &str) {
+        let mut vibrators = self.vibrators.lock().expect("vibrator lock poisoned");
+        *vibrators.entry(name.to_string()).or_insert(0) += 1;
+    }
+
+    pub(crate) fn unregister_vibrator(&self, name: &str) {
+        let mut vibrators = self.vibrators.lock().expect("vibrator lock poisoned");
+        if let Some(count) = vibrators.get_mut(name) {
+            *count -= 1;
+            if *count == 0 {
+                vibrators.remove(name);
+            }
+        }
+    }
+
+    /// Waves queued on local channels that some receiver has not read yet
+    pub async fn pending_deliveries(&self) -> Vec<(String, usize)> {
+        let mut pending: Vec<(String, usize)> = self
+            .channels
+            .iter()
+            .filter(|entry| entry.sender.receiver_count() > 0 && !entry.sender.is_empty())
+            .map(|entry| (entry.key().clone(), entry.sender.len()))
+            .collect();
+        if !self.taps.is_empty() {
+            pending.push(("<tap>".to_string(), self.taps.len()));
         }
+        pending
     }
 
-    /// Get list of active channels
+    /// Get list of active channels in this layer's namespace
     pub async fn active_channels(&self) -> Vec<String> {
-        let channels = self.channels.read().await;
-        channels.keys().cloned().collect()
+        match &self.config.namespace {
+            Some(namespace) => {
+                let prefix = format!("{}.", namespace);
+                self.channels
+                    .iter()
+                    .filter_map(|entry| entry.key().strip_prefix(&prefix).map(str::to_string))
+                    .collect()
+            }
+            None => self
+                .channels
+                .iter()
+                .map(|entry| entry.key().clone())
+                .collect(),
+        }
     }
 
-    /// Remove a specific channel (cleanup)
+    /// Remove a specific channel (cleanup), stopping its NATS subscription
     pub async fn remove_channel(&self, channel: &Channel) -> Result<()> {
         let channel_name = channel.name();
-        let mut channels = self.channels.write().await;
-
-        if channels.remove(channel_name).is_some() {
+        let scoped = scoped_name(self.config.namespace.as_deref(), channel_name);
+        if let Some((_, entry)) = self.channels.remove(&scoped) {
+            remove_wildcard_route(&self.wildcards, &entry);
+            record_channel_gauges(&self.channels);
             info!("Removed channel {}", channel_name);
+            self.events.publish(AetherEvent::ChannelRemoved {
+                channel: scoped,
+                idle: false,
+            });
             Ok(())
         } else {
             Err(AetherError::ChannelNotFound(channel_name.to_string()))
         }
     }
 
-    /// Clear the Aether layer
+    /// Remove a channel once its last receiver is gone, closing its NATS bridge
+    ///
+    /// Returns whether it was removed; a channel other receivers still hold
+    /// stays, as does one that does not exist.
+    pub async fn release_channel(&self, channel: &Channel) -> bool {
+        let scoped = scoped_name(self.config.namespace.as_deref(), channel.name());
+        let Some((_, entry)) = self
+            .channels
+            .remove_if(&scoped, |_, entry| entry.sender.receiver_count() == 0)
+        else {
+            return false;
+        };
+        remove_wildcard_route(&self.wildcards, &entry);
+        record_channel_gauges(&self.channels);
+        debug!("Released channel {}", scoped);
+        self.events.publish(AetherEvent::ChannelRemoved {
+            channel: scoped,
+            idle: false,
+        });
+        true
+    }
+
+    /// Remove channels with no receivers that have been idle past the timeout
+    ///
+    /// Returns the removed channel names. Does nothing unless
+    /// `channel_idle_timeout_ms` is set.
+    pub async fn collect_idle_channels(&self) -> Vec<String> {
+        let Some(timeout_ms) = self.config.channel_idle_timeout_ms else {
+            return Vec::new();
+        };
+        let timeout = chrono::Duration::milliseconds(timeout_ms as i64);
+        let now = self.clock.now();
+        let candidates: Vec<String> = self
+            .channels
+            .iter()
+            .filter(|entry| entry.is_idle(now, timeout))
+            .map(|entry| entry.key().clone())
+            .collect();
+        // Re-checked under the shard lock in case a subscriber arrived meanwhile
+        let idle: Vec<String> = candidates
+            .into_iter()
+            .filter(|name| {
+                let removed = self
+                    .channels
+                    .remove_if(name, |_, entry| entry.is_idle(now, timeout));
+                if let Some((_, entry)) = &removed {
+                    remove_wildcard_route(&self.wildcards, entry);
+                }
+                removed.is_some()
+            })
+            .collect();
+        for name in &idle {
+            debug!("Collected idle channel {}", name);
+            self.events.publish(AetherEvent::ChannelRemoved {
+                channel: name.clone(),
+                idle: true,
+            });
+        }
+        if !idle.is_empty() {
+            metrics::counter!("aether_channels_collected_total").increment(idle.len() as u64);
+            record_channel_gauges(&self.channels);
+        }
+        idle
+    }
+
+    /// Periodically collect idle channels; `None` when no idle timeout is set
+    pub fn spawn_channel_janitor(&self) -> Option<JoinHandle<()>> {
+        let timeout_ms = self.config.channel_idle_timeout_ms?;
+        let aether = self.clone();
+        let period = std::time::Duration::from_millis((timeout_ms / 2).max(1000));
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            loop {
+                ticker.tick().await;
+                let collected = aether.collect_idle_channels().await;
+                if !collected.is_empty() {
+                    info!("Collected {} idle channels", collected.len());
+                }
+            }
+        }))
+    }
+
+    /// NATS subscriber tasks currently feeding local channels
+    pub async fn nats_bridge_tasks(&self) -> usize {
+        self.channels
+            .iter()
+            .filter(|entry| entry.has_running_bridge())
+            .count()
+    }
+
+    /// Clear the Aether layer, stopping every NATS subscription
     pub async fn clear(&self) {
-        let mut channels = self.channels.write().await;
-        channels.clear();
+        self.channels.retain(|_, entry| {
+            remove_wildcard_route(&self.wildcards, entry);
+            false
+        });
+        record_channel_gauges(&self.channels);
         info!("Cleared the Aether layer");
     }
 
@@ -424,6 +1671,259 @@ impl Aether {
         &self.config
     }
 
+    /// View of this layer in another namespace, sharing the same transport
+    ///
+    /// Bridges and the auth token are not inherited.
+    pub fn for_namespace(&self, namespace: impl Into<String>) -> Self {
+        let mut scoped = self.clone();
+        scoped.config.namespace = Some(namespace.into());
+        scoped.config.bridges = Vec::new();
+        scoped.config.auth_token = None;
+        scoped.cluster = Arc::new(PeerTable::new());
+        scoped
+    }
+
+    /// Time source used by this layer
+    pub fn clock(&self) -> &SharedClock {
+        &self.clock
+    }
+
+    pub(crate) fn cluster_table(&self) -> &PeerTable {
+        &self.cluster
+    }
+
+    /// Persistence log, if persistence is enabled
+    pub fn wave_store(&self) -> Option<&crate::persistence::WaveStore> {
+        self.store.as_ref()
+    }
+
+    /// Redaction rules for anything that logs, records or exports waves
+    pub fn redactor(&self) -> &Arc<Redactor> {
+        &self.redactor
+    }
+
+    /// Fault injection controls, if chaos is configured
+    pub fn chaos(&self) -> Option<&Chaos> {
+        self.chaos.as_deref()
+    }
+
+    /// Flush pending NATS publishes and queued persistence writes
+    pub async fn flush(&self) -> Result<()> {
+        if let Some(client) = self.nats_client.get() {
+            client
+                .flush()
+                .await
+                .map_err(|e| AetherError::TransmissionFailed(e.to_string()))?;
+        }
+        if let Some(writer) = &self.writer {
+            writer
+                .flush()
+                .await
+                .map_err(|e| AetherError::PersistenceError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Save a snapshot of the current state immediately
+    ///
+    /// Returns `None` when persistence is disabled or nothing has been logged yet.
+    pub async fn snapshot(&self) -> Result<Option<crate::persistence::AetherSnapshot>> {
+        let (Some(store), Some(writer)) = (&self.store, &self.writer) else {
+            return Ok(None);
+        };
+        writer
+            .flush()
+            .await
+            .map_err(|e| AetherError::PersistenceError(e.to_string()))?;
+        let last_index = store
+            .last_index()
+            .map_err(|e| AetherError::PersistenceError(e.to_string()))?;
+        let Some(last_index) = last_index else {
+            return Ok(None);
+        };
+
+        let snapshot = {
+            let stats = self.stats.read().await;
+            self.build_snapshot(last_index, self.current_stats(&stats))
+        };
+        store
+            .save_snapshot(&snapshot)
+            .map_err(|e| AetherError::PersistenceError(e.to_string()))?;
+        self.events.publish(AetherEvent::SnapshotSaved {
+            last_index: snapshot.last_index,
+            total_waves: snapshot.stats.total_waves,
+        });
+        Ok(Some(snapshot))
+    }
+
+    /// Snapshot now if waves were logged since the last snapshot
+    ///
+    /// Returns `None` when there was nothing new to cover.
+    pub async fn snapshot_if_behind(
+        &self,
+    ) -> Result<Option<crate::persistence::AetherSnapshot>> {
+        let (Some(store), Some(writer)) = (&self.store, &self.writer) else {
+            return Ok(None);
+        };
+        writer
+            .flush()
+            .await
+            .map_err(|e| AetherError::PersistenceError(e.to_string()))?;
+        let covered = store
+            .load_snapshot()
+            .map_err(|e| AetherError::PersistenceError(e.to_string()))?
+            .map(|snapshot| snapshot.last_index);
+        let last_index = store
+            .last_index()
+            .map_err(|e| AetherError::PersistenceError(e.to_string()))?;
+        if last_index.is_none() || last_index == covered {
+            return Ok(None);
+        }
+        self.snapshot().await
+    }
+
+    /// Periodically snapshot a layer that logged waves since its last snapshot
+    ///
+    /// Complements `snapshot_interval`, which only fires on busy layers.
+    /// `None` without persistence or when `snapshot_every_secs` is 0.
+    pub fn spawn_snapshot_scheduler(&self) -> Option<JoinHandle<()>> {
+        if self.store.is_none() || self.config.snapshot_every_secs == 0 {
+            return None;
+        }
+        let aether = self.clone();
+        let period = std::time::Duration::from_secs(self.config.snapshot_every_secs);
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            loop {
+                ticker.tick().await;
+                match aether.snapshot_if_behind().await {
+                    Ok(Some(snapshot)) => {
+                        debug!("Scheduled snapshot at index {}", snapshot.last_index)
+                    }
+                    Ok(None) => {}
+                    Err(err) => warn!("Failed to save scheduled snapshot: {}", err),
+                }
+            }
+        }))
+    }
+
+    /// Waves transmitted per channel name, including those counted by the
+    /// restored snapshot
+    pub fn channel_totals(&self) -> BTreeMap<String, u64> {
+        self.channel_totals
+            .lock()
+            .expect("channel totals lock poisoned")
+            .clone()
+    }
+
+    fn build_snapshot(
+        &self,
+        last_index: u64,
+        stats: AetherStats,
+    ) -> crate::persistence::AetherSnapshot {
+        let mut channel_names: Vec<String> = self
+            .channels
+            .iter()
+            .map(|entry| entry.key().clone())
+            .collect();
+        channel_names.sort();
+        let cursors = match self.store.as_ref().map(|store| store.cursors()) {
+            Some(Ok(cursors)) => cursors,
+            Some(Err(err)) => {
+                warn!("Failed to read log cursors for snapshot: {}", err);
+                BTreeMap::new()
+            }
+            None => BTreeMap::new(),
+        };
+        crate::persistence::AetherSnapshot {
+            last_index,
+            stats,
+            timestamp: self.clock.now(),
+            sequences: self.sequences.lock().expect("sequence lock poisoned").clone(),
+            channels: channel_names,
+            vibrators: self.vibrators(),
+            registry: self.registry.as_ref().map(|registry| registry.to_config()),
+            channel_totals: self.channel_totals(),
+            cursors,
+        }
+    }
+
+    /// Rebuild counters, channels and the registry from the last snapshot
+    ///
+    /// Waves logged after the snapshot are counted and advance the sequence
+    /// numbers, so numbering continues where the previous run stopped. A
+    /// configured registry takes precedence over the snapshot's. Local channels
+    /// are only recreated for the in-memory transport; with NATS they are
+    /// created (and subscribed) on first use. Call once at startup.
+    pub async fn restore_from_snapshot(
+        &mut self,
+    ) -> Result<Option<crate::persistence::AetherSnapshot>> {
+        let Some(store) = &self.store else {
+            return Ok(None);
+        };
+        let Some(snapshot) = store
+            .load_snapshot()
+            .map_err(|e| AetherError::PersistenceError(e.to_string()))?
+        else {
+            return Ok(None);
+        };
+        let later = store
+            .read_from(snapshot.last_index + 1)
+            .map_err(|e| AetherError::PersistenceError(e.to_string()))?;
+
+        {
+            let mut sequences = self.sequences.lock().expect("sequence lock poisoned");
+            sequences.extend(snapshot.sequences.clone());
+            for wave in &later {
+                if let Some(sequence) = wave.sequence() {
+                    let key = sequence_key(
+                        &scoped_name(wave.namespace(), wave.channel().name()),
+                        wave.source(),
+                    );
+                    let last = sequences.entry(key).or_insert(0);
+                    *last = (*last).max(sequence);
+                }
+            }
+        }
+
+        self.stats.write().await.total_waves = snapshot.stats.total_waves + later.len() as u64;
+        {
+            let mut totals = self
+                .channel_totals
+                .lock()
+                .expect("channel totals lock poisoned");
+            totals.extend(snapshot.channel_totals.clone());
+            for wave in &later {
+                *totals.entry(wave.channel().name().to_string()).or_default() += 1;
+            }
+        }
+
+        if !self.config.use_nats {
+            let now = self.clock.now();
+            for name in &snapshot.channels {
+                self.channels.entry(name.clone()).or_insert_with(|| {
+                    let entry = ChannelEntry::new(name, self.config.channel_buffer_size, now);
+                    add_wildcard_route(&self.wildcards, name, &entry);
+                    entry
+                });
+            }
+            record_channel_gauges(&self.channels);
+        }
+
+        if self.registry.is_none() {
+            if let Some(registry) = &snapshot.registry {
+                self.registry = Some(Arc::new(ChannelRegistry::from_config(registry.clone())));
+            }
+        }
+
+        info!(
+            "Restored snapshot at index {} ({} later waves)",
+            snapshot.last_index,
+            later.len()
+        );
+        Ok(Some(snapshot))
+    }
+
     /// Recover waves from persistence store since last snapshot
     pub fn recover_waves(&self) -> Result<Vec<Wave>> {
         if let Some(store) = &self.store {
@@ -439,19 +1939,29 @@ impl Aether {
         }
     }
 
+    /// Server URLs in the order this layer tries them
+    fn nats_urls(&self) -> Vec<String> {
+        if self.config.nats_servers.is_empty() {
+            vec![self.config.nats_url.clone()]
+        } else {
+            connection::server_order(&self.config.nats_servers, &mut rand::thread_rng())
+        }
+    }
+
     async fn nats_client(&self) -> Result<async_nats::Client> {
-        let url = self.config.nats_url.clone();
+        let urls = self.nats_urls();
         let tls_required = self.config.nats_tls_required;
         let client = self
             .nats_client
             .get_or_try_init(|| async move {
-                if tls_required && !url.starts_with("tls://") {
+                if tls_required && urls.iter().any(|url| !url.starts_with("tls://")) {
                     return Err(AetherError::ConnectionFailed(
                         "TLS required for NATS connection".to_string(),
                     ));
                 }
 
-                let mut options = ConnectOptions::new();
+                // Keep the weighted order instead of letting the client reshuffle it
+                let mut options = ConnectOptions::new().retain_servers_order();
 
                 if tls_required {
                     options = options.require_tls(true);
@@ -468,42 +1978,134 @@ impl Aether {
                     options = options.add_client_certificate(cert_path.into(), key_path.into());
                 }
 
-                options
-                    .connect(url)
-                    .await
-                    .map_err(|e| AetherError::ConnectionFailed(e.to_string()))
+                // The first `Connected` is the initial connect; later ones follow a drop
+                let events = self.events.clone();
+                let connection = Arc::clone(&self.connection);
+                let clock = Arc::clone(&self.clock);
+                let client = Arc::clone(&self.nats_client);
+                let dropped = Arc::new(AtomicBool::new(false));
+                options = options.event_callback(move |event| {
+                    let events = events.clone();
+                    let connection = Arc::clone(&connection);
+                    let now = clock.now();
+                    let server = client
+                        .get()
+                        .map(|client| connection::server_label(&client.server_info()));
+                    let dropped = Arc::clone(&dropped);
+                    async move {
+                        match event {
+                            async_nats::Event::Connected => {
+                                connection.connected(server, now);
+                                events.publish(if dropped.load(Ordering::Relaxed) {
+                                    AetherEvent::NatsReconnected
+                                } else {
+                                    AetherEvent::NatsConnected
+                                });
+                            }
+                            async_nats::Event::Disconnected => {
+                                dropped.store(true, Ordering::Relaxed);
+                                connection.dropped();
+                                events.publish(AetherEvent::NatsDisconnected);
+                            }
+                            async_nats::Event::Closed => connection.closed(),
+                            async_nats::Event::ServerError(err) => {
+                                connection.error(err.to_string(), now)
+                            }
+                            async_nats::Event::ClientError(err) => {
+                                connection.error(err.to_string(), now)
+                            }
+                            _ => {}
+                        }
+                    }
+                });
+
+                let client = options.connect(urls).await.map_err(|e| {
+                    self.connection.error(e.to_string(), self.clock.now());
+                    AetherError::ConnectionFailed(e.to_string())
+                })?;
+                // The callback runs on the client's task; record the first connect now
+                let server = connection::server_label(&client.server_info());
+                self.connection.connected(Some(server), self.clock.now());
+                Ok(client)
             })
             .await?;
         Ok(client.clone())
     }
 }
 
+impl Default for Aether {
+    /// Create an Aether layer with default configuration
+    fn default() -> Self {
+        Self::new(AetherConfig::default())
+    }
+}
+
 impl Clone for Aether {
     fn clone(&self) -> Self {
         Self {
             config: self.config.clone(),
             channels: Arc::clone(&self.channels),
+            wildcards: Arc::clone(&self.wildcards),
             stats: Arc::clone(&self.stats),
+            sketches: Arc::clone(&self.sketches),
+            sources: Arc::clone(&self.sources),
+            loops: Arc::clone(&self.loops),
+            events: self.events.clone(),
+            connection: Arc::clone(&self.connection),
             nats_client: Arc::clone(&self.nats_client),
+            publish_buffers: self.publish_buffers.clone(),
             store: self.store.clone(),
+            writer: self.writer.clone(),
+            audit: self.audit.clone(),
+            taps: self.taps.clone(),
+            chaos: self.chaos.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+            quotas: self.quotas.clone(),
+            registry: self.registry.clone(),
+            last_values: self.last_values.clone(),
+            redactor: Arc::clone(&self.redactor),
+            vibrators: Arc::clone(&self.vibrators),
+            sequences: Arc::clone(&self.sequences),
+            channel_totals: Arc::clone(&self.channel_totals),
+            instance: self.instance,
+            epoch: self.epoch,
+            source_sequences: Arc::clone(&self.source_sequences),
+            clock: Arc::clone(&self.clock),
+            cluster: Arc::clone(&self.cluster),
+            transforms: Arc::clone(&self.transforms),
+            validators: Arc::clone(&self.validators),
         }
     }
 }
 
-fn nats_subject(channel_name: &str) -> String {
-    if channel_name == "*" {
-        ">".to_string()
-    } else {
-        channel_name.to_string()
+/// NATS subject for a channel; "*" and ">" share NATS semantics so pass through
+fn nats_subject(namespace: Option<&str>, channel_name: &str) -> String {
+    scoped_name(namespace, channel_name)
+}
+
+/// Transport-level name of a channel inside a namespace
+fn scoped_name(namespace: Option<&str>, channel_name: &str) -> String {
+    match namespace {
+        Some(namespace) => format!("{}.{}", namespace, channel_name),
+        None => channel_name.to_string(),
     }
 }
 
-fn is_valid_channel_name(name: &str, max_len: usize) -> bool {
+/// Sequences count per stream, and receivers tell streams apart by channel
+/// and source; `@` never appears in a channel name, so keys can't collide
+fn sequence_key(channel_name: &str, source: Option<&str>) -> String {
+    match source {
+        Some(source) => format!("{}@{}", channel_name, source),
+        None => channel_name.to_string(),
+    }
+}
+
+pub(crate) fn is_valid_channel_name(name: &str, max_len: usize) -> bool {
     if name.is_empty() || name.len() > max_len {
         return false;
     }
     name.chars()
-        .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-' || c == '*')
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '*' | '>'))
 }
 
 #[cfg(test)]
@@ -541,6 +2143,203 @@ mod tests {
         assert_eq!(received.channel().name(), channel.name());
     }
 
+    #[tokio::test]
+    async fn test_emits_are_stamped_with_epoch_and_source_sequence() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            ..AetherConfig::default()
+        });
+        let mut receiver = aether.subscribe(&Channel::new("orders.>")).await;
+        for channel in ["orders.created", "orders.shipped"] {
+            let wave = Wave::builder(channel).source("orders").build();
+            aether.emit(wave).await.unwrap();
+        }
+
+        let first = receiver.recv().await.unwrap();
+        let second = receiver.recv().await.unwrap();
+        assert_eq!(first.epoch(), Some(aether.epoch()));
+        assert_eq!(first.instance(), Some(&aether.instance_id()));
+        assert_eq!(first.source_sequence(), Some(1));
+        assert_eq!(second.source_sequence(), Some(2));
+        assert_eq!((first.sequence(), second.sequence()), (Some(1), Some(1)));
+    }
+
+    #[tokio::test]
+    async fn test_try_new_fails_while_another_layer_holds_the_store() {
+        let path = std::env::temp_dir().join(format!("aether-lease-{}", uuid::Uuid::new_v4()));
+        let config = AetherConfig {
+            use_nats: false,
+            persistence_enabled: true,
+            persistence_path: path.to_string_lossy().into_owned(),
+            ..AetherConfig::default()
+        };
+        let first = Aether::try_new(config.clone()).unwrap();
+        assert!(first.wave_store().unwrap().is_writable());
+
+        let err = Aether::try_new(config.clone()).err().unwrap().to_string();
+        assert!(err.contains("already open for writing"), "{}", err);
+        assert!(Aether::new(config).wave_store().is_none());
+
+        drop(first);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    #[should_panic(expected = "Failed to load persistence encryption keys")]
+    fn test_new_refuses_to_run_when_encryption_keys_fail_to_load() {
+        let path = std::env::temp_dir().join(format!("aether-keys-{}", uuid::Uuid::new_v4()));
+        Aether::new(AetherConfig {
+            use_nats: false,
+            persistence_enabled: true,
+            persistence_path: path.to_string_lossy().into_owned(),
+            persistence_encryption: Some(EncryptionConfig {
+                active_key: "k1".to_string(),
+                keys: vec![crate::encryption::EncryptionKeyConfig {
+                    id: "k1".to_string(),
+                    key: None,
+                    key_env: Some("AETHER_TEST_KEY_THAT_IS_NEVER_SET".to_string()),
+                    key_file: None,
+                }],
+            }),
+            ..AetherConfig::default()
+        });
+    }
+
+    #[tokio::test]
+    async fn test_snapshots_carry_channel_totals_and_reader_cursors() {
+        let path = std::env::temp_dir().join(format!("aether-snapshot-{}", uuid::Uuid::new_v4()));
+        let config = AetherConfig {
+            use_nats: false,
+            persistence_enabled: true,
+            persistence_path: path.to_string_lossy().into_owned(),
+            ..AetherConfig::default()
+        };
+        let aether = Aether::new(config.clone());
+        let _receiver = aether.subscribe(&Channel::new("orders.>")).await;
+        for channel in ["orders.created", "orders.created", "orders.shipped"] {
+            aether.emit(Wave::new(channel, serde_json::json!({}))).await.unwrap();
+        }
+        aether.wave_store().unwrap().save_cursor("exporter", 1).unwrap();
+
+        let snapshot = aether.snapshot_if_behind().await.unwrap().unwrap();
+        assert_eq!(snapshot.last_index, 2);
+        assert_eq!(snapshot.channel_totals["orders.created"], 2);
+        assert_eq!(snapshot.cursors["exporter"], 1);
+        assert_eq!(snapshot.retain_from(), 1);
+        assert!(aether.snapshot_if_behind().await.unwrap().is_none());
+
+        aether
+            .emit(Wave::new("orders.shipped", serde_json::json!({})))
+            .await
+            .unwrap();
+        aether.flush().await.unwrap();
+        drop(aether);
+
+        // sled's IO threads can hold the file lock briefly after the drop
+        let mut restarted = Aether::new(config.clone());
+        while restarted.wave_store().is_none() {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            restarted = Aether::new(config.clone());
+        }
+        restarted.restore_from_snapshot().await.unwrap().unwrap();
+        let totals = restarted.channel_totals();
+        assert_eq!((totals["orders.created"], totals["orders.shipped"]), (2, 2));
+
+        drop(restarted);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[tokio::test]
+    async fn test_wildcard_subscriptions_receive_matching_waves() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            ..AetherConfig::default()
+        });
+        let mut all_orders = aether.subscribe(&Channel::new("orders.>")).await;
+        let mut failures = aether.subscribe(&Channel::new("orders.*.failed")).await;
+
+        aether
+            .emit(Wave::new("orders.eu.failed", serde_json::json!({})))
+            .await
+            .unwrap();
+        aether
+            .emit(Wave::new("orders.eu.created", serde_json::json!({})))
+            .await
+            .unwrap();
+
+        assert_eq!(all_orders.recv().await.unwrap().channel().name(), "orders.eu.failed");
+        assert_eq!(all_orders.recv().await.unwrap().channel().name(), "orders.eu.created");
+        assert_eq!(failures.recv().await.unwrap().channel().name(), "orders.eu.failed");
+        assert!(failures.try_recv().is_err());
+
+        let err = aether
+            .emit(Wave::new("orders.>", serde_json::json!({})))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AetherError::ValidationFailed(_)));
+
+        // Removing a wildcard channel stops routing to it
+        assert_eq!(aether.wildcards.load().len(), 2);
+        aether.remove_channel(&Channel::new("orders.>")).await.unwrap();
+        assert_eq!(aether.wildcards.load().len(), 1);
+        aether.clear().await;
+        assert!(aether.wildcards.load().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_propagation_limit_policies() {
+        let config = |policy| AetherConfig {
+            use_nats: false,
+            max_propagation: 1,
+            propagation_limit_policy: policy,
+            ..AetherConfig::default()
+        };
+        let mut hopped = Wave::new("orders.created", serde_json::json!({ "id": 7 }));
+        hopped.propagate();
+
+        let strict = Aether::new(config(PropagationLimitPolicy::Error));
+        let err = strict.emit(hopped.clone()).await.unwrap_err();
+        assert!(matches!(err, AetherError::PropagationLimitReached(_)));
+
+        let quiet = Aether::new(config(PropagationLimitPolicy::Drop));
+        quiet.emit(hopped.clone()).await.unwrap();
+
+        let dead_letters = Aether::new(config(PropagationLimitPolicy::DeadLetter));
+        let mut rx = dead_letters
+            .subscribe(&Channel::new("aether.deadletter.orders.created"))
+            .await;
+        dead_letters.emit(hopped.clone()).await.unwrap();
+        let dead = rx.recv().await.unwrap();
+        assert_eq!(dead.payload()["wave_id"], hopped.id().to_string());
+        assert_eq!(dead.payload()["channel"], "orders.created");
+        assert_eq!(dead.payload()["wave"]["payload"]["id"], 7);
+    }
+
+    #[tokio::test]
+    async fn test_dead_letter_goes_through_admission_checks() {
+        let channel = "orders.eu.created";
+        let dead_letters = Aether::new(AetherConfig {
+            use_nats: false,
+            max_propagation: 1,
+            propagation_limit_policy: PropagationLimitPolicy::DeadLetter,
+            max_channel_length: channel.len(),
+            ..AetherConfig::default()
+        });
+        // The dead-letter name is longer than allowed
+        let mut rx = dead_letters
+            .subscribe(&Channel::new("aether.deadletter.orders.eu.created"))
+            .await;
+        let mut hopped = Wave::new(channel, serde_json::json!({ "id": 8 }));
+        hopped.propagate();
+
+        let receipt = dead_letters.emit(hopped).await.unwrap();
+        assert_eq!(receipt.transport, EmitTransport::Dropped);
+        assert!(matches!(
+            rx.try_recv(),
+            Err(broadcast::error::TryRecvError::Empty)
+        ));
+    }
+
     #[tokio::test]
     async fn test_multiple_subscribers() {
         let aether = Aether::new(AetherConfig {
@@ -562,4 +2361,212 @@ mod tests {
         assert!(rx1.recv().await.is_ok());
         assert!(rx2.recv().await.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_namespaces_are_isolated() {
+        let staging = Aether::new(AetherConfig {
+            use_nats: false,
+            namespace: Some("staging".to_string()),
+            ..AetherConfig::default()
+        });
+        let prod = staging.for_namespace("prod");
+        let channel = Channel::new("orders.created");
+        let mut staging_rx = staging.subscribe(&channel).await;
+        let mut prod_rx = prod.subscribe(&channel).await;
+
+        prod.emit(Wave::new(channel.clone(), serde_json::json!({})))
+            .await
+            .unwrap();
+
+        assert_eq!(prod_rx.recv().await.unwrap().namespace(), Some("prod"));
+        assert!(staging_rx.try_recv().is_err());
+        assert_eq!(staging.active_channels().await, vec!["orders.created"]);
+    }
+
+    #[tokio::test]
+    async fn test_cross_namespace_emit_requires_bridge() {
+        let config = AetherConfig {
+            use_nats: false,
+            namespace: Some("staging".to_string()),
+            ..AetherConfig::default()
+        };
+        let isolated = Aether::new(config.clone());
+        let mut wave = Wave::new("orders.created", serde_json::json!({}));
+        wave.set_namespace("prod");
+        let err = isolated.emit(wave.clone()).await.unwrap_err();
+        assert!(matches!(err, AetherError::AuthorizationFailed(_)));
+
+        let bridged = Aether::new(AetherConfig {
+            bridges: vec![NamespaceBridge {
+                namespace: "prod".to_string(),
+                auth_token: Some("prod-token".to_string()),
+            }],
+            ..config
+        });
+        let mut prod_rx = bridged
+            .for_namespace("prod")
+            .subscribe(&Channel::new("orders.created"))
+            .await;
+        assert!(bridged.emit(wave.clone()).await.is_err());
+
+        wave.set_auth_token("prod-token");
+        bridged.emit(wave).await.unwrap();
+        assert!(prod_rx.recv().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_idle_channels_without_receivers_are_collected() {
+        let clock = crate::clock::VirtualClock::new();
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            channel_idle_timeout_ms: Some(60_000),
+            ..AetherConfig::default()
+        })
+        .with_clock(Arc::new(clock.clone()));
+        drop(aether.subscribe(&Channel::new("orders.created")).await);
+        let _listening = aether.subscribe(&Channel::new("orders.paid")).await;
+
+        clock.advance(std::time::Duration::from_secs(30));
+        assert!(aether.collect_idle_channels().await.is_empty());
+        let _ = aether
+            .emit(Wave::new("orders.created", serde_json::json!({})))
+            .await;
+
+        clock.advance(std::time::Duration::from_secs(45));
+        assert!(aether.collect_idle_channels().await.is_empty());
+
+        clock.advance(std::time::Duration::from_secs(30));
+        assert_eq!(aether.collect_idle_channels().await, vec!["orders.created"]);
+        assert_eq!(aether.active_channels().await, vec!["orders.paid"]);
+    }
+
+    #[tokio::test]
+    async fn test_removing_channels_cancels_bridges() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            ..AetherConfig::default()
+        });
+        let subscription = Arc::new(());
+        for name in ["orders.created", "orders.paid"] {
+            let mut entry = ChannelEntry::new(name, 16, aether.clock.now());
+            let held = Arc::clone(&subscription);
+            entry.bridge = Some(tokio::spawn(async move {
+                let _held = held;
+                futures::future::pending::<()>().await
+            }));
+            aether.channels.insert(name.to_string(), entry);
+        }
+        assert_eq!(aether.nats_bridge_tasks().await, 2);
+
+        aether
+            .remove_channel(&Channel::new("orders.created"))
+            .await
+            .unwrap();
+        assert_eq!(aether.nats_bridge_tasks().await, 1);
+        aether.clear().await;
+        assert_eq!(aether.nats_bridge_tasks().await, 0);
+
+        tokio::task::yield_now().await;
+        assert_eq!(Arc::strong_count(&subscription), 1);
+    }
+
+    #[tokio::test]
+    async fn test_releasing_a_channel_waits_for_its_last_receiver() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            ..AetherConfig::default()
+        });
+        let channel = Channel::new("orders.created");
+        let receiver = aether.subscribe(&channel).await;
+        let mut entry = aether.channels.get_mut("orders.created").unwrap();
+        entry.bridge = Some(tokio::spawn(futures::future::pending::<()>()));
+        drop(entry);
+
+        assert!(!aether.release_channel(&channel).await);
+        assert_eq!(aether.nats_bridge_tasks().await, 1);
+        drop(receiver);
+        assert!(aether.release_channel(&channel).await);
+        assert_eq!(aether.nats_bridge_tasks().await, 0);
+        assert!(!aether.release_channel(&channel).await);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_producers_share_channels() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            ..AetherConfig::default()
+        });
+        let mut all_orders = aether.subscribe(&Channel::new("orders.>")).await;
+
+        let producers: Vec<_> = (0..8)
+            .map(|producer| {
+                let aether = aether.clone();
+                tokio::spawn(async move {
+                    for n in 0..50 {
+                        let channel = format!("orders.{}", (producer + n) % 4);
+                        aether
+                            .emit(Wave::new(channel, serde_json::json!({})))
+                            .await
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for producer in producers {
+            producer.await.unwrap();
+        }
+
+        assert_eq!(aether.stats().await.total_waves, 400);
+        assert_eq!(aether.active_channels().await.len(), 5);
+        assert_eq!(all_orders.len(), 400);
+        assert!(all_orders.try_recv().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_stats_sketch_volume_sources_and_sizes() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            ..AetherConfig::default()
+        });
+        let _receiver = aether.subscribe(&Channel::new(">")).await;
+        for n in 0..300 {
+            let channel = match n % 6 {
+                0..=2 => "orders.created",
+                3 | 4 => "payments.completed",
+                _ => "inventory.level",
+            };
+            let wave = Wave::builder(channel)
+                .payload(serde_json::json!({ "pad": "x".repeat(n % 100) }))
+                .source(format!("service-{}", n % 7))
+                .wave_type(if n % 3 == 0 {
+                    WaveType::Command
+                } else {
+                    WaveType::Event
+                })
+                .build();
+            aether.emit(wave).await.unwrap();
+        }
+
+        let stats = aether.stats().await;
+        assert_eq!(stats.total_waves, 300);
+        let top: Vec<_> = stats
+            .top_channels
+            .iter()
+            .map(|(c, n)| (c.as_str(), *n))
+            .collect();
+        assert_eq!(
+            top,
+            vec![
+                ("orders.created", 150),
+                ("payments.completed", 100),
+                ("inventory.level", 50)
+            ]
+        );
+        assert_eq!(stats.distinct_sources, 7);
+        assert_eq!(stats.waves_by_type["Command"], 100);
+        assert_eq!(stats.waves_by_type["Event"], 200);
+        // {"pad":""} is 10 bytes plus up to 99 pad characters
+        assert!((55..=65).contains(&stats.payload_bytes_p50), "{:?}", stats);
+        assert!(stats.payload_bytes_p99 >= 105, "{:?}", stats);
+    }
 }
@@ -0,0 +1,148 @@
+//! Amplitude by wave type: layer-side defaults and floors.
+//!
+//! A layer configured with a policy gives every wave built without an explicit
+//! amplitude the one for its type, so "a command outranks a broadcast" holds
+//! across teams. Both are applied by `Aether::emit`, which sets the default
+//! and then raises any wave below its type's floor before the validity check.
+
+use crate::wave::WaveType;
+use serde::Deserialize;
+
+/// One amplitude per wave type; unset types are left alone
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+pub struct TypeAmplitudes {
+    #[serde(default)]
+    pub command: Option<f64>,
+    #[serde(default)]
+    pub query: Option<f64>,
+    #[serde(default)]
+    pub response: Option<f64>,
+    #[serde(default)]
+    pub event: Option<f64>,
+    #[serde(default)]
+    pub broadcast: Option<f64>,
+}
+
+impl TypeAmplitudes {
+    /// Commands first, then requests and their answers, events, broadcasts
+    pub fn recommended() -> Self {
+        Self {
+            command: Some(1.0),
+            query: Some(0.9),
+            response: Some(0.9),
+            event: Some(0.7),
+            broadcast: Some(0.4),
+        }
+    }
+
+    pub fn get(&self, wave_type: &WaveType) -> Option<f64> {
+        match wave_type {
+            WaveType::Command => self.command,
+            WaveType::Query => self.query,
+            WaveType::Response => self.response,
+            WaveType::Event => self.event,
+            WaveType::Broadcast => self.broadcast,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AmplitudePolicy {
+    /// Amplitude for waves built without one; types left out keep 1.0
+    #[serde(default = "TypeAmplitudes::recommended")]
+    pub defaults: TypeAmplitudes,
+    /// Lowest amplitude the layer lets each type leave with
+    #[serde(default)]
+    pub floors: TypeAmplitudes,
+}
+
+impl Default for AmplitudePolicy {
+    fn default() -> Self {
+        Self {
+            defaults: TypeAmplitudes::recommended(),
+            floors: TypeAmplitudes::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aether::{Aether, AetherConfig};
+    use crate::channel::Channel;
+    use crate::wave::Wave;
+
+    #[test]
+    fn test_policy_parses_partial_tables() {
+        let policy: AmplitudePolicy =
+            serde_json::from_value(serde_json::json!({ "floors": { "command": 0.9 } })).unwrap();
+        assert_eq!(policy.defaults, TypeAmplitudes::recommended());
+        assert_eq!(policy.floors.get(&WaveType::Command), Some(0.9));
+        assert_eq!(policy.floors.get(&WaveType::Event), None);
+    }
+
+    #[tokio::test]
+    async fn test_layer_raises_waves_to_their_type_floor() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            amplitude_policy: Some(AmplitudePolicy {
+                defaults: TypeAmplitudes::recommended(),
+                floors: TypeAmplitudes {
+                    command: Some(0.9),
+                    ..TypeAmplitudes::default()
+                },
+            }),
+            ..AetherConfig::default()
+        });
+        let mut receiver = aether.subscribe(&Channel::new("orders.>")).await;
+
+        for (wave_type, amplitude) in [(WaveType::Command, 0.005), (WaveType::Event, 0.3)] {
+            let wave = Wave::builder("orders.cancel")
+                .wave_type(wave_type)
+                .amplitude(amplitude)
+                .build();
+            aether.emit(wave).await.unwrap();
+        }
+
+        // One hop of attenuation after the floor
+        let command = receiver.recv().await.unwrap();
+        assert!((command.amplitude().value() - 0.9 * 0.95).abs() < 1e-9);
+        let event = receiver.recv().await.unwrap();
+        assert!((event.amplitude().value() - 0.3 * 0.95).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_each_layer_defaults_unset_amplitudes_by_its_own_policy() {
+        let with_policy = Aether::new(AetherConfig {
+            use_nats: false,
+            amplitude_policy: Some(AmplitudePolicy::default()),
+            ..AetherConfig::default()
+        });
+        let without_policy = Aether::new(AetherConfig {
+            use_nats: false,
+            ..AetherConfig::default()
+        });
+        let mut defaulted = with_policy.subscribe(&Channel::new("orders.>")).await;
+        let mut untouched = without_policy.subscribe(&Channel::new("orders.>")).await;
+
+        let broadcast = || {
+            Wave::builder("orders.announce")
+                .wave_type(WaveType::Broadcast)
+                .build()
+        };
+        with_policy.emit(broadcast()).await.unwrap();
+        let explicit = Wave::builder("orders.announce")
+            .wave_type(WaveType::Broadcast)
+            .amplitude(0.8)
+            .build();
+        with_policy.emit(explicit).await.unwrap();
+        without_policy.emit(broadcast()).await.unwrap();
+
+        let wave = defaulted.recv().await.unwrap();
+        assert!((wave.amplitude().value() - 0.4 * 0.95).abs() < 1e-9);
+        let wave = defaulted.recv().await.unwrap();
+        assert!((wave.amplitude().value() - 0.8 * 0.95).abs() < 1e-9);
+        let wave = untouched.recv().await.unwrap();
+        assert!((wave.amplitude().value() - 0.95).abs() < 1e-9);
+    }
+}
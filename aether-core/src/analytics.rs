@@ -0,0 +1,418 @@
+//! Wave pattern analytics: per-channel baselines and anomaly flags.
+//!
+//! An [`AnomalyDetector`] is fed one [`WindowBatch`] at a time (or the
+//! [`ChannelActivity`] a summarizing window kept of one) and keeps an
+//! exponentially weighted baseline of each channel's rate and mean amplitude.
+//! Once a channel has been seen for `warmup_windows` windows, a window that
+//! departs sharply from its baseline is reported as an [`Anomaly`].
+//! Baselines of channels that stay empty for `evict_after_idle_windows`
+//! windows are dropped, and at most `max_channels` are kept.
+
+use crate::labels::{LabelBudget, DEFAULT_LABEL_BUDGET};
+use crate::wave::Wave;
+use crate::window::{WindowBatch, WindowContents};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Channels labelled individually in the anomaly metrics
+static ANOMALY_CHANNELS: LabelBudget = LabelBudget::new(DEFAULT_LABEL_BUDGET);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AnomalyConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Observation window
+    #[serde(default = "default_window_ms")]
+    pub window_ms: u64,
+    /// Weight of the newest window in the baseline (0..=1)
+    #[serde(default = "default_baseline_alpha")]
+    pub baseline_alpha: f64,
+    /// Windows a channel must be seen for before it is judged
+    #[serde(default = "default_warmup_windows")]
+    pub warmup_windows: u32,
+    /// Rate above this multiple of the baseline is a spike
+    #[serde(default = "default_spike_factor")]
+    pub spike_factor: f64,
+    /// Baseline rate (waves/s) above which an empty window is silence
+    #[serde(default = "default_silence_min_rate")]
+    pub silence_min_rate: f64,
+    /// Mean amplitude below this fraction of the baseline is a collapse
+    #[serde(default = "default_amplitude_collapse_ratio")]
+    pub amplitude_collapse_ratio: f64,
+    #[serde(default = "default_alert_channel")]
+    pub alert_channel: String,
+    /// Consecutive empty windows after which a channel's baseline is dropped
+    #[serde(default = "default_evict_after_idle_windows")]
+    pub evict_after_idle_windows: u32,
+    /// Channels with a baseline at once; new channels past it are not judged
+    #[serde(default = "default_max_channels")]
+    pub max_channels: usize,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            window_ms: default_window_ms(),
+            baseline_alpha: default_baseline_alpha(),
+            warmup_windows: default_warmup_windows(),
+            spike_factor: default_spike_factor(),
+            silence_min_rate: default_silence_min_rate(),
+            amplitude_collapse_ratio: default_amplitude_collapse_ratio(),
+            alert_channel: default_alert_channel(),
+            evict_after_idle_windows: default_evict_after_idle_windows(),
+            max_channels: default_max_channels(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_window_ms() -> u64 {
+    10_000
+}
+
+fn default_baseline_alpha() -> f64 {
+    0.2
+}
+
+fn default_warmup_windows() -> u32 {
+    3
+}
+
+fn default_spike_factor() -> f64 {
+    3.0
+}
+
+fn default_silence_min_rate() -> f64 {
+    0.5
+}
+
+fn default_amplitude_collapse_ratio() -> f64 {
+    0.5
+}
+
+fn default_alert_channel() -> String {
+    "aether.alerts.anomaly".to_string()
+}
+
+fn default_evict_after_idle_windows() -> u32 {
+    60
+}
+
+fn default_max_channels() -> usize {
+    1024
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    /// A busy channel went quiet
+    Silence,
+    /// Rate far above the baseline
+    RateSpike,
+    /// Mean amplitude far below the baseline
+    AmplitudeCollapse,
+}
+
+impl AnomalyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnomalyKind::Silence => "silence",
+            AnomalyKind::RateSpike => "rate_spike",
+            AnomalyKind::AmplitudeCollapse => "amplitude_collapse",
+        }
+    }
+}
+
+/// One channel departing from its baseline in one window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Anomaly {
+    pub channel: String,
+    pub kind: AnomalyKind,
+    /// Waves/s for rate anomalies, mean amplitude for collapses
+    pub observed: f64,
+    pub baseline: f64,
+    pub window_end: DateTime<Utc>,
+}
+
+/// Wave count and amplitude sum per channel over one window
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChannelActivity {
+    pub channels: HashMap<String, (u64, f64)>,
+}
+
+impl WindowContents for ChannelActivity {
+    fn add(&mut self, wave: &Wave) {
+        let (count, amplitude) = self
+            .channels
+            .entry(wave.channel().name().to_string())
+            .or_default();
+        *count += 1;
+        *amplitude += wave.amplitude().value();
+    }
+}
+
+/// Smoothed behaviour of one channel
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ChannelBaseline {
+    /// Waves per second
+    pub rate: f64,
+    pub amplitude: f64,
+    /// Windows folded in so far
+    pub windows: u32,
+    /// Empty windows in a row up to the latest
+    pub idle_windows: u32,
+}
+
+/// Tracks channel baselines across windows and flags anomalies
+#[derive(Debug)]
+pub struct AnomalyDetector {
+    config: AnomalyConfig,
+    baselines: HashMap<String, ChannelBaseline>,
+}
+
+impl AnomalyDetector {
+    pub fn new(config: AnomalyConfig) -> Self {
+        Self {
+            config,
+            baselines: HashMap::new(),
+        }
+    }
+
+    pub fn config(&self) -> &AnomalyConfig {
+        &self.config
+    }
+
+    pub fn baseline(&self, channel: &str) -> Option<&ChannelBaseline> {
+        self.baselines.get(channel)
+    }
+
+    /// Fold one window into the baselines; returns the anomalies it showed
+    ///
+    /// Waves on the alert channel are ignored so alerts cannot feed back.
+    pub fn observe(&mut self, batch: &WindowBatch) -> Vec<Anomaly> {
+        let mut activity = ChannelActivity::default();
+        for wave in &batch.waves {
+            activity.add(wave);
+        }
+        self.observe_activity(batch.start, batch.end, &activity)
+    }
+
+    /// Fold one window's per-channel activity into the baselines
+    pub fn observe_activity(
+        &mut self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        activity: &ChannelActivity,
+    ) -> Vec<Anomaly> {
+        let secs = (end - start).num_milliseconds().max(1) as f64 / 1000.0;
+        let mut seen: Vec<_> = activity.channels.iter().collect();
+        seen.sort_by(|a, b| a.0.cmp(b.0));
+        let mut windows: HashMap<&str, (u64, f64)> = HashMap::new();
+        let mut tracked = self.baselines.len();
+        for (channel, &(count, amplitude)) in seen {
+            if *channel == self.config.alert_channel {
+                continue;
+            }
+            if !self.baselines.contains_key(channel.as_str()) {
+                if tracked >= self.config.max_channels {
+                    metrics::counter!("aether_anomaly_untracked_waves_total").increment(count);
+                    continue;
+                }
+                tracked += 1;
+            }
+            windows.insert(channel, (count, amplitude));
+        }
+        for channel in self.baselines.keys() {
+            windows.entry(channel.as_str()).or_default();
+        }
+
+        let alpha = self.config.baseline_alpha.clamp(0.0, 1.0);
+        let mut anomalies = Vec::new();
+        let mut updated = Vec::with_capacity(windows.len());
+        let mut evicted = Vec::new();
+        for (channel, (count, amplitude_sum)) in windows {
+            let rate = count as f64 / secs;
+            let amplitude = (count > 0).then(|| amplitude_sum / count as f64);
+            let mut flag = |kind: AnomalyKind, observed: f64, baseline: f64| {
+                metrics::counter!(
+                    "aether_anomalies_total",
+                    "channel" => ANOMALY_CHANNELS.label(channel),
+                    "kind" => kind.as_str()
+                )
+                .increment(1);
+                anomalies.push(Anomaly {
+                    channel: channel.to_string(),
+                    kind,
+                    observed,
+                    baseline,
+                    window_end: end,
+                });
+            };
+
+            let baseline = match self.baselines.get(channel).copied() {
+                None => ChannelBaseline {
+                    rate,
+                    amplitude: amplitude.unwrap_or_default(),
+                    windows: 1,
+                    idle_windows: 0,
+                },
+                Some(baseline) => {
+                    if baseline.windows >= self.config.warmup_windows {
+                        if count == 0 && baseline.rate >= self.config.silence_min_rate {
+                            flag(AnomalyKind::Silence, rate, baseline.rate);
+                        }
+                        if baseline.rate > 0.0 && rate > baseline.rate * self.config.spike_factor {
+                            flag(AnomalyKind::RateSpike, rate, baseline.rate);
+                        }
+                        if let Some(amplitude) = amplitude {
+                            if amplitude < baseline.amplitude * self.config.amplitude_collapse_ratio
+                            {
+                                flag(
+                                    AnomalyKind::AmplitudeCollapse,
+                                    amplitude,
+                                    baseline.amplitude,
+                                );
+                            }
+                        }
+                    }
+                    ChannelBaseline {
+                        rate: baseline.rate + alpha * (rate - baseline.rate),
+                        amplitude: amplitude.map_or(baseline.amplitude, |amplitude| {
+                            baseline.amplitude + alpha * (amplitude - baseline.amplitude)
+                        }),
+                        windows: baseline.windows.saturating_add(1),
+                        idle_windows: if count == 0 {
+                            baseline.idle_windows.saturating_add(1)
+                        } else {
+                            0
+                        },
+                    }
+                }
+            };
+            let label = ANOMALY_CHANNELS.label(channel);
+            metrics::gauge!("aether_channel_rate", "channel" => label.clone()).set(rate);
+            metrics::gauge!("aether_channel_baseline_rate", "channel" => label).set(baseline.rate);
+            if baseline.idle_windows >= self.config.evict_after_idle_windows {
+                evicted.push(channel.to_string());
+            } else {
+                updated.push((channel.to_string(), baseline));
+            }
+        }
+        for channel in evicted {
+            self.baselines.remove(&channel);
+        }
+        self.baselines.extend(updated);
+        anomalies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wave::Wave;
+
+    fn window(index: i64, channels: &[(&str, usize, f64)]) -> WindowBatch {
+        let start = DateTime::<Utc>::UNIX_EPOCH + chrono::Duration::seconds(index * 10);
+        let waves = channels
+            .iter()
+            .flat_map(|&(channel, count, amplitude)| {
+                (0..count).map(move |_| {
+                    Wave::builder(channel)
+                        .payload(serde_json::json!({}))
+                        .amplitude(amplitude)
+                        .build()
+                })
+            })
+            .collect();
+        WindowBatch {
+            start,
+            end: start + chrono::Duration::seconds(10),
+            waves,
+            late: 0,
+        }
+    }
+
+    fn kinds(anomalies: &[Anomaly]) -> Vec<(&str, AnomalyKind)> {
+        let mut kinds: Vec<_> = anomalies
+            .iter()
+            .map(|anomaly| (anomaly.channel.as_str(), anomaly.kind))
+            .collect();
+        kinds.sort_by_key(|(channel, _)| *channel);
+        kinds
+    }
+
+    #[test]
+    fn test_flags_silence_spikes_and_amplitude_collapse() {
+        let mut detector = AnomalyDetector::new(AnomalyConfig::default());
+        for index in 0..3 {
+            let quiet = detector.observe(&window(
+                index,
+                &[("orders.created", 20, 0.8), ("inventory.level", 10, 0.9)],
+            ));
+            assert!(quiet.is_empty(), "warm-up flagged {:?}", quiet);
+        }
+        let steady = detector.observe(&window(
+            3,
+            &[("orders.created", 22, 0.8), ("inventory.level", 9, 0.9)],
+        ));
+        assert!(steady.is_empty());
+
+        let anomalies = detector.observe(&window(
+            4,
+            &[
+                ("orders.created", 100, 0.8),
+                ("aether.alerts.anomaly", 5, 1.0),
+            ],
+        ));
+        assert_eq!(
+            kinds(&anomalies),
+            vec![
+                ("inventory.level", AnomalyKind::Silence),
+                ("orders.created", AnomalyKind::RateSpike)
+            ]
+        );
+        assert!(detector.baseline("aether.alerts.anomaly").is_none());
+
+        let anomalies = detector.observe(&window(
+            5,
+            &[("orders.created", 20, 0.1), ("inventory.level", 10, 0.9)],
+        ));
+        assert_eq!(
+            kinds(&anomalies),
+            vec![("orders.created", AnomalyKind::AmplitudeCollapse)]
+        );
+        assert!(anomalies[0].baseline > 0.7);
+    }
+
+    #[test]
+    fn test_idle_channels_are_evicted_and_channels_capped() {
+        let mut detector = AnomalyDetector::new(AnomalyConfig {
+            evict_after_idle_windows: 2,
+            max_channels: 2,
+            ..AnomalyConfig::default()
+        });
+        detector.observe(&window(
+            0,
+            &[
+                ("orders.created", 5, 0.8),
+                ("inventory.level", 5, 0.9),
+                ("payments.captured", 5, 0.9),
+            ],
+        ));
+        let tracked = ["orders.created", "inventory.level", "payments.captured"]
+            .into_iter()
+            .filter(|channel| detector.baseline(channel).is_some())
+            .count();
+        assert_eq!(tracked, 2);
+
+        detector.observe(&window(1, &[("orders.created", 5, 0.8)]));
+        detector.observe(&window(2, &[("orders.created", 5, 0.8)]));
+        assert!(detector.baseline("orders.created").is_some());
+        assert_eq!(detector.baselines.len(), 1);
+    }
+}
@@ -0,0 +1,792 @@
+//! Service runtime: the startup, wave loop and shutdown shared by every binary.
+
+use crate::{
+    apply_resource_limits, doctor, init_observability, init_ops, install_panic_hook,
+    retry_with_timeout_named, spawn_runtime_metrics, spawn_storage_monitor, start_exports,
+    start_resource_monitoring_with_alerts, watch_config, Aether, AppConfig, AuditKind,
+    BlockingWatchdog, Channel, CheckStatus, CircuitBreaker, ControlPlane, DoctorMode, EmitReceipt,
+    FlowTraceIndex, HealthState, Heartbeat, HopKeys, LoadShedder, Notifier, ObserveSampler,
+    OpsConfig, PhysicsHistory, Priority, Readiness, ResourceLimits, ResourceMonitorConfig,
+    RetryPolicy, TaskManager, TaskRateLimiter, TopologyTracker, VersionRouter, Vibrator,
+    VibratorConfig, VibratorEmitter, Wave, WaveContext, WaveHandler, WaveIndex, WaveRouter,
+    WaveValidator, WaveValidators,
+};
+use anyhow::Context;
+use futures::future::BoxFuture;
+use std::future::Future;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{debug, error, info, warn};
+
+type StateInit<S> = Box<dyn FnOnce(ServiceContext) -> BoxFuture<'static, anyhow::Result<S>> + Send>;
+
+/// A service binary: config, observability, vibrator and handlers in one place
+///
+/// ```no_run
+/// use aether_core::{handler, AetherApp, Wave};
+///
+/// #[handler(channel = "orders.created")]
+/// async fn order_created(wave: Wave) {
+///     println!("{}", wave.payload());
+/// }
+///
+/// # async fn run() -> anyhow::Result<()> {
+/// AetherApp::builder()
+///     .config("service-beta")
+///     .handler(order_created)
+///     .run()
+///     .await
+/// # }
+/// ```
+pub struct AetherApp;
+
+impl AetherApp {
+    pub fn builder() -> AetherAppBuilder<()> {
+        AetherAppBuilder {
+            service: None,
+            version: None,
+            channels: Vec::new(),
+            init: Box::new(|_| Box::pin(async { Ok(()) })),
+            handlers: Vec::new(),
+            topology: false,
+            flow_traces: false,
+            physics_history: false,
+            wave_search: false,
+            observe_sampling: false,
+            validators: WaveValidators::new(),
+        }
+    }
+}
+
+pub struct AetherAppBuilder<S> {
+    service: Option<String>,
+    version: Option<String>,
+    channels: Vec<Channel>,
+    init: StateInit<S>,
+    handlers: Vec<Box<dyn WaveHandler<S>>>,
+    topology: bool,
+    flow_traces: bool,
+    physics_history: bool,
+    wave_search: bool,
+    observe_sampling: bool,
+    validators: WaveValidators,
+}
+
+impl AetherAppBuilder<()> {
+    /// Build the handlers' shared state once the vibrator is connected
+    ///
+    /// Panics if handlers were registered first, since they are typed on the state.
+    pub fn state<S, F, Fut>(self, init: F) -> AetherAppBuilder<S>
+    where
+        F: FnOnce(ServiceContext) -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<S>> + Send + 'static,
+    {
+        assert!(
+            self.handlers.is_empty(),
+            "AetherAppBuilder::state must be called before handler"
+        );
+        AetherAppBuilder {
+            service: self.service,
+            version: self.version,
+            channels: self.channels,
+            init: Box::new(move |ctx| Box::pin(init(ctx))),
+            handlers: Vec::new(),
+            topology: self.topology,
+            flow_traces: self.flow_traces,
+            physics_history: self.physics_history,
+            wave_search: self.wave_search,
+            observe_sampling: self.observe_sampling,
+            validators: self.validators,
+        }
+    }
+}
+
+impl<S: Send + Sync + 'static> AetherAppBuilder<S> {
+    /// Service name used to load `config/<name>.toml` and watch it for changes
+    pub fn config(mut self, service: impl Into<String>) -> Self {
+        self.service = Some(service.into());
+        self
+    }
+
+    /// Version announced to the cluster, usually `env!("CARGO_PKG_VERSION")`
+    ///
+    /// `service.version` in the config takes precedence.
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Channels to resonate on when the config lists none (default: the handlers' channels)
+    pub fn channels<C: Into<Channel>>(mut self, channels: impl IntoIterator<Item = C>) -> Self {
+        self.channels = channels.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Track the service topology seen on `>` and serve it as `dump_topology`
+    /// on the control plane and `GET /topology` (`/topology.dot`) on the health server
+    pub fn topology(mut self) -> Self {
+        self.topology = true;
+        self
+    }
+
+    /// Index flow-trace breadcrumbs seen on `>` so `trace_wave` on the control
+    /// plane can replay any traced wave's path
+    pub fn flow_traces(mut self) -> Self {
+        self.flow_traces = true;
+        self
+    }
+
+    /// Run interference detection over `>` and persist it, served as
+    /// `physics_history` on the control plane and `GET /physics` on the health
+    /// server; needs `[aether] persistence_enabled`
+    pub fn physics_history(mut self) -> Self {
+        self.physics_history = true;
+        self
+    }
+
+    /// Keep recent waves seen on `>` for `search_waves` and `tail_waves` on the
+    /// control plane (`aether-cli search`)
+    pub fn wave_search(mut self) -> Self {
+        self.wave_search = true;
+        self
+    }
+
+    /// Hand handlers only the share of waves `[observe_sampling]` picks; the
+    /// rest are dropped on receipt, before any handler task is spawned
+    pub fn observe_sampling(mut self) -> Self {
+        self.observe_sampling = true;
+        self
+    }
+
+    /// Check waves this service emits on channels matching `pattern`; a
+    /// rejection fails the emit with `ValidatorRejected` naming `name`
+    pub fn validator(
+        mut self,
+        name: impl Into<String>,
+        pattern: impl Into<Channel>,
+        validator: impl WaveValidator + 'static,
+    ) -> Self {
+        self.validators = self.validators.validator(name, pattern, validator);
+        self
+    }
+
+    /// Route waves to a handler; earlier handlers take precedence
+    pub fn handler(mut self, handler: impl WaveHandler<S>) -> Self {
+        self.handlers.push(Box::new(handler));
+        self
+    }
+
+    /// Run until Ctrl-C
+    pub async fn run(self) -> anyhow::Result<()> {
+        self.run_until(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+    }
+
+    /// Run until `shutdown` completes or a drain is requested, then drain
+    /// within the shutdown grace period
+    pub async fn run_until(self, shutdown: impl Future<Output = ()>) -> anyhow::Result<()> {
+        let service = self
+            .service
+            .clone()
+            .context("AetherApp needs a service name; call .config(...)")?;
+        let config_rx = watch_config(&service).context("failed to load service config")?;
+        let app_config = config_rx.borrow().clone();
+
+        // Panic hook & resource limits
+        install_panic_hook();
+        apply_resource_limits(
+            app_config.operations.memory_limit_bytes,
+            app_config.operations.cpu_time_limit_secs,
+        )
+        .context("failed to apply resource limits")?;
+
+        // Self-test before the metrics and health ports are taken
+        let report = match app_config.operations.doctor {
+            DoctorMode::Off => None,
+            DoctorMode::Warn | DoctorMode::Enforce => Some(doctor(&app_config).await),
+        };
+
+        let observability =
+            init_observability(&app_config).context("failed to init observability")?;
+        if let Some(report) = report {
+            for check in &report.checks {
+                match check.status {
+                    CheckStatus::Fail => error!("doctor: {} failed: {}", check.name, check.detail),
+                    CheckStatus::Warn => warn!("doctor: {}: {}", check.name, check.detail),
+                    CheckStatus::Pass | CheckStatus::Skip => {
+                        info!("doctor: {}: {}", check.name, check.detail)
+                    }
+                }
+            }
+            if !report.passed() && app_config.operations.doctor == DoctorMode::Enforce {
+                anyhow::bail!("startup self-test failed; see the doctor lines above");
+            }
+        }
+        let ops = init_ops(&OpsConfig {
+            enable_health: app_config.operations.health_enabled,
+            health_bind: app_config.operations.health_bind.clone(),
+            shutdown_grace_ms: app_config.operations.shutdown_grace_ms,
+            memory_limit_bytes: app_config.operations.memory_limit_bytes,
+            cpu_time_limit_secs: app_config.operations.cpu_time_limit_secs,
+        });
+
+        let served = self
+            .serve(app_config, Some(config_rx), Some(ops.health()), shutdown)
+            .await;
+        observability.shutdown().await;
+        served
+    }
+
+    /// Everything after process setup: layer, vibrator, state and the wave loop
+    async fn serve(
+        self,
+        app_config: AppConfig,
+        mut config_rx: Option<watch::Receiver<AppConfig>>,
+        health: Option<&HealthState>,
+        shutdown: impl Future<Output = ()>,
+    ) -> anyhow::Result<()> {
+        let name = app_config.service.name.clone();
+        info!("🌊 Starting {}...", name);
+
+        let mut aether = Aether::try_new(app_config.aether_config())
+            .context("failed to start Aether layer")?
+            .with_validators(self.validators.clone());
+        aether
+            .restore_from_snapshot()
+            .await
+            .context("failed to restore Aether snapshot")?;
+        let _exports =
+            start_exports(&aether, &app_config.exports).context("failed to start exporters")?;
+        let _channel_janitor = aether.spawn_channel_janitor();
+        let _snapshot_scheduler = aether.spawn_snapshot_scheduler();
+        let _source_reports = aether.spawn_source_reports(&name, &app_config.source_reports);
+        let _notifier = match Notifier::from_config(&app_config.notifications, &name)
+            .context("failed to configure notifications")?
+        {
+            Some(notifier) => Some(notifier.spawn(&aether).await),
+            None => None,
+        };
+
+        if let Some(mut config_rx) = config_rx.clone() {
+            let audit_aether = aether.clone();
+            tokio::spawn(async move {
+                while config_rx.changed().await.is_ok() {
+                    let updated = config_rx.borrow().clone();
+                    info!("🔄 Config reloaded for {}", updated.service.name);
+                    audit_aether.record_audit(
+                        AuditKind::ConfigReload,
+                        Some(&updated.service.name),
+                        serde_json::json!({ "service": updated.service.name }),
+                    );
+                }
+            });
+        }
+
+        let defaults = if self.channels.is_empty() {
+            let mut routed: Vec<Channel> = Vec::new();
+            for handler in &self.handlers {
+                let channel = Channel::new(handler.channel());
+                if !routed.contains(&channel) {
+                    routed.push(channel);
+                }
+            }
+            routed
+        } else {
+            self.channels
+        };
+        let channels = resonant_channels(&app_config.service.channels, &defaults);
+
+        let mut task_manager = TaskManager::with_rate_limiter(
+            app_config.service.max_inflight,
+            app_config.service.priority_weights,
+            TaskRateLimiter::from_config(
+                app_config.service.rate_limit_per_sec,
+                app_config.service.rate_limit_burst,
+                &app_config.service.channel_rate_limits,
+            ),
+        );
+        if let Some(timeout_ms) = app_config.service.spawn_timeout_ms {
+            task_manager = task_manager.with_spawn_timeout(Duration::from_millis(timeout_ms));
+        }
+        let version = app_config
+            .service
+            .version
+            .clone()
+            .or(self.version)
+            .unwrap_or_else(|| "unknown".to_string());
+        let version_router =
+            VersionRouter::new(Some(version.clone()), app_config.rollout.rules.clone());
+        let load_shedder = LoadShedder::from_config(&app_config.load_shedding)
+            .map(|shedder| shedder.with_inflight(task_manager.inflight_counter()));
+        let mut config = VibratorConfig::new(name.clone())
+            .with_channels(channels.iter().map(Channel::new).collect())
+            .with_auth_token(app_config.aether.auth_token.clone())
+            .with_noise_floor(app_config.service.noise_floor)
+            .with_natural_frequency(app_config.service.natural_frequency)
+            .with_load_shedder(load_shedder)
+            .with_hop_keys(HopKeys::new(&app_config.hopping))
+            .with_version_router(version_router.clone())
+            .with_reflections(app_config.service.reflect)
+            .with_stale_epochs_discarded(app_config.service.discard_stale_epochs)
+            .with_sampler(
+                self.observe_sampling
+                    .then(|| ObserveSampler::new(&app_config.observe_sampling)),
+            );
+        for floor in &app_config.service.channel_noise_floors {
+            config = config.with_channel_noise_floor(floor.channel.as_str(), floor.noise_floor);
+        }
+        for declared in &app_config.service.channel_resonances {
+            config = config.with_channel_resonance(declared.channel.as_str(), declared.resonance);
+        }
+
+        let mut vibrator = Vibrator::new(config, &aether).await?;
+        let intake = vibrator.control();
+        let (_storage_monitor, storage) = match aether.wave_store() {
+            Some(store) => spawn_storage_monitor(
+                store.clone(),
+                app_config.storage_monitoring.clone(),
+                Some(vibrator.emitter()),
+            )
+            .unzip(),
+            None => (None, None),
+        };
+        if let Some(health) = health {
+            // Ready once the NATS link is up, until draining starts and while
+            // the wave store's volume has room; the body carries the full
+            // connection state
+            let probe = aether.clone();
+            let draining = intake.clone();
+            let storage = storage.unwrap_or_default();
+            health.set_readiness_check(move || {
+                let state = probe.connection_state();
+                Readiness {
+                    ready: state.is_connected() && !draining.is_draining() && storage.is_healthy(),
+                    body: serde_json::to_string(&state).unwrap_or_default(),
+                }
+            });
+        }
+        let monitoring = &app_config.resource_monitoring;
+        let limits = ResourceLimits::detect(
+            app_config.operations.memory_limit_bytes,
+            app_config.operations.cpu_time_limit_secs,
+        );
+        limits.record_metrics();
+        info!(
+            "Resource limits: memory ceiling {:?} bytes, cgroup CPU {:?} cores",
+            limits.memory_ceiling(),
+            limits.cgroup_cpu_cores
+        );
+        let _resource_monitor = start_resource_monitoring_with_alerts(
+            ResourceMonitorConfig {
+                enabled: monitoring.enabled,
+                interval_ms: monitoring.interval_ms,
+                leak_detection_enabled: monitoring.leak_detection_enabled,
+                leak_growth_bytes_per_min: monitoring.leak_growth_bytes_per_min,
+                memory_ceiling_bytes: limits.memory_ceiling(),
+                leak_exhaustion_mins: monitoring.leak_exhaustion_mins,
+                leak_window_intervals: monitoring.leak_window_intervals,
+                cpu_pressure_window_intervals: monitoring.cpu_pressure_window_intervals,
+                leak_sustained_intervals: monitoring.leak_sustained_intervals,
+                heap_profile_dir: monitoring.heap_profile_dir.clone().map(Into::into),
+                leak_alert_channel: monitoring.leak_alert_channel.clone(),
+                allocator_metrics_enabled: monitoring.allocator_metrics_enabled,
+            },
+            vibrator.emitter(),
+        );
+        let runtime = &app_config.runtime_metrics;
+        let _runtime_metrics = runtime
+            .enabled
+            .then(|| spawn_runtime_metrics(Duration::from_millis(runtime.interval_ms)));
+        let watchdog = (runtime.enabled && runtime.blocking_watchdog).then(|| {
+            let watchdog =
+                BlockingWatchdog::new(Duration::from_millis(runtime.blocking_threshold_ms));
+            watchdog.spawn();
+            watchdog
+        });
+
+        let topology = if self.topology {
+            Some(TopologyTracker::new(&aether, &app_config.topology))
+        } else {
+            None
+        };
+        let _topology_task = match &topology {
+            Some(tracker) => {
+                if let Some(health) = health {
+                    let json = tracker.clone();
+                    health.set_endpoint("/topology", move || {
+                        serde_json::to_string(&json.snapshot()).unwrap_or_default()
+                    });
+                    let dot = tracker.clone();
+                    health.set_endpoint("/topology.dot", move || dot.snapshot().to_dot());
+                }
+                Some(tracker.spawn().await)
+            }
+            None => None,
+        };
+
+        let flow_traces = self.flow_traces.then(|| {
+            let config = app_config.aether.flow_trace.clone().unwrap_or_default();
+            FlowTraceIndex::new(config.index_capacity)
+        });
+        let _flow_trace_task = match &flow_traces {
+            Some(index) => Some(index.spawn(&aether).await),
+            None => None,
+        };
+
+        let physics_history = if self.physics_history {
+            match aether.wave_store() {
+                Some(store) => PhysicsHistory::open(store, app_config.physics_history.clone())
+                    .map_err(|err| warn!("Failed to open physics history: {}", err))
+                    .ok(),
+                None => {
+                    warn!("Physics history needs persistence enabled; not recording");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let _physics_task = match &physics_history {
+            Some(history) => {
+                if let Some(health) = health {
+                    let json = history.clone();
+                    health.set_endpoint("/physics", move || {
+                        serde_json::to_string(&json.summaries().unwrap_or_default())
+                            .unwrap_or_default()
+                    });
+                }
+                Some(
+                    history
+                        .spawn(&aether, app_config.physics.clone(), &name)
+                        .await,
+                )
+            }
+            None => None,
+        };
+
+        let wave_index = self
+            .wave_search
+            .then(|| WaveIndex::new(&app_config.wave_index));
+        let _wave_index_task = match &wave_index {
+            Some(index) => Some(index.spawn(&aether).await),
+            None => None,
+        };
+
+        // Runtime control plane
+        let _control = if app_config.control.enabled {
+            let mut control = ControlPlane::new(name.clone(), &aether)
+                .with_auth_token(app_config.control.auth_token.clone())
+                .with_vibrator(vibrator.control())
+                .with_rollout(version_router);
+            if let Some(tracker) = topology {
+                control = control.with_topology(tracker);
+            }
+            if let Some(index) = flow_traces {
+                control = control.with_flow_traces(index);
+            }
+            if let Some(history) = physics_history {
+                control = control.with_physics_history(history);
+            }
+            if let Some(index) = wave_index {
+                control = control.with_wave_index(index);
+            }
+            Some(control.spawn().await)
+        } else {
+            None
+        };
+
+        let retry_policy = RetryPolicy::new(
+            app_config.service.retry_max,
+            Duration::from_millis(app_config.service.retry_base_delay_ms),
+            Duration::from_millis(app_config.service.retry_max_delay_ms),
+        );
+        let breaker_name = format!("{}.breaker", name);
+        let breaker = CircuitBreaker::new(
+            app_config.service.circuit_breaker_failure_threshold,
+            Duration::from_millis(app_config.service.circuit_breaker_open_ms),
+            app_config.service.circuit_breaker_half_open_successes,
+        )
+        .named(breaker_name.clone())
+        .with_audit(breaker_name.clone(), aether.audit())
+        .with_events(breaker_name, &aether);
+        let grace = Duration::from_millis(app_config.operations.shutdown_grace_ms);
+        let membership = if app_config.cluster.enabled {
+            let node = Heartbeat::new(name.clone(), version)
+                .with_channels(channel_names(&vibrator.resonant_channels()));
+            let inflight = task_manager.inflight_counter();
+            let capacity = app_config.service.max_inflight.max(1) as f64;
+            Some(
+                aether
+                    .join_cluster(node, &app_config.cluster)
+                    .await
+                    .with_load(move || inflight.load(Ordering::Relaxed) as f64 / capacity),
+            )
+        } else {
+            None
+        };
+        let ctx = ServiceContext {
+            timeout: Duration::from_millis(app_config.service.timeout_ms),
+            config: Arc::new(app_config),
+            aether: aether.clone(),
+            emitter: vibrator.emitter(),
+            retry_policy,
+            breaker,
+        };
+
+        let state = (self.init)(ctx)
+            .await
+            .context("failed to initialize service state")?;
+        let router = Arc::new(
+            self.handlers
+                .into_iter()
+                .fold(WaveRouter::new(state), WaveRouter::route_boxed),
+        );
+
+        info!("✨ {} connected to the Aether layer", name);
+        info!("📡 Resonant channels: {:?}", vibrator.resonant_channels());
+
+        tokio::pin!(shutdown);
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => {
+                    info!("Shutdown signal received");
+                    break;
+                }
+                _ = intake.drain_requested() => {
+                    info!("Drain requested");
+                    break;
+                }
+                Some(configured) = configured_channels(&mut config_rx) => {
+                    let channels = resonant_channels(&configured, &defaults);
+                    vibrator.reconcile_channels(&channels).await;
+                    if let Some(membership) = &membership {
+                        membership.set_channels(channel_names(&vibrator.resonant_channels()));
+                    }
+                }
+                wave = vibrator.receive() => {
+                    let Some(wave) = wave else {
+                        // No channels left; wait for a reconcile to add some
+                        tokio::time::sleep(Duration::from_millis(10)).await;
+                        continue;
+                    };
+                    debug!(
+                        "🌊 Received wave: channel={}, type={:?}, amplitude={:.2}",
+                        wave.channel(),
+                        wave.wave_type(),
+                        wave.amplitude().value()
+                    );
+                    dispatch(&mut task_manager, &router, &watchdog, wave).await;
+                    task_manager.reap().await;
+                }
+            }
+        }
+
+        // Drain: stop intake (readiness turns false), hand waves already taken
+        // off the subscriptions to their handlers, let in-flight handlers
+        // finish, then unsubscribe and flush what they emitted
+        intake.drain();
+        if let Some(membership) = membership {
+            membership.leave().await;
+        }
+        let deadline = tokio::time::Instant::now() + grace;
+        let handed_over = tokio::time::timeout_at(deadline, async {
+            while let Some(wave) = vibrator.receive().await {
+                dispatch(&mut task_manager, &router, &watchdog, wave).await;
+            }
+        })
+        .await;
+        if handed_over.is_err() {
+            warn!("Shutdown grace ended while dispatching held waves");
+        }
+        let left = deadline.saturating_duration_since(tokio::time::Instant::now());
+        if !task_manager.wait_idle(left).await {
+            warn!(
+                "{} tasks still running after the {:?} shutdown grace",
+                task_manager.inflight() + task_manager.queued(),
+                grace
+            );
+        }
+        let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+        match vibrator.drain(remaining).await {
+            Ok(held) if !held.is_empty() => {
+                metrics::counter!("aether_drain_dropped_waves_total").increment(held.len() as u64);
+                warn!("Dropped {} received waves not yet handled", held.len())
+            }
+            Ok(_) => info!("{} drained", name),
+            Err(err) => warn!("Drain of {} incomplete: {}", name, err),
+        }
+        Ok(())
+    }
+}
+
+/// What the runtime hands to the state initializer
+#[derive(Clone)]
+pub struct ServiceContext {
+    config: Arc<AppConfig>,
+    aether: Aether,
+    emitter: VibratorEmitter,
+    retry_policy: RetryPolicy,
+    timeout: Duration,
+    breaker: CircuitBreaker,
+}
+
+impl ServiceContext {
+    /// Config as loaded at startup
+    pub fn config(&self) -> &AppConfig {
+        &self.config
+    }
+
+    pub fn aether(&self) -> &Aether {
+        &self.aether
+    }
+
+    pub fn emitter(&self) -> &VibratorEmitter {
+        &self.emitter
+    }
+
+    pub fn breaker(&self) -> &CircuitBreaker {
+        &self.breaker
+    }
+
+    /// Emit through the circuit breaker, retrying each attempt under the service timeout
+    ///
+    /// Retries are recorded under `operation`, a fixed name for the call
+    /// site (`"send_payment_request"`), not the channel, which may be dynamic.
+    pub async fn emit(
+        &self,
+        operation: &str,
+        channel: impl Into<Channel>,
+        payload: serde_json::Value,
+    ) -> anyhow::Result<EmitReceipt> {
+        let channel = channel.into();
+        self.breaker
+            .call(|| async {
+                retry_with_timeout_named(operation, &self.retry_policy, self.timeout, || {
+                    self.emitter.emit_wave(channel.clone(), payload.clone())
+                })
+                .await
+            })
+            .await
+    }
+}
+
+/// Run `wave` through its handler on the task manager
+async fn dispatch<S: Send + Sync + 'static>(
+    task_manager: &mut TaskManager,
+    router: &Arc<WaveRouter<S>>,
+    watchdog: &Option<BlockingWatchdog>,
+    wave: Wave,
+) {
+    let router = Arc::clone(router);
+    let priority = Priority::for_wave(&wave);
+    let watchdog = watchdog.clone();
+    let lane = wave.channel().clone();
+    let spawned = task_manager
+        .spawn_for(priority, &lane, async move {
+            let channel = wave.channel().clone();
+            let context = WaveContext::of(&wave);
+            let dispatch = context.scope(router.dispatch(wave));
+            let handled = match &watchdog {
+                Some(watchdog) => watchdog.watch(channel.name(), dispatch).await,
+                None => dispatch.await,
+            };
+            if handled.is_none() {
+                debug!("No handler for channel: {}", channel);
+            }
+        })
+        .await;
+    if let Err(err) = spawned {
+        warn!("Dropped wave on {}: {}", lane, err);
+    }
+}
+
+/// Configured channels, or the service's defaults when none are set
+fn resonant_channels(configured: &[String], defaults: &[Channel]) -> Vec<String> {
+    if configured.is_empty() {
+        defaults.iter().map(|c| c.name().to_string()).collect()
+    } else {
+        configured.to_vec()
+    }
+}
+
+fn channel_names(channels: &[Channel]) -> Vec<String> {
+    channels.iter().map(|c| c.name().to_string()).collect()
+}
+
+/// Next change to the configured channels; pending forever without a watcher
+async fn configured_channels(
+    config_rx: &mut Option<watch::Receiver<AppConfig>>,
+) -> Option<Vec<String>> {
+    match config_rx {
+        Some(rx) => {
+            rx.changed().await.ok()?;
+            let channels = rx.borrow_and_update().service.channels.clone();
+            Some(channels)
+        }
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{handler, Wave};
+
+    #[handler(channel = "orders.created")]
+    async fn order_created(ctx: &ServiceContext, wave: Wave) -> anyhow::Result<()> {
+        let order = wave.payload()["order_id"].clone();
+        let confirmation = serde_json::json!({ "order_id": order });
+        ctx.emit("confirm_order", "orders.confirmed", confirmation)
+            .await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_serves_handlers_until_shutdown() {
+        let mut app_config = AppConfig::default();
+        app_config.service.name = "service-test".to_string();
+        app_config.aether.use_nats = false;
+        app_config.resource_monitoring.enabled = false;
+        app_config.operations.shutdown_grace_ms = 0;
+
+        let (ready_tx, ready_rx) = tokio::sync::oneshot::channel();
+        let (done_tx, done_rx) = tokio::sync::oneshot::channel::<()>();
+        let app = AetherApp::builder()
+            .state(|ctx| async move {
+                ready_tx.send(ctx.clone()).ok();
+                Ok(ctx)
+            })
+            .handler(order_created);
+        let serving = tokio::spawn(app.serve(app_config, None, None, async {
+            done_rx.await.ok();
+        }));
+
+        let ctx = ready_rx.await.unwrap();
+        let aether = ctx.aether().clone();
+        let confirmed = Channel::new("orders.confirmed");
+        let mut confirmations = aether.subscribe(&confirmed).await;
+        // Resonating on the handler channels is the default
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        aether
+            .emit(Wave::new(
+                "orders.created",
+                serde_json::json!({"order_id": "A-1"}),
+            ))
+            .await
+            .unwrap();
+
+        let wave = tokio::time::timeout(Duration::from_secs(2), confirmations.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(wave.payload()["order_id"], "A-1");
+        assert_eq!(wave.source(), Some("service-test"));
+
+        done_tx.send(()).unwrap();
+        serving.await.unwrap().unwrap();
+    }
+}
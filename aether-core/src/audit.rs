@@ -0,0 +1,525 @@
+//! Audit log: hash-chained record of security-relevant events in the WaveStore.
+
+use crate::persistence::WaveStore;
+use crate::rate_limit::TokenBucket;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sled::Tree;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub(crate) const AUDIT_TREE: &str = "audit";
+
+/// `aether.audit`: chain key and how many rejected emits get recorded
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuditConfig {
+    /// Environment variable holding the key the chain is HMAC'd with
+    #[serde(default)]
+    pub key_env: Option<String>,
+    /// File holding the key, e.g. a mounted secret
+    #[serde(default)]
+    pub key_file: Option<String>,
+    /// Rejected emits recorded per second (0 records all); the rest are
+    /// counted in the next one
+    #[serde(default = "default_rejections_per_sec")]
+    pub rejections_per_sec: f64,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            key_env: None,
+            key_file: None,
+            rejections_per_sec: default_rejections_per_sec(),
+        }
+    }
+}
+
+fn default_rejections_per_sec() -> f64 {
+    10.0
+}
+
+impl AuditConfig {
+    /// The chain key; `None` leaves the chain a plain SHA-256 one
+    pub fn load_key(&self) -> Result<Option<Vec<u8>>> {
+        let key = match (&self.key_env, &self.key_file) {
+            (None, None) => return Ok(None),
+            (Some(var), None) => std::env::var(var)
+                .with_context(|| format!("audit key: environment variable {} not set", var))?,
+            (None, Some(path)) => std::fs::read_to_string(path)
+                .with_context(|| format!("audit key: failed to read {}", path))?,
+            _ => bail!("audit key: set at most one of key_env and key_file"),
+        };
+        let key = key.trim();
+        if key.is_empty() {
+            bail!("audit key is empty");
+        }
+        Ok(Some(key.as_bytes().to_vec()))
+    }
+}
+
+/// Category of an audited event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditKind {
+    /// Missing or wrong auth token
+    AuthFailure,
+    /// Authenticated but not permitted (source allow-list, namespace bridge)
+    AclDenial,
+    ConfigReload,
+    /// Administrative command received on the control channel
+    ControlCommand,
+    CircuitBreakerTrip,
+}
+
+impl AuditKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditKind::AuthFailure => "auth_failure",
+            AuditKind::AclDenial => "acl_denial",
+            AuditKind::ConfigReload => "config_reload",
+            AuditKind::ControlCommand => "control_command",
+            AuditKind::CircuitBreakerTrip => "circuit_breaker_trip",
+        }
+    }
+}
+
+impl std::str::FromStr for AuditKind {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        serde_json::from_value(serde_json::Value::String(value.to_string()))
+            .map_err(|_| anyhow!("unknown audit kind: {}", value))
+    }
+}
+
+/// One entry in the audit chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub index: u64,
+    pub timestamp: DateTime<Utc>,
+    pub kind: AuditKind,
+    /// Who triggered the event (wave source, service or breaker name)
+    pub actor: Option<String>,
+    pub detail: serde_json::Value,
+    /// Hash of the previous entry; empty for the first
+    pub prev_hash: String,
+    /// SHA-256 over `prev_hash` and this entry's fields (HMAC-SHA256 under
+    /// the audit key when there is one), hex encoded
+    pub hash: String,
+}
+
+impl AuditEvent {
+    fn compute_hash(&self, key: Option<&[u8]>) -> String {
+        #[derive(Serialize)]
+        struct Hashed<'a> {
+            index: u64,
+            timestamp: &'a DateTime<Utc>,
+            kind: AuditKind,
+            actor: &'a Option<String>,
+            detail: &'a serde_json::Value,
+        }
+        let body = serde_json::to_vec(&Hashed {
+            index: self.index,
+            timestamp: &self.timestamp,
+            kind: self.kind,
+            actor: &self.actor,
+            detail: &self.detail,
+        })
+        .expect("audit event serializes");
+
+        let digest = if let Some(key) = key {
+            let mut mac =
+                <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts any key length");
+            mac.update(self.prev_hash.as_bytes());
+            mac.update(&body);
+            mac.finalize().into_bytes()
+        } else {
+            let mut hasher = Sha256::new();
+            hasher.update(self.prev_hash.as_bytes());
+            hasher.update(&body);
+            hasher.finalize()
+        };
+        digest.iter().fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{:02x}", byte);
+            hex
+        })
+    }
+}
+
+/// Caps how many rejected emits are recorded, counting the ones skipped
+#[derive(Debug)]
+struct RejectionBudget {
+    /// `None` records every rejection
+    bucket: Option<TokenBucket>,
+    suppressed: u64,
+}
+
+impl RejectionBudget {
+    fn new(per_sec: f64) -> Self {
+        Self {
+            bucket: (per_sec > 0.0).then(|| TokenBucket::new(per_sec, per_sec)),
+            suppressed: 0,
+        }
+    }
+
+    /// Take one rejection; the number skipped since the last one, or `None` past the budget
+    fn admit(&mut self) -> Option<u64> {
+        if let Some(bucket) = &mut self.bucket {
+            if bucket.reserve(1.0, Duration::ZERO).is_err() {
+                self.suppressed += 1;
+                return None;
+            }
+        }
+        Some(std::mem::take(&mut self.suppressed))
+    }
+}
+
+/// Filter for `AuditLog::query`; unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    pub kind: Option<AuditKind>,
+    pub actor: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    /// Keep only the newest `limit` matches
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    fn matches(&self, event: &AuditEvent) -> bool {
+        self.kind.is_none_or(|kind| event.kind == kind)
+            && self
+                .actor
+                .as_ref()
+                .is_none_or(|actor| event.actor.as_ref() == Some(actor))
+            && self.since.is_none_or(|since| event.timestamp >= since)
+            && self.until.is_none_or(|until| event.timestamp < until)
+    }
+}
+
+/// Append-only, hash-chained audit tree
+///
+/// Each entry hashes the previous entry's hash, so editing or deleting an
+/// entry breaks the chain from that point on; `verify` finds the break.
+/// Without a key anyone who can write the store can also recompute the
+/// chain, so production logs should be opened with one. A chain only
+/// verifies under the key it was started with (or without one if it was
+/// started without), so set the key before the first entry.
+#[derive(Clone)]
+pub struct AuditLog {
+    tree: Tree,
+    /// Next index and hash of the last entry
+    head: Arc<Mutex<(u64, String)>>,
+    key: Option<Arc<[u8]>>,
+    rejections: Arc<Mutex<RejectionBudget>>,
+}
+
+impl AuditLog {
+    /// Open the store's audit log, HMAC-chained under `key` if given
+    ///
+    /// Fails if the last entry was not written under the same key.
+    pub fn open(store: &WaveStore, key: Option<Vec<u8>>) -> Result<Self> {
+        let tree = store.open_tree(AUDIT_TREE)?;
+        let head = match tree.last()? {
+            Some((_, value)) => {
+                let last: AuditEvent = serde_json::from_slice(&value)?;
+                if last.compute_hash(key.as_deref()) != last.hash {
+                    bail!(
+                        "audit log entry {} was not written under the configured audit key",
+                        last.index
+                    );
+                }
+                (last.index + 1, last.hash)
+            }
+            None => (0, String::new()),
+        };
+        let log = Self {
+            tree,
+            head: Arc::new(Mutex::new(head)),
+            key: key.map(Arc::from),
+            rejections: Arc::new(Mutex::new(RejectionBudget::new(
+                default_rejections_per_sec(),
+            ))),
+        };
+        Ok(log)
+    }
+
+    /// Record at most `per_sec` rejected emits a second, with a one second
+    /// burst; 0 records all of them
+    pub fn with_rejection_rate(mut self, per_sec: f64) -> Self {
+        self.rejections = Arc::new(Mutex::new(RejectionBudget::new(per_sec)));
+        self
+    }
+
+    /// Like `record` for a refused emit, within the rejection budget
+    ///
+    /// Rejections past the budget are only counted; the next one recorded
+    /// carries the count as `suppressed` in its detail.
+    pub fn record_rejection(
+        &self,
+        kind: AuditKind,
+        actor: Option<&str>,
+        mut detail: serde_json::Value,
+    ) -> Result<Option<AuditEvent>> {
+        let admitted = self
+            .rejections
+            .lock()
+            .expect("audit rejections poisoned")
+            .admit();
+        let Some(suppressed) = admitted else {
+            metrics::counter!("aether_audit_rejections_suppressed_total").increment(1);
+            return Ok(None);
+        };
+        if suppressed > 0 {
+            if let Some(detail) = detail.as_object_mut() {
+                detail.insert("suppressed".to_string(), suppressed.into());
+            }
+        }
+        self.record(kind, actor, detail).map(Some)
+    }
+
+    pub fn record(
+        &self,
+        kind: AuditKind,
+        actor: Option<&str>,
+        detail: serde_json::Value,
+    ) -> Result<AuditEvent> {
+        let mut head = self.head.lock().expect("audit head poisoned");
+        let mut event = AuditEvent {
+            index: head.0,
+            timestamp: Utc::now(),
+            kind,
+            actor: actor.map(str::to_string),
+            detail,
+            prev_hash: head.1.clone(),
+            hash: String::new(),
+        };
+        event.hash = event.compute_hash(self.key.as_deref());
+        self.tree
+            .insert(event.index.to_be_bytes(), serde_json::to_vec(&event)?)?;
+        *head = (event.index + 1, event.hash.clone());
+        metrics::counter!("aether_audit_events_total", "kind" => kind.as_str()).increment(1);
+        Ok(event)
+    }
+
+    /// Matching events, oldest first
+    pub fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEvent>> {
+        let mut events = Vec::new();
+        for item in self.tree.iter() {
+            let (_, value) = item?;
+            let event: AuditEvent = serde_json::from_slice(&value)?;
+            if query.matches(&event) {
+                events.push(event);
+            }
+        }
+        if let Some(limit) = query.limit {
+            let skip = events.len().saturating_sub(limit);
+            events.drain(..skip);
+        }
+        Ok(events)
+    }
+
+    /// Index of the first entry that breaks the chain, if any
+    pub fn verify(&self) -> Result<Option<u64>> {
+        let mut expected = (0u64, String::new());
+        for item in self.tree.iter() {
+            let (key, value) = item?;
+            let position = key
+                .as_ref()
+                .try_into()
+                .map(u64::from_be_bytes)
+                .unwrap_or(expected.0);
+            let Ok(event) = serde_json::from_slice::<AuditEvent>(&value) else {
+                return Ok(Some(position));
+            };
+            if event.index != expected.0
+                || event.prev_hash != expected.1
+                || event.hash != event.compute_hash(self.key.as_deref())
+            {
+                return Ok(Some(expected.0));
+            }
+            expected = (event.index + 1, event.hash);
+        }
+        Ok(None)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+}
+
+impl std::fmt::Debug for AuditLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditLog")
+            .field("entries", &self.tree.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Aether, AetherConfig, AetherError, Wave};
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("aether-{}-{}", name, uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_chain_detects_tampering() {
+        let path = temp_path("audit-chain");
+        let store = WaveStore::open(&path).unwrap();
+        let audit = AuditLog::open(&store, None).unwrap();
+        audit
+            .record(
+                AuditKind::ConfigReload,
+                Some("service-alpha"),
+                serde_json::json!({}),
+            )
+            .unwrap();
+        audit
+            .record(
+                AuditKind::ControlCommand,
+                Some("aether-cli"),
+                serde_json::json!({"command": "pause"}),
+            )
+            .unwrap();
+        audit
+            .record(
+                AuditKind::ConfigReload,
+                Some("service-beta"),
+                serde_json::json!({}),
+            )
+            .unwrap();
+
+        // Reopening continues the chain
+        let reopened = AuditLog::open(&store, None).unwrap();
+        let last = reopened
+            .record(AuditKind::AuthFailure, None, serde_json::json!({}))
+            .unwrap();
+        assert_eq!(last.index, 3);
+        assert_eq!(reopened.verify().unwrap(), None);
+
+        let reloads = reopened
+            .query(&AuditQuery {
+                kind: Some(AuditKind::ConfigReload),
+                ..AuditQuery::default()
+            })
+            .unwrap();
+        assert_eq!(reloads.len(), 2);
+
+        let mut forged = reloads[0].clone();
+        forged.actor = Some("someone-else".to_string());
+        audit
+            .tree
+            .insert(0u64.to_be_bytes(), serde_json::to_vec(&forged).unwrap())
+            .unwrap();
+        assert_eq!(audit.verify().unwrap(), Some(0));
+
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_keyed_chain_cannot_be_recomputed_without_the_key() {
+        let path = temp_path("audit-keyed");
+        let store = WaveStore::open(&path).unwrap();
+        let key = b"audit-secret".to_vec();
+        let audit = AuditLog::open(&store, Some(key.clone())).unwrap();
+        let event = audit
+            .record(
+                AuditKind::ControlCommand,
+                Some("aether-cli"),
+                serde_json::json!({}),
+            )
+            .unwrap();
+        assert_eq!(audit.verify().unwrap(), None);
+        assert!(AuditLog::open(&store, None).is_err());
+        assert!(AuditLog::open(&store, Some(b"other".to_vec())).is_err());
+
+        // Rehashing a forged entry without the key does not pass as keyed
+        let mut forged = event.clone();
+        forged.actor = Some("someone-else".to_string());
+        forged.hash = forged.compute_hash(None);
+        audit
+            .tree
+            .insert(0u64.to_be_bytes(), serde_json::to_vec(&forged).unwrap())
+            .unwrap();
+        assert_eq!(audit.verify().unwrap(), Some(0));
+
+        drop(store);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rejections_past_the_budget_are_counted_not_stored() {
+        let path = temp_path("audit-rejections");
+        let store = WaveStore::open(&path).unwrap();
+        let audit = AuditLog::open(&store, None)
+            .unwrap()
+            .with_rejection_rate(2.0);
+        let recorded = (0..5)
+            .filter_map(|_| {
+                audit
+                    .record_rejection(AuditKind::AuthFailure, None, serde_json::json!({}))
+                    .unwrap()
+            })
+            .count();
+        assert_eq!(recorded, 2);
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        let event = audit
+            .record_rejection(AuditKind::AuthFailure, None, serde_json::json!({}))
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.detail["suppressed"], 3);
+        assert_eq!(audit.len(), 3);
+        assert_eq!(audit.verify().unwrap(), None);
+
+        drop(store);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_emits_are_audited() {
+        let path = temp_path("audit-emit");
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            persistence_enabled: true,
+            persistence_path: path.to_string_lossy().into_owned(),
+            auth_token: Some("secret".to_string()),
+            allowed_sources: vec!["service-alpha".to_string()],
+            ..AetherConfig::default()
+        });
+
+        let unauthenticated = Wave::new("orders.created", serde_json::json!({}));
+        let err = aether.emit(unauthenticated).await.unwrap_err();
+        assert!(matches!(err, AetherError::AuthorizationFailed(_)));
+
+        let mut denied = Wave::builder(crate::Channel::new("orders.created"))
+            .source("service-mallory")
+            .build();
+        denied.set_auth_token("secret".to_string());
+        assert!(aether.emit(denied).await.is_err());
+
+        let audit = aether.audit().unwrap();
+        let kinds: Vec<AuditKind> = audit
+            .query(&AuditQuery::default())
+            .unwrap()
+            .into_iter()
+            .map(|event| event.kind)
+            .collect();
+        assert_eq!(kinds, vec![AuditKind::AuthFailure, AuditKind::AclDenial]);
+
+        drop(aether);
+        let _ = std::fs::remove_dir_all(path);
+    }
+}
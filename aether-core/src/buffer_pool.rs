@@ -45,10 +45,6 @@ pub struct PooledBytesMut {
 }
 
 impl PooledBytesMut {
-    pub fn as_mut(&mut self) -> &mut BytesMut {
-        self.buffer.as_mut().expect("buffer already taken")
-    }
-
     pub fn len(&self) -> usize {
         self.buffer.as_ref().map(|b| b.len()).unwrap_or(0)
     }
@@ -64,6 +60,12 @@ impl PooledBytesMut {
     }
 }
 
+impl AsMut<BytesMut> for PooledBytesMut {
+    fn as_mut(&mut self) -> &mut BytesMut {
+        self.buffer.as_mut().expect("buffer already taken")
+    }
+}
+
 impl Drop for PooledBytesMut {
     fn drop(&mut self) {
         if let Some(buffer) = self.buffer.take() {
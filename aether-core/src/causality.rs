@@ -0,0 +1,323 @@
+//! Causality inspection over the wave log, for `aether-cli inspect`.
+//!
+//! Waves emitted by a handler carry the handled wave's ID as their causation
+//! ID and inherit its correlation ID (see [`crate::wave_context`]), so the
+//! log holds the tree each flow grew. [`CausalTree`] rebuilds the waves one
+//! wave triggered; [`WaveComparison`] lines two waves up: payload changes,
+//! shared causes and the time between them.
+
+use crate::{persistence::WaveStore, wave::Wave};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// What the inspector shows of a wave
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WaveSummary {
+    pub id: Uuid,
+    pub channel: String,
+    pub source: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub correlation_id: String,
+    pub causation_id: Option<Uuid>,
+}
+
+impl From<&Wave> for WaveSummary {
+    fn from(wave: &Wave) -> Self {
+        Self {
+            id: *wave.id(),
+            channel: wave.channel().name().to_string(),
+            source: wave.source().map(str::to_string),
+            timestamp: *wave.timestamp(),
+            correlation_id: wave.correlation_id(),
+            causation_id: wave.causation_id(),
+        }
+    }
+}
+
+/// A wave and the waves emitted while handling it, recursively
+#[derive(Debug, Clone, Serialize)]
+pub struct CausalTree {
+    #[serde(flatten)]
+    pub wave: WaveSummary,
+    pub caused: Vec<CausalTree>,
+}
+
+impl CausalTree {
+    /// Tree of logged waves `root` triggered; `None` if `root` is not in the log
+    ///
+    /// Reads the waves sharing `root`'s correlation ID through the store's
+    /// correlation index, so a handler that overrides the correlation ID cuts
+    /// its branch off the tree.
+    pub fn build(store: &WaveStore, root: &Uuid) -> Result<Option<Self>> {
+        let Some((_, wave)) = store.get_wave(root)? else {
+            return Ok(None);
+        };
+        let mut children: HashMap<Uuid, Vec<WaveSummary>> = HashMap::new();
+        for (_, wave) in store.read_correlation(&wave.correlation_id())? {
+            if let Some(cause) = wave.causation_id() {
+                children.entry(cause).or_default().push((&wave).into());
+            }
+        }
+        let mut seen = HashSet::new();
+        Ok(Some(grow((&wave).into(), &mut children, &mut seen)))
+    }
+
+    /// Waves in the tree, the root included
+    pub fn wave_count(&self) -> usize {
+        1 + self
+            .caused
+            .iter()
+            .map(CausalTree::wave_count)
+            .sum::<usize>()
+    }
+
+    /// One line per wave, indented by depth, timed from the root
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        self.render_into(&mut out, 0, self.wave.timestamp);
+        out
+    }
+
+    fn render_into(&self, out: &mut String, depth: usize, start: DateTime<Utc>) {
+        let elapsed = (self.wave.timestamp - start).num_milliseconds();
+        out.push_str(&format!(
+            "{}{} {} from {} +{}ms\n",
+            "  ".repeat(depth),
+            self.wave.channel,
+            self.wave.id,
+            self.wave.source.as_deref().unwrap_or("?"),
+            elapsed
+        ));
+        for child in &self.caused {
+            child.render_into(out, depth + 1, start);
+        }
+    }
+}
+
+fn grow(
+    wave: WaveSummary,
+    children: &mut HashMap<Uuid, Vec<WaveSummary>>,
+    seen: &mut HashSet<Uuid>,
+) -> CausalTree {
+    // A forged causation cycle must not recurse forever
+    seen.insert(wave.id);
+    let mut direct = children.remove(&wave.id).unwrap_or_default();
+    direct.retain(|child| !seen.contains(&child.id));
+    direct.sort_by_key(|child| child.timestamp);
+    let caused = direct
+        .into_iter()
+        .map(|child| grow(child, children, seen))
+        .collect();
+    CausalTree { wave, caused }
+}
+
+/// The wave with `id` followed by its causes, nearest first, as far as the log reaches
+pub fn causation_chain(store: &WaveStore, id: &Uuid) -> Result<Vec<WaveSummary>> {
+    let mut chain: Vec<WaveSummary> = Vec::new();
+    let mut next = Some(*id);
+    while let Some(id) = next {
+        if chain.iter().any(|wave| wave.id == id) {
+            break;
+        }
+        let Some((_, wave)) = store.get_wave(&id)? else {
+            break;
+        };
+        next = wave.causation_id();
+        chain.push((&wave).into());
+    }
+    Ok(chain)
+}
+
+/// A field whose value differs between two payloads
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PayloadChange {
+    /// JSON pointer to the field, `""` for the whole payload
+    pub path: String,
+    /// `None` when the field only exists in the second payload
+    pub before: Option<serde_json::Value>,
+    /// `None` when the field only exists in the first payload
+    pub after: Option<serde_json::Value>,
+}
+
+/// Field-by-field differences from `before` to `after`
+pub fn diff_payloads(before: &serde_json::Value, after: &serde_json::Value) -> Vec<PayloadChange> {
+    let mut changes = Vec::new();
+    diff_at(String::new(), Some(before), Some(after), &mut changes);
+    changes
+}
+
+fn diff_at(
+    path: String,
+    before: Option<&serde_json::Value>,
+    after: Option<&serde_json::Value>,
+    changes: &mut Vec<PayloadChange>,
+) {
+    use serde_json::Value;
+    match (before, after) {
+        (Some(Value::Object(before)), Some(Value::Object(after))) => {
+            let mut keys: Vec<&String> = before.keys().chain(after.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let path = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+                diff_at(path, before.get(key), after.get(key), changes);
+            }
+        }
+        (Some(Value::Array(before)), Some(Value::Array(after))) => {
+            for index in 0..before.len().max(after.len()) {
+                let path = format!("{}/{}", path, index);
+                diff_at(path, before.get(index), after.get(index), changes);
+            }
+        }
+        (before, after) if before != after => changes.push(PayloadChange {
+            path,
+            before: before.cloned(),
+            after: after.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+/// Two logged waves side by side
+#[derive(Debug, Clone, Serialize)]
+pub struct WaveComparison {
+    pub first: WaveSummary,
+    pub second: WaveSummary,
+    pub payload_changes: Vec<PayloadChange>,
+    pub same_correlation: bool,
+    /// Causes both waves descend from, nearest first; either wave itself counts
+    pub common_causes: Vec<WaveSummary>,
+    /// Time from `first` to `second`; negative if `second` is older
+    pub elapsed_ms: i64,
+}
+
+impl WaveComparison {
+    pub fn between(store: &WaveStore, first: &Uuid, second: &Uuid) -> Result<Self> {
+        let find = |id: &Uuid| {
+            store
+                .get_wave(id)?
+                .map(|(_, wave)| wave)
+                .ok_or_else(|| anyhow!("wave {} is not in the log", id))
+        };
+        let (first_wave, second_wave) = (find(first)?, find(second)?);
+        let second_chain: HashSet<Uuid> = causation_chain(store, second)?
+            .into_iter()
+            .map(|wave| wave.id)
+            .collect();
+        let common_causes = causation_chain(store, first)?
+            .into_iter()
+            .filter(|wave| second_chain.contains(&wave.id))
+            .collect();
+        let first = WaveSummary::from(&first_wave);
+        let second = WaveSummary::from(&second_wave);
+        Ok(Self {
+            payload_changes: diff_payloads(first_wave.payload(), second_wave.payload()),
+            same_correlation: first.correlation_id == second.correlation_id,
+            common_causes,
+            elapsed_ms: (second.timestamp - first.timestamp).num_milliseconds(),
+            first,
+            second,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wave_context::{CAUSATION_KEY, CORRELATION_KEY};
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("aether-{}-{}", name, uuid::Uuid::new_v4()))
+    }
+
+    fn caused_by(channel: &str, cause: &Wave, payload: serde_json::Value) -> Wave {
+        Wave::builder(channel)
+            .payload(payload)
+            .metadata(serde_json::json!({
+                CORRELATION_KEY: cause.correlation_id(),
+                CAUSATION_KEY: cause.id().to_string(),
+            }))
+            .build()
+    }
+
+    #[test]
+    fn test_tree_and_comparison_follow_causation_links() {
+        let path = temp_path("causality");
+        let store = WaveStore::open(&path).unwrap();
+        let order = Wave::new("orders.created", serde_json::json!({ "order": "ORD-1" }));
+        let reserve = caused_by(
+            "inventory.reserve",
+            &order,
+            serde_json::json!({ "order": "ORD-1", "qty": 2 }),
+        );
+        let charge = caused_by(
+            "payments.charge",
+            &order,
+            serde_json::json!({ "order": "ORD-1", "amount": 30 }),
+        );
+        let reserved = caused_by("inventory.reserved", &reserve, serde_json::json!({}));
+        let unrelated = Wave::new("orders.created", serde_json::json!({ "order": "ORD-2" }));
+        for wave in [&order, &reserve, &charge, &reserved, &unrelated] {
+            store.append_wave(wave).unwrap();
+        }
+
+        let tree = CausalTree::build(&store, order.id()).unwrap().unwrap();
+        assert_eq!(tree.wave_count(), 4);
+        let rendered = tree.render();
+        assert!(rendered.starts_with("orders.created"));
+        assert!(rendered.contains("\n    inventory.reserved "));
+        assert!(CausalTree::build(&store, &Uuid::new_v4())
+            .unwrap()
+            .is_none());
+
+        let comparison = WaveComparison::between(&store, reserved.id(), charge.id()).unwrap();
+        assert!(comparison.same_correlation);
+        let causes: Vec<Uuid> = comparison.common_causes.iter().map(|w| w.id).collect();
+        assert_eq!(causes, vec![*order.id()]);
+        let paths: Vec<&str> = comparison
+            .payload_changes
+            .iter()
+            .map(|change| change.path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["/amount", "/order"]);
+
+        drop(store);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_payload_diff_reports_changed_added_and_removed_fields() {
+        let before = serde_json::json!({ "qty": 2, "tags": ["a", "b"], "note": "x" });
+        let after = serde_json::json!({ "qty": 3, "tags": ["a"], "sku": "S-1" });
+        let changes = diff_payloads(&before, &after);
+        assert_eq!(
+            changes,
+            vec![
+                PayloadChange {
+                    path: "/note".to_string(),
+                    before: Some("x".into()),
+                    after: None,
+                },
+                PayloadChange {
+                    path: "/qty".to_string(),
+                    before: Some(2.into()),
+                    after: Some(3.into()),
+                },
+                PayloadChange {
+                    path: "/sku".to_string(),
+                    before: None,
+                    after: Some("S-1".into()),
+                },
+                PayloadChange {
+                    path: "/tags/1".to_string(),
+                    before: Some("b".into()),
+                    after: None,
+                },
+            ]
+        );
+        assert!(diff_payloads(&before, &before).is_empty());
+    }
+}
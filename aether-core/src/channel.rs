@@ -1,25 +1,75 @@
 //! Channel - frequency space for waves
 
-use serde::{Deserialize, Serialize};
+use dashmap::DashMap;
+use serde::{
+    de::{DeserializeOwned, IgnoredAny},
+    Deserialize, Serialize,
+};
 use std::fmt;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::marker::PhantomData;
+use std::sync::{Arc, LazyLock};
+use crate::clock::{Clock, SystemClock};
+use crate::wave::{Wave, WaveBuilder};
+use crate::{AetherError, Result};
+
+/// Matches exactly one segment
+const SINGLE_WILDCARD: &str = "*";
+
+/// Matches one or more trailing segments
+const MULTI_WILDCARD: &str = ">";
+
+/// Interned names stop being added past this many, so ad-hoc names cannot grow it unbounded
+const MAX_INTERNED_CHANNELS: usize = 4096;
+
+/// Channels created so far, shared by later channels with the same name
+static INTERNED: LazyLock<DashMap<Arc<str>, Channel>> = LazyLock::new(DashMap::new);
 
 /// A channel represents a specific frequency band and acts as a message category
+///
+/// Cloning is cheap: the name and segments are shared.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "ChannelRepr")]
 pub struct Channel {
     /// Channel name (e.g., "orders", "payments", "notifications")
-    name: String,
+    name: Arc<str>,
     /// Supports hierarchical structure (e.g., "orders.created", "orders.updated")
-    segments: Vec<String>,
+    segments: Arc<[String]>,
+}
+
+/// Wire form of a channel; segments are rebuilt from the name
+#[derive(Deserialize)]
+struct ChannelRepr {
+    name: String,
+    #[serde(default, rename = "segments")]
+    _segments: IgnoredAny,
+}
+
+impl From<ChannelRepr> for Channel {
+    fn from(repr: ChannelRepr) -> Self {
+        Self::new(repr.name)
+    }
 }
 
 impl Channel {
     /// Create a new channel
+    ///
+    /// Names are interned, so channels with a name seen before share its storage.
     pub fn new(name: impl Into<String>) -> Self {
         let name = name.into();
-        let segments = name.split('.').map(|s| s.to_string()).collect();
+        if let Some(channel) = INTERNED.get(name.as_str()) {
+            return channel.clone();
+        }
 
-        Self { name, segments }
+        let channel = Self {
+            segments: name.split('.').map(|s| s.to_string()).collect(),
+            name: name.into(),
+        };
+        if INTERNED.len() < MAX_INTERNED_CHANNELS {
+            INTERNED
+                .entry(Arc::clone(&channel.name))
+                .or_insert_with(|| channel.clone());
+        }
+        channel
     }
 
     /// Get the channel name
@@ -27,26 +77,29 @@ impl Channel {
         &self.name
     }
 
-    /// Determine whether this channel matches another channel
-    /// Supports wildcard ("*")
+    /// Determine whether this channel matches a pattern
+    ///
+    /// Follows NATS subject rules: "*" matches exactly one segment and a
+    /// trailing ">" matches one or more segments ("orders.>", "orders.*.failed").
     pub fn matches(&self, pattern: &Channel) -> bool {
-        if pattern.name == "*" {
-            return true;
-        }
-
-        if self.segments.len() != pattern.segments.len() {
-            // Support patterns like "orders.*"
-            if pattern.segments.last() == Some(&"*".to_string()) {
-                let pattern_prefix = &pattern.segments[..pattern.segments.len() - 1];
-                return self.segments.starts_with(pattern_prefix);
+        let mut segments = self.segments.iter();
+        for (i, p) in pattern.segments.iter().enumerate() {
+            if p == MULTI_WILDCARD {
+                return i == pattern.segments.len() - 1 && segments.next().is_some();
+            }
+            match segments.next() {
+                Some(s) if p == SINGLE_WILDCARD || s == p => {}
+                _ => return false,
             }
-            return false;
         }
+        segments.next().is_none()
+    }
 
+    /// Whether this channel is a pattern rather than a concrete channel
+    pub fn is_wildcard(&self) -> bool {
         self.segments
             .iter()
-            .zip(pattern.segments.iter())
-            .all(|(s, p)| p == "*" || s == p)
+            .any(|s| s == SINGLE_WILDCARD || s == MULTI_WILDCARD)
     }
 
     /// Create a child channel by concatenation
@@ -84,11 +137,12 @@ impl Channel {
 
     /// Create a frequency-hopped channel based on current system time
     pub fn hop_now(&self, hop_count: u16, hop_interval_ms: u64) -> Self {
-        let now_ms = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as u64;
-        self.hop_at_ms(now_ms, hop_count, hop_interval_ms)
+        self.hop_on(&SystemClock, hop_count, hop_interval_ms)
+    }
+
+    /// Create a frequency-hopped channel based on the given clock
+    pub fn hop_on(&self, clock: &dyn Clock, hop_count: u16, hop_interval_ms: u64) -> Self {
+        self.hop_at_ms(clock.now_ms(), hop_count, hop_interval_ms)
     }
 
     fn hop_seed(&self) -> u64 {
@@ -116,6 +170,161 @@ impl From<&str> for Channel {
     }
 }
 
+/// Channel name known at compile time, tied to its payload type
+///
+/// Usually declared with [`channels!`](crate::channels) so that a misspelled
+/// channel is an unknown identifier instead of a silently unused channel.
+pub struct TypedChannel<P = serde_json::Value> {
+    name: &'static str,
+    payload: PhantomData<fn() -> P>,
+}
+
+impl<P> TypedChannel<P> {
+    /// Panics (at compile time in a `const`) if the name is not a valid channel
+    pub const fn new(name: &'static str) -> Self {
+        let bytes = name.as_bytes();
+        assert!(!bytes.is_empty(), "channel name is empty");
+        let mut i = 0;
+        while i < bytes.len() {
+            let b = bytes[i];
+            let valid = b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'-' | b'*' | b'>');
+            assert!(valid, "channel name contains an invalid character");
+            assert!(
+                b != b'.' || (i > 0 && i + 1 < bytes.len() && bytes[i + 1] != b'.'),
+                "channel name has an empty segment"
+            );
+            i += 1;
+        }
+        Self {
+            name,
+            payload: PhantomData,
+        }
+    }
+
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn channel(&self) -> Channel {
+        Channel::new(self.name)
+    }
+}
+
+impl<P: Serialize> TypedChannel<P> {
+    /// Start a wave on this channel carrying `payload`
+    pub fn wave(&self, payload: &P) -> Result<WaveBuilder> {
+        let payload =
+            serde_json::to_value(payload).map_err(|e| AetherError::CodecError(e.to_string()))?;
+        Ok(Wave::builder(self.channel()).payload(payload))
+    }
+}
+
+impl<P: DeserializeOwned> TypedChannel<P> {
+    /// Decode a wave's payload, checking it was sent on this channel
+    pub fn decode(&self, wave: &Wave) -> Result<P> {
+        if wave.channel().name() != self.name {
+            return Err(AetherError::ValidationFailed(format!(
+                "wave on {} decoded as {}",
+                wave.channel(),
+                self.name
+            )));
+        }
+        serde_json::from_value(wave.payload().clone())
+            .map_err(|e| AetherError::CodecError(e.to_string()))
+    }
+}
+
+impl<P> Clone for TypedChannel<P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P> Copy for TypedChannel<P> {}
+
+impl<P> fmt::Debug for TypedChannel<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TypedChannel").field(&self.name).finish()
+    }
+}
+
+impl<P> fmt::Display for TypedChannel<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+impl<P> From<TypedChannel<P>> for Channel {
+    fn from(channel: TypedChannel<P>) -> Self {
+        channel.channel()
+    }
+}
+
+impl<P> From<&TypedChannel<P>> for Channel {
+    fn from(channel: &TypedChannel<P>) -> Self {
+        channel.channel()
+    }
+}
+
+/// Declare channel constants, optionally with a payload struct per channel
+///
+/// ```
+/// aether_core::channels! {
+///     /// Order accepted after the inventory check
+///     pub ORDERS_CONFIRMED = "orders.confirmed" => OrderConfirmed {
+///         pub order_id: String,
+///         pub total: f64,
+///     };
+///     pub ORDERS_ALL = "orders.*";
+/// }
+///
+/// let wave = ORDERS_CONFIRMED
+///     .wave(&OrderConfirmed { order_id: "A-1".into(), total: 9.5 })
+///     .unwrap()
+///     .build();
+/// assert_eq!(ORDERS_CONFIRMED.decode(&wave).unwrap().order_id, "A-1");
+/// assert!(wave.channel().matches(&ORDERS_ALL.channel()));
+/// ```
+#[macro_export]
+macro_rules! channels {
+    () => {};
+    (
+        $(#[$meta:meta])*
+        $vis:vis $name:ident = $channel:literal => $payload:ident {
+            $($(#[$field_meta:meta])* $field_vis:vis $field:ident : $ty:ty),* $(,)?
+        };
+        $($rest:tt)*
+    ) => {
+        $(#[$meta])*
+        $vis const $name: $crate::TypedChannel<$payload> = $crate::TypedChannel::new($channel);
+
+        #[doc = concat!("Payload of `", $channel, "`")]
+        #[derive(
+            Debug,
+            Clone,
+            PartialEq,
+            $crate::__private::serde::Serialize,
+            $crate::__private::serde::Deserialize,
+        )]
+        #[serde(crate = "aether_core::__private::serde")]
+        $vis struct $payload {
+            $($(#[$field_meta])* $field_vis $field: $ty),*
+        }
+
+        $crate::channels!($($rest)*);
+    };
+    (
+        $(#[$meta:meta])*
+        $vis:vis $name:ident = $channel:literal;
+        $($rest:tt)*
+    ) => {
+        $(#[$meta])*
+        $vis const $name: $crate::TypedChannel = $crate::TypedChannel::new($channel);
+
+        $crate::channels!($($rest)*);
+    };
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -131,7 +340,7 @@ mod tests {
         let channel = Channel::new("orders.created");
         let pattern1 = Channel::new("orders.created");
         let pattern2 = Channel::new("orders.*");
-        let pattern3 = Channel::new("*");
+        let pattern3 = Channel::new(">");
         let pattern4 = Channel::new("payments.created");
 
         assert!(channel.matches(&pattern1));
@@ -140,6 +349,49 @@ mod tests {
         assert!(!channel.matches(&pattern4));
     }
 
+    #[test]
+    fn test_channel_hierarchy_wildcards() {
+        let failed = Channel::new("orders.eu.failed");
+
+        assert!(failed.matches(&Channel::new("orders.>")));
+        assert!(failed.matches(&Channel::new("orders.*.failed")));
+        assert!(failed.matches(&Channel::new("*.*.*")));
+        assert!(!failed.matches(&Channel::new("orders.*")));
+        assert!(!failed.matches(&Channel::new("*")));
+        assert!(!Channel::new("orders").matches(&Channel::new("orders.>")));
+        assert!(!failed.matches(&Channel::new("orders.>.failed")));
+
+        assert!(Channel::new("orders.>").is_wildcard());
+        assert!(!failed.is_wildcard());
+    }
+
+    crate::channels! {
+        pub ORDERS_CREATED = "orders.created" => OrderCreated {
+            pub order_id: String,
+            #[serde(default)]
+            pub items: Vec<String>,
+        };
+        PAYMENTS = "payments.>";
+    }
+
+    #[test]
+    fn test_typed_channel_round_trip() {
+        let order = OrderCreated {
+            order_id: "A-1".to_string(),
+            items: vec!["ItemA".to_string()],
+        };
+        let wave = ORDERS_CREATED.wave(&order).unwrap().build();
+        assert_eq!(wave.channel().name(), "orders.created");
+        assert_eq!(ORDERS_CREATED.decode(&wave).unwrap(), order);
+
+        let other = Wave::new(PAYMENTS.name(), serde_json::json!({"order_id": "A-1"}));
+        assert!(matches!(
+            ORDERS_CREATED.decode(&other),
+            Err(AetherError::ValidationFailed(_))
+        ));
+        assert_eq!(Channel::from(PAYMENTS), Channel::new("payments.>"));
+    }
+
     #[test]
     fn test_channel_child() {
         let parent = Channel::new("orders");
@@ -170,4 +422,30 @@ mod tests {
         let hop2 = base.hop_at_ms(1_000, 5, 200);
         assert_eq!(hop1.name(), hop2.name());
     }
+
+    #[test]
+    fn test_channel_hop_on_virtual_clock() {
+        let clock = crate::clock::VirtualClock::at(chrono::DateTime::UNIX_EPOCH);
+        let base = Channel::new("orders");
+        assert_eq!(base.hop_on(&clock, 5, 200), base.hop_at_ms(0, 5, 200));
+
+        clock.advance(std::time::Duration::from_millis(200));
+        assert_eq!(base.hop_on(&clock, 5, 200), base.hop_at_ms(200, 5, 200));
+    }
+
+    #[test]
+    fn test_interned_channels_share_storage_and_wire_format() {
+        let first = Channel::new("orders.interned");
+        let second = Channel::new(String::from("orders.interned"));
+        assert!(Arc::ptr_eq(&first.name, &second.name));
+        assert!(Arc::ptr_eq(&first.segments, &second.segments));
+
+        let encoded = serde_json::to_value(&first).unwrap();
+        assert_eq!(
+            encoded,
+            serde_json::json!({"name": "orders.interned", "segments": ["orders", "interned"]})
+        );
+        let decoded: Channel = serde_json::from_value(encoded).unwrap();
+        assert!(Arc::ptr_eq(&decoded.name, &first.name));
+    }
 }
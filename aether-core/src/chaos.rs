@@ -0,0 +1,177 @@
+//! Chaos: seeded fault injection for the transport path.
+//!
+//! Applied inside `Aether::emit` after validation and persistence, so it
+//! affects both the in-memory and the NATS transport.
+
+use crate::{channel::Channel, wave::Wave, AetherError, Result};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Fault injection settings
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ChaosConfig {
+    /// RNG seed; the same seed yields the same fault sequence
+    pub seed: u64,
+    /// Probability that a wave is silently lost
+    pub drop_probability: f64,
+    /// Probability that a wave is delivered twice
+    pub duplicate_probability: f64,
+    /// Maximum random delay added before transmission
+    pub latency_jitter_ms: u64,
+    /// Hold this many waves and release them shuffled (0 or 1 disables)
+    pub reorder_window: usize,
+    /// Channel patterns whose emits fail as if the broker were unreachable
+    pub partitioned_channels: Vec<String>,
+}
+
+/// Runtime fault injection state shared by Aether clones
+#[derive(Debug)]
+pub struct Chaos {
+    config: ChaosConfig,
+    rng: Mutex<StdRng>,
+    partitions: Mutex<Vec<Channel>>,
+    reorder_buffer: Mutex<Vec<Wave>>,
+}
+
+impl Chaos {
+    pub fn new(config: ChaosConfig) -> Self {
+        let partitions = config
+            .partitioned_channels
+            .iter()
+            .map(Channel::new)
+            .collect();
+        Self {
+            rng: Mutex::new(StdRng::seed_from_u64(config.seed)),
+            partitions: Mutex::new(partitions),
+            reorder_buffer: Mutex::new(Vec::new()),
+            config,
+        }
+    }
+
+    /// Cut off channels matching the given patterns
+    pub fn partition(&self, patterns: &[&str]) {
+        let mut partitions = self.partitions.lock().expect("chaos lock poisoned");
+        partitions.extend(patterns.iter().map(|p| Channel::new(*p)));
+    }
+
+    /// Remove all partitions
+    pub fn heal(&self) {
+        self.partitions.lock().expect("chaos lock poisoned").clear();
+    }
+
+    /// Decide what the transport sees for this wave
+    ///
+    /// Returns the waves to transmit now: empty when dropped or held for
+    /// reordering, two copies when duplicated.
+    pub(crate) async fn apply(&self, wave: Wave) -> Result<Vec<Wave>> {
+        if self.is_partitioned(wave.channel()) {
+            metrics::counter!("aether_chaos_partitioned_total").increment(1);
+            return Err(AetherError::TransmissionFailed(format!(
+                "chaos: channel {} is partitioned",
+                wave.channel()
+            )));
+        }
+
+        let (dropped, duplicated, delay) = {
+            let mut rng = self.rng.lock().expect("chaos lock poisoned");
+            let dropped = rng.gen_bool(self.config.drop_probability.clamp(0.0, 1.0));
+            let duplicated = rng.gen_bool(self.config.duplicate_probability.clamp(0.0, 1.0));
+            let delay = if self.config.latency_jitter_ms > 0 {
+                rng.gen_range(0..=self.config.latency_jitter_ms)
+            } else {
+                0
+            };
+            (dropped, duplicated, delay)
+        };
+
+        if delay > 0 {
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+        }
+
+        if dropped {
+            metrics::counter!("aether_chaos_dropped_total").increment(1);
+            return Ok(Vec::new());
+        }
+
+        let mut waves = vec![wave];
+        if duplicated {
+            metrics::counter!("aether_chaos_duplicated_total").increment(1);
+            waves.push(waves[0].clone());
+        }
+
+        if self.config.reorder_window > 1 {
+            let mut buffer = self.reorder_buffer.lock().expect("chaos lock poisoned");
+            buffer.extend(waves);
+            if buffer.len() < self.config.reorder_window {
+                return Ok(Vec::new());
+            }
+            let mut released: Vec<Wave> = buffer.drain(..).collect();
+            released.shuffle(&mut *self.rng.lock().expect("chaos lock poisoned"));
+            return Ok(released);
+        }
+
+        Ok(waves)
+    }
+
+    fn is_partitioned(&self, channel: &Channel) -> bool {
+        self.partitions
+            .lock()
+            .expect("chaos lock poisoned")
+            .iter()
+            .any(|pattern| channel.matches(pattern))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_same_seed_same_faults() {
+        let config = ChaosConfig {
+            seed: 7,
+            drop_probability: 0.5,
+            ..ChaosConfig::default()
+        };
+        let a = Chaos::new(config.clone());
+        let b = Chaos::new(config);
+
+        for _ in 0..20 {
+            let wave = Wave::new("chaos.seed", serde_json::json!({}));
+            let left = a.apply(wave.clone()).await.unwrap().len();
+            let right = b.apply(wave).await.unwrap().len();
+            assert_eq!(left, right);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reorder_window_holds_then_releases() {
+        let chaos = Chaos::new(ChaosConfig {
+            reorder_window: 3,
+            ..ChaosConfig::default()
+        });
+
+        let wave = || Wave::new("chaos.reorder", serde_json::json!({}));
+        assert!(chaos.apply(wave()).await.unwrap().is_empty());
+        assert!(chaos.apply(wave()).await.unwrap().is_empty());
+        assert_eq!(chaos.apply(wave()).await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_partition_and_heal() {
+        let chaos = Chaos::new(ChaosConfig::default());
+        chaos.partition(&["payments.*"]);
+
+        let wave = Wave::new("payments.request", serde_json::json!({}));
+        let err = chaos.apply(wave.clone()).await.unwrap_err();
+        assert!(err.is_recoverable());
+
+        chaos.heal();
+        assert_eq!(chaos.apply(wave).await.unwrap().len(), 1);
+    }
+}
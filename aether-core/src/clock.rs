@@ -0,0 +1,102 @@
+//! Clock: source of wall-clock time for decay, hopping, and snapshots.
+//!
+//! Production uses `SystemClock`. Tests and simulations inject a
+//! `VirtualClock` and move it forward explicitly.
+
+use chrono::{DateTime, Utc};
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Time source shared by the Aether layer, waves, and channels
+pub trait Clock: Debug + Send + Sync {
+    /// Current time
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Current time in milliseconds since the Unix epoch
+    fn now_ms(&self) -> u64 {
+        self.now().timestamp_millis().max(0) as u64
+    }
+}
+
+/// Clock handle held by the Aether layer
+pub type SharedClock = Arc<dyn Clock>;
+
+/// Reads the operating system clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Manually driven clock; clones share the same time
+#[derive(Debug, Clone)]
+pub struct VirtualClock {
+    now: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl VirtualClock {
+    /// Start frozen at the current system time
+    pub fn new() -> Self {
+        Self::at(Utc::now())
+    }
+
+    /// Start frozen at a fixed time (for reproducible runs)
+    pub fn at(start: DateTime<Utc>) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(start)),
+        }
+    }
+
+    /// Move time forward
+    pub fn advance(&self, duration: Duration) {
+        let step = chrono::Duration::from_std(duration).unwrap_or(chrono::Duration::MAX);
+        let mut now = self.now.lock().expect("clock lock poisoned");
+        *now = now
+            .checked_add_signed(step)
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+    }
+
+    /// Jump to a specific time
+    pub fn set(&self, time: DateTime<Utc>) {
+        *self.now.lock().expect("clock lock poisoned") = time;
+    }
+}
+
+impl Default for VirtualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().expect("clock lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_virtual_clock_advances_only_when_told() {
+        let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let clock = VirtualClock::at(start);
+        assert_eq!(clock.now(), start);
+
+        clock.advance(Duration::from_millis(1500));
+        assert_eq!(clock.now_ms(), 1_700_000_001_500);
+    }
+
+    #[test]
+    fn test_virtual_clock_clones_share_time() {
+        let clock = VirtualClock::new();
+        let shared = clock.clone();
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(clock.now(), shared.now());
+    }
+}
@@ -0,0 +1,468 @@
+//! Cluster membership: nodes gossip heartbeats over waves and track their peers.
+//!
+//! Each node that joins emits a [`Heartbeat`] on [`MEMBERSHIP_CHANNEL`] and
+//! records everyone else's in the layer's peer table. Failure detection is
+//! timeout based: a peer goes `Suspect`, then `Dead`, as heartbeats stop, and
+//! is forgotten after `forget_after_ms`.
+
+use crate::{aether::Aether, channel::Channel, wave::Wave};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Channel every member announces itself on
+pub const MEMBERSHIP_CHANNEL: &str = "aether.cluster.membership";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClusterConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_heartbeat_interval_ms")]
+    pub heartbeat_interval_ms: u64,
+    /// Silence after which a peer is suspected
+    #[serde(default = "default_suspect_after_ms")]
+    pub suspect_after_ms: u64,
+    /// Silence after which a peer is considered dead
+    #[serde(default = "default_dead_after_ms")]
+    pub dead_after_ms: u64,
+    /// Silence after which a dead peer is dropped from the view
+    #[serde(default = "default_forget_after_ms")]
+    pub forget_after_ms: u64,
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            heartbeat_interval_ms: default_heartbeat_interval_ms(),
+            suspect_after_ms: default_suspect_after_ms(),
+            dead_after_ms: default_dead_after_ms(),
+            forget_after_ms: default_forget_after_ms(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_heartbeat_interval_ms() -> u64 {
+    1000
+}
+
+fn default_suspect_after_ms() -> u64 {
+    3000
+}
+
+fn default_dead_after_ms() -> u64 {
+    10_000
+}
+
+fn default_forget_after_ms() -> u64 {
+    60_000
+}
+
+/// What a node announces about itself
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Heartbeat {
+    /// Distinguishes replicas sharing a service name
+    pub node_id: Uuid,
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub channels: Vec<String>,
+    /// Utilisation in `0.0..=1.0`
+    #[serde(default)]
+    pub load: f64,
+    /// Sent once on graceful shutdown
+    #[serde(default)]
+    pub leaving: bool,
+}
+
+impl Heartbeat {
+    pub fn new(name: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            node_id: Uuid::new_v4(),
+            name: name.into(),
+            version: version.into(),
+            channels: Vec::new(),
+            load: 0.0,
+            leaving: false,
+        }
+    }
+
+    pub fn with_channels(mut self, channels: Vec<String>) -> Self {
+        self.channels = channels;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PeerStatus {
+    Alive,
+    Suspect,
+    Dead,
+}
+
+impl PeerStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PeerStatus::Alive => "alive",
+            PeerStatus::Suspect => "suspect",
+            PeerStatus::Dead => "dead",
+        }
+    }
+}
+
+/// A member as last heard from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Peer {
+    pub node_id: Uuid,
+    pub name: String,
+    pub version: String,
+    pub channels: Vec<String>,
+    pub load: f64,
+    pub last_seen: DateTime<Utc>,
+    pub status: PeerStatus,
+}
+
+/// Snapshot of the peer table, sorted by service name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClusterView {
+    pub peers: Vec<Peer>,
+}
+
+impl ClusterView {
+    pub fn alive(&self) -> impl Iterator<Item = &Peer> {
+        self.peers
+            .iter()
+            .filter(|peer| peer.status == PeerStatus::Alive)
+    }
+
+    /// Whether at least one instance of `service` is alive
+    pub fn is_alive(&self, service: &str) -> bool {
+        self.alive().any(|peer| peer.name == service)
+    }
+
+    /// Peers in `status`
+    pub fn count(&self, status: PeerStatus) -> usize {
+        self.peers
+            .iter()
+            .filter(|peer| peer.status == status)
+            .count()
+    }
+}
+
+/// Latest heartbeat per node, shared by clones of a layer
+#[derive(Debug)]
+pub(crate) struct PeerTable {
+    peers: RwLock<HashMap<Uuid, (Heartbeat, DateTime<Utc>)>>,
+    /// Nodes that left, so heartbeats still in flight don't bring them back
+    departed: RwLock<HashMap<Uuid, DateTime<Utc>>>,
+    config: RwLock<ClusterConfig>,
+}
+
+impl PeerTable {
+    pub(crate) fn new() -> Self {
+        Self {
+            peers: RwLock::new(HashMap::new()),
+            departed: RwLock::new(HashMap::new()),
+            config: RwLock::new(ClusterConfig::default()),
+        }
+    }
+
+    fn configure(&self, config: &ClusterConfig) {
+        *self.config.write().expect("cluster config lock poisoned") = config.clone();
+    }
+
+    /// Record a heartbeat; returns true for a node not seen before
+    fn observe(&self, heartbeat: Heartbeat, now: DateTime<Utc>) -> bool {
+        let mut departed = self.departed.write().expect("peer table lock poisoned");
+        let mut peers = self.peers.write().expect("peer table lock poisoned");
+        if heartbeat.leaving {
+            peers.remove(&heartbeat.node_id);
+            departed.insert(heartbeat.node_id, now);
+            return false;
+        }
+        if departed.contains_key(&heartbeat.node_id) {
+            return false;
+        }
+        peers.insert(heartbeat.node_id, (heartbeat, now)).is_none()
+    }
+
+    pub(crate) fn view(&self, now: DateTime<Utc>) -> ClusterView {
+        let config = self
+            .config
+            .read()
+            .expect("cluster config lock poisoned")
+            .clone();
+        let forgotten =
+            |last_seen: &DateTime<Utc>| silence_ms(now, *last_seen) >= config.forget_after_ms;
+        self.departed
+            .write()
+            .expect("peer table lock poisoned")
+            .retain(|_, left_at| !forgotten(left_at));
+        let mut peers = self.peers.write().expect("peer table lock poisoned");
+        peers.retain(|_, (_, last_seen)| !forgotten(last_seen));
+        let mut view: Vec<Peer> = peers
+            .values()
+            .map(|(heartbeat, last_seen)| {
+                let silence = silence_ms(now, *last_seen);
+                let status = if silence >= config.dead_after_ms {
+                    PeerStatus::Dead
+                } else if silence >= config.suspect_after_ms {
+                    PeerStatus::Suspect
+                } else {
+                    PeerStatus::Alive
+                };
+                Peer {
+                    node_id: heartbeat.node_id,
+                    name: heartbeat.name.clone(),
+                    version: heartbeat.version.clone(),
+                    channels: heartbeat.channels.clone(),
+                    load: heartbeat.load,
+                    last_seen: *last_seen,
+                    status,
+                }
+            })
+            .collect();
+        view.sort_by(|a, b| a.name.cmp(&b.name).then(a.node_id.cmp(&b.node_id)));
+        ClusterView { peers: view }
+    }
+}
+
+fn silence_ms(now: DateTime<Utc>, last_seen: DateTime<Utc>) -> u64 {
+    (now - last_seen).num_milliseconds().max(0) as u64
+}
+
+type LoadFn = Arc<dyn Fn() -> f64 + Send + Sync>;
+
+struct LocalNode {
+    heartbeat: Mutex<Heartbeat>,
+    load: Mutex<Option<LoadFn>>,
+}
+
+impl LocalNode {
+    fn heartbeat(&self) -> Heartbeat {
+        let load = self.load.lock().expect("load lock poisoned").clone();
+        let mut heartbeat = self
+            .heartbeat
+            .lock()
+            .expect("heartbeat lock poisoned")
+            .clone();
+        if let Some(load) = load {
+            heartbeat.load = load().clamp(0.0, 1.0);
+        }
+        heartbeat
+    }
+}
+
+/// This node's membership; dropping it stops the heartbeats
+pub struct ClusterMembership {
+    aether: Aether,
+    local: Arc<LocalNode>,
+    task: JoinHandle<()>,
+}
+
+impl ClusterMembership {
+    pub fn node_id(&self) -> Uuid {
+        self.local
+            .heartbeat
+            .lock()
+            .expect("heartbeat lock poisoned")
+            .node_id
+    }
+
+    /// Channels announced from the next heartbeat on
+    pub fn set_channels(&self, channels: Vec<String>) {
+        self.local
+            .heartbeat
+            .lock()
+            .expect("heartbeat lock poisoned")
+            .channels = channels;
+    }
+
+    /// Sample the node's load for every heartbeat
+    pub fn with_load(self, load: impl Fn() -> f64 + Send + Sync + 'static) -> Self {
+        *self.local.load.lock().expect("load lock poisoned") = Some(Arc::new(load));
+        self
+    }
+
+    /// Tell peers this node is going away instead of letting it time out
+    pub async fn leave(self) {
+        self.task.abort();
+        let mut heartbeat = self.local.heartbeat();
+        heartbeat.leaving = true;
+        let now = self.aether.clock().now();
+        self.aether.cluster_table().observe(heartbeat.clone(), now);
+        if let Err(err) = emit_heartbeat(&self.aether, &heartbeat).await {
+            warn!("Failed to announce cluster leave: {}", err);
+        }
+    }
+}
+
+impl Drop for ClusterMembership {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn emit_heartbeat(aether: &Aether, heartbeat: &Heartbeat) -> crate::Result<()> {
+    let payload = serde_json::to_value(heartbeat)
+        .map_err(|e| crate::AetherError::CodecError(e.to_string()))?;
+    let mut wave = Wave::builder(MEMBERSHIP_CHANNEL)
+        .payload(payload)
+        .source(heartbeat.name.as_str())
+        .timestamp(aether.clock().now())
+        .build();
+    if let Some(token) = &aether.config().auth_token {
+        wave.set_auth_token(token.clone());
+    }
+    aether.emit(wave).await?;
+    Ok(())
+}
+
+impl Aether {
+    /// Announce `node` on the membership channel and track peers until the
+    /// returned membership is dropped
+    pub async fn join_cluster(&self, node: Heartbeat, config: &ClusterConfig) -> ClusterMembership {
+        self.cluster_table().configure(config);
+        let mut heartbeats = self.subscribe(&Channel::new(MEMBERSHIP_CHANNEL)).await;
+        let local = Arc::new(LocalNode {
+            heartbeat: Mutex::new(node),
+            load: Mutex::new(None),
+        });
+        let aether = self.clone();
+        let announcing = Arc::clone(&local);
+        let period = std::time::Duration::from_millis(config.heartbeat_interval_ms.max(1));
+        let task = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {
+                        if let Err(err) = emit_heartbeat(&aether, &announcing.heartbeat()).await {
+                            debug!("Heartbeat not sent: {}", err);
+                        }
+                        let view = aether.cluster_view();
+                        for status in [PeerStatus::Alive, PeerStatus::Suspect, PeerStatus::Dead] {
+                            metrics::gauge!("aether_cluster_peers", "status" => status.as_str())
+                                .set(view.count(status) as f64);
+                        }
+                    }
+                    received = heartbeats.recv() => match received {
+                        Ok(wave) => aether.observe_heartbeat(&wave),
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                }
+            }
+        });
+        ClusterMembership {
+            aether: self.clone(),
+            local,
+            task,
+        }
+    }
+
+    /// Members heard from recently, including this node once it has joined
+    pub fn cluster_view(&self) -> ClusterView {
+        self.cluster_table().view(self.clock().now())
+    }
+
+    fn observe_heartbeat(&self, wave: &Wave) {
+        let heartbeat = match serde_json::from_value::<Heartbeat>(wave.payload().clone()) {
+            Ok(heartbeat) => heartbeat,
+            Err(err) => {
+                debug!("Ignoring malformed heartbeat: {}", err);
+                return;
+            }
+        };
+        let (name, leaving) = (heartbeat.name.clone(), heartbeat.leaving);
+        if self.cluster_table().observe(heartbeat, self.clock().now()) {
+            info!("🤝 {} joined the cluster", name);
+        } else if leaving {
+            info!("👋 {} left the cluster", name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aether::AetherConfig;
+    use crate::clock::VirtualClock;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_members_discover_and_expire_peers() {
+        let clock = Arc::new(VirtualClock::new());
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            ..AetherConfig::default()
+        })
+        .with_clock(clock.clone());
+        let config = ClusterConfig {
+            heartbeat_interval_ms: 10,
+            ..ClusterConfig::default()
+        };
+        let beta =
+            Heartbeat::new("service-beta", "2.0.0").with_channels(vec!["inventory.*".into()]);
+        let beta = aether.join_cluster(beta, &config).await.with_load(|| 0.25);
+        let gateway = aether
+            .join_cluster(Heartbeat::new("aether-gateway", "1.0.0"), &config)
+            .await;
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let view = aether.cluster_view();
+        assert!(view.is_alive("service-beta") && view.is_alive("aether-gateway"));
+        let peer = view
+            .peers
+            .iter()
+            .find(|p| p.name == "service-beta")
+            .unwrap();
+        assert_eq!((peer.version.as_str(), peer.load), ("2.0.0", 0.25));
+        assert_eq!(peer.channels, ["inventory.*"]);
+
+        gateway.leave().await;
+        assert!(!aether.cluster_view().is_alive("aether-gateway"));
+
+        // Stopped without leaving: suspected, then dead, then forgotten
+        let beta_id = beta.node_id();
+        drop(beta);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        clock.advance(Duration::from_secs(5));
+        let view = aether.cluster_view();
+        assert_eq!(view.peers.len(), 1);
+        assert_eq!(view.peers[0].node_id, beta_id);
+        assert_eq!(view.peers[0].status, PeerStatus::Suspect);
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(aether.cluster_view().count(PeerStatus::Dead), 1);
+        clock.advance(Duration::from_secs(60));
+        assert!(aether.cluster_view().peers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_heartbeats_carry_the_layer_token() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            auth_token: Some("secret".into()),
+            ..AetherConfig::default()
+        });
+        let config = ClusterConfig {
+            heartbeat_interval_ms: 10,
+            ..ClusterConfig::default()
+        };
+        let _member = aether
+            .join_cluster(Heartbeat::new("service-beta", "2.0.0"), &config)
+            .await;
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(aether.cluster_view().is_alive("service-beta"));
+    }
+}
@@ -0,0 +1,395 @@
+//! Debounce, throttle and duplicate suppression: thin out bursts of waves
+//! before they are handled.
+//!
+//! All wrap a [`Vibrator`] and only change what `receive` returns. Debouncing
+//! holds the latest wave per channel and key until the key has been quiet for
+//! an interval (or, with a max wait, until it has been held that long);
+//! throttling passes the first wave on a channel straight through
+//! and then at most one (the latest) per interval. Duplicate suppression
+//! holds each wave for a phase window and merges identical payloads that
+//! interfere constructively into it.
+
+use crate::{
+    dispatcher::PartitionKey,
+    physics::{Interference, PhysicsEngine},
+    vibrator::Vibrator,
+    wave::Wave,
+};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Metadata key counting the duplicates merged into a delivered wave
+pub const DUPLICATES_KEY: &str = "duplicates";
+
+#[derive(Debug)]
+enum Mode {
+    Debounce(PartitionKey),
+    Throttle,
+    Interference,
+}
+
+impl Mode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Mode::Debounce(_) => "debounce",
+            Mode::Throttle => "throttle",
+            Mode::Interference => "interference",
+        }
+    }
+}
+
+/// Held wave, due at `due`; `seq` breaks ties in arrival order
+#[derive(Debug)]
+struct Pending {
+    wave: Wave,
+    due: Instant,
+    /// When the first wave of this slot was held
+    since: Instant,
+    seq: u64,
+}
+
+/// A vibrator whose `receive` debounces or throttles
+pub struct Coalesced {
+    vibrator: Vibrator,
+    interval: Duration,
+    mode: Mode,
+    max_wait: Option<Duration>,
+    /// Keyed by channel and the mode's key within it
+    pending: HashMap<(String, String), Pending>,
+    /// Last delivery per channel (throttle only)
+    last_sent: HashMap<String, Instant>,
+    seq: u64,
+}
+
+impl Vibrator {
+    /// Deliver only the latest wave per channel and key once the key has
+    /// been quiet for `interval`; waves without a key pass straight through
+    pub fn debounce(self, interval: Duration, key: PartitionKey) -> Coalesced {
+        Coalesced::new(self, interval, Mode::Debounce(key))
+    }
+
+    /// Deliver at most one wave per channel per `interval`, keeping the latest
+    /// of those that arrive in between
+    pub fn throttle(self, interval: Duration) -> Coalesced {
+        Coalesced::new(self, interval, Mode::Throttle)
+    }
+
+    /// Hold each wave for `window` and fold in waves on the same channel with
+    /// the same payload that interfere constructively with it
+    ///
+    /// The delivered wave carries the combined amplitude and the number of
+    /// merged duplicates (see [`Wave::duplicates`]). Same payloads from a
+    /// different hop interfere destructively and are delivered on their own.
+    pub fn suppress_duplicates(self, window: Duration) -> Coalesced {
+        Coalesced::new(self, window, Mode::Interference)
+    }
+}
+
+impl Wave {
+    /// Duplicates merged into this wave by duplicate suppression
+    pub fn duplicates(&self) -> u64 {
+        self.metadata()
+            .get(DUPLICATES_KEY)
+            .and_then(serde_json::Value::as_u64)
+            .unwrap_or(0)
+    }
+}
+
+/// Hash of the payload
+fn payload_key(wave: &Wave) -> String {
+    let mut hasher = DefaultHasher::new();
+    match wave.payload_bytes() {
+        Some(bytes) => bytes.hash(&mut hasher),
+        None => wave.payload().to_string().hash(&mut hasher),
+    }
+    format!("{:016x}", hasher.finish())
+}
+
+impl Coalesced {
+    fn new(vibrator: Vibrator, interval: Duration, mode: Mode) -> Self {
+        Self {
+            vibrator,
+            interval,
+            mode,
+            max_wait: None,
+            pending: HashMap::new(),
+            last_sent: HashMap::new(),
+            seq: 0,
+        }
+    }
+
+    /// Deliver a debounced key once it has been held this long, even if
+    /// waves keep arriving for it (default: no limit)
+    pub fn with_max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = Some(max_wait);
+        self
+    }
+
+    pub fn vibrator(&self) -> &Vibrator {
+        &self.vibrator
+    }
+
+    pub fn vibrator_mut(&mut self) -> &mut Vibrator {
+        &mut self.vibrator
+    }
+
+    /// Unwrap the vibrator; held waves are dropped
+    pub fn into_inner(self) -> Vibrator {
+        self.vibrator
+    }
+
+    /// Next wave to handle; held waves are released once the vibrator has no
+    /// more channels
+    pub async fn receive(&mut self) -> Option<Wave> {
+        loop {
+            let now = Instant::now();
+            if let Some(wave) = self.take_due(now) {
+                return Some(wave);
+            }
+            let next_due = self.pending.values().map(|pending| pending.due).min();
+
+            tokio::select! {
+                wave = self.vibrator.receive() => match wave {
+                    Some(wave) => {
+                        if let Some(wave) = self.hold(wave, Instant::now()) {
+                            return Some(wave);
+                        }
+                    }
+                    None => return self.take_due(Instant::now() + self.interval),
+                },
+                _ = tokio::time::sleep_until(next_due.unwrap_or(now)), if next_due.is_some() => {}
+            }
+        }
+    }
+
+    /// Hold `wave`, or return it if it may be delivered right away
+    fn hold(&mut self, wave: Wave, now: Instant) -> Option<Wave> {
+        let channel = wave.channel().name().to_string();
+        let (key, due) = match &self.mode {
+            Mode::Debounce(key) => {
+                let key = (channel, key.extract(&wave)?);
+                let quiet = now + self.interval;
+                let due = match (self.max_wait, self.pending.get(&key)) {
+                    (Some(max_wait), Some(pending)) => quiet.min(pending.since + max_wait),
+                    (Some(max_wait), None) => quiet.min(now + max_wait),
+                    (None, _) => quiet,
+                };
+                (key, due)
+            }
+            Mode::Throttle => {
+                let key = (channel, String::new());
+                match self.last_sent.get(&key.0) {
+                    Some(last) if now < *last + self.interval => {
+                        let due = *last + self.interval;
+                        (key, due)
+                    }
+                    _ if !self.pending.contains_key(&key) => {
+                        self.last_sent.insert(key.0, now);
+                        return Some(wave);
+                    }
+                    _ => (key, now),
+                }
+            }
+            Mode::Interference => {
+                let key = (channel, payload_key(&wave));
+                if let Some(pending) = self.pending.get_mut(&key) {
+                    let Interference::Constructive { amplitude } =
+                        PhysicsEngine::calculate_interference(&pending.wave, &wave)
+                    else {
+                        return Some(wave);
+                    };
+                    pending.wave.raise_amplitude(amplitude.value());
+                    let metadata = pending.wave.metadata_mut();
+                    if !metadata.is_object() {
+                        *metadata = serde_json::json!({});
+                    }
+                    let duplicates = metadata[DUPLICATES_KEY].as_u64().unwrap_or(0) + 1;
+                    metadata[DUPLICATES_KEY] = duplicates.into();
+                    metrics::counter!("aether_coalesced_waves_total", "mode" => self.mode.as_str())
+                        .increment(1);
+                    return None;
+                }
+                (key, now + self.interval)
+            }
+        };
+        self.seq += 1;
+        let since = self.pending.get(&key).map_or(now, |pending| pending.since);
+        let replaced = self.pending.insert(
+            key,
+            Pending {
+                wave,
+                due,
+                since,
+                seq: self.seq,
+            },
+        );
+        if replaced.is_some() {
+            metrics::counter!("aether_coalesced_waves_total", "mode" => self.mode.as_str())
+                .increment(1);
+        }
+        None
+    }
+
+    /// Remove the earliest held wave due by `now`
+    fn take_due(&mut self, now: Instant) -> Option<Wave> {
+        let key = self
+            .pending
+            .iter()
+            .filter(|(_, pending)| pending.due <= now)
+            .min_by_key(|(_, pending)| (pending.due, pending.seq))
+            .map(|(key, _)| key.clone())?;
+        let pending = self.pending.remove(&key)?;
+        if let Mode::Throttle = self.mode {
+            self.last_sent.insert(key.0, Instant::now());
+        }
+        Some(pending.wave)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aether::{Aether, AetherConfig};
+    use crate::channel::Channel;
+    use crate::vibrator::VibratorConfig;
+
+    async fn vibrators(channel: &str) -> (Vibrator, Vibrator) {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            ..AetherConfig::default()
+        });
+        let receiver = Vibrator::new(
+            VibratorConfig::new("receiver").with_channels(vec![Channel::new(channel)]),
+            &aether,
+        )
+        .await
+        .unwrap();
+        (receiver, Vibrator::create("sender", &aether).await.unwrap())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_debounce_keeps_latest_wave_per_key() {
+        let (receiver, sender) = vibrators("inventory.level").await;
+        let mut levels = receiver.debounce(Duration::from_millis(100), PartitionKey::field("sku"));
+        for (sku, level) in [("a", 1), ("b", 7), ("a", 2), ("a", 3)] {
+            sender
+                .emit_wave(
+                    Channel::new("inventory.level"),
+                    serde_json::json!({ "sku": sku, "level": level }),
+                )
+                .await
+                .unwrap();
+        }
+
+        let started = Instant::now();
+        let first = levels.receive().await.unwrap();
+        let second = levels.receive().await.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        assert_eq!(first.payload()["sku"], "b");
+        assert_eq!(second.payload()["level"], 3);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(500), levels.receive())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_debounce_keys_by_channel_and_caps_wait() {
+        let (receiver, sender) = vibrators("inventory.*").await;
+        let mut levels = receiver
+            .debounce(Duration::from_millis(100), PartitionKey::field("sku"))
+            .with_max_wait(Duration::from_millis(250));
+        for channel in ["inventory.level", "inventory.reserved"] {
+            sender
+                .emit_wave(
+                    Channel::new(channel),
+                    serde_json::json!({ "sku": "a", "level": 1 }),
+                )
+                .await
+                .unwrap();
+        }
+        let first = levels.receive().await.unwrap();
+        let second = levels.receive().await.unwrap();
+        assert_eq!(first.channel().name(), "inventory.level");
+        assert_eq!(second.channel().name(), "inventory.reserved");
+
+        // A key updated more often than the quiet interval is still delivered
+        let started = Instant::now();
+        let updates = tokio::spawn(async move {
+            for level in 0..10 {
+                sender
+                    .emit_wave(
+                        Channel::new("inventory.level"),
+                        serde_json::json!({ "sku": "a", "level": level }),
+                    )
+                    .await
+                    .unwrap();
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        });
+        let capped = levels.receive().await.unwrap();
+        let waited = started.elapsed();
+        assert!(waited >= Duration::from_millis(250) && waited < Duration::from_millis(300));
+        assert!(capped.payload()["level"].as_u64().unwrap() < 9);
+        updates.abort();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_throttle_passes_first_then_latest_per_interval() {
+        let (receiver, sender) = vibrators("config.*").await;
+        let mut changes = receiver.throttle(Duration::from_millis(100));
+        for version in 1..=3 {
+            sender
+                .emit_wave(
+                    Channel::new("config.changed"),
+                    serde_json::json!({ "version": version }),
+                )
+                .await
+                .unwrap();
+        }
+
+        let started = Instant::now();
+        assert_eq!(changes.receive().await.unwrap().payload()["version"], 1);
+        assert!(started.elapsed() < Duration::from_millis(100));
+        assert_eq!(changes.receive().await.unwrap().payload()["version"], 3);
+        assert!(started.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_suppress_duplicates_merges_constructive_retries() {
+        let (receiver, sender) = vibrators("payments.*").await;
+        let mut payments = receiver.suppress_duplicates(Duration::from_millis(100));
+        let wave = |id: u32| {
+            Wave::builder("payments.captured")
+                .payload(serde_json::json!({ "id": id }))
+                .amplitude(0.3)
+                .build()
+        };
+        for id in [1, 1, 2, 1] {
+            sender.emit(wave(id)).await.unwrap();
+        }
+        // Same payload one hop further along: out of phase, not merged
+        let mut relayed = wave(1);
+        relayed.propagate();
+        sender.emit(relayed).await.unwrap();
+
+        let started = Instant::now();
+        let out_of_phase = payments.receive().await.unwrap();
+        assert!(started.elapsed() < Duration::from_millis(100));
+        assert_eq!(out_of_phase.propagation_count(), 2);
+        assert_eq!(out_of_phase.duplicates(), 0);
+
+        let merged = payments.receive().await.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        assert_eq!(merged.payload()["id"], 1);
+        assert_eq!(merged.duplicates(), 2);
+        assert!((merged.amplitude().value() - 3.0 * 0.3 * 0.95).abs() < 1e-9);
+        let single = payments.receive().await.unwrap();
+        assert_eq!(single.payload()["id"], 2);
+        assert_eq!(single.duplicates(), 0);
+    }
+}
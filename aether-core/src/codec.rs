@@ -0,0 +1,92 @@
+//! Codec: canonical wire encodings for waves.
+
+use crate::{wave::Wave, AetherError, Result};
+use bytes::{BufMut, BytesMut};
+use serde::{Deserialize, Serialize};
+
+/// Wire encoding for a wave
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WaveCodec {
+    /// UTF-8 JSON (NATS and persistence default)
+    #[default]
+    Json,
+    /// MessagePack with named fields
+    MessagePack,
+}
+
+impl WaveCodec {
+    pub fn encode(&self, wave: &Wave) -> Result<Vec<u8>> {
+        match self {
+            WaveCodec::Json => {
+                serde_json::to_vec(wave).map_err(|e| AetherError::CodecError(e.to_string()))
+            }
+            WaveCodec::MessagePack => {
+                rmp_serde::to_vec_named(wave).map_err(|e| AetherError::CodecError(e.to_string()))
+            }
+        }
+    }
+
+    /// Append the encoding to a buffer, reusing its spare capacity
+    pub fn encode_into(&self, wave: &Wave, buffer: &mut BytesMut) -> Result<()> {
+        let mut writer = buffer.writer();
+        match self {
+            WaveCodec::Json => serde_json::to_writer(&mut writer, wave)
+                .map_err(|e| AetherError::CodecError(e.to_string())),
+            WaveCodec::MessagePack => rmp_serde::encode::write_named(&mut writer, wave)
+                .map_err(|e| AetherError::CodecError(e.to_string())),
+        }
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> Result<Wave> {
+        match self {
+            WaveCodec::Json => {
+                serde_json::from_slice(bytes).map_err(|e| AetherError::CodecError(e.to_string()))
+            }
+            WaveCodec::MessagePack => {
+                rmp_serde::from_slice(bytes).map_err(|e| AetherError::CodecError(e.to_string()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_fields_survive_codec_hop() {
+        let mut json: serde_json::Value =
+            serde_json::to_value(Wave::new("codec.future", serde_json::json!({}))).unwrap();
+        json["trace_flags"] = serde_json::json!({"sampled": true});
+
+        let wave = WaveCodec::Json
+            .decode(&serde_json::to_vec(&json).unwrap())
+            .unwrap();
+        let packed = WaveCodec::MessagePack.encode(&wave).unwrap();
+        let unpacked = WaveCodec::MessagePack.decode(&packed).unwrap();
+        let reencoded: serde_json::Value =
+            serde_json::from_slice(&WaveCodec::Json.encode(&unpacked).unwrap()).unwrap();
+
+        assert_eq!(reencoded["trace_flags"]["sampled"], true);
+    }
+
+    #[test]
+    fn test_decode_rejects_garbage() {
+        let err = WaveCodec::Json.decode(b"not a wave").unwrap_err();
+        assert!(matches!(err, AetherError::CodecError(_)));
+    }
+
+    #[test]
+    fn test_encode_into_matches_encode() {
+        let wave = Wave::new("codec.pooled", serde_json::json!({"n": 1}));
+        let mut buffer = BytesMut::new();
+        for codec in [WaveCodec::Json, WaveCodec::MessagePack] {
+            codec.encode_into(&wave, &mut buffer).unwrap();
+            assert_eq!(
+                buffer.split().as_ref(),
+                codec.encode(&wave).unwrap().as_slice()
+            );
+        }
+    }
+}
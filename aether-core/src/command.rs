@@ -0,0 +1,364 @@
+//! Commands: a `Command` wave answered by a `Response` wave carrying its outcome.
+//!
+//! Senders use [`VibratorEmitter::send_command`](crate::VibratorEmitter::send_command),
+//! or [`VibratorEmitter::scatter_gather`](crate::VibratorEmitter::scatter_gather) to
+//! ask every responder on a channel; receivers implement [`CommandHandler`] and
+//! answer through [`VibratorEmitter::handle_command`](crate::VibratorEmitter::handle_command).
+
+use crate::{
+    channel::Channel,
+    wave::{Wave, WaveType},
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// How long `send_command` waits for a response
+pub const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Metadata key naming the channel a response should go to
+pub const REPLY_TO: &str = "reply_to";
+
+/// Channel prefix for command responses
+pub const COMMAND_REPLY_PREFIX: &str = "aether.reply";
+
+/// Channel a sender listens on for responses to its commands
+pub fn command_reply_channel(sender: &str) -> Channel {
+    Channel::new(format!("{}.{}", COMMAND_REPLY_PREFIX, sender))
+}
+
+/// What became of a command
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandOutcome {
+    /// Executed; carries the handler's result
+    Accepted(serde_json::Value),
+    /// Refused by the handler, with its reason
+    Rejected(String),
+    /// No response before the deadline
+    TimedOut,
+}
+
+impl CommandOutcome {
+    pub fn is_accepted(&self) -> bool {
+        matches!(self, CommandOutcome::Accepted(_))
+    }
+
+    /// Metric label
+    pub fn label(&self) -> &'static str {
+        match self {
+            CommandOutcome::Accepted(_) => "accepted",
+            CommandOutcome::Rejected(_) => "rejected",
+            CommandOutcome::TimedOut => "timed_out",
+        }
+    }
+}
+
+/// When a scatter-gather query stops collecting responses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GatherPolicy {
+    /// Return as soon as `n` responders answered
+    FirstN(usize),
+    /// Collect until the timeout; at least `n` responders must answer
+    Quorum(usize),
+}
+
+impl GatherPolicy {
+    pub fn min_responses(&self) -> usize {
+        match self {
+            GatherPolicy::FirstN(n) | GatherPolicy::Quorum(n) => *n,
+        }
+    }
+}
+
+/// Response payload for a command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandReply {
+    /// ID of the command wave this responds to
+    pub command_id: Uuid,
+    pub responder: String,
+    pub accepted: bool,
+    /// Result when accepted, reason when rejected
+    #[serde(default)]
+    pub detail: serde_json::Value,
+}
+
+impl CommandReply {
+    pub(crate) fn new(command: &Wave, responder: &str, outcome: &CommandOutcome) -> Self {
+        let (accepted, detail) = match outcome {
+            CommandOutcome::Accepted(result) => (true, result.clone()),
+            CommandOutcome::Rejected(reason) => (false, reason.as_str().into()),
+            CommandOutcome::TimedOut => (false, "timed out".into()),
+        };
+        Self {
+            command_id: *command.id(),
+            responder: responder.to_string(),
+            accepted,
+            detail,
+        }
+    }
+
+    pub fn outcome(self) -> CommandOutcome {
+        if self.accepted {
+            CommandOutcome::Accepted(self.detail)
+        } else {
+            let reason = match self.detail {
+                serde_json::Value::String(reason) => reason,
+                other => other.to_string(),
+            };
+            CommandOutcome::Rejected(reason)
+        }
+    }
+}
+
+/// Receiving side of a command (or scatter-gather query) channel
+#[async_trait]
+pub trait CommandHandler: Send + Sync {
+    /// `Ok(result)` accepts the command, `Err(reason)` rejects it
+    async fn execute(&self, command: &Wave) -> std::result::Result<serde_json::Value, String>;
+}
+
+/// Where the response to `command` goes: its `reply_to`, else `<channel>.reply`
+pub(crate) fn reply_channel(command: &Wave) -> Channel {
+    command
+        .metadata()
+        .get(REPLY_TO)
+        .and_then(|v| v.as_str())
+        .map(Channel::new)
+        .unwrap_or_else(|| command.channel().child("reply"))
+}
+
+/// Wait for the response to `command_id`, ignoring other traffic on the channel
+pub(crate) async fn await_reply(
+    replies: &mut broadcast::Receiver<Wave>,
+    command_id: Uuid,
+    timeout: Duration,
+) -> CommandOutcome {
+    let wait = async {
+        loop {
+            let wave = match replies.recv().await {
+                Ok(wave) => wave,
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return CommandOutcome::TimedOut,
+            };
+            if wave.wave_type() != &WaveType::Response {
+                continue;
+            }
+            match serde_json::from_value::<CommandReply>(wave.payload().clone()) {
+                Ok(reply) if reply.command_id == command_id => return reply.outcome(),
+                _ => continue,
+            }
+        }
+    };
+    tokio::time::timeout(timeout, wait)
+        .await
+        .unwrap_or(CommandOutcome::TimedOut)
+}
+
+/// Collect responses to `request_id` until `policy` is met or the timeout passes
+///
+/// Only the first reply from each source counts, so a responder answering
+/// twice cannot make up a quorum on its own.
+pub(crate) async fn gather_replies(
+    replies: &mut broadcast::Receiver<Wave>,
+    request_id: Uuid,
+    policy: GatherPolicy,
+    timeout: Duration,
+) -> Vec<CommandReply> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut gathered = Vec::new();
+    let mut responders = HashSet::new();
+    loop {
+        if let GatherPolicy::FirstN(n) = policy {
+            if gathered.len() >= n {
+                break;
+            }
+        }
+        let wave = match tokio::time::timeout_at(deadline, replies.recv()).await {
+            Err(_) | Ok(Err(broadcast::error::RecvError::Closed)) => break,
+            Ok(Err(broadcast::error::RecvError::Lagged(_))) => continue,
+            Ok(Ok(wave)) => wave,
+        };
+        if wave.wave_type() != &WaveType::Response {
+            continue;
+        }
+        match serde_json::from_value::<CommandReply>(wave.payload().clone()) {
+            Ok(reply) if reply.command_id == request_id => {
+                let responder = wave.source().unwrap_or(&reply.responder).to_string();
+                if responders.insert(responder) {
+                    gathered.push(reply);
+                }
+            }
+            _ => continue,
+        }
+    }
+    gathered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aether::{Aether, AetherConfig};
+    use crate::vibrator::Vibrator;
+    use crate::AetherError;
+
+    struct Payments;
+
+    #[async_trait]
+    impl CommandHandler for Payments {
+        async fn execute(&self, command: &Wave) -> std::result::Result<serde_json::Value, String> {
+            match command.payload()["amount"].as_f64() {
+                Some(amount) if amount > 0.0 => Ok(serde_json::json!({ "charged": amount })),
+                _ => Err("invalid amount".to_string()),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_command_outcomes_round_trip() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            ..AetherConfig::default()
+        });
+        let mut payments = Vibrator::create("service-payments", &aether).await.unwrap();
+        payments.resonate_on(Channel::new("payments.request")).await;
+        let responder = payments.emitter();
+        tokio::spawn(async move {
+            while let Some(wave) = payments.receive().await {
+                responder.handle_command(&wave, &Payments).await.unwrap();
+            }
+        });
+
+        let orders = Vibrator::create("service-orders", &aether).await.unwrap();
+        let accepted = orders
+            .send_command("payments.request", serde_json::json!({ "amount": 15.0 }))
+            .await
+            .unwrap();
+        assert_eq!(
+            accepted,
+            CommandOutcome::Accepted(serde_json::json!({ "charged": 15.0 }))
+        );
+        let rejected = orders
+            .send_command("payments.request", serde_json::json!({ "amount": 0 }))
+            .await
+            .unwrap();
+        assert_eq!(rejected, CommandOutcome::Rejected("invalid amount".into()));
+
+        let unanswered = orders
+            .emitter()
+            .send_command_with_timeout(
+                "refunds.request",
+                serde_json::json!({}),
+                Duration::from_millis(50),
+            )
+            .await
+            .unwrap();
+        assert_eq!(unanswered, CommandOutcome::TimedOut);
+
+        let event = Wave::new("payments.request", serde_json::json!({ "amount": 1 }));
+        assert!(orders
+            .emitter()
+            .handle_command(&event, &Payments)
+            .await
+            .is_err());
+    }
+
+    struct Shard(i64);
+
+    #[async_trait]
+    impl CommandHandler for Shard {
+        async fn execute(&self, _query: &Wave) -> std::result::Result<serde_json::Value, String> {
+            Ok(serde_json::json!({ "stock": self.0 }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scatter_gather_policies() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            ..AetherConfig::default()
+        });
+        for (name, stock) in [("inventory-shard-1", 10), ("inventory-shard-2", 5)] {
+            let mut shard = Vibrator::create(name, &aether).await.unwrap();
+            shard.resonate_on(Channel::new("inventory.stock")).await;
+            let responder = shard.emitter();
+            tokio::spawn(async move {
+                while let Some(wave) = shard.receive().await {
+                    responder
+                        .handle_command(&wave, &Shard(stock))
+                        .await
+                        .unwrap();
+                }
+            });
+        }
+        let gateway = Vibrator::create("aether-gateway", &aether)
+            .await
+            .unwrap()
+            .emitter();
+        let timeout = Duration::from_millis(200);
+
+        let all = gateway
+            .scatter_gather("inventory.stock", serde_json::json!({}), 2, timeout)
+            .await
+            .unwrap();
+        let total: i64 = all
+            .iter()
+            .map(|r| r.detail["stock"].as_i64().unwrap())
+            .sum();
+        assert_eq!(total, 15);
+
+        let first = gateway
+            .scatter_gather_with(
+                "inventory.stock",
+                serde_json::json!({}),
+                GatherPolicy::FirstN(1),
+                timeout,
+            )
+            .await
+            .unwrap();
+        assert_eq!(first.len(), 1);
+
+        let err = gateway
+            .scatter_gather("inventory.stock", serde_json::json!({}), 3, timeout)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AetherError::QuorumNotReached(_)), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_scatter_gather_counts_each_responder_once() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            ..AetherConfig::default()
+        });
+        let mut shard = Vibrator::create("inventory-shard-1", &aether)
+            .await
+            .unwrap();
+        shard.resonate_on(Channel::new("inventory.stock")).await;
+        let responder = shard.emitter();
+        tokio::spawn(async move {
+            while let Some(wave) = shard.receive().await {
+                for _ in 0..2 {
+                    responder.handle_command(&wave, &Shard(10)).await.unwrap();
+                }
+            }
+        });
+        let gateway = Vibrator::create("aether-gateway", &aether)
+            .await
+            .unwrap()
+            .emitter();
+
+        let err = gateway
+            .scatter_gather(
+                "inventory.stock",
+                serde_json::json!({}),
+                2,
+                Duration::from_millis(200),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AetherError::QuorumNotReached(_)), "{}", err);
+    }
+}
@@ -1,6 +1,34 @@
 //! Configuration management for Aether services
 
-use crate::aether::AetherConfig;
+use crate::aether::{AetherConfig, NamespaceBridge, PropagationLimitPolicy};
+use crate::amplitude_policy::AmplitudePolicy;
+use crate::analytics::AnomalyConfig;
+use crate::audit::AuditConfig;
+use crate::chaos::ChaosConfig;
+use crate::cluster::ClusterConfig;
+use crate::connection::NatsServer;
+use crate::encryption::EncryptionConfig;
+use crate::export::ExportConfig;
+use crate::flow_trace::FlowTraceConfig;
+use crate::hopping::HoppingConfig;
+use crate::notify::NotificationsConfig;
+use crate::operations::DoctorMode;
+use crate::persistence::Durability;
+use crate::physics::{PhysicsConfig, Resonance};
+use crate::physics_history::PhysicsHistoryConfig;
+use crate::rate_limit::{ChannelQuota, RateLimitConfig};
+use crate::redaction::RedactionRule;
+use crate::registry::RegistryConfig;
+use crate::rollout::RolloutConfig;
+use crate::runtime_metrics::RuntimeMetricsConfig;
+use crate::sampling::{ObserveSamplingConfig, SamplingConfig};
+use crate::shedding::LoadSheddingConfig;
+use crate::source_stats::SourceReportConfig;
+use crate::storage_monitoring::StorageMonitoringConfig;
+use crate::task_manager::{ChannelRateLimit, PriorityWeights};
+use crate::topology::TopologyConfig;
+use crate::validation::RequiredFields;
+use crate::wave_index::WaveIndexConfig;
 use config::{Config, Environment, File};
 use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::Deserialize;
@@ -18,7 +46,7 @@ pub enum ConfigError {
 
 pub type ConfigResult<T> = std::result::Result<T, ConfigError>;
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct AppConfig {
     #[serde(default)]
     pub service: ServiceConfig,
@@ -32,6 +60,39 @@ pub struct AppConfig {
     pub operations: OperationsConfig,
     #[serde(default)]
     pub resource_monitoring: ResourceMonitoringConfig,
+    #[serde(default)]
+    pub storage_monitoring: StorageMonitoringConfig,
+    #[serde(default)]
+    pub control: ControlConfig,
+    #[serde(default)]
+    pub exports: Vec<ExportConfig>,
+    #[serde(default)]
+    pub load_shedding: LoadSheddingConfig,
+    #[serde(default)]
+    pub hopping: HoppingConfig,
+    #[serde(default)]
+    pub cluster: ClusterConfig,
+    #[serde(default)]
+    pub rollout: RolloutConfig,
+    #[serde(default)]
+    pub anomaly: AnomalyConfig,
+    /// Share of waves the gateway's observer handles, per channel
+    #[serde(default)]
+    pub observe_sampling: ObserveSamplingConfig,
+    #[serde(default)]
+    pub source_reports: SourceReportConfig,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    #[serde(default)]
+    pub topology: TopologyConfig,
+    #[serde(default)]
+    pub wave_index: WaveIndexConfig,
+    #[serde(default)]
+    pub physics: PhysicsConfig,
+    #[serde(default)]
+    pub physics_history: PhysicsHistoryConfig,
+    #[serde(default)]
+    pub runtime_metrics: RuntimeMetricsConfig,
 }
 
 impl AppConfig {
@@ -46,29 +107,29 @@ impl AppConfig {
     }
 }
 
-impl Default for AppConfig {
-    fn default() -> Self {
-        Self {
-            service: ServiceConfig::default(),
-            aether: AetherLayerConfig::default(),
-            logging: LoggingConfig::default(),
-            observability: ObservabilityConfig::default(),
-            operations: OperationsConfig::default(),
-            resource_monitoring: ResourceMonitoringConfig::default(),
-        }
-    }
-}
-
 #[derive(Debug, Clone, Deserialize)]
 pub struct ServiceConfig {
     #[serde(default)]
     pub name: String,
+    /// Announced in cluster heartbeats and matched by rollout rules;
+    /// overrides the binary's version
+    #[serde(default)]
+    pub version: Option<String>,
     #[serde(default)]
     pub channels: Vec<String>,
     #[serde(default = "default_max_inflight")]
     pub max_inflight: usize,
     #[serde(default)]
     pub rate_limit_per_sec: Option<f64>,
+    /// Handler tasks that may start back to back; one second's worth by default
+    #[serde(default)]
+    pub rate_limit_burst: Option<f64>,
+    /// Per-channel task start rates, each matching channel with its own bucket
+    #[serde(default)]
+    pub channel_rate_limits: Vec<ChannelRateLimit>,
+    /// Drop a wave instead of waiting longer than this for room in the task backlog
+    #[serde(default)]
+    pub spawn_timeout_ms: Option<u64>,
     #[serde(default = "default_timeout_ms")]
     pub timeout_ms: u64,
     #[serde(default = "default_retry_max")]
@@ -85,15 +146,37 @@ pub struct ServiceConfig {
     pub circuit_breaker_half_open_successes: usize,
     #[serde(default = "default_noise_floor")]
     pub noise_floor: f64,
+    /// Per-channel noise floors; the first matching pattern wins over `noise_floor`
+    #[serde(default)]
+    pub channel_noise_floors: Vec<ChannelNoiseFloor>,
+    /// Channels whose waves are amplified before the noise floor applies
+    #[serde(default)]
+    pub channel_resonances: Vec<ChannelResonance>,
+    /// Frequency (0.0-1.0) the service is tuned to; channels without a declared
+    /// resonance are amplified by how closely they resonate with it
+    #[serde(default)]
+    pub natural_frequency: Option<f64>,
+    /// Acknowledge every received wave with a reflection on `aether.echo.<channel>`
+    #[serde(default)]
+    pub reflect: bool,
+    /// Drop waves a restarted producer emitted before its restart
+    #[serde(default)]
+    pub discard_stale_epochs: bool,
+    #[serde(default)]
+    pub priority_weights: PriorityWeights,
 }
 
 impl Default for ServiceConfig {
     fn default() -> Self {
         Self {
             name: String::new(),
+            version: None,
             channels: Vec::new(),
             max_inflight: default_max_inflight(),
             rate_limit_per_sec: None,
+            rate_limit_burst: None,
+            channel_rate_limits: Vec::new(),
+            spawn_timeout_ms: None,
             timeout_ms: default_timeout_ms(),
             retry_max: default_retry_max(),
             retry_base_delay_ms: default_retry_base_delay_ms(),
@@ -102,6 +185,12 @@ impl Default for ServiceConfig {
             circuit_breaker_open_ms: default_circuit_open_ms(),
             circuit_breaker_half_open_successes: default_circuit_half_open_successes(),
             noise_floor: default_noise_floor(),
+            channel_noise_floors: Vec::new(),
+            channel_resonances: Vec::new(),
+            natural_frequency: None,
+            reflect: false,
+            discard_stale_epochs: false,
+            priority_weights: PriorityWeights::default(),
         }
     }
 }
@@ -142,6 +231,22 @@ fn default_noise_floor() -> f64 {
     0.01
 }
 
+/// Noise floor for channels matching a pattern
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ChannelNoiseFloor {
+    /// Channel pattern (e.g. "metrics.>")
+    pub channel: String,
+    pub noise_floor: f64,
+}
+
+/// Declared resonance for channels matching a pattern
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ChannelResonance {
+    /// Channel pattern (e.g. "payments.>")
+    pub channel: String,
+    pub resonance: Resonance,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct LoggingConfig {
     #[serde(default = "default_log_level")]
@@ -170,6 +275,38 @@ pub struct ObservabilityConfig {
     pub metrics_bind: String,
     #[serde(default)]
     pub otlp_endpoint: Option<String>,
+    /// Push metrics instead of (or as well as) serving them for scraping
+    #[serde(default)]
+    pub metrics_push: Option<MetricsPushConfig>,
+    #[serde(default)]
+    pub sampling: SamplingConfig,
+}
+
+/// Periodic OTLP/HTTP push of the metrics to an OpenTelemetry collector
+#[derive(Debug, Clone, Deserialize)]
+pub struct MetricsPushConfig {
+    /// OTLP/HTTP metrics URL, e.g. `http://otel-collector:4318/v1/metrics`
+    pub endpoint: String,
+    #[serde(default = "default_metrics_push_interval_ms")]
+    pub interval_ms: u64,
+    #[serde(default = "default_metrics_push_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// `service.instance.id` telling this process apart from other replicas;
+    /// a new random id on every start by default
+    #[serde(default)]
+    pub instance_id: Option<String>,
+}
+
+fn default_metrics_push_timeout_ms() -> u64 {
+    5_000
+}
+
+fn default_metrics_push_interval_ms() -> u64 {
+    15_000
 }
 
 impl Default for ObservabilityConfig {
@@ -179,6 +316,8 @@ impl Default for ObservabilityConfig {
             metrics_enabled: default_metrics_enabled(),
             metrics_bind: default_metrics_bind(),
             otlp_endpoint: None,
+            metrics_push: None,
+            sampling: SamplingConfig::default(),
         }
     }
 }
@@ -207,6 +346,9 @@ pub struct OperationsConfig {
     pub memory_limit_bytes: Option<u64>,
     #[serde(default)]
     pub cpu_time_limit_secs: Option<u64>,
+    /// Run the startup self-test before binding anything
+    #[serde(default)]
+    pub doctor: DoctorMode,
 }
 
 impl Default for OperationsConfig {
@@ -217,6 +359,7 @@ impl Default for OperationsConfig {
             shutdown_grace_ms: default_shutdown_grace_ms(),
             memory_limit_bytes: None,
             cpu_time_limit_secs: None,
+            doctor: DoctorMode::default(),
         }
     }
 }
@@ -243,6 +386,21 @@ pub struct ResourceMonitoringConfig {
     pub leak_detection_enabled: bool,
     #[serde(default = "default_leak_growth_bytes_per_min")]
     pub leak_growth_bytes_per_min: u64,
+    #[serde(default = "default_leak_window_intervals")]
+    pub leak_window_intervals: usize,
+    /// Samples CPU throttling and tokio timer lag are averaged over
+    #[serde(default = "default_cpu_pressure_window_intervals")]
+    pub cpu_pressure_window_intervals: usize,
+    #[serde(default = "default_leak_sustained_intervals")]
+    pub leak_sustained_intervals: usize,
+    /// Growth that would hit the memory ceiling (configured or cgroup) within
+    /// this many minutes counts as a leak
+    #[serde(default = "default_leak_exhaustion_mins")]
+    pub leak_exhaustion_mins: u64,
+    #[serde(default)]
+    pub heap_profile_dir: Option<String>,
+    #[serde(default = "default_leak_alert_channel")]
+    pub leak_alert_channel: String,
     #[serde(default = "default_allocator_metrics_enabled")]
     pub allocator_metrics_enabled: bool,
 }
@@ -254,6 +412,12 @@ impl Default for ResourceMonitoringConfig {
             interval_ms: default_resource_monitor_interval_ms(),
             leak_detection_enabled: default_leak_detection_enabled(),
             leak_growth_bytes_per_min: default_leak_growth_bytes_per_min(),
+            leak_window_intervals: default_leak_window_intervals(),
+            cpu_pressure_window_intervals: default_cpu_pressure_window_intervals(),
+            leak_sustained_intervals: default_leak_sustained_intervals(),
+            leak_exhaustion_mins: default_leak_exhaustion_mins(),
+            heap_profile_dir: None,
+            leak_alert_channel: default_leak_alert_channel(),
             allocator_metrics_enabled: default_allocator_metrics_enabled(),
         }
     }
@@ -275,16 +439,46 @@ fn default_leak_growth_bytes_per_min() -> u64 {
     10 * 1024 * 1024
 }
 
+fn default_leak_window_intervals() -> usize {
+    30
+}
+
+fn default_cpu_pressure_window_intervals() -> usize {
+    10
+}
+
+fn default_leak_sustained_intervals() -> usize {
+    10
+}
+
+fn default_leak_exhaustion_mins() -> u64 {
+    30
+}
+
+fn default_leak_alert_channel() -> String {
+    "aether.alerts.memory".to_string()
+}
+
 fn default_allocator_metrics_enabled() -> bool {
     false
 }
 
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ControlConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub auth_token: Option<String>,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct AetherLayerConfig {
     #[serde(default = "default_channel_buffer_size")]
     pub channel_buffer_size: usize,
     #[serde(default = "default_max_propagation")]
     pub max_propagation: u32,
+    #[serde(default)]
+    pub propagation_limit_policy: PropagationLimitPolicy,
     #[serde(default = "default_attenuation_factor")]
     pub attenuation_factor: f64,
     #[serde(default = "default_min_amplitude")]
@@ -295,6 +489,9 @@ pub struct AetherLayerConfig {
     pub use_nats: bool,
     #[serde(default = "default_nats_url")]
     pub nats_url: String,
+    /// Overrides `nats_url` when set
+    #[serde(default)]
+    pub nats_servers: Vec<NatsServer>,
 
     #[serde(default = "default_nats_tls_required")]
     pub nats_tls_required: bool,
@@ -324,6 +521,59 @@ pub struct AetherLayerConfig {
     pub persistence_path: String,
     #[serde(default = "default_snapshot_interval")]
     pub snapshot_interval: u64,
+    #[serde(default = "default_snapshot_every_secs")]
+    pub snapshot_every_secs: u64,
+    #[serde(default)]
+    pub persistence_durability: Durability,
+    #[serde(default = "default_persistence_queue_size")]
+    pub persistence_queue_size: usize,
+    #[serde(default = "default_persistence_flush_interval_ms")]
+    pub persistence_flush_interval_ms: u64,
+    /// AES-256-GCM keys sealing the wave log at rest; off unless set
+    #[serde(default)]
+    pub persistence_encryption: Option<EncryptionConfig>,
+
+    /// Fault injection for tests and staging; never set in production
+    #[serde(default)]
+    pub chaos: Option<ChaosConfig>,
+
+    #[serde(default)]
+    pub namespace: Option<String>,
+    #[serde(default)]
+    pub bridges: Vec<NamespaceBridge>,
+
+    #[serde(default)]
+    pub rate_limit: Option<RateLimitConfig>,
+    #[serde(default)]
+    pub channel_quotas: Vec<ChannelQuota>,
+
+    #[serde(default)]
+    pub registry: Option<RegistryConfig>,
+
+    #[serde(default)]
+    pub retained_channels: Vec<String>,
+
+    #[serde(default)]
+    pub redaction: Vec<RedactionRule>,
+
+    #[serde(default)]
+    pub channel_idle_timeout_ms: Option<u64>,
+
+    /// Hop-by-hop breadcrumbs on emitted waves; off unless set
+    #[serde(default)]
+    pub flow_trace: Option<FlowTraceConfig>,
+
+    /// Default amplitudes and floors per wave type
+    #[serde(default)]
+    pub amplitude_policy: Option<AmplitudePolicy>,
+
+    /// Payload fields emit requires per channel
+    #[serde(default)]
+    pub required_fields: Vec<RequiredFields>,
+
+    /// Audit chain key and rejected-emit budget
+    #[serde(default)]
+    pub audit: AuditConfig,
 }
 
 impl Default for AetherLayerConfig {
@@ -331,11 +581,13 @@ impl Default for AetherLayerConfig {
         Self {
             channel_buffer_size: default_channel_buffer_size(),
             max_propagation: default_max_propagation(),
+            propagation_limit_policy: PropagationLimitPolicy::default(),
             attenuation_factor: default_attenuation_factor(),
             min_amplitude: default_min_amplitude(),
             enable_physics: default_enable_physics(),
             use_nats: default_use_nats(),
             nats_url: default_nats_url(),
+            nats_servers: Vec::new(),
             nats_tls_required: default_nats_tls_required(),
             auth_token: None,
             allowed_sources: Vec::new(),
@@ -347,6 +599,24 @@ impl Default for AetherLayerConfig {
             persistence_enabled: default_persistence_enabled(),
             persistence_path: default_persistence_path(),
             snapshot_interval: default_snapshot_interval(),
+            snapshot_every_secs: default_snapshot_every_secs(),
+            persistence_durability: Durability::default(),
+            persistence_queue_size: default_persistence_queue_size(),
+            persistence_flush_interval_ms: default_persistence_flush_interval_ms(),
+            persistence_encryption: None,
+            chaos: None,
+            namespace: None,
+            bridges: Vec::new(),
+            rate_limit: None,
+            channel_quotas: Vec::new(),
+            registry: None,
+            retained_channels: Vec::new(),
+            redaction: Vec::new(),
+            channel_idle_timeout_ms: None,
+            flow_trace: None,
+            amplitude_policy: None,
+            required_fields: Vec::new(),
+            audit: AuditConfig::default(),
         }
     }
 }
@@ -356,11 +626,13 @@ impl From<AetherLayerConfig> for AetherConfig {
         Self {
             channel_buffer_size: config.channel_buffer_size,
             max_propagation: config.max_propagation,
+            propagation_limit_policy: config.propagation_limit_policy,
             attenuation_factor: config.attenuation_factor,
             min_amplitude: config.min_amplitude,
             enable_physics: config.enable_physics,
             use_nats: config.use_nats,
             nats_url: config.nats_url,
+            nats_servers: config.nats_servers,
             nats_tls_required: config.nats_tls_required,
             auth_token: config.auth_token,
             allowed_sources: config.allowed_sources,
@@ -372,6 +644,24 @@ impl From<AetherLayerConfig> for AetherConfig {
             persistence_enabled: config.persistence_enabled,
             persistence_path: config.persistence_path,
             snapshot_interval: config.snapshot_interval,
+            snapshot_every_secs: config.snapshot_every_secs,
+            persistence_durability: config.persistence_durability,
+            persistence_queue_size: config.persistence_queue_size,
+            persistence_flush_interval_ms: config.persistence_flush_interval_ms,
+            persistence_encryption: config.persistence_encryption,
+            chaos: config.chaos,
+            namespace: config.namespace,
+            bridges: config.bridges,
+            rate_limit: config.rate_limit,
+            channel_quotas: config.channel_quotas,
+            registry: config.registry,
+            retained_channels: config.retained_channels,
+            redaction: config.redaction,
+            channel_idle_timeout_ms: config.channel_idle_timeout_ms,
+            flow_trace: config.flow_trace,
+            amplitude_policy: config.amplitude_policy,
+            required_fields: config.required_fields,
+            audit: config.audit,
         }
     }
 }
@@ -428,6 +718,18 @@ fn default_snapshot_interval() -> u64 {
     1000
 }
 
+fn default_snapshot_every_secs() -> u64 {
+    300
+}
+
+fn default_persistence_queue_size() -> usize {
+    10_000
+}
+
+fn default_persistence_flush_interval_ms() -> u64 {
+    500
+}
+
 pub fn load_config(service_name: &str) -> ConfigResult<AppConfig> {
     let paths = config_paths(service_name);
     load_config_from_paths(service_name, &paths)
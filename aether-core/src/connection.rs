@@ -0,0 +1,316 @@
+//! NATS servers and connection state, fed by the client's connection events.
+//!
+//! Layers without NATS report `Connected` from the moment they are created:
+//! there is no link that could drop.
+//!
+//! With several [`NatsServer`]s configured, each layer shuffles them by weight
+//! once and keeps that order, so the client fails over down the list and
+//! weights decide how services spread across servers or clusters.
+
+use crate::aether::Aether;
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// A NATS server (or cluster seed) and its share of first connections
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct NatsServer {
+    pub url: String,
+    /// Relative share of layers that try this server first; 0 for failover only
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+}
+
+impl NatsServer {
+    pub fn new(url: impl Into<String>, weight: u32) -> Self {
+        Self {
+            url: url.into(),
+            weight,
+        }
+    }
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+/// Weighted shuffle (Efraimidis-Spirakis); weight-0 servers follow in config order
+pub(crate) fn server_order(servers: &[NatsServer], rng: &mut impl Rng) -> Vec<String> {
+    let mut weighted: Vec<(f64, &str)> = servers
+        .iter()
+        .filter(|server| server.weight > 0)
+        .map(|server| {
+            let key = rng.gen::<f64>().powf(1.0 / server.weight as f64);
+            (key, server.url.as_str())
+        })
+        .collect();
+    weighted.sort_by(|a, b| b.0.total_cmp(&a.0));
+    weighted
+        .into_iter()
+        .map(|(_, url)| url)
+        .chain(
+            servers
+                .iter()
+                .filter(|server| server.weight == 0)
+                .map(|server| server.url.as_str()),
+        )
+        .map(str::to_string)
+        .collect()
+}
+
+/// Metric label for the server a client is attached to
+pub(crate) fn server_label(info: &async_nats::ServerInfo) -> String {
+    if info.server_name.is_empty() {
+        format!("{}:{}", info.host, info.port)
+    } else {
+        info.server_name.clone()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionStatus {
+    Connected,
+    /// The link dropped and the client is retrying
+    Reconnecting,
+    /// Never connected, or the client gave up
+    Disconnected,
+}
+
+impl ConnectionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConnectionStatus::Connected => "connected",
+            ConnectionStatus::Reconnecting => "reconnecting",
+            ConnectionStatus::Disconnected => "disconnected",
+        }
+    }
+
+    /// Value of the `aether_nats_connection_state` gauge
+    fn gauge(&self) -> f64 {
+        match self {
+            ConnectionStatus::Disconnected => 0.0,
+            ConnectionStatus::Reconnecting => 1.0,
+            ConnectionStatus::Connected => 2.0,
+        }
+    }
+}
+
+/// Snapshot returned by [`Aether::connection_state`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConnectionState {
+    pub status: ConnectionStatus,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<DateTime<Utc>>,
+    /// Server the client is attached to, when known
+    pub server: Option<String>,
+    /// When the current connection was established
+    pub connected_since: Option<DateTime<Utc>>,
+    /// Time connected, zero unless `Connected`
+    #[serde(with = "duration_ms")]
+    pub uptime: Duration,
+    pub reconnects: u64,
+}
+
+impl ConnectionState {
+    pub fn is_connected(&self) -> bool {
+        self.status == ConnectionStatus::Connected
+    }
+}
+
+mod duration_ms {
+    use serde::Serializer;
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_millis() as u64)
+    }
+}
+
+#[derive(Debug)]
+struct Tracked {
+    status: ConnectionStatus,
+    last_error: Option<(String, DateTime<Utc>)>,
+    server: Option<String>,
+    connected_since: Option<DateTime<Utc>>,
+    reconnects: u64,
+}
+
+impl Tracked {
+    fn detach(&mut self) {
+        if let Some(server) = self.server.take() {
+            metrics::gauge!("aether_nats_server_active", "server" => server).set(0.0);
+        }
+        self.connected_since = None;
+    }
+}
+
+/// Connection bookkeeping shared by every clone of an `Aether`
+#[derive(Debug)]
+pub(crate) struct ConnectionTracker {
+    tracked: Mutex<Tracked>,
+}
+
+impl ConnectionTracker {
+    pub(crate) fn new(use_nats: bool, now: DateTime<Utc>) -> Self {
+        let status = if use_nats {
+            ConnectionStatus::Disconnected
+        } else {
+            ConnectionStatus::Connected
+        };
+        metrics::gauge!("aether_nats_connection_state").set(status.gauge());
+        Self {
+            tracked: Mutex::new(Tracked {
+                status,
+                last_error: None,
+                server: None,
+                connected_since: (!use_nats).then_some(now),
+                reconnects: 0,
+            }),
+        }
+    }
+
+    fn update(&self, apply: impl FnOnce(&mut Tracked)) {
+        let mut tracked = self.tracked.lock().expect("connection lock poisoned");
+        apply(&mut tracked);
+        metrics::gauge!("aether_nats_connection_state").set(tracked.status.gauge());
+    }
+
+    /// Attached to `server` (if known); repeats for the same link are ignored
+    pub(crate) fn connected(&self, server: Option<String>, now: DateTime<Utc>) {
+        self.update(|tracked| {
+            let same_link = tracked.status == ConnectionStatus::Connected
+                && (server.is_none() || server == tracked.server);
+            if same_link {
+                return;
+            }
+            if tracked.status == ConnectionStatus::Reconnecting {
+                tracked.reconnects += 1;
+            }
+            tracked.detach();
+            if let Some(server) = &server {
+                metrics::gauge!("aether_nats_server_active", "server" => server.clone()).set(1.0);
+                metrics::counter!("aether_nats_server_connects_total", "server" => server.clone())
+                    .increment(1);
+            }
+            tracked.status = ConnectionStatus::Connected;
+            tracked.server = server;
+            tracked.connected_since = Some(now);
+        });
+    }
+
+    /// The client lost the link and keeps retrying
+    pub(crate) fn dropped(&self) {
+        self.update(|tracked| {
+            if let Some(server) = &tracked.server {
+                metrics::counter!("aether_nats_server_drops_total", "server" => server.clone())
+                    .increment(1);
+            }
+            tracked.status = ConnectionStatus::Reconnecting;
+            tracked.detach();
+        });
+    }
+
+    /// No connection and no retry in progress
+    pub(crate) fn closed(&self) {
+        self.update(|tracked| {
+            tracked.status = ConnectionStatus::Disconnected;
+            tracked.detach();
+        });
+    }
+
+    pub(crate) fn error(&self, error: impl Into<String>, now: DateTime<Utc>) {
+        let error = error.into();
+        self.update(|tracked| {
+            if let Some(server) = &tracked.server {
+                metrics::counter!("aether_nats_server_errors_total", "server" => server.clone())
+                    .increment(1);
+            }
+            tracked.last_error = Some((error, now));
+        });
+    }
+
+    pub(crate) fn state(&self, now: DateTime<Utc>) -> ConnectionState {
+        let tracked = self.tracked.lock().expect("connection lock poisoned");
+        let uptime = tracked
+            .connected_since
+            .filter(|_| tracked.status == ConnectionStatus::Connected)
+            .and_then(|since| (now - since).to_std().ok())
+            .unwrap_or_default();
+        ConnectionState {
+            status: tracked.status,
+            last_error: tracked.last_error.as_ref().map(|(error, _)| error.clone()),
+            last_error_at: tracked.last_error.as_ref().map(|(_, at)| *at),
+            server: tracked.server.clone(),
+            connected_since: tracked.connected_since,
+            uptime,
+            reconnects: tracked.reconnects,
+        }
+    }
+}
+
+impl Aether {
+    /// Current state of the NATS connection
+    pub fn connection_state(&self) -> ConnectionState {
+        self.connection().state(self.clock().now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_tracker_follows_drop_reconnect_and_errors() {
+        let t0 = Utc::now();
+        let secs = |s: i64| t0 + chrono::Duration::seconds(s);
+        let tracker = ConnectionTracker::new(true, t0);
+        assert_eq!(tracker.state(t0).status, ConnectionStatus::Disconnected);
+
+        tracker.error("connection refused", secs(1));
+        tracker.connected(Some("nats-a".to_string()), secs(2));
+        tracker.connected(None, secs(3));
+        let state = tracker.state(secs(12));
+        assert!(state.is_connected());
+        assert_eq!(state.uptime, Duration::from_secs(10));
+        assert_eq!(state.last_error.as_deref(), Some("connection refused"));
+
+        tracker.dropped();
+        let state = tracker.state(secs(13));
+        assert_eq!(state.status, ConnectionStatus::Reconnecting);
+        assert_eq!(state.uptime, Duration::ZERO);
+        tracker.connected(Some("nats-b".to_string()), secs(20));
+        let state = tracker.state(secs(21));
+        assert_eq!(state.reconnects, 1);
+        assert_eq!(state.server.as_deref(), Some("nats-b"));
+        assert_eq!(state.connected_since, Some(secs(20)));
+
+        let local = ConnectionTracker::new(false, t0).state(secs(5));
+        assert!(local.is_connected());
+        assert_eq!(local.uptime, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_server_order_follows_weights_and_keeps_standbys_last() {
+        let servers = [
+            NatsServer::new("nats://east:4222", 3),
+            NatsServer::new("nats://west:4222", 1),
+            NatsServer::new("nats://standby:4222", 0),
+        ];
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let mut east_first = 0;
+        for _ in 0..4_000 {
+            let order = server_order(&servers, &mut rng);
+            assert_eq!(order.len(), 3);
+            assert_eq!(order[2], "nats://standby:4222");
+            if order[0] == "nats://east:4222" {
+                east_first += 1;
+            }
+        }
+        // Expected share 3/4
+        assert!((2_800..3_200).contains(&east_first), "{}", east_first);
+    }
+}
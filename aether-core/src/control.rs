@@ -0,0 +1,624 @@
+//! Control plane: runtime administration over `aether.control.<service>`.
+
+use crate::{
+    aether::Aether,
+    audit::AuditKind,
+    channel::Channel,
+    flow_trace::FlowTraceIndex,
+    physics_history::{PatternQuery, PhysicsHistory},
+    rollout::{RolloutRule, VersionRouter},
+    topology::{TopologyFormat, TopologyTracker},
+    vibrator::VibratorControl,
+    wave::{Wave, WaveType},
+    wave_index::{WaveIndex, WaveQuery, TAIL_CHANNEL_PREFIX},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Channel prefix for control commands
+pub const CONTROL_CHANNEL_PREFIX: &str = "aether.control";
+
+/// Control channel for a service
+pub fn control_channel(service: &str) -> Channel {
+    Channel::new(format!("{}.{}", CONTROL_CHANNEL_PREFIX, service))
+}
+
+/// Default reply channel for a service's control responses
+pub fn reply_channel(service: &str) -> Channel {
+    control_channel(service).child("reply")
+}
+
+/// Administrative commands accepted on the control channel
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ControlCommand {
+    /// Stop consuming waves (subscriptions stay active)
+    Pause,
+    /// Resume consuming waves
+    Resume,
+    /// Replace the log filter directives
+    SetLogLevel { level: String },
+    /// Report Aether layer statistics
+    DumpStats,
+    /// Save a persistence snapshot immediately
+    Snapshot,
+    /// Drain the service: stop intake, finish in-flight work, flush and exit
+    Drain,
+    /// Route `percent` of a channel's waves to instances labelled `version`
+    SetRollout {
+        channel: String,
+        version: String,
+        percent: f64,
+    },
+    /// Stop splitting a channel between versions
+    ClearRollout { channel: String },
+    /// Report the observed service topology as JSON or Graphviz DOT
+    DumpTopology {
+        #[serde(default)]
+        format: TopologyFormat,
+    },
+    /// Report every hop a traced wave took
+    TraceWave { wave_id: Uuid },
+    /// Report persisted channel summaries and interference pattern detections
+    PhysicsHistory {
+        #[serde(default)]
+        channel: Option<String>,
+        #[serde(default)]
+        since: Option<DateTime<Utc>>,
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    /// Report recent waves matching a query, oldest first
+    SearchWaves {
+        #[serde(default)]
+        query: WaveQuery,
+    },
+    /// Forward new waves matching a query onto `stream` (an `aether.tail.*`
+    /// channel) for `duration_ms`; repeat to extend
+    TailWaves {
+        #[serde(default)]
+        query: WaveQuery,
+        stream: String,
+        #[serde(default = "default_tail_ms")]
+        duration_ms: u64,
+    },
+    /// Copy the wave store into `path`, a new directory readable by its
+    /// owner only, for tooling that cannot open the leased store
+    CheckpointStore { path: String },
+}
+
+fn default_tail_ms() -> u64 {
+    30_000
+}
+
+/// Outcome of a control command, emitted on the reply channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControlResponse {
+    /// ID of the command wave this responds to
+    pub request_id: Uuid,
+    pub service: String,
+    pub ok: bool,
+    #[serde(default)]
+    pub detail: serde_json::Value,
+}
+
+/// Control plane listener for a single service
+pub struct ControlPlane {
+    service: String,
+    aether: Aether,
+    auth_token: Option<String>,
+    vibrator: Option<VibratorControl>,
+    rollout: Option<VersionRouter>,
+    topology: Option<TopologyTracker>,
+    flow_traces: Option<FlowTraceIndex>,
+    physics_history: Option<PhysicsHistory>,
+    wave_index: Option<WaveIndex>,
+}
+
+impl ControlPlane {
+    pub fn new(service: impl Into<String>, aether: &Aether) -> Self {
+        let service = service.into();
+        if let Some(store) = aether.wave_store() {
+            if let Err(err) = store.set_lease_service(&service) {
+                warn!(
+                    "Failed to record {} as the store's writer: {}",
+                    service, err
+                );
+            }
+        }
+        Self {
+            service,
+            aether: aether.clone(),
+            auth_token: aether.config().auth_token.clone(),
+            vibrator: None,
+            rollout: None,
+            topology: None,
+            flow_traces: None,
+            physics_history: None,
+            wave_index: None,
+        }
+    }
+
+    /// Token required in the `control_token` metadata of command waves
+    /// (defaults to the Aether auth token)
+    pub fn with_auth_token(mut self, token: Option<String>) -> Self {
+        if token.is_some() {
+            self.auth_token = token;
+        }
+        self
+    }
+
+    /// Vibrator affected by pause/resume/drain commands
+    pub fn with_vibrator(mut self, control: VibratorControl) -> Self {
+        self.vibrator = Some(control);
+        self
+    }
+
+    /// Version routing adjusted by rollout commands
+    pub fn with_rollout(mut self, router: VersionRouter) -> Self {
+        self.rollout = Some(router);
+        self
+    }
+
+    /// Topology reported by `dump_topology`
+    pub fn with_topology(mut self, tracker: TopologyTracker) -> Self {
+        self.topology = Some(tracker);
+        self
+    }
+
+    /// Wave paths reported by `trace_wave`
+    pub fn with_flow_traces(mut self, index: FlowTraceIndex) -> Self {
+        self.flow_traces = Some(index);
+        self
+    }
+
+    /// Summaries and detections reported by `physics_history`
+    pub fn with_physics_history(mut self, history: PhysicsHistory) -> Self {
+        self.physics_history = Some(history);
+        self
+    }
+
+    /// Recent waves searched by `search_waves` and tailed by `tail_waves`
+    pub fn with_wave_index(mut self, index: WaveIndex) -> Self {
+        self.wave_index = Some(index);
+        self
+    }
+
+    /// Channel this control plane listens on
+    pub fn channel(&self) -> Channel {
+        control_channel(&self.service)
+    }
+
+    /// Start listening for control commands
+    pub async fn spawn(self) -> JoinHandle<()> {
+        if self.auth_token.is_none() {
+            warn!(
+                "Control plane for {} has no auth token; commands are unauthenticated",
+                self.service
+            );
+        }
+
+        let mut receiver = self.aether.subscribe(&self.channel()).await;
+        info!("Control plane listening on {}", self.channel());
+
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(wave) => {
+                        if let Some(response) = self.handle(&wave).await {
+                            self.reply(&wave, response).await;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Control plane missed {} commands", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    /// Execute a control command wave
+    ///
+    /// Non-command waves are ignored and yield `None`.
+    pub async fn handle(&self, wave: &Wave) -> Option<ControlResponse> {
+        if wave.wave_type() != &WaveType::Command {
+            return None;
+        }
+
+        if let Some(expected) = &self.auth_token {
+            if wave.control_token() != Some(expected.as_str()) {
+                warn!("Rejected unauthenticated control command {}", wave.id());
+                self.aether.record_audit(
+                    AuditKind::AuthFailure,
+                    wave.source(),
+                    serde_json::json!({
+                        "wave_id": wave.id(),
+                        "channel": wave.channel().name(),
+                        "reason": "unauthenticated control command",
+                    }),
+                );
+                return Some(self.respond(wave, false, "unauthorized".into()));
+            }
+        }
+
+        let command = match serde_json::from_value::<ControlCommand>(wave.payload().clone()) {
+            Ok(command) => command,
+            Err(err) => {
+                return Some(self.respond(wave, false, format!("invalid command: {}", err).into()))
+            }
+        };
+
+        debug!("Control command for {}: {:?}", self.service, command);
+        let audited = serde_json::to_value(&command).unwrap_or(serde_json::Value::Null);
+        let (ok, detail) = self.execute(command).await;
+        self.aether.record_audit(
+            AuditKind::ControlCommand,
+            wave.source(),
+            serde_json::json!({
+                "wave_id": wave.id(),
+                "service": self.service,
+                "command": audited,
+                "ok": ok,
+            }),
+        );
+        Some(self.respond(wave, ok, detail))
+    }
+
+    async fn execute(&self, command: ControlCommand) -> (bool, serde_json::Value) {
+        match command {
+            ControlCommand::Pause | ControlCommand::Resume | ControlCommand::Drain => {
+                let Some(vibrator) = &self.vibrator else {
+                    return (false, "no vibrator attached".into());
+                };
+                match command {
+                    ControlCommand::Pause => vibrator.pause(),
+                    ControlCommand::Resume => vibrator.resume(),
+                    _ => vibrator.drain(),
+                }
+                info!("Control: {:?} applied to {}", command, self.service);
+                (
+                    true,
+                    serde_json::json!({
+                        "paused": vibrator.is_paused(),
+                        "draining": vibrator.is_draining(),
+                    }),
+                )
+            }
+            ControlCommand::SetLogLevel { level } => {
+                match crate::observability::set_log_level(&level) {
+                    Ok(()) => (true, serde_json::json!({ "level": level })),
+                    Err(err) => (false, err.to_string().into()),
+                }
+            }
+            ControlCommand::DumpStats => {
+                let stats = self.aether.stats().await;
+                (
+                    true,
+                    serde_json::to_value(stats).unwrap_or(serde_json::Value::Null),
+                )
+            }
+            ControlCommand::Snapshot => match self.aether.snapshot().await {
+                Ok(Some(snapshot)) => (
+                    true,
+                    serde_json::to_value(snapshot).unwrap_or(serde_json::Value::Null),
+                ),
+                Ok(None) => (false, "persistence disabled or empty".into()),
+                Err(err) => (false, err.to_string().into()),
+            },
+            ControlCommand::SetRollout {
+                channel,
+                version,
+                percent,
+            } => {
+                let Some(router) = &self.rollout else {
+                    return (false, "no version router attached".into());
+                };
+                router.set_rule(RolloutRule {
+                    channel,
+                    version,
+                    percent,
+                });
+                self.rollout_applied(router)
+            }
+            ControlCommand::ClearRollout { channel } => {
+                let Some(router) = &self.rollout else {
+                    return (false, "no version router attached".into());
+                };
+                if !router.remove_rule(&channel) {
+                    return (false, format!("no rollout on {}", channel).into());
+                }
+                self.rollout_applied(router)
+            }
+            ControlCommand::DumpTopology { format } => match &self.topology {
+                Some(tracker) => (true, tracker.snapshot().render(format)),
+                None => (false, "topology tracking disabled".into()),
+            },
+            ControlCommand::TraceWave { wave_id } => {
+                let Some(index) = &self.flow_traces else {
+                    return (false, "flow trace index disabled".into());
+                };
+                match index.path(&wave_id) {
+                    Some(path) => (
+                        true,
+                        serde_json::to_value(path).unwrap_or(serde_json::Value::Null),
+                    ),
+                    None => (false, format!("no trace for wave {}", wave_id).into()),
+                }
+            }
+            ControlCommand::PhysicsHistory {
+                channel,
+                since,
+                limit,
+            } => {
+                let Some(history) = &self.physics_history else {
+                    return (false, "physics history disabled".into());
+                };
+                let summaries = match &channel {
+                    Some(channel) => history
+                        .summary(channel)
+                        .map(|summary| summary.into_iter().collect()),
+                    None => history.summaries(),
+                };
+                let query = PatternQuery {
+                    channel,
+                    since,
+                    limit,
+                };
+                match summaries.and_then(|summaries| Ok((summaries, history.detections(&query)?))) {
+                    Ok((summaries, detections)) => (
+                        true,
+                        serde_json::json!({
+                            "summaries": summaries,
+                            "detections": detections,
+                        }),
+                    ),
+                    Err(err) => (false, err.to_string().into()),
+                }
+            }
+            ControlCommand::SearchWaves { query } => match &self.wave_index {
+                Some(index) => (true, serde_json::json!({ "waves": index.search(&query) })),
+                None => (false, "wave index disabled".into()),
+            },
+            ControlCommand::TailWaves {
+                query,
+                stream,
+                duration_ms,
+            } => {
+                let Some(index) = &self.wave_index else {
+                    return (false, "wave index disabled".into());
+                };
+                if !stream.starts_with(&format!("{}.", TAIL_CHANNEL_PREFIX)) {
+                    return (
+                        false,
+                        format!("stream must be under {}", TAIL_CHANNEL_PREFIX).into(),
+                    );
+                }
+                let stream = Channel::new(stream);
+                let lease = index
+                    .tail(
+                        &self.aether,
+                        query,
+                        stream.clone(),
+                        Duration::from_millis(duration_ms),
+                        &self.service,
+                    )
+                    .await;
+                (
+                    true,
+                    serde_json::json!({
+                        "stream": stream.name(),
+                        "lease_ms": lease.as_millis() as u64,
+                    }),
+                )
+            }
+            ControlCommand::CheckpointStore { path } => {
+                let Some(store) = self.aether.wave_store().cloned() else {
+                    return (false, "persistence disabled".into());
+                };
+                let target = path.clone();
+                let checkpoint = tokio::task::spawn_blocking(move || {
+                    store.checkpoint(&target).map(|()| store.len())
+                })
+                .await;
+                match checkpoint {
+                    Ok(Ok(waves)) => (true, serde_json::json!({ "path": path, "waves": waves })),
+                    Ok(Err(err)) => (false, format!("{:#}", err).into()),
+                    Err(err) => (false, err.to_string().into()),
+                }
+            }
+        }
+    }
+
+    fn rollout_applied(&self, router: &VersionRouter) -> (bool, serde_json::Value) {
+        info!(
+            "Control: rollout for {} is now {:?}",
+            self.service,
+            router.rules()
+        );
+        (
+            true,
+            serde_json::json!({
+                "version": router.version(),
+                "rules": router.rules(),
+            }),
+        )
+    }
+
+    fn respond(&self, wave: &Wave, ok: bool, detail: serde_json::Value) -> ControlResponse {
+        ControlResponse {
+            request_id: *wave.id(),
+            service: self.service.clone(),
+            ok,
+            detail,
+        }
+    }
+
+    async fn reply(&self, command: &Wave, response: ControlResponse) {
+        let channel = command
+            .metadata()
+            .get("reply_to")
+            .and_then(|v| v.as_str())
+            .map(Channel::new)
+            .unwrap_or_else(|| reply_channel(&self.service));
+
+        let payload = match serde_json::to_value(&response) {
+            Ok(payload) => payload,
+            Err(err) => {
+                warn!("Failed to encode control response: {}", err);
+                return;
+            }
+        };
+
+        let mut wave = Wave::builder(channel)
+            .wave_type(WaveType::Response)
+            .payload(payload)
+            .source(self.service.clone())
+            .build();
+        if let Some(token) = &self.aether.config().auth_token {
+            wave.set_auth_token(token.clone());
+        }
+
+        if let Err(err) = self.aether.emit(wave).await {
+            warn!("Failed to emit control response: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aether::AetherConfig;
+    use crate::vibrator::Vibrator;
+
+    fn test_aether() -> Aether {
+        Aether::new(AetherConfig {
+            use_nats: false,
+            ..AetherConfig::default()
+        })
+    }
+
+    fn command_wave(command: serde_json::Value, token: Option<&str>) -> Wave {
+        let mut wave = Wave::builder(control_channel("svc"))
+            .wave_type(WaveType::Command)
+            .payload(command)
+            .build();
+        if let Some(token) = token {
+            wave.set_control_token(token);
+        }
+        wave
+    }
+
+    #[tokio::test]
+    async fn test_control_pause_and_resume() {
+        let aether = test_aether();
+        let vibrator = Vibrator::create("svc", &aether).await.unwrap();
+        let plane = ControlPlane::new("svc", &aether).with_vibrator(vibrator.control());
+
+        let response = plane
+            .handle(&command_wave(serde_json::json!({"command": "pause"}), None))
+            .await
+            .unwrap();
+        assert!(response.ok);
+        assert!(vibrator.control().is_paused());
+
+        plane
+            .handle(&command_wave(
+                serde_json::json!({"command": "resume"}),
+                None,
+            ))
+            .await
+            .unwrap();
+        assert!(!vibrator.control().is_paused());
+    }
+
+    #[tokio::test]
+    async fn test_control_rejects_bad_token() {
+        let aether = test_aether();
+        let plane = ControlPlane::new("svc", &aether).with_auth_token(Some("secret".into()));
+
+        let response = plane
+            .handle(&command_wave(
+                serde_json::json!({"command": "dump_stats"}),
+                Some("wrong"),
+            ))
+            .await
+            .unwrap();
+        assert!(!response.ok);
+
+        let response = plane
+            .handle(&command_wave(
+                serde_json::json!({"command": "dump_stats"}),
+                Some("secret"),
+            ))
+            .await
+            .unwrap();
+        assert!(response.ok);
+    }
+
+    #[tokio::test]
+    async fn test_control_replies_on_reply_channel() {
+        let aether = test_aether();
+        let mut replies = aether.subscribe(&reply_channel("svc")).await;
+        let _task = ControlPlane::new("svc", &aether).spawn().await;
+
+        aether
+            .emit(command_wave(
+                serde_json::json!({"command": "dump_stats"}),
+                None,
+            ))
+            .await
+            .unwrap();
+
+        let reply = tokio::time::timeout(std::time::Duration::from_millis(200), replies.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        let response: ControlResponse = serde_json::from_value(reply.payload().clone()).unwrap();
+        assert!(response.ok);
+    }
+
+    #[tokio::test]
+    async fn test_control_token_separate_from_layer_token() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            auth_token: Some("layer".into()),
+            ..AetherConfig::default()
+        });
+        let mut replies = aether.subscribe(&reply_channel("svc")).await;
+        let _task = ControlPlane::new("svc", &aether)
+            .with_auth_token(Some("admin".into()))
+            .spawn()
+            .await;
+
+        let mut wave = command_wave(serde_json::json!({"command": "dump_stats"}), Some("admin"));
+        wave.set_auth_token("layer");
+        aether.emit(wave).await.unwrap();
+
+        let reply = tokio::time::timeout(std::time::Duration::from_millis(200), replies.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(reply.auth_token(), Some("layer"));
+        assert_eq!(reply.control_token(), None);
+        let response: ControlResponse = serde_json::from_value(reply.payload().clone()).unwrap();
+        assert!(response.ok);
+
+        // The layer token alone does not authorize control commands
+        let mut wave = command_wave(serde_json::json!({"command": "dump_stats"}), None);
+        wave.set_auth_token("layer");
+        aether.emit(wave).await.unwrap();
+
+        let reply = tokio::time::timeout(std::time::Duration::from_millis(200), replies.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        let response: ControlResponse = serde_json::from_value(reply.payload().clone()).unwrap();
+        assert!(!response.ok);
+    }
+}
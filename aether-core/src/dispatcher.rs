@@ -0,0 +1,232 @@
+//! Keyed dispatch: per-key ordered processing across concurrent worker lanes.
+
+use crate::{handler_metrics::observe_handler, wave::Wave, AetherError, Result};
+use futures::FutureExt;
+use std::collections::hash_map::DefaultHasher;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use tracing::{debug, error};
+
+type KeyFn = Arc<dyn Fn(&Wave) -> Option<String> + Send + Sync>;
+
+/// How a wave's partition key is found
+#[derive(Clone)]
+pub enum PartitionKey {
+    /// Field from the payload, falling back to metadata (e.g. "order_id")
+    Field(String),
+    /// Wave source
+    Source,
+    /// Channel name
+    Channel,
+    /// Custom extractor
+    Custom(KeyFn),
+}
+
+impl PartitionKey {
+    pub fn field(name: impl Into<String>) -> Self {
+        Self::Field(name.into())
+    }
+
+    pub fn custom(f: impl Fn(&Wave) -> Option<String> + Send + Sync + 'static) -> Self {
+        Self::Custom(Arc::new(f))
+    }
+
+    /// Extract the key, or `None` if the wave has none
+    pub fn extract(&self, wave: &Wave) -> Option<String> {
+        match self {
+            PartitionKey::Field(name) => wave
+                .payload()
+                .get(name)
+                .or_else(|| wave.metadata().get(name))
+                .filter(|value| !value.is_null())
+                .map(|value| match value.as_str() {
+                    Some(s) => s.to_string(),
+                    None => value.to_string(),
+                }),
+            PartitionKey::Source => wave.source().map(str::to_string),
+            PartitionKey::Channel => Some(wave.channel().name().to_string()),
+            PartitionKey::Custom(f) => f(wave),
+        }
+    }
+}
+
+impl std::fmt::Debug for PartitionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PartitionKey::Field(name) => f.debug_tuple("Field").field(name).finish(),
+            PartitionKey::Source => f.write_str("Source"),
+            PartitionKey::Channel => f.write_str("Channel"),
+            PartitionKey::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
+
+/// Keyed dispatcher configuration
+#[derive(Debug, Clone)]
+pub struct KeyedDispatcherConfig {
+    pub key: PartitionKey,
+    /// Number of worker lanes (concurrency across keys)
+    pub lanes: usize,
+    /// Waves buffered per lane before `dispatch` waits
+    pub lane_capacity: usize,
+}
+
+impl KeyedDispatcherConfig {
+    pub fn new(key: PartitionKey) -> Self {
+        Self {
+            key,
+            lanes: 8,
+            lane_capacity: 64,
+        }
+    }
+
+    pub fn with_lanes(mut self, lanes: usize) -> Self {
+        self.lanes = lanes;
+        self
+    }
+
+    pub fn with_lane_capacity(mut self, lane_capacity: usize) -> Self {
+        self.lane_capacity = lane_capacity;
+        self
+    }
+}
+
+/// Routes waves to a fixed lane per key so each key is handled in order
+///
+/// Waves with different keys are processed concurrently; waves without a key
+/// are spread across lanes by ID.
+pub struct KeyedDispatcher {
+    key: PartitionKey,
+    lanes: Vec<mpsc::Sender<Wave>>,
+    workers: JoinSet<()>,
+}
+
+impl KeyedDispatcher {
+    pub fn new<H, Fut>(config: KeyedDispatcherConfig, handler: H) -> Self
+    where
+        H: Fn(Wave) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let handler = Arc::new(handler);
+        let mut workers = JoinSet::new();
+        let lanes = (0..config.lanes.max(1))
+            .map(|lane| {
+                let (tx, mut rx) = mpsc::channel::<Wave>(config.lane_capacity.max(1));
+                let handler = Arc::clone(&handler);
+                workers.spawn(async move {
+                    while let Some(wave) = rx.recv().await {
+                        let id = *wave.id();
+                        let channel = wave.channel().name().to_string();
+                        if AssertUnwindSafe(observe_handler(&channel, handler(wave)))
+                            .catch_unwind()
+                            .await
+                            .is_err()
+                        {
+                            error!("Handler panicked on wave {} in lane {}", id, lane);
+                        }
+                    }
+                    debug!("Dispatcher lane {} stopped", lane);
+                });
+                tx
+            })
+            .collect();
+
+        Self {
+            key: config.key,
+            lanes,
+            workers,
+        }
+    }
+
+    /// Lane a wave is routed to
+    pub fn lane_for(&self, wave: &Wave) -> usize {
+        let mut hasher = DefaultHasher::new();
+        match self.key.extract(wave) {
+            Some(key) => key.hash(&mut hasher),
+            None => wave.id().hash(&mut hasher),
+        }
+        (hasher.finish() % self.lanes.len() as u64) as usize
+    }
+
+    /// Queue a wave on its lane, waiting if the lane is full
+    pub async fn dispatch(&self, wave: Wave) -> Result<()> {
+        let lane = self.lane_for(&wave);
+        self.lanes[lane].send(wave).await.map_err(|_| {
+            AetherError::TransmissionFailed(format!("dispatcher lane {} is closed", lane))
+        })
+    }
+
+    /// Stop accepting waves and wait for queued ones to finish
+    pub async fn shutdown(mut self) {
+        self.lanes.clear();
+        while self.workers.join_next().await.is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_per_key_order_is_preserved() {
+        let log = Arc::new(Mutex::new(Vec::<(String, u64)>::new()));
+        let sink = Arc::clone(&log);
+        let dispatcher = KeyedDispatcher::new(
+            KeyedDispatcherConfig::new(PartitionKey::field("order_id")).with_lanes(4),
+            move |wave: Wave| {
+                let sink = Arc::clone(&sink);
+                async move {
+                    let seq = wave.payload()["seq"].as_u64().unwrap();
+                    // Earlier updates take longer, which would reorder a spawn-per-wave model
+                    tokio::time::sleep(Duration::from_millis(5 - seq)).await;
+                    let order = wave.payload()["order_id"].as_str().unwrap().to_string();
+                    sink.lock().unwrap().push((order, seq));
+                }
+            },
+        );
+
+        for seq in 0..5 {
+            for order in ["A", "B", "C"] {
+                let wave = Wave::new(
+                    "orders.updated",
+                    serde_json::json!({"order_id": order, "seq": seq}),
+                );
+                dispatcher.dispatch(wave).await.unwrap();
+            }
+        }
+        dispatcher.shutdown().await;
+
+        let log = log.lock().unwrap();
+        assert_eq!(log.len(), 15);
+        for order in ["A", "B", "C"] {
+            let seqs: Vec<u64> = log
+                .iter()
+                .filter(|(o, _)| o == order)
+                .map(|(_, s)| *s)
+                .collect();
+            assert_eq!(seqs, vec![0, 1, 2, 3, 4]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_key_falls_back_to_metadata() {
+        let key = PartitionKey::field("tenant");
+        let wave = Wave::builder("orders.created")
+            .metadata(serde_json::json!({"tenant": "acme"}))
+            .build();
+        assert_eq!(key.extract(&wave).as_deref(), Some("acme"));
+
+        let dispatcher = KeyedDispatcher::new(KeyedDispatcherConfig::new(key), |_| async {});
+        let lane = dispatcher.lane_for(&wave);
+        let again = Wave::builder("orders.updated")
+            .metadata(serde_json::json!({"tenant": "acme"}))
+            .build();
+        assert_eq!(dispatcher.lane_for(&again), lane);
+    }
+}
@@ -0,0 +1,156 @@
+//! Reflections: faint delivery acknowledgements on `aether.echo.<channel>`.
+//!
+//! A vibrator built with [`VibratorConfig::with_reflections`](crate::VibratorConfig::with_reflections)
+//! reflects every wave it hands to the caller. Senders use
+//! [`VibratorEmitter::emit_reflected`](crate::VibratorEmitter::emit_reflected) to wait
+//! for them. A reflection only says the wave reached a receiver, not that it
+//! was processed; use commands for that.
+
+use crate::{
+    channel::Channel,
+    wave::{Wave, WaveType},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Channel prefix reflections travel under, out of reach of patterns on the
+/// reflected channel such as `orders.>`
+pub const ECHO_CHANNEL_PREFIX: &str = "aether.echo";
+
+/// Amplitude of reflection waves
+pub const REFLECTION_AMPLITUDE: f64 = 0.1;
+
+/// Channel carrying reflections of waves on `channel`
+pub fn echo_channel(channel: &Channel) -> Channel {
+    Channel::new(format!("{}.{}", ECHO_CHANNEL_PREFIX, channel.name()))
+}
+
+/// Whether `channel` carries reflections, which are never reflected again
+pub fn is_echo_channel(channel: &Channel) -> bool {
+    channel
+        .name()
+        .strip_prefix(ECHO_CHANNEL_PREFIX)
+        .is_some_and(|rest| rest.starts_with('.'))
+}
+
+/// Acknowledgement that `receiver` took delivery of a wave
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reflection {
+    pub wave_id: Uuid,
+    pub receiver: String,
+    pub channel: String,
+    pub received_at: DateTime<Utc>,
+}
+
+impl Reflection {
+    pub(crate) fn to_wave(&self, receiver: &Arc<str>) -> Wave {
+        Wave::builder(echo_channel(&Channel::new(self.channel.as_str())))
+            .wave_type(WaveType::Response)
+            .amplitude(REFLECTION_AMPLITUDE)
+            .payload(serde_json::to_value(self).unwrap_or_default())
+            .source(Arc::clone(receiver))
+            .timestamp(self.received_at)
+            .build()
+    }
+}
+
+/// Collect reflections of `wave_id` until `expected` arrived or `timeout` passed
+pub(crate) async fn await_reflections(
+    echoes: &mut broadcast::Receiver<Wave>,
+    wave_id: Uuid,
+    expected: usize,
+    timeout: Duration,
+) -> Vec<Reflection> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut reflections = Vec::new();
+    while reflections.len() < expected {
+        let wave = match tokio::time::timeout_at(deadline, echoes.recv()).await {
+            Err(_) | Ok(Err(broadcast::error::RecvError::Closed)) => break,
+            Ok(Err(broadcast::error::RecvError::Lagged(_))) => continue,
+            Ok(Ok(wave)) => wave,
+        };
+        match serde_json::from_value::<Reflection>(wave.payload().clone()) {
+            Ok(reflection) if reflection.wave_id == wave_id => reflections.push(reflection),
+            _ => continue,
+        }
+    }
+    reflections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aether::{Aether, AetherConfig};
+    use crate::vibrator::{Vibrator, VibratorConfig};
+
+    #[tokio::test]
+    async fn test_receivers_reflect_and_sender_awaits_them() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            ..AetherConfig::default()
+        });
+        let mut receivers = Vec::new();
+        for name in ["inventory", "audit"] {
+            let config = VibratorConfig::new(name)
+                .with_channels(vec![Channel::new("orders.created")])
+                .with_reflections(true);
+            receivers.push(Vibrator::new(config, &aether).await.unwrap());
+        }
+        let mut silent = Vibrator::new(
+            VibratorConfig::new("silent").with_channels(vec![Channel::new("orders.>")]),
+            &aether,
+        )
+        .await
+        .unwrap();
+        let sender = Vibrator::create("checkout", &aether).await.unwrap();
+
+        let wave = Wave::new("orders.created", serde_json::json!({ "id": 1 }));
+        let wave_id = *wave.id();
+        let mut tasks = Vec::new();
+        for mut receiver in receivers {
+            tasks.push(tokio::spawn(async move {
+                receiver.receive().await.unwrap();
+                receiver
+            }));
+        }
+        let reflections = sender
+            .emitter()
+            .emit_reflected(wave, 2, Duration::from_secs(1))
+            .await
+            .unwrap();
+
+        let mut by = reflections
+            .iter()
+            .map(|reflection| reflection.receiver.as_str())
+            .collect::<Vec<_>>();
+        by.sort();
+        assert_eq!(by, ["audit", "inventory"]);
+        assert!(reflections.iter().all(|reflection| {
+            reflection.wave_id == wave_id && reflection.channel == "orders.created"
+        }));
+
+        // Echoes stay off the silent receiver's `orders.>` pattern
+        assert_eq!(silent.receive().await.unwrap().id(), &wave_id);
+        let echo = tokio::time::timeout(Duration::from_millis(50), silent.receive()).await;
+        assert!(echo.is_err());
+        assert!(is_echo_channel(&echo_channel(&Channel::new(
+            "orders.created"
+        ))));
+        assert!(!is_echo_channel(&Channel::new("orders.created.echo")));
+
+        let unheard = Wave::new("orders.created", serde_json::json!({ "id": 2 }));
+        let reflections = sender
+            .emitter()
+            .emit_reflected(unheard, 1, Duration::from_millis(50))
+            .await
+            .unwrap();
+        assert!(reflections.is_empty());
+        for task in tasks {
+            task.await.unwrap();
+        }
+    }
+}
@@ -0,0 +1,282 @@
+//! Encryption of WaveStore log entries and projection checkpoints at rest.
+//!
+//! Each entry is sealed with AES-256-GCM under the keyring's active key and
+//! carries that key's ID, so after a rotation older entries still open with
+//! the key they were written under until `WaveStore::reencrypt` moves them
+//! to the new one. Index keys (channel names, correlation IDs) are replaced
+//! by an HMAC under a key derived from the active key, so lookups still work
+//! without the index revealing them.
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{anyhow, bail, Context, Result};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::Deserialize;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::fmt;
+
+/// First byte of an encrypted log entry
+pub(crate) const SEALED_MAGIC: u8 = 0xAF;
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Keys for `aether.persistence_encryption`
+#[derive(Debug, Clone, Deserialize)]
+pub struct EncryptionConfig {
+    /// ID of the key new entries are sealed with
+    pub active_key: String,
+    /// Every key entries may still be sealed with, the active one included
+    pub keys: Vec<EncryptionKeyConfig>,
+}
+
+/// One AES-256 key, 64 hex characters, from exactly one source
+#[derive(Debug, Clone, Deserialize)]
+pub struct EncryptionKeyConfig {
+    /// Stored with every entry sealed under this key; keep it short
+    pub id: String,
+    /// The key itself (avoid outside development)
+    #[serde(default)]
+    pub key: Option<String>,
+    /// Environment variable holding the key
+    #[serde(default)]
+    pub key_env: Option<String>,
+    /// File holding the key, e.g. a secret mounted by a KMS agent
+    #[serde(default)]
+    pub key_file: Option<String>,
+}
+
+impl EncryptionKeyConfig {
+    fn load(&self) -> Result<[u8; KEY_LEN]> {
+        let hex = match (&self.key, &self.key_env, &self.key_file) {
+            (Some(key), None, None) => key.clone(),
+            (None, Some(var), None) => std::env::var(var)
+                .with_context(|| format!("key {}: environment variable {} not set", self.id, var))?,
+            (None, None, Some(path)) => std::fs::read_to_string(path)
+                .with_context(|| format!("key {}: failed to read {}", self.id, path))?,
+            _ => bail!("key {}: set exactly one of key, key_env and key_file", self.id),
+        };
+        from_hex(hex.trim())
+            .and_then(|bytes| <[u8; KEY_LEN]>::try_from(bytes).ok())
+            .ok_or_else(|| anyhow!("key {}: expected {} hex characters", self.id, KEY_LEN * 2))
+    }
+}
+
+/// Keys a WaveStore seals and opens entries with
+#[derive(Clone)]
+pub struct Keyring {
+    active: String,
+    ciphers: HashMap<String, Aes256Gcm>,
+    /// Blinds index keys; derived from the active key
+    index_key: [u8; KEY_LEN],
+}
+
+impl fmt::Debug for Keyring {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut ids: Vec<&String> = self.ciphers.keys().collect();
+        ids.sort();
+        f.debug_struct("Keyring")
+            .field("active", &self.active)
+            .field("keys", &ids)
+            .finish()
+    }
+}
+
+impl Keyring {
+    /// A keyring sealing new entries with `key`
+    pub fn new(id: impl Into<String>, key: [u8; KEY_LEN]) -> Result<Self> {
+        let id = id.into();
+        validate_id(&id)?;
+        let mut ciphers = HashMap::new();
+        ciphers.insert(id.clone(), Aes256Gcm::new(&key.into()));
+        Ok(Self {
+            active: id,
+            ciphers,
+            index_key: derive_index_key(&key),
+        })
+    }
+
+    /// Also open entries sealed with an older key
+    pub fn with_key(mut self, id: impl Into<String>, key: [u8; KEY_LEN]) -> Result<Self> {
+        let id = id.into();
+        validate_id(&id)?;
+        self.ciphers.insert(id, Aes256Gcm::new(&key.into()));
+        Ok(self)
+    }
+
+    pub fn from_config(config: &EncryptionConfig) -> Result<Self> {
+        let mut ciphers = HashMap::new();
+        let mut index_key = None;
+        for key in &config.keys {
+            validate_id(&key.id)?;
+            let bytes = key.load()?;
+            if key.id == config.active_key {
+                index_key = Some(derive_index_key(&bytes));
+            }
+            if ciphers
+                .insert(key.id.clone(), Aes256Gcm::new(&bytes.into()))
+                .is_some()
+            {
+                bail!("key {} is listed twice", key.id);
+            }
+        }
+        let Some(index_key) = index_key else {
+            bail!("active key {} is not among the keys", config.active_key);
+        };
+        Ok(Self {
+            active: config.active_key.clone(),
+            ciphers,
+            index_key,
+        })
+    }
+
+    pub fn active_key_id(&self) -> &str {
+        &self.active
+    }
+
+    /// Encrypt `plaintext` under the active key
+    pub(crate) fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let cipher = &self.ciphers[&self.active];
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let mut entry = Vec::with_capacity(2 + self.active.len() + NONCE_LEN + plaintext.len() + 16);
+        entry.push(SEALED_MAGIC);
+        entry.push(self.active.len() as u8);
+        entry.extend_from_slice(self.active.as_bytes());
+        // The header is authenticated so an entry cannot be relabelled to another key
+        let sealed = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: &entry,
+                },
+            )
+            .map_err(|_| anyhow!("failed to encrypt log entry"))?;
+        entry.extend_from_slice(&nonce);
+        entry.extend_from_slice(&sealed);
+        Ok(entry)
+    }
+
+    /// Decrypt an entry produced by `seal`
+    pub(crate) fn open(&self, entry: &[u8]) -> std::result::Result<Vec<u8>, String> {
+        let (id, header_len) = sealed_key_id(entry).ok_or("truncated encryption header")?;
+        let cipher = self
+            .ciphers
+            .get(id)
+            .ok_or_else(|| format!("sealed with unknown key {}", id))?;
+        let body = &entry[header_len..];
+        if body.len() < NONCE_LEN {
+            return Err("truncated nonce".to_string());
+        }
+        let (nonce, sealed) = body.split_at(NONCE_LEN);
+        cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: sealed,
+                    aad: &entry[..header_len],
+                },
+            )
+            .map_err(|_| format!("failed to decrypt with key {}", id))
+    }
+
+    /// Keyed digest standing in for an index key, stable while the active key is
+    pub(crate) fn blind(&self, value: &[u8]) -> Vec<u8> {
+        let mut mac = <HmacSha256 as Mac>::new_from_slice(&self.index_key)
+            .expect("HMAC accepts any key length");
+        mac.update(value);
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    /// Whether this keyring can open entries sealed with `id`
+    pub(crate) fn has_key(&self, id: &str) -> bool {
+        self.ciphers.contains_key(id)
+    }
+}
+
+/// Key ID of a sealed entry and the length of its header
+pub(crate) fn sealed_key_id(entry: &[u8]) -> Option<(&str, usize)> {
+    if entry.first() != Some(&SEALED_MAGIC) {
+        return None;
+    }
+    let id_len = *entry.get(1)? as usize;
+    let id = entry.get(2..2 + id_len)?;
+    Some((std::str::from_utf8(id).ok()?, 2 + id_len))
+}
+
+fn derive_index_key(key: &[u8; KEY_LEN]) -> [u8; KEY_LEN] {
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(b"aether index key");
+    mac.finalize().into_bytes().into()
+}
+
+fn validate_id(id: &str) -> Result<()> {
+    if id.is_empty() || id.len() > u8::MAX as usize {
+        bail!("key ID must be 1 to 255 bytes");
+    }
+    Ok(())
+}
+
+pub(crate) fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_open_with_the_key_they_were_sealed_with() {
+        let old = Keyring::new("2026-04", [1; KEY_LEN]).unwrap();
+        let sealed = old.seal(b"ORD-1").unwrap();
+        assert_eq!(sealed_key_id(&sealed).unwrap().0, "2026-04");
+        assert!(!sealed.windows(5).any(|window| window == b"ORD-1"));
+
+        let rotated = Keyring::new("2026-10", [2; KEY_LEN])
+            .unwrap()
+            .with_key("2026-04", [1; KEY_LEN])
+            .unwrap();
+        assert_eq!(rotated.open(&sealed).unwrap(), b"ORD-1");
+        let resealed = rotated.seal(b"ORD-1").unwrap();
+        assert!(old.open(&resealed).unwrap_err().contains("unknown key 2026-10"));
+
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(rotated.open(&tampered).is_err());
+    }
+
+    #[test]
+    fn test_config_requires_one_source_per_key_and_the_active_key() {
+        let key = |id: &str| EncryptionKeyConfig {
+            id: id.to_string(),
+            key: Some("ab".repeat(KEY_LEN)),
+            key_env: None,
+            key_file: None,
+        };
+        let config = EncryptionConfig {
+            active_key: "k2".to_string(),
+            keys: vec![key("k1"), key("k2")],
+        };
+        assert_eq!(Keyring::from_config(&config).unwrap().active_key_id(), "k2");
+
+        let missing = EncryptionConfig {
+            active_key: "k3".to_string(),
+            ..config.clone()
+        };
+        assert!(Keyring::from_config(&missing).is_err());
+
+        let mut ambiguous = config;
+        ambiguous.keys[0].key_env = Some("AETHER_STORE_KEY".to_string());
+        assert!(Keyring::from_config(&ambiguous).is_err());
+    }
+}
@@ -0,0 +1,186 @@
+//! Lifecycle events: what the layer did, as values instead of log lines.
+//!
+//! [`Aether::events`] hands out a receiver for every [`AetherEvent`] published
+//! after it was created. Events are dropped when nobody is listening, and a
+//! receiver that falls behind skips the oldest ones like any broadcast
+//! receiver.
+
+use crate::{aether::Aether, flow_trace::Breadcrumb};
+use serde::Serialize;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Events buffered per receiver before the oldest are skipped
+const EVENT_BUFFER: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AetherEvent {
+    /// A local channel was created by a subscription or the first wave on it
+    ChannelCreated {
+        channel: String,
+    },
+    /// A local channel was removed; `idle` when the janitor collected it
+    ChannelRemoved {
+        channel: String,
+        idle: bool,
+    },
+    /// A receiver fell behind and missed `skipped` waves
+    SubscriberLagged {
+        subscriber: String,
+        channel: String,
+        skipped: u64,
+    },
+    NatsConnected,
+    NatsDisconnected,
+    /// Connected again after a disconnect
+    NatsReconnected,
+    /// A snapshot covering waves up to `last_index` was written
+    SnapshotSaved {
+        last_index: u64,
+        total_waves: u64,
+    },
+    /// A circuit breaker tripped after `failures` consecutive failures
+    CircuitOpened {
+        name: String,
+        failures: usize,
+    },
+    /// A circuit breaker recovered from half-open
+    CircuitClosed {
+        name: String,
+    },
+    /// `service` emitted a wave it had already emitted; `cycle` is the loop, ending in the dropped emit
+    LoopDetected {
+        wave_id: Uuid,
+        service: String,
+        cycle: Vec<Breadcrumb>,
+    },
+    /// `subscriber` heard `source` emit under a newer epoch: the producer restarted
+    SourceRestarted {
+        subscriber: String,
+        source: String,
+        previous_epoch: u64,
+        epoch: u64,
+    },
+}
+
+impl AetherEvent {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AetherEvent::ChannelCreated { .. } => "channel_created",
+            AetherEvent::ChannelRemoved { .. } => "channel_removed",
+            AetherEvent::SubscriberLagged { .. } => "subscriber_lagged",
+            AetherEvent::NatsConnected => "nats_connected",
+            AetherEvent::NatsDisconnected => "nats_disconnected",
+            AetherEvent::NatsReconnected => "nats_reconnected",
+            AetherEvent::SnapshotSaved { .. } => "snapshot_saved",
+            AetherEvent::CircuitOpened { .. } => "circuit_opened",
+            AetherEvent::CircuitClosed { .. } => "circuit_closed",
+            AetherEvent::LoopDetected { .. } => "loop_detected",
+            AetherEvent::SourceRestarted { .. } => "source_restarted",
+        }
+    }
+}
+
+/// Publishing side, shared by every clone of an `Aether` and its helpers
+#[derive(Debug, Clone)]
+pub(crate) struct EventBus {
+    sender: broadcast::Sender<AetherEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUFFER);
+        Self { sender }
+    }
+}
+
+impl EventBus {
+    pub(crate) fn publish(&self, event: AetherEvent) {
+        metrics::counter!("aether_events_total", "kind" => event.kind()).increment(1);
+        // No receivers is not an error; nobody asked to be told
+        let _ = self.sender.send(event);
+    }
+
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<AetherEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Aether {
+    /// Receive lifecycle events published from now on
+    pub fn events(&self) -> broadcast::Receiver<AetherEvent> {
+        self.event_bus().subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aether::AetherConfig;
+    use crate::channel::Channel;
+    use crate::reliability::CircuitBreaker;
+    use crate::vibrator::{Vibrator, VibratorConfig};
+    use crate::wave::Wave;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_events_report_channel_lifecycle_and_breaker_trips() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            channel_buffer_size: 2,
+            ..AetherConfig::default()
+        });
+        let mut events = aether.events();
+
+        let mut slow = Vibrator::new(
+            VibratorConfig::new("slow").with_channels(vec![Channel::new("orders.created")]),
+            &aether,
+        )
+        .await
+        .unwrap();
+        for id in 0..4 {
+            aether
+                .emit(Wave::new("orders.created", serde_json::json!({ "id": id })))
+                .await
+                .unwrap();
+        }
+        assert_eq!(slow.receive().await.unwrap().payload()["id"], 2);
+        aether
+            .remove_channel(&Channel::new("orders.created"))
+            .await
+            .unwrap();
+
+        let breaker =
+            CircuitBreaker::new(1, Duration::from_secs(60), 1).with_events("billing", &aether);
+        let _ = breaker
+            .call(|| async { Err::<(), _>(anyhow::anyhow!("down")) })
+            .await;
+
+        let mut seen = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            seen.push(event);
+        }
+        assert_eq!(
+            seen,
+            vec![
+                AetherEvent::ChannelCreated {
+                    channel: "orders.created".to_string()
+                },
+                AetherEvent::SubscriberLagged {
+                    subscriber: "slow".to_string(),
+                    channel: "orders.created".to_string(),
+                    skipped: 2
+                },
+                AetherEvent::ChannelRemoved {
+                    channel: "orders.created".to_string(),
+                    idle: false
+                },
+                AetherEvent::CircuitOpened {
+                    name: "billing".to_string(),
+                    failures: 1
+                },
+            ]
+        );
+    }
+}
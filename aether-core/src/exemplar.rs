@@ -0,0 +1,221 @@
+//! Exemplars: sampled trace IDs attached to latency histogram buckets.
+//!
+//! The Prometheus exporter has no exemplar support, so the recorder is
+//! wrapped: latency histograms remember the trace of the latest sampled
+//! observation per bucket, and the OpenMetrics rendering appends it to the
+//! matching `_bucket` line.
+
+use crate::handler_metrics::LATENCY_BUCKETS;
+use metrics::{
+    Counter, Gauge, Histogram, HistogramFn, Key, KeyName, Metadata, Recorder, SharedString, Unit,
+};
+use metrics_exporter_prometheus::formatting::key_to_parts;
+use metrics_exporter_prometheus::PrometheusRecorder;
+use opentelemetry::trace::TraceContextExt;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Histograms with this suffix get buckets, and so exemplars
+pub(crate) const LATENCY_SUFFIX: &str = "_seconds";
+
+struct Exemplar {
+    trace_id: String,
+    value: f64,
+    timestamp: f64,
+}
+
+/// Latest exemplar per bucket, keyed by the bucket's series (e.g.
+/// `aether_handler_duration_seconds_bucket{channel="orders",le="0.01"}`)
+#[derive(Default)]
+pub(crate) struct Exemplars {
+    latest: Mutex<HashMap<String, Exemplar>>,
+}
+
+impl Exemplars {
+    fn store(&self, series: &str, value: f64, trace_id: String) {
+        let timestamp = chrono::Utc::now().timestamp_micros() as f64 / 1_000_000.0;
+        let exemplar = Exemplar {
+            trace_id,
+            value,
+            timestamp,
+        };
+        let mut latest = self.latest.lock().expect("exemplars poisoned");
+        match latest.get_mut(series) {
+            Some(slot) => *slot = exemplar,
+            None => {
+                latest.insert(series.to_string(), exemplar);
+            }
+        }
+    }
+
+    /// Convert Prometheus text output to OpenMetrics with exemplars
+    pub(crate) fn render_openmetrics(&self, prometheus: &str) -> String {
+        let latest = self.latest.lock().expect("exemplars poisoned");
+        let mut output = String::with_capacity(prometheus.len());
+        for line in prometheus.lines() {
+            if let Some(meta) = line.strip_prefix("# ") {
+                // OpenMetrics names counter families without the `_total` suffix
+                let mut parts = meta.splitn(3, ' ');
+                if let (Some(kind), Some(name)) = (parts.next(), parts.next()) {
+                    let family = name.strip_suffix("_total").unwrap_or(name);
+                    let _ = write!(output, "# {} {}", kind, family);
+                    if let Some(rest) = parts.next() {
+                        let _ = write!(output, " {}", rest);
+                    }
+                    output.push('\n');
+                    continue;
+                }
+            }
+            output.push_str(line);
+            let exemplar = line
+                .rsplit_once(' ')
+                .and_then(|(series, _)| latest.get(series));
+            if let Some(exemplar) = exemplar {
+                let _ = write!(
+                    output,
+                    " # {{trace_id=\"{}\"}} {} {}",
+                    exemplar.trace_id, exemplar.value, exemplar.timestamp
+                );
+            }
+            output.push('\n');
+        }
+        output.push_str("# EOF\n");
+        output
+    }
+}
+
+/// Prometheus recorder whose latency histograms also capture exemplars
+pub(crate) struct ExemplarRecorder {
+    inner: PrometheusRecorder,
+    exemplars: Arc<Exemplars>,
+}
+
+impl ExemplarRecorder {
+    pub(crate) fn new(inner: PrometheusRecorder, exemplars: Arc<Exemplars>) -> Self {
+        Self { inner, exemplars }
+    }
+}
+
+impl Recorder for ExemplarRecorder {
+    fn describe_counter(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.inner.describe_counter(key, unit, description)
+    }
+
+    fn describe_gauge(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.inner.describe_gauge(key, unit, description)
+    }
+
+    fn describe_histogram(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.inner.describe_histogram(key, unit, description)
+    }
+
+    fn register_counter(&self, key: &Key, metadata: &Metadata<'_>) -> Counter {
+        self.inner.register_counter(key, metadata)
+    }
+
+    fn register_gauge(&self, key: &Key, metadata: &Metadata<'_>) -> Gauge {
+        self.inner.register_gauge(key, metadata)
+    }
+
+    fn register_histogram(&self, key: &Key, metadata: &Metadata<'_>) -> Histogram {
+        let inner = self.inner.register_histogram(key, metadata);
+        if !key.name().ends_with(LATENCY_SUFFIX) {
+            return inner;
+        }
+        Histogram::from_arc(Arc::new(ExemplarHistogram {
+            inner,
+            buckets: bucket_series(key),
+            exemplars: Arc::clone(&self.exemplars),
+        }))
+    }
+}
+
+struct ExemplarHistogram {
+    inner: Histogram,
+    /// Series name of each bucket in `LATENCY_BUCKETS`, then `+Inf`
+    buckets: Vec<String>,
+    exemplars: Arc<Exemplars>,
+}
+
+impl HistogramFn for ExemplarHistogram {
+    fn record(&self, value: f64) {
+        self.inner.record(value);
+        if let Some(trace_id) = sampled_trace_id() {
+            let bucket = LATENCY_BUCKETS
+                .iter()
+                .position(|bound| value <= *bound)
+                .unwrap_or(LATENCY_BUCKETS.len());
+            self.exemplars.store(&self.buckets[bucket], value, trace_id);
+        }
+    }
+}
+
+/// Bucket series names exactly as the exporter renders them
+fn bucket_series(key: &Key) -> Vec<String> {
+    let (name, labels) = key_to_parts(key, None);
+    let bounds = LATENCY_BUCKETS
+        .iter()
+        .map(f64::to_string)
+        .chain(std::iter::once("+Inf".to_string()));
+    bounds
+        .map(|bound| {
+            let mut series = format!("{}_bucket{{", name);
+            for label in &labels {
+                let _ = write!(series, "{},", label);
+            }
+            let _ = write!(series, "le=\"{}\"}}", bound);
+            series
+        })
+        .collect()
+}
+
+/// Trace ID of the current span, if it will be exported
+fn sampled_trace_id() -> Option<String> {
+    let context = tracing::Span::current().context();
+    let span = context.span();
+    let span_context = span.span_context();
+    (span_context.is_valid() && span_context.is_sampled())
+        .then(|| span_context.trace_id().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openmetrics_rendering_attaches_exemplars() {
+        let exemplars = Exemplars::default();
+        let key = Key::from_parts(
+            "aether_handler_duration_seconds",
+            vec![metrics::Label::new("channel", "orders.created")],
+        );
+        let series = bucket_series(&key);
+        exemplars.store(
+            &series[3],
+            0.004,
+            "4bf92f3577b34da6a3ce929d0e0e4736".to_string(),
+        );
+
+        let prometheus = format!(
+            "# TYPE aether_waves_total counter\naether_waves_total 3\n\
+             # TYPE aether_handler_duration_seconds histogram\n{} 1\n{} 1\n",
+            series[2], series[3]
+        );
+        let rendered = exemplars.render_openmetrics(&prometheus);
+        let lines: Vec<&str> = rendered.lines().collect();
+
+        assert_eq!(lines[0], "# TYPE aether_waves counter");
+        assert_eq!(lines[1], "aether_waves_total 3");
+        assert_eq!(
+            lines[3],
+            "aether_handler_duration_seconds_bucket{channel=\"orders.created\",le=\"0.0025\"} 1"
+        );
+        assert!(lines[4].starts_with(
+            "aether_handler_duration_seconds_bucket{channel=\"orders.created\",le=\"0.005\"} 1 \
+             # {trace_id=\"4bf92f3577b34da6a3ce929d0e0e4736\"} 0.004 "
+        ));
+        assert_eq!(lines.last(), Some(&"# EOF"));
+    }
+}
@@ -0,0 +1,258 @@
+//! Subscription filters: drop unwanted waves before they are delivered.
+//!
+//! A filter is a closure or a small expression over the payload:
+//!
+//! ```text
+//! /region == "eu" && /total >= 100 || /priority
+//! ```
+//!
+//! Operands are JSON pointers (RFC 6901) and JSON literals; `&&` binds tighter
+//! than `||`. A bare pointer is true when the field is present and not
+//! `null` or `false`. Filters run in the vibrator after the NATS bridge, so
+//! narrow the channel itself to cut transport traffic.
+
+use crate::{wave::Wave, AetherError, Result};
+use serde_json::Value;
+use std::fmt;
+use std::sync::Arc;
+
+/// Predicate deciding whether a subscription delivers a wave
+#[derive(Clone)]
+pub struct WaveFilter {
+    description: Arc<str>,
+    predicate: Arc<dyn Fn(&Wave) -> bool + Send + Sync>,
+}
+
+impl WaveFilter {
+    pub fn new(predicate: impl Fn(&Wave) -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            description: "<closure>".into(),
+            predicate: Arc::new(predicate),
+        }
+    }
+
+    /// Compile a filter expression
+    pub fn parse(expr: &str) -> Result<Self> {
+        let invalid = |reason: String| {
+            AetherError::ValidationFailed(format!("filter `{}`: {}", expr, reason))
+        };
+        let tokens = tokenize(expr).map_err(invalid)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let compiled = parser.any().map_err(invalid)?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(invalid(format!("unexpected {:?}", token)));
+        }
+        Ok(Self {
+            description: expr.into(),
+            predicate: Arc::new(move |wave| compiled.eval(wave.payload())),
+        })
+    }
+
+    pub fn matches(&self, wave: &Wave) -> bool {
+        (self.predicate)(wave)
+    }
+}
+
+impl fmt::Debug for WaveFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("WaveFilter")
+            .field(&self.description)
+            .finish()
+    }
+}
+
+impl TryFrom<&str> for WaveFilter {
+    type Error = AetherError;
+
+    fn try_from(expr: &str) -> Result<Self> {
+        Self::parse(expr)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Pointer(String),
+    Literal(Value),
+    Op(Op),
+    And,
+    Or,
+}
+
+enum Expr {
+    Any(Vec<Expr>),
+    All(Vec<Expr>),
+    Truthy(String),
+    Compare(String, Op, Value),
+}
+
+impl Expr {
+    fn eval(&self, payload: &Value) -> bool {
+        match self {
+            Expr::Any(exprs) => exprs.iter().any(|expr| expr.eval(payload)),
+            Expr::All(exprs) => exprs.iter().all(|expr| expr.eval(payload)),
+            Expr::Truthy(pointer) => !matches!(
+                payload.pointer(pointer),
+                None | Some(Value::Null) | Some(Value::Bool(false))
+            ),
+            Expr::Compare(pointer, op, expected) => payload
+                .pointer(pointer)
+                .is_some_and(|actual| compare(actual, *op, expected)),
+        }
+    }
+}
+
+/// Numbers compare by value, strings lexically; other types only by (in)equality
+fn compare(actual: &Value, op: Op, expected: &Value) -> bool {
+    let ordering = match (actual, expected) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    };
+    match (op, ordering) {
+        (Op::Eq, Some(ordering)) => ordering.is_eq(),
+        (Op::Ne, Some(ordering)) => ordering.is_ne(),
+        (Op::Eq, None) => actual == expected,
+        (Op::Ne, None) => actual != expected,
+        (Op::Gt, Some(ordering)) => ordering.is_gt(),
+        (Op::Ge, Some(ordering)) => ordering.is_ge(),
+        (Op::Lt, Some(ordering)) => ordering.is_lt(),
+        (Op::Le, Some(ordering)) => ordering.is_le(),
+        _ => false,
+    }
+}
+
+fn tokenize(expr: &str) -> std::result::Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = expr.trim_start();
+    while !rest.is_empty() {
+        let (token, len) = if rest.starts_with('/') {
+            let len = rest
+                .find(|c: char| c.is_whitespace() || "=!<>&|".contains(c))
+                .unwrap_or(rest.len());
+            (Token::Pointer(rest[..len].to_string()), len)
+        } else if let Some((op, len)) = [
+            ("==", Token::Op(Op::Eq)),
+            ("!=", Token::Op(Op::Ne)),
+            (">=", Token::Op(Op::Ge)),
+            ("<=", Token::Op(Op::Le)),
+            (">", Token::Op(Op::Gt)),
+            ("<", Token::Op(Op::Lt)),
+            ("&&", Token::And),
+            ("||", Token::Or),
+        ]
+        .into_iter()
+        .find(|(symbol, _)| rest.starts_with(symbol))
+        .map(|(symbol, token)| (token, symbol.len()))
+        {
+            (op, len)
+        } else {
+            // A JSON literal runs to the end of its string, or to the next space
+            let mut stream = serde_json::Deserializer::from_str(rest).into_iter::<Value>();
+            match stream.next() {
+                Some(Ok(value)) => (Token::Literal(value), stream.byte_offset()),
+                _ => {
+                    let word = rest.split_whitespace().next().unwrap_or(rest);
+                    return Err(format!("invalid literal `{}`", word));
+                }
+            }
+        };
+        tokens.push(token);
+        rest = rest[len..].trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn any(&mut self) -> std::result::Result<Expr, String> {
+        let mut exprs = vec![self.all()?];
+        while self.eat(&Token::Or) {
+            exprs.push(self.all()?);
+        }
+        Ok(Expr::Any(exprs))
+    }
+
+    fn all(&mut self) -> std::result::Result<Expr, String> {
+        let mut exprs = vec![self.clause()?];
+        while self.eat(&Token::And) {
+            exprs.push(self.clause()?);
+        }
+        Ok(Expr::All(exprs))
+    }
+
+    fn clause(&mut self) -> std::result::Result<Expr, String> {
+        let Some(Token::Pointer(pointer)) = self.next() else {
+            return Err("expected a JSON pointer such as /total".to_string());
+        };
+        let Some(Token::Op(op)) = self.tokens.get(self.pos).cloned() else {
+            return Ok(Expr::Truthy(pointer));
+        };
+        self.pos += 1;
+        match self.next() {
+            Some(Token::Literal(value)) => Ok(Expr::Compare(pointer, op, value)),
+            _ => Err(format!("expected a literal after {}", pointer)),
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let found = self.tokens.get(self.pos) == Some(token);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(payload: Value) -> Wave {
+        Wave::new("orders.created", payload)
+    }
+
+    #[test]
+    fn test_expression_filters_payloads() {
+        let filter = WaveFilter::parse(r#"/region == "eu" && /total >= 100 || /rush"#).unwrap();
+        assert!(filter.matches(&order(serde_json::json!({"region": "eu", "total": 100.0}))));
+        assert!(!filter.matches(&order(serde_json::json!({"region": "eu", "total": 99}))));
+        assert!(!filter.matches(&order(serde_json::json!({"region": "us", "total": 500}))));
+        assert!(filter.matches(&order(serde_json::json!({"region": "us", "rush": true}))));
+        assert!(!filter.matches(&order(serde_json::json!({"rush": null}))));
+
+        let nested = WaveFilter::parse(r#"/customer/tier != "free""#).unwrap();
+        assert!(nested.matches(&order(serde_json::json!({"customer": {"tier": "gold"}}))));
+        assert!(!nested.matches(&order(serde_json::json!({}))));
+
+        for invalid in ["", "/total >", "total > 1", "/a == 1 &&", "/a == nope"] {
+            assert!(
+                matches!(
+                    WaveFilter::parse(invalid),
+                    Err(AetherError::ValidationFailed(_))
+                ),
+                "{:?} should not parse",
+                invalid
+            );
+        }
+    }
+}
@@ -0,0 +1,418 @@
+//! Flow tracing: hop-by-hop breadcrumbs on waves, and an index that replays them.
+//!
+//! With `[aether.flow_trace]` set, every emit appends a [`Breadcrumb`] (who
+//! emitted, where, when) to the wave's `trace` metadata. Re-emitting a received
+//! wave keeps its ID and trail, so the trail shows every service that passed it
+//! on. Trails are capped: the origin is always kept, then the newest hops, and
+//! `trace_dropped` counts the ones cut from the middle.
+//!
+//! A [`FlowTraceIndex`] (the gateway runs one) remembers the latest trail per
+//! wave ID and how many times the wave was emitted.
+//!
+//! With `detect_loops`, a wave whose trail already names the emitting service
+//! is dropped as a loop instead of circling until `max_propagation`, and an
+//! [`AetherEvent::LoopDetected`] names the cycle. Waves emitted through
+//! `Aether::emit` rather than a vibrator are attributed to their source, so
+//! raw re-emits of a wave count as the origin emitting it again.
+
+use crate::{aether::Aether, channel::Channel, events::AetherEvent, wave::Wave};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// Metadata key holding the breadcrumb trail
+pub const TRACE_KEY: &str = "trace";
+/// Metadata key counting breadcrumbs cut to respect the cap
+pub const TRACE_DROPPED_KEY: &str = "trace_dropped";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FlowTraceConfig {
+    /// Breadcrumbs kept per wave, the origin included
+    #[serde(default = "default_max_breadcrumbs")]
+    pub max_breadcrumbs: usize,
+    /// Wave IDs remembered by a [`FlowTraceIndex`]
+    #[serde(default = "default_index_capacity")]
+    pub index_capacity: usize,
+    /// Drop waves whose trail already names the emitting service
+    #[serde(default)]
+    pub detect_loops: bool,
+    /// How long the channel pair that closed a loop stays blocked; 0 to never block
+    #[serde(default)]
+    pub loop_quarantine_ms: u64,
+}
+
+impl Default for FlowTraceConfig {
+    fn default() -> Self {
+        Self {
+            max_breadcrumbs: default_max_breadcrumbs(),
+            index_capacity: default_index_capacity(),
+            detect_loops: false,
+            loop_quarantine_ms: 0,
+        }
+    }
+}
+
+fn default_max_breadcrumbs() -> usize {
+    16
+}
+
+fn default_index_capacity() -> usize {
+    10_000
+}
+
+/// One emit of a wave
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Breadcrumb {
+    pub service: String,
+    pub channel: String,
+    pub at: DateTime<Utc>,
+    /// Propagation count after this emit
+    pub hop: u32,
+}
+
+impl Wave {
+    /// Breadcrumb trail, oldest first; empty unless flow tracing is on
+    pub fn breadcrumbs(&self) -> Vec<Breadcrumb> {
+        self.metadata()
+            .get(TRACE_KEY)
+            .cloned()
+            .and_then(|trail| serde_json::from_value(trail).ok())
+            .unwrap_or_default()
+    }
+
+    /// Breadcrumbs cut from the middle of the trail
+    pub fn breadcrumbs_dropped(&self) -> u64 {
+        self.metadata()
+            .get(TRACE_DROPPED_KEY)
+            .and_then(|dropped| dropped.as_u64())
+            .unwrap_or(0)
+    }
+
+    /// Forget the trail, e.g. before replaying a stored wave as a fresh emit
+    pub fn clear_breadcrumbs(&mut self) {
+        if let Some(metadata) = self.metadata_mut().as_object_mut() {
+            metadata.remove(TRACE_KEY);
+            metadata.remove(TRACE_DROPPED_KEY);
+        }
+    }
+
+    pub(crate) fn push_breadcrumb(&mut self, breadcrumb: Breadcrumb, max_breadcrumbs: usize) {
+        let breadcrumb = match serde_json::to_value(breadcrumb) {
+            Ok(breadcrumb) => breadcrumb,
+            Err(_) => return,
+        };
+        let metadata = self.metadata_mut();
+        if !metadata.is_object() {
+            *metadata = serde_json::json!({});
+        }
+        let Some(metadata) = metadata.as_object_mut() else {
+            return;
+        };
+        let trail = metadata
+            .entry(TRACE_KEY)
+            .or_insert_with(|| serde_json::Value::Array(Vec::new()));
+        if !trail.is_array() {
+            *trail = serde_json::Value::Array(Vec::new());
+        }
+        let Some(trail) = trail.as_array_mut() else {
+            return;
+        };
+        trail.push(breadcrumb);
+        let mut dropped = 0;
+        while trail.len() > max_breadcrumbs.max(1) {
+            // Keep where it started; lose the oldest relays
+            trail.remove(1);
+            dropped += 1;
+        }
+        if dropped > 0 {
+            let total = metadata
+                .get(TRACE_DROPPED_KEY)
+                .and_then(|dropped| dropped.as_u64())
+                .unwrap_or(0)
+                + dropped;
+            metadata.insert(TRACE_DROPPED_KEY.to_string(), total.into());
+        }
+    }
+}
+
+/// What the loop guard decided about an emit
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum LoopCheck {
+    Clear,
+    /// The emitter is already on the trail; `cycle` runs from its earlier emit to this one
+    Loop {
+        cycle: Vec<Breadcrumb>,
+    },
+    /// Waves from `from` re-emitted onto `to` are blocked
+    Quarantined {
+        from: String,
+        to: String,
+    },
+}
+
+/// Channel pairs blocked after closing a loop, shared by every clone of an `Aether`
+#[derive(Debug, Default)]
+pub(crate) struct LoopGuard {
+    quarantined: Mutex<HashMap<(String, String), DateTime<Utc>>>,
+}
+
+impl LoopGuard {
+    /// Judge the emit that would append `next` to `trail`
+    pub(crate) fn check(
+        &self,
+        trail: &[Breadcrumb],
+        next: &Breadcrumb,
+        config: &FlowTraceConfig,
+    ) -> LoopCheck {
+        let pair = trail
+            .last()
+            .map(|previous| (previous.channel.clone(), next.channel.clone()));
+        let mut quarantined = self.quarantined.lock().expect("loop guard lock poisoned");
+        quarantined.retain(|_, until| *until > next.at);
+        if let Some((from, to)) = pair.as_ref().filter(|pair| quarantined.contains_key(pair)) {
+            return LoopCheck::Quarantined {
+                from: from.clone(),
+                to: to.clone(),
+            };
+        }
+        if !config.detect_loops {
+            return LoopCheck::Clear;
+        }
+        let Some(start) = trail.iter().position(|hop| hop.service == next.service) else {
+            return LoopCheck::Clear;
+        };
+        if let Some(pair) = pair.filter(|_| config.loop_quarantine_ms > 0) {
+            let until = next.at + chrono::Duration::milliseconds(config.loop_quarantine_ms as i64);
+            quarantined.insert(pair, until);
+        }
+        let mut cycle = trail[start..].to_vec();
+        cycle.push(next.clone());
+        LoopCheck::Loop { cycle }
+    }
+}
+
+impl AetherEvent {
+    pub(crate) fn loop_detected(wave: &Wave, cycle: Vec<Breadcrumb>) -> Self {
+        AetherEvent::LoopDetected {
+            wave_id: *wave.id(),
+            service: cycle
+                .last()
+                .map(|hop| hop.service.clone())
+                .unwrap_or_default(),
+            cycle,
+        }
+    }
+}
+
+/// Everything known about one wave's path
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WavePath {
+    pub wave_id: Uuid,
+    /// Times the wave was seen emitted
+    pub emits: u64,
+    /// Breadcrumbs cut from the longest trail seen
+    pub dropped: u64,
+    /// Longest trail seen, oldest first
+    pub hops: Vec<Breadcrumb>,
+}
+
+#[derive(Debug, Default)]
+struct IndexedPaths {
+    paths: HashMap<Uuid, WavePath>,
+    order: VecDeque<Uuid>,
+}
+
+/// Latest trail per traced wave ID; clones share the index
+#[derive(Debug, Clone)]
+pub struct FlowTraceIndex {
+    capacity: usize,
+    indexed: Arc<Mutex<IndexedPaths>>,
+}
+
+impl FlowTraceIndex {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            indexed: Arc::default(),
+        }
+    }
+
+    /// Remember `wave`'s trail; waves without breadcrumbs are ignored
+    pub fn record(&self, wave: &Wave) {
+        let hops = wave.breadcrumbs();
+        if hops.is_empty() {
+            return;
+        }
+        let mut indexed = self.indexed.lock().expect("flow trace lock poisoned");
+        let id = *wave.id();
+        if !indexed.paths.contains_key(&id) {
+            if indexed.order.len() >= self.capacity {
+                if let Some(oldest) = indexed.order.pop_front() {
+                    indexed.paths.remove(&oldest);
+                }
+            }
+            indexed.order.push_back(id);
+        }
+        let path = indexed.paths.entry(id).or_insert_with(|| WavePath {
+            wave_id: id,
+            emits: 0,
+            dropped: 0,
+            hops: Vec::new(),
+        });
+        path.emits += 1;
+        // Copies can arrive out of order; the furthest-travelled one has the full trail
+        let furthest = |hops: &[Breadcrumb]| hops.last().map_or(0, |hop| hop.hop);
+        if furthest(&hops) >= furthest(&path.hops) {
+            path.hops = hops;
+            path.dropped = wave.breadcrumbs_dropped();
+        }
+    }
+
+    pub fn path(&self, wave_id: &Uuid) -> Option<WavePath> {
+        self.indexed
+            .lock()
+            .expect("flow trace lock poisoned")
+            .paths
+            .get(wave_id)
+            .cloned()
+    }
+
+    /// Record every wave on the layer until the task is aborted
+    pub async fn spawn(&self, aether: &Aether) -> JoinHandle<()> {
+        let stream = aether.tap(Channel::new(">")).await;
+        let index = self.clone();
+        tokio::spawn(async move {
+            futures::pin_mut!(stream);
+            while let Some(wave) = stream.next().await {
+                index.record(&wave);
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aether::AetherConfig;
+    use crate::vibrator::{Vibrator, VibratorConfig};
+
+    #[tokio::test]
+    async fn test_breadcrumbs_follow_re_emits_and_index_rebuilds_path() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            flow_trace: Some(FlowTraceConfig {
+                max_breadcrumbs: 3,
+                ..FlowTraceConfig::default()
+            }),
+            ..AetherConfig::default()
+        });
+        let index = FlowTraceIndex::new(100);
+        let task = index.spawn(&aether).await;
+
+        let mut consumer = Vibrator::new(
+            VibratorConfig::new("fulfillment").with_channels(vec![Channel::new("orders.>")]),
+            &aether,
+        )
+        .await
+        .unwrap();
+        let checkout = Vibrator::new(VibratorConfig::new("checkout"), &aether)
+            .await
+            .unwrap();
+        let replayer = Vibrator::new(VibratorConfig::new("replayer"), &aether)
+            .await
+            .unwrap();
+        checkout
+            .emit_wave("orders.created", serde_json::json!({ "id": 7 }))
+            .await
+            .unwrap();
+        let mut wave = consumer.receive().await.unwrap();
+        for _ in 0..4 {
+            replayer.emit(wave.clone()).await.unwrap();
+            wave = consumer.receive().await.unwrap();
+        }
+
+        let trail = wave.breadcrumbs();
+        assert_eq!(
+            trail.iter().map(|b| b.service.as_str()).collect::<Vec<_>>(),
+            ["checkout", "replayer", "replayer"]
+        );
+        assert_eq!(trail.iter().map(|b| b.hop).collect::<Vec<_>>(), [1, 4, 5]);
+        assert_eq!(wave.breadcrumbs_dropped(), 2);
+
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        let path = index.path(wave.id()).unwrap();
+        assert_eq!(path.emits, 5);
+        assert_eq!(path.dropped, 2);
+        assert_eq!(path.hops, trail);
+        task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_repeat_emitter_is_dropped_as_loop_and_pair_quarantined() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            flow_trace: Some(FlowTraceConfig {
+                detect_loops: true,
+                loop_quarantine_ms: 60_000,
+                ..FlowTraceConfig::default()
+            }),
+            ..AetherConfig::default()
+        });
+        let mut consumer = Vibrator::new(
+            VibratorConfig::new("fulfillment").with_channels(vec![Channel::new("orders.>")]),
+            &aether,
+        )
+        .await
+        .unwrap();
+        let checkout = Vibrator::new(VibratorConfig::new("checkout"), &aether)
+            .await
+            .unwrap();
+        let replayer = Vibrator::new(VibratorConfig::new("replayer"), &aether)
+            .await
+            .unwrap();
+
+        checkout
+            .emit_wave("orders.created", serde_json::json!({ "id": 7 }))
+            .await
+            .unwrap();
+        let first = consumer.receive().await.unwrap();
+        replayer.emit(first.clone()).await.unwrap();
+        let relayed = consumer.receive().await.unwrap();
+        let relayed_sequence = relayed.sequence().unwrap();
+        // Second pass through the replayer closes the loop
+        let mut events = aether.events();
+        replayer.emit(relayed).await.unwrap();
+
+        let event = events.try_recv().unwrap();
+        let after_loop = Wave::builder("orders.created").source("checkout").build();
+        checkout.emit(after_loop).await.unwrap();
+        let next = consumer.receive().await.unwrap();
+        // The dropped re-emit took no sequence number, so receivers see no gap
+        assert_eq!(next.sequence(), Some(relayed_sequence + 1));
+        let AetherEvent::LoopDetected {
+            wave_id,
+            service,
+            cycle,
+        } = event
+        else {
+            panic!("expected a loop, got {:?}", event);
+        };
+        assert_eq!((wave_id, service.as_str()), (*first.id(), "replayer"));
+        assert_eq!(cycle.iter().map(|hop| hop.hop).collect::<Vec<_>>(), [2, 3]);
+
+        // orders.created -> orders.created is now blocked for any re-emit
+        let guard = Vibrator::new(VibratorConfig::new("auditor"), &aether)
+            .await
+            .unwrap();
+        guard.emit(first).await.unwrap();
+        checkout
+            .emit_wave("orders.created", serde_json::json!({ "id": 8 }))
+            .await
+            .unwrap();
+        assert_eq!(consumer.receive().await.unwrap().payload()["id"], 8);
+    }
+}
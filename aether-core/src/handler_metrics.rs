@@ -0,0 +1,127 @@
+//! Handler metrics: latency, outcome and inflight counts per channel.
+
+use crate::sampling::ERROR_ATTRIBUTE;
+use futures::FutureExt;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use tokio::time::Instant;
+use tracing::{info_span, Instrument};
+
+/// Bucket bounds (seconds) for the latency histograms
+pub const LATENCY_BUCKETS: &[f64] = &[
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Handler return values that map to an `outcome` label
+pub trait HandlerOutcome {
+    fn outcome(&self) -> &'static str;
+}
+
+impl HandlerOutcome for () {
+    fn outcome(&self) -> &'static str {
+        "ok"
+    }
+}
+
+impl<T, E> HandlerOutcome for std::result::Result<T, E> {
+    fn outcome(&self) -> &'static str {
+        match self {
+            Ok(_) => "ok",
+            Err(_) => "error",
+        }
+    }
+}
+
+/// Run a handler, recording `aether_handler_duration_seconds{channel,outcome}`
+/// and `aether_handler_inflight{channel}`
+///
+/// The handler runs in an `aether.handle` span whose trace becomes the
+/// histogram exemplar. Panics are recorded with outcome `panic` and then
+/// resumed.
+pub async fn observe_handler<F>(channel: &str, handler: F) -> F::Output
+where
+    F: Future,
+    F::Output: HandlerOutcome,
+{
+    let channel = channel.to_string();
+    let inflight = metrics::gauge!("aether_handler_inflight", "channel" => channel.clone());
+    inflight.increment(1.0);
+    let span = info_span!(
+        "aether.handle",
+        aether.channel = %channel,
+        error = tracing::field::Empty,
+    );
+    let started = Instant::now();
+    let result = AssertUnwindSafe(handler)
+        .catch_unwind()
+        .instrument(span.clone())
+        .await;
+    inflight.decrement(1.0);
+
+    let outcome = match &result {
+        Ok(output) => output.outcome(),
+        Err(_) => "panic",
+    };
+    if outcome != "ok" {
+        span.record(ERROR_ATTRIBUTE, true);
+    }
+    let elapsed = started.elapsed().as_secs_f64();
+    span.in_scope(|| {
+        metrics::histogram!(
+            "aether_handler_duration_seconds",
+            "channel" => channel,
+            "outcome" => outcome
+        )
+        .record(elapsed)
+    });
+
+    match result {
+        Ok(output) => output,
+        Err(panic) => std::panic::resume_unwind(panic),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observability::prometheus_builder;
+
+    async fn buggy() {
+        panic!("handler bug");
+    }
+
+    #[test]
+    fn test_outcomes_are_labelled() {
+        let recorder = prometheus_builder().unwrap().build_recorder();
+        let handle = recorder.handle();
+        metrics::with_local_recorder(&recorder, || {
+            futures::executor::block_on(async {
+                observe_handler("orders.created", async {}).await;
+                let failed: Result<(), &str> =
+                    observe_handler("orders.created", async { Err("boom") }).await;
+                assert!(failed.is_err());
+                let panicked = AssertUnwindSafe(observe_handler("orders.paid", buggy()))
+                    .catch_unwind()
+                    .await;
+                assert!(panicked.is_err());
+            })
+        });
+
+        let rendered = handle.render();
+        for labels in [
+            r#"channel="orders.created",outcome="ok""#,
+            r#"channel="orders.created",outcome="error""#,
+            r#"channel="orders.paid",outcome="panic""#,
+        ] {
+            let count = format!("aether_handler_duration_seconds_count{{{}}} 1", labels);
+            assert!(
+                rendered.contains(&count),
+                "missing {} in\n{}",
+                count,
+                rendered
+            );
+        }
+        assert!(rendered.contains("aether_handler_duration_seconds_bucket"));
+        assert!(rendered.contains(r#"aether_handler_inflight{channel="orders.paid"} 0"#));
+    }
+}
@@ -0,0 +1,544 @@
+//! Frequency hopping schedules shared between a sender and its receivers.
+//!
+//! The sender announces a signed schedule (seed, interval, epoch) on the base
+//! channel. Receivers verify it, note how far their clock is from the
+//! sender's, and listen on the hop the sender is using rather than guessing
+//! from their own clock.
+//!
+//! Time-based hopping can also be keyed: with a shared secret configured for
+//! a base channel, hop channels are named from HMAC(secret, channel, slot)
+//! instead of indexing the public `hop0..hopN` set, so only key holders can
+//! tell which hop is live.
+
+use crate::{
+    channel::Channel,
+    wave::{Wave, WaveType},
+    AetherError, Result,
+};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HoppingConfig {
+    /// Shared secret per base channel or pattern; the longest matching pattern wins
+    #[serde(default)]
+    pub keys: BTreeMap<String, String>,
+    /// Channels without a key hop on the old name-derived scheme; when false
+    /// emitting on them fails
+    #[serde(default = "default_legacy_fallback")]
+    pub legacy_fallback: bool,
+}
+
+impl Default for HoppingConfig {
+    fn default() -> Self {
+        Self {
+            keys: BTreeMap::new(),
+            legacy_fallback: default_legacy_fallback(),
+        }
+    }
+}
+
+fn default_legacy_fallback() -> bool {
+    true
+}
+
+/// Hop index derivation for time-based hopping
+#[derive(Debug, Clone)]
+pub struct HopKeys {
+    keys: Vec<(Channel, Vec<u8>)>,
+    legacy_fallback: bool,
+}
+
+impl HopKeys {
+    pub fn new(config: &HoppingConfig) -> Self {
+        let mut keys: Vec<(Channel, Vec<u8>)> = config
+            .keys
+            .iter()
+            .map(|(pattern, secret)| (Channel::new(pattern), secret.as_bytes().to_vec()))
+            .collect();
+        keys.sort_by_key(|(pattern, _)| std::cmp::Reverse(pattern.name().len()));
+        Self {
+            keys,
+            legacy_fallback: config.legacy_fallback,
+        }
+    }
+
+    /// Secret for a base channel
+    pub fn key_for(&self, base: &Channel) -> Option<&[u8]> {
+        self.keys
+            .iter()
+            .find(|(pattern, _)| base.matches(pattern))
+            .map(|(_, secret)| secret.as_slice())
+    }
+
+    /// Current hop plus the neighbouring hop when within `grace_ms` of a
+    /// slot boundary, current first
+    pub fn hops_near(
+        &self,
+        base: &Channel,
+        timestamp_ms: u64,
+        hop_count: u16,
+        hop_interval_ms: u64,
+        grace_ms: u64,
+    ) -> Result<Vec<Channel>> {
+        let mut hops: Vec<Channel> = Vec::with_capacity(2);
+        for at in slot_times_near(timestamp_ms, 0, hop_interval_ms, grace_ms) {
+            let hop = self.hop_at_ms(base, at, hop_count, hop_interval_ms)?;
+            if !hops.contains(&hop) {
+                hops.push(hop);
+            }
+        }
+        Ok(hops)
+    }
+
+    /// Hop channel at a timestamp (milliseconds since epoch)
+    ///
+    /// `hop_count` only bounds the legacy scheme; keyed hops are named from
+    /// the MAC itself.
+    pub fn hop_at_ms(
+        &self,
+        base: &Channel,
+        timestamp_ms: u64,
+        hop_count: u16,
+        hop_interval_ms: u64,
+    ) -> Result<Channel> {
+        match self.key_for(base) {
+            Some(secret) => {
+                let slot = timestamp_ms / hop_interval_ms.max(1);
+                Ok(keyed_hop(secret, base, slot))
+            }
+            None if self.legacy_fallback => {
+                Ok(base.hop_at_ms(timestamp_ms, hop_count, hop_interval_ms))
+            }
+            None => Err(AetherError::ValidationFailed(format!(
+                "no hop key configured for {}",
+                base
+            ))),
+        }
+    }
+}
+
+impl Default for HopKeys {
+    /// No keys; every channel uses the legacy scheme
+    fn default() -> Self {
+        Self::new(&HoppingConfig::default())
+    }
+}
+
+/// Hop channel for a slot, `<base>.hop<n>` with `n` taken from HMAC-SHA256(secret, channel, slot)
+///
+/// `n` is 64 bits of the MAC rather than an index into the public
+/// `hop0..hopN` set, so the live hop can't be enumerated without the key.
+pub fn keyed_hop(secret: &[u8], base: &Channel, slot: u64) -> Channel {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(base.name().as_bytes());
+    mac.update(&[0]);
+    mac.update(&slot.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+    let mut head = [0u8; 8];
+    head.copy_from_slice(&digest[..8]);
+    Channel::new(format!("{}.hop{}", base.name(), u64::from_be_bytes(head)))
+}
+
+/// When and where a base channel hops
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HopSchedule {
+    pub seed: u64,
+    pub hop_count: u16,
+    pub interval_ms: u64,
+    /// Start of slot 0 (milliseconds since epoch)
+    pub epoch_ms: u64,
+}
+
+impl HopSchedule {
+    /// Schedule with a random seed
+    pub fn new(hop_count: u16, interval_ms: u64, epoch_ms: u64) -> Self {
+        Self {
+            seed: rand::random(),
+            hop_count: hop_count.max(1),
+            interval_ms: interval_ms.max(1),
+            epoch_ms,
+        }
+    }
+
+    /// Slot number at a sender timestamp
+    pub fn slot_at(&self, timestamp_ms: u64) -> u64 {
+        timestamp_ms.saturating_sub(self.epoch_ms) / self.interval_ms.max(1)
+    }
+
+    /// Hop index used during a slot
+    pub fn index_for_slot(&self, slot: u64) -> u16 {
+        (slot.wrapping_add(self.seed) % self.hop_count.max(1) as u64) as u16
+    }
+
+    /// Hop channel at a sender timestamp
+    pub fn channel_at(&self, base: &Channel, timestamp_ms: u64) -> Channel {
+        base.hop(
+            self.index_for_slot(self.slot_at(timestamp_ms)),
+            self.hop_count,
+        )
+    }
+
+    /// Current hop plus the neighbouring hop when within `grace_ms` of a
+    /// slot boundary, current first
+    pub fn channels_near(&self, base: &Channel, timestamp_ms: u64, grace_ms: u64) -> Vec<Channel> {
+        let mut hops: Vec<Channel> = Vec::with_capacity(2);
+        for at in slot_times_near(timestamp_ms, self.epoch_ms, self.interval_ms, grace_ms) {
+            let hop = self.channel_at(base, at);
+            if !hops.contains(&hop) {
+                hops.push(hop);
+            }
+        }
+        hops
+    }
+}
+
+/// `timestamp_ms`, then a time in the previous or next slot when the
+/// timestamp is within `grace_ms` of that boundary
+fn slot_times_near(timestamp_ms: u64, epoch_ms: u64, interval_ms: u64, grace_ms: u64) -> Vec<u64> {
+    let interval = interval_ms.max(1);
+    let into_slot = timestamp_ms.saturating_sub(epoch_ms) % interval;
+    let slot_start = timestamp_ms - into_slot;
+    let mut times = vec![timestamp_ms];
+    if grace_ms > 0 && into_slot < grace_ms && slot_start > epoch_ms {
+        times.push(slot_start - 1);
+    }
+    if grace_ms > 0 && interval - into_slot <= grace_ms {
+        times.push(slot_start + interval);
+    }
+    times
+}
+
+/// Signed hop schedule, emitted on the base channel
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HopAnnouncement {
+    pub schedule: HopSchedule,
+    /// Sender clock when announced, for receivers to measure drift
+    pub issued_at_ms: u64,
+    /// Hex HMAC-SHA256 over the base channel, schedule and issue time
+    pub signature: String,
+}
+
+impl HopAnnouncement {
+    pub fn new(base: &Channel, schedule: HopSchedule, issued_at_ms: u64, secret: &[u8]) -> Self {
+        let signature = to_hex(&sign(base, &schedule, issued_at_ms, secret));
+        Self {
+            schedule,
+            issued_at_ms,
+            signature,
+        }
+    }
+
+    /// Whether the signature matches for this base channel and secret
+    pub fn verify(&self, base: &Channel, secret: &[u8]) -> bool {
+        let Some(signature) = from_hex(&self.signature) else {
+            return false;
+        };
+        let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts any key length");
+        mac.update(&signed_bytes(base, &self.schedule, self.issued_at_ms));
+        mac.verify_slice(&signature).is_ok()
+    }
+
+    /// Announcement wave on the base channel
+    pub fn to_wave(&self, base: &Channel) -> Wave {
+        Wave::builder(base.clone())
+            .wave_type(WaveType::Broadcast)
+            .payload(serde_json::to_value(self).expect("hop announcement serializes"))
+            .build()
+    }
+
+    /// Parse an announcement wave; `None` for other waves on the base channel
+    pub fn from_wave(wave: &Wave) -> Option<Self> {
+        if *wave.wave_type() != WaveType::Broadcast {
+            return None;
+        }
+        serde_json::from_value(wave.payload().clone()).ok()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Synced {
+    schedule: HopSchedule,
+    issued_at_ms: u64,
+    /// Sender clock minus receiver clock
+    offset_ms: i64,
+}
+
+impl Synced {
+    fn sender_ms(&self, local_ms: u64) -> u64 {
+        (local_ms as i64).saturating_add(self.offset_ms).max(0) as u64
+    }
+
+    fn channel_at(&self, base: &Channel, local_ms: u64) -> Channel {
+        self.schedule.channel_at(base, self.sender_ms(local_ms))
+    }
+}
+
+/// Receiver side of the hop-sync handshake
+///
+/// After a schedule change the previous schedule is still listened on for
+/// the overlap window, so waves sent before the sender switched arrive.
+#[derive(Debug, Clone)]
+pub struct HopSync {
+    base: Channel,
+    secret: Vec<u8>,
+    overlap_ms: u64,
+    grace_ms: u64,
+    current: Option<Synced>,
+    /// Replaced schedule and the local time its overlap ends
+    previous: Option<(Synced, u64)>,
+}
+
+impl HopSync {
+    pub fn new(base: Channel, secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            base,
+            secret: secret.into(),
+            overlap_ms: 0,
+            grace_ms: 0,
+            current: None,
+            previous: None,
+        }
+    }
+
+    /// Keep listening on a replaced schedule for this long
+    pub fn with_overlap(mut self, overlap: Duration) -> Self {
+        self.overlap_ms = overlap.as_millis() as u64;
+        self
+    }
+
+    /// Also listen on the neighbouring hop this close to a slot boundary
+    pub fn with_grace(mut self, grace: Duration) -> Self {
+        self.grace_ms = grace.as_millis() as u64;
+        self
+    }
+
+    pub fn base(&self) -> &Channel {
+        &self.base
+    }
+
+    /// Schedule currently followed, once an announcement arrived
+    pub fn schedule(&self) -> Option<&HopSchedule> {
+        self.current.as_ref().map(|synced| &synced.schedule)
+    }
+
+    /// Apply a wave received on the base channel at local time `now_ms`
+    ///
+    /// Returns whether the schedule changed. Waves that are not announcements
+    /// and announcements older than the current one are ignored; a bad
+    /// signature is an error.
+    pub fn observe(&mut self, wave: &Wave, now_ms: u64) -> Result<bool> {
+        let Some(announcement) = HopAnnouncement::from_wave(wave) else {
+            return Ok(false);
+        };
+        if !announcement.verify(&self.base, &self.secret) {
+            return Err(AetherError::AuthorizationFailed(format!(
+                "invalid hop schedule signature on {}",
+                self.base
+            )));
+        }
+        if let Some(current) = &self.current {
+            if announcement.issued_at_ms <= current.issued_at_ms {
+                return Ok(false);
+            }
+        }
+
+        let synced = Synced {
+            schedule: announcement.schedule,
+            issued_at_ms: announcement.issued_at_ms,
+            offset_ms: announcement.issued_at_ms as i64 - now_ms as i64,
+        };
+        let changed = match self.current.take() {
+            Some(current) if current.schedule == synced.schedule => false,
+            Some(current) => {
+                self.previous = Some((current, now_ms.saturating_add(self.overlap_ms)));
+                true
+            }
+            None => true,
+        };
+        self.current = Some(synced);
+        Ok(changed)
+    }
+
+    /// Channels to resonate on at local time `now_ms`
+    ///
+    /// Always the base channel (for announcements), plus the current hop (and
+    /// its neighbour near a slot boundary) and, during an overlap window, the
+    /// replaced schedule's hop.
+    pub fn listening_set(&self, now_ms: u64) -> Vec<Channel> {
+        let mut channels = vec![self.base.clone()];
+        if let Some(current) = &self.current {
+            channels.extend(current.schedule.channels_near(
+                &self.base,
+                current.sender_ms(now_ms),
+                self.grace_ms,
+            ));
+        }
+        if let Some((previous, until_ms)) = &self.previous {
+            let hop = previous.channel_at(&self.base, now_ms);
+            if now_ms < *until_ms && !channels.contains(&hop) {
+                channels.push(hop);
+            }
+        }
+        channels
+    }
+}
+
+fn signed_bytes(base: &Channel, schedule: &HopSchedule, issued_at_ms: u64) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(base.name().len() + 34);
+    bytes.extend_from_slice(base.name().as_bytes());
+    bytes.push(0);
+    bytes.extend_from_slice(&schedule.seed.to_be_bytes());
+    bytes.extend_from_slice(&schedule.hop_count.to_be_bytes());
+    bytes.extend_from_slice(&schedule.interval_ms.to_be_bytes());
+    bytes.extend_from_slice(&schedule.epoch_ms.to_be_bytes());
+    bytes.extend_from_slice(&issued_at_ms.to_be_bytes());
+    bytes
+}
+
+fn sign(base: &Channel, schedule: &HopSchedule, issued_at_ms: u64, secret: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(&signed_bytes(base, schedule, issued_at_ms));
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    })
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"hop-secret";
+
+    #[test]
+    fn test_receiver_follows_sender_clock() {
+        let base = Channel::new("orders");
+        let schedule = HopSchedule::new(8, 100, 0);
+        let sender_now = 10_050;
+        let announcement = HopAnnouncement::new(&base, schedule.clone(), sender_now, SECRET);
+
+        // The receiver's clock runs 250ms behind, more than two hops
+        let receiver_now = sender_now - 250;
+        let mut sync = HopSync::new(base.clone(), SECRET);
+        assert!(sync
+            .observe(&announcement.to_wave(&base), receiver_now)
+            .unwrap());
+        assert_eq!(
+            sync.listening_set(receiver_now + 120),
+            vec![base.clone(), schedule.channel_at(&base, sender_now + 120)]
+        );
+
+        let mut forged = HopSync::new(base.clone(), b"other-secret".to_vec());
+        assert!(forged
+            .observe(&announcement.to_wave(&base), receiver_now)
+            .is_err());
+        assert!(forged.schedule().is_none());
+    }
+
+    #[test]
+    fn test_keyed_hopping_and_legacy_fallback() {
+        let orders = Channel::new("orders");
+        let keys = HopKeys::new(&HoppingConfig {
+            keys: BTreeMap::from([("orders".to_string(), "k1".to_string())]),
+            legacy_fallback: false,
+        });
+        let other_key = HopKeys::new(&HoppingConfig {
+            keys: BTreeMap::from([("orders".to_string(), "k2".to_string())]),
+            legacy_fallback: false,
+        });
+
+        let slots = |keys: &HopKeys| -> Vec<Channel> {
+            (0..64)
+                .map(|slot| keys.hop_at_ms(&orders, slot * 100, 16, 100).unwrap())
+                .collect()
+        };
+        let keyed = slots(&keys);
+        assert_eq!(keyed, slots(&keys));
+        let public = orders.hop_set(16);
+        assert!(keyed.iter().all(|hop| !public.contains(hop)));
+        assert!(keyed
+            .iter()
+            .all(|hop| hop.matches(&Channel::new("orders.*"))));
+        assert_ne!(keyed, slots(&other_key));
+        assert_ne!(keyed, slots(&HopKeys::default()));
+
+        let payments = Channel::new("payments");
+        assert!(keys.hop_at_ms(&payments, 0, 16, 100).is_err());
+        assert_eq!(
+            HopKeys::default()
+                .hop_at_ms(&payments, 1_000, 5, 200)
+                .unwrap(),
+            payments.hop_at_ms(1_000, 5, 200)
+        );
+    }
+
+    #[test]
+    fn test_grace_window_covers_neighbouring_hops() {
+        let base = Channel::new("orders");
+        let keys = HopKeys::default();
+        let hop = |at| keys.hop_at_ms(&base, at, 64, 200).unwrap();
+        let near = |at| keys.hops_near(&base, at, 64, 200, 30).unwrap();
+
+        assert_eq!(near(1_100), vec![hop(1_100)]);
+        assert_eq!(near(1_180), vec![hop(1_180), hop(1_200)]);
+        assert_eq!(near(1_210), vec![hop(1_210), hop(1_199)]);
+
+        let schedule = HopSchedule::new(64, 200, 1_000);
+        assert_eq!(schedule.channels_near(&base, 1_010, 30).len(), 1);
+        assert_eq!(
+            schedule.channels_near(&base, 1_390, 30),
+            vec![
+                schedule.channel_at(&base, 1_390),
+                schedule.channel_at(&base, 1_400)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_replaced_schedule_is_kept_for_overlap() {
+        let base = Channel::new("orders");
+        let old = HopSchedule::new(8, 1_000, 0);
+        let new = HopSchedule {
+            seed: old.seed.wrapping_add(3),
+            ..old.clone()
+        };
+        let mut sync = HopSync::new(base.clone(), SECRET).with_overlap(Duration::from_millis(500));
+        let announce = |schedule: &HopSchedule, at| {
+            HopAnnouncement::new(&base, schedule.clone(), at, SECRET).to_wave(&base)
+        };
+
+        assert!(sync.observe(&announce(&old, 1_000), 1_000).unwrap());
+        assert!(sync.observe(&announce(&new, 2_000), 2_000).unwrap());
+        // A replayed older announcement does not roll back
+        assert!(!sync.observe(&announce(&old, 1_500), 2_100).unwrap());
+
+        let during = sync.listening_set(2_200);
+        assert_eq!(during.len(), 3);
+        assert!(during.contains(&old.channel_at(&base, 2_200)));
+        assert_eq!(
+            sync.listening_set(2_600),
+            vec![base.clone(), new.channel_at(&base, 2_600)]
+        );
+    }
+}
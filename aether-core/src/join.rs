@@ -0,0 +1,323 @@
+//! Joins: pair waves from two channels that share a key.
+//!
+//! A [`WaveJoin`] holds each wave until a partner with the same key arrives on
+//! the other channel, or until the join window runs out. Partners are matched
+//! first come, first served, so repeated keys pair up in arrival order.
+
+use crate::{aether::Aether, channel::Channel, dispatcher::PartitionKey, wave::Wave};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+/// Which input of a join a wave came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinSide {
+    Left,
+    Right,
+}
+
+impl JoinSide {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JoinSide::Left => "left",
+            JoinSide::Right => "right",
+        }
+    }
+}
+
+/// Outcome of a join
+#[derive(Debug, Clone)]
+pub enum JoinEvent {
+    /// Waves from both channels with the same key
+    Matched {
+        key: String,
+        left: Box<Wave>,
+        right: Box<Wave>,
+    },
+    /// A wave whose partner did not arrive within the window
+    Expired {
+        key: String,
+        side: JoinSide,
+        wave: Box<Wave>,
+    },
+}
+
+impl JoinEvent {
+    pub fn key(&self) -> &str {
+        match self {
+            JoinEvent::Matched { key, .. } | JoinEvent::Expired { key, .. } => key,
+        }
+    }
+
+    /// Wave on `channel` carrying both payloads, or the lone one with its side
+    pub fn to_wave(&self, channel: impl Into<Channel>) -> Wave {
+        let payload = match self {
+            JoinEvent::Matched { key, left, right } => serde_json::json!({
+                "key": key,
+                "left": left.payload(),
+                "right": right.payload(),
+            }),
+            JoinEvent::Expired { key, side, wave } => serde_json::json!({
+                "key": key,
+                "side": side.as_str(),
+                "channel": wave.channel().name(),
+                "payload": wave.payload(),
+            }),
+        };
+        Wave::builder(channel).payload(payload).build()
+    }
+}
+
+#[derive(Debug)]
+struct Held {
+    wave: Box<Wave>,
+    expires_at: DateTime<Utc>,
+}
+
+/// Pairs waves from a left and a right channel by key within a time window
+#[derive(Debug)]
+pub struct WaveJoin {
+    left: Channel,
+    right: Channel,
+    key: PartitionKey,
+    window: Duration,
+    held_left: HashMap<String, VecDeque<Held>>,
+    held_right: HashMap<String, VecDeque<Held>>,
+}
+
+impl WaveJoin {
+    pub fn new(
+        left: impl Into<Channel>,
+        right: impl Into<Channel>,
+        key: PartitionKey,
+        window: Duration,
+    ) -> Self {
+        Self {
+            left: left.into(),
+            right: right.into(),
+            key,
+            window,
+            held_left: HashMap::new(),
+            held_right: HashMap::new(),
+        }
+    }
+
+    /// Number of waves still waiting for a partner
+    pub fn pending(&self) -> usize {
+        self.held_left
+            .values()
+            .chain(self.held_right.values())
+            .map(VecDeque::len)
+            .sum()
+    }
+
+    /// Add a wave received at `now`; returns expired waves and any match
+    ///
+    /// Waves on neither channel, or without a key, are ignored.
+    pub fn push(&mut self, wave: Wave, now: DateTime<Utc>) -> Vec<JoinEvent> {
+        let mut events = self.expire(now);
+        let side = if wave.channel().matches(&self.left) {
+            JoinSide::Left
+        } else if wave.channel().matches(&self.right) {
+            JoinSide::Right
+        } else {
+            return events;
+        };
+        let Some(key) = self.key.extract(&wave) else {
+            return events;
+        };
+
+        let (own, other) = match side {
+            JoinSide::Left => (&mut self.held_left, &mut self.held_right),
+            JoinSide::Right => (&mut self.held_right, &mut self.held_left),
+        };
+        let partner = other.get_mut(&key).and_then(VecDeque::pop_front);
+        if other.get(&key).is_some_and(VecDeque::is_empty) {
+            other.remove(&key);
+        }
+        match partner {
+            Some(partner) => {
+                let (left, right) = match side {
+                    JoinSide::Left => (Box::new(wave), partner.wave),
+                    JoinSide::Right => (partner.wave, Box::new(wave)),
+                };
+                metrics::counter!("aether_join_events_total", "outcome" => "matched").increment(1);
+                events.push(JoinEvent::Matched { key, left, right });
+            }
+            None => {
+                let expires_at = chrono::Duration::from_std(self.window)
+                    .ok()
+                    .and_then(|window| now.checked_add_signed(window))
+                    .unwrap_or(DateTime::<Utc>::MAX_UTC);
+                own.entry(key).or_default().push_back(Held {
+                    wave: Box::new(wave),
+                    expires_at,
+                });
+            }
+        }
+        events
+    }
+
+    /// Give up on waves whose window ended by `now`
+    pub fn expire(&mut self, now: DateTime<Utc>) -> Vec<JoinEvent> {
+        let mut events = Vec::new();
+        for (side, held) in [
+            (JoinSide::Left, &mut self.held_left),
+            (JoinSide::Right, &mut self.held_right),
+        ] {
+            held.retain(|key, waves| {
+                while waves.front().is_some_and(|held| held.expires_at <= now) {
+                    let held = waves.pop_front().expect("front checked");
+                    metrics::counter!("aether_join_events_total", "outcome" => "expired")
+                        .increment(1);
+                    events.push(JoinEvent::Expired {
+                        key: key.clone(),
+                        side,
+                        wave: held.wave,
+                    });
+                }
+                !waves.is_empty()
+            });
+        }
+        events
+    }
+
+    /// Join the waves on both channels in the background, calling `handler`
+    /// with every match and expiry
+    pub async fn spawn<F, Fut>(mut self, aether: &Aether, mut handler: F) -> JoinHandle<()>
+    where
+        F: FnMut(JoinEvent) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send,
+    {
+        let left = aether.tap(self.left.clone()).await;
+        let right = aether.tap(self.right.clone()).await;
+        let clock = aether.clock().clone();
+        let tick = (self.window / 4).clamp(Duration::from_millis(10), Duration::from_secs(1));
+        tokio::spawn(async move {
+            let mut waves = futures::stream::select(Box::pin(left), Box::pin(right));
+            let mut ticker = tokio::time::interval(tick);
+            loop {
+                let events = tokio::select! {
+                    wave = waves.next() => match wave {
+                        Some(wave) => self.push(wave, clock.now()),
+                        None => break,
+                    },
+                    _ = ticker.tick() => self.expire(clock.now()),
+                };
+                for event in events {
+                    handler(event).await;
+                }
+            }
+        })
+    }
+
+    /// Emit matches on `output` and expired waves on `<output>.unmatched`
+    pub async fn spawn_emitting(
+        self,
+        aether: &Aether,
+        output: impl Into<Channel>,
+    ) -> JoinHandle<()> {
+        let output = output.into();
+        let unmatched = Channel::new(format!("{}.unmatched", output.name()));
+        let emitter = aether.clone();
+        self.spawn(aether, move |event| {
+            let channel = match event {
+                JoinEvent::Matched { .. } => output.clone(),
+                JoinEvent::Expired { .. } => unmatched.clone(),
+            };
+            let aether = emitter.clone();
+            async move {
+                let mut wave = event.to_wave(channel);
+                if let Some(token) = &aether.config().auth_token {
+                    wave.set_auth_token(token.clone());
+                }
+                if let Err(e) = aether.emit(wave).await {
+                    warn!("Failed to emit join result for {}: {}", event.key(), e);
+                }
+            }
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wave(channel: &str, order_id: &str) -> Wave {
+        Wave::new(channel, serde_json::json!({ "order_id": order_id }))
+    }
+
+    #[test]
+    fn test_join_pairs_by_key_and_expires_lonely_waves() {
+        let mut join = WaveJoin::new(
+            "orders.confirmed",
+            "payments.completed",
+            PartitionKey::field("order_id"),
+            Duration::from_secs(30),
+        );
+        let t0 = Utc::now();
+        let secs = |s: i64| t0 + chrono::Duration::seconds(s);
+
+        assert!(join.push(wave("orders.confirmed", "o-1"), t0).is_empty());
+        assert!(join.push(wave("orders.confirmed", "o-2"), t0).is_empty());
+        assert!(join.push(wave("orders.shipped", "o-1"), t0).is_empty());
+        let events = join.push(wave("payments.completed", "o-1"), secs(10));
+        let [JoinEvent::Matched { key, left, right }] = events.as_slice() else {
+            panic!("expected a match, got {:?}", events);
+        };
+        assert_eq!(key, "o-1");
+        assert_eq!(left.channel().name(), "orders.confirmed");
+        assert_eq!(right.channel().name(), "payments.completed");
+
+        // o-2's payment never arrives; o-3's arrives without an order
+        assert!(join
+            .push(wave("payments.completed", "o-3"), secs(20))
+            .is_empty());
+        let events = join.expire(secs(30));
+        assert!(matches!(
+            events.as_slice(),
+            [JoinEvent::Expired { key, side: JoinSide::Left, .. }] if key == "o-2"
+        ));
+        assert_eq!(join.pending(), 1);
+        let combined = join.expire(secs(50))[0].to_wave("orders.paid.unmatched");
+        assert_eq!(combined.payload()["side"], "right");
+        assert_eq!(combined.payload()["payload"]["order_id"], "o-3");
+    }
+
+    #[tokio::test]
+    async fn test_spawned_join_emits_combined_waves() {
+        let aether = Aether::new(crate::aether::AetherConfig {
+            use_nats: false,
+            ..crate::aether::AetherConfig::default()
+        });
+        let paid = aether.tap(Channel::new("orders.paid")).await;
+        let task = WaveJoin::new(
+            "orders.confirmed",
+            "payments.completed",
+            PartitionKey::field("order_id"),
+            Duration::from_secs(30),
+        )
+        .spawn_emitting(&aether, "orders.paid")
+        .await;
+
+        aether
+            .emit(wave("payments.completed", "o-9"))
+            .await
+            .unwrap();
+        aether.emit(wave("orders.confirmed", "o-9")).await.unwrap();
+
+        futures::pin_mut!(paid);
+        let joined = tokio::time::timeout(Duration::from_millis(500), paid.next())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(joined.payload()["key"], "o-9");
+        assert_eq!(joined.payload()["left"]["order_id"], "o-9");
+        task.abort();
+    }
+}
@@ -0,0 +1,68 @@
+//! Bounded metric label values.
+//!
+//! Channel names and sources come from callers, so using them raw as labels
+//! lets the number of series grow without limit. A [`LabelBudget`] admits
+//! the first values it sees and folds the rest into [`OVERFLOW_LABEL`].
+
+use std::collections::BTreeSet;
+use std::sync::RwLock;
+
+/// Label value for everything past a budget
+pub(crate) const OVERFLOW_LABEL: &str = "other";
+
+/// Distinct values a label keeps unless its budget says otherwise
+pub(crate) const DEFAULT_LABEL_BUDGET: usize = 256;
+
+/// Caps the distinct values one metric label takes
+pub(crate) struct LabelBudget {
+    limit: usize,
+    admitted: RwLock<BTreeSet<String>>,
+}
+
+impl LabelBudget {
+    pub(crate) const fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            admitted: RwLock::new(BTreeSet::new()),
+        }
+    }
+
+    /// `value` if it is already admitted or there is room, `OVERFLOW_LABEL` otherwise
+    pub(crate) fn label(&self, value: &str) -> String {
+        if self
+            .admitted
+            .read()
+            .expect("label budget lock poisoned")
+            .contains(value)
+        {
+            return value.to_string();
+        }
+        let mut admitted = self.admitted.write().expect("label budget lock poisoned");
+        if admitted.len() < self.limit || admitted.contains(value) {
+            admitted.insert(value.to_string());
+            value.to_string()
+        } else {
+            OVERFLOW_LABEL.to_string()
+        }
+    }
+}
+
+impl Default for LabelBudget {
+    fn default() -> Self {
+        Self::new(DEFAULT_LABEL_BUDGET)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_folds_values_past_the_limit() {
+        let budget = LabelBudget::new(2);
+        assert_eq!(budget.label("orders"), "orders");
+        assert_eq!(budget.label("payments"), "payments");
+        assert_eq!(budget.label("inventory"), OVERFLOW_LABEL);
+        assert_eq!(budget.label("orders"), "orders");
+    }
+}
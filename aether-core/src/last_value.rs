@@ -0,0 +1,137 @@
+//! Last-value cache: the most recent wave on retained channels, for late joiners.
+//!
+//! The cache is local to each layer. Under NATS a layer only holds waves it
+//! emitted itself or received on its own subscriptions; retained values are
+//! not shared between instances.
+
+use crate::{channel::Channel, wave::Wave};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use uuid::Uuid;
+
+/// What a `store` replaced, so a wave that never went out can be taken back
+#[derive(Debug)]
+pub(crate) struct Displaced {
+    scoped_name: String,
+    id: Uuid,
+    previous: Option<Wave>,
+}
+
+/// Latest wave per channel for channels matching the retained patterns
+#[derive(Debug)]
+pub(crate) struct LastValueCache {
+    patterns: Vec<Channel>,
+    waves: RwLock<HashMap<String, Wave>>,
+}
+
+impl LastValueCache {
+    pub(crate) fn new(patterns: &[String]) -> Self {
+        Self {
+            patterns: patterns.iter().map(Channel::new).collect(),
+            waves: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Remember `wave` under its transport name if its channel is retained,
+    /// returning what it replaced
+    pub(crate) fn store(&self, scoped_name: String, wave: &Wave) -> Option<Displaced> {
+        if !self
+            .patterns
+            .iter()
+            .any(|pattern| wave.channel().matches(pattern))
+        {
+            return None;
+        }
+        let mut waves = self.waves.write().expect("last value lock poisoned");
+        // Redelivered or replayed waves must not overwrite newer state
+        if let Some(current) = waves.get(&scoped_name) {
+            if current.timestamp() > wave.timestamp() {
+                return None;
+            }
+        }
+        let previous = waves.insert(scoped_name.clone(), wave.clone());
+        Some(Displaced {
+            scoped_name,
+            id: *wave.id(),
+            previous,
+        })
+    }
+
+    /// Put back what a `store` replaced, unless a newer wave has replaced it since
+    pub(crate) fn undo(&self, displaced: Displaced) {
+        let mut waves = self.waves.write().expect("last value lock poisoned");
+        if waves.get(&displaced.scoped_name).map(Wave::id) != Some(&displaced.id) {
+            return;
+        }
+        match displaced.previous {
+            Some(previous) => waves.insert(displaced.scoped_name, previous),
+            None => waves.remove(&displaced.scoped_name),
+        };
+    }
+
+    pub(crate) fn get(&self, scoped_name: &str) -> Option<Wave> {
+        self.waves
+            .read()
+            .expect("last value lock poisoned")
+            .get(scoped_name)
+            .cloned()
+    }
+
+    /// Cached waves in a namespace whose channel matches `pattern`, oldest first
+    pub(crate) fn matching(&self, namespace: Option<&str>, pattern: &Channel) -> Vec<Wave> {
+        let mut matching: Vec<Wave> = self
+            .waves
+            .read()
+            .expect("last value lock poisoned")
+            .values()
+            .filter(|wave| wave.namespace() == namespace && wave.channel().matches(pattern))
+            .cloned()
+            .collect();
+        matching.sort_by_key(|wave| *wave.timestamp());
+        matching
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_retained_channels_are_cached() {
+        let cache = LastValueCache::new(&["inventory.*".to_string()]);
+        let first = Wave::new("inventory.level", serde_json::json!({"ItemA": 100}));
+        let second = Wave::new("inventory.level", serde_json::json!({"ItemA": 99}));
+        cache.store("inventory.level".to_string(), &first);
+        cache.store("inventory.level".to_string(), &second);
+        cache.store(
+            "orders.created".to_string(),
+            &Wave::new("orders.created", serde_json::json!({})),
+        );
+
+        assert_eq!(cache.get("inventory.level"), Some(second.clone()));
+        assert_eq!(cache.get("orders.created"), None);
+        assert_eq!(
+            cache.matching(None, &Channel::new("inventory.>")),
+            vec![second]
+        );
+        assert!(cache
+            .matching(Some("staging"), &Channel::new("inventory.>"))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_undo_puts_back_the_replaced_wave() {
+        let cache = LastValueCache::new(&["inventory.*".to_string()]);
+        let first = Wave::new("inventory.level", serde_json::json!({"ItemA": 100}));
+        let failed = Wave::new("inventory.level", serde_json::json!({"ItemA": 99}));
+        cache.store("inventory.level".to_string(), &first);
+        let displaced = cache.store("inventory.level".to_string(), &failed).unwrap();
+        cache.undo(displaced);
+        assert_eq!(cache.get("inventory.level"), Some(first));
+
+        let only = Wave::new("inventory.count", serde_json::json!({"ItemA": 1}));
+        let displaced = cache.store("inventory.count".to_string(), &only).unwrap();
+        cache.undo(displaced);
+        assert_eq!(cache.get("inventory.count"), None);
+    }
+}
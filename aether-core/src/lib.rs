@@ -2,39 +2,173 @@
 //!
 //! A microservice framework applying aether theory to system architecture
 
+extern crate self as aether_core;
+
 pub mod aether;
+pub mod amplitude_policy;
+pub mod analytics;
+pub mod app;
+pub mod audit;
 pub mod buffer_pool;
+pub mod causality;
 pub mod channel;
+pub mod chaos;
+pub mod clock;
+pub mod cluster;
+pub mod coalesce;
+pub mod codec;
+pub mod command;
 pub mod config;
+pub mod connection;
+pub mod control;
+pub mod dispatcher;
+pub mod echo;
+pub mod encryption;
+pub mod events;
+mod exemplar;
+pub mod export;
+pub mod filter;
+pub mod flow_trace;
+pub mod handler_metrics;
+pub mod hopping;
+pub mod join;
+mod labels;
+mod metrics_push;
+mod last_value;
 pub mod observability;
 pub mod operations;
+mod log_writer;
+pub mod notify;
 pub mod persistence;
 pub mod physics;
+pub mod physics_history;
+pub mod projection;
+pub mod receipt;
+pub mod rate_limit;
+pub mod registry;
+pub mod recording;
+pub mod redaction;
 pub mod reliability;
 pub mod resource_monitoring;
+pub mod rollout;
+pub mod router;
+pub mod runtime_metrics;
+pub mod sampling;
+pub mod shedding;
+pub mod sequencing;
+pub mod shard;
+pub mod simulation;
+mod sketch;
+pub mod source_stats;
+pub mod storage_monitoring;
 pub mod task_manager;
+#[cfg(any(test, feature = "testkit"))]
+pub mod testkit;
+pub mod topology;
+pub mod transform;
+pub mod validation;
 pub mod vibrator;
 pub mod wave;
+pub mod wave_context;
+pub mod wave_index;
+pub mod window;
 
-pub use aether::{Aether, AetherConfig, AetherStats};
+pub use aether::{
+    Aether, AetherConfig, AetherStats, NamespaceBridge, PropagationLimitPolicy,
+    DEAD_LETTER_CHANNEL_PREFIX,
+};
+pub use amplitude_policy::{AmplitudePolicy, TypeAmplitudes};
+pub use analytics::{
+    Anomaly, AnomalyConfig, AnomalyDetector, AnomalyKind, ChannelActivity, ChannelBaseline,
+};
+pub use app::{AetherApp, AetherAppBuilder, ServiceContext};
+pub use audit::{AuditConfig, AuditEvent, AuditKind, AuditLog, AuditQuery};
 pub use buffer_pool::{BytePool, PooledBytesMut};
-pub use channel::Channel;
+pub use causality::{causation_chain, CausalTree, PayloadChange, WaveComparison, WaveSummary};
+pub use channel::{Channel, TypedChannel};
+pub use chaos::{Chaos, ChaosConfig};
+pub use clock::{Clock, SharedClock, SystemClock, VirtualClock};
+pub use cluster::{
+    ClusterConfig, ClusterMembership, ClusterView, Heartbeat, Peer, PeerStatus, MEMBERSHIP_CHANNEL,
+};
+pub use coalesce::Coalesced;
+pub use codec::WaveCodec;
+pub use connection::{ConnectionState, ConnectionStatus, NatsServer};
+pub use command::{CommandHandler, CommandOutcome, CommandReply, GatherPolicy};
 pub use config::{
-    load_config, watch_config, AetherLayerConfig, AppConfig, ConfigError, LoggingConfig,
-    ObservabilityConfig, ServiceConfig,
+    load_config, watch_config, AetherLayerConfig, AppConfig, ChannelNoiseFloor, ChannelResonance,
+    ConfigError, ControlConfig, LoggingConfig, MetricsPushConfig, ObservabilityConfig, ServiceConfig,
+};
+pub use control::{control_channel, ControlCommand, ControlPlane, ControlResponse};
+pub use dispatcher::{KeyedDispatcher, KeyedDispatcherConfig, PartitionKey};
+pub use echo::{echo_channel, Reflection};
+pub use encryption::{EncryptionConfig, EncryptionKeyConfig, Keyring};
+pub use events::AetherEvent;
+pub use export::{
+    start_exports, ExportConfig, ExportSink, Exporter, NdjsonFileSink, ObjectStoreSink,
+    SinkConfig, WebhookSink,
 };
-pub use observability::{init_observability, ObservabilityGuard};
+pub use filter::WaveFilter;
+pub use flow_trace::{Breadcrumb, FlowTraceConfig, FlowTraceIndex, WavePath};
+pub use handler_metrics::{observe_handler, HandlerOutcome};
+pub use hopping::{HopAnnouncement, HopKeys, HopSchedule, HopSync, HoppingConfig};
+pub use join::{JoinEvent, JoinSide, WaveJoin};
+pub use notify::{Notification, NotificationsConfig, Notifier, WebhookConfig};
+pub use observability::{init_observability, set_log_level, ObservabilityGuard};
 pub use operations::{
-    apply_resource_limits, init_ops, install_panic_hook, shutdown_signal, wait_for_shutdown,
-    OpsConfig,
+    apply_resource_limits, doctor, init_ops, install_panic_hook, shutdown_signal,
+    wait_for_shutdown, CheckStatus, DoctorCheck, DoctorMode, DoctorReport, HealthState, OpsConfig,
+    OpsHandle, Readiness,
+};
+pub use persistence::{
+    AetherSnapshot, ArchiveSummary, CorruptEntry, Durability, RecoveryMode, RecoveryReport,
+    StoreStats, WaveStore,
+};
+pub use physics::{Interference, InterferencePattern, PhysicsConfig, PhysicsEngine, Resonance};
+pub use physics_history::{
+    ChannelSummary, PatternDetection, PatternQuery, PhysicsHistory, PhysicsHistoryConfig,
+};
+pub use projection::{Projection, ProjectionRunner, ProjectionView};
+pub use receipt::{EmitOptions, EmitReceipt, EmitTransport};
+pub use rate_limit::{ChannelQuota, RateLimitConfig, SourceLimit};
+pub use registry::{ChannelRegistry, ChannelSpec, FieldType, RegistryConfig, RegistryMode};
+pub use recording::{load_recording, load_recording_async, replay_recording, WaveRecorder};
+pub use redaction::{RedactionRule, Redactor};
+pub use reliability::{
+    hedge, retry_with_timeout, retry_with_timeout_named, CircuitBreaker, HedgePolicy, RetryPolicy,
+};
+pub use resource_monitoring::{
+    start_resource_monitoring, start_resource_monitoring_with_alerts, ResourceLimits,
+    ResourceMonitorConfig,
+};
+pub use rollout::{RolloutConfig, RolloutRule, VersionRouter};
+pub use router::{IntoHandlerResult, WaveHandler, WaveRouter};
+pub use runtime_metrics::{spawn_runtime_metrics, BlockingWatchdog, RuntimeMetricsConfig};
+pub use sampling::{ObserveSampler, ObserveSamplingConfig, SamplingConfig, WaveSampler};
+pub use sequencing::{EpochCheck, Ordered, ReorderBuffer, SequenceGap, SourceEpochs};
+pub use shard::AetherShardSet;
+pub use shedding::{Admission, LoadShedder, LoadSheddingConfig, ShedAction};
+pub use simulation::Simulation;
+pub use source_stats::{SourceReportConfig, SourceStats};
+pub use storage_monitoring::{spawn_storage_monitor, StorageHealth, StorageMonitoringConfig};
+pub use task_manager::{
+    ChannelRateLimit, Priority, PriorityWeights, TaskManager, TaskRateLimiter, TaskStats,
+};
+pub use topology::{
+    EdgeKind, Topology, TopologyConfig, TopologyEdge, TopologyFormat, TopologyTracker,
+};
+pub use transform::{map_payload, StripFields, TransformPipeline, Transformer};
+pub use validation::{RequireFields, RequiredFields, WaveValidator, WaveValidators};
+pub use vibrator::{
+    ChannelChanges, ResonanceHandle, Vibrator, VibratorConfig, VibratorControl, VibratorEmitter,
 };
-pub use persistence::{AetherSnapshot, WaveStore};
-pub use physics::{Interference, PhysicsEngine, Resonance};
-pub use reliability::{retry_with_timeout, CircuitBreaker, RetryPolicy};
-pub use resource_monitoring::{start_resource_monitoring, ResourceMonitorConfig};
-pub use task_manager::TaskManager;
-pub use vibrator::{Vibrator, VibratorConfig, VibratorEmitter};
 pub use wave::{Amplitude, Wave, WaveType};
+pub use wave_context::{WaveContext, CAUSATION_KEY, CORRELATION_KEY};
+pub use wave_index::{tail_channel, WaveIndex, WaveIndexConfig, WaveQuery};
+pub use window::{WaveWindow, Window, WindowBatch, WindowContents};
+
+/// Turn an async fn into a [`WaveHandler`] for a channel; see [`WaveRouter`]
+pub use aether_macros::handler;
 
 /// Error type for the Aether architecture
 #[derive(Debug, thiserror::Error)]
@@ -62,19 +196,58 @@ pub enum AetherError {
 
     #[error("Validation failed: {0}")]
     ValidationFailed(String),
+
+    #[error("Validation failed: validator {validator} rejected wave on {channel}: {reason}")]
+    ValidatorRejected {
+        validator: String,
+        channel: String,
+        reason: String,
+    },
+
+    #[error("Codec error: {0}")]
+    CodecError(String),
+
+    #[error("Rate limited: {0}")]
+    RateLimited(String),
+
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
+
+    #[error("Quorum not reached: {0}")]
+    QuorumNotReached(String),
+
+    #[error("Propagation limit reached: {0}")]
+    PropagationLimitReached(String),
+
+    #[error("Saturated: {0}")]
+    Saturated(String),
 }
 
 impl AetherError {
     pub fn is_recoverable(&self) -> bool {
         matches!(
             self,
-            AetherError::ConnectionFailed(_) | AetherError::TransmissionFailed(_)
+            AetherError::ConnectionFailed(_)
+                | AetherError::TransmissionFailed(_)
+                | AetherError::RateLimited(_)
+                | AetherError::QuotaExceeded(_)
+                | AetherError::QuorumNotReached(_)
+                | AetherError::Saturated(_)
         )
     }
 }
 
 pub type Result<T> = std::result::Result<T, AetherError>;
 
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::router::{decode_payload, run_handler};
+    pub use anyhow;
+    pub use futures::future::BoxFuture;
+    pub use serde;
+    pub use serde_json;
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -0,0 +1,241 @@
+//! Background writer that takes WaveStore appends off the emit path.
+
+use crate::events::{AetherEvent, EventBus};
+use crate::persistence::{AetherSnapshot, Durability, WaveStore};
+use crate::wave::Wave;
+use anyhow::{anyhow, Result};
+use std::thread::JoinHandle;
+use tokio::sync::{mpsc, oneshot};
+use tracing::warn;
+
+/// Most operations folded into one group commit
+const MAX_BATCH: usize = 256;
+
+enum WriteOp {
+    Append {
+        wave: Box<Wave>,
+        /// Set in sync mode; answered after the batch is flushed
+        ack: Option<oneshot::Sender<Result<u64>>>,
+    },
+    /// Saved with `last_index` set to the last wave written before it
+    Snapshot(Box<AetherSnapshot>),
+    Flush(oneshot::Sender<Result<()>>),
+}
+
+/// Owns the store's write side on a dedicated thread fed by a bounded queue
+///
+/// A full queue makes `append` wait, so a slow disk slows emitters down
+/// instead of growing memory. Dropping the writer drains the queue and joins
+/// the thread, so the store can be reopened right after.
+pub(crate) struct LogWriter {
+    queue: Option<mpsc::Sender<WriteOp>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl LogWriter {
+    pub(crate) fn spawn(store: WaveStore, queue_size: usize, events: EventBus) -> Self {
+        let (queue, ops) = mpsc::channel(queue_size.max(1));
+        let thread = std::thread::Builder::new()
+            .name("aether-log-writer".to_string())
+            .spawn(move || run(store, ops, events))
+            .expect("failed to spawn log writer thread");
+        Self {
+            queue: Some(queue),
+            thread: Some(thread),
+        }
+    }
+
+    /// Queue a wave; for `Fsync`, wait until it is flushed and return its index
+    pub(crate) async fn append(&self, wave: &Wave, durability: Durability) -> Result<Option<u64>> {
+        match durability {
+            Durability::Unlogged => Ok(None),
+            Durability::Buffered => {
+                self.send(WriteOp::Append {
+                    wave: Box::new(wave.clone()),
+                    ack: None,
+                })
+                .await?;
+                Ok(None)
+            }
+            Durability::Fsync => {
+                let (ack, done) = oneshot::channel();
+                self.send(WriteOp::Append {
+                    wave: Box::new(wave.clone()),
+                    ack: Some(ack),
+                })
+                .await?;
+                let index = done.await.map_err(|_| anyhow!("log writer stopped"))??;
+                Ok(Some(index))
+            }
+        }
+    }
+
+    /// Save a snapshot once every wave queued before it is written
+    pub(crate) async fn snapshot(&self, snapshot: AetherSnapshot) -> Result<()> {
+        self.send(WriteOp::Snapshot(Box::new(snapshot))).await
+    }
+
+    /// Wait until everything queued so far is written and flushed
+    pub(crate) async fn flush(&self) -> Result<()> {
+        let (ack, done) = oneshot::channel();
+        self.send(WriteOp::Flush(ack)).await?;
+        done.await.map_err(|_| anyhow!("log writer stopped"))?
+    }
+
+    async fn send(&self, op: WriteOp) -> Result<()> {
+        let queue = self.queue.as_ref().expect("log writer queue taken");
+        queue
+            .send(op)
+            .await
+            .map_err(|_| anyhow!("log writer stopped"))?;
+        metrics::gauge!("aether_persistence_queue_depth")
+            .set((queue.max_capacity() - queue.capacity()) as f64);
+        Ok(())
+    }
+}
+
+impl Drop for LogWriter {
+    fn drop(&mut self) {
+        self.queue.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn run(store: WaveStore, mut ops: mpsc::Receiver<WriteOp>, events: EventBus) {
+    let mut batch = Vec::with_capacity(MAX_BATCH);
+    while let Some(op) = ops.blocking_recv() {
+        batch.push(op);
+        while batch.len() < MAX_BATCH {
+            match ops.try_recv() {
+                Ok(op) => batch.push(op),
+                Err(_) => break,
+            }
+        }
+        write_batch(&store, &mut batch, &events);
+    }
+    if let Err(err) = store.flush() {
+        warn!("Failed to flush persistence store: {}", err);
+    }
+}
+
+/// Write a batch and, if anyone is waiting on it, flush once for all of them
+fn write_batch(store: &WaveStore, batch: &mut Vec<WriteOp>, events: &EventBus) {
+    let mut appended = Vec::new();
+    let mut flushes = Vec::new();
+    for op in batch.drain(..) {
+        match op {
+            WriteOp::Append { wave, ack } => {
+                let result = store.append_wave(&wave);
+                if let Err(err) = &result {
+                    warn!("Failed to persist wave: {}", err);
+                    metrics::counter!("aether_persistence_errors_total").increment(1);
+                }
+                if let Some(ack) = ack {
+                    appended.push((ack, result));
+                }
+            }
+            WriteOp::Snapshot(mut snapshot) => match store.last_index() {
+                Ok(Some(index)) => {
+                    snapshot.last_index = index;
+                    match store.save_snapshot(&snapshot) {
+                        Ok(()) => events.publish(AetherEvent::SnapshotSaved {
+                            last_index: index,
+                            total_waves: snapshot.stats.total_waves,
+                        }),
+                        Err(err) => warn!("Failed to save snapshot: {}", err),
+                    }
+                }
+                Ok(None) => {}
+                Err(err) => warn!("Failed to save snapshot: {}", err),
+            },
+            WriteOp::Flush(ack) => flushes.push(ack),
+        }
+    }
+    metrics::counter!("aether_persistence_batches_total").increment(1);
+
+    if appended.is_empty() && flushes.is_empty() {
+        return;
+    }
+    let flushed = store.flush().map_err(|e| e.to_string());
+    for (ack, result) in appended {
+        let result = match &flushed {
+            Ok(()) => result,
+            Err(err) => Err(anyhow!("flush failed: {}", err)),
+        };
+        let _ = ack.send(result);
+    }
+    for ack in flushes {
+        let _ = ack.send(flushed.clone().map_err(|err| anyhow!(err)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("aether-{}-{}", name, uuid::Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn test_sync_appends_are_durable_when_acked() {
+        let path = temp_path("log-writer");
+        let store = WaveStore::open(&path).unwrap();
+        let writer = LogWriter::spawn(store.clone(), 4, EventBus::default());
+
+        let emits = (0..16).map(|n| {
+            let writer = &writer;
+            async move {
+                let wave = Wave::new("orders.created", serde_json::json!({ "n": n }));
+                writer.append(&wave, Durability::Fsync).await.unwrap()
+            }
+        });
+        let mut indices: Vec<u64> = futures::future::join_all(emits)
+            .await
+            .into_iter()
+            .map(Option::unwrap)
+            .collect();
+        indices.sort_unstable();
+        assert_eq!(indices, (0..16).collect::<Vec<_>>());
+        assert_eq!(store.len(), 16);
+
+        drop(writer);
+        drop(store);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_follows_queued_waves() {
+        let path = temp_path("log-writer-snapshot");
+        let store = WaveStore::open(&path).unwrap();
+        let writer = LogWriter::spawn(store.clone(), 64, EventBus::default());
+        for _ in 0..3 {
+            let wave = Wave::new("orders.created", serde_json::json!({}));
+            let queued = writer.append(&wave, Durability::Buffered).await.unwrap();
+            assert_eq!(queued, None);
+        }
+        writer
+            .snapshot(AetherSnapshot {
+                last_index: 0,
+                stats: Default::default(),
+                timestamp: chrono::Utc::now(),
+                sequences: Default::default(),
+                channels: Vec::new(),
+                vibrators: Vec::new(),
+                registry: None,
+                channel_totals: Default::default(),
+                cursors: Default::default(),
+            })
+            .await
+            .unwrap();
+        writer.flush().await.unwrap();
+
+        assert_eq!(store.load_snapshot().unwrap().unwrap().last_index, 2);
+
+        drop(writer);
+        drop(store);
+        let _ = std::fs::remove_dir_all(path);
+    }
+}
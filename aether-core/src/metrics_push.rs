@@ -0,0 +1,488 @@
+//! OTLP metrics push for processes that can't be scraped.
+//!
+//! Serverless functions and batch jobs often never live long enough, or sit
+//! behind enough network, that nothing reaches their `/metrics` endpoint.
+//! With `[observability.metrics_push]` set, the recorder is rendered on an
+//! interval and POSTed to an OpenTelemetry collector as OTLP/HTTP JSON:
+//! counters become monotonic sums, gauges gauges, and the `*_seconds`
+//! histograms and other summaries keep their buckets and quantiles.
+//!
+//! Every push carries `service.name` and `service.instance.id` resource
+//! attributes, so replicas of a service stay separate series instead of
+//! overwriting each other, and [`MetricsPusher::shutdown`] sends one last
+//! push so a job's final counts are not lost with the process.
+
+use crate::config::MetricsPushConfig;
+use metrics_exporter_prometheus::PrometheusHandle;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Background task pushing the metrics until shut down
+#[derive(Debug)]
+pub struct MetricsPusher {
+    stop: Option<oneshot::Sender<()>>,
+    task: Option<JoinHandle<()>>,
+    timeout: Duration,
+}
+
+impl MetricsPusher {
+    /// Start pushing `handle`'s metrics as `service`
+    pub fn spawn(
+        config: &MetricsPushConfig,
+        service: &str,
+        handle: PrometheusHandle,
+    ) -> anyhow::Result<Self> {
+        let url = reqwest::Url::parse(&config.endpoint)?;
+        if !matches!(url.scheme(), "http" | "https") {
+            anyhow::bail!("unsupported metrics push scheme {}", url.scheme());
+        }
+        let timeout = Duration::from_millis(config.timeout_ms.max(1));
+        let client = reqwest::Client::builder().timeout(timeout).build()?;
+        let instance = config
+            .instance_id
+            .clone()
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        let mut resource = vec![
+            attribute("service.name", service),
+            attribute("service.instance.id", &instance),
+        ];
+        if let Ok(host) = std::env::var("HOSTNAME") {
+            resource.push(attribute("host.name", &host));
+        }
+        info!(
+            "Pushing metrics to {} every {}ms as instance {}",
+            url, config.interval_ms, instance
+        );
+
+        let push = Push {
+            client,
+            url,
+            username: config.username.clone(),
+            password: config.password.clone(),
+            resource,
+            start_unix_nanos: unix_nanos(),
+        };
+        let interval = Duration::from_millis(config.interval_ms.max(1));
+        let (stop, mut stopped) = oneshot::channel();
+        let task = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            ticker.tick().await;
+            loop {
+                // A dropped sender stops the task too, after the final push
+                let last = tokio::select! {
+                    _ = ticker.tick() => false,
+                    _ = &mut stopped => true,
+                };
+                push.send(&handle.render()).await;
+                if last {
+                    break;
+                }
+            }
+        });
+        Ok(Self {
+            stop: Some(stop),
+            task: Some(task),
+            timeout,
+        })
+    }
+
+    /// Send a final push and wait for it, up to the push timeout
+    pub async fn shutdown(mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+        if let Some(task) = self.task.take() {
+            if tokio::time::timeout(self.timeout + Duration::from_millis(100), task)
+                .await
+                .is_err()
+            {
+                warn!("Final metrics push did not finish in time");
+            }
+        }
+    }
+}
+
+impl Drop for MetricsPusher {
+    fn drop(&mut self) {
+        // Best effort: the final push only lands if the runtime outlives us
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+    }
+}
+
+struct Push {
+    client: reqwest::Client,
+    url: reqwest::Url,
+    username: Option<String>,
+    password: Option<String>,
+    resource: Vec<Value>,
+    start_unix_nanos: u128,
+}
+
+impl Push {
+    async fn send(&self, rendered: &str) {
+        let body = otlp_request(
+            &self.resource,
+            rendered,
+            self.start_unix_nanos,
+            unix_nanos(),
+        );
+        let mut request = self
+            .client
+            .post(self.url.clone())
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_string());
+        if let Some(username) = &self.username {
+            request = request.basic_auth(username, self.password.as_ref());
+        }
+        match request.send().await.and_then(|r| r.error_for_status()) {
+            Ok(_) => debug!("Pushed metrics to {}", self.url),
+            Err(err) => warn!("Metrics push to {} failed: {}", self.url, err),
+        }
+    }
+}
+
+fn unix_nanos() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+}
+
+fn attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Counter,
+    Gauge,
+    Histogram,
+    Summary,
+}
+
+type Labels = Vec<(String, String)>;
+
+#[derive(Debug, Default)]
+struct Point {
+    value: f64,
+    sum: f64,
+    count: f64,
+    /// `(le, cumulative count)` for histograms, `(quantile, value)` for summaries
+    buckets: Vec<(f64, f64)>,
+}
+
+#[derive(Debug)]
+struct Family {
+    name: String,
+    kind: Kind,
+    points: BTreeMap<Labels, Point>,
+}
+
+/// Build an OTLP `ExportMetricsServiceRequest` from the Prometheus text format
+fn otlp_request(resource: &[Value], rendered: &str, start: u128, now: u128) -> Value {
+    let metrics: Vec<Value> = parse_families(rendered)
+        .iter()
+        .map(|family| family.to_otlp(&start.to_string(), &now.to_string()))
+        .collect();
+    json!({
+        "resourceMetrics": [{
+            "resource": { "attributes": resource },
+            "scopeMetrics": [{
+                "scope": { "name": "aether", "version": env!("CARGO_PKG_VERSION") },
+                "metrics": metrics,
+            }],
+        }],
+    })
+}
+
+fn parse_families(rendered: &str) -> Vec<Family> {
+    let mut families: Vec<Family> = Vec::new();
+    for line in rendered.lines().map(str::trim) {
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(declaration) = line.strip_prefix("# TYPE ") {
+            let mut parts = declaration.split_whitespace();
+            if let (Some(name), Some(kind)) = (parts.next(), parts.next()) {
+                let kind = match kind {
+                    "counter" => Kind::Counter,
+                    "histogram" => Kind::Histogram,
+                    "summary" => Kind::Summary,
+                    _ => Kind::Gauge,
+                };
+                families.push(Family {
+                    name: name.to_string(),
+                    kind,
+                    points: BTreeMap::new(),
+                });
+            }
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+        let Some((name, mut labels, value)) = parse_sample(line) else {
+            debug!("Skipping unparsable metrics line: {}", line);
+            continue;
+        };
+        let family = match families.last_mut() {
+            Some(family) if name.starts_with(family.name.as_str()) => family,
+            _ => {
+                families.push(Family {
+                    name: name.clone(),
+                    kind: Kind::Gauge,
+                    points: BTreeMap::new(),
+                });
+                families.last_mut().expect("just pushed")
+            }
+        };
+        let suffix = &name[family.name.len()..];
+        let bound = match (family.kind, suffix) {
+            (Kind::Histogram, "_bucket") => take_label(&mut labels, "le"),
+            (Kind::Summary, "") => take_label(&mut labels, "quantile"),
+            _ => None,
+        };
+        let point = family.points.entry(labels).or_default();
+        match (suffix, bound) {
+            (_, Some(bound)) => point.buckets.push((bound, value)),
+            ("_sum", None) if family.kind != Kind::Counter && family.kind != Kind::Gauge => {
+                point.sum = value
+            }
+            ("_count", None) if family.kind != Kind::Counter && family.kind != Kind::Gauge => {
+                point.count = value
+            }
+            _ => point.value = value,
+        }
+    }
+    families
+}
+
+fn take_label(labels: &mut Labels, key: &str) -> Option<f64> {
+    let index = labels.iter().position(|(k, _)| k == key)?;
+    labels.remove(index).1.parse().ok()
+}
+
+/// `name{key="value",...} 1.5`, with the label values unescaped
+fn parse_sample(line: &str) -> Option<(String, Labels, f64)> {
+    let name_end = line
+        .find(|c: char| c == '{' || c.is_whitespace())
+        .unwrap_or(line.len());
+    let name = line[..name_end].to_string();
+    let mut rest = &line[name_end..];
+    let mut labels = Labels::new();
+    if let Some(inner) = rest.strip_prefix('{') {
+        let mut chars = inner.char_indices().peekable();
+        let mut key = String::new();
+        let end = loop {
+            let (index, c) = chars.next()?;
+            match c {
+                '}' => break index,
+                ',' | ' ' => {}
+                '=' => {
+                    if chars.next()?.1 != '"' {
+                        return None;
+                    }
+                    let mut value = String::new();
+                    loop {
+                        match chars.next()?.1 {
+                            '"' => break,
+                            '\\' => match chars.next()?.1 {
+                                'n' => value.push('\n'),
+                                other => value.push(other),
+                            },
+                            other => value.push(other),
+                        }
+                    }
+                    labels.push((std::mem::take(&mut key), value));
+                }
+                other => key.push(other),
+            }
+        };
+        rest = &inner[end + 1..];
+    }
+    let value = rest.split_whitespace().next()?.parse().ok()?;
+    Some((name, labels, value))
+}
+
+impl Family {
+    fn to_otlp(&self, start: &str, now: &str) -> Value {
+        let points = self.points.iter().map(|(labels, point)| {
+            let attributes: Vec<Value> = labels.iter().map(|(k, v)| attribute(k, v)).collect();
+            let mut data = json!({
+                "attributes": attributes,
+                "startTimeUnixNano": start,
+                "timeUnixNano": now,
+            });
+            let fields = data.as_object_mut().expect("object literal");
+            match self.kind {
+                Kind::Counter | Kind::Gauge => {
+                    fields.insert("asDouble".to_string(), json!(point.value));
+                }
+                Kind::Histogram => {
+                    let mut buckets = point.buckets.clone();
+                    buckets.sort_by(|a, b| a.0.total_cmp(&b.0));
+                    let mut bounds = Vec::new();
+                    let mut counts = Vec::new();
+                    let mut previous = 0.0;
+                    for (le, cumulative) in buckets {
+                        if le.is_finite() {
+                            bounds.push(le);
+                        }
+                        counts.push(((cumulative - previous).max(0.0) as u64).to_string());
+                        previous = cumulative;
+                    }
+                    if counts.len() == bounds.len() {
+                        // No +Inf bucket rendered; the rest of the count overflows
+                        counts.push(((point.count - previous).max(0.0) as u64).to_string());
+                    }
+                    fields.insert("count".to_string(), json!((point.count as u64).to_string()));
+                    fields.insert("sum".to_string(), json!(point.sum));
+                    fields.insert("explicitBounds".to_string(), json!(bounds));
+                    fields.insert("bucketCounts".to_string(), json!(counts));
+                }
+                Kind::Summary => {
+                    let quantiles: Vec<Value> = point
+                        .buckets
+                        .iter()
+                        .map(|(quantile, value)| json!({ "quantile": quantile, "value": value }))
+                        .collect();
+                    fields.insert("count".to_string(), json!((point.count as u64).to_string()));
+                    fields.insert("sum".to_string(), json!(point.sum));
+                    fields.insert("quantileValues".to_string(), json!(quantiles));
+                }
+            }
+            data
+        });
+        let points: Vec<Value> = points.collect();
+        let data = match self.kind {
+            Kind::Counter => (
+                "sum",
+                json!({
+                    "dataPoints": points,
+                    "aggregationTemporality": 2,
+                    "isMonotonic": true,
+                }),
+            ),
+            Kind::Gauge => ("gauge", json!({ "dataPoints": points })),
+            Kind::Histogram => (
+                "histogram",
+                json!({
+                    "dataPoints": points,
+                    "aggregationTemporality": 2,
+                }),
+            ),
+            Kind::Summary => ("summary", json!({ "dataPoints": points })),
+        };
+        json!({ "name": self.name, data.0: data.1 })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observability::prometheus_builder;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_rendered_metrics_convert_to_otlp() {
+        let rendered = "\
+# TYPE aether_waves_total counter
+aether_waves_total{channel=\"orders.created\",note=\"a \\\"quoted\\\" one\"} 3
+# TYPE aether_inflight gauge
+aether_inflight 1.5
+# TYPE aether_latency_seconds histogram
+aether_latency_seconds_bucket{le=\"0.1\"} 2
+aether_latency_seconds_bucket{le=\"1\"} 3
+aether_latency_seconds_bucket{le=\"+Inf\"} 4
+aether_latency_seconds_sum 2.5
+aether_latency_seconds_count 4
+";
+        let request = otlp_request(&[attribute("service.name", "billing")], rendered, 1, 2);
+        let metrics = &request["resourceMetrics"][0]["scopeMetrics"][0]["metrics"];
+
+        let waves = &metrics[0];
+        assert_eq!(waves["name"], "aether_waves_total");
+        assert_eq!(waves["sum"]["isMonotonic"], true);
+        let point = &waves["sum"]["dataPoints"][0];
+        assert_eq!(point["asDouble"], 3.0);
+        assert_eq!(
+            point["attributes"][1]["value"]["stringValue"],
+            "a \"quoted\" one"
+        );
+
+        assert_eq!(metrics[1]["gauge"]["dataPoints"][0]["asDouble"], 1.5);
+
+        let latency = &metrics[2]["histogram"]["dataPoints"][0];
+        assert_eq!(latency["explicitBounds"], json!([0.1, 1.0]));
+        assert_eq!(latency["bucketCounts"], json!(["2", "1", "1"]));
+        assert_eq!(latency["count"], "4");
+        assert_eq!(latency["sum"], 2.5);
+    }
+
+    #[tokio::test]
+    async fn test_pusher_tags_the_instance_and_pushes_on_shutdown() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/v1/metrics", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            while serde_json::from_str::<Value>(
+                String::from_utf8_lossy(&request)
+                    .split_once("\r\n\r\n")
+                    .map_or("", |(_, body)| body),
+            )
+            .is_err()
+            {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                .await
+                .unwrap();
+            let request = String::from_utf8_lossy(&request).to_string();
+            let (_, body) = request.split_once("\r\n\r\n").unwrap();
+            serde_json::from_str::<Value>(body).unwrap()
+        });
+
+        let recorder = prometheus_builder().unwrap().build_recorder();
+        metrics::with_local_recorder(&recorder, || {
+            metrics::counter!("aether_jobs_total").increment(7);
+        });
+        let config = MetricsPushConfig {
+            endpoint,
+            // Long enough that only the shutdown push can be seen
+            interval_ms: 60_000,
+            timeout_ms: 2_000,
+            username: None,
+            password: None,
+            instance_id: Some("batch-7".to_string()),
+        };
+        let pusher = MetricsPusher::spawn(&config, "billing", recorder.handle()).unwrap();
+        pusher.shutdown().await;
+
+        let body = server.await.unwrap();
+        let resource = &body["resourceMetrics"][0]["resource"]["attributes"];
+        assert!(resource
+            .as_array()
+            .unwrap()
+            .contains(&attribute("service.name", "billing")));
+        assert!(resource
+            .as_array()
+            .unwrap()
+            .contains(&attribute("service.instance.id", "batch-7")));
+        let metrics = &body["resourceMetrics"][0]["scopeMetrics"][0]["metrics"];
+        assert_eq!(metrics[0]["name"], "aether_jobs_total");
+        assert_eq!(metrics[0]["sum"]["dataPoints"][0]["asDouble"], 7.0);
+    }
+}
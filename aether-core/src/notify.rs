@@ -0,0 +1,479 @@
+//! Notifier: forward critical events and alerts to chat/on-call webhooks.
+//!
+//! Two inputs feed it: [`AetherEvent`]s (circuit breaker trips, NATS
+//! disconnects) and alert waves on `alert_channel` (memory leaks, anomalies,
+//! standing waves). Each becomes a [`Notification`] whose `kind` is the event
+//! kind or the alert payload's `kind` field, and every webhook that lists that
+//! kind receives a Slack-compatible `{"text": ...}` POST, over HTTPS for Slack
+//! itself. Repeats of a kind within `min_interval_ms` are suppressed per
+//! webhook and counted in the next message that gets through.
+//!
+//! Only alerts this instance emitted are forwarded: under NATS every
+//! instance sees every alert, and each would otherwise post it again.
+
+use crate::{
+    aether::Aether,
+    channel::Channel,
+    events::AetherEvent,
+    reliability::{retry_with_timeout_named, RetryPolicy},
+    wave::Wave,
+};
+use anyhow::{bail, Context, Result};
+use futures::future::BoxFuture;
+use futures::{FutureExt, StreamExt};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, Semaphore};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+/// Deliveries in flight at once; notifications beyond that wait their turn
+/// without holding up the event loop
+const MAX_CONCURRENT_DELIVERIES: usize = 16;
+
+/// Kind that matches every notification
+pub const ANY_KIND: &str = "*";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotificationsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Alert waves on channels matching this pattern are forwarded
+    #[serde(default = "default_alert_channel")]
+    pub alert_channel: String,
+    /// Per webhook and kind, at most one message per interval
+    #[serde(default = "default_min_interval_ms")]
+    pub min_interval_ms: u64,
+    #[serde(default = "default_retry_max")]
+    pub retry_max: usize,
+    #[serde(default = "default_retry_base_delay_ms")]
+    pub retry_base_delay_ms: u64,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            alert_channel: default_alert_channel(),
+            min_interval_ms: default_min_interval_ms(),
+            retry_max: default_retry_max(),
+            retry_base_delay_ms: default_retry_base_delay_ms(),
+            timeout_ms: default_timeout_ms(),
+            webhooks: Vec::new(),
+        }
+    }
+}
+
+/// One destination (`[[notifications.webhooks]]`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookConfig {
+    pub name: String,
+    /// `https://` or `http://`, e.g. a Slack incoming-webhook URL
+    pub url: String,
+    /// Notification kinds to send, or `"*"` for all
+    #[serde(default = "default_kinds")]
+    pub kinds: Vec<String>,
+    #[serde(default)]
+    pub auth_token: Option<String>,
+}
+
+fn default_alert_channel() -> String {
+    "aether.alerts.>".to_string()
+}
+
+fn default_min_interval_ms() -> u64 {
+    60_000
+}
+
+fn default_retry_max() -> usize {
+    3
+}
+
+fn default_retry_base_delay_ms() -> u64 {
+    500
+}
+
+fn default_timeout_ms() -> u64 {
+    5_000
+}
+
+fn default_kinds() -> Vec<String> {
+    [
+        "circuit_opened",
+        "nats_disconnected",
+        "memory_leak_suspected",
+        "standing_wave",
+    ]
+    .into_iter()
+    .map(str::to_string)
+    .collect()
+}
+
+/// Something worth telling a human about
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub kind: String,
+    pub text: String,
+    pub details: serde_json::Value,
+}
+
+impl Notification {
+    pub fn from_event(event: &AetherEvent) -> Self {
+        let text = match event {
+            AetherEvent::CircuitOpened { name, failures } => {
+                format!(
+                    "Circuit breaker {} opened after {} failures",
+                    name, failures
+                )
+            }
+            AetherEvent::CircuitClosed { name } => format!("Circuit breaker {} closed", name),
+            AetherEvent::NatsDisconnected => "Lost the connection to NATS".to_string(),
+            AetherEvent::NatsReconnected => "Reconnected to NATS".to_string(),
+            AetherEvent::SubscriberLagged {
+                subscriber,
+                channel,
+                skipped,
+            } => format!("{} missed {} waves on {}", subscriber, skipped, channel),
+            AetherEvent::LoopDetected { service, cycle, .. } => {
+                let hops: Vec<String> = cycle
+                    .iter()
+                    .map(|hop| format!("{}@{}", hop.service, hop.channel))
+                    .collect();
+                format!("{} re-emitted a wave: {}", service, hops.join(" -> "))
+            }
+            other => other.kind().replace('_', " "),
+        };
+        Self {
+            kind: event.kind().to_string(),
+            text,
+            details: serde_json::to_value(event).unwrap_or_default(),
+        }
+    }
+
+    /// Alert wave; the kind comes from the payload's `kind` field
+    pub fn from_alert(wave: &Wave) -> Self {
+        let kind = wave.payload()["kind"]
+            .as_str()
+            .unwrap_or("alert")
+            .to_string();
+        let text = match wave.payload()["channel"].as_str() {
+            Some(channel) => format!("{} on {}", kind.replace('_', " "), channel),
+            None => format!("{} ({})", kind.replace('_', " "), wave.channel().name()),
+        };
+        Self {
+            kind,
+            text,
+            details: wave.payload().clone(),
+        }
+    }
+
+    /// Slack incoming-webhook body; other chat tools accept the same `text` field
+    fn to_slack(&self, service: &str, suppressed: u64) -> serde_json::Value {
+        let mut text = format!("*[{}]* {}", service, self.text);
+        if suppressed > 0 {
+            text.push_str(&format!(" ({} similar suppressed)", suppressed));
+        }
+        serde_json::json!({
+            "text": text,
+            "kind": self.kind,
+            "service": service,
+            "details": self.details,
+        })
+    }
+}
+
+/// Last delivery of one kind and the repeats held back since
+#[derive(Debug)]
+struct RateWindow {
+    sent_at: Instant,
+    suppressed: u64,
+}
+
+struct Webhook {
+    name: String,
+    url: reqwest::Url,
+    kinds: Vec<String>,
+    auth_token: Option<String>,
+    windows: HashMap<String, RateWindow>,
+}
+
+impl Webhook {
+    fn wants(&self, kind: &str) -> bool {
+        self.kinds
+            .iter()
+            .any(|wanted| wanted == kind || wanted == ANY_KIND)
+    }
+
+    /// Suppressed count to report if `kind` may be sent now
+    fn admit(&mut self, kind: &str, now: Instant, min_interval: Duration) -> Option<u64> {
+        match self.windows.get_mut(kind) {
+            Some(window) if now < window.sent_at + min_interval => {
+                window.suppressed += 1;
+                metrics::counter!(
+                    "aether_notifications_total",
+                    "webhook" => self.name.clone(),
+                    "outcome" => "suppressed"
+                )
+                .increment(1);
+                None
+            }
+            Some(window) => {
+                let suppressed = std::mem::take(&mut window.suppressed);
+                window.sent_at = now;
+                Some(suppressed)
+            }
+            None => {
+                self.windows.insert(
+                    kind.to_string(),
+                    RateWindow {
+                        sent_at: now,
+                        suppressed: 0,
+                    },
+                );
+                Some(0)
+            }
+        }
+    }
+}
+
+/// Delivers notifications to the configured webhooks
+pub struct Notifier {
+    service: String,
+    client: reqwest::Client,
+    webhooks: Vec<Webhook>,
+    alert_channel: Channel,
+    min_interval: Duration,
+    retry: RetryPolicy,
+    timeout: Duration,
+}
+
+impl Notifier {
+    /// `None` when notifications are disabled or no webhook is configured
+    pub fn from_config(config: &NotificationsConfig, service: &str) -> Result<Option<Self>> {
+        if !config.enabled || config.webhooks.is_empty() {
+            return Ok(None);
+        }
+        let webhooks = config
+            .webhooks
+            .iter()
+            .map(|webhook| {
+                Ok(Webhook {
+                    name: webhook.name.clone(),
+                    url: parse_webhook_url(&webhook.url)
+                        .with_context(|| format!("invalid webhook {}", webhook.name))?,
+                    kinds: webhook.kinds.clone(),
+                    auth_token: webhook.auth_token.clone(),
+                    windows: HashMap::new(),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Some(Self {
+            service: service.to_string(),
+            client: reqwest::Client::builder()
+                .build()
+                .context("failed to build the webhook client")?,
+            webhooks,
+            alert_channel: Channel::new(config.alert_channel.clone()),
+            min_interval: Duration::from_millis(config.min_interval_ms),
+            retry: RetryPolicy::new(
+                config.retry_max,
+                Duration::from_millis(config.retry_base_delay_ms),
+                Duration::from_millis(config.retry_base_delay_ms.saturating_mul(8)),
+            ),
+            timeout: Duration::from_millis(config.timeout_ms.max(1)),
+        }))
+    }
+
+    /// Send to every webhook that wants this kind and is not rate limited;
+    /// returns how many accepted it
+    pub async fn notify(&mut self, notification: &Notification) -> usize {
+        futures::future::join_all(self.deliveries(notification))
+            .await
+            .into_iter()
+            .filter(|delivered| *delivered)
+            .count()
+    }
+
+    /// One delivery, retries included, per webhook admitting `notification`
+    fn deliveries(&mut self, notification: &Notification) -> Vec<BoxFuture<'static, bool>> {
+        let now = Instant::now();
+        let mut deliveries = Vec::new();
+        for webhook in &mut self.webhooks {
+            if !webhook.wants(&notification.kind) {
+                continue;
+            }
+            let Some(suppressed) = webhook.admit(&notification.kind, now, self.min_interval) else {
+                continue;
+            };
+            let body = notification.to_slack(&self.service, suppressed).to_string();
+            let client = self.client.clone();
+            let url = webhook.url.clone();
+            let auth_token = webhook.auth_token.clone();
+            let name = webhook.name.clone();
+            let kind = notification.kind.clone();
+            let retry = self.retry.clone();
+            let timeout = self.timeout;
+            deliveries.push(
+                async move {
+                    let operation = format!("webhook {}", name);
+                    let result = retry_with_timeout_named(&operation, &retry, timeout, || {
+                        let mut request = client
+                            .post(url.clone())
+                            .header(reqwest::header::CONTENT_TYPE, "application/json")
+                            .body(body.clone());
+                        if let Some(token) = &auth_token {
+                            request = request.bearer_auth(token);
+                        }
+                        async move { request.send().await?.error_for_status().map(|_| ()) }
+                    })
+                    .await;
+                    let outcome = match &result {
+                        Ok(()) => "sent",
+                        Err(err) => {
+                            warn!("Failed to notify {} of {}: {}", name, kind, err);
+                            "failed"
+                        }
+                    };
+                    metrics::counter!(
+                        "aether_notifications_total",
+                        "webhook" => name,
+                        "outcome" => outcome
+                    )
+                    .increment(1);
+                    result.is_ok()
+                }
+                .boxed(),
+            );
+        }
+        deliveries
+    }
+
+    /// Forward the layer's events and the alert waves it emitted until the
+    /// layer goes away
+    ///
+    /// Deliveries run in the background, so a slow or retrying webhook does
+    /// not hold up the notifications behind it.
+    pub async fn spawn(mut self, aether: &Aether) -> JoinHandle<()> {
+        let mut events = aether.events();
+        let alerts = aether.tap(self.alert_channel.clone()).await;
+        let instance = aether.instance_id();
+        let in_flight = Arc::new(Semaphore::new(MAX_CONCURRENT_DELIVERIES));
+        info!(
+            "Notifying {} webhook(s) of events and {} alerts",
+            self.webhooks.len(),
+            self.alert_channel
+        );
+        tokio::spawn(async move {
+            futures::pin_mut!(alerts);
+            loop {
+                let notification = tokio::select! {
+                    event = events.recv() => match event {
+                        Ok(event) => Notification::from_event(&event),
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            warn!("Notifier missed {} events", skipped);
+                            continue;
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    wave = alerts.next() => match wave {
+                        // Another instance's alert; that instance posts it
+                        Some(wave) if wave.instance() != Some(&instance) => continue,
+                        Some(wave) => Notification::from_alert(&wave),
+                        None => break,
+                    },
+                };
+                for delivery in self.deliveries(&notification) {
+                    let in_flight = Arc::clone(&in_flight);
+                    tokio::spawn(async move {
+                        let _permit = in_flight.acquire_owned().await;
+                        delivery.await;
+                    });
+                }
+            }
+        })
+    }
+}
+
+fn parse_webhook_url(url: &str) -> Result<reqwest::Url> {
+    let url = reqwest::Url::parse(url)?;
+    if !matches!(url.scheme(), "http" | "https") {
+        bail!("unsupported scheme {}", url.scheme());
+    }
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_notifier_retries_filters_and_rate_limits() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hooks/ops", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for status in ["503 Service Unavailable", "200 OK", "200 OK"] {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                // Headers, then a JSON object body
+                while !String::from_utf8_lossy(&request)
+                    .split_once("\r\n\r\n")
+                    .is_some_and(|(_, body)| body.ends_with('}'))
+                {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                requests.push(String::from_utf8_lossy(&request).to_string());
+                let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status);
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+            requests
+        });
+
+        let config = NotificationsConfig {
+            enabled: true,
+            min_interval_ms: 200,
+            retry_base_delay_ms: 10,
+            webhooks: vec![WebhookConfig {
+                name: "ops".to_string(),
+                url,
+                kinds: default_kinds(),
+                auth_token: None,
+            }],
+            ..NotificationsConfig::default()
+        };
+        let mut notifier = Notifier::from_config(&config, "billing").unwrap().unwrap();
+        let opened = Notification::from_event(&AetherEvent::CircuitOpened {
+            name: "billing.breaker".to_string(),
+            failures: 5,
+        });
+
+        assert_eq!(notifier.notify(&opened).await, 1);
+        assert_eq!(notifier.notify(&opened).await, 0);
+        let created = Notification::from_event(&AetherEvent::ChannelCreated {
+            channel: "orders.created".to_string(),
+        });
+        assert_eq!(notifier.notify(&created).await, 0);
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert_eq!(notifier.notify(&opened).await, 1);
+
+        let requests = server.await.unwrap();
+        assert!(requests[1].starts_with("POST /hooks/ops HTTP/1.1"));
+        assert!(requests[1]
+            .to_ascii_lowercase()
+            .contains("content-type: application/json"));
+        assert!(requests[1]
+            .contains("*[billing]* Circuit breaker billing.breaker opened after 5 failures"));
+        assert!(requests[2].contains("(1 similar suppressed)"));
+    }
+}
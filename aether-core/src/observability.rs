@@ -1,12 +1,18 @@
 //! Observability utilities: logging, metrics, and tracing.
 
 use crate::config::AppConfig;
+use crate::exemplar::{ExemplarRecorder, Exemplars, LATENCY_SUFFIX};
+use crate::handler_metrics::LATENCY_BUCKETS;
+use crate::metrics_push::MetricsPusher;
+use crate::sampling::{self, WaveSampler};
+use metrics_exporter_prometheus::Matcher;
 use metrics_exporter_prometheus::PrometheusBuilder;
 use metrics_exporter_prometheus::PrometheusHandle;
 use opentelemetry::KeyValue;
 use opentelemetry_otlp::WithExportConfig;
 use opentelemetry_sdk::runtime::Tokio;
 use opentelemetry_sdk::trace as sdktrace;
+use std::sync::{Arc, OnceLock};
 use std::time::Duration;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpListener;
@@ -14,11 +20,23 @@ use tokio::task::JoinHandle;
 use tracing::{info, warn};
 use tracing_subscriber::layer::{Layer, SubscriberExt};
 use tracing_subscriber::util::SubscriberInitExt;
-use tracing_subscriber::{fmt, EnvFilter};
+use tracing_subscriber::{fmt, reload, EnvFilter, Registry};
+
+static LOG_FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
 
 #[derive(Debug)]
 pub struct ObservabilityGuard {
     _metrics_task: Option<JoinHandle<()>>,
+    metrics_push: Option<MetricsPusher>,
+}
+
+impl ObservabilityGuard {
+    /// Flush what would otherwise be lost with the process: one last metrics push
+    pub async fn shutdown(mut self) {
+        if let Some(pusher) = self.metrics_push.take() {
+            pusher.shutdown().await;
+        }
+    }
 }
 
 impl Drop for ObservabilityGuard {
@@ -31,6 +49,9 @@ pub fn init_observability(config: &AppConfig) -> anyhow::Result<ObservabilityGua
     let env_filter = EnvFilter::try_from_default_env()
         .or_else(|_| EnvFilter::try_new(config.logging.level.clone()))
         .unwrap_or_else(|_| EnvFilter::new("info"));
+    let (env_filter, filter_handle) = reload::Layer::new(env_filter);
+    let _ = LOG_FILTER.set(filter_handle);
+    sampling::set_wave_log_ratio(config.observability.sampling.wave_log_ratio);
 
     let fmt_layer: Box<dyn tracing_subscriber::Layer<_> + Send + Sync> = if config.observability.log_json {
         fmt::layer()
@@ -59,7 +80,11 @@ pub fn init_observability(config: &AppConfig) -> anyhow::Result<ObservabilityGua
 
         let tracer = opentelemetry_otlp::new_pipeline()
             .tracing()
-            .with_trace_config(sdktrace::Config::default().with_resource(resource))
+            .with_trace_config(
+                sdktrace::Config::default()
+                    .with_resource(resource)
+                    .with_sampler(WaveSampler::new(&config.observability.sampling)),
+            )
             .with_exporter(
                 opentelemetry_otlp::new_exporter()
                     .tonic()
@@ -74,30 +99,60 @@ pub fn init_observability(config: &AppConfig) -> anyhow::Result<ObservabilityGua
         registry.init();
     }
 
-    let metrics_task = if config.observability.metrics_enabled {
-        let handle = install_metrics_recorder()?;
-        Some(spawn_metrics_server(
-            config.observability.metrics_bind.clone(),
-            handle,
-        ))
+    let push = config.observability.metrics_push.as_ref();
+    let (metrics_task, metrics_push) = if config.observability.metrics_enabled || push.is_some() {
+        let (handle, exemplars) = install_metrics_recorder()?;
+        let metrics_push = push
+            .map(|push| MetricsPusher::spawn(push, &config.service.name, handle.clone()))
+            .transpose()?;
+        let metrics_task = config.observability.metrics_enabled.then(|| {
+            spawn_metrics_server(config.observability.metrics_bind.clone(), handle, exemplars)
+        });
+        (metrics_task, metrics_push)
     } else {
-        None
+        (None, None)
     };
 
     info!("Observability initialized");
 
     Ok(ObservabilityGuard {
         _metrics_task: metrics_task,
+        metrics_push,
     })
 }
 
-fn install_metrics_recorder() -> anyhow::Result<PrometheusHandle> {
-    let builder = PrometheusBuilder::new();
-    let handle = builder.install_recorder()?;
-    Ok(handle)
+/// Replace the active log filter (e.g. "debug" or "aether_core=trace")
+pub fn set_log_level(directives: &str) -> anyhow::Result<()> {
+    let handle = LOG_FILTER
+        .get()
+        .ok_or_else(|| anyhow::anyhow!("observability not initialized"))?;
+    let filter = EnvFilter::try_new(directives)?;
+    handle.reload(filter)?;
+    info!("Log level changed to {}", directives);
+    Ok(())
+}
+
+/// Install the global recorder
+fn install_metrics_recorder() -> anyhow::Result<(PrometheusHandle, Arc<Exemplars>)> {
+    let recorder = prometheus_builder()?.build_recorder();
+    let handle = recorder.handle();
+    let exemplars = Arc::new(Exemplars::default());
+    metrics::set_global_recorder(ExemplarRecorder::new(recorder, Arc::clone(&exemplars)))?;
+    Ok((handle, exemplars))
+}
+
+/// Latency metrics (`*_seconds`) render as histograms with fixed buckets
+pub(crate) fn prometheus_builder() -> anyhow::Result<PrometheusBuilder> {
+    let builder = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Suffix(LATENCY_SUFFIX.to_string()), LATENCY_BUCKETS)?;
+    Ok(builder)
 }
 
-fn spawn_metrics_server(bind: String, handle: PrometheusHandle) -> JoinHandle<()> {
+fn spawn_metrics_server(
+    bind: String,
+    handle: PrometheusHandle,
+    exemplars: Arc<Exemplars>,
+) -> JoinHandle<()> {
     tokio::spawn(async move {
         match TcpListener::bind(&bind).await {
             Ok(listener) => {
@@ -106,8 +161,11 @@ fn spawn_metrics_server(bind: String, handle: PrometheusHandle) -> JoinHandle<()
                     match listener.accept().await {
                         Ok((mut socket, _)) => {
                             let handle = handle.clone();
+                            let exemplars = Arc::clone(&exemplars);
                             tokio::spawn(async move {
-                                if let Err(err) = serve_metrics(&mut socket, handle).await {
+                                if let Err(err) =
+                                    serve_metrics(&mut socket, handle, &exemplars).await
+                                {
                                     warn!("Metrics request failed: {}", err);
                                 }
                             });
@@ -129,12 +187,26 @@ fn spawn_metrics_server(bind: String, handle: PrometheusHandle) -> JoinHandle<()
 async fn serve_metrics(
     socket: &mut tokio::net::TcpStream,
     handle: PrometheusHandle,
+    exemplars: &Exemplars,
 ) -> anyhow::Result<()> {
     let mut buffer = [0u8; 1024];
     let n = socket.read(&mut buffer).await?;
     let request = String::from_utf8_lossy(&buffer[..n]);
 
-    let response = if request.starts_with("GET /metrics") {
+    // Exemplars are only part of the OpenMetrics format
+    let response = if request.starts_with("GET /metrics")
+        && request
+            .to_ascii_lowercase()
+            .contains("application/openmetrics-text")
+    {
+        let body = exemplars.render_openmetrics(&handle.render());
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/openmetrics-text; version=1.0.0; \
+             charset=utf-8\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    } else if request.starts_with("GET /metrics") {
         let body = handle.render();
         format!(
             "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\n\r\n{}",
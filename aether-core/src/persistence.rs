@@ -1,48 +1,784 @@
 //! Persistence: append-only log and snapshot for restart recovery.
 
-use crate::{AetherStats, Wave};
-use anyhow::Result;
+use crate::audit::{AuditEvent, AUDIT_TREE};
+use crate::encryption::{from_hex, sealed_key_id, Keyring, SEALED_MAGIC};
+use crate::{registry::RegistryConfig, AetherStats, Wave};
+use anyhow::{bail, Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sled::{Db, Tree};
-use std::path::Path;
+use sha2::{Digest, Sha256};
+use sled::transaction::ConflictableTransactionError;
+use sled::{Db, IVec, Transactional, Tree};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use uuid::Uuid;
 
 const META_TREE: &str = "meta";
 const LOG_TREE: &str = "log";
+const QUARANTINE_TREE: &str = "quarantine";
+const WAVE_ID_TREE: &str = "wave_ids";
+const CHANNEL_INDEX_TREE: &str = "channel_index";
+const CORRELATION_INDEX_TREE: &str = "correlation_index";
 const KEY_LAST_INDEX: &[u8] = b"last_index";
 const KEY_SNAPSHOT: &[u8] = b"snapshot";
+const KEY_EPOCH: &[u8] = b"epoch";
+const KEY_INSTANCE: &[u8] = b"instance";
+const KEY_INDEX_VERSION: &[u8] = b"index_version";
+const KEY_INDEX_BLINDING: &[u8] = b"index_blinding";
+const CURSOR_PREFIX: &str = "cursor:";
+/// Lock file in the store directory held by the one process writing the store
+const LEASE_FILE: &str = "writer.lease";
+
+/// Background flush interval of stores opened with `WaveStore::open`
+const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Bumped whenever the secondary indices change shape; older stores are
+/// reindexed when opened
+const INDEX_VERSION: u64 = 1;
+
+/// `format` field of a wave archive's header line
+const ARCHIVE_FORMAT: &str = "aether-wave-archive";
+/// Archive layout written by `WaveStore::export`; `import` reads this and older
+const ARCHIVE_VERSION: u32 = 2;
+
+/// First byte of a checksummed log entry; older entries are bare JSON
+const ENTRY_MAGIC: u8 = 0xAE;
+/// Magic byte plus big-endian CRC32 of the JSON body
+const ENTRY_HEADER_LEN: usize = 5;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AetherSnapshot {
     pub last_index: u64,
     pub stats: AetherStats,
     pub timestamp: DateTime<Utc>,
+    /// Last sequence number per channel (transport name) and source
+    #[serde(default)]
+    pub sequences: HashMap<String, u64>,
+    /// Local channels (transport names)
+    #[serde(default)]
+    pub channels: Vec<String>,
+    /// Names of the vibrators attached when the snapshot was taken
+    #[serde(default)]
+    pub vibrators: Vec<String>,
+    #[serde(default)]
+    pub registry: Option<RegistryConfig>,
+    /// Waves transmitted per channel name up to `last_index`
+    #[serde(default)]
+    pub channel_totals: BTreeMap<String, u64>,
+    /// Positions saved by named log readers (exporters, catch-up cursors,
+    /// projection checkpoints)
+    #[serde(default)]
+    pub cursors: BTreeMap<String, u64>,
+}
+
+impl AetherSnapshot {
+    /// First log index anything recorded in the snapshot still has to read
+    ///
+    /// Entries before it are covered by the snapshot and by every reader's
+    /// cursor, so retention may drop them.
+    pub fn retain_from(&self) -> u64 {
+        self.cursors
+            .values()
+            .copied()
+            .fold(self.last_index + 1, u64::min)
+    }
+}
+
+/// When `Aether::emit` returns relative to the log write
+///
+/// Set for the layer by `persistence_durability` and per wave by
+/// [`EmitOptions`](crate::receipt::EmitOptions). Only single waves can be
+/// `Unlogged`: such a wave never gets a log index, so it is missing from
+/// `wave_ids`, catch-up and replay.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Durability {
+    /// Not logged at all, for waves a crash may lose without harm;
+    /// rejected as the layer's `persistence_durability`
+    Unlogged,
+    /// Fire-and-forget: queue the write and flush on the background interval
+    #[default]
+    Buffered,
+    /// Wait until the wave is written and fsynced; concurrent emits share a flush
+    #[serde(alias = "sync")]
+    Fsync,
+}
+
+/// What a recovering read does with entries that fail to decode
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RecoveryMode {
+    /// Leave them in the log and read past them
+    #[default]
+    Skip,
+    /// Move them out of the log into the quarantine tree
+    Quarantine,
+}
+
+/// A log entry that failed its checksum or did not decode
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CorruptEntry {
+    pub index: u64,
+    pub reason: String,
+}
+
+/// Outcome of scanning the log
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RecoveryReport {
+    /// Entries scanned, corrupt ones included
+    pub scanned: usize,
+    pub corrupt: Vec<CorruptEntry>,
+    /// Index after the last entry scanned
+    pub next_index: u64,
+}
+
+/// First line of a wave archive
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchiveHeader {
+    format: String,
+    version: u32,
+    exported_at: DateTime<Utc>,
+    last_index: Option<u64>,
+    epoch: u64,
+    snapshot: Option<AetherSnapshot>,
+    cursors: BTreeMap<String, u64>,
+}
+
+/// One line between an archive's header and trailer
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum ArchiveEntry {
+    /// A logged wave at its original index
+    Wave { index: u64, wave: Wave },
+    /// A logged wave sealed by the exporting store's keyring, hex encoded
+    Sealed { index: u64, sealed: String },
+    /// An audit log entry, chain hashes intact
+    Audit { audit: AuditEvent },
+}
+
+/// Last line of a wave archive: SHA-256 over every line before it
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchiveTrailer {
+    entries: usize,
+    #[serde(default)]
+    audit_events: usize,
+    sha256: String,
+}
+
+/// What `WaveStore::export` wrote or `WaveStore::import` restored
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveSummary {
+    pub version: u32,
+    pub entries: usize,
+    pub audit_events: usize,
+    /// Whether the waves are sealed rather than in the clear
+    pub sealed: bool,
+    /// Corrupt log entries left out of an export
+    pub skipped: usize,
+    pub last_index: Option<u64>,
+    pub sha256: String,
+}
+
+/// Process holding a store's writer lease, as recorded in its lease file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeaseHolder {
+    pub pid: u32,
+    pub host: String,
+    pub acquired_at: DateTime<Utc>,
+    /// Service whose control plane answers `checkpoint_store` for the store
+    #[serde(default)]
+    pub service: Option<String>,
+}
+
+impl std::fmt::Display for LeaseHolder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "pid {} on {} since {}",
+            self.pid, self.host, self.acquired_at
+        )?;
+        if let Some(service) = &self.service {
+            write!(f, " ({})", service)?;
+        }
+        Ok(())
+    }
+}
+
+/// Exclusive lock on a store directory, released when the process exits
+#[derive(Debug)]
+struct WriterLease {
+    file: File,
+    holder: LeaseHolder,
+}
+
+impl WriterLease {
+    /// Take the lease or fail naming the process that holds it
+    fn acquire(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+        let path = dir.join(LEASE_FILE);
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        if let Err(err) = file.try_lock() {
+            let holder = read_lease_holder(&path).map_or_else(
+                || "another process".to_string(),
+                |holder| holder.to_string(),
+            );
+            bail!(
+                "wave store {} is already open for writing by {} ({}); give each \
+                 instance its own persistence_path, or read a checkpoint of it",
+                dir.display(),
+                holder,
+                err
+            );
+        }
+        let holder = LeaseHolder {
+            pid: std::process::id(),
+            host: std::env::var("HOSTNAME").unwrap_or_else(|_| "unknown host".to_string()),
+            acquired_at: Utc::now(),
+            service: None,
+        };
+        write_lease_holder(&file, &holder)?;
+        Ok(Self { file, holder })
+    }
+
+    /// Who holds the lease on `dir` right now; `None` when nobody does
+    fn holder(dir: &Path) -> Result<Option<LeaseHolder>> {
+        let path = dir.join(LEASE_FILE);
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to open {}", path.display()))
+            }
+        };
+        if file.try_lock_shared().is_ok() {
+            // The file outlives its holder; a lock we can take is a stale one
+            return Ok(None);
+        }
+        Ok(Some(read_lease_holder(&path).unwrap_or(LeaseHolder {
+            pid: 0,
+            host: "unknown host".to_string(),
+            acquired_at: Utc::now(),
+            service: None,
+        })))
+    }
+}
+
+fn read_lease_holder(path: &Path) -> Option<LeaseHolder> {
+    let text = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+fn write_lease_holder(mut file: &File, holder: &LeaseHolder) -> Result<()> {
+    file.set_len(0)?;
+    file.seek(SeekFrom::Start(0))?;
+    serde_json::to_writer(&mut file, holder)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Create `dir` readable by its owner only, as checkpoints hold every wave
+fn create_private_dir(dir: &Path) -> Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder
+        .create(dir)
+        .with_context(|| format!("failed to create {}", dir.display()))
+}
+
+/// Size and entry counts of a store, from `WaveStore::stats`
+#[derive(Debug, Clone, Serialize)]
+pub struct StoreStats {
+    /// Directory of the store; the `store` label of its gauges
+    pub store: String,
+    pub size_on_disk_bytes: u64,
+    /// Entries per tree (log, meta, quarantine and the secondary indices)
+    pub tree_entries: BTreeMap<&'static str, usize>,
+    /// When this handle last flushed to disk, explicitly or in the
+    /// background; `None` until it has
+    pub last_flush: Option<DateTime<Utc>>,
+}
+
+impl StoreStats {
+    pub fn publish_metrics(&self) {
+        let store = self.store.clone();
+        metrics::gauge!("aether_store_size_bytes", "store" => store.clone())
+            .set(self.size_on_disk_bytes as f64);
+        for (tree, entries) in &self.tree_entries {
+            metrics::gauge!("aether_store_tree_entries", "store" => store.clone(), "tree" => *tree)
+                .set(*entries as f64);
+        }
+        if let Some(last_flush) = self.last_flush {
+            let age = (Utc::now() - last_flush).num_milliseconds().max(0);
+            metrics::gauge!("aether_store_last_flush_age_seconds", "store" => store)
+                .set(age as f64 / 1000.0);
+        }
+    }
+}
+
+/// Entries in the large trees, counted once on open and kept up to date by
+/// every write through the store, so `stats` need not walk them
+#[derive(Debug, Default)]
+struct TreeCounts {
+    log: AtomicU64,
+    quarantine: AtomicU64,
+    ids: AtomicU64,
+    channels: AtomicU64,
+    correlations: AtomicU64,
+}
+
+/// Count an insert that returned `previous`
+fn count_insert(count: &AtomicU64, previous: Option<IVec>) {
+    if previous.is_none() {
+        count.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Count a remove that returned `previous`
+fn count_remove(count: &AtomicU64, previous: Option<IVec>) {
+    if previous.is_some() {
+        count.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl RecoveryReport {
+    pub fn is_clean(&self) -> bool {
+        self.corrupt.is_empty()
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct WaveStore {
-    db: Db,
+    /// The background flusher holds a weak reference, so it stops once the
+    /// last handle is dropped
+    db: Arc<Db>,
+    dir: Arc<PathBuf>,
     log: Tree,
     meta: Tree,
+    quarantine: Tree,
+    /// Wave ID to log index
+    ids: Tree,
+    /// `channel \0 index` keys, one per logged wave; the channel is blinded
+    /// when the store is encrypted
+    channels: Tree,
+    /// `correlation ID \0 index` keys, one per logged wave; blinded like `channels`
+    correlations: Tree,
+    /// Seals log entries at rest when set
+    keyring: Option<Arc<Keyring>>,
+    /// Held while the store is open for writing; `None` when read-only
+    lease: Option<Arc<WriterLease>>,
+    /// Unix milliseconds of the last flush, 0 before the first
+    last_flush_ms: Arc<AtomicI64>,
+    counts: Arc<TreeCounts>,
 }
 
 impl WaveStore {
+    /// Open for writing, taking the store's writer lease
+    ///
+    /// Fails with the holder's pid and host while another process has the
+    /// store open for writing.
     pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::open_with_flush_interval(path, DEFAULT_FLUSH_INTERVAL)
+    }
+
+    /// Open with a background flush running every `flush_interval`
+    pub fn open_with_flush_interval(
+        path: impl AsRef<Path>,
+        flush_interval: Duration,
+    ) -> Result<Self> {
+        let lease = WriterLease::acquire(path.as_ref())?;
+        let store = Self::from_db(open_db(path.as_ref())?, path.as_ref(), None, Some(lease))?;
+        store.spawn_flusher(flush_interval);
+        Ok(store)
+    }
+
+    /// Like `open_with_flush_interval`, sealing log entries with `keyring`
+    ///
+    /// Entries written without encryption still read; `reencrypt` seals them.
+    pub fn open_encrypted(
+        path: impl AsRef<Path>,
+        flush_interval: Duration,
+        keyring: Keyring,
+    ) -> Result<Self> {
+        let lease = WriterLease::acquire(path.as_ref())?;
+        let store = Self::from_db(
+            open_db(path.as_ref())?,
+            path.as_ref(),
+            Some(Arc::new(keyring)),
+            Some(lease),
+        )?;
+        store.spawn_flusher(flush_interval);
+        Ok(store)
+    }
+
+    /// Open for reading in place, e.g. from tooling
+    ///
+    /// Fails naming the holder while a process has the store open for
+    /// writing; read a `checkpoint` of it instead. Every write fails, and
+    /// stale indices are left as they are.
+    pub fn open_read_only(path: impl AsRef<Path>, keyring: Option<Keyring>) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.join("conf").exists() {
+            bail!("no wave store at {}", path.display());
+        }
+        if let Some(holder) = WriterLease::holder(path)? {
+            bail!(
+                "wave store {} is open for writing by {}; read a checkpoint of it instead",
+                path.display(),
+                holder
+            );
+        }
         let db = sled::open(path)?;
+        Self::from_db(db, path, keyring.map(Arc::new), None)
+    }
+
+    /// Open a checkpoint written by `checkpoint` for reading, removing it
+    /// when the store is dropped
+    pub fn open_checkpoint(path: impl AsRef<Path>, keyring: Option<Keyring>) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.join("conf").exists() {
+            bail!("no wave store at {}", path.display());
+        }
+        let db = sled::Config::new().path(path).temporary(true).open()?;
+        Self::from_db(db, path, keyring.map(Arc::new), None)
+    }
+
+    /// Who holds the writer lease on the store at `path`, if anyone
+    pub fn lease_holder(path: impl AsRef<Path>) -> Result<Option<LeaseHolder>> {
+        WriterLease::holder(path.as_ref())
+    }
+
+    /// Record the service whose control plane can checkpoint this store, so
+    /// tooling that finds it leased knows whom to ask
+    pub fn set_lease_service(&self, service: &str) -> Result<()> {
+        let lease = self
+            .lease
+            .as_ref()
+            .context("wave store is open read-only")?;
+        let holder = LeaseHolder {
+            service: Some(service.to_string()),
+            ..lease.holder.clone()
+        };
+        write_lease_holder(&lease.file, &holder)
+    }
+
+    /// Copy the store into `dir` through sled's export, without the lease
+    ///
+    /// Only the lease holder can take a consistent copy, so tooling asks the
+    /// writing service for one over the control plane. `dir` is created
+    /// readable by its owner only; sealed entries stay sealed.
+    pub fn checkpoint(&self, dir: impl AsRef<Path>) -> Result<()> {
+        let dir = dir.as_ref();
+        create_private_dir(dir)?;
+        if std::fs::read_dir(dir)?.next().is_some() {
+            bail!("checkpoint directory {} is not empty", dir.display());
+        }
+        self.flush()?;
+        let copy = sled::open(dir)?;
+        copy.import(self.db.export());
+        copy.flush()?;
+        Ok(())
+    }
+
+    /// Keys sealing this store's entries, if it is encrypted
+    pub fn keyring(&self) -> Option<&Keyring> {
+        self.keyring.as_deref()
+    }
+
+    /// Whether this handle holds the writer lease; read-only stores do not
+    pub fn is_writable(&self) -> bool {
+        self.lease.is_some()
+    }
+
+    fn ensure_writable(&self) -> Result<()> {
+        if self.lease.is_none() {
+            bail!("wave store is open read-only");
+        }
+        Ok(())
+    }
+
+    fn from_db(
+        db: Db,
+        dir: &Path,
+        keyring: Option<Arc<Keyring>>,
+        lease: Option<WriterLease>,
+    ) -> Result<Self> {
         let log = db.open_tree(LOG_TREE)?;
         let meta = db.open_tree(META_TREE)?;
-        Ok(Self { db, log, meta })
+        let quarantine = db.open_tree(QUARANTINE_TREE)?;
+        let ids = db.open_tree(WAVE_ID_TREE)?;
+        let channels = db.open_tree(CHANNEL_INDEX_TREE)?;
+        let correlations = db.open_tree(CORRELATION_INDEX_TREE)?;
+        let counts = TreeCounts {
+            log: AtomicU64::new(log.len() as u64),
+            quarantine: AtomicU64::new(quarantine.len() as u64),
+            ids: AtomicU64::new(ids.len() as u64),
+            channels: AtomicU64::new(channels.len() as u64),
+            correlations: AtomicU64::new(correlations.len() as u64),
+        };
+        let store = Self {
+            db: Arc::new(db),
+            dir: Arc::new(dir.to_path_buf()),
+            log,
+            meta,
+            quarantine,
+            ids,
+            channels,
+            correlations,
+            keyring,
+            lease: lease.map(Arc::new),
+            last_flush_ms: Arc::new(AtomicI64::new(0)),
+            counts: Arc::new(counts),
+        };
+        if !store.indices_current()? {
+            if store.is_writable() && store.log.is_empty() {
+                store.stamp_indices()?;
+            } else {
+                warn!(
+                    "Indices of wave store {} are stale; channel and correlation lookups \
+                     miss waves until it is reindexed",
+                    store.dir.display()
+                );
+            }
+        }
+        Ok(store)
+    }
+
+    /// Whether the indices were built by this version under the current blinding key
+    fn indices_current(&self) -> Result<bool> {
+        let version = self
+            .meta
+            .get(KEY_INDEX_VERSION)?
+            .map(|bytes| decode_index(&bytes))
+            .transpose()?;
+        let blinding = self.meta.get(KEY_INDEX_BLINDING)?;
+        Ok(version == Some(INDEX_VERSION) && blinding.as_deref() == Some(self.blinding()))
+    }
+
+    fn stamp_indices(&self) -> Result<()> {
+        self.meta
+            .insert(KEY_INDEX_VERSION, INDEX_VERSION.to_be_bytes().as_slice())?;
+        self.meta.insert(KEY_INDEX_BLINDING, self.blinding())?;
+        Ok(())
     }
 
     pub fn append_wave(&self, wave: &Wave) -> Result<u64> {
+        self.ensure_writable()?;
+        let started = Instant::now();
         let index = self.next_index()?;
-        let key = index.to_be_bytes();
-        let value = serde_json::to_vec(wave)?;
-        self.log.insert(key, value)?;
-        self.meta.insert(KEY_LAST_INDEX, index.to_be_bytes().as_slice())?;
+        self.write_entry(index, wave, true)?;
+        metrics::histogram!("aether_store_append_seconds").record(started.elapsed().as_secs_f64());
         Ok(index)
     }
 
+    /// Write `wave` to the log at `index` together with its index entries
+    /// (and, with `last_index`, the last-index marker) in one transaction
+    fn write_entry(&self, index: u64, wave: &Wave, last_index: bool) -> Result<()> {
+        let key = index.to_be_bytes();
+        let entry = self.encode(wave)?;
+        let channel = index_key(&self.index_name(wave.channel().name()), index);
+        let correlation = index_key(&self.index_name(&wave.correlation_id()), index);
+        let trees = (
+            &self.log,
+            &self.ids,
+            &self.channels,
+            &self.correlations,
+            &self.meta,
+        );
+        let previous = trees.transaction(|(log, ids, channels, correlations, meta)| {
+            let previous = (
+                log.insert(key.as_slice(), entry.as_slice())?,
+                ids.insert(wave.id().as_bytes().as_slice(), key.as_slice())?,
+                channels.insert(channel.as_slice(), &[])?,
+                correlations.insert(correlation.as_slice(), &[])?,
+            );
+            if last_index {
+                meta.insert(KEY_LAST_INDEX, key.as_slice())?;
+            }
+            Ok::<_, ConflictableTransactionError<sled::Error>>(previous)
+        })?;
+        count_insert(&self.counts.log, previous.0);
+        count_insert(&self.counts.ids, previous.1);
+        count_insert(&self.counts.channels, previous.2);
+        count_insert(&self.counts.correlations, previous.3);
+        Ok(())
+    }
+
+    fn index_wave(&self, index: u64, wave: &Wave) -> Result<()> {
+        let previous = self
+            .ids
+            .insert(wave.id().as_bytes(), index.to_be_bytes().as_slice())?;
+        count_insert(&self.counts.ids, previous);
+        let previous = self.channels.insert(
+            index_key(&self.index_name(wave.channel().name()), index),
+            &[],
+        )?;
+        count_insert(&self.counts.channels, previous);
+        let previous = self.correlations.insert(
+            index_key(&self.index_name(&wave.correlation_id()), index),
+            &[],
+        )?;
+        count_insert(&self.counts.correlations, previous);
+        Ok(())
+    }
+
+    /// How `value` appears in the channel and correlation indices
+    fn index_name(&self, value: &str) -> Vec<u8> {
+        match &self.keyring {
+            Some(keyring) => keyring.blind(value.as_bytes()),
+            None => value.as_bytes().to_vec(),
+        }
+    }
+
+    /// Key the indices are blinded under; empty when they are in the clear
+    fn blinding(&self) -> &[u8] {
+        self.keyring
+            .as_ref()
+            .map_or(&[], |keyring| keyring.active_key_id().as_bytes())
+    }
+
+    /// Rebuild the wave ID, channel and correlation indices from the log,
+    /// returning the number of waves indexed
+    ///
+    /// Needed by stores written before the current indices or under another
+    /// blinding key; corrupt entries are left out.
+    pub fn reindex(&self) -> Result<usize> {
+        self.ensure_writable()?;
+        self.rebuild_indices()
+    }
+
+    /// `reindex` on a thread of its own if the indices are stale
+    ///
+    /// Opening never reindexes, so tooling like `store verify` is not held up
+    /// by it; services call this once the store is open.
+    pub fn reindex_in_background(&self) -> Result<Option<std::thread::JoinHandle<()>>> {
+        if !self.is_writable() || self.indices_current()? {
+            return Ok(None);
+        }
+        let store = self.clone();
+        let handle = std::thread::Builder::new()
+            .name("aether-store-reindex".into())
+            .spawn(move || match store.reindex() {
+                Ok(indexed) => info!(
+                    "Reindexed {} waves in wave store {}",
+                    indexed,
+                    store.dir.display()
+                ),
+                Err(err) => warn!(
+                    "Reindexing wave store {} failed: {}",
+                    store.dir.display(),
+                    err
+                ),
+            })
+            .expect("failed to spawn store reindex thread");
+        Ok(Some(handle))
+    }
+
+    fn rebuild_indices(&self) -> Result<usize> {
+        self.ids.clear()?;
+        self.channels.clear()?;
+        self.correlations.clear()?;
+        for count in [
+            &self.counts.ids,
+            &self.counts.channels,
+            &self.counts.correlations,
+        ] {
+            count.store(0, Ordering::Relaxed);
+        }
+        let mut indexed = 0;
+        for item in self.log.iter() {
+            let (key, value) = item?;
+            if let Ok(wave) = self.decode(&value) {
+                self.index_wave(decode_index(&key)?, &wave)?;
+                indexed += 1;
+            }
+        }
+        // Appends racing the clear above can leave the counts off; recount once
+        self.counts
+            .ids
+            .store(self.ids.len() as u64, Ordering::Relaxed);
+        self.counts
+            .channels
+            .store(self.channels.len() as u64, Ordering::Relaxed);
+        self.counts
+            .correlations
+            .store(self.correlations.len() as u64, Ordering::Relaxed);
+        self.stamp_indices()?;
+        Ok(indexed)
+    }
+
+    /// Log index of the wave with this ID, if it was logged
+    pub fn index_of(&self, id: &Uuid) -> Result<Option<u64>> {
+        self.ids
+            .get(id.as_bytes())?
+            .map(|key| decode_index(&key))
+            .transpose()
+    }
+
+    /// The logged wave with this ID and its log index
+    ///
+    /// `None` if it was never logged or its entry is corrupt.
+    pub fn get_wave(&self, id: &Uuid) -> Result<Option<(u64, Wave)>> {
+        let Some(key) = self.ids.get(id.as_bytes())? else {
+            return Ok(None);
+        };
+        let index = decode_index(&key)?;
+        let Some(entry) = self.log.get(&key)? else {
+            return Ok(None);
+        };
+        Ok(self.decode(&entry).ok().map(|wave| (index, wave)))
+    }
+
+    /// Waves logged on exactly this channel (transport name) at or after
+    /// `start_index`, in log order
+    pub fn read_channel(&self, channel: &str, start_index: u64) -> Result<Vec<(u64, Wave)>> {
+        let name = self.index_name(channel);
+        let start = index_key(&name, start_index);
+        let end = index_key(&name, u64::MAX);
+        let keys = self.channels.range(start..=end).keys();
+        self.read_indexed(keys)
+    }
+
+    /// Waves sharing this correlation ID, in log order
+    pub fn read_correlation(&self, correlation_id: &str) -> Result<Vec<(u64, Wave)>> {
+        let mut prefix = self.index_name(correlation_id);
+        prefix.push(0);
+        let keys = self.correlations.scan_prefix(prefix).keys();
+        self.read_indexed(keys)
+    }
+
+    /// Log entries behind index keys; entries since quarantined or corrupt are skipped
+    fn read_indexed(
+        &self,
+        keys: impl Iterator<Item = sled::Result<sled::IVec>>,
+    ) -> Result<Vec<(u64, Wave)>> {
+        let mut waves = Vec::new();
+        for key in keys {
+            let key = key?;
+            let index = decode_index(&key[key.len().saturating_sub(8)..])?;
+            if let Some(entry) = self.log.get(index.to_be_bytes())? {
+                if let Ok(wave) = self.decode(&entry) {
+                    waves.push((index, wave));
+                }
+            }
+        }
+        Ok(waves)
+    }
+
     pub fn load_snapshot(&self) -> Result<Option<AetherSnapshot>> {
         match self.meta.get(KEY_SNAPSHOT)? {
             Some(bytes) => {
@@ -54,36 +790,986 @@ impl WaveStore {
     }
 
     pub fn save_snapshot(&self, snapshot: &AetherSnapshot) -> Result<()> {
+        self.ensure_writable()?;
         let bytes = serde_json::to_vec(snapshot)?;
         self.meta.insert(KEY_SNAPSHOT, bytes)?;
         Ok(())
     }
 
+    /// Waves at or after `start_index`, skipping corrupt entries
     pub fn read_from(&self, start_index: u64) -> Result<Vec<Wave>> {
+        let (waves, _) = self.recover_from(start_index, RecoveryMode::Skip)?;
+        Ok(waves.into_iter().map(|(_, wave)| wave).collect())
+    }
+
+    /// Waves at or after `start_index` with their log index, plus a report of
+    /// the entries that failed their checksum or did not decode
+    pub fn recover_from(
+        &self,
+        start_index: u64,
+        mode: RecoveryMode,
+    ) -> Result<(Vec<(u64, Wave)>, RecoveryReport)> {
+        self.scan(start_index, usize::MAX, mode)
+    }
+
+    /// Like `recover_from` with `RecoveryMode::Skip`, but stops after `limit` entries
+    pub fn read_batch(
+        &self,
+        start_index: u64,
+        limit: usize,
+    ) -> Result<(Vec<(u64, Wave)>, RecoveryReport)> {
+        self.scan(start_index, limit, RecoveryMode::Skip)
+    }
+
+    fn scan(
+        &self,
+        start_index: u64,
+        limit: usize,
+        mode: RecoveryMode,
+    ) -> Result<(Vec<(u64, Wave)>, RecoveryReport)> {
+        if mode == RecoveryMode::Quarantine {
+            self.ensure_writable()?;
+        }
         let mut waves = Vec::new();
-        for item in self
-            .log
-            .range(start_index.to_be_bytes()..)
-        {
+        let mut report = RecoveryReport {
+            next_index: start_index,
+            ..RecoveryReport::default()
+        };
+        for item in self.log.range(start_index.to_be_bytes()..).take(limit) {
+            let (key, value) = item?;
+            let index = decode_index(&key)?;
+            report.scanned += 1;
+            report.next_index = index + 1;
+            match self.decode(&value) {
+                Ok(wave) => waves.push((index, wave)),
+                Err(reason) => {
+                    warn!("Corrupt log entry {}: {}", index, reason);
+                    metrics::counter!("aether_store_corrupt_entries_total").increment(1);
+                    if mode == RecoveryMode::Quarantine && !self.missing_key(&value) {
+                        count_insert(
+                            &self.counts.quarantine,
+                            self.quarantine.insert(&key, value)?,
+                        );
+                        count_remove(&self.counts.log, self.log.remove(&key)?);
+                    }
+                    report.corrupt.push(CorruptEntry { index, reason });
+                }
+            }
+        }
+        Ok((waves, report))
+    }
+
+    /// Check every log entry without modifying the store
+    pub fn verify(&self) -> Result<RecoveryReport> {
+        let mut report = RecoveryReport::default();
+        for item in self.log.iter() {
+            let (key, value) = item?;
+            let index = decode_index(&key)?;
+            report.scanned += 1;
+            report.next_index = index + 1;
+            if let Err(reason) = self.decode(&value) {
+                report.corrupt.push(CorruptEntry { index, reason });
+            }
+        }
+        Ok(report)
+    }
+
+    /// Seal every log entry not yet sealed with the active key, returning how
+    /// many were rewritten
+    ///
+    /// Run after rotating keys; once it returns, keys other than the active
+    /// one can be dropped from the keyring. Entries that do not open are left
+    /// as they are.
+    pub fn reencrypt(&self) -> Result<usize> {
+        self.ensure_writable()?;
+        let Some(keyring) = &self.keyring else {
+            bail!("store is not encrypted");
+        };
+        let mut rewritten = 0;
+        for item in self.log.iter() {
+            let (key, value) = item?;
+            if sealed_key_id(&value).map(|(id, _)| id) == Some(keyring.active_key_id()) {
+                continue;
+            }
+            let Ok(wave) = self.decode(&value) else {
+                continue;
+            };
+            count_insert(&self.counts.log, self.log.insert(key, self.encode(&wave)?)?);
+            rewritten += 1;
+        }
+        Ok(rewritten)
+    }
+
+    /// Write the log, audit trail, snapshot, cursors and epoch to a portable
+    /// archive at `path`
+    ///
+    /// The archive is NDJSON: a versioned header, one line per wave at its
+    /// log index, one per audit entry, and a trailer with the counts and a
+    /// SHA-256 over every line before it. An encrypted store seals the waves
+    /// with its active key, so the archive only restores into a store holding
+    /// that key; `export_decrypted` writes them in the clear. Corrupt entries
+    /// are skipped and counted.
+    pub fn export(&self, path: impl AsRef<Path>) -> Result<ArchiveSummary> {
+        self.write_archive(path.as_ref(), self.keyring.is_some())
+    }
+
+    /// Like `export`, but with every wave in the clear so the archive restores
+    /// under any keyring; keep it as safe as the store
+    pub fn export_decrypted(&self, path: impl AsRef<Path>) -> Result<ArchiveSummary> {
+        self.write_archive(path.as_ref(), false)
+    }
+
+    fn write_archive(&self, path: &Path, sealed: bool) -> Result<ArchiveSummary> {
+        let file = File::create(path)
+            .with_context(|| format!("failed to create {}", path.display()))?;
+        let mut out = BufWriter::new(file);
+        let mut hasher = Sha256::new();
+        let mut write_line = |out: &mut BufWriter<File>, line: Vec<u8>| -> Result<()> {
+            hasher.update(&line);
+            hasher.update(b"\n");
+            out.write_all(&line)?;
+            out.write_all(b"\n")?;
+            Ok(())
+        };
+
+        let header = ArchiveHeader {
+            format: ARCHIVE_FORMAT.to_string(),
+            version: ARCHIVE_VERSION,
+            exported_at: Utc::now(),
+            last_index: self.last_index()?,
+            epoch: self.current_epoch()?,
+            snapshot: self.load_snapshot()?,
+            cursors: self.cursors()?,
+        };
+        write_line(&mut out, serde_json::to_vec(&header)?)?;
+        let (mut entries, mut skipped) = (0, 0);
+        for item in self.log.iter() {
+            let (key, value) = item?;
+            let index = decode_index(&key)?;
+            let wave = match self.decode(&value) {
+                Ok(wave) => wave,
+                Err(reason) => {
+                    warn!("Leaving corrupt log entry {} out of the archive: {}", index, reason);
+                    skipped += 1;
+                    continue;
+                }
+            };
+            let entry = if sealed {
+                ArchiveEntry::Sealed {
+                    index,
+                    sealed: to_hex(&self.encode(&wave)?),
+                }
+            } else {
+                ArchiveEntry::Wave { index, wave }
+            };
+            write_line(&mut out, serde_json::to_vec(&entry)?)?;
+            entries += 1;
+        }
+        let mut audit_events = 0;
+        for item in self.open_tree(AUDIT_TREE)?.iter() {
             let (_, value) = item?;
-            let wave = serde_json::from_slice::<Wave>(&value)?;
-            waves.push(wave);
+            let audit: AuditEvent = serde_json::from_slice(&value)?;
+            write_line(
+                &mut out,
+                serde_json::to_vec(&ArchiveEntry::Audit { audit })?,
+            )?;
+            audit_events += 1;
         }
-        Ok(waves)
+        let sha256 = to_hex(&hasher.finalize());
+        serde_json::to_writer(
+            &mut out,
+            &ArchiveTrailer {
+                entries,
+                audit_events,
+                sha256: sha256.clone(),
+            },
+        )?;
+        out.write_all(b"\n")?;
+        out.into_inner().map_err(|err| err.into_error())?.sync_all()?;
+        Ok(ArchiveSummary {
+            version: ARCHIVE_VERSION,
+            entries,
+            audit_events,
+            sealed,
+            skipped,
+            last_index: header.last_index,
+            sha256,
+        })
     }
 
-    fn next_index(&self) -> Result<u64> {
+    /// Restore an archive written by `export` into this store, which must be empty
+    ///
+    /// A first pass streams the archive to check it against its trailer and
+    /// to open every sealed wave before anything is written; a second pass
+    /// writes it. Waves keep their log indices and are sealed with this
+    /// store's keyring, if any; the epoch only ever moves forward.
+    pub fn import(&self, path: impl AsRef<Path>) -> Result<ArchiveSummary> {
+        let path = path.as_ref();
+        self.ensure_writable()?;
+        let audit = self.open_tree(AUDIT_TREE)?;
+        if !self.log.is_empty() || !audit.is_empty() {
+            bail!("refusing to import into a store that already holds waves");
+        }
+        scan_archive(path, |entry| match entry {
+            ArchiveEntry::Sealed { index, sealed } => self.open_sealed(index, &sealed).map(drop),
+            _ => Ok(()),
+        })?;
+        let (header, trailer, sealed) = scan_archive(path, |entry| {
+            match entry {
+                ArchiveEntry::Wave { index, wave } => self.restore_entry(index, &wave)?,
+                ArchiveEntry::Sealed { index, sealed } => {
+                    self.restore_entry(index, &self.open_sealed(index, &sealed)?)?
+                }
+                ArchiveEntry::Audit { audit: event } => {
+                    audit.insert(event.index.to_be_bytes(), serde_json::to_vec(&event)?)?;
+                }
+            }
+            Ok(())
+        })?;
+        if let Some(last_index) = header.last_index {
+            self.meta
+                .insert(KEY_LAST_INDEX, last_index.to_be_bytes().as_slice())?;
+        }
+        if let Some(snapshot) = &header.snapshot {
+            self.save_snapshot(snapshot)?;
+        }
+        for (name, next_index) in &header.cursors {
+            self.save_cursor(name, *next_index)?;
+        }
+        let epoch = self.current_epoch()?.max(header.epoch);
+        self.meta.insert(KEY_EPOCH, epoch.to_be_bytes().as_slice())?;
+        self.flush()?;
+        Ok(ArchiveSummary {
+            version: header.version,
+            entries: trailer.entries,
+            audit_events: trailer.audit_events,
+            sealed,
+            skipped: 0,
+            last_index: header.last_index,
+            sha256: trailer.sha256,
+        })
+    }
+
+    fn restore_entry(&self, index: u64, wave: &Wave) -> Result<()> {
+        self.write_entry(index, wave, false)
+    }
+
+    fn open_sealed(&self, index: u64, sealed: &str) -> Result<Wave> {
+        let bytes =
+            from_hex(sealed).with_context(|| format!("archive entry {} is not hex", index))?;
+        self.decode(&bytes)
+            .map_err(|reason| anyhow::anyhow!("archive entry {}: {}", index, reason))
+    }
+
+    fn current_epoch(&self) -> Result<u64> {
+        self.meta
+            .get(KEY_EPOCH)?
+            .map_or(Ok(0), |bytes| decode_index(&bytes))
+    }
+
+    /// Indices of entries moved out of the log by a quarantining read
+    pub fn quarantined(&self) -> Result<Vec<u64>> {
+        self.quarantine
+            .iter()
+            .keys()
+            .map(|key| decode_index(&key?))
+            .collect()
+    }
+
+    /// Another tree in the same database (e.g. the audit log)
+    pub(crate) fn open_tree(&self, name: &str) -> Result<Tree> {
+        Ok(self.db.open_tree(name)?)
+    }
+
+    /// Position saved by a named log reader (e.g. an exporter)
+    pub fn load_cursor(&self, name: &str) -> Result<Option<u64>> {
+        let key = format!("{}{}", CURSOR_PREFIX, name);
+        self.meta
+            .get(key.as_bytes())?
+            .map(|bytes| decode_index(&bytes))
+            .transpose()
+    }
+
+    pub fn save_cursor(&self, name: &str, next_index: u64) -> Result<()> {
+        self.ensure_writable()?;
+        let key = format!("{}{}", CURSOR_PREFIX, name);
+        self.meta
+            .insert(key.as_bytes(), next_index.to_be_bytes().as_slice())?;
+        Ok(())
+    }
+
+    /// Every position saved with `save_cursor`, by reader name
+    pub fn cursors(&self) -> Result<BTreeMap<String, u64>> {
+        self.meta
+            .scan_prefix(CURSOR_PREFIX.as_bytes())
+            .map(|item| {
+                let (key, value) = item?;
+                let name = String::from_utf8_lossy(&key[CURSOR_PREFIX.len()..]).into_owned();
+                Ok((name, decode_index(&value)?))
+            })
+            .collect()
+    }
+
+    /// Identity of the layer owning this store, created on first use
+    pub fn instance_id(&self) -> Result<Uuid> {
+        self.ensure_writable()?;
+        let fresh = Uuid::new_v4();
+        let stored = match self.meta.compare_and_swap(
+            KEY_INSTANCE,
+            None as Option<&[u8]>,
+            Some(fresh.as_bytes().as_slice()),
+        )? {
+            Ok(()) => return Ok(fresh),
+            Err(existing) => existing.current.context("instance id missing")?,
+        };
+        Uuid::from_slice(&stored).context("corrupt instance id")
+    }
+
+    /// Count one more start of the layer owning this store, returning the new epoch
+    pub fn advance_epoch(&self) -> Result<u64> {
+        self.ensure_writable()?;
+        let updated = self.meta.update_and_fetch(KEY_EPOCH, |current| {
+            let epoch = current.and_then(|bytes| decode_index(bytes).ok()).unwrap_or(0) + 1;
+            Some(epoch.to_be_bytes().to_vec())
+        })?;
+        updated.map_or(Ok(1), |bytes| decode_index(&bytes))
+    }
+
+    /// Number of waves in the log
+    pub fn len(&self) -> usize {
+        self.log.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.log.is_empty()
+    }
+
+    pub fn last_index(&self) -> Result<Option<u64>> {
         if let Some(bytes) = self.meta.get(KEY_LAST_INDEX)? {
             let mut arr = [0u8; 8];
             arr.copy_from_slice(&bytes);
-            Ok(u64::from_be_bytes(arr) + 1)
+            Ok(Some(u64::from_be_bytes(arr)))
         } else {
-            Ok(0)
+            Ok(None)
         }
     }
 
+    fn next_index(&self) -> Result<u64> {
+        Ok(self.last_index()?.map(|index| index + 1).unwrap_or(0))
+    }
+
     pub fn flush(&self) -> Result<()> {
-        self.db.flush()?;
-        Ok(())
+        flush_db(&self.db, &self.last_flush_ms)
+    }
+
+    /// Flush every `interval` on a thread of its own until the last handle is dropped
+    ///
+    /// Replaces sled's background flush, which `last_flush` could not see.
+    fn spawn_flusher(&self, interval: Duration) {
+        let db = Arc::downgrade(&self.db);
+        let last_flush_ms = Arc::clone(&self.last_flush_ms);
+        let interval = interval.max(Duration::from_millis(1));
+        std::thread::Builder::new()
+            .name("aether-store-flush".into())
+            .spawn(move || loop {
+                std::thread::sleep(interval);
+                let Some(db) = Weak::upgrade(&db) else {
+                    break;
+                };
+                if let Err(err) = flush_db(&db, &last_flush_ms) {
+                    warn!("Background wave store flush failed: {}", err);
+                }
+            })
+            .expect("failed to spawn store flush thread");
+    }
+
+    /// Size on disk and entries per tree
+    ///
+    /// The log, quarantine and index trees are counted as they are written;
+    /// only the small meta tree is walked.
+    pub fn stats(&self) -> Result<StoreStats> {
+        let counted = |count: &AtomicU64| count.load(Ordering::Relaxed) as usize;
+        let last_flush_ms = self.last_flush_ms.load(Ordering::Relaxed);
+        Ok(StoreStats {
+            store: self.dir.display().to_string(),
+            size_on_disk_bytes: self.db.size_on_disk()?,
+            tree_entries: BTreeMap::from([
+                (LOG_TREE, counted(&self.counts.log)),
+                (META_TREE, self.meta.len()),
+                (QUARANTINE_TREE, counted(&self.counts.quarantine)),
+                (WAVE_ID_TREE, counted(&self.counts.ids)),
+                (CHANNEL_INDEX_TREE, counted(&self.counts.channels)),
+                (CORRELATION_INDEX_TREE, counted(&self.counts.correlations)),
+            ]),
+            last_flush: (last_flush_ms > 0)
+                .then(|| DateTime::from_timestamp_millis(last_flush_ms))
+                .flatten(),
+        })
+    }
+
+    /// Directory the store was opened from
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+}
+
+/// sled database without its own background flush; `WaveStore::spawn_flusher` runs it
+fn open_db(path: &Path) -> Result<Db> {
+    Ok(sled::Config::new().path(path).flush_every_ms(None).open()?)
+}
+
+fn flush_db(db: &Db, last_flush_ms: &AtomicI64) -> Result<()> {
+    let started = Instant::now();
+    db.flush()?;
+    metrics::histogram!("aether_store_flush_seconds").record(started.elapsed().as_secs_f64());
+    last_flush_ms.store(Utc::now().timestamp_millis(), Ordering::Relaxed);
+    Ok(())
+}
+
+/// Stream an archive through `visit`, line by line, then check it against
+/// its trailer; returns the header, the trailer and whether waves were sealed
+///
+/// `visit` sees entries before the checksum is known, so callers that write
+/// must have scanned the archive once already.
+fn scan_archive(
+    path: &Path,
+    mut visit: impl FnMut(ArchiveEntry) -> Result<()>,
+) -> Result<(ArchiveHeader, ArchiveTrailer, bool)> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut lines = BufReader::new(file).lines();
+    let mut hasher = Sha256::new();
+
+    let line = lines.next().transpose()?.context("archive is empty")?;
+    hasher.update(line.as_bytes());
+    hasher.update(b"\n");
+    let header: ArchiveHeader =
+        serde_json::from_str(&line).context("invalid archive header")?;
+    if header.format != ARCHIVE_FORMAT {
+        bail!("not a wave archive (format {:?})", header.format);
+    }
+    if header.version > ARCHIVE_VERSION {
+        bail!(
+            "archive version {} is newer than the supported {}",
+            header.version,
+            ARCHIVE_VERSION
+        );
+    }
+
+    let (mut entries, mut audit_events, mut sealed) = (0, 0, false);
+    let trailer = loop {
+        let line = lines
+            .next()
+            .transpose()?
+            .context("archive is truncated: no trailer")?;
+        if let Ok(trailer) = serde_json::from_str::<ArchiveTrailer>(&line) {
+            break trailer;
+        }
+        hasher.update(line.as_bytes());
+        hasher.update(b"\n");
+        let entry: ArchiveEntry = serde_json::from_str(&line)
+            .with_context(|| format!("invalid archive entry {}", entries + audit_events + 1))?;
+        match &entry {
+            ArchiveEntry::Audit { .. } => audit_events += 1,
+            ArchiveEntry::Sealed { .. } => {
+                sealed = true;
+                entries += 1;
+            }
+            ArchiveEntry::Wave { .. } => entries += 1,
+        }
+        visit(entry)?;
+    };
+    // Anything appended after the trailer is outside the checksum
+    for line in lines {
+        if !line?.trim().is_empty() {
+            bail!("archive has data after its trailer");
+        }
+    }
+    let sha256 = to_hex(&hasher.finalize());
+    if trailer.sha256 != sha256
+        || trailer.entries != entries
+        || trailer.audit_events != audit_events
+    {
+        bail!("archive checksum mismatch: it was modified or damaged");
+    }
+    Ok((header, trailer, sealed))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    })
+}
+
+impl WaveStore {
+    /// Log entry for `wave`, sealed when the store has a keyring
+    pub(crate) fn encode(&self, wave: &Wave) -> Result<Vec<u8>> {
+        let entry = encode_entry(wave)?;
+        match &self.keyring {
+            Some(keyring) => keyring.seal(&entry),
+            None => Ok(entry),
+        }
+    }
+
+    pub(crate) fn decode(&self, entry: &[u8]) -> std::result::Result<Wave, String> {
+        if entry.first() != Some(&SEALED_MAGIC) {
+            return decode_entry(entry);
+        }
+        let keyring = self
+            .keyring
+            .as_ref()
+            .ok_or("entry is encrypted and the store has no keyring")?;
+        decode_entry(&keyring.open(entry)?)
+    }
+
+    /// Sealed with a key this store does not hold; not corruption, so never quarantined
+    fn missing_key(&self, entry: &[u8]) -> bool {
+        sealed_key_id(entry).is_some_and(|(id, _)| {
+            !self
+                .keyring
+                .as_ref()
+                .is_some_and(|keyring| keyring.has_key(id))
+        })
+    }
+}
+
+fn encode_entry(wave: &Wave) -> Result<Vec<u8>> {
+    let body = serde_json::to_vec(wave)?;
+    let mut entry = Vec::with_capacity(ENTRY_HEADER_LEN + body.len());
+    entry.push(ENTRY_MAGIC);
+    entry.extend_from_slice(&crc32fast::hash(&body).to_be_bytes());
+    entry.extend_from_slice(&body);
+    Ok(entry)
+}
+
+fn decode_entry(entry: &[u8]) -> std::result::Result<Wave, String> {
+    let body = match entry.first() {
+        Some(&ENTRY_MAGIC) => {
+            if entry.len() < ENTRY_HEADER_LEN {
+                return Err("truncated entry header".to_string());
+            }
+            let mut stored = [0u8; 4];
+            stored.copy_from_slice(&entry[1..ENTRY_HEADER_LEN]);
+            let stored = u32::from_be_bytes(stored);
+            let body = &entry[ENTRY_HEADER_LEN..];
+            let computed = crc32fast::hash(body);
+            if stored != computed {
+                return Err(format!(
+                    "checksum mismatch (stored {:08x}, computed {:08x})",
+                    stored, computed
+                ));
+            }
+            body
+        }
+        _ => entry,
+    };
+    serde_json::from_slice(body).map_err(|e| format!("invalid wave: {}", e))
+}
+
+/// Secondary index key: the indexed value, a NUL separator, then the
+/// big-endian log index so entries sort in log order under each value
+fn index_key(value: &[u8], index: u64) -> Vec<u8> {
+    let mut key = Vec::with_capacity(value.len() + 9);
+    key.extend_from_slice(value);
+    key.push(0);
+    key.extend_from_slice(&index.to_be_bytes());
+    key
+}
+
+fn decode_index(key: &[u8]) -> Result<u64> {
+    let bytes: [u8; 8] = key
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("invalid log key of {} bytes", key.len()))?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("aether-{}-{}", name, uuid::Uuid::new_v4()))
+    }
+
+    /// sled's IO threads can hold the file lock briefly after the last handle drops
+    fn reopen(open: impl Fn() -> Result<WaveStore>) -> WaveStore {
+        for _ in 0..50 {
+            if let Ok(store) = open() {
+                return store;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        open().unwrap()
+    }
+
+    #[test]
+    fn test_epoch_advances_on_every_start() {
+        let path = temp_path("store-epoch");
+        let instance;
+        {
+            let store = WaveStore::open(&path).unwrap();
+            assert_eq!(store.advance_epoch().unwrap(), 1);
+            instance = store.instance_id().unwrap();
+            assert_eq!(store.instance_id().unwrap(), instance);
+        }
+        let store = reopen(|| WaveStore::open(&path));
+        assert_eq!(store.advance_epoch().unwrap(), 2);
+        assert_eq!(store.instance_id().unwrap(), instance);
+        drop(store);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_corrupt_entries_are_skipped_and_quarantined() {
+        let path = temp_path("store-corrupt");
+        let store = WaveStore::open(&path).unwrap();
+        for n in 0..4 {
+            store
+                .append_wave(&Wave::new("orders.created", serde_json::json!({ "n": n })))
+                .unwrap();
+        }
+        // Flip a payload byte in entry 1 and truncate entry 3 mid-write
+        let mut flipped = store.log.get(1u64.to_be_bytes()).unwrap().unwrap().to_vec();
+        let last = flipped.len() - 2;
+        flipped[last] ^= 0x01;
+        store.log.insert(1u64.to_be_bytes(), flipped).unwrap();
+        let torn = store.log.get(3u64.to_be_bytes()).unwrap().unwrap();
+        store.log.insert(3u64.to_be_bytes(), &torn[..3]).unwrap();
+
+        let report = store.verify().unwrap();
+        assert_eq!(report.scanned, 4);
+        let corrupt: Vec<u64> = report.corrupt.iter().map(|entry| entry.index).collect();
+        assert_eq!(corrupt, vec![1, 3]);
+        assert!(report.corrupt[0].reason.contains("checksum mismatch"));
+
+        assert_eq!(store.read_from(0).unwrap().len(), 2);
+        let (waves, report) = store.recover_from(0, RecoveryMode::Quarantine).unwrap();
+        let indices: Vec<u64> = waves.iter().map(|(index, _)| *index).collect();
+        assert_eq!(indices, vec![0, 2]);
+        assert_eq!(report.next_index, 4);
+        assert_eq!(store.quarantined().unwrap(), vec![1, 3]);
+        assert!(store.verify().unwrap().is_clean());
+
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_legacy_entries_without_checksum_still_decode() {
+        let path = temp_path("store-legacy");
+        let store = WaveStore::open(&path).unwrap();
+        let legacy = Wave::new("orders.created", serde_json::json!({"legacy": true}));
+        store
+            .log
+            .insert(0u64.to_be_bytes(), serde_json::to_vec(&legacy).unwrap())
+            .unwrap();
+        store
+            .meta
+            .insert(KEY_LAST_INDEX, 0u64.to_be_bytes().as_slice())
+            .unwrap();
+        store
+            .append_wave(&Wave::new("orders.created", serde_json::json!({})))
+            .unwrap();
+
+        assert!(store.verify().unwrap().is_clean());
+        assert_eq!(store.read_from(0).unwrap()[0], legacy);
+
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_channel_and_correlation_indices_are_maintained_and_rebuilt() {
+        let path = temp_path("store-index");
+        let store = WaveStore::open(&path).unwrap();
+        let order = Wave::new("orders.created", serde_json::json!({"order": 1}));
+        let reserve = Wave::builder("inventory.reserve")
+            .metadata(serde_json::json!({ "correlation_id": order.correlation_id() }))
+            .build();
+        let other = Wave::new("orders.created.eu", serde_json::json!({"order": 2}));
+        for wave in [&order, &reserve, &other] {
+            store.append_wave(wave).unwrap();
+        }
+        store
+            .append_wave(&Wave::new("orders.created", serde_json::json!({"order": 3})))
+            .unwrap();
+
+        let indices =
+            |waves: Vec<(u64, Wave)>| waves.into_iter().map(|(i, _)| i).collect::<Vec<_>>();
+        assert_eq!(
+            indices(store.read_channel("orders.created", 0).unwrap()),
+            vec![0, 3]
+        );
+        assert_eq!(
+            indices(store.read_channel("orders.created", 1).unwrap()),
+            vec![3]
+        );
+        assert!(store.read_channel("orders", 0).unwrap().is_empty());
+        assert_eq!(
+            indices(store.read_correlation(&order.correlation_id()).unwrap()),
+            vec![0, 1]
+        );
+
+        // A store written before the indices existed is reindexed off the opening thread
+        store.channels.clear().unwrap();
+        store.correlations.clear().unwrap();
+        store.meta.remove(KEY_INDEX_VERSION).unwrap();
+        drop(store);
+        let store = reopen(|| WaveStore::open(&path));
+        assert!(store.read_channel("orders.created", 0).unwrap().is_empty());
+        store
+            .reindex_in_background()
+            .unwrap()
+            .unwrap()
+            .join()
+            .unwrap();
+        assert!(store.reindex_in_background().unwrap().is_none());
+        assert_eq!(
+            indices(store.read_channel("orders.created", 0).unwrap()),
+            vec![0, 3]
+        );
+        assert_eq!(store.get_wave(reserve.id()).unwrap().unwrap().0, 1);
+
+        drop(store);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_encrypted_entries_are_sealed_and_survive_key_rotation() {
+        let path = temp_path("store-encrypted");
+        let flush = Duration::from_millis(100);
+        let old = Keyring::new("k1", [7; 32]).unwrap();
+        let store = WaveStore::open_encrypted(&path, flush, old).unwrap();
+        let wave = Wave::new("payments.captured", serde_json::json!({"card": "4111"}));
+        store.append_wave(&wave).unwrap();
+        let raw = store.log.get(0u64.to_be_bytes()).unwrap().unwrap();
+        assert!(!raw.windows(4).any(|window| window == b"4111"));
+        // Index keys are blinded, yet lookups by channel still work
+        for key in store.channels.iter().keys() {
+            assert!(!key.unwrap().windows(8).any(|window| window == b"payments"));
+        }
+        assert_eq!(store.read_channel("payments.captured", 0).unwrap().len(), 1);
+        drop(store);
+
+        // Without the key the entry is reported but never quarantined
+        let plain = reopen(|| WaveStore::open(&path));
+        let (_, report) = plain.recover_from(0, RecoveryMode::Quarantine).unwrap();
+        assert_eq!(report.corrupt.len(), 1);
+        assert!(plain.quarantined().unwrap().is_empty());
+        drop(plain);
+
+        let rotated = Keyring::new("k2", [8; 32])
+            .unwrap()
+            .with_key("k1", [7; 32])
+            .unwrap();
+        let store = reopen(|| WaveStore::open_encrypted(&path, flush, rotated.clone()));
+        assert_eq!(store.get_wave(wave.id()).unwrap().unwrap().1, wave);
+        assert_eq!(store.reencrypt().unwrap(), 1);
+        assert_eq!(store.reencrypt().unwrap(), 0);
+        // Indices blinded under the old key are rebuilt under the new one
+        assert_eq!(store.reindex().unwrap(), 1);
+        drop(store);
+
+        let retired = Keyring::new("k2", [8; 32]).unwrap();
+        let store = reopen(|| WaveStore::open_encrypted(&path, flush, retired.clone()));
+        assert_eq!(store.read_from(0).unwrap(), vec![wave.clone()]);
+        let correlated = store.read_correlation(&wave.correlation_id()).unwrap();
+        assert_eq!(correlated.len(), 1);
+
+        drop(store);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_export_round_trips_through_a_verified_archive() {
+        let (path, restored_path) = (temp_path("store-export"), temp_path("store-import"));
+        let archive = temp_path("archive").with_extension("ndjson");
+        let store = WaveStore::open(&path).unwrap();
+        let waves: Vec<Wave> = (0..3)
+            .map(|n| Wave::new("orders.created", serde_json::json!({ "order": n })))
+            .collect();
+        for wave in &waves {
+            store.append_wave(wave).unwrap();
+        }
+        store.save_cursor("exporter", 2).unwrap();
+        store.advance_epoch().unwrap();
+
+        let exported = store.export(&archive).unwrap();
+        assert_eq!((exported.entries, exported.last_index), (3, Some(2)));
+
+        let restored = WaveStore::open(&restored_path).unwrap();
+        let imported = restored.import(&archive).unwrap();
+        assert_eq!(imported.sha256, exported.sha256);
+        assert_eq!(restored.read_from(0).unwrap(), waves);
+        assert_eq!(restored.get_wave(waves[2].id()).unwrap().unwrap().0, 2);
+        assert_eq!(restored.load_cursor("exporter").unwrap(), Some(2));
+        assert_eq!(restored.advance_epoch().unwrap(), 2);
+        assert!(restored.import(&archive).is_err());
+
+        let tampered = std::fs::read_to_string(&archive)
+            .unwrap()
+            .replace("\"order\":1", "\"order\":9");
+        std::fs::write(&archive, tampered).unwrap();
+        let empty_path = temp_path("store-tampered");
+        let empty = WaveStore::open(&empty_path).unwrap();
+        let err = empty.import(&archive).unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"));
+        assert!(empty.is_empty());
+
+        drop((store, restored, empty));
+        for path in [path, restored_path, empty_path] {
+            let _ = std::fs::remove_dir_all(path);
+        }
+        let _ = std::fs::remove_file(archive);
+    }
+
+    #[test]
+    fn test_encrypted_export_stays_sealed_and_carries_the_audit_log() {
+        let (path, restored_path) = (temp_path("store-sealed"), temp_path("store-unsealed"));
+        let archive = temp_path("archive").with_extension("ndjson");
+        let flush = Duration::from_millis(100);
+        let keyring = Keyring::new("k1", [7; 32]).unwrap();
+        let store = WaveStore::open_encrypted(&path, flush, keyring.clone()).unwrap();
+        let wave = Wave::new("payments.captured", serde_json::json!({"holder": "Jane Roe"}));
+        store.append_wave(&wave).unwrap();
+        let audit = crate::audit::AuditLog::open(&store, None).unwrap();
+        audit
+            .record(crate::audit::AuditKind::ConfigReload, None, serde_json::json!({}))
+            .unwrap();
+
+        let exported = store.export(&archive).unwrap();
+        assert!(exported.sealed);
+        assert_eq!((exported.entries, exported.audit_events), (1, 1));
+        let contents = std::fs::read_to_string(&archive).unwrap();
+        assert!(!contents.contains("Jane Roe"));
+
+        // Sealed entries do not open without the key, and nothing is written
+        let plain = WaveStore::open(&restored_path).unwrap();
+        let err = plain.import(&archive).unwrap_err();
+        assert!(err.to_string().contains("no keyring"), "{}", err);
+        assert!(plain.is_empty());
+        drop(plain);
+
+        let restored = reopen(|| WaveStore::open_encrypted(&restored_path, flush, keyring.clone()));
+        restored.import(&archive).unwrap();
+        assert_eq!(restored.read_from(0).unwrap(), vec![wave.clone()]);
+        let restored_audit = crate::audit::AuditLog::open(&restored, None).unwrap();
+        assert_eq!(restored_audit.len(), 1);
+        assert_eq!(restored_audit.verify().unwrap(), None);
+
+        assert!(!store.export_decrypted(&archive).unwrap().sealed);
+        assert!(std::fs::read_to_string(&archive).unwrap().contains("Jane Roe"));
+
+        drop((store, restored));
+        for path in [path, restored_path] {
+            let _ = std::fs::remove_dir_all(path);
+        }
+        let _ = std::fs::remove_file(archive);
+    }
+
+    #[test]
+    fn test_import_rejects_entries_after_the_trailer() {
+        let (path, restored_path) = (temp_path("store-export"), temp_path("store-import"));
+        let archive = temp_path("archive").with_extension("ndjson");
+        let store = WaveStore::open(&path).unwrap();
+        store
+            .append_wave(&Wave::new("orders.created", serde_json::json!({ "order": 1 })))
+            .unwrap();
+        store.export(&archive).unwrap();
+
+        let mut contents = std::fs::read_to_string(&archive).unwrap();
+        let entry = contents.lines().nth(1).unwrap().to_string();
+        contents.push_str(&entry);
+        contents.push('\n');
+        std::fs::write(&archive, contents).unwrap();
+
+        let restored = WaveStore::open(&restored_path).unwrap();
+        let err = restored.import(&archive).unwrap_err();
+        assert!(err.to_string().contains("after its trailer"), "{}", err);
+        assert!(restored.is_empty());
+
+        drop((store, restored));
+        for path in [path, restored_path] {
+            let _ = std::fs::remove_dir_all(path);
+        }
+        let _ = std::fs::remove_file(archive);
+    }
+
+    #[test]
+    fn test_writer_lease_excludes_second_writer_but_not_readers() {
+        let path = temp_path("store-lease");
+        let store = WaveStore::open(&path).unwrap();
+        let wave = Wave::new("orders.created", serde_json::json!({}));
+        store.append_wave(&wave).unwrap();
+        store.flush().unwrap();
+
+        let err = WaveStore::open(&path).unwrap_err().to_string();
+        assert!(err.contains(&format!("pid {}", std::process::id())), "{}", err);
+
+        store.set_lease_service("orders").unwrap();
+        let holder = WaveStore::lease_holder(&path).unwrap().unwrap();
+        assert_eq!(holder.pid, std::process::id());
+        assert_eq!(holder.service.as_deref(), Some("orders"));
+        let err = WaveStore::open_read_only(&path, None).unwrap_err().to_string();
+        assert!(err.contains("checkpoint"), "{}", err);
+
+        let checkpoint = temp_path("store-lease-checkpoint");
+        store.checkpoint(&checkpoint).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&checkpoint).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+        let reader = WaveStore::open_checkpoint(&checkpoint, None).unwrap();
+        assert!(!reader.is_writable());
+        assert_eq!(reader.get_wave(wave.id()).unwrap().unwrap().1, wave);
+        assert!(reader.append_wave(&wave).is_err());
+        assert!(reader.recover_from(0, RecoveryMode::Quarantine).is_err());
+        drop(reader);
+        assert!(!checkpoint.exists());
+
+        drop(store);
+        assert!(WaveStore::lease_holder(&path).unwrap().is_none());
+        let reader = reopen(|| WaveStore::open_read_only(&path, None));
+        assert_eq!(reader.len(), 1);
+        drop(reader);
+        let store = reopen(|| WaveStore::open(&path));
+        assert!(store.is_writable());
+        assert!(WaveStore::open_read_only(temp_path("store-missing"), None).is_err());
+
+        drop(store);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_stats_count_entries_per_tree_and_track_flushes() {
+        let path = temp_path("store-stats");
+        let store = WaveStore::open_with_flush_interval(&path, Duration::from_secs(3600)).unwrap();
+        assert!(store.stats().unwrap().last_flush.is_none());
+        for order in ["ORD-1", "ORD-2"] {
+            let wave = Wave::new("orders.created", serde_json::json!({ "order": order }));
+            store.append_wave(&wave).unwrap();
+        }
+        store.flush().unwrap();
+
+        let stats = store.stats().unwrap();
+        assert_eq!(stats.tree_entries[LOG_TREE], 2);
+        assert_eq!(stats.tree_entries[CHANNEL_INDEX_TREE], 2);
+        assert_eq!(stats.tree_entries[QUARANTINE_TREE], 0);
+        assert!(stats.size_on_disk_bytes > 0);
+        assert!(stats.last_flush.is_some());
+        assert_eq!(stats.store, path.display().to_string());
+        assert_eq!(store.path(), path.as_path());
+
+        drop(store);
+        let store =
+            reopen(|| WaveStore::open_with_flush_interval(&path, Duration::from_millis(10)));
+        let stats = store.stats().unwrap();
+        assert_eq!(stats.tree_entries[LOG_TREE], 2);
+        assert_eq!(stats.tree_entries[WAVE_ID_TREE], 2);
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(store.stats().unwrap().last_flush.is_some());
+
+        drop(store);
+        let _ = std::fs::remove_dir_all(path);
     }
 }
@@ -1,25 +1,138 @@
 //! Physics - physical simulation engine
 
+use crate::channel::Channel;
+use crate::physics_history::PhysicsHistory;
 use crate::wave::{Amplitude, Wave};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use tracing::warn;
+
+/// Tuning for [`PhysicsEngine::detect_patterns`]
+#[derive(Debug, Clone, Deserialize)]
+pub struct PhysicsConfig {
+    /// Share of the compared waves that must agree before a pattern is reported
+    #[serde(default = "default_interference_threshold")]
+    pub interference_threshold: f64,
+    /// Waves remembered per channel before the oldest are trimmed
+    #[serde(default = "default_window_size")]
+    pub window_size: usize,
+    /// Oldest waves dropped at once when the window is full
+    #[serde(default = "default_window_trim")]
+    pub window_trim: usize,
+    /// Agreeing waves needed for a pattern, however small the window
+    #[serde(default = "default_min_pattern_waves")]
+    pub min_pattern_waves: usize,
+    /// Channel patterns analysed
+    #[serde(default = "default_channels")]
+    pub channels: Vec<String>,
+    /// Channel patterns skipped even when `channels` matches
+    #[serde(default)]
+    pub exclude_channels: Vec<String>,
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        Self {
+            interference_threshold: default_interference_threshold(),
+            window_size: default_window_size(),
+            window_trim: default_window_trim(),
+            min_pattern_waves: default_min_pattern_waves(),
+            channels: default_channels(),
+            exclude_channels: Vec::new(),
+        }
+    }
+}
+
+fn default_interference_threshold() -> f64 {
+    0.5
+}
+
+fn default_window_size() -> usize {
+    100
+}
+
+fn default_window_trim() -> usize {
+    50
+}
+
+fn default_min_pattern_waves() -> usize {
+    6
+}
+
+fn default_channels() -> Vec<String> {
+    vec![">".to_string()]
+}
 
 /// Physics engine - simulates interactions between waves
 pub struct PhysicsEngine {
     /// Wave history (for interference calculations)
     wave_history: HashMap<String, Vec<Wave>>,
 
-    /// Detection threshold for interference patterns
-    interference_threshold: f64,
+    config: PhysicsConfig,
+    channels: Vec<Channel>,
+    exclude_channels: Vec<Channel>,
+
+    /// Where detections and channel summaries are persisted
+    history: Option<PhysicsHistory>,
 }
 
 impl PhysicsEngine {
     pub fn new() -> Self {
+        Self::from_config(PhysicsConfig::default())
+    }
+
+    pub fn from_config(config: PhysicsConfig) -> Self {
+        let patterns = |names: &[String]| names.iter().map(Channel::new).collect();
         Self {
             wave_history: HashMap::new(),
-            interference_threshold: 0.5,
+            channels: patterns(&config.channels),
+            exclude_channels: patterns(&config.exclude_channels),
+            config,
+            history: None,
         }
     }
 
+    pub fn config(&self) -> &PhysicsConfig {
+        &self.config
+    }
+
+    /// Whether waves on `channel` are analysed
+    pub fn tracks(&self, channel: &str) -> bool {
+        let channel = Channel::new(channel);
+        self.channels.iter().any(|pattern| channel.matches(pattern))
+            && !self
+                .exclude_channels
+                .iter()
+                .any(|pattern| channel.matches(pattern))
+    }
+
+    /// Persist detections to `history`, refilling the in-memory window from its recent waves
+    pub fn with_history(mut self, history: PhysicsHistory) -> Self {
+        match history.recent_waves() {
+            Ok(waves) => {
+                for wave in waves {
+                    if !self.tracks(wave.channel().name()) {
+                        continue;
+                    }
+                    let history = self
+                        .wave_history
+                        .entry(wave.channel().name().to_string())
+                        .or_default();
+                    trim_window(history, &self.config);
+                    history.push(wave);
+                }
+            }
+            Err(err) => warn!("Failed to replay physics history: {}", err),
+        }
+        self.history = Some(history);
+        self
+    }
+
+    /// Waves currently remembered for `channel`
+    pub fn window_len(&self, channel: &str) -> usize {
+        self.wave_history.get(channel).map_or(0, Vec::len)
+    }
+
     /// Calculate interference between two waves
     pub fn calculate_interference(wave1: &Wave, wave2: &Wave) -> Interference {
         let amp1 = wave1.amplitude().value();
@@ -44,8 +157,8 @@ impl PhysicsEngine {
     }
 
     /// Determine whether a wave resonates at a specific channel
-    pub fn check_resonance(&self, wave: &Wave, target_frequency: f64) -> Resonance {
-        let wave_frequency = self.estimate_frequency(wave);
+    pub fn check_resonance(wave: &Wave, target_frequency: f64) -> Resonance {
+        let wave_frequency = Self::estimate_frequency(wave);
         let diff = (wave_frequency - target_frequency).abs();
 
         if diff < 0.1 {
@@ -57,8 +170,13 @@ impl PhysicsEngine {
         }
     }
 
+    /// Boost `wave` by the gain of `resonance`, capped at full amplitude
+    pub fn amplify(wave: &mut Wave, resonance: &Resonance) {
+        wave.amplify(resonance.gain());
+    }
+
     /// Estimate wave frequency (from channel name)
-    fn estimate_frequency(&self, wave: &Wave) -> f64 {
+    pub(crate) fn estimate_frequency(wave: &Wave) -> f64 {
         // Simple frequency estimate (use hash of channel name)
         let channel_name = wave.channel().name();
         let hash = channel_name
@@ -68,16 +186,19 @@ impl PhysicsEngine {
     }
 
     /// Detect interference patterns from multiple waves
+    ///
+    /// Waves on channels the config does not track are ignored.
     pub fn detect_patterns(&mut self, channel: &str, wave: Wave) -> Option<InterferencePattern> {
+        if !self.tracks(channel) {
+            return None;
+        }
         let history = self
             .wave_history
             .entry(channel.to_string())
-            .or_insert_with(Vec::new);
+            .or_default();
 
         // Remove old entries if history grows too large
-        if history.len() > 100 {
-            history.drain(0..50);
-        }
+        trim_window(history, &self.config);
 
         // Compare the new wave with historical waves
         let mut constructive_count = 0;
@@ -90,28 +211,35 @@ impl PhysicsEngine {
             }
         }
 
-        history.push(wave);
-
         let total = constructive_count + destructive_count;
         let threshold = if total == 0 {
             0
         } else {
-            (self.interference_threshold * total as f64).ceil() as usize
+            (self.config.interference_threshold * total as f64).ceil() as usize
         };
+        let min_waves = self.config.min_pattern_waves;
 
-        if constructive_count > destructive_count
+        let pattern = if constructive_count > destructive_count
             && constructive_count >= threshold
-            && constructive_count > 5
+            && constructive_count >= min_waves
         {
             Some(InterferencePattern::StandingWave)
         } else if destructive_count > constructive_count
             && destructive_count >= threshold
-            && destructive_count > 5
+            && destructive_count >= min_waves
         {
             Some(InterferencePattern::Cancellation)
         } else {
             None
+        };
+
+        if let Some(persisted) = &self.history {
+            if let Err(err) = persisted.record(channel, &wave, pattern.as_ref()) {
+                warn!("Failed to persist physics history for {}: {}", channel, err);
+            }
         }
+        history.push(wave);
+        pattern
     }
 
     /// Simulate wave diffraction (avoid obstacles)
@@ -128,6 +256,13 @@ impl Default for PhysicsEngine {
     }
 }
 
+fn trim_window(history: &mut Vec<Wave>, config: &PhysicsConfig) {
+    if history.len() > config.window_size {
+        let trim = config.window_trim.clamp(1, history.len());
+        history.drain(0..trim);
+    }
+}
+
 /// Interference types
 #[derive(Debug, Clone)]
 pub enum Interference {
@@ -138,7 +273,8 @@ pub enum Interference {
 }
 
 /// Resonance strength
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Resonance {
     /// Strong resonance
     Strong,
@@ -148,8 +284,20 @@ pub enum Resonance {
     Weak,
 }
 
+impl Resonance {
+    /// Amplitude multiplier for waves received at this resonance
+    pub fn gain(&self) -> f64 {
+        match self {
+            Resonance::Strong => 1.5,
+            Resonance::Moderate => 1.2,
+            Resonance::Weak => 1.0,
+        }
+    }
+}
+
 /// Interference patterns
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum InterferencePattern {
     /// Standing wave (same pattern repeats)
     StandingWave,
@@ -186,15 +334,53 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_config_tunes_detection_and_skips_excluded_channels() {
+        let mut engine = PhysicsEngine::from_config(PhysicsConfig {
+            window_size: 4,
+            window_trim: 2,
+            min_pattern_waves: 3,
+            exclude_channels: vec!["metrics.>".to_string()],
+            ..PhysicsConfig::default()
+        });
+        let wave = |channel: &str| Wave::builder(Channel::new(channel)).build();
+
+        let detected = (0..4)
+            .map(|_| engine.detect_patterns("orders.created", wave("orders.created")))
+            .collect::<Vec<_>>();
+        assert!(detected[..3].iter().all(Option::is_none));
+        assert_eq!(detected[3], Some(InterferencePattern::StandingWave));
+        for _ in 0..2 {
+            engine.detect_patterns("orders.created", wave("orders.created"));
+        }
+        assert_eq!(engine.window_len("orders.created"), 4);
+
+        for _ in 0..10 {
+            assert_eq!(
+                engine.detect_patterns("metrics.cpu", wave("metrics.cpu")),
+                None
+            );
+        }
+        assert_eq!(engine.window_len("metrics.cpu"), 0);
+        assert!(!engine.tracks("metrics.cpu"));
+    }
+
     #[test]
     fn test_resonance_check() {
-        let engine = PhysicsEngine::new();
         let wave = Wave::builder(Channel::new("test.resonance")).build();
+        let frequency = PhysicsEngine::estimate_frequency(&wave);
 
-        let resonance = engine.check_resonance(&wave, 0.5);
-        assert!(matches!(
-            resonance,
-            Resonance::Strong | Resonance::Moderate | Resonance::Weak
-        ));
+        assert_eq!(
+            PhysicsEngine::check_resonance(&wave, frequency),
+            Resonance::Strong
+        );
+        assert_eq!(
+            PhysicsEngine::check_resonance(&wave, frequency + 0.2),
+            Resonance::Moderate
+        );
+        assert_eq!(
+            PhysicsEngine::check_resonance(&wave, frequency + 0.5),
+            Resonance::Weak
+        );
     }
 }
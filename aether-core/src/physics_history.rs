@@ -0,0 +1,570 @@
+//! Physics history: interference patterns and channel summaries kept in the WaveStore.
+//!
+//! A [`PhysicsEngine`] only compares a wave against a window of recent waves
+//! on its channel, in memory. With a [`PhysicsHistory`] attached, every detected
+//! pattern is appended to the `physics_patterns` tree and each channel's running
+//! summary lives in `physics_summaries`, so a standing wave that builds up over
+//! days survives restarts. The waves the engine saw last are kept in
+//! `physics_recent` and refill its in-memory window on startup; under NATS
+//! they include other instances' waves, which this instance's log does not.
+//! Recording only updates memory; the trees are written in batches by
+//! [`PhysicsHistory::flush`].
+//!
+//! Services built with `AetherAppBuilder::physics_history` (the gateway) feed
+//! it from `>` and serve it as `physics_history` on the control plane and
+//! `GET /physics` on the health server. A channel that starts standing emits a
+//! `standing_wave` alert on [`STANDING_WAVE_ALERT_CHANNEL`] for the notifier.
+
+use crate::{
+    aether::Aether,
+    channel::Channel,
+    persistence::WaveStore,
+    physics::{InterferencePattern, PhysicsConfig, PhysicsEngine},
+    wave::Wave,
+};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use sled::{Batch, Tree};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+const PATTERNS_TREE: &str = "physics_patterns";
+const SUMMARIES_TREE: &str = "physics_summaries";
+const RECENT_TREE: &str = "physics_recent";
+const INTERNAL_PREFIX: &str = "aether.";
+
+/// Alert channel for channels that start standing
+pub const STANDING_WAVE_ALERT_CHANNEL: &str = "aether.alerts.physics";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PhysicsHistoryConfig {
+    /// Detections kept; the oldest are dropped first
+    #[serde(default = "default_max_detections")]
+    pub max_detections: u64,
+    /// Recent waves kept to refill the engine's window on startup
+    #[serde(default = "default_replay_waves")]
+    pub replay_waves: usize,
+    /// How often recorded summaries, detections and waves are written out
+    #[serde(default = "default_flush_interval_ms")]
+    pub flush_interval_ms: u64,
+}
+
+impl Default for PhysicsHistoryConfig {
+    fn default() -> Self {
+        Self {
+            max_detections: default_max_detections(),
+            replay_waves: default_replay_waves(),
+            flush_interval_ms: default_flush_interval_ms(),
+        }
+    }
+}
+
+fn default_max_detections() -> u64 {
+    100_000
+}
+
+fn default_replay_waves() -> usize {
+    1_000
+}
+
+fn default_flush_interval_ms() -> u64 {
+    1_000
+}
+
+/// One interference pattern the engine picked up
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatternDetection {
+    pub index: u64,
+    pub channel: String,
+    pub pattern: InterferencePattern,
+    pub at: DateTime<Utc>,
+    /// Amplitude of the wave that completed the pattern
+    pub amplitude: f64,
+}
+
+/// Everything the engine has seen on one channel
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelSummary {
+    pub channel: String,
+    pub waves: u64,
+    pub standing_waves: u64,
+    pub cancellations: u64,
+    pub mean_amplitude: f64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+impl ChannelSummary {
+    fn new(channel: &str, at: DateTime<Utc>) -> Self {
+        Self {
+            channel: channel.to_string(),
+            waves: 0,
+            standing_waves: 0,
+            cancellations: 0,
+            mean_amplitude: 0.0,
+            first_seen: at,
+            last_seen: at,
+        }
+    }
+
+    fn observe(&mut self, wave: &Wave, pattern: Option<&InterferencePattern>) {
+        self.waves += 1;
+        self.mean_amplitude += (wave.amplitude().value() - self.mean_amplitude) / self.waves as f64;
+        self.last_seen = self.last_seen.max(*wave.timestamp());
+        match pattern {
+            Some(InterferencePattern::StandingWave) => self.standing_waves += 1,
+            Some(InterferencePattern::Cancellation) => self.cancellations += 1,
+            Some(InterferencePattern::Complex) | None => {}
+        }
+    }
+}
+
+/// Filter for `PhysicsHistory::detections`; unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct PatternQuery {
+    pub channel: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    /// Keep only the newest `limit` matches
+    pub limit: Option<usize>,
+}
+
+/// Persisted detections and summaries, shared by every clone
+#[derive(Clone)]
+pub struct PhysicsHistory {
+    store: WaveStore,
+    patterns: Tree,
+    summaries: Tree,
+    recent: Tree,
+    next_index: Arc<AtomicU64>,
+    pending: Arc<Mutex<Pending>>,
+    config: PhysicsHistoryConfig,
+}
+
+/// What `record` changed since the last `flush`
+#[derive(Default)]
+struct Pending {
+    /// Every channel's summary; the tree only holds flushed copies
+    summaries: HashMap<String, ChannelSummary>,
+    dirty: HashSet<String>,
+    detections: Vec<PatternDetection>,
+    /// Waves seen since the last flush, for the next start's window
+    recent: VecDeque<Wave>,
+    next_recent: u64,
+}
+
+impl PhysicsHistory {
+    pub fn open(store: &WaveStore, config: PhysicsHistoryConfig) -> Result<Self> {
+        let patterns = store.open_tree(PATTERNS_TREE)?;
+        let summaries = store.open_tree(SUMMARIES_TREE)?;
+        let recent = store.open_tree(RECENT_TREE)?;
+        let next_index = match patterns.last()? {
+            Some((key, _)) => index_of(&key) + 1,
+            None => 0,
+        };
+        let mut pending = Pending {
+            next_recent: match recent.last()? {
+                Some((key, _)) => index_of(&key) + 1,
+                None => 0,
+            },
+            ..Pending::default()
+        };
+        for entry in summaries.iter() {
+            let summary: ChannelSummary = serde_json::from_slice(&entry?.1)?;
+            pending.summaries.insert(summary.channel.clone(), summary);
+        }
+        let history = Self {
+            store: store.clone(),
+            patterns,
+            summaries,
+            recent,
+            next_index: Arc::new(AtomicU64::new(next_index)),
+            pending: Arc::new(Mutex::new(pending)),
+            config,
+        };
+        // A smaller cap than last run's applies at once
+        while let Some((key, _)) = history.patterns.first()? {
+            if index_of(&key) + history.config.max_detections >= next_index {
+                break;
+            }
+            history.patterns.remove(key)?;
+        }
+        Ok(history)
+    }
+
+    /// Fold `wave` into its channel's summary and keep `pattern` if one was detected
+    ///
+    /// Only touches memory; nothing reaches the store until [`Self::flush`].
+    pub fn record(
+        &self,
+        channel: &str,
+        wave: &Wave,
+        pattern: Option<&InterferencePattern>,
+    ) -> Result<()> {
+        let mut pending = self.pending.lock().expect("physics history lock poisoned");
+        pending
+            .summaries
+            .entry(channel.to_string())
+            .or_insert_with(|| ChannelSummary::new(channel, *wave.timestamp()))
+            .observe(wave, pattern);
+        pending.dirty.insert(channel.to_string());
+        if pending.recent.len() >= self.config.replay_waves {
+            pending.recent.pop_front();
+        }
+        if self.config.replay_waves > 0 {
+            pending.recent.push_back(wave.clone());
+        }
+
+        if let Some(pattern) = pattern {
+            let index = self.next_index.fetch_add(1, Ordering::SeqCst);
+            pending.detections.push(PatternDetection {
+                index,
+                channel: channel.to_string(),
+                pattern: *pattern,
+                at: *wave.timestamp(),
+                amplitude: wave.amplitude().value(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Write what `record` kept in memory to the store, in one batch per tree
+    ///
+    /// Blocking; [`Self::spawn`] runs it every `flush_interval_ms` off the
+    /// async workers.
+    pub fn flush(&self) -> Result<()> {
+        let (summaries, detections, recent, first_recent) = {
+            let mut pending = self.pending.lock().expect("physics history lock poisoned");
+            let dirty = std::mem::take(&mut pending.dirty);
+            let summaries: Vec<ChannelSummary> = dirty
+                .iter()
+                .filter_map(|channel| pending.summaries.get(channel).cloned())
+                .collect();
+            let recent = std::mem::take(&mut pending.recent);
+            let first_recent = pending.next_recent;
+            pending.next_recent += recent.len() as u64;
+            // Left in place until written, so `detections` never misses them
+            (summaries, pending.detections.clone(), recent, first_recent)
+        };
+
+        let mut batch = Batch::default();
+        for summary in &summaries {
+            batch.insert(summary.channel.as_str(), serde_json::to_vec(summary)?);
+        }
+        self.summaries.apply_batch(batch)?;
+
+        let mut batch = Batch::default();
+        for detection in &detections {
+            batch.insert(
+                &detection.index.to_be_bytes(),
+                serde_json::to_vec(detection)?,
+            );
+            if let Some(expired) = detection.index.checked_sub(self.config.max_detections) {
+                batch.remove(&expired.to_be_bytes());
+            }
+        }
+        self.patterns.apply_batch(batch)?;
+        self.pending
+            .lock()
+            .expect("physics history lock poisoned")
+            .detections
+            .drain(..detections.len());
+
+        let mut batch = Batch::default();
+        for (offset, wave) in recent.iter().enumerate() {
+            let index = first_recent + offset as u64;
+            batch.insert(&index.to_be_bytes(), self.store.encode(wave)?);
+        }
+        let next_recent = first_recent + recent.len() as u64;
+        if let Some(expired) = next_recent.checked_sub(self.config.replay_waves as u64) {
+            for entry in self.recent.range(..expired.to_be_bytes()) {
+                batch.remove(entry?.0);
+            }
+        }
+        self.recent.apply_batch(batch)?;
+        Ok(())
+    }
+
+    /// Stored and pending detections matching `query`, oldest first
+    pub fn detections(&self, query: &PatternQuery) -> Result<Vec<PatternDetection>> {
+        let pending = self
+            .pending
+            .lock()
+            .expect("physics history lock poisoned")
+            .detections
+            .clone();
+        let oldest = self
+            .next_index
+            .load(Ordering::SeqCst)
+            .saturating_sub(self.config.max_detections);
+        let mut stored = Vec::new();
+        for entry in self.patterns.range(oldest.to_be_bytes()..) {
+            stored.push(serde_json::from_slice::<PatternDetection>(&entry?.1)?);
+        }
+        let mut matches = Vec::new();
+        for detection in stored.into_iter().chain(pending) {
+            if detection.index < oldest
+                || query
+                    .channel
+                    .as_ref()
+                    .is_some_and(|channel| &detection.channel != channel)
+                || query.since.is_some_and(|since| detection.at < since)
+            {
+                continue;
+            }
+            matches.push(detection);
+        }
+        // Written but not yet dropped from pending by a concurrent flush
+        matches.sort_by_key(|detection| detection.index);
+        matches.dedup_by_key(|detection| detection.index);
+        if let Some(limit) = query.limit {
+            let skip = matches.len().saturating_sub(limit);
+            matches.drain(..skip);
+        }
+        Ok(matches)
+    }
+
+    /// Summaries of every channel seen, ordered by channel name
+    pub fn summaries(&self) -> Result<Vec<ChannelSummary>> {
+        let pending = self.pending.lock().expect("physics history lock poisoned");
+        let mut summaries: Vec<ChannelSummary> = pending.summaries.values().cloned().collect();
+        summaries.sort_by(|a, b| a.channel.cmp(&b.channel));
+        Ok(summaries)
+    }
+
+    pub fn summary(&self, channel: &str) -> Result<Option<ChannelSummary>> {
+        let pending = self.pending.lock().expect("physics history lock poisoned");
+        Ok(pending.summaries.get(channel).cloned())
+    }
+
+    /// The last `replay_waves` waves the history saw, wherever they were
+    /// emitted, that the engine's window is refilled from
+    pub(crate) fn recent_waves(&self) -> Result<Vec<Wave>> {
+        let mut waves = Vec::new();
+        for entry in self.recent.iter() {
+            let (_, value) = entry?;
+            waves.push(self.store.decode(&value).map_err(anyhow::Error::msg)?);
+        }
+        let skip = waves.len().saturating_sub(self.config.replay_waves);
+        waves.drain(..skip);
+        Ok(waves)
+    }
+
+    /// Feed every non-internal wave on the layer through an engine built from
+    /// `config` until the task is aborted
+    ///
+    /// Alerts once each time a channel starts standing, not for every wave
+    /// while it stays that way; `service` is their source. What the engine
+    /// recorded is flushed every `flush_interval_ms` on the blocking pool.
+    pub async fn spawn(
+        &self,
+        aether: &Aether,
+        config: PhysicsConfig,
+        service: &str,
+    ) -> JoinHandle<()> {
+        let stream = aether.tap(Channel::new(">")).await;
+        let mut engine = PhysicsEngine::from_config(config).with_history(self.clone());
+        let history = self.clone();
+        let aether = aether.clone();
+        let service = service.to_string();
+        let mut flushes =
+            tokio::time::interval(Duration::from_millis(self.config.flush_interval_ms.max(1)));
+        flushes.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        tokio::spawn(async move {
+            futures::pin_mut!(stream);
+            let mut standing = HashSet::new();
+            loop {
+                let wave = tokio::select! {
+                    wave = stream.next() => match wave {
+                        Some(wave) => wave,
+                        None => break,
+                    },
+                    _ = flushes.tick() => {
+                        let history = history.clone();
+                        match tokio::task::spawn_blocking(move || history.flush()).await {
+                            Ok(Ok(())) => {}
+                            Ok(Err(err)) => warn!("Failed to flush physics history: {}", err),
+                            Err(err) => warn!("Physics history flush panicked: {}", err),
+                        }
+                        continue;
+                    }
+                };
+                let channel = wave.channel().name().to_string();
+                if channel.starts_with(INTERNAL_PREFIX) {
+                    continue;
+                }
+                let amplitude = wave.amplitude().value();
+                match engine.detect_patterns(&channel, wave) {
+                    Some(InterferencePattern::StandingWave) => {
+                        if standing.insert(channel.clone()) {
+                            alert_standing_wave(&aether, &service, &channel, amplitude).await;
+                        }
+                    }
+                    _ => {
+                        standing.remove(&channel);
+                    }
+                }
+            }
+            if let Err(err) = tokio::task::spawn_blocking(move || history.flush())
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|flushed| flushed)
+            {
+                warn!("Failed to flush physics history: {}", err);
+            }
+        })
+    }
+}
+
+async fn alert_standing_wave(aether: &Aether, service: &str, channel: &str, amplitude: f64) {
+    let mut alert = Wave::builder(STANDING_WAVE_ALERT_CHANNEL)
+        .payload(serde_json::json!({
+            "kind": "standing_wave",
+            "channel": channel,
+            "amplitude": amplitude,
+        }))
+        .source(service)
+        .build();
+    if let Some(token) = &aether.config().auth_token {
+        alert.set_auth_token(token.clone());
+    }
+    if let Err(err) = aether.emit(alert).await {
+        warn!(
+            "Failed to emit standing wave alert for {}: {}",
+            channel, err
+        );
+    }
+}
+
+fn index_of(key: &[u8]) -> u64 {
+    key.try_into().map(u64::from_be_bytes).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wave::WaveType;
+
+    #[test]
+    fn test_detections_and_summaries_survive_reopen() {
+        let path = std::env::temp_dir().join(format!("aether-physics-{}", uuid::Uuid::new_v4()));
+        let store = WaveStore::open(&path).unwrap();
+        let wave = |channel: &str| {
+            Wave::builder(channel)
+                .wave_type(WaveType::Event)
+                .amplitude(0.8)
+                .build()
+        };
+        // Seen, not emitted here: the window refills without the wave log
+        let history = PhysicsHistory::open(&store, PhysicsHistoryConfig::default()).unwrap();
+        for _ in 0..3 {
+            history
+                .record("orders.created", &wave("orders.created"), None)
+                .unwrap();
+        }
+        history.flush().unwrap();
+        drop(history);
+        assert_eq!(store.last_index().unwrap(), None);
+
+        let history = PhysicsHistory::open(&store, PhysicsHistoryConfig::default()).unwrap();
+        let mut engine = PhysicsEngine::new().with_history(history.clone());
+        assert_eq!(engine.window_len("orders.created"), 3);
+        let mut detected = 0;
+        for _ in 0..8 {
+            if engine
+                .detect_patterns("orders.created", wave("orders.created"))
+                .is_some()
+            {
+                detected += 1;
+            }
+        }
+        engine.detect_patterns("payments.settled", wave("payments.settled"));
+        assert!(detected > 0);
+        assert_eq!(
+            history.summary("orders.created").unwrap().unwrap().waves,
+            11
+        );
+        history.flush().unwrap();
+        drop(engine);
+        drop(history);
+
+        let config = PhysicsHistoryConfig {
+            max_detections: 2,
+            ..PhysicsHistoryConfig::default()
+        };
+        let reopened = PhysicsHistory::open(&store, config).unwrap();
+        let summary = reopened.summary("orders.created").unwrap().unwrap();
+        assert_eq!(summary.waves, 11);
+        assert_eq!(summary.standing_waves, detected);
+        assert!((summary.mean_amplitude - 0.8).abs() < 1e-9);
+        let channels = reopened
+            .summaries()
+            .unwrap()
+            .into_iter()
+            .map(|summary| summary.channel)
+            .collect::<Vec<_>>();
+        assert_eq!(channels, ["orders.created", "payments.settled"]);
+
+        let detections = reopened.detections(&PatternQuery::default()).unwrap();
+        assert_eq!(detections.len(), 2);
+        assert!(detections
+            .iter()
+            .all(|detection| detection.pattern == InterferencePattern::StandingWave));
+        assert_eq!(detections[1].index, detected - 1);
+        let filtered = reopened
+            .detections(&PatternQuery {
+                channel: Some("payments.settled".into()),
+                ..PatternQuery::default()
+            })
+            .unwrap();
+        assert!(filtered.is_empty());
+
+        drop(reopened);
+        drop(store);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[tokio::test]
+    async fn test_channel_starting_to_stand_alerts_once() {
+        let path = std::env::temp_dir().join(format!("aether-physics-{}", uuid::Uuid::new_v4()));
+        let aether = Aether::new(crate::AetherConfig {
+            use_nats: false,
+            persistence_enabled: true,
+            persistence_path: path.to_string_lossy().into_owned(),
+            ..crate::AetherConfig::default()
+        });
+        let history = PhysicsHistory::open(
+            aether.wave_store().unwrap(),
+            PhysicsHistoryConfig::default(),
+        )
+        .unwrap();
+        let mut alerts = aether
+            .subscribe(&Channel::new(STANDING_WAVE_ALERT_CHANNEL))
+            .await;
+        let task = history
+            .spawn(&aether, PhysicsConfig::default(), "gateway")
+            .await;
+
+        for _ in 0..12 {
+            let wave = Wave::builder("orders.created").amplitude(0.8).build();
+            aether.emit(wave).await.unwrap();
+        }
+        let alert = tokio::time::timeout(std::time::Duration::from_secs(1), alerts.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(alert.payload()["kind"], "standing_wave");
+        assert_eq!(alert.payload()["channel"], "orders.created");
+        assert_eq!(alert.source(), Some("gateway"));
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(alerts.try_recv().is_err());
+
+        task.abort();
+        let _ = std::fs::remove_dir_all(path);
+    }
+}
@@ -0,0 +1,423 @@
+//! Projections: fold wave streams into queryable state checkpointed in sled.
+
+use crate::{
+    aether::Aether,
+    channel::Channel,
+    encryption::{Keyring, SEALED_MAGIC},
+    persistence::{RecoveryMode, WaveStore},
+    wave::Wave,
+};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+const PROJECTION_TREE_PREFIX: &str = "projection:";
+const KEY_CHECKPOINT: &[u8] = b"checkpoint";
+const PROJECTION_CURSOR_PREFIX: &str = "projection.";
+
+/// Log entries read per batch when rebuilding past state
+const REPLAY_BATCH: usize = 1_000;
+
+/// Derives state from the waves on a channel pattern
+pub trait Projection: Send + Sync + 'static {
+    type State: Default + Serialize + DeserializeOwned + Send + Sync + 'static;
+    type Delta;
+
+    /// Stable name; the checkpoint is stored under it
+    fn name(&self) -> &str;
+
+    /// Channels this projection reads
+    fn pattern(&self) -> Channel;
+
+    /// Change a wave makes to the state, if any
+    fn apply(&self, wave: &Wave) -> Option<Self::Delta>;
+
+    /// Fold a change into the state
+    fn fold(&self, state: &mut Self::State, delta: Self::Delta);
+
+    /// Rebuild the state as it was at `at` from the wave log
+    ///
+    /// Every logged wave on the pattern stamped at or before `at` is folded
+    /// in, so a wave whose producer clock ran behind still counts. Reads the
+    /// whole log; meant for debugging, not for serving queries.
+    fn state_at(&self, store: &WaveStore, at: DateTime<Utc>) -> Result<Self::State> {
+        let pattern = self.pattern();
+        let mut state = Self::State::default();
+        let mut next_index = 0;
+        loop {
+            let (waves, report) = store.read_batch(next_index, REPLAY_BATCH)?;
+            for (_, wave) in &waves {
+                if *wave.timestamp() > at || !wave.channel().matches(&pattern) {
+                    continue;
+                }
+                if let Some(delta) = self.apply(wave) {
+                    self.fold(&mut state, delta);
+                }
+            }
+            if report.scanned < REPLAY_BATCH {
+                return Ok(state);
+            }
+            next_index = report.next_index;
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Checkpoint<S> {
+    /// First WaveStore index not yet folded in
+    next_index: u64,
+    state: S,
+}
+
+/// Read-only handle to a projection's current state
+pub struct ProjectionView<S> {
+    state: Arc<RwLock<S>>,
+}
+
+impl<S> Clone for ProjectionView<S> {
+    fn clone(&self) -> Self {
+        Self {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl<S> ProjectionView<S> {
+    /// Run a query against the current state
+    pub fn read<R>(&self, query: impl FnOnce(&S) -> R) -> R {
+        query(&self.state.read().expect("projection state poisoned"))
+    }
+}
+
+impl<S: Clone> ProjectionView<S> {
+    pub fn snapshot(&self) -> S {
+        self.read(S::clone)
+    }
+}
+
+/// Keeps a projection up to date and checkpoints it
+///
+/// With a WaveStore the runner tails the log by index, so a restart resumes
+/// exactly after the last checkpoint. Without one it follows live waves and
+/// the checkpoint only restores the state.
+pub struct ProjectionRunner<P: Projection> {
+    projection: P,
+    tree: sled::Tree,
+    /// Seals checkpoints at rest when set
+    keyring: Option<Keyring>,
+    state: Arc<RwLock<P::State>>,
+    next_index: u64,
+    uncheckpointed: u64,
+    checkpoint_every: u64,
+    poll_interval: Duration,
+}
+
+impl<P: Projection> ProjectionRunner<P> {
+    /// Open the checkpoint database at `path` and restore the last checkpoint
+    pub fn open(projection: P, path: impl AsRef<Path>) -> Result<Self> {
+        Self::open_with(projection, path, None)
+    }
+
+    /// Like `open`, sealing checkpoints with `keyring`
+    ///
+    /// Use the wave store's keyring: the state is derived from payloads. A
+    /// checkpoint written without encryption still restores and is sealed
+    /// at the next checkpoint.
+    pub fn open_encrypted(projection: P, path: impl AsRef<Path>, keyring: Keyring) -> Result<Self> {
+        Self::open_with(projection, path, Some(keyring))
+    }
+
+    fn open_with(projection: P, path: impl AsRef<Path>, keyring: Option<Keyring>) -> Result<Self> {
+        let db = sled::open(path)?;
+        let tree = db.open_tree(format!("{}{}", PROJECTION_TREE_PREFIX, projection.name()))?;
+        let (next_index, state) = match tree.get(KEY_CHECKPOINT)? {
+            Some(bytes) => {
+                let bytes = match (bytes.first(), &keyring) {
+                    (Some(&SEALED_MAGIC), Some(keyring)) => {
+                        keyring.open(&bytes).map_err(|err| anyhow!(err))?
+                    }
+                    (Some(&SEALED_MAGIC), None) => {
+                        return Err(anyhow!("checkpoint is encrypted and no keyring was given"));
+                    }
+                    _ => bytes.to_vec(),
+                };
+                let checkpoint: Checkpoint<P::State> = serde_json::from_slice(&bytes)?;
+                info!(
+                    "Restored projection {} at index {}",
+                    projection.name(),
+                    checkpoint.next_index
+                );
+                (checkpoint.next_index, checkpoint.state)
+            }
+            None => (0, P::State::default()),
+        };
+
+        Ok(Self {
+            projection,
+            tree,
+            keyring,
+            state: Arc::new(RwLock::new(state)),
+            next_index,
+            uncheckpointed: 0,
+            checkpoint_every: 100,
+            poll_interval: Duration::from_millis(100),
+        })
+    }
+
+    /// Checkpoint after this many folded waves (default 100)
+    pub fn with_checkpoint_every(mut self, waves: u64) -> Self {
+        self.checkpoint_every = waves.max(1);
+        self
+    }
+
+    /// How often to look for new waves in the WaveStore (default 100ms)
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    pub fn view(&self) -> ProjectionView<P::State> {
+        ProjectionView {
+            state: Arc::clone(&self.state),
+        }
+    }
+
+    /// First WaveStore index not yet folded in
+    pub fn next_index(&self) -> u64 {
+        self.next_index
+    }
+
+    /// Fold one wave; returns whether it changed the state
+    pub fn apply(&mut self, wave: &Wave) -> bool {
+        if !wave.channel().matches(&self.projection.pattern()) {
+            return false;
+        }
+        let Some(delta) = self.projection.apply(wave) else {
+            return false;
+        };
+        let mut state = self.state.write().expect("projection state poisoned");
+        self.projection.fold(&mut state, delta);
+        self.uncheckpointed += 1;
+        true
+    }
+
+    /// Fold every logged wave after the checkpoint; returns how many were read
+    ///
+    /// Corrupt entries are skipped so one bad record cannot stall the projection.
+    /// Checkpoints are also recorded as the store cursor `projection.<name>`,
+    /// so layer snapshots know how far back the projection still reads.
+    pub fn catch_up(&mut self, store: &WaveStore) -> Result<usize> {
+        let (waves, report) = store.recover_from(self.next_index, RecoveryMode::Skip)?;
+        for (_, wave) in &waves {
+            self.apply(wave);
+        }
+        self.next_index = report.next_index;
+        if self.uncheckpointed >= self.checkpoint_every {
+            self.checkpoint()?;
+            store.save_cursor(
+                &format!("{}{}", PROJECTION_CURSOR_PREFIX, self.projection.name()),
+                self.next_index,
+            )?;
+        }
+        Ok(waves.len())
+    }
+
+    /// Persist the state and log position now
+    pub fn checkpoint(&mut self) -> Result<()> {
+        let bytes = self.read_checkpoint()?;
+        self.tree.insert(KEY_CHECKPOINT, bytes)?;
+        self.tree.flush()?;
+        self.uncheckpointed = 0;
+        debug!(
+            "Checkpointed projection {} at index {}",
+            self.projection.name(),
+            self.next_index
+        );
+        Ok(())
+    }
+
+    fn read_checkpoint(&self) -> Result<Vec<u8>> {
+        let state = self.state.read().expect("projection state poisoned");
+        let bytes = serde_json::to_vec(&Checkpoint {
+            next_index: self.next_index,
+            state: &*state,
+        })?;
+        match &self.keyring {
+            Some(keyring) => keyring.seal(&bytes),
+            None => Ok(bytes),
+        }
+    }
+
+    /// Keep the projection current in the background
+    pub async fn spawn(mut self, aether: &Aether) -> JoinHandle<Result<()>> {
+        match aether.wave_store().cloned() {
+            Some(store) => tokio::spawn(async move {
+                loop {
+                    if let Err(err) = self.catch_up(&store) {
+                        warn!(
+                            "Projection {} failed to catch up: {}",
+                            self.projection.name(),
+                            err
+                        );
+                    }
+                    tokio::time::sleep(self.poll_interval).await;
+                }
+            }),
+            None => {
+                let stream = aether.tap(self.projection.pattern()).await;
+                tokio::spawn(async move {
+                    futures::pin_mut!(stream);
+                    while let Some(wave) = stream.next().await {
+                        if self.apply(&wave) && self.uncheckpointed >= self.checkpoint_every {
+                            self.checkpoint()?;
+                        }
+                    }
+                    self.checkpoint()
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    struct Stock;
+
+    impl Projection for Stock {
+        type State = BTreeMap<String, i64>;
+        type Delta = (String, i64);
+
+        fn name(&self) -> &str {
+            "stock"
+        }
+
+        fn pattern(&self) -> Channel {
+            Channel::new("inventory.*")
+        }
+
+        fn apply(&self, wave: &Wave) -> Option<Self::Delta> {
+            let item = wave.payload()["item"].as_str()?.to_string();
+            let quantity = wave.payload()["quantity"].as_i64()?;
+            match wave.channel().name() {
+                "inventory.restocked" => Some((item, quantity)),
+                "inventory.reserved" => Some((item, -quantity)),
+                _ => None,
+            }
+        }
+
+        fn fold(&self, state: &mut Self::State, (item, change): Self::Delta) {
+            *state.entry(item).or_default() += change;
+        }
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("aether-{}-{}", name, uuid::Uuid::new_v4()))
+    }
+
+    fn stock_wave(channel: &str, quantity: i64) -> Wave {
+        Wave::new(
+            channel,
+            serde_json::json!({"item": "ItemA", "quantity": quantity}),
+        )
+    }
+
+    fn stock_wave_at(channel: &str, quantity: i64, at: &str) -> Wave {
+        Wave::builder(channel)
+            .payload(serde_json::json!({"item": "ItemA", "quantity": quantity}))
+            .timestamp(at.parse().unwrap())
+            .build()
+    }
+
+    #[test]
+    fn test_state_at_replays_waves_up_to_the_timestamp() {
+        let log_path = temp_path("projection-state-at");
+        let store = WaveStore::open(&log_path).unwrap();
+        for wave in [
+            stock_wave_at("inventory.restocked", 10, "2024-05-01T14:00:00Z"),
+            stock_wave_at("inventory.reserved", 4, "2024-05-01T14:01:00Z"),
+            stock_wave_at("inventory.reserved", 9, "2024-05-01T14:03:00Z"),
+            // Logged late from a producer whose clock lags
+            stock_wave_at("inventory.reserved", 2, "2024-05-01T14:01:30Z"),
+        ] {
+            store.append_wave(&wave).unwrap();
+        }
+
+        let at = |time: &str| time.parse::<DateTime<Utc>>().unwrap();
+        let before = Stock.state_at(&store, at("2024-05-01T13:59:00Z")).unwrap();
+        assert!(before.is_empty());
+        let during = Stock.state_at(&store, at("2024-05-01T14:02:00Z")).unwrap();
+        assert_eq!(during["ItemA"], 4);
+        let after = Stock.state_at(&store, at("2024-05-01T14:05:00Z")).unwrap();
+        assert_eq!(after["ItemA"], -5);
+
+        drop(store);
+        let _ = std::fs::remove_dir_all(log_path);
+    }
+
+    #[test]
+    fn test_catch_up_resumes_from_checkpoint() {
+        let log_path = temp_path("projection-log");
+        let checkpoint_path = temp_path("projection-checkpoint");
+        let store = WaveStore::open(&log_path).unwrap();
+        store
+            .append_wave(&stock_wave("inventory.restocked", 100))
+            .unwrap();
+        store.append_wave(&stock_wave("orders.created", 5)).unwrap();
+        store
+            .append_wave(&stock_wave("inventory.reserved", 3))
+            .unwrap();
+
+        let mut runner = ProjectionRunner::open(Stock, &checkpoint_path).unwrap();
+        assert_eq!(runner.catch_up(&store).unwrap(), 3);
+        runner.checkpoint().unwrap();
+        assert_eq!(runner.view().snapshot()["ItemA"], 97);
+        drop(runner);
+
+        store
+            .append_wave(&stock_wave("inventory.reserved", 7))
+            .unwrap();
+        let mut restored = ProjectionRunner::open(Stock, &checkpoint_path).unwrap();
+        assert_eq!(restored.next_index(), 3);
+        assert_eq!(restored.catch_up(&store).unwrap(), 1);
+        assert_eq!(restored.view().read(|stock| stock["ItemA"]), 90);
+
+        let _ = std::fs::remove_dir_all(log_path);
+        let _ = std::fs::remove_dir_all(checkpoint_path);
+    }
+
+    #[test]
+    fn test_encrypted_checkpoint_needs_the_keyring() {
+        let log_path = temp_path("projection-sealed-log");
+        let checkpoint_path = temp_path("projection-sealed-checkpoint");
+        let keyring = Keyring::new("k1", [7; 32]).unwrap();
+        let store = WaveStore::open(&log_path).unwrap();
+        store
+            .append_wave(&stock_wave("inventory.restocked", 4111))
+            .unwrap();
+
+        let mut runner =
+            ProjectionRunner::open_encrypted(Stock, &checkpoint_path, keyring.clone()).unwrap();
+        runner.catch_up(&store).unwrap();
+        runner.checkpoint().unwrap();
+        let raw = runner.tree.get(KEY_CHECKPOINT).unwrap().unwrap();
+        assert!(!raw.windows(4).any(|window| window == b"4111"));
+        drop(runner);
+
+        assert!(ProjectionRunner::open(Stock, &checkpoint_path).is_err());
+        let restored = ProjectionRunner::open_encrypted(Stock, &checkpoint_path, keyring).unwrap();
+        assert_eq!(restored.view().snapshot()["ItemA"], 4111);
+
+        drop(store);
+        let _ = std::fs::remove_dir_all(log_path);
+        let _ = std::fs::remove_dir_all(checkpoint_path);
+    }
+}
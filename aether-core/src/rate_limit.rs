@@ -0,0 +1,414 @@
+//! Rate limiting: token buckets applied to producers and channels in `Aether::emit`.
+
+use crate::labels::{LabelBudget, DEFAULT_LABEL_BUDGET};
+use crate::{channel::Channel, AetherError, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Bucket key for waves without a source
+const ANONYMOUS_SOURCE: &str = "<anonymous>";
+
+/// Sources labelled individually in the source rate limit counters
+static LIMITED_SOURCES: LabelBudget = LabelBudget::new(DEFAULT_LABEL_BUDGET);
+
+/// Per-source emission limits
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    /// Sustained waves per second allowed for each source (0 leaves sources unlimited)
+    pub per_source_per_sec: f64,
+    /// Burst capacity (defaults to one second of traffic)
+    pub burst: Option<f64>,
+    /// Delay offenders up to this long instead of rejecting (0 rejects)
+    pub max_delay_ms: u64,
+    /// Limits for specific sources
+    pub overrides: HashMap<String, SourceLimit>,
+}
+
+/// Limit for one source
+#[derive(Debug, Clone, Deserialize)]
+pub struct SourceLimit {
+    /// Sustained waves per second (0 leaves the source unlimited)
+    pub per_sec: f64,
+    #[serde(default)]
+    pub burst: Option<f64>,
+}
+
+/// Throughput quota for channels matching a pattern
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChannelQuota {
+    /// Channel pattern (e.g. "orders.*"); each matching channel gets its own budget
+    pub channel: String,
+    #[serde(default)]
+    pub waves_per_sec: Option<f64>,
+    #[serde(default)]
+    pub bytes_per_sec: Option<f64>,
+    /// Bucket size in waves: the most let through at once (defaults to `waves_per_sec`, min 1)
+    #[serde(default)]
+    pub burst_waves: Option<f64>,
+    /// Bucket size in bytes: the most let through at once (defaults to `bytes_per_sec`, min 1)
+    #[serde(default)]
+    pub burst_bytes: Option<f64>,
+}
+
+/// Token bucket that can go into debt so callers can wait out a short deficit
+#[derive(Debug)]
+pub(crate) struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    pub(crate) fn new(rate: f64, capacity: f64) -> Self {
+        let capacity = capacity.max(1.0);
+        Self {
+            rate: rate.max(f64::MIN_POSITIVE),
+            capacity,
+            tokens: capacity,
+            updated: Instant::now(),
+        }
+    }
+
+    /// Reserve tokens, waiting at most `max_wait` for them
+    ///
+    /// `Ok(wait)` means the tokens are taken and the caller should sleep for
+    /// `wait`; `Err(retry_after)` means nothing was taken.
+    pub(crate) fn reserve(
+        &mut self,
+        amount: f64,
+        max_wait: Duration,
+    ) -> std::result::Result<Duration, Duration> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.updated = now;
+
+        let deficit = amount - self.tokens;
+        let wait = if deficit > 0.0 {
+            Duration::try_from_secs_f64(deficit / self.rate).unwrap_or(Duration::MAX)
+        } else {
+            Duration::ZERO
+        };
+        if wait > max_wait {
+            return Err(wait);
+        }
+        self.tokens -= amount;
+        Ok(wait)
+    }
+
+    /// Give back tokens from a reservation that was not used
+    pub(crate) fn refund(&mut self, amount: f64) {
+        self.tokens = (self.tokens + amount).min(self.capacity);
+    }
+
+    /// Refilled to capacity by `now`, so indistinguishable from a new bucket
+    fn is_full(&self, now: Instant) -> bool {
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens + elapsed * self.rate >= self.capacity
+    }
+}
+
+/// How often a [`BucketMap`] looks for buckets to drop
+const BUCKET_SWEEP_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Token buckets by key that forgets the idle ones
+///
+/// A bucket that has refilled behaves exactly like a new one, so it is
+/// dropped at the next sweep; keys seen once don't stay around forever.
+#[derive(Debug)]
+pub(crate) struct BucketMap {
+    buckets: HashMap<String, TokenBucket>,
+    swept: Instant,
+}
+
+impl Default for BucketMap {
+    fn default() -> Self {
+        Self {
+            buckets: HashMap::new(),
+            swept: Instant::now(),
+        }
+    }
+}
+
+impl BucketMap {
+    /// Bucket for `key`, created with `new` if it was not kept
+    pub(crate) fn bucket(
+        &mut self,
+        key: &str,
+        new: impl FnOnce() -> TokenBucket,
+    ) -> &mut TokenBucket {
+        let now = Instant::now();
+        if now.duration_since(self.swept) >= BUCKET_SWEEP_INTERVAL {
+            self.buckets.retain(|_, bucket| !bucket.is_full(now));
+            self.swept = now;
+        }
+        self.buckets.entry(key.to_string()).or_insert_with(new)
+    }
+}
+
+/// Token bucket per wave source
+#[derive(Debug)]
+pub(crate) struct SourceRateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<BucketMap>,
+}
+
+impl SourceRateLimiter {
+    pub(crate) fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(BucketMap::default()),
+        }
+    }
+
+    /// Admit one wave from `source`, sleeping if the config allows a delay
+    pub(crate) async fn acquire(&self, source: Option<&str>) -> Result<()> {
+        let source = source.unwrap_or(ANONYMOUS_SOURCE);
+        let Some((rate, burst)) = self.limit_for(source) else {
+            return Ok(());
+        };
+        let max_wait = Duration::from_millis(self.config.max_delay_ms);
+
+        let reservation = self
+            .buckets
+            .lock()
+            .expect("rate limiter lock poisoned")
+            .bucket(source, || TokenBucket::new(rate, burst.unwrap_or(rate)))
+            .reserve(1.0, max_wait);
+
+        match reservation {
+            Ok(wait) if wait.is_zero() => Ok(()),
+            Ok(wait) => {
+                metrics::counter!(
+                    "aether_rate_limit_delayed_total",
+                    "source" => LIMITED_SOURCES.label(source)
+                )
+                .increment(1);
+                tokio::time::sleep(wait).await;
+                Ok(())
+            }
+            Err(retry_after) => {
+                metrics::counter!(
+                    "aether_rate_limited_total",
+                    "source" => LIMITED_SOURCES.label(source)
+                )
+                .increment(1);
+                Err(AetherError::RateLimited(format!(
+                    "source {} exceeded its rate limit, retry in {:?}",
+                    source, retry_after
+                )))
+            }
+        }
+    }
+
+    /// Rate and burst for `source`; `None` when it is unlimited
+    fn limit_for(&self, source: &str) -> Option<(f64, Option<f64>)> {
+        let (rate, burst) = match self.config.overrides.get(source) {
+            Some(limit) => (limit.per_sec, limit.burst),
+            None => (self.config.per_source_per_sec, self.config.burst),
+        };
+        (rate > 0.0).then_some((rate, burst))
+    }
+}
+
+#[derive(Debug)]
+struct QuotaBuckets {
+    waves: Option<TokenBucket>,
+    bytes: Option<TokenBucket>,
+}
+
+/// Per-channel wave and byte budgets
+#[derive(Debug)]
+pub(crate) struct ChannelQuotas {
+    quotas: Vec<(Channel, ChannelQuota)>,
+    buckets: Mutex<HashMap<String, QuotaBuckets>>,
+}
+
+impl ChannelQuotas {
+    pub(crate) fn new(quotas: Vec<ChannelQuota>) -> Self {
+        Self {
+            quotas: quotas
+                .into_iter()
+                .map(|quota| (Channel::new(quota.channel.clone()), quota))
+                .collect(),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Charge one wave of `bytes` to its channel; rejects instead of delaying
+    pub(crate) fn charge(&self, channel: &Channel, bytes: usize) -> Result<()> {
+        let Some((_, quota)) = self
+            .quotas
+            .iter()
+            .find(|(pattern, _)| channel.matches(pattern))
+        else {
+            return Ok(());
+        };
+
+        let mut buckets = self.buckets.lock().expect("quota lock poisoned");
+        let buckets = buckets
+            .entry(channel.name().to_string())
+            .or_insert_with(|| QuotaBuckets {
+                waves: quota
+                    .waves_per_sec
+                    .map(|rate| TokenBucket::new(rate, quota.burst_waves.unwrap_or(rate))),
+                bytes: quota
+                    .bytes_per_sec
+                    .map(|rate| TokenBucket::new(rate, quota.burst_bytes.unwrap_or(rate))),
+            });
+
+        if let Some(waves) = &mut buckets.waves {
+            if waves.reserve(1.0, Duration::ZERO).is_err() {
+                return Err(quota_exceeded(channel, "waves"));
+            }
+        }
+        if let Some(byte_bucket) = &mut buckets.bytes {
+            if byte_bucket.reserve(bytes as f64, Duration::ZERO).is_err() {
+                if let Some(waves) = &mut buckets.waves {
+                    waves.refund(1.0);
+                }
+                return Err(quota_exceeded(channel, "bytes"));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn quota_exceeded(channel: &Channel, kind: &'static str) -> AetherError {
+    metrics::counter!(
+        "aether_quota_exceeded_total",
+        "channel" => channel.name().to_string(),
+        "kind" => kind
+    )
+    .increment(1);
+    AetherError::QuotaExceeded(format!("channel {} exceeded its {} quota", channel, kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_bucket_refills_over_time() {
+        let mut bucket = TokenBucket::new(2.0, 2.0);
+        assert!(bucket.reserve(1.0, Duration::ZERO).is_ok());
+        assert!(bucket.reserve(1.0, Duration::ZERO).is_ok());
+        assert_eq!(
+            bucket.reserve(1.0, Duration::ZERO),
+            Err(Duration::from_millis(500))
+        );
+
+        tokio::time::advance(Duration::from_millis(500)).await;
+        assert!(bucket.reserve(1.0, Duration::ZERO).is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_bucket_map_drops_refilled_buckets() {
+        let mut buckets = BucketMap::default();
+        for key in ["orders.1", "orders.2"] {
+            let bucket = buckets.bucket(key, || TokenBucket::new(1.0, 1.0));
+            assert!(bucket.reserve(1.0, Duration::ZERO).is_ok());
+        }
+        tokio::time::advance(BUCKET_SWEEP_INTERVAL).await;
+        let slow = buckets.bucket("orders.2", || TokenBucket::new(0.01, 1.0));
+        assert!(slow.reserve(1.0, Duration::ZERO).is_ok());
+        assert_eq!(buckets.buckets.len(), 1);
+
+        // orders.2 is still refilling at the next sweep, so it is kept
+        tokio::time::advance(BUCKET_SWEEP_INTERVAL).await;
+        buckets.bucket("orders.3", || TokenBucket::new(1.0, 1.0));
+        assert_eq!(buckets.buckets.len(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_overrides_and_delay() {
+        let limiter = SourceRateLimiter::new(RateLimitConfig {
+            per_source_per_sec: 1.0,
+            max_delay_ms: 100,
+            overrides: HashMap::from([(
+                "batch".to_string(),
+                SourceLimit {
+                    per_sec: 10.0,
+                    burst: None,
+                },
+            )]),
+            ..RateLimitConfig::default()
+        });
+
+        assert!(limiter.acquire(Some("chatty")).await.is_ok());
+        let err = limiter.acquire(Some("chatty")).await.unwrap_err();
+        assert!(matches!(err, AetherError::RateLimited(_)));
+        // A refilled bucket is swept like any other
+        tokio::time::advance(BUCKET_SWEEP_INTERVAL).await;
+        assert!(limiter.acquire(Some("chatty")).await.is_ok());
+
+        for _ in 0..10 {
+            limiter.acquire(Some("batch")).await.unwrap();
+        }
+        let start = Instant::now();
+        limiter.acquire(Some("batch")).await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_millis(100));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_zero_limit_leaves_sources_unlimited() {
+        let limiter = SourceRateLimiter::new(RateLimitConfig {
+            overrides: HashMap::from([(
+                "batch".to_string(),
+                SourceLimit {
+                    per_sec: 1.0,
+                    burst: None,
+                },
+            )]),
+            ..RateLimitConfig::default()
+        });
+        for _ in 0..100 {
+            limiter.acquire(Some("chatty")).await.unwrap();
+            limiter.acquire(None).await.unwrap();
+        }
+        limiter.acquire(Some("batch")).await.unwrap();
+        assert!(limiter.acquire(Some("batch")).await.is_err());
+        assert!(limiter
+            .buckets
+            .lock()
+            .unwrap()
+            .buckets
+            .contains_key("batch"));
+        assert_eq!(limiter.buckets.lock().unwrap().buckets.len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_channel_quota_waves_and_bytes() {
+        let quotas = ChannelQuotas::new(vec![ChannelQuota {
+            channel: "metrics.*".to_string(),
+            waves_per_sec: Some(2.0),
+            bytes_per_sec: Some(100.0),
+            burst_waves: None,
+            burst_bytes: None,
+        }]);
+        let cpu = Channel::new("metrics.cpu");
+        let mem = Channel::new("metrics.mem");
+
+        quotas.charge(&cpu, 10).unwrap();
+        assert!(matches!(
+            quotas.charge(&cpu, 200),
+            Err(AetherError::QuotaExceeded(_))
+        ));
+        // The rejected byte charge refunded its wave token
+        quotas.charge(&cpu, 10).unwrap();
+        assert!(quotas.charge(&cpu, 10).is_err());
+
+        // Each channel has its own budget; unmatched channels are unlimited
+        quotas.charge(&mem, 10).unwrap();
+        for _ in 0..10 {
+            quotas
+                .charge(&Channel::new("orders.created"), 1_000)
+                .unwrap();
+        }
+    }
+}
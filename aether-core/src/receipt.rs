@@ -0,0 +1,169 @@
+//! How an emit treats the wave and what it did with it: who heard it and
+//! whether it was stored.
+
+use crate::persistence::Durability;
+use serde::Serialize;
+use uuid::Uuid;
+
+/// Per-wave settings for `Aether::emit_with`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EmitOptions {
+    /// Overrides the layer's `persistence_durability`; unset keeps it
+    pub durability: Option<Durability>,
+}
+
+impl EmitOptions {
+    pub fn durability(durability: Durability) -> Self {
+        Self {
+            durability: Some(durability),
+        }
+    }
+}
+
+/// How the wave left the layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmitTransport {
+    /// In-process broadcast channels
+    Local,
+    /// Published to NATS
+    Nats,
+    /// Not sent: dropped by a transformer, the propagation limit, loop
+    /// detection or the amplitude floor
+    Dropped,
+}
+
+/// Returned by `Aether::emit` for a wave the layer accepted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EmitReceipt {
+    pub wave_id: Uuid,
+    /// Subscribers the wave reached; `None` over NATS, which does not report them
+    pub receivers: Option<usize>,
+    /// Log index once the write is synced; `None` when not persisted or still buffered
+    pub persisted_index: Option<u64>,
+    pub transport: EmitTransport,
+}
+
+impl EmitReceipt {
+    pub(crate) fn dropped(wave_id: Uuid) -> Self {
+        Self {
+            wave_id,
+            receivers: Some(0),
+            persisted_index: None,
+            transport: EmitTransport::Dropped,
+        }
+    }
+
+    /// Nobody was known to be listening: dropped, or sent with no local subscriber
+    ///
+    /// Over NATS only a dropped wave is known to be unheard, since NATS does
+    /// not report subscribers; don't rely on this to detect missing consumers there.
+    pub fn unheard(&self) -> bool {
+        self.receivers == Some(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aether::{Aether, AetherConfig, PropagationLimitPolicy};
+    use crate::channel::Channel;
+    use crate::wave::Wave;
+
+    fn wave(channel: &str) -> Wave {
+        Wave::builder(channel)
+            .payload(serde_json::json!({ "order_id": "ORD-1" }))
+            .source("service-alpha")
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_receipt_reports_receivers_and_persistence() {
+        let path = std::env::temp_dir().join(format!("aether-receipt-{}", Uuid::new_v4()));
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            persistence_enabled: true,
+            persistence_path: path.to_string_lossy().into_owned(),
+            persistence_durability: Durability::Fsync,
+            max_propagation: 1,
+            propagation_limit_policy: PropagationLimitPolicy::Drop,
+            ..AetherConfig::default()
+        });
+
+        let unheard = aether.emit(wave("orders.created")).await.unwrap();
+        assert!(unheard.unheard());
+        assert_eq!(unheard.transport, EmitTransport::Local);
+        assert!(unheard.persisted_index.is_some());
+
+        let _exact = aether.subscribe(&Channel::new("orders.created")).await;
+        let _pattern = aether.subscribe(&Channel::new("orders.*")).await;
+        let sent = wave("orders.created");
+        let id = *sent.id();
+        let heard = aether.emit(sent).await.unwrap();
+        assert_eq!(heard.wave_id, id);
+        assert_eq!(heard.receivers, Some(2));
+        assert!(heard.persisted_index > unheard.persisted_index);
+
+        let mut relayed = wave("orders.created");
+        relayed.propagate();
+        let dropped = aether.emit(relayed).await.unwrap();
+        assert_eq!(dropped.transport, EmitTransport::Dropped);
+        assert!(dropped.unheard());
+        assert_eq!(dropped.persisted_index, None);
+
+        drop(aether);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[tokio::test]
+    async fn test_emit_options_override_the_layer_durability() {
+        let path = std::env::temp_dir().join(format!("aether-durability-{}", Uuid::new_v4()));
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            persistence_enabled: true,
+            persistence_path: path.to_string_lossy().into_owned(),
+            ..AetherConfig::default()
+        });
+        let store = aether.wave_store().unwrap().clone();
+
+        let telemetry = aether
+            .emit_with(
+                wave("metrics.cpu"),
+                EmitOptions::durability(Durability::Unlogged),
+            )
+            .await
+            .unwrap();
+        assert_eq!(telemetry.persisted_index, None);
+        let payment = aether
+            .emit_with(
+                wave("payments.charged"),
+                EmitOptions::durability(Durability::Fsync),
+            )
+            .await
+            .unwrap();
+        // Acknowledged only once the wave is on disk
+        assert_eq!(payment.persisted_index, Some(0));
+        assert_eq!(store.len(), 1);
+        let (_, logged) = store.get_wave(&payment.wave_id).unwrap().unwrap();
+        assert_eq!(logged.channel().name(), "payments.charged");
+
+        let fsync: Durability = serde_json::from_str("\"fsync\"").unwrap();
+        assert_eq!(fsync, Durability::Fsync);
+        let sync: Durability = serde_json::from_str("\"sync\"").unwrap();
+        assert_eq!(sync, Durability::Fsync);
+
+        drop(store);
+        drop(aether);
+        let _ = std::fs::remove_dir_all(&path);
+
+        // A layer that logs nothing must not pass for a persistent one
+        let unlogged = Aether::try_new(AetherConfig {
+            use_nats: false,
+            persistence_enabled: true,
+            persistence_path: path.to_string_lossy().into_owned(),
+            persistence_durability: Durability::Unlogged,
+            ..AetherConfig::default()
+        });
+        assert!(unlogged.is_err());
+    }
+}
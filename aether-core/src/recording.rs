@@ -0,0 +1,338 @@
+//! Recording: capture waves to NDJSON or a WaveStore and replay them later.
+
+use crate::{
+    aether::Aether, channel::Channel, persistence::WaveStore, receipt::EmitTransport,
+    redaction::Redactor, wave::Wave,
+};
+use anyhow::Result;
+use futures::{Stream, StreamExt};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+type WaveFilter = Arc<dyn Fn(&Wave) -> bool + Send + Sync>;
+
+/// Most waves written per blocking-pool hop in `record_stream`
+const RECORD_BATCH: usize = 256;
+
+enum Sink {
+    Ndjson(BufWriter<File>),
+    Store(WaveStore),
+}
+
+/// Writes matching waves to a recording file
+pub struct WaveRecorder {
+    sink: Sink,
+    filter: Option<WaveFilter>,
+    redactor: Option<Arc<Redactor>>,
+    sample_rate: f64,
+    recorded: u64,
+}
+
+impl WaveRecorder {
+    /// Record to a newline-delimited JSON file (truncates existing content)
+    pub fn ndjson(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self::with_sink(Sink::Ndjson(BufWriter::new(file))))
+    }
+
+    /// Record into a WaveStore directory
+    pub fn wave_store(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::with_sink(Sink::Store(WaveStore::open(path)?)))
+    }
+
+    fn with_sink(sink: Sink) -> Self {
+        Self {
+            sink,
+            filter: None,
+            redactor: None,
+            sample_rate: 1.0,
+            recorded: 0,
+        }
+    }
+
+    /// Only record waves accepted by the filter
+    pub fn with_filter(mut self, filter: impl Fn(&Wave) -> bool + Send + Sync + 'static) -> Self {
+        self.filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Mask fields before waves are written
+    pub fn with_redactor(mut self, redactor: Arc<Redactor>) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// Record roughly this fraction of waves (0.0..=1.0)
+    ///
+    /// Sampling is keyed on the wave ID, so the same wave is always kept or
+    /// dropped regardless of which recorder sees it.
+    pub fn with_sample_rate(mut self, sample_rate: f64) -> Self {
+        self.sample_rate = sample_rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Number of waves written so far
+    pub fn recorded(&self) -> u64 {
+        self.recorded
+    }
+
+    /// Record a wave if it passes the filter and sampling
+    pub fn record(&mut self, wave: &Wave) -> Result<bool> {
+        if let Some(filter) = &self.filter {
+            if !filter(wave) {
+                return Ok(false);
+            }
+        }
+        if !is_sampled(wave, self.sample_rate) {
+            return Ok(false);
+        }
+
+        let wave = match &self.redactor {
+            Some(redactor) => redactor.redact(wave),
+            None => std::borrow::Cow::Borrowed(wave),
+        };
+        match &mut self.sink {
+            Sink::Ndjson(writer) => {
+                serde_json::to_writer(&mut *writer, wave.as_ref())?;
+                writer.write_all(b"\n")?;
+            }
+            Sink::Store(store) => {
+                store.append_wave(&wave)?;
+            }
+        }
+        self.recorded += 1;
+        Ok(true)
+    }
+
+    /// Flush buffered output
+    pub fn flush(&mut self) -> Result<()> {
+        match &mut self.sink {
+            Sink::Ndjson(writer) => writer.flush()?,
+            Sink::Store(store) => store.flush()?,
+        }
+        Ok(())
+    }
+
+    /// Record every wave from a stream until it ends
+    ///
+    /// Waves already waiting are written together on the blocking pool, so
+    /// file and store I/O never stalls the runtime.
+    pub async fn record_stream(self, stream: impl Stream<Item = Wave>) -> Result<u64> {
+        let batches = stream.ready_chunks(RECORD_BATCH);
+        futures::pin_mut!(batches);
+        let mut recorder = self;
+        while let Some(waves) = batches.next().await {
+            recorder = tokio::task::spawn_blocking(move || {
+                for wave in &waves {
+                    if let Err(err) = recorder.record(wave) {
+                        warn!("Failed to record wave {}: {}", wave.id(), err);
+                    }
+                }
+                recorder
+            })
+            .await?;
+        }
+        tokio::task::spawn_blocking(move || {
+            recorder.flush()?;
+            Ok(recorder.recorded)
+        })
+        .await?
+    }
+
+    /// Record waves matching a pattern in the background
+    pub async fn spawn(self, aether: &Aether, pattern: Channel) -> JoinHandle<Result<u64>> {
+        info!("Recording waves on {}", pattern);
+        let stream = aether.tap(pattern).await;
+        tokio::spawn(self.record_stream(stream))
+    }
+}
+
+/// Load a recording (NDJSON file or WaveStore directory)
+///
+/// A WaveStore is opened read-only; one a live service is writing fails to
+/// load, so load a checkpoint of it (`WaveStore::checkpoint`) instead.
+pub fn load_recording(path: impl AsRef<Path>) -> Result<Vec<Wave>> {
+    let path = path.as_ref();
+    if path.is_dir() {
+        return WaveStore::open_read_only(path, None)?.read_from(0);
+    }
+
+    let reader = BufReader::new(File::open(path)?);
+    let mut waves = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        waves.push(serde_json::from_str::<Wave>(&line)?);
+    }
+    Ok(waves)
+}
+
+/// Emit recorded waves into an Aether layer in order, returning how many were sent
+///
+/// Hop counts start again at zero and flow-trace trails are cleared, so
+/// replayed traffic is neither dead-lettered for hops it made when it was
+/// recorded nor dropped as a loop through the services it already passed.
+pub async fn replay_recording(aether: &Aether, waves: Vec<Wave>) -> crate::Result<usize> {
+    let mut sent = 0;
+    for mut wave in waves {
+        wave.reset_propagation();
+        wave.clear_breadcrumbs();
+        if aether.emit(wave).await?.transport != EmitTransport::Dropped {
+            sent += 1;
+        }
+    }
+    Ok(sent)
+}
+
+/// Load a recording without blocking the runtime; see [`load_recording`]
+pub async fn load_recording_async(path: impl AsRef<Path>) -> Result<Vec<Wave>> {
+    let path = path.as_ref().to_path_buf();
+    tokio::task::spawn_blocking(move || load_recording(path)).await?
+}
+
+fn is_sampled(wave: &Wave, sample_rate: f64) -> bool {
+    if sample_rate >= 1.0 {
+        return true;
+    }
+    let bucket = (wave.id().as_u128() % 10_000) as f64;
+    bucket < sample_rate * 10_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aether::AetherConfig;
+    use crate::flow_trace::FlowTraceConfig;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("aether-{}-{}", name, uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_ndjson_round_trip_with_filter() {
+        let path = temp_path("recording.ndjson");
+        let mut recorder = WaveRecorder::ndjson(&path)
+            .unwrap()
+            .with_filter(|wave| wave.channel().name() != "skip.me");
+
+        recorder
+            .record(&Wave::new("orders.created", serde_json::json!({"id": 1})))
+            .unwrap();
+        recorder
+            .record(&Wave::new("skip.me", serde_json::json!({})))
+            .unwrap();
+        recorder.flush().unwrap();
+        assert_eq!(recorder.recorded(), 1);
+
+        let waves = load_recording(&path).unwrap();
+        assert_eq!(waves.len(), 1);
+        assert_eq!(waves[0].channel().name(), "orders.created");
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_load_store_held_by_a_writer() {
+        let path = temp_path("leased-store");
+        let mut recorder = WaveRecorder::wave_store(&path).unwrap();
+        recorder
+            .record(&Wave::new("orders.created", serde_json::json!({"id": 1})))
+            .unwrap();
+        recorder.flush().unwrap();
+
+        // The recorder still holds the writer lease
+        let err = load_recording(&path).unwrap_err().to_string();
+        assert!(err.contains("checkpoint"), "{}", err);
+        drop(recorder);
+        let waves = load_recording(&path).unwrap();
+        assert_eq!(waves.len(), 1);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_zero_sample_rate_records_nothing() {
+        let path = temp_path("sampled.ndjson");
+        let mut recorder = WaveRecorder::ndjson(&path).unwrap().with_sample_rate(0.0);
+        let recorded = recorder
+            .record(&Wave::new("orders.created", serde_json::json!({})))
+            .unwrap();
+        assert!(!recorded);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_tap_feeds_recorder() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            ..AetherConfig::default()
+        });
+        let path = temp_path("tap.ndjson");
+        let task = WaveRecorder::ndjson(&path)
+            .unwrap()
+            .spawn(&aether, Channel::new("orders.*"))
+            .await;
+
+        aether
+            .emit(Wave::new("orders.created", serde_json::json!({})))
+            .await
+            .unwrap();
+        aether
+            .emit(Wave::new("payments.completed", serde_json::json!({})))
+            .await
+            .unwrap();
+
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        task.abort();
+        let _ = task.await;
+
+        // Dropping the aborted recorder flushes the buffered writer
+        let waves = load_recording_async(&path).await.unwrap();
+        assert_eq!(waves.len(), 1);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_replay_restarts_hop_counts() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            max_propagation: 2,
+            ..AetherConfig::default()
+        });
+        let channel = Channel::new("orders.created");
+        let mut rx = aether.subscribe(&channel).await;
+        let mut recorded = Wave::new(channel.name(), serde_json::json!({"id": 1}));
+        recorded.propagate();
+        recorded.propagate();
+
+        assert_eq!(replay_recording(&aether, vec![recorded]).await.unwrap(), 1);
+        let replayed = rx.recv().await.unwrap();
+        assert_eq!(replayed.propagation_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_replay_is_not_dropped_as_a_loop() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            flow_trace: Some(FlowTraceConfig {
+                detect_loops: true,
+                ..FlowTraceConfig::default()
+            }),
+            ..AetherConfig::default()
+        });
+        let channel = Channel::new("orders.created");
+        let mut rx = aether.subscribe(&channel).await;
+        let recorded = Wave::builder(channel.clone()).source("checkout").build();
+        aether.emit(recorded).await.unwrap();
+        let recorded = rx.recv().await.unwrap();
+        assert_eq!(recorded.breadcrumbs().len(), 1);
+
+        assert_eq!(replay_recording(&aether, vec![recorded]).await.unwrap(), 1);
+        let replayed = rx.recv().await.unwrap();
+        assert_eq!(replayed.breadcrumbs().len(), 1);
+    }
+}
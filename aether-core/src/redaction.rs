@@ -0,0 +1,169 @@
+//! Redaction: mask payload fields in waves that are logged, recorded or exported.
+//!
+//! Waves travel between services untouched; only the copies handed to
+//! observers (logs, recordings, exporters) are masked.
+
+use crate::{channel::Channel, wave::Wave};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// Replacement for masked values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Fields to mask on a channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionRule {
+    /// Channel name or pattern (e.g. "payments.>")
+    pub channel: String,
+    /// JSON pointers into the payload (e.g. "/customer/name"); a `*` segment
+    /// matches every array element or object field
+    pub paths: Vec<String>,
+}
+
+/// Compiled redaction rules; every matching rule applies
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    rules: Vec<(Channel, Vec<Vec<String>>)>,
+}
+
+impl Redactor {
+    pub fn new(rules: &[RedactionRule]) -> Self {
+        Self {
+            rules: rules
+                .iter()
+                .map(|rule| {
+                    let paths = rule.paths.iter().map(|path| parse_pointer(path)).collect();
+                    (Channel::new(&rule.channel), paths)
+                })
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The wave's payload with matching fields masked
+    pub fn payload<'w>(&self, wave: &'w Wave) -> Cow<'w, serde_json::Value> {
+        let mut paths = self.paths_for(wave.channel()).peekable();
+        if paths.peek().is_none() {
+            return Cow::Borrowed(wave.payload());
+        }
+        let mut payload = wave.payload().clone();
+        for path in paths {
+            mask(&mut payload, path);
+        }
+        Cow::Owned(payload)
+    }
+
+    /// Copy of the wave safe to hand to observers
+    ///
+    /// Raw byte payloads are opaque and passed through unchanged.
+    pub fn redact<'w>(&self, wave: &'w Wave) -> Cow<'w, Wave> {
+        match self.payload(wave) {
+            Cow::Borrowed(_) => Cow::Borrowed(wave),
+            Cow::Owned(payload) => {
+                let mut redacted = wave.clone();
+                *redacted.payload_mut() = payload;
+                Cow::Owned(redacted)
+            }
+        }
+    }
+
+    fn paths_for<'a>(&'a self, channel: &'a Channel) -> impl Iterator<Item = &'a [String]> + 'a {
+        self.rules
+            .iter()
+            .filter(move |(pattern, _)| channel.matches(pattern))
+            .flat_map(|(_, paths)| paths.iter().map(Vec::as_slice))
+    }
+}
+
+/// Split an RFC 6901 pointer into unescaped segments ("" is the whole payload)
+fn parse_pointer(pointer: &str) -> Vec<String> {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+        .collect()
+}
+
+/// Mask the values at `path`; missing fields are left alone
+fn mask(value: &mut serde_json::Value, path: &[String]) {
+    let Some((segment, rest)) = path.split_first() else {
+        *value = serde_json::Value::String(REDACTED.to_string());
+        return;
+    };
+    match value {
+        serde_json::Value::Object(fields) if segment == "*" => {
+            fields.values_mut().for_each(|field| mask(field, rest));
+        }
+        serde_json::Value::Object(fields) => {
+            if let Some(field) = fields.get_mut(segment) {
+                mask(field, rest);
+            }
+        }
+        serde_json::Value::Array(items) if segment == "*" => {
+            items.iter_mut().for_each(|item| mask(item, rest));
+        }
+        serde_json::Value::Array(items) => {
+            if let Some(item) = segment
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| items.get_mut(index))
+            {
+                mask(item, rest);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules_mask_matching_channels_only() {
+        let redactor = Redactor::new(&[
+            RedactionRule {
+                channel: "payments.>".to_string(),
+                paths: vec!["/card/number".to_string(), "/customer".to_string()],
+            },
+            RedactionRule {
+                channel: "orders.*".to_string(),
+                paths: vec!["/items/*/gift_note".to_string(), "/a~1b".to_string()],
+            },
+        ]);
+
+        let payment = Wave::new(
+            "payments.captured",
+            serde_json::json!({
+                "order_id": "o-1",
+                "customer": {"name": "Ada"},
+                "card": {"number": "4111", "brand": "visa"},
+            }),
+        );
+        let redacted = redactor.redact(&payment);
+        assert_eq!(
+            redacted.payload(),
+            &serde_json::json!({
+                "order_id": "o-1",
+                "customer": REDACTED,
+                "card": {"number": REDACTED, "brand": "visa"},
+            })
+        );
+        assert_eq!(payment.payload()["customer"]["name"], "Ada");
+
+        let order = Wave::new(
+            "orders.created",
+            serde_json::json!({"items": [{"gift_note": "hi"}, {"sku": "A"}], "a/b": 1}),
+        );
+        assert_eq!(
+            redactor.payload(&order).into_owned(),
+            serde_json::json!({"items": [{"gift_note": REDACTED}, {"sku": "A"}], "a/b": REDACTED})
+        );
+
+        let other = Wave::new("inventory.level", serde_json::json!({"customer": "x"}));
+        assert!(matches!(redactor.redact(&other), Cow::Borrowed(_)));
+    }
+}
@@ -0,0 +1,326 @@
+//! Channel registry: declared channels with ownership, retention and payload schema.
+
+use crate::{channel::Channel, wave::Wave, AetherError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use tracing::warn;
+
+/// Edit distance under which an undeclared channel is reported as a likely typo
+const SUGGESTION_DISTANCE: usize = 2;
+
+/// How emits on undeclared channels or with mismatched payloads are handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RegistryMode {
+    /// Log a warning and emit anyway
+    #[default]
+    Warn,
+    /// Reject the emit
+    Strict,
+}
+
+/// Expected JSON type of a payload field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    String,
+    Number,
+    Boolean,
+    Object,
+    Array,
+    Any,
+}
+
+impl FieldType {
+    fn accepts(self, value: &serde_json::Value) -> bool {
+        match self {
+            FieldType::String => value.is_string(),
+            FieldType::Number => value.is_number(),
+            FieldType::Boolean => value.is_boolean(),
+            FieldType::Object => value.is_object(),
+            FieldType::Array => value.is_array(),
+            FieldType::Any => true,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            FieldType::String => "string",
+            FieldType::Number => "number",
+            FieldType::Boolean => "boolean",
+            FieldType::Object => "object",
+            FieldType::Array => "array",
+            FieldType::Any => "any",
+        }
+    }
+}
+
+/// A declared channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelSpec {
+    /// Channel name or pattern (e.g. "orders.created", "metrics.>")
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Owning service
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// How long consumers and stores should keep waves
+    #[serde(default)]
+    pub retention_secs: Option<u64>,
+    /// Required payload fields and their types
+    #[serde(default)]
+    pub schema: BTreeMap<String, FieldType>,
+}
+
+impl ChannelSpec {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: String::new(),
+            owner: None,
+            retention_secs: None,
+            schema: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    pub fn with_owner(mut self, owner: impl Into<String>) -> Self {
+        self.owner = Some(owner.into());
+        self
+    }
+
+    pub fn with_retention_secs(mut self, retention_secs: u64) -> Self {
+        self.retention_secs = Some(retention_secs);
+        self
+    }
+
+    pub fn with_field(mut self, field: impl Into<String>, field_type: FieldType) -> Self {
+        self.schema.insert(field.into(), field_type);
+        self
+    }
+
+    /// Describe the first schema violation in a payload, if any
+    fn violation(&self, payload: &serde_json::Value) -> Option<String> {
+        self.schema
+            .iter()
+            .find_map(|(field, field_type)| match payload.get(field) {
+                None => Some(format!("missing field {}", field)),
+                Some(value) if !field_type.accepts(value) => {
+                    Some(format!("field {} is not a {}", field, field_type.name()))
+                }
+                Some(_) => None,
+            })
+    }
+}
+
+/// Registry configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RegistryConfig {
+    pub mode: RegistryMode,
+    pub channels: Vec<ChannelSpec>,
+}
+
+/// Declared channels, checked by `Aether::emit`
+#[derive(Debug, Clone, Default)]
+pub struct ChannelRegistry {
+    mode: RegistryMode,
+    specs: Vec<(Channel, ChannelSpec)>,
+}
+
+impl ChannelRegistry {
+    pub fn new(mode: RegistryMode) -> Self {
+        Self {
+            mode,
+            specs: Vec::new(),
+        }
+    }
+
+    pub fn from_config(config: RegistryConfig) -> Self {
+        config
+            .channels
+            .into_iter()
+            .fold(Self::new(config.mode), |registry, spec| {
+                registry.declare(spec)
+            })
+    }
+
+    /// Declare a channel, replacing an earlier declaration with the same name
+    pub fn declare(mut self, spec: ChannelSpec) -> Self {
+        self.specs
+            .retain(|(_, existing)| existing.name != spec.name);
+        self.specs.push((Channel::new(spec.name.clone()), spec));
+        self
+    }
+
+    pub fn mode(&self) -> RegistryMode {
+        self.mode
+    }
+
+    /// Configuration that rebuilds this registry
+    pub fn to_config(&self) -> RegistryConfig {
+        RegistryConfig {
+            mode: self.mode,
+            channels: self.specs.iter().map(|(_, spec)| spec.clone()).collect(),
+        }
+    }
+
+    /// Declaration for a channel; exact names win over patterns
+    pub fn lookup(&self, channel: &Channel) -> Option<&ChannelSpec> {
+        self.specs
+            .iter()
+            .find(|(declared, _)| declared == channel)
+            .or_else(|| {
+                self.specs
+                    .iter()
+                    .find(|(declared, _)| declared.is_wildcard() && channel.matches(declared))
+            })
+            .map(|(_, spec)| spec)
+    }
+
+    /// Declared channels sorted by name
+    pub fn specs(&self) -> Vec<&ChannelSpec> {
+        let mut specs: Vec<_> = self.specs.iter().map(|(_, spec)| spec).collect();
+        specs.sort_by(|a, b| a.name.cmp(&b.name));
+        specs
+    }
+
+    /// Check a wave against its declaration; errors only in strict mode
+    pub fn check(&self, wave: &Wave) -> Result<()> {
+        let channel = wave.channel();
+        let problem = match self.lookup(channel) {
+            None => match self.suggest(channel.name()) {
+                Some(near) => format!(
+                    "channel {} is not declared (did you mean {}?)",
+                    channel, near
+                ),
+                None => format!("channel {} is not declared", channel),
+            },
+            Some(spec) => match wave.payload_bytes() {
+                // Raw payloads are opaque to the schema
+                Some(_) => return Ok(()),
+                None => match spec.violation(wave.payload()) {
+                    Some(violation) => format!("payload on {}: {}", channel, violation),
+                    None => return Ok(()),
+                },
+            },
+        };
+
+        let channel_label = channel.name().to_string();
+        metrics::counter!("aether_registry_violations_total", "channel" => channel_label)
+            .increment(1);
+        match self.mode {
+            RegistryMode::Warn => {
+                warn!("{}", problem);
+                Ok(())
+            }
+            RegistryMode::Strict => Err(AetherError::ValidationFailed(problem)),
+        }
+    }
+
+    /// Closest declared channel name within a small edit distance
+    pub fn suggest(&self, name: &str) -> Option<&str> {
+        self.specs
+            .iter()
+            .filter(|(declared, _)| !declared.is_wildcard())
+            .map(|(_, spec)| (edit_distance(name, &spec.name), spec.name.as_str()))
+            .filter(|(distance, _)| *distance <= SUGGESTION_DISTANCE)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, name)| name)
+    }
+
+    /// Human-readable catalog of declared channels
+    pub fn render_catalog(&self) -> String {
+        let mut out = String::new();
+        for spec in self.specs() {
+            let _ = write!(out, "{}", spec.name);
+            if let Some(owner) = &spec.owner {
+                let _ = write!(out, " (owner: {})", owner);
+            }
+            if let Some(retention) = spec.retention_secs {
+                let _ = write!(out, " [retention: {}s]", retention);
+            }
+            if !spec.description.is_empty() {
+                let _ = write!(out, " - {}", spec.description);
+            }
+            if !spec.schema.is_empty() {
+                let fields: Vec<String> = spec
+                    .schema
+                    .iter()
+                    .map(|(field, field_type)| format!("{}: {}", field, field_type.name()))
+                    .collect();
+                let _ = write!(out, " {{ {} }}", fields.join(", "));
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry(mode: RegistryMode) -> ChannelRegistry {
+        ChannelRegistry::new(mode)
+            .declare(
+                ChannelSpec::new("orders.confirmed")
+                    .with_owner("service-beta")
+                    .with_field("order_id", FieldType::String),
+            )
+            .declare(ChannelSpec::new("metrics.>"))
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_typos_and_bad_payloads() {
+        let registry = registry(RegistryMode::Strict);
+
+        let typo = Wave::new("orders.comfirmed", serde_json::json!({"order_id": "1"}));
+        let err = registry.check(&typo).unwrap_err().to_string();
+        assert!(err.contains("did you mean orders.confirmed"), "{}", err);
+
+        let bad = Wave::new("orders.confirmed", serde_json::json!({"order_id": 1}));
+        assert!(registry.check(&bad).is_err());
+
+        let good = Wave::new("orders.confirmed", serde_json::json!({"order_id": "1"}));
+        assert!(registry.check(&good).is_ok());
+        assert!(registry
+            .check(&Wave::new("metrics.cpu.load", serde_json::json!({})))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_warn_mode_allows_and_catalog_lists_channels() {
+        let registry = registry(RegistryMode::Warn);
+        assert!(registry
+            .check(&Wave::new("orders.comfirmed", serde_json::json!({})))
+            .is_ok());
+
+        let catalog = registry.render_catalog();
+        assert_eq!(
+            catalog,
+            "metrics.>\norders.confirmed (owner: service-beta) { order_id: string }\n"
+        );
+    }
+}
@@ -1,6 +1,15 @@
-//! Reliability utilities: retry, timeout, and circuit breaker.
+//! Reliability utilities: retry, timeout, hedging, and circuit breaker.
+//!
+//! Both are instrumented under an `operation` label naming the downstream:
+//! `retry_with_timeout_named` records attempts, timeouts and time spent per
+//! call, and a breaker built with `CircuitBreaker::named` records its state,
+//! transitions, rejected calls and time spent open.
 
+use crate::aether::Aether;
+use crate::audit::{AuditKind, AuditLog};
+use crate::events::{AetherEvent, EventBus};
 use anyhow::{anyhow, Result};
+use futures::stream::{FuturesUnordered, StreamExt};
 use std::future::Future;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -33,12 +42,42 @@ impl RetryPolicy {
     }
 }
 
+/// When `hedge` starts another attempt
+#[derive(Debug, Clone)]
+pub struct HedgePolicy {
+    /// Wait this long for an attempt before starting the next one
+    pub delay: Duration,
+    /// Attempts in flight at most, the first one included
+    pub max_attempts: usize,
+}
+
+impl HedgePolicy {
+    /// One hedge, started after `delay`
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            max_attempts: 2,
+        }
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CircuitBreaker {
     state: Arc<Mutex<CircuitState>>,
     failure_threshold: usize,
     open_duration: Duration,
     half_open_successes: usize,
+    /// `operation` label on the breaker's metrics
+    name: Option<String>,
+    /// Breaker name and the log its trips are recorded in
+    audit: Option<(String, AuditLog)>,
+    /// Breaker name and the layer its state changes are published on
+    events: Option<(String, EventBus)>,
 }
 
 #[derive(Debug)]
@@ -48,6 +87,25 @@ enum CircuitState {
     HalfOpen { successes: usize },
 }
 
+impl CircuitState {
+    fn label(&self) -> &'static str {
+        match self {
+            CircuitState::Closed { .. } => "closed",
+            CircuitState::Open { .. } => "open",
+            CircuitState::HalfOpen { .. } => "half_open",
+        }
+    }
+
+    /// `aether_circuit_state` value: 0 closed, 1 half-open, 2 open
+    fn level(&self) -> f64 {
+        match self {
+            CircuitState::Closed { .. } => 0.0,
+            CircuitState::HalfOpen { .. } => 1.0,
+            CircuitState::Open { .. } => 2.0,
+        }
+    }
+}
+
 impl CircuitBreaker {
     pub fn new(failure_threshold: usize, open_duration: Duration, half_open_successes: usize) -> Self {
         Self {
@@ -55,6 +113,66 @@ impl CircuitBreaker {
             failure_threshold: failure_threshold.max(1),
             open_duration,
             half_open_successes: half_open_successes.max(1),
+            name: None,
+            audit: None,
+            events: None,
+        }
+    }
+
+    /// Record state transitions and time spent open under `operation="<name>"`
+    pub fn named(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Record trips to the audit log under `name`
+    pub fn with_audit(mut self, name: impl Into<String>, audit: Option<&AuditLog>) -> Self {
+        self.audit = audit.map(|audit| (name.into(), audit.clone()));
+        self
+    }
+
+    /// Publish opens and recoveries as `Aether::events` under `name`
+    pub fn with_events(mut self, name: impl Into<String>, aether: &Aether) -> Self {
+        self.events = Some((name.into(), aether.event_bus().clone()));
+        self
+    }
+
+    fn publish(&self, event: impl FnOnce(String) -> AetherEvent) {
+        if let Some((name, events)) = &self.events {
+            events.publish(event(name.clone()));
+        }
+    }
+
+    /// Move to `to`, recording the transition if the breaker is named
+    fn transition(&self, state: &mut CircuitState, to: CircuitState) {
+        if let Some(operation) = &self.name {
+            if let CircuitState::Open { opened_at } = state {
+                metrics::histogram!("aether_circuit_open_seconds", "operation" => operation.clone())
+                    .record(opened_at.elapsed().as_secs_f64());
+            }
+            metrics::counter!(
+                "aether_circuit_transitions_total",
+                "operation" => operation.clone(),
+                "from" => state.label(),
+                "to" => to.label()
+            )
+            .increment(1);
+            metrics::gauge!("aether_circuit_state", "operation" => operation.clone())
+                .set(to.level());
+        }
+        *state = to;
+    }
+
+    fn record_trip(&self, failures: usize) {
+        self.publish(|name| AetherEvent::CircuitOpened { name, failures });
+        if let Some((name, audit)) = &self.audit {
+            let detail = serde_json::json!({
+                "failures": failures,
+                "open_ms": self.open_duration.as_millis() as u64,
+            });
+            if let Err(err) = audit.record(AuditKind::CircuitBreakerTrip, Some(name), detail) {
+                tracing::warn!("Failed to record circuit breaker trip: {}", err);
+            }
         }
     }
 
@@ -65,14 +183,15 @@ impl CircuitBreaker {
     {
         {
             let mut state = self.state.lock().await;
-            match &mut *state {
-                CircuitState::Open { opened_at } => {
-                    if opened_at.elapsed() < self.open_duration {
-                        return Err(anyhow!("circuit open"));
+            if let CircuitState::Open { opened_at } = &*state {
+                if opened_at.elapsed() < self.open_duration {
+                    if let Some(operation) = &self.name {
+                        metrics::counter!("aether_circuit_rejected_total", "operation" => operation.clone())
+                            .increment(1);
                     }
-                    *state = CircuitState::HalfOpen { successes: 0 };
+                    return Err(anyhow!("circuit open"));
                 }
-                _ => {}
+                self.transition(&mut state, CircuitState::HalfOpen { successes: 0 });
             }
         }
 
@@ -86,21 +205,23 @@ impl CircuitBreaker {
             (CircuitState::Closed { failures }, false) => {
                 *failures += 1;
                 if *failures >= self.failure_threshold {
-                    *state = CircuitState::Open {
-                        opened_at: Instant::now(),
-                    };
+                    let failures = *failures;
+                    let opened_at = Instant::now();
+                    self.transition(&mut state, CircuitState::Open { opened_at });
+                    self.record_trip(failures);
                 }
             }
             (CircuitState::HalfOpen { successes }, true) => {
                 *successes += 1;
                 if *successes >= self.half_open_successes {
-                    *state = CircuitState::Closed { failures: 0 };
+                    self.transition(&mut state, CircuitState::Closed { failures: 0 });
+                    self.publish(|name| AetherEvent::CircuitClosed { name });
                 }
             }
             (CircuitState::HalfOpen { .. }, false) => {
-                *state = CircuitState::Open {
-                    opened_at: Instant::now(),
-                };
+                let opened_at = Instant::now();
+                self.transition(&mut state, CircuitState::Open { opened_at });
+                self.record_trip(1);
             }
             (CircuitState::Open { .. }, _) => {}
         }
@@ -109,7 +230,38 @@ impl CircuitBreaker {
     }
 }
 
+/// Retry `f` under `policy`, giving each attempt `timeout`; not instrumented
 pub async fn retry_with_timeout<F, Fut, T, E>(
+    policy: &RetryPolicy,
+    timeout: Duration,
+    f: F,
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::result::Result<T, E>>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    retry(None, policy, timeout, f).await
+}
+
+/// Like `retry_with_timeout`, recording attempts, timed-out attempts and the
+/// time each call took under `operation="<operation>"`
+pub async fn retry_with_timeout_named<F, Fut, T, E>(
+    operation: &str,
+    policy: &RetryPolicy,
+    timeout: Duration,
+    f: F,
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::result::Result<T, E>>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    retry(Some(operation), policy, timeout, f).await
+}
+
+async fn retry<F, Fut, T, E>(
+    operation: Option<&str>,
     policy: &RetryPolicy,
     timeout: Duration,
     mut f: F,
@@ -119,18 +271,38 @@ where
     Fut: Future<Output = std::result::Result<T, E>>,
     E: std::error::Error + Send + Sync + 'static,
 {
+    let started = Instant::now();
+    let finish = |attempts: usize, outcome: &'static str| {
+        let Some(operation) = operation else {
+            return;
+        };
+        let operation = operation.to_string();
+        metrics::histogram!("aether_retry_attempts", "operation" => operation.clone())
+            .record(attempts as f64);
+        metrics::histogram!("aether_retry_duration_seconds", "operation" => operation.clone(), "outcome" => outcome)
+            .record(started.elapsed().as_secs_f64());
+    };
     let mut attempt = 0;
     loop {
         let result = tokio::time::timeout(timeout, f()).await;
         match result {
-            Ok(Ok(value)) => return Ok(value),
+            Ok(Ok(value)) => {
+                finish(attempt + 1, "ok");
+                return Ok(value);
+            }
             Ok(Err(err)) => {
                 if attempt >= policy.max_retries {
+                    finish(attempt + 1, "error");
                     return Err(anyhow!(err));
                 }
             }
             Err(_) => {
+                if let Some(operation) = operation {
+                    metrics::counter!("aether_retry_timeouts_total", "operation" => operation.to_string())
+                        .increment(1);
+                }
                 if attempt >= policy.max_retries {
+                    finish(attempt + 1, "timeout");
                     return Err(anyhow!("timeout"));
                 }
             }
@@ -143,3 +315,152 @@ where
         }
     }
 }
+
+/// Run `f`, starting another attempt each time `policy.delay` passes without
+/// an answer, and return the first success
+///
+/// Losing attempts are dropped, which cancels them. A failed attempt starts
+/// the next one at once; the last error is returned only when every attempt
+/// failed. `f` must be safe to run more than once, like a `Query`.
+pub async fn hedge<F, Fut, T, E>(policy: &HedgePolicy, mut f: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::result::Result<T, E>>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let max_attempts = policy.max_attempts.max(1);
+    let mut attempts = FuturesUnordered::new();
+    attempts.push(indexed(0, f()));
+    let mut launched = 1;
+    let timer = sleep(policy.delay);
+    tokio::pin!(timer);
+    loop {
+        tokio::select! {
+            Some((index, result)) = attempts.next() => match result {
+                Ok(value) => {
+                    if index > 0 {
+                        metrics::counter!("aether_hedge_wins_total").increment(1);
+                    }
+                    return Ok(value);
+                }
+                Err(err) if launched == max_attempts && attempts.is_empty() => {
+                    return Err(anyhow!(err));
+                }
+                Err(_) if launched < max_attempts => {
+                    attempts.push(indexed(launched, f()));
+                    launched += 1;
+                    timer.as_mut().reset(tokio::time::Instant::now() + policy.delay);
+                }
+                Err(_) => {}
+            },
+            _ = &mut timer, if launched < max_attempts => {
+                metrics::counter!("aether_hedges_total").increment(1);
+                attempts.push(indexed(launched, f()));
+                launched += 1;
+                timer.as_mut().reset(tokio::time::Instant::now() + policy.delay);
+            }
+        }
+    }
+}
+
+async fn indexed<Fut: Future>(index: usize, fut: Fut) -> (usize, Fut::Output) {
+    (index, fut.await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observability::prometheus_builder;
+
+    #[test]
+    fn test_retries_and_breaker_are_labelled_by_operation() {
+        let recorder = prometheus_builder().unwrap().build_recorder();
+        let handle = recorder.handle();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        metrics::with_local_recorder(&recorder, || {
+            runtime.block_on(async {
+                let policy = RetryPolicy::new(2, Duration::from_millis(10), Duration::from_secs(1));
+                let timeout = Duration::from_millis(50);
+                let mut calls = 0;
+                let result = retry_with_timeout_named("billing", &policy, timeout, || {
+                    calls += 1;
+                    let hang = calls == 1;
+                    async move {
+                        if hang {
+                            sleep(Duration::from_secs(1)).await;
+                        }
+                        Ok::<_, std::io::Error>(calls)
+                    }
+                })
+                .await;
+                assert_eq!(result.unwrap(), 2);
+
+                let breaker =
+                    CircuitBreaker::new(1, Duration::from_millis(100), 1).named("billing");
+                let failing = || async { Err::<(), _>(anyhow!("down")) };
+                assert!(breaker.call(failing).await.is_err());
+                assert!(breaker.call(failing).await.is_err());
+                sleep(Duration::from_millis(150)).await;
+                assert!(breaker.call(|| async { Ok(()) }).await.is_ok());
+            })
+        });
+
+        let rendered = handle.render();
+        for line in [
+            r#"aether_retry_timeouts_total{operation="billing"} 1"#,
+            r#"aether_retry_attempts_sum{operation="billing"} 2"#,
+            r#"aether_retry_duration_seconds_count{operation="billing",outcome="ok"} 1"#,
+            r#"aether_circuit_rejected_total{operation="billing"} 1"#,
+            r#"aether_circuit_transitions_total{operation="billing",from="closed",to="open"} 1"#,
+            r#"aether_circuit_transitions_total{operation="billing",from="open",to="half_open"} 1"#,
+            r#"aether_circuit_transitions_total{operation="billing",from="half_open",to="closed"} 1"#,
+            r#"aether_circuit_state{operation="billing"} 0"#,
+            r#"aether_circuit_open_seconds_count{operation="billing"} 1"#,
+        ] {
+            assert!(rendered.contains(line), "missing {} in\n{}", line, rendered);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_hedge_returns_first_answer_and_cancels_the_rest() {
+        let policy = HedgePolicy::new(Duration::from_millis(50)).with_max_attempts(3);
+        let started = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let finished = Arc::new(std::sync::Mutex::new(Vec::new()));
+        // The first responder stalls; the hedge sent at 50ms answers at 60ms
+        let latencies = [1000, 10, 10];
+        let begin = tokio::time::Instant::now();
+        let mut attempt = 0;
+        let answer = hedge(&policy, || {
+            let index = attempt;
+            attempt += 1;
+            let (started, finished) = (Arc::clone(&started), Arc::clone(&finished));
+            async move {
+                started.lock().unwrap().push(index);
+                sleep(Duration::from_millis(latencies[index])).await;
+                finished.lock().unwrap().push(index);
+                Ok::<_, std::io::Error>(index)
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(answer, 1);
+        assert_eq!(begin.elapsed(), Duration::from_millis(60));
+        assert_eq!(*started.lock().unwrap(), [0, 1]);
+        sleep(Duration::from_secs(2)).await;
+        assert_eq!(*finished.lock().unwrap(), [1]);
+
+        // Failures hedge at once and the last error surfaces
+        let mut attempt = 0;
+        let failed = hedge(&HedgePolicy::new(Duration::from_secs(10)), || {
+            attempt += 1;
+            let error = std::io::Error::other(format!("attempt {}", attempt));
+            async move { Err::<(), _>(error) }
+        })
+        .await;
+        assert_eq!(failed.unwrap_err().to_string(), "attempt 2");
+        assert_eq!(begin.elapsed(), Duration::from_millis(2060));
+    }
+}
@@ -1,17 +1,303 @@
-//! Resource monitoring: memory usage, leak detection, allocator metrics.
+//! Resource monitoring: memory usage, leak detection, allocator metrics, CPU
+//! pressure, and the effective limits (configured and cgroup) the process runs under.
+//!
+//! CPU pressure has two signals. cgroup throttling comes from `cpu.stat`: the
+//! share of CFS periods in which the quota ran out, which shows up as latency
+//! spikes while CPU usage looks fine. Runtime lag is how late the monitor's own
+//! timer fires, which grows when tokio workers are starved or blocked by long
+//! polls. Both are averaged over a window so load shedding reacts to sustained
+//! pressure, not one slow tick.
 
+use crate::vibrator::VibratorEmitter;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant};
 use sysinfo::System;
 use tokio::task::JoinHandle;
 use tokio::time::sleep;
 use tracing::warn;
 
+/// Last sampled RSS; 0 until the monitor has run
+static RSS_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Windowed throttled share of CFS periods in parts per million; `u64::MAX` until known
+static THROTTLED_PPM: AtomicU64 = AtomicU64::new(u64::MAX);
+
+/// Windowed mean timer lag in microseconds; `u64::MAX` until known
+static SCHEDULING_DELAY_US: AtomicU64 = AtomicU64::new(u64::MAX);
+
+/// cgroup v1 reports "unlimited" as a huge page-aligned number
+const CGROUP_UNLIMITED: u64 = 1 << 60;
+
+/// Limits the process runs under, from config and from its cgroup
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ResourceLimits {
+    /// Set by `apply_resource_limits` (rlimit or Job Object)
+    pub memory_bytes: Option<u64>,
+    pub cpu_time_secs: Option<u64>,
+    /// Tightest `memory.max` (v2) or `memory.limit_in_bytes` (v1) up the hierarchy
+    pub cgroup_memory_bytes: Option<u64>,
+    /// CPU quota in cores from `cpu.max` (v2) or the CFS quota (v1)
+    pub cgroup_cpu_cores: Option<f64>,
+}
+
+impl ResourceLimits {
+    /// The configured limits plus whatever the process's cgroup imposes
+    pub fn detect(memory_bytes: Option<u64>, cpu_time_secs: Option<u64>) -> Self {
+        let (cgroup_memory_bytes, cgroup_cpu_cores) =
+            match std::fs::read_to_string("/proc/self/cgroup") {
+                Ok(membership) => cgroup_limits(Path::new("/sys/fs/cgroup"), &membership),
+                Err(_) => (None, None),
+            };
+        Self {
+            memory_bytes,
+            cpu_time_secs,
+            cgroup_memory_bytes,
+            cgroup_cpu_cores,
+        }
+    }
+
+    /// Lowest memory ceiling that applies, whichever set it
+    pub fn memory_ceiling(&self) -> Option<u64> {
+        match (self.memory_bytes, self.cgroup_memory_bytes) {
+            (Some(configured), Some(cgroup)) => Some(configured.min(cgroup)),
+            (configured, cgroup) => configured.or(cgroup),
+        }
+    }
+
+    /// Publish the limits as gauges; unset limits are left out
+    pub fn record_metrics(&self) {
+        if let Some(bytes) = self.memory_bytes {
+            metrics::gauge!("process_memory_limit_bytes", "source" => "process").set(bytes as f64);
+        }
+        if let Some(bytes) = self.cgroup_memory_bytes {
+            metrics::gauge!("process_memory_limit_bytes", "source" => "cgroup").set(bytes as f64);
+        }
+        if let Some(bytes) = self.memory_ceiling() {
+            metrics::gauge!("process_memory_ceiling_bytes").set(bytes as f64);
+        }
+        if let Some(seconds) = self.cpu_time_secs {
+            metrics::gauge!("process_cpu_time_limit_seconds").set(seconds as f64);
+        }
+        if let Some(cores) = self.cgroup_cpu_cores {
+            metrics::gauge!("process_cpu_limit_cores").set(cores);
+        }
+    }
+}
+
+/// Controllers and path of each hierarchy in `/proc/self/cgroup`
+fn cgroup_entries(membership: &str) -> impl Iterator<Item = (Vec<&str>, &str)> {
+    membership.lines().filter_map(|line| {
+        let mut fields = line.splitn(3, ':');
+        let (_, controllers, path) = (fields.next()?, fields.next()?, fields.next()?);
+        Some((controllers.split(',').collect(), path))
+    })
+}
+
+/// Memory and CPU limits for the cgroup named in `membership` (the contents of
+/// `/proc/self/cgroup`) under `root`
+fn cgroup_limits(root: &Path, membership: &str) -> (Option<u64>, Option<f64>) {
+    let mut memory = None;
+    let mut cores = None;
+    for (controllers, path) in cgroup_entries(membership) {
+        if controllers == [""] {
+            // Unified (v2) hierarchy
+            for dir in cgroup_dirs(root, path) {
+                memory = min_some(memory, read_limit(&dir.join("memory.max")));
+                cores = min_some(cores, read_v2_cores(&dir.join("cpu.max")));
+            }
+        } else if controllers.contains(&"memory") {
+            for dir in cgroup_dirs(&root.join("memory"), path) {
+                memory = min_some(memory, read_limit(&dir.join("memory.limit_in_bytes")));
+            }
+        } else if controllers.contains(&"cpu") {
+            for dir in cgroup_dirs(&root.join(controllers.join(",")), path) {
+                cores = min_some(cores, read_v1_cores(&dir));
+            }
+        }
+    }
+    (memory, cores)
+}
+
+/// `cpu.stat` of the process's own cgroup, if CPU accounting is visible
+fn cgroup_cpu_stat_path(root: &Path, membership: &str) -> Option<PathBuf> {
+    cgroup_entries(membership).find_map(|(controllers, path)| {
+        let root = if controllers == [""] {
+            root.to_path_buf()
+        } else if controllers.contains(&"cpu") {
+            root.join(controllers.join(","))
+        } else {
+            return None;
+        };
+        let stat = cgroup_dirs(&root, path).first()?.join("cpu.stat");
+        stat.is_file().then_some(stat)
+    })
+}
+
+/// Throttling counters from `cpu.stat`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct CpuStat {
+    periods: u64,
+    throttled_periods: u64,
+    throttled_usec: u64,
+}
+
+impl CpuStat {
+    /// v2 reports `throttled_usec`, v1 `throttled_time` in nanoseconds
+    fn parse(text: &str) -> Self {
+        let mut stat = Self::default();
+        for line in text.lines() {
+            let Some((key, value)) = line.split_once(' ') else {
+                continue;
+            };
+            let Ok(value) = value.trim().parse::<u64>() else {
+                continue;
+            };
+            match key {
+                "nr_periods" => stat.periods = value,
+                "nr_throttled" => stat.throttled_periods = value,
+                "throttled_usec" => stat.throttled_usec = value,
+                "throttled_time" => stat.throttled_usec = value / 1000,
+                _ => {}
+            }
+        }
+        stat
+    }
+}
+
+/// Share of CFS periods throttled over the monitor's window, if known
+pub fn current_cpu_throttled_ratio() -> Option<f64> {
+    match THROTTLED_PPM.load(Ordering::Relaxed) {
+        u64::MAX => None,
+        ppm => Some(ppm as f64 / 1e6),
+    }
+}
+
+/// Mean lag of the monitor's timer over its window, if known
+pub fn current_scheduling_delay() -> Option<Duration> {
+    match SCHEDULING_DELAY_US.load(Ordering::Relaxed) {
+        u64::MAX => None,
+        micros => Some(Duration::from_micros(micros)),
+    }
+}
+
+/// Throttling and timer lag over the last `window` monitor ticks
+#[derive(Debug)]
+struct CpuPressure {
+    stats: VecDeque<CpuStat>,
+    delays: VecDeque<Duration>,
+    window: usize,
+}
+
+impl CpuPressure {
+    fn new(config: &ResourceMonitorConfig) -> Self {
+        // One more stat than intervals: the ratio is over deltas
+        let window = config.cpu_pressure_window_intervals.max(1);
+        Self {
+            stats: VecDeque::with_capacity(window + 1),
+            delays: VecDeque::with_capacity(window),
+            window,
+        }
+    }
+
+    /// Throttled share of the periods elapsed across the window
+    fn observe_stat(&mut self, stat: CpuStat) -> Option<f64> {
+        if self.stats.len() > self.window {
+            self.stats.pop_front();
+        }
+        self.stats.push_back(stat);
+        let (first, last) = (self.stats.front()?, self.stats.back()?);
+        let periods = last.periods.checked_sub(first.periods)?;
+        let throttled = last
+            .throttled_periods
+            .checked_sub(first.throttled_periods)?;
+        (periods > 0).then(|| throttled as f64 / periods as f64)
+    }
+
+    fn observe_delay(&mut self, delay: Duration) -> Duration {
+        if self.delays.len() == self.window {
+            self.delays.pop_front();
+        }
+        self.delays.push_back(delay);
+        self.delays.iter().sum::<Duration>() / self.delays.len() as u32
+    }
+}
+
+/// The process's cgroup directory and its ancestors up to `root`
+///
+/// In a container the path is often outside the mounted namespace, in which
+/// case only `root` (the container's own cgroup) is read.
+fn cgroup_dirs(root: &Path, path: &str) -> Vec<PathBuf> {
+    let leaf = root.join(path.trim_start_matches('/'));
+    if !leaf.is_dir() || path.contains("..") {
+        return vec![root.to_path_buf()];
+    }
+    leaf.ancestors()
+        .take_while(|dir| dir.starts_with(root))
+        .map(Path::to_path_buf)
+        .collect()
+}
+
+fn min_some<T: PartialOrd>(current: Option<T>, next: Option<T>) -> Option<T> {
+    match (current, next) {
+        (Some(current), Some(next)) => Some(if next < current { next } else { current }),
+        (current, next) => current.or(next),
+    }
+}
+
+fn read_limit(path: &Path) -> Option<u64> {
+    let text = std::fs::read_to_string(path).ok()?;
+    text.trim()
+        .parse::<u64>()
+        .ok()
+        .filter(|bytes| *bytes < CGROUP_UNLIMITED)
+}
+
+/// `cpu.max` holds `<quota> <period>` or `max <period>`
+fn read_v2_cores(path: &Path) -> Option<f64> {
+    let text = std::fs::read_to_string(path).ok()?;
+    let mut fields = text.split_whitespace();
+    let quota: f64 = fields.next()?.parse().ok()?;
+    let period: f64 = fields.next()?.parse().ok()?;
+    (period > 0.0).then_some(quota / period)
+}
+
+/// `cpu.cfs_quota_us` is -1 when unlimited
+fn read_v1_cores(dir: &Path) -> Option<f64> {
+    let read = |name: &str| -> Option<f64> {
+        std::fs::read_to_string(dir.join(name))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    };
+    let quota = read("cpu.cfs_quota_us")?;
+    let period = read("cpu.cfs_period_us")?;
+    (quota > 0.0 && period > 0.0).then_some(quota / period)
+}
+
 #[derive(Debug, Clone)]
 pub struct ResourceMonitorConfig {
     pub enabled: bool,
     pub interval_ms: u64,
     pub leak_detection_enabled: bool,
     pub leak_growth_bytes_per_min: u64,
+    /// Memory the process cannot grow past (see [`ResourceLimits::memory_ceiling`])
+    pub memory_ceiling_bytes: Option<u64>,
+    /// With a ceiling, growth that would reach it within this many minutes
+    /// counts as a leak even below `leak_growth_bytes_per_min`
+    pub leak_exhaustion_mins: u64,
+    /// Samples the growth rate is measured over
+    pub leak_window_intervals: usize,
+    /// Samples CPU throttling and timer lag are averaged over
+    pub cpu_pressure_window_intervals: usize,
+    /// Consecutive intervals the rate must stay above the threshold
+    pub leak_sustained_intervals: usize,
+    /// Where to dump a jemalloc heap profile when a leak is suspected
+    pub heap_profile_dir: Option<PathBuf>,
+    /// Channel for leak alert waves (see `start_resource_monitoring_with_alerts`)
+    pub leak_alert_channel: String,
     pub allocator_metrics_enabled: bool,
 }
 
@@ -22,12 +308,117 @@ impl Default for ResourceMonitorConfig {
             interval_ms: 1000,
             leak_detection_enabled: false,
             leak_growth_bytes_per_min: 10 * 1024 * 1024,
+            memory_ceiling_bytes: None,
+            leak_exhaustion_mins: 30,
+            leak_window_intervals: 30,
+            cpu_pressure_window_intervals: 10,
+            leak_sustained_intervals: 10,
+            heap_profile_dir: None,
+            leak_alert_channel: "aether.alerts.memory".to_string(),
             allocator_metrics_enabled: false,
         }
     }
 }
 
+/// Resident memory at the last monitoring tick, if monitoring is running
+pub fn current_rss_bytes() -> Option<u64> {
+    match RSS_BYTES.load(Ordering::Relaxed) {
+        0 => None,
+        rss => Some(rss),
+    }
+}
+
+/// Memory growth sustained long enough to look like a leak
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LeakSuspicion {
+    growth_per_min: f64,
+    window: Duration,
+    /// At this rate, when the memory ceiling would be hit
+    minutes_to_ceiling: Option<f64>,
+}
+
+/// Flags memory that keeps growing across a sliding window
+///
+/// A single fast interval (cache warm-up, a burst) is not enough: the growth
+/// rate over the whole window has to stay above the threshold for
+/// `sustained` consecutive samples. Fires once per episode. With a memory
+/// ceiling, a slower rate that would still exhaust it within
+/// `leak_exhaustion_mins` counts too, so a tight cgroup limit is not reached
+/// unannounced.
+#[derive(Debug)]
+struct LeakDetector {
+    samples: VecDeque<(u64, Instant)>,
+    window: usize,
+    sustained: usize,
+    threshold_per_min: f64,
+    ceiling: Option<u64>,
+    exhaustion_mins: f64,
+    streak: usize,
+}
+
+impl LeakDetector {
+    fn new(config: &ResourceMonitorConfig) -> Self {
+        let window = config.leak_window_intervals.max(2);
+        Self {
+            samples: VecDeque::with_capacity(window),
+            window,
+            sustained: config.leak_sustained_intervals.max(1),
+            threshold_per_min: config.leak_growth_bytes_per_min as f64,
+            ceiling: config.memory_ceiling_bytes,
+            exhaustion_mins: config.leak_exhaustion_mins as f64,
+            streak: 0,
+        }
+    }
+
+    fn observe(&mut self, rss_bytes: u64, now: Instant) -> Option<LeakSuspicion> {
+        if self.samples.len() == self.window {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((rss_bytes, now));
+
+        let (&(first, start), &(last, end)) = (self.samples.front()?, self.samples.back()?);
+        let elapsed = end.duration_since(start);
+        if self.samples.len() < self.window || elapsed.is_zero() {
+            return None;
+        }
+        let growth_per_min = last.saturating_sub(first) as f64 / elapsed.as_secs_f64() * 60.0;
+        metrics::gauge!("process_memory_growth_bytes_per_min").set(growth_per_min);
+
+        let minutes_to_ceiling = self
+            .ceiling
+            .filter(|_| growth_per_min > 0.0)
+            .map(|ceiling| ceiling.saturating_sub(last) as f64 / growth_per_min);
+        let exhausting = minutes_to_ceiling.is_some_and(|mins| mins < self.exhaustion_mins);
+        if growth_per_min <= self.threshold_per_min && !exhausting {
+            self.streak = 0;
+            return None;
+        }
+        self.streak += 1;
+        (self.streak == self.sustained).then_some(LeakSuspicion {
+            growth_per_min,
+            window: elapsed,
+            minutes_to_ceiling,
+        })
+    }
+}
+
 pub fn start_resource_monitoring(config: ResourceMonitorConfig) -> Option<JoinHandle<()>> {
+    spawn_monitor(config, None)
+}
+
+/// Like `start_resource_monitoring`, also emitting an alert wave when a leak
+/// is suspected
+pub fn start_resource_monitoring_with_alerts(
+    config: ResourceMonitorConfig,
+    emitter: VibratorEmitter,
+) -> Option<JoinHandle<()>> {
+    spawn_monitor(config, Some(emitter))
+}
+
+fn spawn_monitor(
+    config: ResourceMonitorConfig,
+    alerts: Option<VibratorEmitter>,
+) -> Option<JoinHandle<()>> {
     if !config.enabled {
         return None;
     }
@@ -35,41 +426,33 @@ pub fn start_resource_monitoring(config: ResourceMonitorConfig) -> Option<JoinHa
     Some(tokio::spawn(async move {
         let pid = sysinfo::get_current_pid().ok();
         let mut system = System::new();
-        let mut last_mem: Option<(u64, Instant)> = None;
+        let mut leak_detector = LeakDetector::new(&config);
+        let mut cpu_pressure = CpuPressure::new(&config);
+        let cpu_stat_path = std::fs::read_to_string("/proc/self/cgroup")
+            .ok()
+            .and_then(|membership| cgroup_cpu_stat_path(Path::new("/sys/fs/cgroup"), &membership));
+        let interval = Duration::from_millis(config.interval_ms);
 
         loop {
             if let Some(pid) = pid {
                 system.refresh_process(pid);
                 if let Some(process) = system.process(pid) {
-                    let rss_kb = process.memory();
-                    let vmem_kb = process.virtual_memory();
-                    let rss_bytes = rss_kb.saturating_mul(1024);
-                    let vmem_bytes = vmem_kb.saturating_mul(1024);
+                    // sysinfo reports bytes
+                    let rss_bytes = process.memory();
+                    let vmem_bytes = process.virtual_memory();
 
+                    RSS_BYTES.store(rss_bytes, Ordering::Relaxed);
                     metrics::gauge!("process_memory_rss_bytes").set(rss_bytes as f64);
                     metrics::gauge!("process_memory_vms_bytes").set(vmem_bytes as f64);
+                    if let Some(ceiling) = config.memory_ceiling_bytes {
+                        metrics::gauge!("process_memory_headroom_bytes")
+                            .set(ceiling.saturating_sub(rss_bytes) as f64);
+                    }
 
                     if config.leak_detection_enabled {
-                        let now = Instant::now();
-                        if let Some((prev_mem, prev_time)) = last_mem {
-                            let elapsed = now.duration_since(prev_time).as_secs_f64();
-                            if elapsed > 1.0 {
-                                let growth_per_min = ((rss_bytes.saturating_sub(prev_mem)) as f64)
-                                    / elapsed
-                                    * 60.0;
-                                metrics::gauge!("process_memory_growth_bytes_per_min")
-                                    .set(growth_per_min);
-                                if growth_per_min as u64 > config.leak_growth_bytes_per_min {
-                                    metrics::counter!("process_memory_leak_suspected_total")
-                                        .increment(1);
-                                    warn!(
-                                        "Possible memory leak: growth {:.0} bytes/min",
-                                        growth_per_min
-                                    );
-                                }
-                            }
+                        if let Some(leak) = leak_detector.observe(rss_bytes, Instant::now()) {
+                            report_leak(&config, alerts.as_ref(), rss_bytes, leak).await;
                         }
-                        last_mem = Some((rss_bytes, now));
                     }
 
                     if config.allocator_metrics_enabled {
@@ -87,11 +470,99 @@ pub fn start_resource_monitoring(config: ResourceMonitorConfig) -> Option<JoinHa
                 }
             }
 
-            sleep(Duration::from_millis(config.interval_ms)).await;
+            if let Some(text) = cpu_stat_path
+                .as_ref()
+                .and_then(|path| std::fs::read_to_string(path).ok())
+            {
+                let stat = CpuStat::parse(&text);
+                metrics::counter!("process_cpu_throttled_periods_total")
+                    .absolute(stat.throttled_periods);
+                metrics::counter!("process_cpu_throttled_usec_total").absolute(stat.throttled_usec);
+                if let Some(ratio) = cpu_pressure.observe_stat(stat) {
+                    THROTTLED_PPM.store((ratio * 1e6) as u64, Ordering::Relaxed);
+                    metrics::gauge!("process_cpu_throttled_ratio").set(ratio);
+                }
+            }
+
+            let started = tokio::time::Instant::now();
+            sleep(interval).await;
+            let delay = cpu_pressure.observe_delay(started.elapsed().saturating_sub(interval));
+            SCHEDULING_DELAY_US.store(delay.as_micros() as u64, Ordering::Relaxed);
+            metrics::gauge!("tokio_scheduling_delay_seconds").set(delay.as_secs_f64());
         }
     }))
 }
 
+async fn report_leak(
+    config: &ResourceMonitorConfig,
+    alerts: Option<&VibratorEmitter>,
+    rss_bytes: u64,
+    leak: LeakSuspicion,
+) {
+    metrics::counter!("process_memory_leak_suspected_total").increment(1);
+    warn!(
+        "Possible memory leak: growth {:.0} bytes/min sustained over {:?}",
+        leak.growth_per_min, leak.window
+    );
+
+    let heap_profile =
+        config
+            .heap_profile_dir
+            .as_ref()
+            .and_then(|dir| match dump_heap_profile(dir) {
+                Ok(path) => {
+                    warn!("Heap profile written to {}", path.display());
+                    Some(path)
+                }
+                Err(err) => {
+                    warn!("Failed to dump heap profile: {}", err);
+                    None
+                }
+            });
+
+    if let Some(emitter) = alerts {
+        let payload = serde_json::json!({
+            "kind": "memory_leak_suspected",
+            "rss_bytes": rss_bytes,
+            "growth_bytes_per_min": leak.growth_per_min,
+            "window_secs": leak.window.as_secs_f64(),
+            "memory_ceiling_bytes": config.memory_ceiling_bytes,
+            "minutes_to_ceiling": leak.minutes_to_ceiling,
+            "heap_profile": heap_profile,
+        });
+        if let Err(err) = emitter
+            .emit_wave(config.leak_alert_channel.as_str(), payload)
+            .await
+        {
+            warn!("Failed to emit leak alert: {}", err);
+        }
+    }
+}
+
+/// Dump a heap profile via `prof.dump`
+///
+/// Needs the `jemalloc-profiling` feature and `MALLOC_CONF=prof:true`.
+#[cfg(feature = "jemalloc")]
+fn dump_heap_profile(dir: &std::path::Path) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let path = dir.join(format!(
+        "heap-{}-{}.prof",
+        std::process::id(),
+        chrono::Utc::now().format("%Y%m%dT%H%M%S")
+    ));
+    let c_path =
+        std::ffi::CString::new(path.to_string_lossy().as_bytes()).map_err(|e| e.to_string())?;
+    // SAFETY: prof.dump takes a NUL-terminated path that outlives the call
+    unsafe { jemalloc_ctl::raw::write(b"prof.dump\0", c_path.as_ptr()) }
+        .map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+#[cfg(not(feature = "jemalloc"))]
+fn dump_heap_profile(_dir: &std::path::Path) -> Result<PathBuf, String> {
+    Err("heap profiles need the jemalloc feature".to_string())
+}
+
 #[cfg(feature = "jemalloc")]
 fn record_jemalloc_metrics() -> Result<(), String> {
     use jemalloc_ctl::{epoch, stats};
@@ -107,3 +578,139 @@ fn record_jemalloc_metrics() -> Result<(), String> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detector() -> LeakDetector {
+        LeakDetector::new(&ResourceMonitorConfig {
+            leak_growth_bytes_per_min: 60,
+            leak_window_intervals: 4,
+            leak_sustained_intervals: 3,
+            ..ResourceMonitorConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_warm_up_burst_is_not_a_leak() {
+        let mut detector = detector();
+        let start = Instant::now();
+        let rss = [1000, 5000, 9000, 9000, 9000, 9000, 9000, 9000];
+        for (tick, rss) in rss.into_iter().enumerate() {
+            let now = start + Duration::from_secs(tick as u64);
+            assert_eq!(detector.observe(rss, now), None, "tick {}", tick);
+        }
+    }
+
+    #[test]
+    fn test_sustained_growth_fires_once() {
+        let mut detector = detector();
+        let start = Instant::now();
+        let fired: Vec<usize> = (0..12)
+            .filter(|tick| {
+                let now = start + Duration::from_secs(*tick as u64);
+                detector.observe(1000 + *tick as u64 * 10, now).is_some()
+            })
+            .collect();
+        // Window fills at tick 3, third consecutive hot window at tick 5
+        assert_eq!(fired, vec![5]);
+    }
+
+    #[test]
+    fn test_slow_growth_toward_ceiling_fires() {
+        let mut detector = LeakDetector::new(&ResourceMonitorConfig {
+            leak_growth_bytes_per_min: 6000,
+            memory_ceiling_bytes: Some(1500),
+            leak_exhaustion_mins: 1,
+            leak_window_intervals: 4,
+            leak_sustained_intervals: 3,
+            ..ResourceMonitorConfig::default()
+        });
+        let start = Instant::now();
+        // 600 bytes/min: far below the threshold, but the ceiling is < 1 min away
+        let fired: Vec<Option<f64>> = (0..8)
+            .filter_map(|tick| {
+                let now = start + Duration::from_secs(tick);
+                detector.observe(1000 + tick * 10, now)
+            })
+            .map(|leak| leak.minutes_to_ceiling)
+            .collect();
+        assert_eq!(fired.len(), 1);
+        assert!(fired[0].is_some_and(|mins| (mins - 0.75).abs() < 1e-9));
+    }
+
+    #[test]
+    fn test_cpu_pressure_averages_over_window() {
+        let mut pressure = CpuPressure::new(&ResourceMonitorConfig {
+            cpu_pressure_window_intervals: 2,
+            ..ResourceMonitorConfig::default()
+        });
+        let stat = |periods, throttled_periods| CpuStat {
+            periods,
+            throttled_periods,
+            throttled_usec: 0,
+        };
+        assert_eq!(pressure.observe_stat(stat(100, 10)), None);
+        assert_eq!(pressure.observe_stat(stat(110, 20)), Some(1.0));
+        assert_eq!(pressure.observe_stat(stat(120, 20)), Some(0.5));
+        // A throttle-free stretch clears it once the window moves past
+        assert_eq!(pressure.observe_stat(stat(130, 20)), Some(0.0));
+
+        assert_eq!(
+            pressure.observe_delay(Duration::from_millis(40)),
+            Duration::from_millis(40)
+        );
+        assert_eq!(
+            pressure.observe_delay(Duration::ZERO),
+            Duration::from_millis(20)
+        );
+        assert_eq!(pressure.observe_delay(Duration::ZERO), Duration::ZERO);
+
+        let v2 =
+            CpuStat::parse("usage_usec 900\nnr_periods 50\nnr_throttled 5\nthrottled_usec 1200\n");
+        assert_eq!(
+            v2,
+            CpuStat {
+                periods: 50,
+                throttled_periods: 5,
+                throttled_usec: 1200
+            }
+        );
+        let v1 = CpuStat::parse("nr_periods 50\nnr_throttled 5\nthrottled_time 3000000\n");
+        assert_eq!(v1.throttled_usec, 3000);
+    }
+
+    #[test]
+    fn test_cgroup_limits_take_tightest_ancestor() {
+        let root = std::env::temp_dir().join(format!("aether-cgroup-{}", uuid::Uuid::new_v4()));
+        let leaf = root.join("system.slice/aether.service");
+        std::fs::create_dir_all(&leaf).unwrap();
+        std::fs::write(root.join("memory.max"), "max\n").unwrap();
+        std::fs::write(root.join("system.slice/memory.max"), "536870912\n").unwrap();
+        std::fs::write(leaf.join("memory.max"), "1073741824\n").unwrap();
+        std::fs::write(leaf.join("cpu.max"), "150000 100000\n").unwrap();
+        std::fs::write(leaf.join("cpu.stat"), "nr_periods 0\n").unwrap();
+
+        let membership = "0::/system.slice/aether.service\n";
+        let (memory, cores) = cgroup_limits(&root, membership);
+        assert_eq!(memory, Some(536_870_912));
+        assert_eq!(cores, Some(1.5));
+        assert_eq!(
+            cgroup_cpu_stat_path(&root, membership),
+            Some(leaf.join("cpu.stat"))
+        );
+        // Outside the mounted namespace only the root is read
+        assert_eq!(cgroup_limits(&root, "0::/../other\n"), (None, None));
+
+        let limits = ResourceLimits {
+            memory_bytes: Some(1 << 30),
+            cgroup_memory_bytes: memory,
+            ..ResourceLimits::default()
+        };
+        assert_eq!(limits.memory_ceiling(), Some(536_870_912));
+        assert_eq!(ResourceLimits::default().memory_ceiling(), None);
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,150 @@
+//! Blue/green rollouts: split a channel's waves between versions of a service.
+//!
+//! Every instance of the service resonates on the same channels. A rule gives
+//! `percent` of a channel's waves to instances whose version label equals the
+//! rule's `version`; all other instances take the rest. The split is decided
+//! from the wave id, so instances agree on each wave without coordinating.
+
+use crate::{channel::Channel, wave::Wave};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+
+/// Send `percent` of the waves on `channel` to instances labelled `version`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RolloutRule {
+    /// Channel or pattern the rule applies to
+    pub channel: String,
+    pub version: String,
+    /// Share of waves in `0.0..=100.0`
+    pub percent: f64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RolloutConfig {
+    #[serde(default)]
+    pub rules: Vec<RolloutRule>,
+}
+
+/// Decides which of a service's versions handles each wave
+///
+/// Clones share the rules, so the control plane can adjust them at runtime.
+#[derive(Debug, Clone)]
+pub struct VersionRouter {
+    version: Option<String>,
+    rules: Arc<RwLock<Vec<(Channel, RolloutRule)>>>,
+}
+
+impl VersionRouter {
+    /// Router for an instance advertising `version`
+    pub fn new(version: Option<String>, rules: Vec<RolloutRule>) -> Self {
+        let router = Self {
+            version,
+            rules: Arc::new(RwLock::new(Vec::new())),
+        };
+        for rule in rules {
+            router.set_rule(rule);
+        }
+        router
+    }
+
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// Whether this instance should handle `wave` (first matching rule wins)
+    pub fn admits(&self, wave: &Wave) -> bool {
+        let rules = self.rules.read().expect("rollout lock poisoned");
+        let Some((_, rule)) = rules
+            .iter()
+            .find(|(pattern, _)| wave.channel().matches(pattern))
+        else {
+            return true;
+        };
+        let routed_to_version = (rollout_bucket(wave) as f64) < rule.percent * 100.0;
+        let is_version = self.version.as_deref() == Some(rule.version.as_str());
+        if routed_to_version == is_version {
+            let variant = if is_version { "version" } else { "rest" };
+            metrics::counter!(
+                "aether_rollout_waves_total",
+                "channel" => rule.channel.clone(),
+                "variant" => variant
+            )
+            .increment(1);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Add a rule, replacing any rule for the same channel
+    pub fn set_rule(&self, mut rule: RolloutRule) {
+        rule.percent = rule.percent.clamp(0.0, 100.0);
+        let mut rules = self.rules.write().expect("rollout lock poisoned");
+        let pattern = Channel::new(&rule.channel);
+        match rules.iter_mut().find(|(existing, _)| *existing == pattern) {
+            Some(existing) => existing.1 = rule,
+            None => rules.push((pattern, rule)),
+        }
+    }
+
+    /// Drop the rule for `channel`; returns false if there was none
+    pub fn remove_rule(&self, channel: &str) -> bool {
+        let pattern = Channel::new(channel);
+        let mut rules = self.rules.write().expect("rollout lock poisoned");
+        let before = rules.len();
+        rules.retain(|(existing, _)| *existing != pattern);
+        rules.len() != before
+    }
+
+    pub fn rules(&self) -> Vec<RolloutRule> {
+        self.rules
+            .read()
+            .expect("rollout lock poisoned")
+            .iter()
+            .map(|(_, rule)| rule.clone())
+            .collect()
+    }
+}
+
+/// Stable bucket in `0..10_000` for a wave, shared by every instance
+fn rollout_bucket(wave: &Wave) -> u32 {
+    (wave.id().as_u128() % 10_000) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_split_waves_without_overlap() {
+        let rule = RolloutRule {
+            channel: "inventory.*".to_string(),
+            version: "v2".to_string(),
+            percent: 20.0,
+        };
+        let blue = VersionRouter::new(Some("v1".to_string()), vec![rule.clone()]);
+        let green = VersionRouter::new(Some("v2".to_string()), vec![rule]);
+
+        let waves: Vec<Wave> = (0..2000)
+            .map(|_| Wave::new("inventory.check", serde_json::json!({})))
+            .collect();
+        let to_green = waves.iter().filter(|w| green.admits(w)).count();
+        assert!(waves.iter().all(|w| blue.admits(w) != green.admits(w)));
+        assert!((300..500).contains(&to_green), "{} waves to v2", to_green);
+
+        // Unrouted channels reach every version
+        let order = Wave::new("orders.created", serde_json::json!({}));
+        assert!(blue.admits(&order) && green.admits(&order));
+
+        // Rules are shared between clones and adjustable at runtime
+        let control = green.clone();
+        control.set_rule(RolloutRule {
+            channel: "inventory.*".to_string(),
+            version: "v2".to_string(),
+            percent: 100.0,
+        });
+        assert!(waves.iter().all(|w| green.admits(w)));
+        assert!(control.remove_rule("inventory.*"));
+        assert!(green.rules().is_empty());
+    }
+}
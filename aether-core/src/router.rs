@@ -0,0 +1,206 @@
+//! Channel routing for typed wave handlers declared with [`handler`](crate::handler).
+
+use crate::handler_metrics::observe_handler;
+use crate::{channel::Channel, wave::Wave, AetherError};
+use futures::future::BoxFuture;
+use serde::de::DeserializeOwned;
+use std::future::Future;
+use tracing::{error, info_span, warn, Instrument};
+
+/// A wave handler bound to a channel (or channel pattern)
+///
+/// Usually generated by `#[aether_core::handler(channel = "...")]`.
+pub trait WaveHandler<S: Sync>: Send + Sync + 'static {
+    /// Channel or pattern the handler is routed on
+    fn channel(&self) -> &'static str;
+
+    fn name(&self) -> &'static str;
+
+    /// Handle one wave; failures have already been logged
+    fn handle<'a>(&'a self, state: &'a S, wave: Wave) -> BoxFuture<'a, anyhow::Result<()>>;
+}
+
+/// Handler return values: `()`, `Result<_, AetherError>` or `anyhow::Result<_>`
+pub trait IntoHandlerResult {
+    fn into_handler_result(self) -> anyhow::Result<()>;
+}
+
+impl IntoHandlerResult for () {
+    fn into_handler_result(self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+impl<T> IntoHandlerResult for std::result::Result<T, AetherError> {
+    fn into_handler_result(self) -> anyhow::Result<()> {
+        self.map(|_| ()).map_err(Into::into)
+    }
+}
+
+impl<T> IntoHandlerResult for anyhow::Result<T> {
+    fn into_handler_result(self) -> anyhow::Result<()> {
+        self.map(|_| ())
+    }
+}
+
+struct Route<S: Sync> {
+    pattern: Channel,
+    handler: Box<dyn WaveHandler<S>>,
+}
+
+/// Dispatches waves to the first registered handler whose channel matches
+pub struct WaveRouter<S: Sync> {
+    state: S,
+    routes: Vec<Route<S>>,
+}
+
+impl<S: Send + Sync + 'static> WaveRouter<S> {
+    pub fn new(state: S) -> Self {
+        Self {
+            state,
+            routes: Vec::new(),
+        }
+    }
+
+    /// Register a handler; earlier routes take precedence
+    pub fn route(self, handler: impl WaveHandler<S>) -> Self {
+        self.route_boxed(Box::new(handler))
+    }
+
+    pub(crate) fn route_boxed(mut self, handler: Box<dyn WaveHandler<S>>) -> Self {
+        self.routes.push(Route {
+            pattern: Channel::new(handler.channel()),
+            handler,
+        });
+        self
+    }
+
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    /// Channels to resonate on so every route receives its waves
+    pub fn channels(&self) -> Vec<Channel> {
+        let mut channels: Vec<Channel> = Vec::new();
+        for route in &self.routes {
+            if !channels.contains(&route.pattern) {
+                channels.push(route.pattern.clone());
+            }
+        }
+        channels
+    }
+
+    /// Name of the handler a wave on `channel` would be routed to
+    pub fn handler_for(&self, channel: &Channel) -> Option<&'static str> {
+        self.find(channel).map(|route| route.handler.name())
+    }
+
+    /// Run the matching handler, or return `None` if no route matches
+    pub async fn dispatch(&self, wave: Wave) -> Option<anyhow::Result<()>> {
+        let route = self.find(wave.channel())?;
+        Some(route.handler.handle(&self.state, wave).await)
+    }
+
+    fn find(&self, channel: &Channel) -> Option<&Route<S>> {
+        self.routes
+            .iter()
+            .find(|route| channel.matches(&route.pattern))
+    }
+}
+
+#[doc(hidden)]
+pub fn decode_payload<P: DeserializeOwned>(wave: &Wave) -> crate::Result<P> {
+    serde_json::from_value(wave.payload().clone())
+        .map_err(|e| AetherError::CodecError(format!("{} payload: {}", wave.channel(), e)))
+}
+
+#[doc(hidden)]
+pub async fn run_handler<F>(handler: &'static str, channel: Channel, fut: F) -> anyhow::Result<()>
+where
+    F: Future<Output = anyhow::Result<()>>,
+{
+    let span = info_span!("aether.handler", aether.handler = handler);
+    let result = observe_handler(channel.name(), fut).instrument(span).await;
+    if let Err(err) = &result {
+        let recoverable = err
+            .downcast_ref::<AetherError>()
+            .is_some_and(AetherError::is_recoverable);
+        if recoverable {
+            warn!(handler, channel = %channel, "Handler failed (recoverable): {:#}", err);
+        } else {
+            error!(handler, channel = %channel, "Handler failed (unrecoverable): {:#}", err);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handler;
+    use serde::Deserialize;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Ledger {
+        seen: Mutex<Vec<String>>,
+    }
+
+    #[derive(Deserialize)]
+    struct OrderCreated {
+        order_id: String,
+    }
+
+    #[handler(channel = "orders.created")]
+    async fn order_created(ledger: &Ledger, order: OrderCreated) -> anyhow::Result<()> {
+        anyhow::ensure!(!order.order_id.is_empty(), "empty order id");
+        ledger.seen.lock().unwrap().push(order.order_id);
+        Ok(())
+    }
+
+    #[handler(channel = "orders.*")]
+    async fn any_order(ledger: &Ledger, wave: Wave) {
+        ledger.seen.lock().unwrap().push(wave.channel().to_string());
+    }
+
+    #[tokio::test]
+    async fn test_routes_typed_payloads_in_registration_order() {
+        let router = WaveRouter::new(Ledger::default())
+            .route(order_created)
+            .route(any_order);
+        assert_eq!(
+            router.channels(),
+            vec![Channel::new("orders.created"), Channel::new("orders.*")]
+        );
+
+        let created = Wave::new("orders.created", serde_json::json!({"order_id": "A-1"}));
+        assert!(router.dispatch(created).await.unwrap().is_ok());
+        let paid = Wave::new("orders.paid", serde_json::json!({}));
+        assert!(router.dispatch(paid).await.unwrap().is_ok());
+        let unrouted = Wave::new("payments.completed", serde_json::json!({}));
+        assert!(router.dispatch(unrouted).await.is_none());
+
+        assert_eq!(*router.state().seen.lock().unwrap(), ["A-1", "orders.paid"]);
+        assert_eq!(
+            router.handler_for(&Channel::new("orders.created")),
+            Some("order_created")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_decode_and_handler_errors_surface() {
+        let router = WaveRouter::new(Ledger::default()).route(order_created);
+
+        let malformed = Wave::new("orders.created", serde_json::json!({"order": 1}));
+        let err = router.dispatch(malformed).await.unwrap().unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<AetherError>(),
+            Some(AetherError::CodecError(_))
+        ));
+
+        let empty = Wave::new("orders.created", serde_json::json!({"order_id": ""}));
+        let err = router.dispatch(empty).await.unwrap().unwrap_err();
+        assert_eq!(err.to_string(), "empty order id");
+        assert!(router.state().seen.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,252 @@
+//! Tokio runtime metrics and a watchdog for handlers that block the executor.
+//!
+//! [`spawn_runtime_metrics`] exports tokio's stable `RuntimeMetrics` (workers,
+//! alive tasks, global queue depth, per-worker busy ratio) as gauges.
+//!
+//! A [`BlockingWatchdog`] (opt-in, `blocking_watchdog = true`) times every
+//! poll of the handler futures it wraps. A
+//! synchronous call inside a handler (a sled read, `std::thread::sleep`) holds
+//! its worker for the whole poll, starving every task queued behind it. Polls
+//! longer than `blocking_threshold_ms` are logged with the wave's channel and
+//! counted in `aether_executor_blocked_total` (the first 256 channels by name,
+//! the rest as `other`); a watchdog thread outside the
+//! runtime also reports polls that are still running past the threshold, so a
+//! handler that never returns is named while it is stuck.
+
+use crate::labels::{LabelBudget, DEFAULT_LABEL_BUDGET};
+use futures::future::poll_fn;
+use serde::Deserialize;
+use std::future::Future;
+use std::pin::pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RuntimeMetricsConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_interval_ms")]
+    pub interval_ms: u64,
+    /// Time every handler poll; registering each wave's handler with the
+    /// watchdog takes a shared lock, so this is off by default
+    #[serde(default)]
+    pub blocking_watchdog: bool,
+    /// Handler polls longer than this are reported as blocking
+    #[serde(default = "default_blocking_threshold_ms")]
+    pub blocking_threshold_ms: u64,
+}
+
+impl Default for RuntimeMetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_ms: default_interval_ms(),
+            blocking_watchdog: false,
+            blocking_threshold_ms: default_blocking_threshold_ms(),
+        }
+    }
+}
+
+/// Channels labelled individually in `aether_executor_blocked_total`
+static BLOCKED_CHANNELS: LabelBudget = LabelBudget::new(DEFAULT_LABEL_BUDGET);
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_ms() -> u64 {
+    1000
+}
+
+fn default_blocking_threshold_ms() -> u64 {
+    100
+}
+
+/// Export the current runtime's metrics every `interval` until aborted
+pub fn spawn_runtime_metrics(interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let metrics = Handle::current().metrics();
+        let workers = metrics.num_workers();
+        let mut busy = vec![Duration::ZERO; workers];
+        let mut last = Instant::now();
+        loop {
+            tokio::time::sleep(interval).await;
+            let now = Instant::now();
+            let elapsed = now.duration_since(last).as_secs_f64();
+            last = now;
+
+            metrics::gauge!("tokio_workers").set(workers as f64);
+            metrics::gauge!("tokio_alive_tasks").set(metrics.num_alive_tasks() as f64);
+            metrics::gauge!("tokio_global_queue_depth").set(metrics.global_queue_depth() as f64);
+            for (worker, previous) in busy.iter_mut().enumerate() {
+                let total = metrics.worker_total_busy_duration(worker);
+                let ratio = (total - *previous).as_secs_f64() / elapsed;
+                *previous = total;
+                metrics::gauge!("tokio_worker_busy_ratio", "worker" => worker.to_string())
+                    .set(ratio.min(1.0));
+                metrics::counter!("tokio_worker_park_total", "worker" => worker.to_string())
+                    .absolute(metrics.worker_park_count(worker));
+            }
+        }
+    })
+}
+
+/// One wrapped future's poll in progress
+#[derive(Debug)]
+struct PollSlot {
+    channel: String,
+    /// Nanoseconds after the watchdog's epoch the current poll began, plus
+    /// one; 0 between polls
+    started: AtomicU64,
+    /// The watchdog already reported the current poll
+    reported: AtomicBool,
+}
+
+/// Reports handler polls that hold a runtime worker too long
+#[derive(Debug, Clone)]
+pub struct BlockingWatchdog {
+    threshold: Duration,
+    epoch: Instant,
+    slots: Arc<Mutex<Vec<Weak<PollSlot>>>>,
+}
+
+impl BlockingWatchdog {
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            threshold,
+            epoch: Instant::now(),
+            slots: Arc::default(),
+        }
+    }
+
+    /// Run `fut`, reporting any single poll longer than the threshold against `channel`
+    pub fn watch<F: Future>(&self, channel: &str, fut: F) -> impl Future<Output = F::Output> {
+        let slot = Arc::new(PollSlot {
+            channel: channel.to_string(),
+            started: AtomicU64::new(0),
+            reported: AtomicBool::new(false),
+        });
+        self.slots
+            .lock()
+            .expect("watchdog lock poisoned")
+            .push(Arc::downgrade(&slot));
+        let (threshold, epoch) = (self.threshold, self.epoch);
+        async move {
+            let mut fut = pin!(fut);
+            poll_fn(|cx| {
+                let started = Instant::now();
+                slot.reported.store(false, Ordering::Relaxed);
+                slot.started
+                    .store(nanos_since(epoch, started) + 1, Ordering::Relaxed);
+                let poll = fut.as_mut().poll(cx);
+                slot.started.store(0, Ordering::Relaxed);
+                let blocked = started.elapsed();
+                if blocked > threshold {
+                    let channel = BLOCKED_CHANNELS.label(&slot.channel);
+                    metrics::counter!("aether_executor_blocked_total", "channel" => channel)
+                        .increment(1);
+                    metrics::histogram!("aether_executor_blocked_seconds")
+                        .record(blocked.as_secs_f64());
+                    warn!(
+                        "Handler on {} blocked the executor for {:?} in one poll",
+                        slot.channel, blocked
+                    );
+                }
+                poll
+            })
+            .await
+        }
+    }
+
+    /// Channels of polls running longer than the threshold, each reported once
+    fn overdue(&self, now: Instant) -> Vec<(String, Duration)> {
+        let now = nanos_since(self.epoch, now);
+        let mut slots = self.slots.lock().expect("watchdog lock poisoned");
+        slots.retain(|slot| slot.strong_count() > 0);
+        slots
+            .iter()
+            .filter_map(Weak::upgrade)
+            .filter_map(|slot| {
+                let started = slot.started.load(Ordering::Relaxed).checked_sub(1)?;
+                let running = Duration::from_nanos(now.saturating_sub(started));
+                (running > self.threshold && !slot.reported.swap(true, Ordering::Relaxed))
+                    .then(|| (slot.channel.clone(), running))
+            })
+            .collect()
+    }
+
+    /// Check for stuck polls from a thread outside the runtime
+    ///
+    /// The thread exits once every clone of the watchdog is dropped.
+    pub fn spawn(&self) -> std::thread::JoinHandle<()> {
+        let watchdog = Arc::downgrade(&self.slots);
+        let (threshold, epoch) = (self.threshold, self.epoch);
+        let tick = (threshold / 2).max(Duration::from_millis(10));
+        std::thread::Builder::new()
+            .name("aether-watchdog".into())
+            .spawn(move || loop {
+                std::thread::sleep(tick);
+                let Some(slots) = watchdog.upgrade() else {
+                    break;
+                };
+                let watchdog = BlockingWatchdog {
+                    threshold,
+                    epoch,
+                    slots,
+                };
+                for (channel, running) in watchdog.overdue(Instant::now()) {
+                    warn!(
+                        "Handler on {} has been blocking a runtime worker for {:?}",
+                        channel, running
+                    );
+                }
+            })
+            .expect("failed to spawn watchdog thread")
+    }
+}
+
+fn nanos_since(epoch: Instant, at: Instant) -> u64 {
+    at.duration_since(epoch).as_nanos() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_watchdog_names_blocking_handler() {
+        let watchdog = BlockingWatchdog::new(Duration::from_millis(20));
+
+        let blocking = tokio::spawn(watchdog.watch("orders.created", async {
+            std::thread::sleep(Duration::from_millis(120));
+            7
+        }));
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        let overdue = watchdog.overdue(Instant::now());
+        assert_eq!(overdue.len(), 1);
+        assert_eq!(overdue[0].0, "orders.created");
+        assert!(overdue[0].1 > Duration::from_millis(20));
+        // Reported once per poll
+        assert!(watchdog.overdue(Instant::now()).is_empty());
+        assert_eq!(blocking.await.unwrap(), 7);
+
+        // Awaiting is not blocking, however long it takes
+        let waiting = watchdog.watch("payments.settled", async {
+            tokio::time::sleep(Duration::from_millis(60)).await;
+        });
+        let waiting = tokio::spawn(waiting);
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert!(watchdog.overdue(Instant::now()).is_empty());
+        waiting.await.unwrap();
+        assert!(watchdog
+            .slots
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|slot| slot.strong_count() == 0));
+    }
+}
@@ -0,0 +1,302 @@
+//! Sampling: how many waves get a trace span exported, a body logged or the
+//! gateway's attention.
+
+use crate::{channel::Channel, wave::Wave};
+use opentelemetry::trace::{
+    Link, SamplingDecision, SamplingResult, SpanKind, TraceContextExt, TraceId,
+};
+use opentelemetry::{Context, KeyValue, Value};
+use opentelemetry_sdk::trace::{Sampler, ShouldSample};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+/// Span attribute holding the wave's channel
+pub const CHANNEL_ATTRIBUTE: &str = "aether.channel";
+/// Span attribute set to `true` when the traced operation failed
+pub const ERROR_ATTRIBUTE: &str = "error";
+
+static WAVE_LOG_RATIO: OnceLock<f64> = OnceLock::new();
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SamplingConfig {
+    /// Fraction of traces exported (0.0 - 1.0)
+    #[serde(default = "default_ratio")]
+    pub ratio: f64,
+    /// Per-channel ratios by name or pattern; the longest matching pattern wins
+    #[serde(default)]
+    pub channels: BTreeMap<String, f64>,
+    /// Export failed spans regardless of ratio
+    #[serde(default = "default_always_sample_errors")]
+    pub always_sample_errors: bool,
+    /// Fraction of emitted waves whose (redacted) body is logged
+    #[serde(default)]
+    pub wave_log_ratio: f64,
+}
+
+impl Default for SamplingConfig {
+    fn default() -> Self {
+        Self {
+            ratio: default_ratio(),
+            channels: BTreeMap::new(),
+            always_sample_errors: default_always_sample_errors(),
+            wave_log_ratio: 0.0,
+        }
+    }
+}
+
+fn default_ratio() -> f64 {
+    1.0
+}
+
+fn default_always_sample_errors() -> bool {
+    true
+}
+
+/// Share of waves the gateway observes, so watching `>` stays cheap at high volume
+#[derive(Debug, Clone, Deserialize)]
+pub struct ObserveSamplingConfig {
+    /// Fraction of waves observed on channels without an override (0.0 - 1.0)
+    #[serde(default = "default_ratio")]
+    pub ratio: f64,
+    /// Per-channel ratios by name or pattern; the longest matching pattern wins
+    #[serde(default)]
+    pub channels: BTreeMap<String, f64>,
+}
+
+impl Default for ObserveSamplingConfig {
+    fn default() -> Self {
+        Self {
+            ratio: default_ratio(),
+            channels: BTreeMap::new(),
+        }
+    }
+}
+
+/// Longest pattern first, so the most specific override wins
+fn channel_ratios(channels: &BTreeMap<String, f64>) -> Vec<(Channel, f64)> {
+    let mut channels: Vec<(Channel, f64)> = channels
+        .iter()
+        .map(|(pattern, ratio)| (Channel::new(pattern), *ratio))
+        .collect();
+    channels.sort_by_key(|(pattern, _)| std::cmp::Reverse(pattern.name().len()));
+    channels
+}
+
+fn ratio_in(channels: &[(Channel, f64)], default: f64, channel: &str) -> f64 {
+    let channel = Channel::new(channel);
+    channels
+        .iter()
+        .find(|(pattern, _)| channel.matches(pattern))
+        .map_or(default, |(_, ratio)| *ratio)
+}
+
+/// Keyed on the wave ID, so every service picks the same waves
+fn in_sample(wave: &Wave, ratio: f64) -> bool {
+    if ratio <= 0.0 {
+        return false;
+    }
+    let bucket = (wave.id().as_u128() % 10_000) as f64;
+    bucket < ratio * 10_000.0
+}
+
+/// Head sampler for the OTLP pipeline
+///
+/// Sampled children follow their parent. Root spans use the ratio of the
+/// channel in their `aether.channel` attribute, falling back to the default
+/// ratio. Spans are built when they close, so an `error = true` recorded
+/// during the span is seen here.
+#[derive(Debug, Clone)]
+pub struct WaveSampler {
+    ratio: f64,
+    channels: Vec<(Channel, f64)>,
+    always_sample_errors: bool,
+}
+
+impl WaveSampler {
+    pub fn new(config: &SamplingConfig) -> Self {
+        Self {
+            ratio: config.ratio,
+            channels: channel_ratios(&config.channels),
+            always_sample_errors: config.always_sample_errors,
+        }
+    }
+
+    /// Trace ratio for a channel
+    pub fn ratio_for(&self, channel: &str) -> f64 {
+        ratio_in(&self.channels, self.ratio, channel)
+    }
+}
+
+impl ShouldSample for WaveSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> SamplingResult {
+        let failed = attributes
+            .iter()
+            .any(|kv| kv.key.as_str() == ERROR_ATTRIBUTE && kv.value == Value::Bool(true));
+        let parent = parent_context.filter(|cx| cx.has_active_span());
+
+        let decision = if failed && self.always_sample_errors {
+            SamplingDecision::RecordAndSample
+        } else if let Some(parent) = parent {
+            if parent.span().span_context().is_sampled() {
+                SamplingDecision::RecordAndSample
+            } else {
+                SamplingDecision::Drop
+            }
+        } else {
+            let ratio = attributes
+                .iter()
+                .find(|kv| kv.key.as_str() == CHANNEL_ATTRIBUTE)
+                .map_or(self.ratio, |kv| self.ratio_for(&kv.value.as_str()));
+            Sampler::TraceIdRatioBased(ratio)
+                .should_sample(None, trace_id, name, span_kind, attributes, links)
+                .decision
+        };
+
+        SamplingResult {
+            decision,
+            attributes: Vec::new(),
+            trace_state: parent
+                .map(|cx| cx.span().span_context().trace_state().clone())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Set the process-wide wave-log ratio; only the first call takes effect
+pub(crate) fn set_wave_log_ratio(ratio: f64) {
+    let _ = WAVE_LOG_RATIO.set(ratio);
+}
+
+/// Whether this wave's body should be logged
+///
+/// Keyed on the wave ID, so every service logs the same waves.
+pub fn should_log_wave(wave: &Wave) -> bool {
+    in_sample(wave, WAVE_LOG_RATIO.get().copied().unwrap_or(0.0))
+}
+
+/// Picks the waves the gateway observes
+#[derive(Debug, Clone)]
+pub struct ObserveSampler {
+    ratio: f64,
+    channels: Vec<(Channel, f64)>,
+}
+
+impl ObserveSampler {
+    pub fn new(config: &ObserveSamplingConfig) -> Self {
+        Self {
+            ratio: config.ratio,
+            channels: channel_ratios(&config.channels),
+        }
+    }
+
+    /// Observe ratio for a channel
+    pub fn ratio_for(&self, channel: &str) -> f64 {
+        ratio_in(&self.channels, self.ratio, channel)
+    }
+
+    /// Whether `wave` is observed; every gateway picks the same waves
+    pub fn observes(&self, wave: &Wave) -> bool {
+        in_sample(wave, self.ratio_for(wave.channel().name()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decide(sampler: &WaveSampler, trace_id: u128, attributes: &[KeyValue]) -> bool {
+        let result = sampler.should_sample(
+            None,
+            TraceId::from(trace_id),
+            "aether.emit",
+            &SpanKind::Internal,
+            attributes,
+            &[],
+        );
+        result.decision == SamplingDecision::RecordAndSample
+    }
+
+    #[test]
+    fn test_channel_overrides_and_errors() {
+        let sampler = WaveSampler::new(&SamplingConfig {
+            ratio: 0.0,
+            channels: BTreeMap::from([
+                ("payments.>".to_string(), 1.0),
+                ("payments.refunds".to_string(), 0.0),
+            ]),
+            ..SamplingConfig::default()
+        });
+        assert_eq!(sampler.ratio_for("payments.captured"), 1.0);
+        assert_eq!(sampler.ratio_for("payments.refunds"), 0.0);
+        assert_eq!(sampler.ratio_for("orders.created"), 0.0);
+
+        let trace_id = u128::MAX;
+        assert!(decide(
+            &sampler,
+            trace_id,
+            &[KeyValue::new(CHANNEL_ATTRIBUTE, "payments.captured")]
+        ));
+        assert!(!decide(
+            &sampler,
+            trace_id,
+            &[KeyValue::new(CHANNEL_ATTRIBUTE, "orders.created")]
+        ));
+        assert!(decide(
+            &sampler,
+            trace_id,
+            &[
+                KeyValue::new(CHANNEL_ATTRIBUTE, "orders.created"),
+                KeyValue::new(ERROR_ATTRIBUTE, true),
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_observe_sampler_keeps_every_payment_and_few_telemetry_waves() {
+        let sampler = ObserveSampler::new(&ObserveSamplingConfig {
+            ratio: 0.5,
+            channels: BTreeMap::from([
+                ("payments.>".to_string(), 1.0),
+                ("telemetry.>".to_string(), 0.01),
+            ]),
+        });
+        let observed = |channel: &str| {
+            (0..10_000)
+                .filter(|_| sampler.observes(&Wave::builder(channel).build()))
+                .count()
+        };
+        assert_eq!(observed("payments.captured"), 10_000);
+        assert!((30..300).contains(&observed("telemetry.cpu")));
+        assert!((4_000..6_000).contains(&observed("orders.created")));
+
+        let wave = Wave::builder("telemetry.cpu").build();
+        assert_eq!(sampler.observes(&wave), sampler.clone().observes(&wave));
+    }
+
+    #[test]
+    fn test_ratio_is_roughly_honoured() {
+        let sampler = WaveSampler::new(&SamplingConfig {
+            ratio: 0.25,
+            ..SamplingConfig::default()
+        });
+        let sampled = (0..10_000u128)
+            .filter(|n| {
+                decide(
+                    &sampler,
+                    n.wrapping_mul(0x9e37_79b9_7f4a_7c15_f39c_c060_5ced_c835),
+                    &[],
+                )
+            })
+            .count();
+        assert!((2_000..3_000).contains(&sampled), "sampled {}", sampled);
+    }
+}
@@ -0,0 +1,350 @@
+//! Sequencing: per-channel sequence numbers and receiver-side reordering.
+//!
+//! Sequences are assigned by the emitting Aether layer, so a stream is
+//! identified by channel, source and layer instance; replicas sharing a
+//! source name number their waves independently. The layer also stamps its
+//! epoch, which grows on every restart of that instance: a wave from an older
+//! epoch than one already seen for its source instance is a stale leftover
+//! of the previous run.
+
+use crate::wave::Wave;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::RangeInclusive;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// Sequence numbers that never arrived before the reorder window closed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceGap {
+    pub channel: String,
+    pub source: Option<String>,
+    pub missing: RangeInclusive<u64>,
+}
+
+impl SequenceGap {
+    /// Number of missed waves
+    pub fn count(&self) -> u64 {
+        self.missing.end() - self.missing.start() + 1
+    }
+}
+
+/// Output of the reorder buffer
+#[derive(Debug, Clone)]
+pub enum Ordered {
+    Wave(Box<Wave>),
+    Gap(SequenceGap),
+}
+
+/// What a wave's epoch says about its producer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EpochCheck {
+    /// Same epoch as before, the first one seen, or a wave without an epoch
+    Current,
+    /// The source restarted since its last wave
+    Restarted { previous: u64 },
+    /// Left over from an epoch the source has since restarted out of
+    Stale { current: u64 },
+}
+
+/// Latest epoch seen per source instance
+#[derive(Debug, Default)]
+pub struct SourceEpochs {
+    latest: HashMap<(String, Option<Uuid>), u64>,
+}
+
+impl SourceEpochs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check a wave against its source instance's latest epoch, moving that forward
+    pub fn observe(&mut self, wave: &Wave) -> EpochCheck {
+        let (Some(source), Some(epoch)) = (wave.source(), wave.epoch()) else {
+            return EpochCheck::Current;
+        };
+        let key = (source.to_string(), wave.instance().copied());
+        let Some(latest) = self.latest.get_mut(&key) else {
+            self.latest.insert(key, epoch);
+            return EpochCheck::Current;
+        };
+        match epoch.cmp(latest) {
+            std::cmp::Ordering::Equal => EpochCheck::Current,
+            std::cmp::Ordering::Less => EpochCheck::Stale { current: *latest },
+            std::cmp::Ordering::Greater => {
+                let previous = std::mem::replace(latest, epoch);
+                EpochCheck::Restarted { previous }
+            }
+        }
+    }
+
+    /// Latest epoch seen from `source` running as `instance`
+    pub fn epoch_of(&self, source: &str, instance: Option<Uuid>) -> Option<u64> {
+        self.latest.get(&(source.to_string(), instance)).copied()
+    }
+}
+
+#[derive(Debug, Default)]
+struct StreamState {
+    epoch: Option<u64>,
+    next: Option<u64>,
+    pending: BTreeMap<u64, (Wave, Instant)>,
+}
+
+/// Holds out-of-order waves until the missing ones arrive or the window expires
+#[derive(Debug)]
+pub struct ReorderBuffer {
+    window: Duration,
+    discard_stale: bool,
+    streams: HashMap<StreamKey, StreamState>,
+}
+
+/// Channel, source and emitting layer instance
+type StreamKey = (String, Option<String>, Option<Uuid>);
+
+impl ReorderBuffer {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            discard_stale: false,
+            streams: HashMap::new(),
+        }
+    }
+
+    /// Drop waves from an older epoch than the stream's instead of passing them through
+    pub fn with_stale_epochs_discarded(mut self, discard: bool) -> Self {
+        self.discard_stale = discard;
+        self
+    }
+
+    /// Accept a wave and return everything now deliverable in order
+    ///
+    /// Waves without a sequence pass straight through; duplicates and waves
+    /// older than what was already delivered are dropped. A wave from a newer
+    /// epoch restarts the stream, dropping waves held from the old one. Waves
+    /// from an older epoch pass through unordered, or are dropped when stale
+    /// epochs are discarded.
+    pub fn push(&mut self, wave: Wave) -> Vec<Ordered> {
+        let Some(sequence) = wave.sequence() else {
+            return vec![Ordered::Wave(Box::new(wave))];
+        };
+        let key = stream_key(&wave);
+        let stream = self.streams.entry(key).or_default();
+        match (wave.epoch(), stream.epoch) {
+            (Some(epoch), Some(current)) if epoch < current && !self.discard_stale => {
+                return vec![Ordered::Wave(Box::new(wave))];
+            }
+            (Some(epoch), Some(current)) if epoch < current => {
+                debug!(
+                    "Dropping wave {} from stale epoch {} (now {})",
+                    wave.id(),
+                    epoch,
+                    current
+                );
+                return Vec::new();
+            }
+            (Some(epoch), Some(current)) if epoch > current => {
+                if !stream.pending.is_empty() {
+                    debug!(
+                        "Producer restarted; dropping {} waves held from epoch {}",
+                        stream.pending.len(),
+                        current
+                    );
+                }
+                *stream = StreamState::default();
+                stream.epoch = Some(epoch);
+            }
+            (Some(epoch), None) => stream.epoch = Some(epoch),
+            _ => {}
+        }
+        let next = *stream.next.get_or_insert(sequence);
+
+        if sequence < next || stream.pending.contains_key(&sequence) {
+            debug!(
+                "Dropping duplicate or stale wave {} (sequence {})",
+                wave.id(),
+                sequence
+            );
+            return Vec::new();
+        }
+
+        stream.pending.insert(sequence, (wave, Instant::now()));
+        let mut out = Vec::new();
+        release_contiguous(stream, &mut out);
+        out
+    }
+
+    /// Give up on missing waves whose successors have waited longer than the window
+    pub fn flush_expired(&mut self) -> Vec<Ordered> {
+        let now = Instant::now();
+        let mut out = Vec::new();
+        for ((channel, source, _), stream) in &mut self.streams {
+            while let Some((&first, &(_, arrived))) = stream.pending.iter().next() {
+                if now.duration_since(arrived) < self.window {
+                    break;
+                }
+                let next = stream.next.unwrap_or(first);
+                if first > next {
+                    let gap = SequenceGap {
+                        channel: channel.clone(),
+                        source: source.clone(),
+                        missing: next..=first - 1,
+                    };
+                    warn!(
+                        "Missed {} waves on {} (sequences {:?})",
+                        gap.count(),
+                        channel,
+                        gap.missing
+                    );
+                    metrics::counter!("aether_sequence_gaps_total").increment(gap.count());
+                    out.push(Ordered::Gap(gap));
+                }
+                stream.next = Some(first);
+                release_contiguous(stream, &mut out);
+            }
+        }
+        out
+    }
+
+    /// Number of waves held back waiting for predecessors
+    pub fn pending(&self) -> usize {
+        self.streams.values().map(|s| s.pending.len()).sum()
+    }
+}
+
+fn stream_key(wave: &Wave) -> StreamKey {
+    (
+        wave.channel().name().to_string(),
+        wave.source().map(str::to_string),
+        wave.instance().copied(),
+    )
+}
+
+fn release_contiguous(stream: &mut StreamState, out: &mut Vec<Ordered>) {
+    while let Some(next) = stream.next {
+        match stream.pending.remove(&next) {
+            Some((wave, _)) => {
+                out.push(Ordered::Wave(Box::new(wave)));
+                stream.next = Some(next + 1);
+            }
+            None => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wave(sequence: u64) -> Wave {
+        let mut wave = Wave::builder("orders.updated").source("alpha").build();
+        wave.set_sequence(sequence);
+        wave
+    }
+
+    fn sequences(out: &[Ordered]) -> Vec<u64> {
+        out.iter()
+            .filter_map(|o| match o {
+                Ordered::Wave(w) => w.sequence(),
+                Ordered::Gap(_) => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_out_of_order_waves_are_delivered_in_order() {
+        let mut buffer = ReorderBuffer::new(Duration::from_millis(100));
+        assert_eq!(sequences(&buffer.push(wave(1))), vec![1]);
+        assert!(buffer.push(wave(3)).is_empty());
+        assert!(buffer.push(wave(4)).is_empty());
+        assert_eq!(sequences(&buffer.push(wave(2))), vec![2, 3, 4]);
+        assert!(buffer.push(wave(2)).is_empty());
+        assert_eq!(buffer.pending(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_gap_reported_after_window() {
+        let mut buffer = ReorderBuffer::new(Duration::from_millis(100));
+        buffer.push(wave(1));
+        buffer.push(wave(4));
+        assert!(buffer.flush_expired().is_empty());
+
+        tokio::time::advance(Duration::from_millis(100)).await;
+        let out = buffer.flush_expired();
+        match &out[0] {
+            Ordered::Gap(gap) => assert_eq!(gap.missing, 2..=3),
+            other => panic!("expected gap, got {:?}", other),
+        }
+        assert_eq!(sequences(&out), vec![4]);
+    }
+
+    const INSTANCE: Uuid = Uuid::from_u128(1);
+
+    fn wave_in(epoch: u64, sequence: u64) -> Wave {
+        wave_from(INSTANCE, epoch, sequence)
+    }
+
+    fn wave_from(instance: Uuid, epoch: u64, sequence: u64) -> Wave {
+        let mut wave = wave(sequence);
+        wave.set_source_position(instance, epoch, sequence);
+        wave
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_newer_epoch_restarts_stream_and_older_is_dropped() {
+        let mut buffer =
+            ReorderBuffer::new(Duration::from_millis(100)).with_stale_epochs_discarded(true);
+        assert_eq!(sequences(&buffer.push(wave_in(1, 7))), vec![7]);
+        assert!(buffer.push(wave_in(1, 9)).is_empty());
+
+        // Restarted producer numbers from 1 again; the held 9 is discarded
+        assert_eq!(sequences(&buffer.push(wave_in(2, 1))), vec![1]);
+        assert_eq!(buffer.pending(), 0);
+        assert!(buffer.push(wave_in(1, 8)).is_empty());
+        assert_eq!(sequences(&buffer.push(wave_in(2, 2))), vec![2]);
+    }
+
+    #[test]
+    fn test_source_epochs_detect_restarts_and_stale_waves() {
+        let mut epochs = SourceEpochs::new();
+        assert_eq!(epochs.observe(&wave_in(3, 1)), EpochCheck::Current);
+        assert_eq!(epochs.observe(&wave_in(3, 2)), EpochCheck::Current);
+        assert_eq!(
+            epochs.observe(&wave_in(4, 1)),
+            EpochCheck::Restarted { previous: 3 }
+        );
+        assert_eq!(
+            epochs.observe(&wave_in(3, 3)),
+            EpochCheck::Stale { current: 4 }
+        );
+        assert_eq!(epochs.epoch_of("alpha", Some(INSTANCE)), Some(4));
+        assert_eq!(epochs.observe(&wave(5)), EpochCheck::Current);
+    }
+
+    #[test]
+    fn test_replicas_of_a_source_keep_separate_epochs() {
+        let mut epochs = SourceEpochs::new();
+        let replica = Uuid::from_u128(2);
+        assert_eq!(epochs.observe(&wave_in(5, 1)), EpochCheck::Current);
+        assert_eq!(
+            epochs.observe(&wave_from(replica, 2, 1)),
+            EpochCheck::Current
+        );
+        assert_eq!(epochs.observe(&wave_in(5, 2)), EpochCheck::Current);
+        assert_eq!(epochs.epoch_of("alpha", Some(replica)), Some(2));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_older_epoch_passes_through_unless_discarded() {
+        let mut buffer = ReorderBuffer::new(Duration::from_millis(100));
+        assert_eq!(sequences(&buffer.push(wave_in(2, 1))), vec![1]);
+        assert_eq!(sequences(&buffer.push(wave_in(1, 8))), vec![8]);
+        assert_eq!(sequences(&buffer.push(wave_in(2, 2))), vec![2]);
+
+        // A replica numbering the same channel from 1 is its own stream
+        let replica = Uuid::from_u128(2);
+        assert_eq!(sequences(&buffer.push(wave_from(replica, 1, 1))), vec![1]);
+        assert_eq!(sequences(&buffer.push(wave_in(2, 3))), vec![3]);
+    }
+}
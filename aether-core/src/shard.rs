@@ -0,0 +1,240 @@
+//! Shard set: several Aether layers with channels partitioned by name hash.
+//!
+//! Each shard has its own channel map, locks, stats and (with NATS) its own
+//! connection, so producers on different channels stop contending. A channel
+//! always maps to the same shard, which keeps per-channel ordering and
+//! sequence numbers intact. Per-source rate limits apply per shard.
+
+use crate::{
+    aether::{Aether, AetherConfig, AetherStats},
+    channel::Channel,
+    receipt::EmitReceipt,
+    sketch::WaveSketches,
+    wave::Wave,
+    Result,
+};
+use tokio::sync::broadcast;
+use tracing::{debug, info};
+
+/// A fixed set of Aether layers that route by channel hash
+#[derive(Clone)]
+pub struct AetherShardSet {
+    shards: Vec<Aether>,
+}
+
+impl AetherShardSet {
+    /// Create `shard_count` layers from one configuration
+    ///
+    /// Each shard persists under `<persistence_path>/shard-<n>`.
+    pub fn new(config: AetherConfig, shard_count: usize) -> Self {
+        let shard_count = shard_count.max(1);
+        info!("Initializing {} Aether shards...", shard_count);
+        let shards = (0..shard_count)
+            .map(|index| {
+                let mut config = config.clone();
+                config.persistence_path = format!("{}/shard-{}", config.persistence_path, index);
+                Aether::new(config)
+            })
+            .collect();
+        Self { shards }
+    }
+
+    /// Wrap existing layers; channel placement depends on their order
+    pub fn from_shards(shards: Vec<Aether>) -> Self {
+        assert!(!shards.is_empty(), "a shard set needs at least one shard");
+        Self { shards }
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    pub fn shards(&self) -> &[Aether] {
+        &self.shards
+    }
+
+    /// Index of the shard that owns a channel
+    pub fn shard_index(&self, channel: &Channel) -> usize {
+        (fnv1a(channel.name().as_bytes()) % self.shards.len() as u64) as usize
+    }
+
+    /// Layer that owns a channel
+    pub fn shard_for(&self, channel: &Channel) -> &Aether {
+        &self.shards[self.shard_index(channel)]
+    }
+
+    /// Emit a wave on the shard owning its channel
+    pub async fn emit(&self, wave: Wave) -> Result<EmitReceipt> {
+        self.shard_for(wave.channel()).emit(wave).await
+    }
+
+    /// Listen on a channel or pattern
+    ///
+    /// A concrete channel subscribes on its own shard. With NATS, one broker
+    /// subscription already sees every shard's waves, so a wildcard pattern
+    /// subscribes on one shard too. Locally a pattern can match channels on
+    /// every shard, so it subscribes on all of them and forwards into one
+    /// receiver; the forwarding tasks stop once the receiver is dropped.
+    pub async fn subscribe(&self, channel: &Channel) -> broadcast::Receiver<Wave> {
+        if !channel.is_wildcard() || self.shards[0].config().use_nats {
+            return self.shard_for(channel).subscribe(channel).await;
+        }
+
+        let buffer_size = self.shards[0].config().channel_buffer_size;
+        let (merged, receiver) = broadcast::channel(buffer_size);
+        for shard in &self.shards {
+            let mut shard_receiver = shard.subscribe(channel).await;
+            let merged = merged.clone();
+            let pattern = channel.clone();
+            tokio::spawn(async move {
+                loop {
+                    let received = tokio::select! {
+                        _ = merged.closed() => break,
+                        received = shard_receiver.recv() => received,
+                    };
+                    match received {
+                        Ok(wave) => {
+                            if merged.send(wave).is_err() {
+                                break;
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            debug!("Shard forwarder for {} skipped {} waves", pattern, skipped);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            });
+        }
+        receiver
+    }
+
+    /// Statistics summed over all shards, with their sketches merged
+    pub async fn stats(&self) -> AetherStats {
+        let mut total = AetherStats::default();
+        let mut sketches = WaveSketches::new();
+        for shard in &self.shards {
+            let stats = shard.stats().await;
+            total.total_waves += stats.total_waves;
+            total.active_channels += stats.active_channels;
+            total.total_vibrators += stats.total_vibrators;
+            sketches.merge(&shard.sketches());
+        }
+        sketches.fill(&mut total);
+        total
+    }
+
+    /// Active channels across all shards
+    pub async fn active_channels(&self) -> Vec<String> {
+        let mut channels = Vec::new();
+        for shard in &self.shards {
+            channels.extend(shard.active_channels().await);
+        }
+        channels.sort();
+        channels
+    }
+}
+
+/// FNV-1a, stable across processes and Rust versions
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shard_set() -> AetherShardSet {
+        AetherShardSet::new(
+            AetherConfig {
+                use_nats: false,
+                ..AetherConfig::default()
+            },
+            4,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_channels_are_spread_and_routed() {
+        let shards = shard_set();
+        let channels: Vec<Channel> = (0..32)
+            .map(|n| Channel::new(format!("orders.{}", n)))
+            .collect();
+        let used: std::collections::HashSet<usize> = channels
+            .iter()
+            .map(|channel| shards.shard_index(channel))
+            .collect();
+        assert_eq!(used.len(), 4);
+
+        let mut receiver = shards.subscribe(&channels[7]).await;
+        shards
+            .emit(Wave::new(channels[7].clone(), serde_json::json!({})))
+            .await
+            .unwrap();
+        assert_eq!(receiver.recv().await.unwrap().channel(), &channels[7]);
+
+        let owner = shards.shard_for(&channels[7]);
+        assert_eq!(owner.stats().await.total_waves, 1);
+        assert_eq!(shards.stats().await.total_waves, 1);
+    }
+
+    #[tokio::test]
+    async fn test_wildcard_subscription_spans_shards() {
+        let shards = shard_set();
+        let mut all_orders = shards.subscribe(&Channel::new("orders.>")).await;
+
+        for n in 0..8 {
+            shards
+                .emit(Wave::new(
+                    format!("orders.{}", n),
+                    serde_json::json!({ "n": n }),
+                ))
+                .await
+                .unwrap();
+        }
+        let mut seen = Vec::new();
+        for _ in 0..8 {
+            let wave = all_orders.recv().await.unwrap();
+            seen.push(wave.payload()["n"].as_u64().unwrap());
+        }
+        seen.sort();
+        assert_eq!(seen, (0..8).collect::<Vec<u64>>());
+    }
+
+    #[tokio::test]
+    async fn test_forwarders_stop_when_the_receiver_is_dropped() {
+        // Channels without receivers are collected at once
+        let shards = AetherShardSet::new(
+            AetherConfig {
+                use_nats: false,
+                channel_idle_timeout_ms: Some(0),
+                ..AetherConfig::default()
+            },
+            4,
+        );
+        let receiver = shards.subscribe(&Channel::new("orders.>")).await;
+        for shard in shards.shards() {
+            assert!(shard.collect_idle_channels().await.is_empty());
+        }
+
+        // No wave arrives on the idle pattern, yet every forwarder exits
+        drop(receiver);
+        let mut remaining = shards.shards().to_vec();
+        tokio::time::timeout(std::time::Duration::from_secs(1), async {
+            while !remaining.is_empty() {
+                let mut waiting = Vec::new();
+                for shard in remaining {
+                    if shard.collect_idle_channels().await.is_empty() {
+                        waiting.push(shard);
+                    }
+                }
+                remaining = waiting;
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+}
@@ -0,0 +1,245 @@
+//! Load shedding: drop or hold back faint waves while the process is overloaded.
+
+use crate::resource_monitoring::{
+    current_cpu_throttled_ratio, current_rss_bytes, current_scheduling_delay,
+};
+use crate::wave::Wave;
+use serde::Deserialize;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// What happens to a wave shed at the receiver
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShedAction {
+    /// Drop the wave
+    #[default]
+    Reject,
+    /// Hold the wave back and deliver it once load recovers
+    Defer,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LoadSheddingConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Resident memory limit (needs resource monitoring enabled)
+    #[serde(default)]
+    pub max_rss_bytes: Option<u64>,
+    /// Tasks running in the service's task manager
+    #[serde(default)]
+    pub max_inflight: Option<usize>,
+    /// Waves queued on the vibrator's most backed-up channel
+    #[serde(default)]
+    pub max_channel_lag: Option<usize>,
+    /// Share of CFS periods throttled, averaged over the monitor's CPU
+    /// pressure window (needs resource monitoring and a cgroup CPU quota)
+    #[serde(default)]
+    pub max_cpu_throttled_ratio: Option<f64>,
+    /// Mean tokio timer lag over the same window
+    #[serde(default)]
+    pub max_scheduling_delay_ms: Option<u64>,
+    /// Waves below this amplitude are shed while overloaded
+    #[serde(default = "default_shed_amplitude")]
+    pub shed_below_amplitude: f64,
+    #[serde(default)]
+    pub action: ShedAction,
+    /// Deferred waves kept at most; the oldest is dropped beyond that
+    #[serde(default = "default_defer_capacity")]
+    pub defer_capacity: usize,
+    /// Shedding stops once every signal is below this fraction of its limit
+    #[serde(default = "default_recovery_ratio")]
+    pub recovery_ratio: f64,
+}
+
+impl Default for LoadSheddingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_rss_bytes: None,
+            max_inflight: None,
+            max_channel_lag: None,
+            max_cpu_throttled_ratio: None,
+            max_scheduling_delay_ms: None,
+            shed_below_amplitude: default_shed_amplitude(),
+            action: ShedAction::default(),
+            defer_capacity: default_defer_capacity(),
+            recovery_ratio: default_recovery_ratio(),
+        }
+    }
+}
+
+fn default_shed_amplitude() -> f64 {
+    0.5
+}
+
+fn default_defer_capacity() -> usize {
+    1000
+}
+
+fn default_recovery_ratio() -> f64 {
+    0.8
+}
+
+/// Decision for one received wave
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Admission {
+    Accept,
+    Reject,
+    Defer,
+}
+
+/// Tracks overload and decides which waves to shed
+///
+/// Shedding starts when any signal crosses its limit and stops once all of
+/// them fall below `recovery_ratio` of their limits, so it does not flap
+/// around the threshold.
+#[derive(Debug, Clone)]
+pub struct LoadShedder {
+    config: Arc<LoadSheddingConfig>,
+    inflight: Option<Arc<AtomicUsize>>,
+    shedding: Arc<AtomicBool>,
+}
+
+impl LoadShedder {
+    pub fn new(config: LoadSheddingConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            inflight: None,
+            shedding: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// `None` when shedding is disabled
+    pub fn from_config(config: &LoadSheddingConfig) -> Option<Self> {
+        config.enabled.then(|| Self::new(config.clone()))
+    }
+
+    /// Watch a task manager's inflight count (see `TaskManager::inflight_counter`)
+    pub fn with_inflight(mut self, inflight: Arc<AtomicUsize>) -> Self {
+        self.inflight = Some(inflight);
+        self
+    }
+
+    pub fn action(&self) -> ShedAction {
+        self.config.action
+    }
+
+    pub fn defer_capacity(&self) -> usize {
+        self.config.defer_capacity
+    }
+
+    pub fn is_shedding(&self) -> bool {
+        self.shedding.load(Ordering::Relaxed)
+    }
+
+    /// Re-evaluate load given the current channel lag; returns whether shedding
+    pub fn update(&self, channel_lag: usize) -> bool {
+        let config = &self.config;
+        let inflight = self
+            .inflight
+            .as_ref()
+            .map(|inflight| inflight.load(Ordering::Relaxed));
+        let signals = [
+            (
+                "rss",
+                current_rss_bytes().map(|rss| rss as f64),
+                config.max_rss_bytes.map(|max| max as f64),
+            ),
+            (
+                "inflight",
+                inflight.map(|n| n as f64),
+                config.max_inflight.map(|max| max as f64),
+            ),
+            (
+                "channel_lag",
+                Some(channel_lag as f64),
+                config.max_channel_lag.map(|max| max as f64),
+            ),
+            (
+                "cpu_throttled",
+                current_cpu_throttled_ratio(),
+                config.max_cpu_throttled_ratio,
+            ),
+            (
+                "scheduling_delay_ms",
+                current_scheduling_delay().map(|delay| delay.as_secs_f64() * 1000.0),
+                config.max_scheduling_delay_ms.map(|max| max as f64),
+            ),
+        ];
+        let readings = signals
+            .iter()
+            .filter_map(|(name, value, limit)| Some((*name, (*value)?, (*limit)?)));
+
+        let was_shedding = self.is_shedding();
+        let shedding = if was_shedding {
+            readings
+                .clone()
+                .any(|(_, value, limit)| value >= limit * config.recovery_ratio)
+        } else {
+            readings.clone().any(|(_, value, limit)| value >= limit)
+        };
+
+        if shedding != was_shedding {
+            self.shedding.store(shedding, Ordering::Relaxed);
+            metrics::gauge!("aether_load_shedding_active").set(if shedding { 1.0 } else { 0.0 });
+            if shedding {
+                let over: Vec<String> = readings
+                    .filter(|(_, value, limit)| value >= limit)
+                    .map(|(name, value, limit)| format!("{}={:.0}/{:.0}", name, value, limit))
+                    .collect();
+                warn!("Load shedding started ({})", over.join(", "));
+            } else {
+                info!("Load shedding stopped");
+            }
+        }
+        shedding
+    }
+
+    /// Decide what to do with a wave given the current channel lag
+    pub fn admit(&self, wave: &Wave, channel_lag: usize) -> Admission {
+        if !self.update(channel_lag) || wave.amplitude().value() >= self.config.shed_below_amplitude
+        {
+            return Admission::Accept;
+        }
+        let (admission, action) = match self.config.action {
+            ShedAction::Reject => (Admission::Reject, "rejected"),
+            ShedAction::Defer => (Admission::Defer, "deferred"),
+        };
+        metrics::counter!("aether_shed_waves_total", "action" => action).increment(1);
+        admission
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sheds_faint_waves_until_recovered() {
+        let inflight = Arc::new(AtomicUsize::new(0));
+        let shedder = LoadShedder::new(LoadSheddingConfig {
+            enabled: true,
+            max_inflight: Some(10),
+            ..LoadSheddingConfig::default()
+        })
+        .with_inflight(Arc::clone(&inflight));
+        let faint = Wave::builder("metrics.cpu").amplitude(0.1).build();
+        let strong = Wave::new("orders.created", serde_json::json!({}));
+
+        assert_eq!(shedder.admit(&faint, 0), Admission::Accept);
+
+        inflight.store(10, Ordering::Relaxed);
+        assert_eq!(shedder.admit(&faint, 0), Admission::Reject);
+        assert_eq!(shedder.admit(&strong, 0), Admission::Accept);
+
+        // Still above 80% of the limit
+        inflight.store(9, Ordering::Relaxed);
+        assert_eq!(shedder.admit(&faint, 0), Admission::Reject);
+
+        inflight.store(7, Ordering::Relaxed);
+        assert_eq!(shedder.admit(&faint, 0), Admission::Accept);
+        assert!(!shedder.is_shedding());
+    }
+}
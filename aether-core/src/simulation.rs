@@ -0,0 +1,119 @@
+//! Simulation: drive an in-memory Aether layer on a virtual clock.
+//!
+//! Time only moves when the simulation advances it, so decay, hopping
+//! windows, and snapshot timestamps are reproducible across runs.
+
+use crate::{
+    aether::{Aether, AetherConfig},
+    clock::{Clock, VirtualClock},
+    Result,
+};
+use chrono::{DateTime, Utc};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// In-memory Aether layer bound to a virtual clock
+pub struct Simulation {
+    aether: Aether,
+    clock: VirtualClock,
+}
+
+impl Simulation {
+    /// Start at the current system time; NATS is always disabled
+    pub fn new(config: AetherConfig) -> Self {
+        Self::starting_at(config, Utc::now())
+    }
+
+    /// Start at a fixed time
+    pub fn starting_at(config: AetherConfig, start: DateTime<Utc>) -> Self {
+        let clock = VirtualClock::at(start);
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            ..config
+        })
+        .with_clock(Arc::new(clock.clone()));
+        Self { aether, clock }
+    }
+
+    pub fn aether(&self) -> &Aether {
+        &self.aether
+    }
+
+    pub fn clock(&self) -> &VirtualClock {
+        &self.clock
+    }
+
+    /// Current virtual time
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    /// Move virtual time forward and let spawned tasks observe it
+    pub async fn advance(&self, duration: Duration) {
+        self.clock.advance(duration);
+        tokio::task::yield_now().await;
+    }
+
+    /// Run `ticks` steps, calling `on_tick` and then advancing by `step`
+    pub async fn run<F, Fut>(&self, ticks: u32, step: Duration, mut on_tick: F) -> Result<()>
+    where
+        F: FnMut(u32, Aether) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        for tick in 0..ticks {
+            on_tick(tick, self.aether.clone()).await?;
+            self.advance(step).await;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{channel::Channel, wave::Wave};
+
+    fn start() -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000, 0).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_run_advances_clock_per_tick() {
+        let sim = Simulation::starting_at(AetherConfig::default(), start());
+        let mut seen = Vec::new();
+
+        sim.run(3, Duration::from_secs(10), |tick, aether| {
+            seen.push((tick, aether.clock().now()));
+            async { Ok(()) }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(seen.len(), 3);
+        assert_eq!(seen[2].1 - seen[0].1, chrono::Duration::seconds(20));
+        assert_eq!(sim.now() - start(), chrono::Duration::seconds(30));
+    }
+
+    #[tokio::test]
+    async fn test_decay_follows_virtual_time() {
+        let sim = Simulation::starting_at(AetherConfig::default(), start());
+        let mut wave = Wave::builder("sim.decay").timestamp(sim.now()).build();
+
+        sim.advance(Duration::from_secs(120)).await;
+        wave.apply_time_decay_on(sim.clock());
+        assert!((wave.amplitude().value() - (-2.0f64).exp()).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_hopping_window_changes_with_clock() {
+        let sim = Simulation::starting_at(AetherConfig::default(), start());
+        let base = Channel::new("sim.hop");
+
+        let first = base.hop_on(sim.clock(), 4, 1000);
+        sim.advance(Duration::from_millis(999)).await;
+        assert_eq!(base.hop_on(sim.clock(), 4, 1000), first);
+        sim.advance(Duration::from_millis(1)).await;
+        assert_ne!(base.hop_on(sim.clock(), 4, 1000), first);
+    }
+}
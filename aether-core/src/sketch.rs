@@ -0,0 +1,341 @@
+//! Bounded-memory streaming sketches behind `AetherStats`.
+//!
+//! - [`HyperLogLog`] estimates distinct counts (about 1.6% error at 4096 registers)
+//! - [`Ckms`] keeps targeted quantiles with per-target rank error
+//! - [`SpaceSaving`] tracks the heaviest hitters with a fixed number of counters
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use crate::{aether::AetherStats, channel::Channel, wave::WaveType};
+
+const HLL_PRECISION: u32 = 12;
+
+/// Hash shared by every sketch in the process
+pub(crate) fn hash_of(value: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Distinct-count estimator
+#[derive(Debug, Clone)]
+pub(crate) struct HyperLogLog {
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    pub(crate) fn new() -> Self {
+        Self {
+            registers: vec![0; 1 << HLL_PRECISION],
+        }
+    }
+
+    pub(crate) fn insert_hash(&mut self, hash: u64) {
+        let index = (hash >> (64 - HLL_PRECISION)) as usize;
+        let rest = hash << HLL_PRECISION;
+        let rank = (rest.leading_zeros() + 1).min(64 - HLL_PRECISION + 1) as u8;
+        let register = &mut self.registers[index];
+        *register = (*register).max(rank);
+    }
+
+    pub(crate) fn merge(&mut self, other: &HyperLogLog) {
+        for (mine, theirs) in self.registers.iter_mut().zip(&other.registers) {
+            *mine = (*mine).max(*theirs);
+        }
+    }
+
+    pub(crate) fn estimate(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|&register| 2f64.powi(-(register as i32)))
+            .sum();
+        let raw = alpha * m * m / sum;
+        let zeros = self
+            .registers
+            .iter()
+            .filter(|&&register| register == 0)
+            .count();
+        // Linear counting is more accurate while many registers are empty
+        if raw <= 2.5 * m && zeros > 0 {
+            (m * (m / zeros as f64).ln()).round() as u64
+        } else {
+            raw.round() as u64
+        }
+    }
+}
+
+/// Quantile sketch for a fixed set of (quantile, error) targets
+///
+/// Cormode, Korn, Muthukrishnan, Srivastava: "Effective computation of biased
+/// quantiles over data streams".
+#[derive(Debug, Clone)]
+pub(crate) struct Ckms {
+    targets: Vec<(f64, f64)>,
+    /// (value, g, delta) sorted by value
+    samples: Vec<(f64, u64, u64)>,
+    count: u64,
+    since_compress: u64,
+    compress_every: u64,
+}
+
+impl Ckms {
+    pub(crate) fn new(targets: &[(f64, f64)]) -> Self {
+        let min_error = targets
+            .iter()
+            .map(|&(_, error)| error)
+            .fold(f64::INFINITY, f64::min);
+        Self {
+            targets: targets.to_vec(),
+            samples: Vec::new(),
+            count: 0,
+            since_compress: 0,
+            compress_every: (1.0 / (2.0 * min_error)).max(1.0) as u64,
+        }
+    }
+
+    /// Allowed rank spread at rank `r`
+    fn invariant(&self, r: f64) -> f64 {
+        let n = self.count as f64;
+        self.targets
+            .iter()
+            .map(|&(q, error)| {
+                if r >= q * n {
+                    2.0 * error * r / q
+                } else {
+                    2.0 * error * (n - r) / (1.0 - q)
+                }
+            })
+            .fold(f64::INFINITY, f64::min)
+            .max(1.0)
+    }
+
+    pub(crate) fn insert(&mut self, value: f64) {
+        self.insert_weighted(value, 1);
+    }
+
+    fn insert_weighted(&mut self, value: f64, weight: u64) {
+        let index = self.samples.partition_point(|&(v, _, _)| v <= value);
+        let delta = if index == 0 || index == self.samples.len() {
+            0
+        } else {
+            let rank: u64 = self.samples[..index].iter().map(|&(_, g, _)| g).sum();
+            (self.invariant(rank as f64).floor() as u64).saturating_sub(1)
+        };
+        self.samples.insert(index, (value, weight, delta));
+        self.count += weight;
+        self.since_compress += 1;
+        if self.since_compress >= self.compress_every {
+            self.compress();
+        }
+    }
+
+    /// Fold another sketch in; error bounds loosen by the other's
+    pub(crate) fn merge(&mut self, other: &Ckms) {
+        for &(value, g, _) in &other.samples {
+            self.insert_weighted(value, g);
+        }
+    }
+
+    fn compress(&mut self) {
+        self.since_compress = 0;
+        if self.samples.len() < 3 {
+            return;
+        }
+        let mut rank: u64 = self.samples.iter().map(|&(_, g, _)| g).sum();
+        let mut i = self.samples.len() - 2;
+        while i >= 1 {
+            rank -= self.samples[i + 1].1;
+            let (_, g, _) = self.samples[i];
+            let (_, next_g, next_delta) = self.samples[i + 1];
+            if (g + next_g + next_delta) as f64 <= self.invariant((rank - g) as f64) {
+                self.samples[i + 1].1 += g;
+                self.samples.remove(i);
+                // The merged sample is subtracted next round; keep `rank` in step
+                rank += next_g;
+            }
+            i -= 1;
+        }
+    }
+
+    pub(crate) fn query(&self, q: f64) -> Option<f64> {
+        let first = self.samples.first()?;
+        let desired = q * self.count as f64;
+        let bound = desired + self.invariant(desired) / 2.0;
+        let mut rank = 0;
+        let mut previous = first.0;
+        for &(value, g, delta) in &self.samples {
+            if (rank + g + delta) as f64 > bound {
+                return Some(previous);
+            }
+            rank += g;
+            previous = value;
+        }
+        Some(previous)
+    }
+}
+
+/// Approximate top-K by count (Metwally et al. "Space-Saving")
+#[derive(Debug, Clone)]
+pub(crate) struct SpaceSaving {
+    capacity: usize,
+    /// Item to (count, overestimate)
+    counters: HashMap<String, (u64, u64)>,
+}
+
+impl SpaceSaving {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            counters: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn insert(&mut self, item: &str, weight: u64) {
+        if let Some((count, _)) = self.counters.get_mut(item) {
+            *count += weight;
+            return;
+        }
+        if self.counters.len() < self.capacity {
+            self.counters.insert(item.to_string(), (weight, 0));
+            return;
+        }
+        // Replace the smallest counter; the newcomer inherits its count as error
+        let (evicted, min) = self
+            .counters
+            .iter()
+            .min_by_key(|(_, &(count, _))| count)
+            .map(|(item, &(count, _))| (item.clone(), count))
+            .expect("capacity is at least one");
+        self.counters.remove(&evicted);
+        self.counters.insert(item.to_string(), (min + weight, min));
+    }
+
+    pub(crate) fn merge(&mut self, other: &SpaceSaving) {
+        for (item, &(count, _)) in &other.counters {
+            self.insert(item, count);
+        }
+    }
+
+    /// Heaviest `k` items, largest first
+    pub(crate) fn top(&self, k: usize) -> Vec<(String, u64)> {
+        let mut top: Vec<(String, u64)> = self
+            .counters
+            .iter()
+            .map(|(item, &(count, _))| (item.clone(), count))
+            .collect();
+        top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top.truncate(k);
+        top
+    }
+}
+
+/// Sketches of every wave the layer has transmitted
+#[derive(Debug, Clone)]
+pub(crate) struct WaveSketches {
+    channels: SpaceSaving,
+    sources: HyperLogLog,
+    payload_bytes: Ckms,
+    waves_by_type: BTreeMap<String, u64>,
+}
+
+/// What the sketches need from one wave
+pub(crate) struct WaveSample {
+    pub(crate) channel: Channel,
+    pub(crate) source: Option<Arc<str>>,
+    pub(crate) wave_type: WaveType,
+    pub(crate) payload_bytes: usize,
+}
+
+/// Channels reported by `AetherStats::top_channels`
+pub(crate) const TOP_CHANNELS: usize = 10;
+
+impl WaveSketches {
+    pub(crate) fn new() -> Self {
+        Self {
+            channels: SpaceSaving::new(TOP_CHANNELS * 8),
+            sources: HyperLogLog::new(),
+            payload_bytes: Ckms::new(&[(0.5, 0.01), (0.99, 0.001)]),
+            waves_by_type: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn record(&mut self, sample: &WaveSample) {
+        self.channels.insert(sample.channel.name(), 1);
+        if let Some(source) = &sample.source {
+            self.sources.insert_hash(hash_of(source));
+        }
+        self.payload_bytes.insert(sample.payload_bytes as f64);
+        *self
+            .waves_by_type
+            .entry(format!("{:?}", sample.wave_type))
+            .or_insert(0) += 1;
+    }
+
+    pub(crate) fn merge(&mut self, other: &WaveSketches) {
+        self.channels.merge(&other.channels);
+        self.sources.merge(&other.sources);
+        self.payload_bytes.merge(&other.payload_bytes);
+        for (wave_type, count) in &other.waves_by_type {
+            *self.waves_by_type.entry(wave_type.clone()).or_insert(0) += count;
+        }
+    }
+
+    /// Write the sketch figures into `stats`
+    pub(crate) fn fill(&self, stats: &mut AetherStats) {
+        stats.top_channels = self.channels.top(TOP_CHANNELS);
+        stats.distinct_sources = self.sources.estimate();
+        stats.payload_bytes_p50 = self.payload_bytes.query(0.5).unwrap_or_default() as u64;
+        stats.payload_bytes_p99 = self.payload_bytes.query(0.99).unwrap_or_default() as u64;
+        stats.waves_by_type = self.waves_by_type.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sketches_stay_close_to_exact_answers() {
+        let mut distinct = HyperLogLog::new();
+        for i in 0..20_000u64 {
+            distinct.insert_hash(hash_of(i % 5_000));
+        }
+        let estimate = distinct.estimate() as f64;
+        assert!((estimate - 5_000.0).abs() < 5_000.0 * 0.05, "{}", estimate);
+
+        let mut sizes = Ckms::new(&[(0.5, 0.01), (0.99, 0.001)]);
+        for i in 0..10_000 {
+            // Shuffled 1..=10_000
+            sizes.insert(((i * 7_919) % 10_000 + 1) as f64);
+        }
+        let p50 = sizes.query(0.5).unwrap();
+        let p99 = sizes.query(0.99).unwrap();
+        assert!((p50 - 5_000.0).abs() <= 200.0, "p50 {}", p50);
+        assert!((p99 - 9_900.0).abs() <= 20.0, "p99 {}", p99);
+        assert!(
+            sizes.samples.len() < 2_000,
+            "{} samples",
+            sizes.samples.len()
+        );
+
+        let mut channels = SpaceSaving::new(8);
+        for i in 0..10_000 {
+            let channel = match i % 10 {
+                0..=4 => "orders.created".to_string(),
+                5..=7 => "payments.completed".to_string(),
+                _ => format!("noise.{}", i),
+            };
+            channels.insert(&channel, 1);
+        }
+        let top = channels.top(2);
+        assert_eq!(top[0].0, "orders.created");
+        assert_eq!(top[1].0, "payments.completed");
+        assert!(top[0].1 >= 5_000);
+    }
+}
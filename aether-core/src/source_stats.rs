@@ -0,0 +1,305 @@
+//! Per-source accounting: who is emitting how much, and how much of it is refused.
+//!
+//! Every emit is charged to the wave's source (`(anonymous)` when it has
+//! none). The table is capped at [`MAX_SOURCES`]; sources beyond that share the
+//! `(other)` row so a misbehaving client minting random source names cannot
+//! grow it without bound.
+
+use crate::labels::{LabelBudget, DEFAULT_LABEL_BUDGET};
+use crate::{aether::Aether, wave::Wave, AetherError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+/// Distinct sources tracked before the rest are folded into `(other)`
+pub const MAX_SOURCES: usize = 1024;
+/// Row for waves emitted without a source
+pub const ANONYMOUS_SOURCE: &str = "(anonymous)";
+/// Row for sources past the cap
+pub const OTHER_SOURCE: &str = "(other)";
+
+/// Sources labelled individually in `aether_source_rejections_total`
+static REJECTED_SOURCES: LabelBudget = LabelBudget::new(DEFAULT_LABEL_BUDGET);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SourceReportConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_interval_ms")]
+    pub interval_ms: u64,
+    #[serde(default = "default_channel")]
+    pub channel: String,
+    /// Sources listed per report, busiest first
+    #[serde(default = "default_top")]
+    pub top: usize,
+}
+
+impl Default for SourceReportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_ms: default_interval_ms(),
+            channel: default_channel(),
+            top: default_top(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    false
+}
+
+fn default_interval_ms() -> u64 {
+    60_000
+}
+
+fn default_channel() -> String {
+    "aether.stats.sources".to_string()
+}
+
+fn default_top() -> usize {
+    20
+}
+
+/// Emit totals for one source
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceStats {
+    pub source: String,
+    /// Waves accepted by the layer
+    pub waves: u64,
+    /// Payload bytes of the accepted waves
+    pub bytes: u64,
+    /// Emits refused by validation, authorization, rate limits or quotas
+    pub rejected: u64,
+}
+
+impl SourceStats {
+    /// Change since `earlier`, the same source's totals at an earlier time
+    fn since(&self, earlier: Option<&SourceStats>) -> SourceStats {
+        let earlier = earlier.cloned().unwrap_or_default();
+        SourceStats {
+            source: self.source.clone(),
+            waves: self.waves.saturating_sub(earlier.waves),
+            bytes: self.bytes.saturating_sub(earlier.bytes),
+            rejected: self.rejected.saturating_sub(earlier.rejected),
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        self.waves == 0 && self.rejected == 0
+    }
+}
+
+/// Running per-source totals shared by every clone of an `Aether`
+#[derive(Debug, Default)]
+pub(crate) struct SourceTable {
+    sources: Mutex<HashMap<Arc<str>, SourceStats>>,
+}
+
+impl SourceTable {
+    fn with_row<F: FnOnce(&mut SourceStats)>(&self, source: Option<&Arc<str>>, update: F) {
+        let mut sources = self.sources.lock().expect("source table lock poisoned");
+        let key: Arc<str> = match source {
+            Some(source) if sources.contains_key(source) || sources.len() < MAX_SOURCES => {
+                Arc::clone(source)
+            }
+            Some(_) => Arc::from(OTHER_SOURCE),
+            None => Arc::from(ANONYMOUS_SOURCE),
+        };
+        let row = sources.entry(key).or_insert_with_key(|key| SourceStats {
+            source: key.to_string(),
+            ..SourceStats::default()
+        });
+        update(row);
+    }
+
+    pub(crate) fn record(&self, source: Option<&Arc<str>>, payload_bytes: usize) {
+        self.with_row(source, |row| {
+            row.waves += 1;
+            row.bytes += payload_bytes as u64;
+        });
+    }
+
+    /// Count `err` against the source if it is a refusal rather than a fault
+    pub(crate) fn reject(&self, source: Option<&Arc<str>>, err: &AetherError) {
+        if !matches!(
+            err,
+            AetherError::ValidationFailed(_)
+                | AetherError::ValidatorRejected { .. }
+                | AetherError::AuthorizationFailed(_)
+                | AetherError::RateLimited(_)
+                | AetherError::QuotaExceeded(_)
+        ) {
+            return;
+        }
+        self.with_row(source, |row| row.rejected += 1);
+        metrics::counter!(
+            "aether_source_rejections_total",
+            "source" => REJECTED_SOURCES.label(source.map_or(ANONYMOUS_SOURCE, |source| source))
+        )
+        .increment(1);
+    }
+
+    /// All rows, busiest first
+    pub(crate) fn snapshot(&self) -> Vec<SourceStats> {
+        let mut rows: Vec<SourceStats> = self
+            .sources
+            .lock()
+            .expect("source table lock poisoned")
+            .values()
+            .cloned()
+            .collect();
+        rows.sort_by(|a, b| {
+            b.waves
+                .cmp(&a.waves)
+                .then_with(|| b.rejected.cmp(&a.rejected))
+                .then_with(|| a.source.cmp(&b.source))
+        });
+        rows
+    }
+}
+
+/// Activity per source between two snapshots, busiest first, idle sources dropped
+pub fn source_deltas(previous: &[SourceStats], current: &[SourceStats]) -> Vec<SourceStats> {
+    let previous: HashMap<&str, &SourceStats> = previous
+        .iter()
+        .map(|row| (row.source.as_str(), row))
+        .collect();
+    let mut deltas: Vec<SourceStats> = current
+        .iter()
+        .map(|row| row.since(previous.get(row.source.as_str()).copied()))
+        .filter(|delta| !delta.is_idle())
+        .collect();
+    deltas.sort_by(|a, b| {
+        b.waves
+            .cmp(&a.waves)
+            .then_with(|| b.rejected.cmp(&a.rejected))
+            .then_with(|| a.source.cmp(&b.source))
+    });
+    deltas
+}
+
+impl Aether {
+    /// Emit and rejection totals per source since the layer started, busiest first
+    pub fn source_stats(&self) -> Vec<SourceStats> {
+        self.source_table().snapshot()
+    }
+
+    /// Emit a summary of the last interval's busiest sources on
+    /// `config.channel`, as `reporter`; `None` when reports are disabled
+    pub fn spawn_source_reports(
+        &self,
+        reporter: &str,
+        config: &SourceReportConfig,
+    ) -> Option<JoinHandle<()>> {
+        if !config.enabled {
+            return None;
+        }
+        let aether = self.clone();
+        let reporter = reporter.to_string();
+        let config = config.clone();
+        let period = Duration::from_millis(config.interval_ms.max(1));
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            ticker.tick().await;
+            let mut previous = aether.source_stats();
+            loop {
+                ticker.tick().await;
+                let current = aether.source_stats();
+                let mut deltas = source_deltas(&previous, &current);
+                previous = current;
+                if deltas.is_empty() {
+                    continue;
+                }
+                let active_sources = deltas.len();
+                deltas.truncate(config.top);
+                let mut report = Wave::builder(config.channel.as_str())
+                    .payload(serde_json::json!({
+                        "interval_ms": config.interval_ms,
+                        "active_sources": active_sources,
+                        "sources": deltas,
+                    }))
+                    .source(reporter.as_str())
+                    .build();
+                if let Some(token) = &aether.config().auth_token {
+                    report.set_auth_token(token.clone());
+                }
+                if let Err(err) = aether.emit(report).await {
+                    warn!("Failed to emit source report: {}", err);
+                }
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aether::AetherConfig;
+    use crate::channel::Channel;
+    use futures::StreamExt;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_source_stats_count_waves_bytes_and_rejections() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            max_payload_bytes: 256,
+            ..AetherConfig::default()
+        });
+        let wave = |source: Option<&str>, payload: serde_json::Value| {
+            let builder = Wave::builder("orders.created").payload(payload);
+            match source {
+                Some(source) => builder.source(source).build(),
+                None => builder.build(),
+            }
+        };
+        for _ in 0..3 {
+            aether
+                .emit(wave(Some("checkout"), serde_json::json!({ "id": 1 })))
+                .await
+                .unwrap();
+        }
+        aether
+            .emit(wave(None, serde_json::json!({ "id": 2 })))
+            .await
+            .unwrap();
+        let oversized = serde_json::json!({ "blob": "x".repeat(300) });
+        assert!(aether
+            .emit(wave(Some("batch-import"), oversized))
+            .await
+            .is_err());
+
+        let stats = aether.source_stats();
+        assert_eq!(stats[0].source, "checkout");
+        assert_eq!(stats[0].waves, 3);
+        assert_eq!(stats[0].bytes, 3 * r#"{"id":1}"#.len() as u64);
+        let anonymous = stats.iter().find(|s| s.source == ANONYMOUS_SOURCE).unwrap();
+        assert_eq!(anonymous.waves, 1);
+        let importer = stats.iter().find(|s| s.source == "batch-import").unwrap();
+        assert_eq!((importer.waves, importer.rejected), (0, 1));
+
+        let reports = aether.tap(Channel::new("aether.stats.sources")).await;
+        let config = SourceReportConfig {
+            enabled: true,
+            interval_ms: 1_000,
+            ..SourceReportConfig::default()
+        };
+        let task = aether.spawn_source_reports("gateway", &config).unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        aether
+            .emit(wave(Some("checkout"), serde_json::json!({ "id": 3 })))
+            .await
+            .unwrap();
+
+        futures::pin_mut!(reports);
+        let report = reports.next().await.unwrap();
+        assert_eq!(report.source(), Some("gateway"));
+        assert_eq!(report.payload()["active_sources"], 1);
+        assert_eq!(report.payload()["sources"][0]["source"], "checkout");
+        assert_eq!(report.payload()["sources"][0]["waves"], 1);
+        task.abort();
+    }
+}
@@ -0,0 +1,270 @@
+//! WaveStore monitoring: size, entry counts and flush age as gauges, and a
+//! disk usage threshold that takes the service out of readiness.
+//!
+//! Every `interval_ms` the monitor publishes [`StoreStats`] and the usage of
+//! the volume holding the store. When usage crosses `disk_usage_threshold`
+//! the service reports not ready, so the load balancer stops sending it work
+//! before sled fails writes on a full disk, and an alert wave goes out on
+//! `alert_channel`; a second alert follows once usage drops back below.
+
+use crate::persistence::{StoreStats, WaveStore};
+use crate::vibrator::VibratorEmitter;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use sysinfo::Disks;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StorageMonitoringConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_interval_ms")]
+    pub interval_ms: u64,
+    /// Share of the store's volume in use (0.0 to 1.0) above which the
+    /// service reports not ready
+    #[serde(default = "default_disk_usage_threshold")]
+    pub disk_usage_threshold: f64,
+    #[serde(default = "default_alert_channel")]
+    pub alert_channel: String,
+}
+
+impl Default for StorageMonitoringConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_ms: default_interval_ms(),
+            disk_usage_threshold: default_disk_usage_threshold(),
+            alert_channel: default_alert_channel(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_ms() -> u64 {
+    10_000
+}
+
+fn default_disk_usage_threshold() -> f64 {
+    0.9
+}
+
+fn default_alert_channel() -> String {
+    "aether.alerts.storage".to_string()
+}
+
+/// Whether the store's volume is below the usage threshold; cheap to clone
+/// into a readiness check
+#[derive(Debug, Clone, Default)]
+pub struct StorageHealth {
+    over_threshold: Arc<AtomicBool>,
+}
+
+impl StorageHealth {
+    pub fn is_healthy(&self) -> bool {
+        !self.over_threshold.load(Ordering::Relaxed)
+    }
+
+    /// Record a usage sample; `Some` when it crosses the threshold either way
+    fn observe(&self, usage: f64, threshold: f64) -> Option<Crossing> {
+        let over = usage >= threshold;
+        if self.over_threshold.swap(over, Ordering::Relaxed) == over {
+            return None;
+        }
+        Some(if over {
+            Crossing::Above
+        } else {
+            Crossing::Below
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Crossing {
+    Above,
+    Below,
+}
+
+/// Space on the volume a path lives on
+#[derive(Debug, Clone, PartialEq)]
+struct DiskUsage {
+    mount_point: PathBuf,
+    total_bytes: u64,
+    available_bytes: u64,
+}
+
+impl DiskUsage {
+    fn used_bytes(&self) -> u64 {
+        self.total_bytes.saturating_sub(self.available_bytes)
+    }
+
+    fn ratio(&self) -> f64 {
+        if self.total_bytes == 0 {
+            return 0.0;
+        }
+        self.used_bytes() as f64 / self.total_bytes as f64
+    }
+}
+
+/// The volume `path` lives on: the one with the longest mount point above it
+fn containing_volume(
+    path: &Path,
+    volumes: impl IntoIterator<Item = DiskUsage>,
+) -> Option<DiskUsage> {
+    volumes
+        .into_iter()
+        .filter(|volume| path.starts_with(&volume.mount_point))
+        .max_by_key(|volume| volume.mount_point.components().count())
+}
+
+fn disk_usage(path: &Path) -> Option<DiskUsage> {
+    let path = path.canonicalize().ok()?;
+    let disks = Disks::new_with_refreshed_list();
+    containing_volume(
+        &path,
+        disks.list().iter().map(|disk| DiskUsage {
+            mount_point: disk.mount_point().to_path_buf(),
+            total_bytes: disk.total_space(),
+            available_bytes: disk.available_space(),
+        }),
+    )
+}
+
+/// Publish the store's metrics every `interval_ms` and watch its volume;
+/// `None` when disabled. Alerts go out through `emitter` when given.
+pub fn spawn_storage_monitor(
+    store: WaveStore,
+    config: StorageMonitoringConfig,
+    emitter: Option<VibratorEmitter>,
+) -> Option<(JoinHandle<()>, StorageHealth)> {
+    if !config.enabled {
+        return None;
+    }
+    let health = StorageHealth::default();
+    let watched = health.clone();
+    let task = tokio::spawn(async move {
+        let interval = Duration::from_millis(config.interval_ms);
+        loop {
+            // Size on disk and volume usage hit the filesystem; keep them off the workers
+            let sampled = store.clone();
+            let sample =
+                tokio::task::spawn_blocking(move || (sampled.stats(), disk_usage(sampled.path())))
+                    .await;
+            match sample {
+                Ok((stats, usage)) => {
+                    publish_stats(stats);
+                    if let Some(usage) = usage {
+                        check_usage(&store, &config, &watched, emitter.as_ref(), &usage).await;
+                    }
+                }
+                Err(err) => warn!("Storage monitor sample failed: {}", err),
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+    Some((task, health))
+}
+
+fn publish_stats(stats: anyhow::Result<StoreStats>) {
+    match stats {
+        Ok(stats) => stats.publish_metrics(),
+        Err(err) => warn!("Failed to read wave store stats: {}", err),
+    }
+}
+
+async fn check_usage(
+    store: &WaveStore,
+    config: &StorageMonitoringConfig,
+    health: &StorageHealth,
+    emitter: Option<&VibratorEmitter>,
+    usage: &DiskUsage,
+) {
+    let ratio = usage.ratio();
+    let label = store.path().display().to_string();
+    metrics::gauge!("aether_store_disk_usage_ratio", "store" => label.clone()).set(ratio);
+    metrics::gauge!("aether_store_disk_available_bytes", "store" => label)
+        .set(usage.available_bytes as f64);
+    let kind = match health.observe(ratio, config.disk_usage_threshold) {
+        None => return,
+        Some(Crossing::Above) => {
+            warn!(
+                "Wave store volume {} is {:.1}% full (threshold {:.1}%); reporting not ready",
+                usage.mount_point.display(),
+                ratio * 100.0,
+                config.disk_usage_threshold * 100.0
+            );
+            "disk_usage_high"
+        }
+        Some(Crossing::Below) => {
+            info!(
+                "Wave store volume {} is back to {:.1}% full",
+                usage.mount_point.display(),
+                ratio * 100.0
+            );
+            "disk_usage_recovered"
+        }
+    };
+    if let Some(emitter) = emitter {
+        let payload = serde_json::json!({
+            "kind": kind,
+            "path": store.path(),
+            "mount_point": usage.mount_point,
+            "used_bytes": usage.used_bytes(),
+            "total_bytes": usage.total_bytes,
+            "usage": ratio,
+            "threshold": config.disk_usage_threshold,
+        });
+        if let Err(err) = emitter
+            .emit_wave(config.alert_channel.as_str(), payload)
+            .await
+        {
+            warn!("Failed to emit storage alert: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn volume(mount_point: &str, total_bytes: u64, available_bytes: u64) -> DiskUsage {
+        DiskUsage {
+            mount_point: mount_point.into(),
+            total_bytes,
+            available_bytes,
+        }
+    }
+
+    #[test]
+    fn test_health_flips_once_per_crossing() {
+        let health = StorageHealth::default();
+        assert!(health.is_healthy());
+        assert_eq!(health.observe(0.5, 0.9), None);
+        assert_eq!(health.observe(0.92, 0.9), Some(Crossing::Above));
+        assert!(!health.is_healthy());
+        assert_eq!(health.observe(0.95, 0.9), None);
+        assert_eq!(health.observe(0.7, 0.9), Some(Crossing::Below));
+        assert!(health.is_healthy());
+    }
+
+    #[test]
+    fn test_store_volume_is_the_deepest_mount_above_it() {
+        let volumes = vec![
+            volume("/", 100, 80),
+            volume("/var/lib/aether", 1000, 50),
+            volume("/var", 500, 400),
+        ];
+        let found = containing_volume(Path::new("/var/lib/aether/waves"), volumes.clone()).unwrap();
+        assert_eq!(found.mount_point, PathBuf::from("/var/lib/aether"));
+        assert!((found.ratio() - 0.95).abs() < 1e-9);
+        let found = containing_volume(Path::new("/srv/waves"), volumes).unwrap();
+        assert_eq!(found.mount_point, PathBuf::from("/"));
+        assert_eq!(volume("/", 0, 0).ratio(), 0.0);
+    }
+}
@@ -1,72 +1,604 @@
-//! Task management with backpressure controls.
+//! Task management with backpressure controls, priority lanes and rate limits.
 
+use crate::channel::Channel;
+use crate::handler_metrics::{observe_handler, HandlerOutcome};
+use crate::rate_limit::{BucketMap, TokenBucket};
+use crate::wave::{Wave, WaveType};
+use crate::{AetherError, Result};
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::{Mutex, Semaphore};
-use tokio::task::JoinSet;
-use tokio::time::Interval;
+use tokio::sync::{Notify, Semaphore};
+use tokio::task::{JoinHandle, JoinSet};
+use tokio::time::Instant;
 use tracing::warn;
 
-#[derive(Debug)]
-struct RateLimiter {
-    interval: Mutex<Interval>,
+/// Events and broadcasts below this amplitude go to the low lane
+const LOW_PRIORITY_AMPLITUDE: f64 = 0.5;
+
+/// Queued tasks allowed per in-flight slot before `spawn` waits
+const BACKLOG_PER_SLOT: usize = 4;
+
+type Task = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+struct Queued {
+    task: Task,
+    priority: Priority,
+    /// Channel whose rate limit applies, if any
+    channel: Option<Channel>,
+    queued_at: Instant,
+}
+
+/// Task start rate for channels matching a pattern
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChannelRateLimit {
+    /// Channel pattern (e.g. "metrics.>"); each matching channel gets its own bucket
+    pub channel: String,
+    pub per_sec: f64,
+    /// Tasks that may start back to back (defaults to one second's worth)
+    #[serde(default)]
+    pub burst: Option<f64>,
+}
+
+/// Token buckets limiting how fast a `TaskManager` starts tasks
+///
+/// The optional global bucket covers every task; channel buckets only the
+/// tasks spawned for a matching channel. A task whose channel is out of
+/// tokens waits aside until its turn comes, so it does not hold up other
+/// channels.
+#[derive(Debug, Default)]
+pub struct TaskRateLimiter {
+    global: Option<std::sync::Mutex<TokenBucket>>,
+    channels: Vec<(Channel, ChannelRateLimit)>,
+    buckets: std::sync::Mutex<BucketMap>,
 }
 
-impl RateLimiter {
-    fn new(rate_per_sec: f64) -> Self {
-        let interval = tokio::time::interval(Duration::from_secs_f64(1.0 / rate_per_sec));
+impl TaskRateLimiter {
+    /// `per_sec` tasks per second overall, with `burst` (one second's worth by default)
+    pub fn new(per_sec: f64, burst: Option<f64>) -> Self {
         Self {
-            interval: Mutex::new(interval),
+            global: Some(std::sync::Mutex::new(TokenBucket::new(
+                per_sec,
+                burst.unwrap_or(per_sec),
+            ))),
+            ..Self::default()
+        }
+    }
+
+    /// Only channel limits, no overall rate
+    pub fn per_channel() -> Self {
+        Self::default()
+    }
+
+    pub fn with_channel(mut self, limit: ChannelRateLimit) -> Self {
+        self.channels
+            .push((Channel::new(limit.channel.as_str()), limit));
+        self
+    }
+
+    /// `None` when neither an overall rate nor channel limits are set
+    pub fn from_config(
+        per_sec: Option<f64>,
+        burst: Option<f64>,
+        channels: &[ChannelRateLimit],
+    ) -> Option<Self> {
+        let limiter = match per_sec.filter(|rate| *rate > 0.0) {
+            Some(rate) => Self::new(rate, burst),
+            None if channels.is_empty() => return None,
+            None => Self::per_channel(),
+        };
+        Some(channels.iter().cloned().fold(limiter, Self::with_channel))
+    }
+
+    /// Take a token for a task on `channel` if one is free right now
+    pub fn try_acquire(&self, channel: Option<&Channel>) -> bool {
+        let limit = channel.and_then(|channel| self.limit_for(channel));
+        if let (Some(channel), Some(limit)) = (channel, limit) {
+            if self
+                .with_bucket(channel, limit, |bucket| bucket.reserve(1.0, Duration::ZERO))
+                .is_err()
+            {
+                throttled("channel", &limit.channel, None);
+                return false;
+            }
+        }
+        let Some(global) = &self.global else {
+            return true;
+        };
+        let taken = global
+            .lock()
+            .expect("rate limiter lock poisoned")
+            .reserve(1.0, Duration::ZERO)
+            .is_ok();
+        if !taken {
+            if let (Some(channel), Some(limit)) = (channel, limit) {
+                self.with_bucket(channel, limit, |bucket| bucket.refund(1.0));
+            }
+            throttled("global", "", None);
+        }
+        taken
+    }
+
+    fn limit_for(&self, channel: &Channel) -> Option<&ChannelRateLimit> {
+        self.channels
+            .iter()
+            .find(|(pattern, _)| channel.matches(pattern))
+            .map(|(_, limit)| limit)
+    }
+
+    fn with_bucket<T>(
+        &self,
+        channel: &Channel,
+        limit: &ChannelRateLimit,
+        f: impl FnOnce(&mut TokenBucket) -> T,
+    ) -> T {
+        let mut buckets = self.buckets.lock().expect("rate limiter lock poisoned");
+        f(buckets.bucket(channel.name(), || {
+            TokenBucket::new(limit.per_sec, limit.burst.unwrap_or(limit.per_sec))
+        }))
+    }
+
+    /// Reserve a channel token, returning how long the task must wait for it
+    fn channel_wait(&self, channel: &Channel) -> Duration {
+        let Some(limit) = self.limit_for(channel) else {
+            return Duration::ZERO;
+        };
+        let wait = self
+            .with_bucket(channel, limit, |bucket| bucket.reserve(1.0, Duration::MAX))
+            .unwrap_or(Duration::MAX);
+        if !wait.is_zero() {
+            throttled("channel", &limit.channel, Some(wait));
+        }
+        wait
+    }
+
+    /// Reserve a global token, returning how long the dispatcher must wait for it
+    fn global_wait(&self) -> Duration {
+        let Some(global) = &self.global else {
+            return Duration::ZERO;
+        };
+        let wait = global
+            .lock()
+            .expect("rate limiter lock poisoned")
+            .reserve(1.0, Duration::MAX)
+            .unwrap_or(Duration::MAX);
+        if !wait.is_zero() {
+            throttled("global", "", Some(wait));
+        }
+        wait
+    }
+}
+
+fn throttled(scope: &'static str, pattern: &str, wait: Option<Duration>) {
+    metrics::counter!("aether_tasks_throttled_total", "scope" => scope, "channel" => pattern.to_string())
+        .increment(1);
+    if let Some(wait) = wait {
+        metrics::histogram!("aether_task_throttle_wait_seconds", "scope" => scope)
+            .record(wait.as_secs_f64());
+    }
+}
+
+/// Processing lane for a task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    High,
+    Normal,
+    Low,
+}
+
+impl Priority {
+    /// Commands and responses first, faint events and broadcasts last
+    pub fn for_wave(wave: &Wave) -> Self {
+        match wave.wave_type() {
+            WaveType::Command | WaveType::Response => Priority::High,
+            _ if wave.amplitude().value() < LOW_PRIORITY_AMPLITUDE => Priority::Low,
+            _ => Priority::Normal,
         }
     }
 
-    async fn acquire(&self) {
-        let mut interval = self.interval.lock().await;
-        interval.tick().await;
+    fn lane(self) -> usize {
+        self as usize
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Priority::High => "high",
+            Priority::Normal => "normal",
+            Priority::Low => "low",
+        }
+    }
+}
+
+/// Dispatch slots each lane gets per round while lanes are contended
+///
+/// Every lane with a non-zero weight is served each round, so bulk work
+/// slows down under overload but never starves.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct PriorityWeights {
+    #[serde(default = "default_high_weight")]
+    pub high: u32,
+    #[serde(default = "default_normal_weight")]
+    pub normal: u32,
+    #[serde(default = "default_low_weight")]
+    pub low: u32,
+}
+
+impl Default for PriorityWeights {
+    fn default() -> Self {
+        Self {
+            high: default_high_weight(),
+            normal: default_normal_weight(),
+            low: default_low_weight(),
+        }
     }
 }
 
-#[derive(Debug)]
+fn default_high_weight() -> u32 {
+    8
+}
+
+fn default_normal_weight() -> u32 {
+    4
+}
+
+fn default_low_weight() -> u32 {
+    1
+}
+
+struct Lanes {
+    queues: [VecDeque<Queued>; 3],
+    weights: [u32; 3],
+    credits: [u32; 3],
+    /// Tasks waiting for their channel's rate limit, with when they may start
+    throttled: Vec<(Instant, Queued)>,
+}
+
+impl Lanes {
+    fn new(weights: PriorityWeights) -> Self {
+        let weights = [weights.high, weights.normal, weights.low].map(|w| w.max(1));
+        Self {
+            queues: Default::default(),
+            weights,
+            credits: weights,
+            throttled: Vec::new(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.queued() + self.throttled.len()
+    }
+
+    fn queued(&self) -> usize {
+        self.queues.iter().map(VecDeque::len).sum()
+    }
+
+    /// Next task allowed to start, or when a throttled one will be
+    ///
+    /// Throttled tasks whose time has come go first. A task popped from a lane
+    /// whose channel is out of tokens is set aside with its reservation.
+    fn next_ready(
+        &mut self,
+        now: Instant,
+        limiter: Option<&TaskRateLimiter>,
+    ) -> std::result::Result<Queued, Option<Instant>> {
+        // Removed in place, so throttled tasks keep their arrival order
+        if let Some(ready) = self.throttled.iter().position(|(at, _)| *at <= now) {
+            return Ok(self.throttled.remove(ready).1);
+        }
+        while let Some(next) = self.pop() {
+            let wait = match (limiter, &next.channel) {
+                (Some(limiter), Some(channel)) => limiter.channel_wait(channel),
+                _ => Duration::ZERO,
+            };
+            if wait.is_zero() {
+                return Ok(next);
+            }
+            self.throttled.push((now + wait, next));
+        }
+        Err(self.throttled.iter().map(|(at, _)| *at).min())
+    }
+
+    /// Weighted round robin, highest lane first within a round
+    fn pop(&mut self) -> Option<Queued> {
+        if self.queued() == 0 {
+            return None;
+        }
+        loop {
+            for lane in 0..self.queues.len() {
+                if self.credits[lane] > 0 && !self.queues[lane].is_empty() {
+                    self.credits[lane] -= 1;
+                    return self.queues[lane].pop_front();
+                }
+            }
+            // Every waiting lane spent its share; start a new round
+            self.credits = self.weights;
+        }
+    }
+}
+
+struct Shared {
+    lanes: std::sync::Mutex<Lanes>,
+    queued: Notify,
+    space: Notify,
+    join_set: std::sync::Mutex<JoinSet<()>>,
+    inflight: Arc<AtomicUsize>,
+    spawned: AtomicU64,
+    /// Tasks done running, panicked or not; `spawned - finished` are pending
+    finished: AtomicU64,
+    finished_notify: Notify,
+    rejected: AtomicU64,
+    spawn_wait_nanos: AtomicU64,
+    permit_wait_nanos: AtomicU64,
+}
+
+impl Shared {
+    /// Update the queue depth gauge; call with the lanes locked
+    fn record_depth(&self, lanes: &Lanes) {
+        metrics::gauge!("aether_tasks_queued").set(lanes.len() as f64);
+    }
+}
+
+/// Snapshot of a `TaskManager`'s load
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TaskStats {
+    pub inflight: usize,
+    pub max_inflight: usize,
+    /// Tasks waiting for a slot, including those held back by a channel rate limit
+    pub queued: usize,
+    /// Of `queued`, tasks waiting for their channel's rate limit
+    pub throttled: usize,
+    pub backlog_limit: usize,
+    pub spawned: u64,
+    /// Spawns given up after the spawn timeout
+    pub rejected: u64,
+    /// Total time spawns waited for room in the backlog
+    pub spawn_wait: Duration,
+    /// Total time queued tasks waited for an inflight slot
+    pub permit_wait: Duration,
+}
+
 pub struct TaskManager {
-    semaphore: Arc<Semaphore>,
-    join_set: JoinSet<()>,
-    rate_limiter: Option<RateLimiter>,
+    shared: Arc<Shared>,
+    max_inflight: usize,
+    backlog_limit: usize,
+    spawn_timeout: Option<Duration>,
+    rate_limiter: Option<Arc<TaskRateLimiter>>,
+    dispatcher: JoinHandle<()>,
+}
+
+impl std::fmt::Debug for TaskManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TaskManager")
+            .field("backlog_limit", &self.backlog_limit)
+            .finish_non_exhaustive()
+    }
 }
 
 impl TaskManager {
     pub fn new(max_inflight: usize, rate_limit_per_sec: Option<f64>) -> Self {
+        Self::with_weights(max_inflight, rate_limit_per_sec, PriorityWeights::default())
+    }
+
+    /// Create with custom lane weights (must be called inside a Tokio runtime)
+    pub fn with_weights(
+        max_inflight: usize,
+        rate_limit_per_sec: Option<f64>,
+        weights: PriorityWeights,
+    ) -> Self {
+        let rate_limiter = TaskRateLimiter::from_config(rate_limit_per_sec, None, &[]);
+        Self::with_rate_limiter(max_inflight, weights, rate_limiter)
+    }
+
+    /// Create with lane weights and token-bucket rate limits (must be called
+    /// inside a Tokio runtime)
+    pub fn with_rate_limiter(
+        max_inflight: usize,
+        weights: PriorityWeights,
+        rate_limiter: Option<TaskRateLimiter>,
+    ) -> Self {
         let max_inflight = max_inflight.max(1);
-        let rate_limiter = rate_limit_per_sec.filter(|v| *v > 0.0).map(RateLimiter::new);
+        let rate_limiter = rate_limiter.map(Arc::new);
+        let shared = Arc::new(Shared {
+            lanes: std::sync::Mutex::new(Lanes::new(weights)),
+            queued: Notify::new(),
+            space: Notify::new(),
+            join_set: std::sync::Mutex::new(JoinSet::new()),
+            inflight: Arc::new(AtomicUsize::new(0)),
+            spawned: AtomicU64::new(0),
+            finished: AtomicU64::new(0),
+            finished_notify: Notify::new(),
+            rejected: AtomicU64::new(0),
+            spawn_wait_nanos: AtomicU64::new(0),
+            permit_wait_nanos: AtomicU64::new(0),
+        });
+        let semaphore = Arc::new(Semaphore::new(max_inflight));
+        let dispatcher = tokio::spawn(dispatch(
+            Arc::clone(&shared),
+            semaphore,
+            rate_limiter.clone(),
+        ));
 
         Self {
-            semaphore: Arc::new(Semaphore::new(max_inflight)),
-            join_set: JoinSet::new(),
+            shared,
+            max_inflight,
+            backlog_limit: max_inflight * BACKLOG_PER_SLOT,
+            spawn_timeout: None,
             rate_limiter,
+            dispatcher,
         }
     }
 
-    pub async fn spawn<F>(&mut self, fut: F)
+    /// Reject spawns that wait longer than `timeout` for room in the backlog
+    pub fn with_spawn_timeout(mut self, timeout: Duration) -> Self {
+        self.spawn_timeout = Some(timeout);
+        self
+    }
+
+    /// The limiter tasks are started under, e.g. to `try_acquire` before producing work
+    pub fn rate_limiter(&self) -> Option<&TaskRateLimiter> {
+        self.rate_limiter.as_deref()
+    }
+
+    /// Queue a task on a priority lane, waiting while the backlog is full
+    ///
+    /// Fails with `AetherError::Saturated` if a spawn timeout is set and passes first.
+    pub async fn spawn<F>(&mut self, priority: Priority, fut: F) -> Result<()>
     where
-        F: std::future::Future<Output = ()> + Send + 'static,
+        F: Future<Output = ()> + Send + 'static,
     {
-        if let Some(rate_limiter) = &self.rate_limiter {
-            rate_limiter.acquire().await;
+        self.enqueue(priority, None, fut).await
+    }
+
+    /// Like `spawn`, also subject to the rate limit for `channel`
+    pub async fn spawn_for<F>(
+        &mut self,
+        priority: Priority,
+        channel: &Channel,
+        fut: F,
+    ) -> Result<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.enqueue(priority, Some(channel.clone()), fut).await
+    }
+
+    async fn enqueue<F>(
+        &mut self,
+        priority: Priority,
+        channel: Option<Channel>,
+        fut: F,
+    ) -> Result<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let started = Instant::now();
+        let deadline = self.spawn_timeout.map(|timeout| started + timeout);
+        let mut task = Some(Queued {
+            task: Box::pin(fut),
+            priority,
+            channel,
+            queued_at: started,
+        });
+        loop {
+            let space = self.shared.space.notified();
+            {
+                let mut lanes = self.shared.lanes.lock().expect("task lanes poisoned");
+                if lanes.len() < self.backlog_limit {
+                    if let Some(task) = task.take() {
+                        lanes.queues[priority.lane()].push_back(task);
+                    }
+                    self.shared.record_depth(&lanes);
+                    drop(lanes);
+                    self.record_spawn_wait(started.elapsed());
+                    self.shared.spawned.fetch_add(1, Ordering::Relaxed);
+                    self.shared.queued.notify_one();
+                    return Ok(());
+                }
+            }
+            match deadline {
+                Some(deadline) => {
+                    if tokio::time::timeout_at(deadline, space).await.is_err() {
+                        self.record_spawn_wait(started.elapsed());
+                        self.shared.rejected.fetch_add(1, Ordering::Relaxed);
+                        metrics::counter!("aether_tasks_rejected_total", "priority" => priority.as_str())
+                            .increment(1);
+                        return Err(AetherError::Saturated(format!(
+                            "task backlog full for {:?}",
+                            started.elapsed()
+                        )));
+                    }
+                }
+                None => space.await,
+            }
         }
+    }
 
-        let permit = match self.semaphore.clone().acquire_owned().await {
-            Ok(permit) => permit,
-            Err(_) => return,
+    fn record_spawn_wait(&self, waited: Duration) {
+        metrics::histogram!("aether_task_spawn_wait_seconds").record(waited.as_secs_f64());
+        self.shared
+            .spawn_wait_nanos
+            .fetch_add(waited.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Queue a wave handler, timed under `aether_handler_duration_seconds`
+    pub async fn spawn_handler<F>(
+        &mut self,
+        priority: Priority,
+        channel: &str,
+        handler: F,
+    ) -> Result<()>
+    where
+        F: Future + Send + 'static,
+        F::Output: HandlerOutcome,
+    {
+        let channel = Channel::new(channel);
+        let label = channel.name().to_string();
+        self.spawn_for(priority, &channel, async move {
+            observe_handler(&label, handler).await;
+        })
+        .await
+    }
+
+    /// Number of tasks running
+    pub fn inflight(&self) -> usize {
+        self.shared.inflight.load(Ordering::Relaxed)
+    }
+
+    /// Live inflight count, e.g. for a `LoadShedder`
+    pub fn inflight_counter(&self) -> Arc<AtomicUsize> {
+        Arc::clone(&self.shared.inflight)
+    }
+
+    /// Number of tasks waiting for a slot
+    pub fn queued(&self) -> usize {
+        self.shared.lanes.lock().expect("task lanes poisoned").len()
+    }
+
+    pub fn stats(&self) -> TaskStats {
+        let (queued, throttled) = {
+            let lanes = self.shared.lanes.lock().expect("task lanes poisoned");
+            (lanes.len(), lanes.throttled.len())
         };
+        let nanos = |counter: &AtomicU64| Duration::from_nanos(counter.load(Ordering::Relaxed));
+        TaskStats {
+            inflight: self.inflight(),
+            max_inflight: self.max_inflight,
+            queued,
+            throttled,
+            backlog_limit: self.backlog_limit,
+            spawned: self.shared.spawned.load(Ordering::Relaxed),
+            rejected: self.shared.rejected.load(Ordering::Relaxed),
+            spawn_wait: nanos(&self.shared.spawn_wait_nanos),
+            permit_wait: nanos(&self.shared.permit_wait_nanos),
+        }
+    }
 
-        self.join_set.spawn(async move {
-            let _permit = permit;
-            fut.await;
-        });
+    /// Wait for every queued and running task to finish, at most `timeout`
+    ///
+    /// Returns false if some were still pending when the timeout passed.
+    pub async fn wait_idle(&self, timeout: Duration) -> bool {
+        tokio::time::timeout(timeout, async {
+            loop {
+                let finished = self.shared.finished_notify.notified();
+                let spawned = self.shared.spawned.load(Ordering::Relaxed);
+                if self.shared.finished.load(Ordering::Relaxed) >= spawned {
+                    return;
+                }
+                finished.await;
+            }
+        })
+        .await
+        .is_ok()
     }
 
     pub async fn reap(&mut self) {
+        let mut join_set = self.shared.join_set.lock().expect("task set poisoned");
         loop {
-            match self.join_set.try_join_next() {
+            match join_set.try_join_next() {
                 Some(Err(err)) => {
                     warn!("Task failed: {}", err);
                 }
@@ -76,3 +608,312 @@ impl TaskManager {
         }
     }
 }
+
+impl Drop for TaskManager {
+    fn drop(&mut self) {
+        self.dispatcher.abort();
+    }
+}
+
+async fn dispatch(
+    shared: Arc<Shared>,
+    semaphore: Arc<Semaphore>,
+    rate_limiter: Option<Arc<TaskRateLimiter>>,
+) {
+    loop {
+        let waiting = Instant::now();
+        let permit = match semaphore.clone().acquire_owned().await {
+            Ok(permit) => permit,
+            Err(_) => return,
+        };
+        let permit_wait = waiting.elapsed();
+
+        let next = loop {
+            let queued = shared.queued.notified();
+            let wake_at = {
+                let mut lanes = shared.lanes.lock().expect("task lanes poisoned");
+                match lanes.next_ready(Instant::now(), rate_limiter.as_deref()) {
+                    Ok(next) => {
+                        shared.record_depth(&lanes);
+                        break next;
+                    }
+                    Err(wake_at) => wake_at,
+                }
+            };
+            match wake_at {
+                Some(at) => {
+                    tokio::select! {
+                        _ = queued => {}
+                        _ = tokio::time::sleep_until(at) => {}
+                    }
+                }
+                None => queued.await,
+            }
+        };
+        shared.space.notify_one();
+
+        if let Some(rate_limiter) = &rate_limiter {
+            let wait = rate_limiter.global_wait();
+            if !wait.is_zero() {
+                tokio::time::sleep(wait).await;
+            }
+        }
+
+        let priority = next.priority.as_str();
+        metrics::histogram!("aether_task_queue_wait_seconds", "priority" => priority)
+            .record(next.queued_at.elapsed().as_secs_f64());
+        // Only the part of the slot wait this task was queued for
+        let permit_wait = permit_wait.min(next.queued_at.elapsed());
+        metrics::histogram!("aether_task_permit_wait_seconds", "priority" => priority)
+            .record(permit_wait.as_secs_f64());
+        shared
+            .permit_wait_nanos
+            .fetch_add(permit_wait.as_nanos() as u64, Ordering::Relaxed);
+        let inflight = InflightGuard::new(&shared);
+        shared
+            .join_set
+            .lock()
+            .expect("task set poisoned")
+            .spawn(async move {
+                let _permit = permit;
+                let _inflight = inflight;
+                next.task.await;
+            });
+    }
+}
+
+/// Counts a running task; decrements even if the task panics
+struct InflightGuard {
+    shared: Arc<Shared>,
+    gauge: metrics::Gauge,
+}
+
+impl InflightGuard {
+    fn new(shared: &Arc<Shared>) -> Self {
+        let gauge = metrics::gauge!("aether_tasks_inflight");
+        shared.inflight.fetch_add(1, Ordering::Relaxed);
+        gauge.increment(1.0);
+        Self {
+            shared: Arc::clone(shared),
+            gauge,
+        }
+    }
+}
+
+impl Drop for InflightGuard {
+    fn drop(&mut self) {
+        self.shared.inflight.fetch_sub(1, Ordering::Relaxed);
+        self.gauge.decrement(1.0);
+        self.shared.finished.fetch_add(1, Ordering::Relaxed);
+        self.shared.finished_notify.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::oneshot;
+
+    type Log = Arc<std::sync::Mutex<Vec<&'static str>>>;
+
+    /// Occupy the only slot until the returned sender fires
+    async fn block_slot(manager: &mut TaskManager) -> oneshot::Sender<()> {
+        let (release_tx, release_rx) = oneshot::channel::<()>();
+        let (started_tx, started_rx) = oneshot::channel();
+        manager
+            .spawn(Priority::High, async move {
+                let _ = started_tx.send(());
+                let _ = release_rx.await;
+            })
+            .await
+            .unwrap();
+        started_rx.await.unwrap();
+        release_tx
+    }
+
+    async fn queue(manager: &mut TaskManager, log: &Log, priority: Priority, name: &'static str) {
+        let log = Arc::clone(log);
+        manager
+            .spawn(priority, async move {
+                log.lock().unwrap().push(name);
+            })
+            .await
+            .unwrap();
+    }
+
+    async fn wait_for(log: &Log, count: usize) -> Vec<&'static str> {
+        while log.lock().unwrap().len() < count {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        log.lock().unwrap().clone()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_high_priority_runs_first_under_load() {
+        let mut manager = TaskManager::new(1, None);
+        let log = Log::default();
+        let release = block_slot(&mut manager).await;
+
+        queue(&mut manager, &log, Priority::Low, "low").await;
+        queue(&mut manager, &log, Priority::Normal, "normal").await;
+        queue(&mut manager, &log, Priority::High, "high").await;
+        assert_eq!(manager.queued(), 3);
+
+        release.send(()).unwrap();
+        assert_eq!(wait_for(&log, 3).await, vec!["high", "normal", "low"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_low_lane_is_not_starved() {
+        let weights = PriorityWeights {
+            high: 2,
+            normal: 1,
+            low: 1,
+        };
+        let mut manager = TaskManager::with_weights(1, None, weights);
+        let log = Log::default();
+        let release = block_slot(&mut manager).await;
+
+        queue(&mut manager, &log, Priority::High, "high").await;
+        queue(&mut manager, &log, Priority::High, "high").await;
+        queue(&mut manager, &log, Priority::High, "high").await;
+        queue(&mut manager, &log, Priority::Low, "low").await;
+
+        release.send(()).unwrap();
+        // The blocker used one high credit, so low gets its turn after one more high
+        assert_eq!(wait_for(&log, 4).await, vec!["high", "low", "high", "high"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limit_allows_burst_then_paces() {
+        let limiter = TaskRateLimiter::new(10.0, Some(3.0));
+        let mut manager =
+            TaskManager::with_rate_limiter(8, PriorityWeights::default(), Some(limiter));
+        let started = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let begin = Instant::now();
+        for _ in 0..5 {
+            let started = Arc::clone(&started);
+            manager
+                .spawn(Priority::Normal, async move {
+                    started.lock().unwrap().push(begin.elapsed().as_millis());
+                })
+                .await
+                .unwrap();
+        }
+        while started.lock().unwrap().len() < 5 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        assert_eq!(*started.lock().unwrap(), [0, 0, 0, 100, 200]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_throttled_channel_does_not_block_others() {
+        let limiter = TaskRateLimiter::per_channel().with_channel(ChannelRateLimit {
+            channel: "metrics.>".into(),
+            per_sec: 1.0,
+            burst: None,
+        });
+        let mut manager =
+            TaskManager::with_rate_limiter(1, PriorityWeights::default(), Some(limiter));
+        let log = Log::default();
+        for (channel, name) in [
+            ("metrics.cpu", "cpu-1"),
+            ("metrics.cpu", "cpu-2"),
+            ("orders.created", "order"),
+        ] {
+            let log = Arc::clone(&log);
+            manager
+                .spawn_for(Priority::Normal, &Channel::new(channel), async move {
+                    log.lock().unwrap().push(name);
+                })
+                .await
+                .unwrap();
+        }
+        assert_eq!(wait_for(&log, 2).await, vec!["cpu-1", "order"]);
+        assert_eq!(wait_for(&log, 3).await, vec!["cpu-1", "order", "cpu-2"]);
+
+        let limiter = manager.rate_limiter().unwrap();
+        assert!(!limiter.try_acquire(Some(&Channel::new("metrics.cpu"))));
+        assert!(limiter.try_acquire(Some(&Channel::new("metrics.disk"))));
+        assert!(limiter.try_acquire(Some(&Channel::new("orders.created"))));
+        assert!(limiter.try_acquire(None));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_throttled_tasks_start_in_arrival_order() {
+        let mut lanes = Lanes::new(PriorityWeights::default());
+        let now = Instant::now();
+        for channel in ["metrics.1", "metrics.2", "metrics.3"] {
+            let queued = Queued {
+                task: Box::pin(async {}),
+                priority: Priority::Normal,
+                channel: Some(Channel::new(channel)),
+                queued_at: now,
+            };
+            lanes.throttled.push((now, queued));
+        }
+        let order: Vec<String> = (0..3)
+            .map(|_| {
+                let next = lanes.next_ready(now, None).ok().unwrap();
+                next.channel.unwrap().name().to_string()
+            })
+            .collect();
+        assert_eq!(order, ["metrics.1", "metrics.2", "metrics.3"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stats_and_spawn_timeout_when_saturated() {
+        let mut manager = TaskManager::new(1, None).with_spawn_timeout(Duration::from_millis(50));
+        let log = Log::default();
+        let release = block_slot(&mut manager).await;
+        for _ in 0..BACKLOG_PER_SLOT {
+            queue(&mut manager, &log, Priority::Normal, "queued").await;
+        }
+
+        let rejected = manager.spawn(Priority::High, async {}).await;
+        assert!(matches!(rejected, Err(AetherError::Saturated(_))));
+        let stats = manager.stats();
+        assert_eq!(stats.inflight, 1);
+        assert_eq!(stats.max_inflight, 1);
+        assert_eq!(stats.queued, BACKLOG_PER_SLOT);
+        assert_eq!(stats.spawned, 1 + BACKLOG_PER_SLOT as u64);
+        assert_eq!(stats.rejected, 1);
+        assert_eq!(stats.spawn_wait, Duration::from_millis(50));
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        release.send(()).unwrap();
+        wait_for(&log, BACKLOG_PER_SLOT).await;
+        let stats = manager.stats();
+        assert_eq!((stats.inflight, stats.queued), (0, 0));
+        // The first queued task waited 80ms for the blocker's slot
+        assert!(stats.permit_wait >= Duration::from_millis(80));
+    }
+
+    #[tokio::test]
+    async fn test_wait_idle_waits_for_queued_and_running_tasks() {
+        let mut manager = TaskManager::new(1, None);
+        let log = Log::default();
+        let release = block_slot(&mut manager).await;
+        queue(&mut manager, &log, Priority::Normal, "queued").await;
+
+        assert!(!manager.wait_idle(Duration::from_millis(20)).await);
+        release.send(()).unwrap();
+        assert!(manager.wait_idle(Duration::from_secs(1)).await);
+        assert_eq!(*log.lock().unwrap(), vec!["queued"]);
+        assert_eq!(manager.inflight(), 0);
+    }
+
+    #[test]
+    fn test_priority_for_wave() {
+        let command = Wave::builder("orders.cancel")
+            .wave_type(WaveType::Command)
+            .build();
+        let faint = Wave::builder("metrics.cpu").amplitude(0.1).build();
+        assert_eq!(Priority::for_wave(&command), Priority::High);
+        assert_eq!(Priority::for_wave(&faint), Priority::Low);
+        assert_eq!(
+            Priority::for_wave(&Wave::new("orders.created", serde_json::json!({}))),
+            Priority::Normal
+        );
+    }
+}
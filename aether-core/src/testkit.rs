@@ -0,0 +1,214 @@
+//! Testkit: deterministic in-memory harness for service tests.
+//!
+//! Enable with the `testkit` feature. Pair with `#[tokio::test(start_paused = true)]`
+//! to run on virtual time, so `advance()` skips timeouts and backoffs instantly
+//! and moves the layer's `VirtualClock` by the same amount.
+
+use crate::{
+    aether::{Aether, AetherConfig},
+    channel::Channel,
+    clock::VirtualClock,
+    vibrator::{Vibrator, VibratorConfig},
+    wave::Wave,
+    Result,
+};
+use futures::StreamExt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// Upper bound on how long `drain()` waits for receivers to catch up
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// In-memory Aether layer that captures every emitted wave
+pub struct TestAether {
+    aether: Aether,
+    clock: VirtualClock,
+    captured: Arc<Mutex<Vec<Wave>>>,
+    capture_task: JoinHandle<()>,
+}
+
+impl TestAether {
+    pub async fn new() -> Self {
+        Self::with_config(AetherConfig::default()).await
+    }
+
+    /// Build from a config; NATS and persistence are always disabled
+    pub async fn with_config(config: AetherConfig) -> Self {
+        let clock = VirtualClock::new();
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            persistence_enabled: false,
+            ..config
+        })
+        .with_clock(Arc::new(clock.clone()));
+
+        let captured = Arc::new(Mutex::new(Vec::new()));
+        let mut stream = Box::pin(aether.tap(Channel::new(">")).await);
+        let sink = Arc::clone(&captured);
+        let capture_task = tokio::spawn(async move {
+            while let Some(wave) = stream.next().await {
+                sink.lock().expect("capture lock poisoned").push(wave);
+            }
+        });
+
+        Self {
+            aether,
+            clock,
+            captured,
+            capture_task,
+        }
+    }
+
+    pub fn aether(&self) -> &Aether {
+        &self.aether
+    }
+
+    /// Clock driving wave timestamps, hopping, and snapshots
+    pub fn clock(&self) -> &VirtualClock {
+        &self.clock
+    }
+
+    /// Create a vibrator attached to this layer
+    pub async fn vibrator(&self, config: VibratorConfig) -> Result<Vibrator> {
+        Vibrator::new(config, &self.aether).await
+    }
+
+    /// Wait until every queued wave has been read by all receivers
+    ///
+    /// Panics if deliveries are still pending after a generous timeout,
+    /// which usually means a receiver is never polled.
+    pub async fn drain(&self) {
+        let deadline = tokio::time::Instant::now() + DRAIN_TIMEOUT;
+        loop {
+            let pending = self.aether.pending_deliveries().await;
+            if pending.is_empty() {
+                return;
+            }
+            if tokio::time::Instant::now() >= deadline {
+                panic!("waves still pending after drain: {:?}", pending);
+            }
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+    }
+
+    /// Advance virtual time (requires a paused clock)
+    pub async fn advance(&self, duration: Duration) {
+        self.clock.advance(duration);
+        tokio::time::advance(duration).await;
+    }
+
+    /// All waves emitted so far, in emission order
+    pub async fn emitted(&self) -> Vec<Wave> {
+        self.drain().await;
+        self.captured.lock().expect("capture lock poisoned").clone()
+    }
+
+    /// Waves emitted on channels matching a pattern
+    pub async fn emitted_on(&self, pattern: impl Into<Channel>) -> Vec<Wave> {
+        let pattern = pattern.into();
+        self.emitted()
+            .await
+            .into_iter()
+            .filter(|wave| wave.channel().matches(&pattern))
+            .collect()
+    }
+
+    /// Return the first wave emitted on a matching channel, or panic
+    pub async fn expect_wave_on(&self, pattern: impl Into<Channel>) -> Wave {
+        let pattern = pattern.into();
+        let emitted = self.emitted().await;
+        match emitted.iter().find(|w| w.channel().matches(&pattern)) {
+            Some(wave) => wave.clone(),
+            None => panic!(
+                "expected a wave on {}, emitted: {:?}",
+                pattern,
+                emitted
+                    .iter()
+                    .map(|w| w.channel().name().to_string())
+                    .collect::<Vec<_>>()
+            ),
+        }
+    }
+
+    /// Panic if any wave was emitted on a matching channel
+    pub async fn expect_no_wave_on(&self, pattern: impl Into<Channel>) {
+        let pattern = pattern.into();
+        let matching = self.emitted_on(pattern.clone()).await;
+        assert!(
+            matching.is_empty(),
+            "expected no wave on {}, found {}",
+            pattern,
+            matching.len()
+        );
+    }
+
+    /// Forget captured waves
+    pub fn clear(&self) {
+        self.captured.lock().expect("capture lock poisoned").clear();
+    }
+}
+
+impl Drop for TestAether {
+    fn drop(&mut self) {
+        self.capture_task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Clock;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_expect_wave_on_without_sleeping() {
+        let harness = TestAether::new().await;
+        let sender = harness
+            .vibrator(VibratorConfig::new("sender"))
+            .await
+            .unwrap();
+
+        sender
+            .emit_wave("orders.created", serde_json::json!({"order_id": "1"}))
+            .await
+            .unwrap();
+
+        let wave = harness.expect_wave_on("orders.created").await;
+        assert_eq!(wave.payload()["order_id"], "1");
+        harness.expect_no_wave_on("payments.*").await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_drain_waits_for_receivers() {
+        let harness = TestAether::new().await;
+        let channel = Channel::new("inventory.check");
+        let mut receiver = harness
+            .vibrator(VibratorConfig::new("receiver").with_channels(vec![channel.clone()]))
+            .await
+            .unwrap();
+        let sender = harness
+            .vibrator(VibratorConfig::new("sender"))
+            .await
+            .unwrap();
+
+        sender
+            .emit_wave(channel.clone(), serde_json::json!({}))
+            .await
+            .unwrap();
+        assert!(!harness.aether().pending_deliveries().await.is_empty());
+
+        let consumer = tokio::spawn(async move { receiver.receive().await });
+        harness.drain().await;
+        assert!(consumer.await.unwrap().is_some());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_advance_moves_virtual_time() {
+        let harness = TestAether::new().await;
+        let start = tokio::time::Instant::now();
+        let wall = harness.clock().now();
+        harness.advance(Duration::from_secs(60)).await;
+        assert!(start.elapsed() >= Duration::from_secs(60));
+        assert_eq!(harness.clock().now() - wall, chrono::Duration::seconds(60));
+    }
+}
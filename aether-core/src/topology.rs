@@ -0,0 +1,300 @@
+//! Observed service topology: who emits to which channel and who consumes it.
+//!
+//! Producer edges come from the waves a [`TopologyTracker`] sees on `>`;
+//! consumer edges come from the channels peers announce in their cluster
+//! heartbeats, with patterns expanded to the observed channels they match.
+//! Internal `aether.*` channels are left out unless asked for.
+
+use crate::{
+    aether::Aether, channel::Channel, cluster::PeerStatus, source_stats::ANONYMOUS_SOURCE,
+    wave::Wave,
+};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+
+/// Distinct producer edges tracked before new ones are dropped
+pub const MAX_EDGES: usize = 4096;
+
+const INTERNAL_PREFIX: &str = "aether.";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TopologyConfig {
+    /// Include the layer's own `aether.*` channels (heartbeats, control, alerts)
+    #[serde(default)]
+    pub include_internal: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeKind {
+    Emits,
+    Consumes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopologyEdge {
+    pub service: String,
+    pub channel: String,
+    pub kind: EdgeKind,
+    /// Waves seen on this edge; 0 for consumer edges
+    pub waves: u64,
+}
+
+/// The graph at one point in time
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Topology {
+    pub services: Vec<String>,
+    pub channels: Vec<String>,
+    pub edges: Vec<TopologyEdge>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TopologyFormat {
+    #[default]
+    Json,
+    Dot,
+}
+
+impl Topology {
+    /// Graphviz rendering: services as boxes, channels as ellipses
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph aether {\n    rankdir=LR;\n");
+        for service in &self.services {
+            let _ = writeln!(
+                dot,
+                "    \"svc:{0}\" [label=\"{0}\", shape=box];",
+                escape(service)
+            );
+        }
+        for channel in &self.channels {
+            let _ = writeln!(
+                dot,
+                "    \"ch:{0}\" [label=\"{0}\", shape=ellipse];",
+                escape(channel)
+            );
+        }
+        for edge in &self.edges {
+            let service = escape(&edge.service);
+            let channel = escape(&edge.channel);
+            let _ = match edge.kind {
+                EdgeKind::Emits => writeln!(
+                    dot,
+                    "    \"svc:{}\" -> \"ch:{}\" [label=\"{}\"];",
+                    service, channel, edge.waves
+                ),
+                EdgeKind::Consumes => {
+                    writeln!(dot, "    \"ch:{}\" -> \"svc:{}\";", channel, service)
+                }
+            };
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// The graph in `format`, as a control response detail
+    pub fn render(&self, format: TopologyFormat) -> serde_json::Value {
+        match format {
+            TopologyFormat::Json => serde_json::to_value(self).unwrap_or(serde_json::Value::Null),
+            TopologyFormat::Dot => self.to_dot().into(),
+        }
+    }
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Builds the topology from observed waves; clones share the graph
+#[derive(Clone)]
+pub struct TopologyTracker {
+    aether: Aether,
+    config: TopologyConfig,
+    emits: Arc<Mutex<BTreeMap<(String, String), u64>>>,
+}
+
+impl TopologyTracker {
+    pub fn new(aether: &Aether, config: &TopologyConfig) -> Self {
+        Self {
+            aether: aether.clone(),
+            config: config.clone(),
+            emits: Arc::default(),
+        }
+    }
+
+    fn tracks(&self, channel: &str) -> bool {
+        self.config.include_internal || !channel.starts_with(INTERNAL_PREFIX)
+    }
+
+    /// Count `wave` as an emit from its source to its channel
+    pub fn record(&self, wave: &Wave) {
+        let channel = wave.channel().name();
+        if !self.tracks(channel) {
+            return;
+        }
+        let source = wave.source().unwrap_or(ANONYMOUS_SOURCE);
+        let mut emits = self.emits.lock().expect("topology lock poisoned");
+        let key = (source.to_string(), channel.to_string());
+        if let Some(waves) = emits.get_mut(&key) {
+            *waves += 1;
+        } else if emits.len() < MAX_EDGES {
+            emits.insert(key, 1);
+        } else {
+            metrics::counter!("aether_topology_edges_dropped_total").increment(1);
+        }
+    }
+
+    /// Record every wave on the layer until the task is aborted
+    pub async fn spawn(&self) -> JoinHandle<()> {
+        let stream = self.aether.tap(Channel::new(">")).await;
+        let tracker = self.clone();
+        tokio::spawn(async move {
+            futures::pin_mut!(stream);
+            while let Some(wave) = stream.next().await {
+                tracker.record(&wave);
+            }
+        })
+    }
+
+    /// Producer edges seen so far plus consumers from the current cluster view
+    pub fn snapshot(&self) -> Topology {
+        let mut edges: Vec<TopologyEdge> = self
+            .emits
+            .lock()
+            .expect("topology lock poisoned")
+            .iter()
+            .map(|((service, channel), waves)| TopologyEdge {
+                service: service.clone(),
+                channel: channel.clone(),
+                kind: EdgeKind::Emits,
+                waves: *waves,
+            })
+            .collect();
+        let observed: BTreeSet<String> = edges.iter().map(|edge| edge.channel.clone()).collect();
+
+        let mut consumes = BTreeSet::new();
+        for peer in self.aether.cluster_view().peers {
+            if peer.status == PeerStatus::Dead {
+                continue;
+            }
+            for subscribed in &peer.channels {
+                let pattern = Channel::new(subscribed.as_str());
+                let mut matched = observed
+                    .iter()
+                    .filter(|channel| Channel::new(channel.as_str()).matches(&pattern))
+                    .peekable();
+                if matched.peek().is_none() {
+                    // Nothing seen on it yet; show the subscription itself
+                    if self.tracks(subscribed) {
+                        consumes.insert((peer.name.clone(), subscribed.clone()));
+                    }
+                    continue;
+                }
+                for channel in matched {
+                    consumes.insert((peer.name.clone(), channel.clone()));
+                }
+            }
+        }
+        edges.extend(consumes.into_iter().map(|(service, channel)| TopologyEdge {
+            service,
+            channel,
+            kind: EdgeKind::Consumes,
+            waves: 0,
+        }));
+
+        let services: BTreeSet<&str> = edges.iter().map(|edge| edge.service.as_str()).collect();
+        let channels: BTreeSet<&str> = edges.iter().map(|edge| edge.channel.as_str()).collect();
+        Topology {
+            services: services.into_iter().map(str::to_string).collect(),
+            channels: channels.into_iter().map(str::to_string).collect(),
+            edges,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aether::AetherConfig;
+    use crate::cluster::{ClusterConfig, Heartbeat};
+    use crate::control::{control_channel, ControlCommand, ControlPlane};
+    use crate::wave::WaveType;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_topology_joins_emitters_and_cluster_consumers() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            ..AetherConfig::default()
+        });
+        let tracker = TopologyTracker::new(&aether, &TopologyConfig::default());
+        let task = tracker.spawn().await;
+
+        let config = ClusterConfig {
+            heartbeat_interval_ms: 10,
+            ..ClusterConfig::default()
+        };
+        let _beta = aether
+            .join_cluster(
+                Heartbeat::new("service-beta", "1.0.0")
+                    .with_channels(vec!["orders.*".into(), "refunds.issued".into()]),
+                &config,
+            )
+            .await;
+        for source in ["service-alpha", "service-alpha", "checkout"] {
+            let wave = Wave::builder("orders.created")
+                .payload(serde_json::json!({ "id": 1 }))
+                .source(source)
+                .build();
+            aether.emit(wave).await.unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let topology = tracker.snapshot();
+        assert_eq!(
+            topology.services,
+            ["checkout", "service-alpha", "service-beta"]
+        );
+        assert_eq!(topology.channels, ["orders.created", "refunds.issued"]);
+        let edge = |service: &str, channel: &str, kind, waves| TopologyEdge {
+            service: service.to_string(),
+            channel: channel.to_string(),
+            kind,
+            waves,
+        };
+        assert_eq!(
+            topology.edges,
+            vec![
+                edge("checkout", "orders.created", EdgeKind::Emits, 1),
+                edge("service-alpha", "orders.created", EdgeKind::Emits, 2),
+                edge("service-beta", "orders.created", EdgeKind::Consumes, 0),
+                edge("service-beta", "refunds.issued", EdgeKind::Consumes, 0),
+            ]
+        );
+        let dot = topology.to_dot();
+        assert!(dot.contains("\"svc:service-alpha\" -> \"ch:orders.created\" [label=\"2\"];"));
+        assert!(dot.contains("\"ch:orders.created\" -> \"svc:service-beta\";"));
+
+        let control = ControlPlane::new("aether-gateway", &aether).with_topology(tracker.clone());
+        let command = Wave::builder(control_channel("aether-gateway"))
+            .wave_type(WaveType::Command)
+            .payload(
+                serde_json::to_value(ControlCommand::DumpTopology {
+                    format: TopologyFormat::Dot,
+                })
+                .unwrap(),
+            )
+            .build();
+        let response = control.handle(&command).await.unwrap();
+        assert!(response.ok);
+        assert_eq!(
+            response.detail,
+            serde_json::Value::from(tracker.snapshot().to_dot())
+        );
+        task.abort();
+    }
+}
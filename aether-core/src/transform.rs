@@ -0,0 +1,193 @@
+//! Transformation pipelines: rewrite payloads per channel on publish or delivery.
+//!
+//! A [`TransformPipeline`] runs on the Aether layer before a wave is
+//! validated, persisted and published (`Aether::with_transforms`), or in a
+//! vibrator before delivery (`VibratorConfig::with_transforms`). Stages run in
+//! registration order on the channels they match; byte payloads pass through
+//! untouched.
+
+use crate::{channel::Channel, wave::Wave, AetherError, Result};
+use serde_json::Value;
+use std::fmt;
+use std::sync::Arc;
+
+/// One payload rewrite
+pub trait Transformer: Send + Sync {
+    /// New payload for `wave`, or `Ok(None)` to drop the wave
+    fn transform(&self, wave: &Wave, payload: Value) -> Result<Option<Value>>;
+}
+
+impl<F> Transformer for F
+where
+    F: Fn(&Wave, Value) -> Result<Option<Value>> + Send + Sync,
+{
+    fn transform(&self, wave: &Wave, payload: Value) -> Result<Option<Value>> {
+        self(wave, payload)
+    }
+}
+
+/// Apply a function to every payload
+pub fn map_payload(f: impl Fn(Value) -> Value + Send + Sync) -> impl Transformer {
+    move |_: &Wave, payload: Value| Ok(Some(f(payload)))
+}
+
+/// Remove fields by JSON pointer (e.g. "/card/number"); missing fields are ignored
+#[derive(Debug, Clone)]
+pub struct StripFields {
+    pointers: Vec<String>,
+}
+
+impl StripFields {
+    pub fn new<S: Into<String>>(pointers: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            pointers: pointers.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl Transformer for StripFields {
+    fn transform(&self, _wave: &Wave, mut payload: Value) -> Result<Option<Value>> {
+        for pointer in &self.pointers {
+            let Some((parent, field)) = pointer.rsplit_once('/') else {
+                return Err(AetherError::ValidationFailed(format!(
+                    "not a JSON pointer: {}",
+                    pointer
+                )));
+            };
+            let field = field.replace("~1", "/").replace("~0", "~");
+            match payload.pointer_mut(parent) {
+                Some(Value::Object(fields)) => {
+                    fields.remove(&field);
+                }
+                Some(Value::Array(items)) => {
+                    if let Ok(index) = field.parse::<usize>() {
+                        if index < items.len() {
+                            items.remove(index);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(Some(payload))
+    }
+}
+
+/// Transformers bound to channel patterns, applied in order
+#[derive(Clone, Default)]
+pub struct TransformPipeline {
+    stages: Vec<(Channel, Arc<dyn Transformer>)>,
+}
+
+impl TransformPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `transformer` on waves whose channel matches `pattern`
+    pub fn stage(
+        mut self,
+        pattern: impl Into<Channel>,
+        transformer: impl Transformer + 'static,
+    ) -> Self {
+        self.stages.push((pattern.into(), Arc::new(transformer)));
+        self
+    }
+
+    /// Append another pipeline's stages after this one's
+    pub fn then(mut self, other: TransformPipeline) -> Self {
+        self.stages.extend(other.stages);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Run the matching stages; `Ok(None)` when one of them dropped the wave
+    pub fn apply(&self, mut wave: Wave) -> Result<Option<Wave>> {
+        if wave.payload_bytes().is_some() {
+            return Ok(Some(wave));
+        }
+        for (pattern, transformer) in &self.stages {
+            if !wave.channel().matches(pattern) {
+                continue;
+            }
+            let payload = std::mem::take(wave.payload_mut());
+            match transformer.transform(&wave, payload)? {
+                Some(payload) => *wave.payload_mut() = payload,
+                None => {
+                    metrics::counter!(
+                        "aether_transform_dropped_total",
+                        "channel" => pattern.name().to_string()
+                    )
+                    .increment(1);
+                    return Ok(None);
+                }
+            }
+        }
+        Ok(Some(wave))
+    }
+}
+
+impl fmt::Debug for TransformPipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.stages.iter().map(|(pattern, _)| pattern.name()))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pipeline_applies_matching_stages_in_order() {
+        let convert = |_: &Wave, mut payload: Value| {
+            let usd = payload["total"].as_f64().unwrap_or_default();
+            payload["total_eur"] = serde_json::json!(usd * 0.5);
+            Ok(Some(payload))
+        };
+        let pipeline = TransformPipeline::new()
+            .stage("orders.*", convert)
+            .stage(
+                "orders.created",
+                StripFields::new(["/card", "/customer/email"]),
+            )
+            .then(
+                TransformPipeline::new().stage("orders.>", |wave: &Wave, payload: Value| {
+                    Ok((wave.channel().name() != "orders.test").then_some(payload))
+                }),
+            );
+
+        let wave = Wave::new(
+            "orders.created",
+            serde_json::json!({
+                "total": 10.0,
+                "card": "4111",
+                "customer": {"name": "Ada", "email": "ada@example.com"},
+            }),
+        );
+        let transformed = pipeline.apply(wave).unwrap().unwrap();
+        assert_eq!(
+            transformed.payload(),
+            &serde_json::json!({"total": 10.0, "total_eur": 5.0, "customer": {"name": "Ada"}})
+        );
+
+        let test = Wave::new("orders.test", serde_json::json!({}));
+        assert!(pipeline.apply(test).unwrap().is_none());
+        let other = Wave::new("payments.completed", serde_json::json!({"card": "4111"}));
+        assert_eq!(pipeline.apply(other.clone()).unwrap(), Some(other));
+
+        let upper = TransformPipeline::new().stage(
+            "greetings",
+            map_payload(|p| Value::from(p.as_str().unwrap_or("").to_uppercase())),
+        );
+        let wave = upper
+            .apply(Wave::new("greetings", "hi".into()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(wave.payload(), "HI");
+    }
+}
@@ -0,0 +1,255 @@
+//! Wave validation hooks run inside `Aether::emit`.
+//!
+//! Beyond the layer's own checks (channel name, payload size, registry, auth),
+//! applications register named [`WaveValidator`]s on channel patterns with
+//! `Aether::with_validators`, and `aether.required_fields` declares required
+//! payload fields per channel without code. Validators run in registration
+//! order once the wave is authorized; the first rejection fails the emit with
+//! `ValidatorRejected` naming the validator, so a malformed wave never reaches
+//! the log or any consumer.
+
+use crate::{channel::Channel, wave::Wave, AetherError, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+
+/// One check on emitted waves
+#[async_trait]
+pub trait WaveValidator: Send + Sync {
+    /// `Err(reason)` rejects the wave
+    async fn validate(&self, wave: &Wave) -> std::result::Result<(), String>;
+}
+
+#[async_trait]
+impl<F> WaveValidator for F
+where
+    F: Fn(&Wave) -> std::result::Result<(), String> + Send + Sync,
+{
+    async fn validate(&self, wave: &Wave) -> std::result::Result<(), String> {
+        self(wave)
+    }
+}
+
+/// Payload fields a channel's waves must carry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequiredFields {
+    /// Channel name or pattern (e.g. "orders.created")
+    pub channel: String,
+    /// JSON pointers into the payload (e.g. "/order_id"); null counts as missing
+    pub fields: Vec<String>,
+}
+
+/// Rejects waves missing any of the given fields, and byte payloads, whose
+/// fields can't be checked
+#[derive(Debug, Clone)]
+pub struct RequireFields {
+    pointers: Vec<String>,
+}
+
+impl RequireFields {
+    pub fn new<S: Into<String>>(pointers: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            pointers: pointers.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl WaveValidator for RequireFields {
+    async fn validate(&self, wave: &Wave) -> std::result::Result<(), String> {
+        if wave.payload_bytes().is_some() {
+            return Err("byte payload where JSON fields are required".to_string());
+        }
+        let missing: Vec<&str> = self
+            .pointers
+            .iter()
+            .filter(|pointer| wave.payload().pointer(pointer).is_none_or(|v| v.is_null()))
+            .map(String::as_str)
+            .collect();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("missing {}", missing.join(", ")))
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Stage {
+    name: String,
+    pattern: Channel,
+    validator: Arc<dyn WaveValidator>,
+}
+
+/// Named validators bound to channel patterns, run in order
+#[derive(Clone, Default)]
+pub struct WaveValidators {
+    stages: Vec<Stage>,
+}
+
+impl WaveValidators {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// One `required_fields` validator per configured channel
+    pub fn from_config(rules: &[RequiredFields]) -> Self {
+        rules.iter().fold(Self::new(), |validators, rule| {
+            validators.validator(
+                "required_fields",
+                rule.channel.as_str(),
+                RequireFields::new(rule.fields.iter().cloned()),
+            )
+        })
+    }
+
+    /// Run `validator` on waves whose channel matches `pattern`; `name`
+    /// appears in rejections and the `aether_validation_rejections_total` metric
+    pub fn validator(
+        mut self,
+        name: impl Into<String>,
+        pattern: impl Into<Channel>,
+        validator: impl WaveValidator + 'static,
+    ) -> Self {
+        self.stages.push(Stage {
+            name: name.into(),
+            pattern: pattern.into(),
+            validator: Arc::new(validator),
+        });
+        self
+    }
+
+    /// Append another set's validators after this one's
+    pub fn then(mut self, other: WaveValidators) -> Self {
+        self.stages.extend(other.stages);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Run the matching validators; the first rejection is the error
+    pub async fn check(&self, wave: &Wave) -> Result<()> {
+        for stage in &self.stages {
+            if !wave.channel().matches(&stage.pattern) {
+                continue;
+            }
+            if let Err(reason) = stage.validator.validate(wave).await {
+                metrics::counter!(
+                    "aether_validation_rejections_total",
+                    "validator" => stage.name.clone()
+                )
+                .increment(1);
+                return Err(AetherError::ValidatorRejected {
+                    validator: stage.name.clone(),
+                    channel: wave.channel().name().to_string(),
+                    reason,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for WaveValidators {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(
+                self.stages
+                    .iter()
+                    .map(|stage| format!("{} on {}", stage.name, stage.pattern.name())),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aether::{Aether, AetherConfig};
+
+    struct KnownCustomers(Vec<&'static str>);
+
+    #[async_trait]
+    impl WaveValidator for KnownCustomers {
+        async fn validate(&self, wave: &Wave) -> std::result::Result<(), String> {
+            tokio::task::yield_now().await;
+            match wave.payload()["customer"].as_str() {
+                Some(customer) if self.0.contains(&customer) => Ok(()),
+                other => Err(format!("unknown customer {:?}", other)),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_emit_rejects_waves_failing_a_validator_by_name() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            required_fields: vec![RequiredFields {
+                channel: "orders.created".to_string(),
+                fields: vec!["/order_id".to_string()],
+            }],
+            ..AetherConfig::default()
+        })
+        .with_validators(
+            WaveValidators::new()
+                .validator("known_customer", "orders.>", KnownCustomers(vec!["C-1"]))
+                .validator("positive_amount", "payments.*", |wave: &Wave| {
+                    match wave.payload()["amount"].as_f64() {
+                        Some(amount) if amount > 0.0 => Ok(()),
+                        _ => Err("amount must be positive".to_string()),
+                    }
+                }),
+        );
+        let emit =
+            |channel: &str, payload: serde_json::Value| aether.emit(Wave::new(channel, payload));
+
+        let err = emit("orders.created", serde_json::json!({ "customer": "C-1" }))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, AetherError::ValidatorRejected { validator, reason, .. }
+            if validator == "required_fields" && reason.contains("/order_id"))
+        );
+
+        // Bytes can't show they carry the field
+        let err = aether
+            .emit(Wave::new_bytes(
+                "orders.created",
+                bytes::Bytes::from_static(b"ORD-1"),
+            ))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, AetherError::ValidatorRejected { validator, .. }
+            if validator == "required_fields")
+        );
+
+        let err = emit(
+            "orders.created",
+            serde_json::json!({ "order_id": "ORD-1", "customer": "C-9" }),
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            matches!(&err, AetherError::ValidatorRejected { validator, channel, .. }
+            if validator == "known_customer" && channel == "orders.created")
+        );
+
+        emit(
+            "orders.created",
+            serde_json::json!({ "order_id": "ORD-1", "customer": "C-1" }),
+        )
+        .await
+        .unwrap();
+        let err = emit("payments.charged", serde_json::json!({ "amount": 0 }))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("positive_amount"), "{}", err);
+        emit("inventory.reserved", serde_json::json!({}))
+            .await
+            .unwrap();
+    }
+}
@@ -1,9 +1,36 @@
 //! Vibrator - a vibrating entity on the Aether layer (microservice)
 
-use crate::{aether::Aether, channel::Channel, wave::Wave, Result};
+use crate::{
+    aether::{is_valid_channel_name, Aether, AetherConfig},
+    channel::Channel,
+    command::{
+        self, CommandHandler, CommandOutcome, CommandReply, GatherPolicy, DEFAULT_COMMAND_TIMEOUT,
+    },
+    echo::{self, echo_channel, is_echo_channel, Reflection},
+    events::AetherEvent,
+    filter::WaveFilter,
+    hopping::{HopAnnouncement, HopKeys, HopSchedule, HopSync},
+    physics::{PhysicsEngine, Resonance},
+    receipt::{EmitOptions, EmitReceipt},
+    rollout::VersionRouter,
+    sampling::ObserveSampler,
+    sequencing::{EpochCheck, Ordered, ReorderBuffer, SequenceGap, SourceEpochs},
+    shedding::{Admission, LoadShedder},
+    transform::TransformPipeline,
+    wave::{Wave, WaveType},
+    AetherError, Result,
+};
 use bytes::Bytes;
-use tokio::sync::broadcast;
-use tracing::{debug, info};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, Notify};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Largest receive buffer a vibrator may ask for
+pub const MAX_BUFFER_SIZE: usize = 65_536;
 
 /// Vibrator configuration
 #[derive(Debug, Clone)]
@@ -22,6 +49,45 @@ pub struct VibratorConfig {
 
     /// Noise floor (waves below this amplitude are ignored)
     pub noise_floor: f64,
+
+    /// Noise floor overrides by channel pattern; the first match wins
+    pub channel_noise_floors: Vec<(Channel, f64)>,
+
+    /// Resonance declared by channel pattern; matching waves are amplified
+    /// before the noise floor applies. The first match wins
+    pub channel_resonances: Vec<(Channel, Resonance)>,
+
+    /// Frequency the vibrator is tuned to; waves on channels without a
+    /// declared resonance get the gain `PhysicsEngine::check_resonance` finds
+    pub natural_frequency: Option<f64>,
+
+    /// Deliver sequenced waves in order, waiting this long for missing ones
+    pub reorder_window: Option<Duration>,
+
+    /// Receive the retained wave of each channel when starting to resonate
+    pub receive_retained: bool,
+
+    /// Shed faint waves in `receive` while overloaded
+    pub load_shedder: Option<LoadShedder>,
+
+    /// Hop index derivation for time-based hopping
+    pub hop_keys: HopKeys,
+
+    /// Share of routed channels this instance's version handles
+    pub version_router: Option<VersionRouter>,
+
+    /// Payload rewrites applied before delivery
+    pub transforms: TransformPipeline,
+
+    /// Acknowledge received waves with a reflection on `aether.echo.<channel>`
+    pub reflect: bool,
+
+    /// Drop waves from a source's earlier epochs once it has restarted
+    pub discard_stale_epochs: bool,
+
+    /// Share of received waves handed on, per channel; the rest are dropped
+    /// before anything else looks at them
+    pub sampler: Option<ObserveSampler>,
 }
 
 impl VibratorConfig {
@@ -32,6 +98,18 @@ impl VibratorConfig {
             buffer_size: 100,
             auth_token: None,
             noise_floor: 0.01,
+            channel_noise_floors: Vec::new(),
+            channel_resonances: Vec::new(),
+            natural_frequency: None,
+            reorder_window: None,
+            receive_retained: false,
+            load_shedder: None,
+            hop_keys: HopKeys::default(),
+            version_router: None,
+            transforms: TransformPipeline::default(),
+            reflect: false,
+            discard_stale_epochs: false,
+            sampler: None,
         }
     }
 
@@ -49,6 +127,179 @@ impl VibratorConfig {
         self.noise_floor = noise_floor;
         self
     }
+
+    pub fn with_reflections(mut self, reflect: bool) -> Self {
+        self.reflect = reflect;
+        self
+    }
+
+    pub fn with_stale_epochs_discarded(mut self, discard: bool) -> Self {
+        self.discard_stale_epochs = discard;
+        self
+    }
+
+    pub fn with_sampler(mut self, sampler: Option<ObserveSampler>) -> Self {
+        self.sampler = sampler;
+        self
+    }
+
+    /// Use `noise_floor` on channels matching `pattern` instead of the global one
+    pub fn with_channel_noise_floor(
+        mut self,
+        pattern: impl Into<Channel>,
+        noise_floor: f64,
+    ) -> Self {
+        self.channel_noise_floors
+            .push((pattern.into(), noise_floor));
+        self
+    }
+
+    /// Noise floor that applies to waves on `channel`
+    pub fn noise_floor_for(&self, channel: &Channel) -> f64 {
+        self.channel_noise_floors
+            .iter()
+            .find(|(pattern, _)| channel.matches(pattern))
+            .map_or(self.noise_floor, |(_, noise_floor)| *noise_floor)
+    }
+
+    /// Resonate with channels matching `pattern` at `resonance`
+    ///
+    /// `Resonance::Strong` lets faint waves on critical channels clear the
+    /// noise floor; the gain is bounded and never lifts a wave past 1.0.
+    pub fn with_channel_resonance(
+        mut self,
+        pattern: impl Into<Channel>,
+        resonance: Resonance,
+    ) -> Self {
+        self.channel_resonances.push((pattern.into(), resonance));
+        self
+    }
+
+    /// Tune to `frequency`, so channels near it resonate without a declaration
+    pub fn with_natural_frequency(mut self, frequency: Option<f64>) -> Self {
+        self.natural_frequency = frequency;
+        self
+    }
+
+    /// Declared resonance with `channel`, if any
+    pub fn resonance_for(&self, channel: &Channel) -> Option<Resonance> {
+        self.channel_resonances
+            .iter()
+            .find(|(pattern, _)| channel.matches(pattern))
+            .map(|(_, resonance)| *resonance)
+    }
+
+    /// Apply resonance gain, then report whether the wave clears the noise floor
+    ///
+    /// A declared resonance wins; otherwise the physics engine measures the
+    /// wave against the natural frequency, if one is set.
+    fn hears(&self, wave: &mut Wave) -> bool {
+        let resonance = self.resonance_for(wave.channel()).or_else(|| {
+            self.natural_frequency
+                .map(|frequency| PhysicsEngine::check_resonance(wave, frequency))
+        });
+        if let Some(resonance) = resonance {
+            PhysicsEngine::amplify(wave, &resonance);
+        }
+        wave.amplitude().value() >= self.noise_floor_for(wave.channel())
+    }
+
+    pub fn with_reorder_window(mut self, window: Duration) -> Self {
+        self.reorder_window = Some(window);
+        self
+    }
+
+    pub fn with_retained(mut self, receive_retained: bool) -> Self {
+        self.receive_retained = receive_retained;
+        self
+    }
+
+    pub fn with_load_shedder(mut self, shedder: Option<LoadShedder>) -> Self {
+        self.load_shedder = shedder;
+        self
+    }
+
+    pub fn with_hop_keys(mut self, hop_keys: HopKeys) -> Self {
+        self.hop_keys = hop_keys;
+        self
+    }
+
+    pub fn with_version_router(mut self, router: VersionRouter) -> Self {
+        self.version_router = Some(router);
+        self
+    }
+
+    /// Append delivery-side transformers
+    pub fn with_transforms(mut self, transforms: TransformPipeline) -> Self {
+        self.transforms = std::mem::take(&mut self.transforms).then(transforms);
+        self
+    }
+
+    /// Check the config against the layer it will run on
+    ///
+    /// Every problem is reported at once, so a misconfigured service fails
+    /// at startup instead of on its first emit.
+    pub fn validate(&self, layer: &AetherConfig) -> Result<()> {
+        let mut problems = Vec::new();
+        if self.name.is_empty() {
+            problems.push("name is empty".to_string());
+        }
+        match (&layer.auth_token, &self.auth_token) {
+            (Some(expected), Some(token)) if token != expected => {
+                problems.push("auth token does not match the layer's".to_string())
+            }
+            (Some(_), None) => problems.push("the layer requires an auth token".to_string()),
+            _ => {}
+        }
+        if !layer.allowed_sources.is_empty() && !layer.allowed_sources.contains(&self.name) {
+            problems.push(format!(
+                "{:?} is not in the layer's allowed_sources",
+                self.name
+            ));
+        }
+        for channel in &self.resonant_channels {
+            if !is_valid_channel_name(channel.name(), layer.max_channel_length) {
+                problems.push(format!(
+                    "invalid channel {:?} (1-{} chars of [A-Za-z0-9._-*>])",
+                    channel.name(),
+                    layer.max_channel_length
+                ));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.noise_floor) {
+            problems.push(format!(
+                "noise floor {} is outside 0.0-1.0",
+                self.noise_floor
+            ));
+        }
+        for (pattern, noise_floor) in &self.channel_noise_floors {
+            if !is_valid_channel_name(pattern.name(), layer.max_channel_length) {
+                problems.push(format!("invalid noise floor pattern {:?}", pattern.name()));
+            }
+            if !(0.0..=1.0).contains(noise_floor) {
+                problems.push(format!(
+                    "noise floor {} for {} is outside 0.0-1.0",
+                    noise_floor,
+                    pattern.name()
+                ));
+            }
+        }
+        if !(1..=MAX_BUFFER_SIZE).contains(&self.buffer_size) {
+            problems.push(format!(
+                "buffer size {} is outside 1-{}",
+                self.buffer_size, MAX_BUFFER_SIZE
+            ));
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(AetherError::InvalidVibrator(format!(
+                "{}: {}",
+                self.name,
+                problems.join("; ")
+            )))
+        }
+    }
 }
 
 /// Vibrator - a service that vibrates on the Aether layer
@@ -63,27 +314,210 @@ pub struct Vibrator {
     /// Reference to the Aether layer
     aether: Aether,
 
+    /// Name stamped on emitted waves, shared instead of copied per wave
+    source: Arc<str>,
+
     /// Receivers for resonant channels
-    receivers: Vec<(Channel, broadcast::Receiver<Wave>)>,
+    receivers: Vec<Subscription>,
+
+    /// Consumption control shared with the control plane
+    control: VibratorControl,
+
+    /// Reorder buffer (when a reorder window is configured)
+    reorder: Option<ReorderBuffer>,
+
+    /// Waves released by the reorder buffer but not yet returned
+    ready: VecDeque<Wave>,
+
+    /// Gaps detected since the last `take_gaps`
+    gaps: Vec<SequenceGap>,
+
+    /// Waves held back by the load shedder until load recovers
+    deferred: VecDeque<Wave>,
+
+    /// Latest epoch heard per source, when discarding stale epochs
+    epochs: SourceEpochs,
+
+    /// Last wave returned per `catch_up_then_follow` channel, which
+    /// `checkpoint_catch_up` resolves to a log index
+    delivered: HashMap<Channel, Option<Uuid>>,
+}
+
+/// A receiver on one resonant channel
+struct Subscription {
+    channel: Channel,
+    receiver: broadcast::Receiver<Wave>,
+    cancelled: Arc<AtomicBool>,
+    /// Waves failing the filter are dropped before delivery
+    filter: Option<WaveFilter>,
+    /// Waves replayed by `catch_up_then_follow` that may still arrive live
+    replay: Option<ReplayWindow>,
+}
+
+/// How long a replay window stays open after the log was read, covering emits
+/// that were logged but not yet delivered at that moment
+const REPLAY_GRACE: Duration = Duration::from_secs(1);
+
+/// IDs replayed by `catch_up_then_follow` that the live subscription may repeat
+///
+/// Only waves already buffered on the subscription when the log was read can
+/// repeat, so the window closes once those are taken and the grace is over,
+/// dropping IDs whose live copy never came (lagged or shed upstream).
+struct ReplayWindow {
+    ids: HashSet<Uuid>,
+    /// Waves buffered on the subscription when the log was read, not yet taken
+    pending: usize,
+    grace_until: Instant,
+}
+
+impl ReplayWindow {
+    /// Whether `wave` was already replayed; counts it against the window
+    fn repeats(&mut self, wave: &Wave) -> bool {
+        self.pending = self.pending.saturating_sub(1);
+        self.ids.remove(wave.id())
+    }
+
+    fn skipped(&mut self, waves: u64) {
+        self.pending = self.pending.saturating_sub(waves as usize);
+    }
+
+    fn is_closed(&self) -> bool {
+        self.ids.is_empty() || (self.pending == 0 && Instant::now() >= self.grace_until)
+    }
+}
+
+/// Handle to one `resonate_on` subscription
+///
+/// Cancelling it from any task stops the vibrator resonating on the channel;
+/// the receiver is dropped on the vibrator's next `receive`.
+#[derive(Debug, Clone)]
+pub struct ResonanceHandle {
+    channel: Channel,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl ResonanceHandle {
+    pub fn channel(&self) -> &Channel {
+        &self.channel
+    }
+
+    /// Stop resonating on this subscription
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Channels added and removed by `Vibrator::reconcile_channels`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChannelChanges {
+    pub added: Vec<Channel>,
+    pub removed: Vec<Channel>,
+}
+
+impl ChannelChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+fn catch_up_cursor(vibrator: &str, channel: &Channel) -> String {
+    format!("catch_up.{}.{}", vibrator, channel)
+}
+
+/// Shared handle for pausing or draining a vibrator's consumption
+#[derive(Debug, Clone)]
+pub struct VibratorControl {
+    /// Vibrator name, labelling the intake gauges
+    name: Arc<str>,
+    paused: Arc<AtomicBool>,
+    draining: Arc<AtomicBool>,
+    /// Wakes `drain_requested` waiters
+    drain_notify: Arc<Notify>,
+}
+
+impl VibratorControl {
+    fn new(name: Arc<str>) -> Self {
+        Self {
+            name,
+            paused: Arc::default(),
+            draining: Arc::default(),
+            drain_notify: Arc::default(),
+        }
+    }
+
+    /// Stop pulling waves from receivers (subscriptions stay active)
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+        metrics::gauge!("aether_intake_paused", "vibrator" => self.name.to_string()).set(1.0);
+    }
+
+    /// Resume pulling waves from receivers
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+        metrics::gauge!("aether_intake_paused", "vibrator" => self.name.to_string()).set(0.0);
+    }
+
+    /// Stop intake permanently so in-flight work can finish
+    pub fn drain(&self) {
+        self.draining.store(true, Ordering::SeqCst);
+        self.drain_notify.notify_waiters();
+        metrics::gauge!("aether_draining", "vibrator" => self.name.to_string()).set(1.0);
+    }
+
+    /// Resolve once draining has been requested, e.g. by a control command
+    pub async fn drain_requested(&self) {
+        // Registered before the check, so a drain in between still wakes it
+        let requested = self.drain_notify.notified();
+        if !self.is_draining() {
+            requested.await;
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
 }
 
 /// Lightweight emitter handle for concurrent tasks
 #[derive(Clone)]
 pub struct VibratorEmitter {
-    name: String,
+    name: Arc<str>,
     aether: Aether,
     auth_token: Option<String>,
+    hop_keys: HopKeys,
 }
 
 impl Vibrator {
-    /// Create a new vibrator
-    pub async fn new(config: VibratorConfig, aether: &Aether) -> Self {
+    /// Create a new vibrator, failing if its config cannot work on `aether`
+    pub async fn new(config: VibratorConfig, aether: &Aether) -> Result<Self> {
+        config.validate(aether.config())?;
         info!("Initializing vibrator {}...", config.name);
 
+        aether.register_vibrator(&config.name);
+        let reorder = config.reorder_window.map(|window| {
+            ReorderBuffer::new(window).with_stale_epochs_discarded(config.discard_stale_epochs)
+        });
+        let source: Arc<str> = config.name.as_str().into();
         let mut vibrator = Self {
+            control: VibratorControl::new(Arc::clone(&source)),
+            source,
             config,
             aether: aether.clone(),
             receivers: Vec::new(),
+            reorder,
+            ready: VecDeque::new(),
+            gaps: Vec::new(),
+            deferred: VecDeque::new(),
+            epochs: SourceEpochs::new(),
+            delivered: HashMap::new(),
         };
 
         // Set initial resonant channels
@@ -92,45 +526,280 @@ impl Vibrator {
             vibrator.resonate_on(channel).await;
         }
 
-        vibrator
+        Ok(vibrator)
     }
 
     /// Simple constructor
-    pub async fn create(name: impl Into<String>, aether: &Aether) -> Self {
+    pub async fn create(name: impl Into<String>, aether: &Aether) -> Result<Self> {
         Self::new(VibratorConfig::new(name), aether).await
     }
 
     /// Start resonating on a specific channel (start listening)
-    pub async fn resonate_on(&mut self, channel: Channel) {
+    pub async fn resonate_on(&mut self, channel: Channel) -> ResonanceHandle {
+        self.resonate(channel, None).await
+    }
+
+    /// Resonate on a channel, delivering only waves that pass `filter`
+    ///
+    /// ```ignore
+    /// let eu = WaveFilter::parse(r#"/region == "eu""#)?;
+    /// vibrator.resonate_on_filtered(Channel::new("orders.*"), eu).await;
+    /// ```
+    pub async fn resonate_on_filtered(
+        &mut self,
+        channel: Channel,
+        filter: WaveFilter,
+    ) -> ResonanceHandle {
+        self.resonate(channel, Some(filter)).await
+    }
+
+    async fn resonate(&mut self, channel: Channel, filter: Option<WaveFilter>) -> ResonanceHandle {
         debug!(
             "Vibrator {} started resonating on channel {}",
             self.config.name, channel
         );
 
-        let receiver = self.aether.subscribe(&channel).await;
-        self.receivers.push((channel, receiver));
+        let receiver = if self.config.receive_retained {
+            let (retained, receiver) = self.aether.subscribe_retained(&channel).await;
+            let config = &self.config;
+            self.ready
+                .extend(retained.into_iter().filter_map(|mut wave| {
+                    let heard = wave.source() != Some(config.name.as_str())
+                        && config.hears(&mut wave)
+                        && filter.as_ref().is_none_or(|filter| filter.matches(&wave));
+                    heard.then_some(wave)
+                }));
+            receiver
+        } else {
+            self.aether.subscribe(&channel).await
+        };
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.receivers.push(Subscription {
+            channel: channel.clone(),
+            receiver,
+            cancelled: Arc::clone(&cancelled),
+            filter,
+            replay: None,
+        });
+        ResonanceHandle { channel, cancelled }
+    }
+
+    /// Resonate on `channel`, first receiving the persisted waves this vibrator
+    /// has not processed yet
+    ///
+    /// The live subscription starts before the log is read, so nothing emitted
+    /// meanwhile is missed; waves logged after that point are remembered by ID
+    /// until their live copies have passed, and not delivered twice. Replay
+    /// starts at the position saved by `checkpoint_catch_up`, else after the
+    /// last snapshot. Returns the number of replayed waves queued ahead of
+    /// live traffic.
+    pub async fn catch_up_then_follow(&mut self, channel: Channel) -> Result<usize> {
+        let Some(store) = self.aether.wave_store().cloned() else {
+            return Err(AetherError::PersistenceError(
+                "catch-up needs persistence enabled".to_string(),
+            ));
+        };
+        let persistence = |e: anyhow::Error| AetherError::PersistenceError(e.to_string());
+
+        // Waves logged up to here were emitted before the subscription
+        self.aether.flush().await?;
+        let high_water = store.last_index().map_err(persistence)?;
+        self.resonate_on(channel.clone()).await;
+        let subscription = self.receivers.len() - 1;
+        self.delivered.entry(channel.clone()).or_insert(None);
+        self.aether.flush().await?;
+
+        let cursor = catch_up_cursor(&self.config.name, &channel);
+        let start = match store.load_cursor(&cursor).map_err(persistence)? {
+            Some(next_index) => next_index,
+            None => store
+                .load_snapshot()
+                .map_err(persistence)?
+                .map_or(0, |snapshot| snapshot.last_index + 1),
+        };
+        let (logged, _) = store
+            .recover_from(start, crate::persistence::RecoveryMode::Skip)
+            .map_err(persistence)?;
+        let mut replayed = 0;
+        let mut ids = HashSet::new();
+        for (index, mut wave) in logged {
+            if !wave.channel().matches(&channel)
+                || wave.source() == Some(self.config.name.as_str())
+                || !self.config.hears(&mut wave)
+            {
+                continue;
+            }
+            if high_water.is_none_or(|high_water| index > high_water) {
+                ids.insert(*wave.id());
+            }
+            self.ready.push_back(wave);
+            replayed += 1;
+        }
+        if !ids.is_empty() {
+            let subscription = &mut self.receivers[subscription];
+            subscription.replay = Some(ReplayWindow {
+                ids,
+                pending: subscription.receiver.len(),
+                grace_until: Instant::now() + REPLAY_GRACE,
+            });
+        }
+        metrics::counter!("aether_catch_up_waves_total").increment(replayed as u64);
+        info!(
+            "Vibrator {} replayed {} waves on {} from index {}",
+            self.config.name, replayed, channel, start
+        );
+        Ok(replayed)
+    }
+
+    /// Record that every wave received so far on `channel` has been processed
+    ///
+    /// Call once state derived from them is durable; the next
+    /// `catch_up_then_follow` replays from after the last wave received, so
+    /// waves logged but still buffered are replayed rather than skipped.
+    pub fn checkpoint_catch_up(&self, channel: &Channel) -> Result<()> {
+        let Some(store) = self.aether.wave_store() else {
+            return Ok(());
+        };
+        let Some(Some(last)) = self.delivered.get(channel) else {
+            return Ok(());
+        };
+        let cursor = catch_up_cursor(&self.config.name, channel);
+        let saved = store.index_of(last).and_then(|index| {
+            let Some(index) = index else {
+                // Not logged (unlogged durability); the cursor stays put
+                return Ok(());
+            };
+            if store.load_cursor(&cursor)?.is_some_and(|next| next > index) {
+                return Ok(());
+            }
+            store.save_cursor(&cursor, index + 1)
+        });
+        saved.map_err(|e| AetherError::PersistenceError(e.to_string()))
+    }
+
+    /// Remember `wave` as the last received on the catch-up channels it is on
+    fn mark_delivered(&mut self, wave: &Wave) {
+        for (channel, last) in &mut self.delivered {
+            if wave.channel().matches(channel) {
+                *last = Some(*wave.id());
+            }
+        }
+    }
+
+    /// Stop resonating on a channel; returns false if not resonating on it
+    ///
+    /// Dropping the receiver releases the vibrator's interest, so an idle
+    /// channel without other receivers can be collected by the Aether layer.
+    pub fn stop_resonating(&mut self, channel: &Channel) -> bool {
+        let before = self.receivers.len();
+        self.receivers.retain(|subscription| {
+            let stopped = &subscription.channel == channel;
+            if stopped {
+                subscription.cancelled.store(true, Ordering::SeqCst);
+            }
+            !stopped
+        });
+        let stopped = self.receivers.len() < before;
+        if stopped {
+            debug!(
+                "Vibrator {} stopped resonating on channel {}",
+                self.config.name, channel
+            );
+        }
+        stopped
+    }
+
+    /// Resonate on exactly these channels, e.g. after a config reload
+    ///
+    /// New channels are subscribed before stale ones are dropped, so channels
+    /// kept in the list never miss a wave.
+    pub async fn reconcile_channels(&mut self, channels: &[String]) -> ChannelChanges {
+        let wanted = channels.iter().map(Channel::new).collect();
+        self.reconcile_where(wanted, |_| true).await
+    }
+
+    /// Reconcile only the channels `managed` accepts, leaving others alone
+    async fn reconcile_where(
+        &mut self,
+        channels: Vec<Channel>,
+        managed: impl Fn(&Channel) -> bool,
+    ) -> ChannelChanges {
+        self.prune_cancelled();
+        let mut wanted: Vec<Channel> = Vec::with_capacity(channels.len());
+        for channel in channels {
+            if !wanted.contains(&channel) {
+                wanted.push(channel);
+            }
+        }
+        let current: Vec<Channel> = self
+            .resonant_channels()
+            .into_iter()
+            .filter(|channel| managed(channel))
+            .collect();
+
+        let mut changes = ChannelChanges::default();
+        for channel in &wanted {
+            if !current.contains(channel) {
+                self.resonate_on(channel.clone()).await;
+                changes.added.push(channel.clone());
+            }
+        }
+        for channel in current {
+            if !wanted.contains(&channel) && !changes.removed.contains(&channel) {
+                self.stop_resonating(&channel);
+                changes.removed.push(channel);
+            }
+        }
+        self.config.resonant_channels = self.resonant_channels();
+
+        if !changes.is_empty() {
+            info!(
+                "Vibrator {} reconciled channels (+{:?}, -{:?})",
+                self.config.name, changes.added, changes.removed
+            );
+        }
+        changes
+    }
+
+    /// Drop receivers whose handles were cancelled
+    fn prune_cancelled(&mut self) {
+        self.receivers
+            .retain(|subscription| !subscription.cancelled.load(Ordering::SeqCst));
     }
 
     /// Resonates on multiple channels
-    pub async fn resonate_on_many(&mut self, channels: Vec<Channel>) {
+    pub async fn resonate_on_many(&mut self, channels: Vec<Channel>) -> Vec<ResonanceHandle> {
+        let mut handles = Vec::with_capacity(channels.len());
         for channel in channels {
-            self.resonate_on(channel).await;
+            handles.push(self.resonate_on(channel).await);
         }
+        handles
     }
 
     /// Resonate on a frequency hopping set derived from a base channel
-    pub async fn resonate_hopping(&mut self, base: Channel, hop_count: u16) {
+    pub async fn resonate_hopping(
+        &mut self,
+        base: Channel,
+        hop_count: u16,
+    ) -> Vec<ResonanceHandle> {
         let hop_channels = base.hop_set(hop_count);
-        self.resonate_on_many(hop_channels).await;
+        self.resonate_on_many(hop_channels).await
     }
 
     /// Emit a wave (send a message)
-    pub async fn emit(&self, mut wave: Wave) -> Result<()> {
+    pub async fn emit(&self, wave: Wave) -> Result<EmitReceipt> {
+        self.emit_with(wave, EmitOptions::default()).await
+    }
+
+    /// Emit with per-wave options; see [`Aether::emit_with`]
+    pub async fn emit_with(&self, mut wave: Wave, options: EmitOptions) -> Result<EmitReceipt> {
         if let Some(token) = &self.config.auth_token {
             wave.set_auth_token(token.clone());
         }
         debug!("Vibrator {} emitted wave {}", self.config.name, wave.id());
-        self.aether.emit(wave).await
+        self.aether
+            .emit_from(wave, Some(&self.config.name), options)
+            .await
     }
 
     /// Build and emit a wave
@@ -138,15 +807,48 @@ impl Vibrator {
         &self,
         channel: impl Into<Channel>,
         payload: serde_json::Value,
-    ) -> Result<()> {
+    ) -> Result<EmitReceipt> {
         let wave = Wave::builder(channel)
             .payload(payload)
-            .source(self.config.name.clone())
+            .source(Arc::clone(&self.source))
+            .timestamp(self.aether.clock().now())
             .build();
 
         self.emit(wave).await
     }
 
+    /// Send a command and wait for its outcome; see [`VibratorEmitter::send_command`]
+    pub async fn send_command(
+        &self,
+        channel: impl Into<Channel>,
+        payload: serde_json::Value,
+    ) -> Result<CommandOutcome> {
+        self.emitter().send_command(channel, payload).await
+    }
+
+    /// Emit and wait for reflections; see [`VibratorEmitter::emit_reflected`]
+    pub async fn emit_reflected(
+        &self,
+        wave: Wave,
+        expected: usize,
+        timeout: Duration,
+    ) -> Result<Vec<Reflection>> {
+        self.emitter().emit_reflected(wave, expected, timeout).await
+    }
+
+    /// Query every responder on a channel; see [`VibratorEmitter::scatter_gather`]
+    pub async fn scatter_gather(
+        &self,
+        channel: impl Into<Channel>,
+        payload: serde_json::Value,
+        min_responses: usize,
+        timeout: Duration,
+    ) -> Result<Vec<CommandReply>> {
+        self.emitter()
+            .scatter_gather(channel, payload, min_responses, timeout)
+            .await
+    }
+
     /// Build and emit a frequency-hopped wave
     pub async fn emit_hopping_wave(
         &self,
@@ -154,7 +856,7 @@ impl Vibrator {
         hop_index: u16,
         hop_count: u16,
         payload: serde_json::Value,
-    ) -> Result<()> {
+    ) -> Result<EmitReceipt> {
         let channel = base_channel.into().hop(hop_index, hop_count);
         self.emit_wave(channel, payload).await
     }
@@ -166,17 +868,87 @@ impl Vibrator {
         hop_count: u16,
         hop_interval_ms: u64,
         payload: serde_json::Value,
-    ) -> Result<()> {
+    ) -> Result<EmitReceipt> {
         let base = base_channel.into();
-        let channel = base.hop_now(hop_count, hop_interval_ms);
+        let channel = self.config.hop_keys.hop_at_ms(
+            &base,
+            self.aether.clock().now_ms(),
+            hop_count,
+            hop_interval_ms,
+        )?;
+        self.emit_wave(channel, payload).await
+    }
+
+    /// Announce a signed hop schedule on the base channel
+    pub async fn announce_hop_schedule(
+        &self,
+        base: &Channel,
+        schedule: HopSchedule,
+        secret: &[u8],
+    ) -> Result<EmitReceipt> {
+        let announcement =
+            HopAnnouncement::new(base, schedule, self.aether.clock().now_ms(), secret);
+        self.emit(announcement.to_wave(base)).await
+    }
+
+    /// Build and emit a wave on the hop an announced schedule uses now
+    pub async fn emit_scheduled_hop_wave(
+        &self,
+        base: &Channel,
+        schedule: &HopSchedule,
+        payload: serde_json::Value,
+    ) -> Result<EmitReceipt> {
+        let channel = schedule.channel_at(base, self.aether.clock().now_ms());
         self.emit_wave(channel, payload).await
     }
 
+    /// Resonate on the channels a hop-sync receiver should listen on now
+    ///
+    /// Call after each announcement and at every hop interval. Only the base
+    /// channel and its hops are touched.
+    pub async fn follow_hop_schedule(&mut self, sync: &HopSync) -> ChannelChanges {
+        let base = sync.base().clone();
+        let wanted = sync.listening_set(self.aether.clock().now_ms());
+        self.reconcile_where(wanted, |channel| is_hop_of(channel, &base))
+            .await
+    }
+
+    /// Resonate on the live time-based hop of `base`
+    ///
+    /// Within `grace` of a slot boundary the neighbouring hop is listened on
+    /// too, so waves emitted just before or after the boundary still arrive.
+    /// Call at least once per hop interval.
+    pub async fn follow_time_hopping(
+        &mut self,
+        base: &Channel,
+        hop_count: u16,
+        hop_interval_ms: u64,
+        grace: Duration,
+    ) -> Result<ChannelChanges> {
+        let wanted = self.config.hop_keys.hops_near(
+            base,
+            self.aether.clock().now_ms(),
+            hop_count,
+            hop_interval_ms,
+            grace.as_millis() as u64,
+        )?;
+        Ok(self
+            .reconcile_where(wanted, |channel| {
+                is_hop_of(channel, base) && channel != base
+            })
+            .await)
+    }
+
     /// Build and emit a wave with raw bytes payload (zero-copy)
-    pub async fn emit_bytes(&self, channel: impl Into<Channel>, payload: Bytes) -> Result<()> {
+    pub async fn emit_bytes(
+        &self,
+        channel: impl Into<Channel>,
+        payload: Bytes,
+    ) -> Result<EmitReceipt> {
         let wave = Wave::builder(channel)
             .payload_bytes(payload)
-            .source(self.config.name.clone())
+            .source(Arc::clone(&self.source))
+            .timestamp(self.aether.clock().now())
             .build();
 
         self.emit(wave).await
@@ -185,54 +957,151 @@ impl Vibrator {
     /// Create a lightweight emitter handle for concurrent tasks
     pub fn emitter(&self) -> VibratorEmitter {
         VibratorEmitter {
-            name: self.config.name.clone(),
+            name: Arc::clone(&self.source),
             aether: self.aether.clone(),
             auth_token: self.config.auth_token.clone(),
+            hop_keys: self.config.hop_keys.clone(),
+        }
+    }
+
+    /// Handle for pausing/draining this vibrator from another task
+    pub fn control(&self) -> VibratorControl {
+        self.control.clone()
+    }
+
+    /// Stop taking waves off the subscriptions, e.g. during a dependency outage
+    ///
+    /// Subscriptions stay open and waves wait in their buffers, up to
+    /// `buffer_size` per channel; beyond that the oldest are lost as lag.
+    pub fn pause(&self) {
+        self.control.pause();
+    }
+
+    /// Take waves off the subscriptions again, starting with those held while paused
+    pub fn resume(&self) {
+        self.control.resume();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.control.is_paused()
+    }
+
+    /// Leave the layer cleanly before a deploy or scale-down
+    ///
+    /// Stops intake for good, so readiness checks built on `is_draining` fail
+    /// at once, drops every subscription, releases the layer channels no other
+    /// receiver holds (closing their NATS subscriptions) and flushes pending
+    /// publishes and persistence writes. Waves already taken off the subscriptions but not
+    /// yet received are returned for the caller to handle. Fails with
+    /// `Saturated` if the flush does not finish within `grace`.
+    pub async fn drain(&mut self, grace: Duration) -> Result<Vec<Wave>> {
+        self.control.drain();
+        let channels = self.resonant_channels();
+        for subscription in self.receivers.drain(..) {
+            subscription.cancelled.store(true, Ordering::SeqCst);
+        }
+        for channel in &channels {
+            self.aether.release_channel(channel).await;
+        }
+        let held: Vec<Wave> = self.ready.drain(..).chain(self.deferred.drain(..)).collect();
+        info!(
+            "Vibrator {} draining: left {:?}, {} waves held",
+            self.config.name,
+            channels,
+            held.len()
+        );
+
+        match tokio::time::timeout(grace, self.aether.flush()).await {
+            Ok(flushed) => flushed?,
+            Err(_) => {
+                return Err(AetherError::Saturated(format!(
+                    "flush did not finish within {:?}",
+                    grace
+                )))
+            }
         }
+        Ok(held)
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.control.is_draining()
     }
 
     /// Receive the next wave (from any channel)
     pub async fn receive(&mut self) -> Option<Wave> {
-        if self.receivers.is_empty() {
-            return None;
+        let wave = self.next_wave().await?;
+        self.mark_delivered(&wave);
+        self.reflect(&wave).await;
+        Some(wave)
+    }
+
+    /// Receive up to `max_n` waves, waiting at most `max_wait` for them
+    ///
+    /// Returns as soon as `max_n` waves are in; empty if none arrived in time or
+    /// no channels are left. Lets handlers pay per-wave overhead once a batch.
+    pub async fn receive_batch(&mut self, max_n: usize, max_wait: Duration) -> Vec<Wave> {
+        let deadline = tokio::time::Instant::now() + max_wait;
+        let mut batch = Vec::with_capacity(max_n.min(self.config.buffer_size));
+        while batch.len() < max_n {
+            match tokio::time::timeout_at(deadline, self.next_wave()).await {
+                Ok(Some(wave)) => batch.push(wave),
+                Ok(None) | Err(_) => break,
+            }
+        }
+        for wave in &batch {
+            self.mark_delivered(wave);
+            self.reflect(wave).await;
         }
+        metrics::histogram!("aether_receive_batch_size").record(batch.len() as f64);
+        batch
+    }
 
-        // Try non-blocking receive from all receivers
+    async fn next_wave(&mut self) -> Option<Wave> {
         loop {
-            for (channel, receiver) in &mut self.receivers {
-                match receiver.try_recv() {
-                    Ok(wave) => {
-                        // Optionally ignore waves sent by self
-                        if let Some(source) = wave.source() {
-                            if source == self.config.name {
-                                continue;
-                            }
-                        }
-
-                        if wave.amplitude().value() < self.config.noise_floor {
-                            continue;
-                        }
-
-                        debug!(
-                            "Vibrator {} received wave {} from channel {}",
-                            self.config.name,
-                            wave.id(),
-                            channel
-                        );
-                        return Some(wave);
-                    }
-                    Err(broadcast::error::TryRecvError::Empty) => continue,
-                    Err(broadcast::error::TryRecvError::Lagged(skipped)) => {
-                        debug!("Vibrator {} missed {} waves", self.config.name, skipped);
-                        continue;
-                    }
-                    Err(broadcast::error::TryRecvError::Closed) => {
-                        debug!("Channel {} was closed", channel);
-                        continue;
-                    }
+            if self.control.is_draining() {
+                // Hand out what was already taken off the subscriptions, then stop
+                return self.ready.pop_front().or_else(|| self.deferred.pop_front());
+            }
+
+            self.prune_cancelled();
+            if self.receivers.is_empty() {
+                return None;
+            }
+
+            if self.control.is_paused() {
+                // Leave waves buffered while paused
+                tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+                continue;
+            }
+
+            if let Some(wave) = self.ready.pop_front() {
+                return Some(wave);
+            }
+            if let Some(reorder) = &mut self.reorder {
+                collect_ordered(reorder.flush_expired(), &mut self.ready, &mut self.gaps);
+            }
+
+            let channel_lag = self
+                .receivers
+                .iter()
+                .map(|s| s.receiver.len())
+                .max()
+                .unwrap_or(0);
+            if let Some(wave) = self.release_deferred(channel_lag, None) {
+                return Some(wave);
+            }
+
+            // Try non-blocking receive from all receivers
+            for index in 0..self.receivers.len() {
+                if let Ok(Some(wave)) = self.take(index, channel_lag) {
+                    return Some(wave);
                 }
             }
 
+            if !self.ready.is_empty() {
+                continue;
+            }
+
             // If all receivers are empty, wait briefly
             tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
         }
@@ -240,38 +1109,322 @@ impl Vibrator {
 
     /// Receive only from a specific channel
     pub async fn receive_from(&mut self, channel: &Channel) -> Option<Wave> {
-        for (ch, receiver) in &mut self.receivers {
-            if ch == channel {
-                loop {
-                    match receiver.recv().await {
-                        Ok(wave) => {
-                            if let Some(source) = wave.source() {
-                                if source == self.config.name {
-                                    continue;
-                                }
-                            }
-                            if wave.amplitude().value() < self.config.noise_floor {
-                                continue;
-                            }
-                            return Some(wave);
-                        }
-                        Err(_) => return None,
-                    }
-                }
-            }
-        }
-        None
+        let wave = self.next_wave_from(channel).await?;
+        self.mark_delivered(&wave);
+        self.reflect(&wave).await;
+        Some(wave)
     }
 
-    /// Get vibrator name
-    pub fn name(&self) -> &str {
-        &self.config.name
-    }
+    async fn next_wave_from(&mut self, channel: &Channel) -> Option<Wave> {
+        loop {
+            if self.control.is_draining() {
+                return take_on(&mut self.ready, channel)
+                    .or_else(|| take_on(&mut self.deferred, channel));
+            }
 
-    /// Get list of resonant channels
-    pub fn resonant_channels(&self) -> Vec<Channel> {
-        self.receivers.iter().map(|(ch, _)| ch.clone()).collect()
-    }
+            self.prune_cancelled();
+            let index = self
+                .receivers
+                .iter()
+                .position(|subscription| &subscription.channel == channel)?;
+
+            if self.control.is_paused() {
+                tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+                continue;
+            }
+
+            if let Some(wave) = take_on(&mut self.ready, channel) {
+                return Some(wave);
+            }
+            if let Some(reorder) = &mut self.reorder {
+                collect_ordered(reorder.flush_expired(), &mut self.ready, &mut self.gaps);
+            }
+
+            let channel_lag = self.receivers[index].receiver.len();
+            if let Some(wave) = self.release_deferred(channel_lag, Some(channel)) {
+                return Some(wave);
+            }
+
+            match self.take(index, channel_lag) {
+                Ok(Some(wave)) => return Some(wave),
+                Ok(None) => continue,
+                Err(Idle::Empty) => {
+                    tokio::time::sleep(tokio::time::Duration::from_millis(10)).await
+                }
+                Err(Idle::Closed) => return None,
+            }
+        }
+    }
+
+    /// Feed the load shedder; once it stops shedding, hand back a deferred wave
+    /// (only one on `channel`, when given)
+    fn release_deferred(&mut self, channel_lag: usize, channel: Option<&Channel>) -> Option<Wave> {
+        let shedder = self.config.load_shedder.as_ref()?;
+        if shedder.update(channel_lag) {
+            return None;
+        }
+        let wave = match channel {
+            Some(channel) => take_on(&mut self.deferred, channel),
+            None => self.deferred.pop_front(),
+        }?;
+        metrics::gauge!("aether_shed_deferred_waves").set(self.deferred.len() as f64);
+        Some(wave)
+    }
+
+    /// Take one wave off subscription `index` and run it through every
+    /// delivery stage: self-echo, stale epochs, catch-up dedup, noise floor,
+    /// filter, version routing, transforms, load shedding and reordering
+    ///
+    /// `Ok(None)` means the wave was dropped, deferred or held for reordering.
+    fn take(
+        &mut self,
+        index: usize,
+        channel_lag: usize,
+    ) -> std::result::Result<Option<Wave>, Idle> {
+        let subscription = &mut self.receivers[index];
+        if subscription.replay.as_ref().is_some_and(ReplayWindow::is_closed) {
+            subscription.replay = None;
+        }
+        let channel = subscription.channel.clone();
+        let mut wave = match subscription.receiver.try_recv() {
+            Ok(wave) => {
+                // Already delivered by `catch_up_then_follow`
+                let replay = subscription.replay.as_mut();
+                if replay.is_some_and(|replay| replay.repeats(&wave)) {
+                    return Ok(None);
+                }
+                wave
+            }
+            Err(broadcast::error::TryRecvError::Empty) => return Err(Idle::Empty),
+            Err(broadcast::error::TryRecvError::Lagged(skipped)) => {
+                if let Some(replay) = &mut subscription.replay {
+                    replay.skipped(skipped);
+                }
+                debug!("Vibrator {} missed {} waves", self.config.name, skipped);
+                self.aether
+                    .event_bus()
+                    .publish(AetherEvent::SubscriberLagged {
+                        subscriber: self.config.name.clone(),
+                        channel: channel.name().to_string(),
+                        skipped,
+                    });
+                return Ok(None);
+            }
+            Err(broadcast::error::TryRecvError::Closed) => {
+                debug!("Channel {} was closed", channel);
+                return Err(Idle::Closed);
+            }
+        };
+
+        // Optionally ignore waves sent by self
+        if wave.source() == Some(self.config.name.as_str()) {
+            return Ok(None);
+        }
+
+        if let Some(sampler) = &self.config.sampler {
+            if !sampler.observes(&wave) {
+                return Ok(None);
+            }
+        }
+
+        if self.config.discard_stale_epochs {
+            match self.epochs.observe(&wave) {
+                EpochCheck::Current => {}
+                EpochCheck::Stale { current } => {
+                    debug!(
+                        "Vibrator {} dropped wave {} from a stale epoch (source now at {})",
+                        self.config.name,
+                        wave.id(),
+                        current
+                    );
+                    metrics::counter!("aether_stale_epoch_waves_total").increment(1);
+                    return Ok(None);
+                }
+                EpochCheck::Restarted { previous } => {
+                    let source = wave.source().unwrap_or_default().to_string();
+                    info!(
+                        "Vibrator {} saw {} restart (epoch {} -> {:?})",
+                        self.config.name,
+                        source,
+                        previous,
+                        wave.epoch()
+                    );
+                    self.aether
+                        .event_bus()
+                        .publish(AetherEvent::SourceRestarted {
+                            subscriber: self.config.name.clone(),
+                            source,
+                            previous_epoch: previous,
+                            epoch: wave.epoch().unwrap_or_default(),
+                        });
+                }
+            }
+        }
+
+        if !self.config.hears(&mut wave) {
+            return Ok(None);
+        }
+
+        if !passes(&self.receivers[index].filter, &wave) {
+            return Ok(None);
+        }
+
+        // Another version of this service handles it
+        if let Some(router) = &self.config.version_router {
+            if !router.admits(&wave) {
+                return Ok(None);
+            }
+        }
+
+        let Some(wave) = transform(&self.config, wave) else {
+            return Ok(None);
+        };
+
+        if let Some(shedder) = &self.config.load_shedder {
+            match shedder.admit(&wave, channel_lag) {
+                Admission::Accept => {}
+                Admission::Reject => return Ok(None),
+                Admission::Defer => {
+                    defer(&mut self.deferred, wave, shedder.defer_capacity());
+                    return Ok(None);
+                }
+            }
+        }
+
+        debug!(
+            "Vibrator {} received wave {} from channel {}",
+            self.config.name,
+            wave.id(),
+            channel
+        );
+        match &mut self.reorder {
+            Some(reorder) => {
+                collect_ordered(reorder.push(wave), &mut self.ready, &mut self.gaps);
+                Ok(None)
+            }
+            None => Ok(Some(wave)),
+        }
+    }
+
+    /// Acknowledge delivery of `wave` when reflections are on
+    async fn reflect(&self, wave: &Wave) {
+        if !self.config.reflect || is_echo_channel(wave.channel()) {
+            return;
+        }
+        let reflection = Reflection {
+            wave_id: *wave.id(),
+            receiver: self.config.name.clone(),
+            channel: wave.channel().name().to_string(),
+            received_at: self.aether.clock().now(),
+        };
+        if let Err(e) = self.emit(reflection.to_wave(&self.source)).await {
+            debug!(
+                "Vibrator {} could not reflect wave {}: {}",
+                self.config.name,
+                wave.id(),
+                e
+            );
+        }
+    }
+
+    /// Get vibrator name
+    pub fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    /// Get list of resonant channels
+    pub fn resonant_channels(&self) -> Vec<Channel> {
+        self.receivers
+            .iter()
+            .filter(|subscription| !subscription.cancelled.load(Ordering::SeqCst))
+            .map(|subscription| subscription.channel.clone())
+            .collect()
+    }
+
+    /// Sequence gaps seen by the reorder buffer since the last call
+    pub fn take_gaps(&mut self) -> Vec<SequenceGap> {
+        std::mem::take(&mut self.gaps)
+    }
+}
+
+impl Drop for Vibrator {
+    fn drop(&mut self) {
+        self.aether.unregister_vibrator(&self.config.name);
+    }
+}
+
+/// Why a subscription yielded nothing at all
+enum Idle {
+    Empty,
+    Closed,
+}
+
+/// Remove the oldest queued wave heard on `channel` (or its hop channels)
+fn take_on(queue: &mut VecDeque<Wave>, channel: &Channel) -> Option<Wave> {
+    let position = queue
+        .iter()
+        .position(|wave| wave.channel().matches(channel) || is_hop_of(wave.channel(), channel))?;
+    queue.remove(position)
+}
+
+/// Whether `channel` is `base` or one of its hop channels
+fn is_hop_of(channel: &Channel, base: &Channel) -> bool {
+    let Some(rest) = channel.name().strip_prefix(base.name()) else {
+        return false;
+    };
+    rest.is_empty()
+        || rest
+            .strip_prefix(".hop")
+            .is_some_and(|index| !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Apply a subscription filter, counting what it drops
+fn passes(filter: &Option<WaveFilter>, wave: &Wave) -> bool {
+    let Some(filter) = filter else {
+        return true;
+    };
+    let passed = filter.matches(wave);
+    if !passed {
+        metrics::counter!("aether_filtered_waves_total").increment(1);
+    }
+    passed
+}
+
+/// Run delivery-side transformers; a failing stage drops the wave
+fn transform(config: &VibratorConfig, wave: Wave) -> Option<Wave> {
+    if config.transforms.is_empty() {
+        return Some(wave);
+    }
+    let id = *wave.id();
+    match config.transforms.apply(wave) {
+        Ok(wave) => wave,
+        Err(e) => {
+            warn!(
+                "Vibrator {} dropped wave {}: transform failed: {}",
+                config.name, id, e
+            );
+            None
+        }
+    }
+}
+
+/// Hold a shed wave back, dropping the oldest one when full
+fn defer(deferred: &mut VecDeque<Wave>, wave: Wave, capacity: usize) {
+    if deferred.len() >= capacity.max(1) {
+        deferred.pop_front();
+        metrics::counter!("aether_shed_waves_total", "action" => "dropped").increment(1);
+    }
+    deferred.push_back(wave);
+    metrics::gauge!("aether_shed_deferred_waves").set(deferred.len() as f64);
+}
+
+fn collect_ordered(out: Vec<Ordered>, ready: &mut VecDeque<Wave>, gaps: &mut Vec<SequenceGap>) {
+    for item in out {
+        match item {
+            Ordered::Wave(wave) => ready.push_back(*wave),
+            Ordered::Gap(gap) => gaps.push(gap),
+        }
+    }
 }
 
 impl VibratorEmitter {
@@ -279,22 +1432,33 @@ impl VibratorEmitter {
         &self.name
     }
 
-    pub async fn emit(&self, wave: Wave) -> Result<()> {
+    /// Payload with the layer's redaction rules applied, for logging
+    pub fn redacted_payload<'w>(&self, wave: &'w Wave) -> std::borrow::Cow<'w, serde_json::Value> {
+        self.aether.redactor().payload(wave)
+    }
+
+    pub async fn emit(&self, wave: Wave) -> Result<EmitReceipt> {
+        self.emit_with(wave, EmitOptions::default()).await
+    }
+
+    /// Emit with per-wave options; see [`Aether::emit_with`]
+    pub async fn emit_with(&self, wave: Wave, options: EmitOptions) -> Result<EmitReceipt> {
         let mut wave = wave;
         if let Some(token) = &self.auth_token {
             wave.set_auth_token(token.clone());
         }
-        self.aether.emit(wave).await
+        self.aether.emit_from(wave, Some(&self.name), options).await
     }
 
     pub async fn emit_wave(
         &self,
         channel: impl Into<Channel>,
         payload: serde_json::Value,
-    ) -> Result<()> {
+    ) -> Result<EmitReceipt> {
         let wave = Wave::builder(channel)
             .payload(payload)
-            .source(self.name.clone())
+            .source(Arc::clone(&self.name))
+            .timestamp(self.aether.clock().now())
             .build();
 
         self.emit(wave).await
@@ -306,7 +1470,7 @@ impl VibratorEmitter {
         hop_index: u16,
         hop_count: u16,
         payload: serde_json::Value,
-    ) -> Result<()> {
+    ) -> Result<EmitReceipt> {
         let channel = base_channel.into().hop(hop_index, hop_count);
         self.emit_wave(channel, payload).await
     }
@@ -317,26 +1481,173 @@ impl VibratorEmitter {
         hop_count: u16,
         hop_interval_ms: u64,
         payload: serde_json::Value,
-    ) -> Result<()> {
+    ) -> Result<EmitReceipt> {
         let base = base_channel.into();
-        let channel = base.hop_now(hop_count, hop_interval_ms);
+        let channel = self.hop_keys.hop_at_ms(
+            &base,
+            self.aether.clock().now_ms(),
+            hop_count,
+            hop_interval_ms,
+        )?;
         self.emit_wave(channel, payload).await
     }
 
-    pub async fn emit_bytes(&self, channel: impl Into<Channel>, payload: Bytes) -> Result<()> {
+    pub async fn emit_bytes(
+        &self,
+        channel: impl Into<Channel>,
+        payload: Bytes,
+    ) -> Result<EmitReceipt> {
         let wave = Wave::builder(channel)
             .payload_bytes(payload)
-            .source(self.name.clone())
+            .source(Arc::clone(&self.name))
+            .timestamp(self.aether.clock().now())
             .build();
 
         self.emit(wave).await
     }
+
+    /// Emit `wave` and collect reflections from up to `expected` receivers
+    ///
+    /// Returns early once `expected` arrived, otherwise whatever came back
+    /// within `timeout`. Errors only if the wave could not be emitted.
+    pub async fn emit_reflected(
+        &self,
+        wave: Wave,
+        expected: usize,
+        timeout: Duration,
+    ) -> Result<Vec<Reflection>> {
+        let mut echoes = self.aether.subscribe(&echo_channel(wave.channel())).await;
+        let wave_id = *wave.id();
+        self.emit(wave).await?;
+        let reflections = echo::await_reflections(&mut echoes, wave_id, expected, timeout).await;
+        metrics::histogram!("aether_reflections_received").record(reflections.len() as f64);
+        Ok(reflections)
+    }
+
+    /// Send a command and wait for its outcome
+    pub async fn send_command(
+        &self,
+        channel: impl Into<Channel>,
+        payload: serde_json::Value,
+    ) -> Result<CommandOutcome> {
+        self.send_command_with_timeout(channel, payload, DEFAULT_COMMAND_TIMEOUT)
+            .await
+    }
+
+    /// Send a command; no response within `timeout` yields `TimedOut`
+    ///
+    /// Errors only if the command itself could not be emitted.
+    pub async fn send_command_with_timeout(
+        &self,
+        channel: impl Into<Channel>,
+        payload: serde_json::Value,
+        timeout: Duration,
+    ) -> Result<CommandOutcome> {
+        let (command_id, mut replies) = self
+            .send_request(channel.into(), WaveType::Command, payload)
+            .await?;
+        let outcome = command::await_reply(&mut replies, command_id, timeout).await;
+        metrics::counter!("aether_commands_total", "outcome" => outcome.label()).increment(1);
+        Ok(outcome)
+    }
+
+    /// Ask every responder on `channel` and collect at least `min_responses`
+    /// answers, waiting the full `timeout` for stragglers
+    pub async fn scatter_gather(
+        &self,
+        channel: impl Into<Channel>,
+        payload: serde_json::Value,
+        min_responses: usize,
+        timeout: Duration,
+    ) -> Result<Vec<CommandReply>> {
+        let policy = GatherPolicy::Quorum(min_responses);
+        self.scatter_gather_with(channel, payload, policy, timeout)
+            .await
+    }
+
+    /// Scatter-gather with an explicit policy; fails with `QuorumNotReached`
+    /// if fewer than `policy.min_responses()` answered in time
+    pub async fn scatter_gather_with(
+        &self,
+        channel: impl Into<Channel>,
+        payload: serde_json::Value,
+        policy: GatherPolicy,
+        timeout: Duration,
+    ) -> Result<Vec<CommandReply>> {
+        let channel = channel.into();
+        let (query_id, mut replies) = self
+            .send_request(channel.clone(), WaveType::Query, payload)
+            .await?;
+        let gathered = command::gather_replies(&mut replies, query_id, policy, timeout).await;
+        metrics::histogram!("aether_scatter_gather_responses").record(gathered.len() as f64);
+        if gathered.len() < policy.min_responses() {
+            return Err(AetherError::QuorumNotReached(format!(
+                "{} of {} responses on {} within {:?}",
+                gathered.len(),
+                policy.min_responses(),
+                channel,
+                timeout
+            )));
+        }
+        Ok(gathered)
+    }
+
+    /// Emit a request whose responses come back on this emitter's reply channel
+    async fn send_request(
+        &self,
+        channel: Channel,
+        wave_type: WaveType,
+        payload: serde_json::Value,
+    ) -> Result<(uuid::Uuid, broadcast::Receiver<Wave>)> {
+        let reply_channel = command::command_reply_channel(&self.name);
+        let replies = self.aether.subscribe(&reply_channel).await;
+        let wave = Wave::builder(channel)
+            .wave_type(wave_type)
+            .payload(payload)
+            .metadata(serde_json::json!({ command::REPLY_TO: reply_channel.name() }))
+            .source(Arc::clone(&self.name))
+            .timestamp(self.aether.clock().now())
+            .build();
+        let request_id = *wave.id();
+        self.emit(wave).await?;
+        Ok((request_id, replies))
+    }
+
+    /// Run `handler` on a command (or query) wave and send the outcome back to its sender
+    pub async fn handle_command<H: CommandHandler + ?Sized>(
+        &self,
+        command: &Wave,
+        handler: &H,
+    ) -> Result<CommandOutcome> {
+        if !matches!(command.wave_type(), WaveType::Command | WaveType::Query) {
+            return Err(AetherError::ValidationFailed(format!(
+                "wave {} on {} is not a command or query",
+                command.id(),
+                command.channel()
+            )));
+        }
+        let outcome = match handler.execute(command).await {
+            Ok(result) => CommandOutcome::Accepted(result),
+            Err(reason) => CommandOutcome::Rejected(reason),
+        };
+        let reply = CommandReply::new(command, &self.name, &outcome);
+        let payload =
+            serde_json::to_value(&reply).map_err(|e| AetherError::CodecError(e.to_string()))?;
+        let wave = Wave::builder(command::reply_channel(command))
+            .wave_type(WaveType::Response)
+            .payload(payload)
+            .source(Arc::clone(&self.name))
+            .timestamp(self.aether.clock().now())
+            .build();
+        self.emit(wave).await?;
+        Ok(outcome)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::aether::AetherConfig;
+    use crate::transform::StripFields;
     use tokio::time::{timeout, Duration};
 
     fn test_aether() -> Aether {
@@ -350,18 +1661,51 @@ mod tests {
     async fn test_vibrator_creation() {
         let aether = test_aether();
         let config = VibratorConfig::new("test-vibrator");
-        let vibrator = Vibrator::new(config, &aether).await;
+        let vibrator = Vibrator::new(config, &aether).await.unwrap();
 
         assert_eq!(vibrator.name(), "test-vibrator");
     }
 
+    #[tokio::test]
+    async fn test_vibrator_config_is_checked_against_the_layer() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            auth_token: Some("secret".to_string()),
+            allowed_sources: vec!["service-alpha".to_string()],
+            ..AetherConfig::default()
+        });
+        let valid = VibratorConfig::new("service-alpha")
+            .with_auth_token(Some("secret".to_string()))
+            .with_channels(vec![Channel::new("orders.*")]);
+        assert!(Vibrator::new(valid.clone(), &aether).await.is_ok());
+
+        let mut config = valid
+            .with_auth_token(Some("stale".to_string()))
+            .with_channels(vec![Channel::new("orders/created")])
+            .with_noise_floor(1.5);
+        config.buffer_size = 0;
+        let Err(AetherError::InvalidVibrator(message)) = Vibrator::new(config, &aether).await
+        else {
+            panic!("invalid config accepted");
+        };
+        assert!(message.starts_with("service-alpha: "));
+        for problem in ["auth token", "orders/created", "noise floor", "buffer size"] {
+            assert!(message.contains(problem), "{}", message);
+        }
+
+        let stranger = VibratorConfig::new("service-gamma").with_auth_token(Some("secret".into()));
+        let err = Vibrator::new(stranger, &aether).await.err().unwrap();
+        assert!(err.to_string().contains("allowed_sources"));
+        assert!(Vibrator::create("service-alpha", &aether).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_vibrator_emit_and_receive() {
         let aether = test_aether();
         let channel = Channel::new("test.communication");
 
-        let mut vibrator1 = Vibrator::create("vibrator-1", &aether).await;
-        let mut vibrator2 = Vibrator::create("vibrator-2", &aether).await;
+        let mut vibrator1 = Vibrator::create("vibrator-1", &aether).await.unwrap();
+        let mut vibrator2 = Vibrator::create("vibrator-2", &aether).await.unwrap();
 
         vibrator1.resonate_on(channel.clone()).await;
         vibrator2.resonate_on(channel.clone()).await;
@@ -380,6 +1724,105 @@ mod tests {
         assert!(wave.is_some());
     }
 
+    #[tokio::test]
+    async fn test_late_joiner_receives_retained_wave() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            retained_channels: vec!["inventory.*".to_string()],
+            ..AetherConfig::default()
+        });
+        let sender = Vibrator::create("sender", &aether).await.unwrap();
+        for stock in [100, 99] {
+            sender
+                .emit_wave("inventory.level", serde_json::json!({"ItemA": stock}))
+                .await
+                .unwrap();
+        }
+        let level = aether.last_wave(&Channel::new("inventory.level")).unwrap();
+        assert_eq!(level.payload()["ItemA"], 99);
+
+        let mut late = Vibrator::new(
+            VibratorConfig::new("late")
+                .with_channels(vec![Channel::new("inventory.*")])
+                .with_retained(true),
+            &aether,
+        )
+        .await
+        .unwrap();
+        let wave = timeout(Duration::from_millis(50), late.receive())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(wave.payload()["ItemA"], 99);
+        assert!(timeout(Duration::from_millis(50), late.receive()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_vibrator_sequences_are_per_channel_and_source() {
+        let aether = test_aether();
+        let orders = Channel::new("orders.sequenced");
+        let metrics = Channel::new("metrics.sequenced");
+
+        let mut receiver = Vibrator::new(
+            VibratorConfig::new("receiver").with_reorder_window(Duration::from_millis(50)),
+            &aether,
+        )
+        .await
+        .unwrap();
+        let sender = Vibrator::create("sender", &aether).await.unwrap();
+        receiver.resonate_on(orders.clone()).await;
+        receiver.resonate_on(metrics.clone()).await;
+
+        for channel in [&orders, &orders, &metrics, &orders] {
+            sender
+                .emit_wave(channel.clone(), serde_json::json!({}))
+                .await
+                .unwrap();
+        }
+
+        let mut seen = Vec::new();
+        for _ in 0..4 {
+            let wave = receiver.receive().await.unwrap();
+            seen.push((wave.channel().name().to_string(), wave.sequence().unwrap()));
+        }
+        seen.sort();
+        assert_eq!(
+            seen,
+            vec![
+                ("metrics.sequenced".to_string(), 1),
+                ("orders.sequenced".to_string(), 1),
+                ("orders.sequenced".to_string(), 2),
+                ("orders.sequenced".to_string(), 3),
+            ]
+        );
+        assert!(receiver.take_gaps().is_empty());
+
+        // A second source on the same channel is its own stream, without gaps
+        let other = Vibrator::create("other-sender", &aether).await.unwrap();
+        for sender in [&sender, &other, &sender, &other] {
+            sender
+                .emit_wave(orders.clone(), serde_json::json!({}))
+                .await
+                .unwrap();
+        }
+        let mut seen = Vec::new();
+        for _ in 0..4 {
+            let wave = receiver.receive().await.unwrap();
+            seen.push((wave.source().unwrap().to_string(), wave.sequence().unwrap()));
+        }
+        seen.sort();
+        assert_eq!(
+            seen,
+            vec![
+                ("other-sender".to_string(), 1),
+                ("other-sender".to_string(), 2),
+                ("sender".to_string(), 4),
+                ("sender".to_string(), 5),
+            ]
+        );
+        assert!(receiver.take_gaps().is_empty());
+    }
+
     #[tokio::test]
     async fn test_vibrator_noise_floor_filters_low_amplitude() {
         let aether = test_aether();
@@ -389,8 +1832,9 @@ mod tests {
             VibratorConfig::new("receiver").with_noise_floor(0.5),
             &aether,
         )
-        .await;
-        let sender = Vibrator::create("sender", &aether).await;
+        .await
+        .unwrap();
+        let sender = Vibrator::create("sender", &aether).await.unwrap();
 
         receiver.resonate_on(channel.clone()).await;
 
@@ -405,6 +1849,208 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_vibrator_channel_noise_floor_overrides_global() {
+        let aether = test_aether();
+        let config = VibratorConfig::new("receiver")
+            .with_noise_floor(0.2)
+            .with_channel_noise_floor("payments.>", 0.0)
+            .with_channel_noise_floor("telemetry.*", 0.8)
+            .with_channel_noise_floor("telemetry.cpu", 0.0);
+        assert_eq!(config.noise_floor_for(&Channel::new("telemetry.cpu")), 0.8);
+        assert_eq!(config.noise_floor_for(&Channel::new("orders.created")), 0.2);
+
+        let mut receiver = Vibrator::new(config, &aether).await.unwrap();
+        let sender = Vibrator::create("sender", &aether).await.unwrap();
+        for channel in ["payments.captured", "telemetry.cpu", "orders.created"] {
+            receiver.resonate_on(Channel::new(channel)).await;
+        }
+
+        for (channel, amplitude) in [
+            ("telemetry.cpu", 0.5),
+            ("orders.created", 0.1),
+            ("payments.captured", 0.05),
+            ("telemetry.cpu", 0.9),
+            ("orders.created", 0.5),
+        ] {
+            let wave = Wave::builder(channel)
+                .payload(serde_json::json!({ "amplitude": amplitude }))
+                .amplitude(amplitude)
+                .build();
+            sender.emit(wave).await.unwrap();
+        }
+
+        let mut heard = Vec::new();
+        while let Ok(Some(wave)) = timeout(Duration::from_millis(50), receiver.receive()).await {
+            heard.push(wave.channel().name().to_string());
+        }
+        heard.sort();
+        assert_eq!(
+            heard,
+            ["orders.created", "payments.captured", "telemetry.cpu"]
+        );
+
+        let bad = VibratorConfig::new("receiver").with_channel_noise_floor("metrics.>", 2.0);
+        assert!(Vibrator::new(bad, &aether).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_vibrator_resonance_amplifies_before_noise_floor() {
+        let aether = test_aether();
+        let config = VibratorConfig::new("receiver")
+            .with_noise_floor(0.4)
+            .with_channel_resonance("payments.>", Resonance::Strong)
+            .with_channel_resonance("orders.*", Resonance::Weak);
+        let mut receiver = Vibrator::new(config, &aether).await.unwrap();
+        let sender = Vibrator::create("sender", &aether).await.unwrap();
+        receiver
+            .resonate_on(Channel::new("payments.captured"))
+            .await;
+        receiver.resonate_on(Channel::new("orders.created")).await;
+
+        for channel in ["orders.created", "payments.captured"] {
+            let wave = Wave::builder(channel).amplitude(0.3).build();
+            sender.emit(wave).await.unwrap();
+        }
+
+        let wave = receiver.receive().await.unwrap();
+        assert_eq!(wave.channel().name(), "payments.captured");
+        // 0.3, attenuated once on emit, then the strong gain
+        assert!((wave.amplitude().value() - 0.3 * 0.95 * 1.5).abs() < 1e-9);
+        assert!(timeout(Duration::from_millis(50), receiver.receive())
+            .await
+            .is_err());
+
+        let loud = Wave::builder("payments.captured").amplitude(0.9).build();
+        sender.emit(loud).await.unwrap();
+        assert_eq!(receiver.receive().await.unwrap().amplitude().value(), 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_vibrator_sampler_drops_unsampled_waves_on_receipt() {
+        let aether = test_aether();
+        let sampler = ObserveSampler::new(&crate::sampling::ObserveSamplingConfig {
+            ratio: 1.0,
+            channels: [("telemetry.>".to_string(), 0.0)].into_iter().collect(),
+        });
+        let config = VibratorConfig::new("receiver").with_sampler(Some(sampler));
+        let mut receiver = Vibrator::new(config, &aether).await.unwrap();
+        let sender = Vibrator::create("sender", &aether).await.unwrap();
+        receiver.resonate_on(Channel::new(">")).await;
+
+        for channel in ["telemetry.cpu", "payments.captured"] {
+            sender.emit(Wave::builder(channel).build()).await.unwrap();
+        }
+
+        let wave = receiver.receive().await.unwrap();
+        assert_eq!(wave.channel().name(), "payments.captured");
+        assert!(timeout(Duration::from_millis(50), receiver.receive())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_vibrator_natural_frequency_amplifies_resonant_channels() {
+        let aether = test_aether();
+        let tuned = Wave::builder("alerts.critical").build();
+        let config = VibratorConfig::new("receiver")
+            .with_noise_floor(0.4)
+            .with_natural_frequency(Some(PhysicsEngine::estimate_frequency(&tuned)));
+        let mut receiver = Vibrator::new(config, &aether).await.unwrap();
+        let sender = Vibrator::create("sender", &aether).await.unwrap();
+        receiver.resonate_on(Channel::new("alerts.critical")).await;
+        receiver.resonate_on(Channel::new("alerts.info")).await;
+        assert_eq!(
+            PhysicsEngine::check_resonance(
+                &Wave::builder("alerts.info").build(),
+                PhysicsEngine::estimate_frequency(&tuned)
+            ),
+            Resonance::Weak
+        );
+
+        for channel in ["alerts.info", "alerts.critical"] {
+            let wave = Wave::builder(channel).amplitude(0.3).build();
+            sender.emit(wave).await.unwrap();
+        }
+
+        // Only the channel on the vibrator's frequency is lifted over the floor
+        let wave = receiver.receive().await.unwrap();
+        assert_eq!(wave.channel().name(), "alerts.critical");
+        assert!((wave.amplitude().value() - 0.3 * 0.95 * 1.5).abs() < 1e-9);
+        assert!(timeout(Duration::from_millis(50), receiver.receive())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_vibrator_filtered_resonance_drops_unmatched_waves() {
+        let aether = test_aether();
+        let mut receiver = Vibrator::create("receiver", &aether).await.unwrap();
+        let sender = Vibrator::create("sender", &aether).await.unwrap();
+        let eu = WaveFilter::parse(r#"/region == "eu""#).unwrap();
+        receiver
+            .resonate_on_filtered(Channel::new("orders.*"), eu)
+            .await;
+
+        for region in ["us", "eu", "apac"] {
+            sender
+                .emit_wave(
+                    Channel::new("orders.created"),
+                    serde_json::json!({ "region": region }),
+                )
+                .await
+                .unwrap();
+        }
+
+        let wave = timeout(Duration::from_millis(200), receiver.receive())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(wave.payload()["region"], "eu");
+        assert!(timeout(Duration::from_millis(50), receiver.receive())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_transforms_rewrite_on_publish_and_delivery() {
+        let aether = test_aether().with_transforms(
+            TransformPipeline::new().stage("payments.*", StripFields::new(["/card"])),
+        );
+        let mut receiver = Vibrator::new(
+            VibratorConfig::new("receiver")
+                .with_channels(vec![Channel::new("payments.>")])
+                .with_transforms(TransformPipeline::new().stage(
+                    "payments.completed",
+                    |_: &Wave, mut payload: serde_json::Value| {
+                        payload["cents"] =
+                            (payload["amount"].as_f64().unwrap_or(0.0) * 100.0).into();
+                        Ok(Some(payload))
+                    },
+                )),
+            &aether,
+        )
+        .await
+        .unwrap();
+        let sender = Vibrator::create("sender", &aether).await.unwrap();
+        sender
+            .emit_wave(
+                Channel::new("payments.completed"),
+                serde_json::json!({"amount": 1.5, "card": "4111"}),
+            )
+            .await
+            .unwrap();
+
+        let wave = timeout(Duration::from_millis(200), receiver.receive())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            wave.payload(),
+            &serde_json::json!({"amount": 1.5, "cents": 150.0})
+        );
+    }
+
     #[tokio::test]
     async fn test_vibrator_time_hopping_emit_is_received() {
         let aether = test_aether();
@@ -412,10 +2058,10 @@ mod tests {
         let hop_count = 4;
         let hop_interval_ms = 50;
 
-        let mut receiver = Vibrator::create("receiver", &aether).await;
+        let mut receiver = Vibrator::create("receiver", &aether).await.unwrap();
         receiver.resonate_hopping(base.clone(), hop_count).await;
 
-        let sender = Vibrator::create("sender", &aether).await;
+        let sender = Vibrator::create("sender", &aether).await.unwrap();
         sender
             .emit_time_hopping_wave(
                 base.clone(),
@@ -436,4 +2082,458 @@ mod tests {
         let hops = base.hop_set(hop_count);
         assert!(hops.iter().any(|h| h.name() == wave.channel().name()));
     }
+
+    #[tokio::test]
+    async fn test_control_resume_wakes_a_blocked_receive() {
+        let aether = test_aether();
+        let channel = Channel::new("pause.test");
+
+        let mut receiver = Vibrator::create("receiver", &aether).await.unwrap();
+        receiver.resonate_on(channel.clone()).await;
+        let control = receiver.control();
+        control.pause();
+
+        let sender = Vibrator::create("sender", &aether).await.unwrap();
+        sender
+            .emit_wave(channel.clone(), serde_json::json!({"msg": "held"}))
+            .await
+            .unwrap();
+
+        // The receive loop runs elsewhere; only the shared handle can resume it
+        let receiving = tokio::spawn(async move { receiver.receive().await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!receiving.is_finished());
+
+        control.resume();
+        assert!(!control.is_paused());
+        let wave = timeout(Duration::from_millis(100), receiving)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(wave.payload()["msg"], "held");
+    }
+
+    #[tokio::test]
+    async fn test_receive_from_holds_waves_while_paused() {
+        let aether = test_aether();
+        let channel = Channel::new("shipping.booked");
+        let mut receiver = Vibrator::create("shipping", &aether).await.unwrap();
+        receiver.resonate_on(channel.clone()).await;
+        let sender = Vibrator::create("orders", &aether).await.unwrap();
+
+        receiver.pause();
+        sender
+            .emit_wave(channel.clone(), serde_json::json!({"parcel": 1}))
+            .await
+            .unwrap();
+        assert!(
+            timeout(Duration::from_millis(50), receiver.receive_from(&channel))
+                .await
+                .is_err()
+        );
+
+        receiver.resume();
+        let wave = timeout(Duration::from_millis(100), receiver.receive_from(&channel))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(wave.payload()["parcel"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_receive_from_skips_waves_already_replayed() {
+        let path = std::env::temp_dir().join(format!("aether-catch-up-{}", Uuid::new_v4()));
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            persistence_enabled: true,
+            persistence_path: path.to_string_lossy().into_owned(),
+            ..AetherConfig::default()
+        });
+        let channel = Channel::new("ledger.posted");
+        let sender = Vibrator::create("ledger", &aether).await.unwrap();
+        sender
+            .emit_wave(channel.clone(), serde_json::json!({ "entry": 0 }))
+            .await
+            .unwrap();
+
+        let mut receiver = Vibrator::create("balances", &aether).await.unwrap();
+        assert_eq!(
+            receiver
+                .catch_up_then_follow(channel.clone())
+                .await
+                .unwrap(),
+            1
+        );
+        let replay = receiver.receive_from(&channel).await.unwrap();
+        assert_eq!(replay.payload()["entry"], 0);
+
+        // Logged after the subscription, so both replayed and still due live
+        let duplicate = Wave::builder(channel.clone())
+            .payload(serde_json::json!({ "entry": 1 }))
+            .source("ledger")
+            .build();
+        receiver.receivers[0].replay = Some(ReplayWindow {
+            ids: HashSet::from([*duplicate.id()]),
+            pending: 1,
+            grace_until: Instant::now() + REPLAY_GRACE,
+        });
+        aether.emit(duplicate).await.unwrap();
+        sender
+            .emit_wave(channel.clone(), serde_json::json!({ "entry": 2 }))
+            .await
+            .unwrap();
+
+        let live = timeout(Duration::from_millis(100), receiver.receive_from(&channel))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(live.payload()["entry"], 2);
+        assert!(receiver.receivers[0].replay.is_none());
+
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[tokio::test]
+    async fn test_replay_window_forgets_ids_that_never_arrive_live() {
+        let aether = test_aether();
+        let channel = Channel::new("ledger.posted");
+        let mut receiver = Vibrator::create("balances", &aether).await.unwrap();
+        receiver.resonate_on(channel.clone()).await;
+        // Replayed, but its live copy was lost to lag before the vibrator took it
+        receiver.receivers[0].replay = Some(ReplayWindow {
+            ids: HashSet::from([Uuid::new_v4()]),
+            pending: 0,
+            grace_until: Instant::now(),
+        });
+
+        let sender = Vibrator::create("ledger", &aether).await.unwrap();
+        sender
+            .emit_wave(channel.clone(), serde_json::json!({ "entry": 1 }))
+            .await
+            .unwrap();
+        let live = timeout(Duration::from_millis(100), receiver.receive())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(live.payload()["entry"], 1);
+        assert!(receiver.receivers[0].replay.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stop_resonating_and_cancel_handle() {
+        let aether = test_aether();
+        let orders = Channel::new("orders.created");
+        let payments = Channel::new("payments.captured");
+
+        let mut receiver = Vibrator::create("receiver", &aether).await.unwrap();
+        receiver.resonate_on(orders.clone()).await;
+        let handle = receiver.resonate_on(payments.clone()).await;
+
+        assert!(receiver.stop_resonating(&orders));
+        assert!(!receiver.stop_resonating(&orders));
+        assert_eq!(receiver.resonant_channels(), vec![payments.clone()]);
+
+        // Nobody listens on the stopped channel any more, so the wave goes nowhere
+        let sender = Vibrator::create("sender", &aether).await.unwrap();
+        sender
+            .emit_wave(orders, serde_json::json!({}))
+            .await
+            .unwrap();
+        assert_eq!(aether.stats().await.total_waves, 0);
+
+        handle.cancel();
+        assert!(receiver.resonant_channels().is_empty());
+        assert!(receiver.receive().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_channels_adds_and_removes() {
+        let aether = test_aether();
+        let mut receiver = Vibrator::new(
+            VibratorConfig::new("receiver").with_channels(vec![
+                Channel::new("orders.created"),
+                Channel::new("orders.paid"),
+            ]),
+            &aether,
+        )
+        .await
+        .unwrap();
+
+        let changes = receiver
+            .reconcile_channels(&["orders.paid".to_string(), "payments.>".to_string()])
+            .await;
+        assert_eq!(changes.added, vec![Channel::new("payments.>")]);
+        assert_eq!(changes.removed, vec![Channel::new("orders.created")]);
+        assert_eq!(
+            receiver.resonant_channels(),
+            vec![Channel::new("orders.paid"), Channel::new("payments.>")]
+        );
+
+        let sender = Vibrator::create("sender", &aether).await.unwrap();
+        sender
+            .emit_wave("payments.captured", serde_json::json!({}))
+            .await
+            .unwrap();
+        let wave = timeout(Duration::from_millis(100), receiver.receive())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(wave.channel().name(), "payments.captured");
+
+        let unchanged = receiver
+            .reconcile_channels(&["payments.>".to_string(), "orders.paid".to_string()])
+            .await;
+        assert!(unchanged.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_replays_logged_waves_then_follows_live() {
+        let path = std::env::temp_dir().join(format!("aether-catch-up-{}", Uuid::new_v4()));
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            persistence_enabled: true,
+            persistence_path: path.to_string_lossy().into_owned(),
+            ..AetherConfig::default()
+        });
+        let channel = Channel::new("ledger.posted");
+        let sender = Vibrator::create("ledger", &aether).await.unwrap();
+        for entry in 0..2 {
+            sender
+                .emit_wave(channel.clone(), serde_json::json!({ "entry": entry }))
+                .await
+                .unwrap();
+        }
+        sender
+            .emit_wave("ledger.voided", serde_json::json!({}))
+            .await
+            .unwrap();
+
+        let mut receiver = Vibrator::create("balances", &aether).await.unwrap();
+        assert_eq!(receiver.catch_up_then_follow(channel.clone()).await.unwrap(), 2);
+        sender
+            .emit_wave(channel.clone(), serde_json::json!({ "entry": 2 }))
+            .await
+            .unwrap();
+        let received = receiver.receive_batch(4, Duration::from_millis(100)).await;
+        let entries: Vec<_> = received.iter().map(|w| w.payload()["entry"].clone()).collect();
+        assert_eq!(entries, vec![0, 1, 2]);
+        // Everything replayed was logged before the subscription; nothing to dedup
+        assert!(receiver.receivers.iter().all(|s| s.replay.is_none()));
+
+        receiver.checkpoint_catch_up(&channel).unwrap();
+        let mut restarted = Vibrator::create("balances", &aether).await.unwrap();
+        assert_eq!(restarted.catch_up_then_follow(channel).await.unwrap(), 0);
+
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_checkpoint_keeps_waves_not_yet_received() {
+        let path = std::env::temp_dir().join(format!("aether-catch-up-{}", Uuid::new_v4()));
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            persistence_enabled: true,
+            persistence_path: path.to_string_lossy().into_owned(),
+            ..AetherConfig::default()
+        });
+        let channel = Channel::new("ledger.posted");
+        let sender = Vibrator::create("ledger", &aether).await.unwrap();
+        let mut receiver = Vibrator::create("balances", &aether).await.unwrap();
+        assert_eq!(
+            receiver
+                .catch_up_then_follow(channel.clone())
+                .await
+                .unwrap(),
+            0
+        );
+        for entry in 0..3 {
+            sender
+                .emit_wave(channel.clone(), serde_json::json!({ "entry": entry }))
+                .await
+                .unwrap();
+        }
+
+        // Entries 1 and 2 are logged but still buffered on the subscription
+        let first = receiver.receive().await.unwrap();
+        assert_eq!(first.payload()["entry"], 0);
+        receiver.checkpoint_catch_up(&channel).unwrap();
+
+        let mut restarted = Vibrator::create("balances", &aether).await.unwrap();
+        assert_eq!(restarted.catch_up_then_follow(channel).await.unwrap(), 2);
+        let entries: Vec<_> = restarted
+            .receive_batch(2, Duration::from_millis(100))
+            .await
+            .iter()
+            .map(|w| w.payload()["entry"].clone())
+            .collect();
+        assert_eq!(entries, vec![1, 2]);
+
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[tokio::test]
+    async fn test_drain_leaves_channels_and_stops_intake() {
+        let aether = test_aether();
+        let channel = Channel::new("billing.invoiced");
+        let mut receiver = Vibrator::create("billing", &aether).await.unwrap();
+        let handle = receiver.resonate_on(channel.clone()).await;
+        let control = receiver.control();
+
+        let held = receiver.drain(Duration::from_secs(1)).await.unwrap();
+        assert!(held.is_empty());
+        assert!(receiver.is_draining());
+        assert!(handle.is_cancelled());
+        assert!(receiver.resonant_channels().is_empty());
+        // Released by the drain, so there is nothing left to release
+        assert!(!aether.release_channel(&channel).await);
+        assert!(receiver.receive().await.is_none());
+        timeout(Duration::from_millis(100), control.drain_requested())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_drain_requested_ends_receive_after_held_waves() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            retained_channels: vec!["billing.*".to_string()],
+            ..AetherConfig::default()
+        });
+        let channel = Channel::new("billing.refunded");
+        let sender = Vibrator::create("payments", &aether).await.unwrap();
+        sender
+            .emit_wave(channel.clone(), serde_json::json!({"refund": 1}))
+            .await
+            .unwrap();
+        let mut receiver = Vibrator::new(
+            VibratorConfig::new("billing")
+                .with_channels(vec![channel.clone()])
+                .with_retained(true),
+            &aether,
+        )
+        .await
+        .unwrap();
+
+        // A control `drain` only flips the shared flag; the receive loop must end
+        receiver.control().drain();
+        sender
+            .emit_wave(channel, serde_json::json!({"refund": 2}))
+            .await
+            .unwrap();
+        let held = timeout(Duration::from_millis(100), receiver.receive())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(held.payload()["refund"], 1);
+        let done = timeout(Duration::from_millis(100), receiver.receive())
+            .await
+            .unwrap();
+        assert!(done.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_paused_vibrator_holds_waves_until_resumed() {
+        let aether = test_aether();
+        let channel = Channel::new("inventory.updated");
+        let mut receiver = Vibrator::create("inventory", &aether).await.unwrap();
+        receiver.resonate_on(channel.clone()).await;
+        let sender = Vibrator::create("warehouse", &aether).await.unwrap();
+
+        receiver.pause();
+        assert!(receiver.is_paused());
+        for sku in 0..3 {
+            sender
+                .emit_wave(channel.clone(), serde_json::json!({ "sku": sku }))
+                .await
+                .unwrap();
+        }
+        assert!(timeout(Duration::from_millis(50), receiver.receive())
+            .await
+            .is_err());
+        assert_eq!(receiver.resonant_channels(), vec![channel.clone()]);
+
+        receiver.resume();
+        let held = receiver.receive_batch(3, Duration::from_secs(1)).await;
+        assert_eq!(held.len(), 3);
+        assert_eq!(held[0].payload()["sku"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_receive_batch_returns_full_or_timed_out_batches() {
+        let aether = test_aether();
+        let channel = Channel::new("inventory.updated");
+        let mut receiver = Vibrator::create("inventory", &aether).await.unwrap();
+        receiver.resonate_on(channel.clone()).await;
+        let sender = Vibrator::create("warehouse", &aether).await.unwrap();
+        for sku in 0..5 {
+            sender
+                .emit_wave(channel.clone(), serde_json::json!({ "sku": sku }))
+                .await
+                .unwrap();
+        }
+
+        let skus = |batch: Vec<Wave>| {
+            batch
+                .iter()
+                .map(|wave| wave.payload()["sku"].as_i64().unwrap())
+                .collect::<Vec<_>>()
+        };
+        let full = receiver.receive_batch(3, Duration::from_secs(5)).await;
+        assert_eq!(skus(full), [0, 1, 2]);
+        let partial = receiver.receive_batch(3, Duration::from_millis(50)).await;
+        assert_eq!(skus(partial), [3, 4]);
+        assert!(receiver
+            .receive_batch(3, Duration::from_millis(30))
+            .await
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_follow_time_hopping_listens_across_boundary() {
+        let start = chrono::DateTime::from_timestamp_millis(10_190).unwrap();
+        let clock = crate::clock::VirtualClock::at(start);
+        let aether = test_aether().with_clock(Arc::new(clock.clone()));
+        let base = Channel::new("orders");
+        let grace = Duration::from_millis(30);
+
+        let mut receiver = Vibrator::create("receiver", &aether).await.unwrap();
+        receiver
+            .resonate_on(Channel::new("payments.captured"))
+            .await;
+        receiver
+            .follow_time_hopping(&base, 8, 200, grace)
+            .await
+            .unwrap();
+        assert_eq!(
+            receiver.resonant_channels(),
+            vec![
+                Channel::new("payments.captured"),
+                base.hop_at_ms(10_190, 8, 200),
+                base.hop_at_ms(10_200, 8, 200),
+            ]
+        );
+
+        // A sender whose clock is already past the boundary still gets through
+        let sender = Vibrator::create("sender", &aether).await.unwrap();
+        clock.advance(Duration::from_millis(15));
+        sender
+            .emit_time_hopping_wave(base.clone(), 8, 200, serde_json::json!({}))
+            .await
+            .unwrap();
+        assert!(timeout(Duration::from_millis(100), receiver.receive())
+            .await
+            .unwrap()
+            .is_some());
+
+        clock.advance(Duration::from_millis(50));
+        let changes = receiver
+            .follow_time_hopping(&base, 8, 200, grace)
+            .await
+            .unwrap();
+        assert_eq!(changes.removed, vec![base.hop_at_ms(10_190, 8, 200)]);
+        assert!(receiver
+            .resonant_channels()
+            .contains(&Channel::new("payments.captured")));
+    }
 }
@@ -1,9 +1,12 @@
 //! Wave - wave message propagating through the Aether layer
 
 use crate::channel::Channel;
+use crate::clock::{Clock, SystemClock};
+use crate::codec::WaveCodec;
 use bytes::Bytes;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use uuid::Uuid;
 
 /// Wave amplitude (represents importance)
@@ -52,7 +55,7 @@ pub enum WaveType {
 }
 
 /// Wave message propagating through the Aether layer
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Wave {
     /// Schema version
     #[serde(default = "default_schema_version")]
@@ -77,7 +80,7 @@ pub struct Wave {
     amplitude: Amplitude,
 
     /// Source vibrator ID
-    source: Option<String>,
+    source: Option<Arc<str>>,
 
     /// Sent timestamp
     timestamp: DateTime<Utc>,
@@ -93,6 +96,43 @@ pub struct Wave {
     /// Propagation count (hop count)
     #[serde(default)]
     propagation_count: u32,
+
+    /// Per-channel sequence assigned by the emitting Aether layer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sequence: Option<u64>,
+
+    /// Identity of the emitting Aether layer; epochs are only comparable within one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    instance: Option<Uuid>,
+
+    /// Start count of the emitting Aether layer; grows each time the producer restarts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    epoch: Option<u64>,
+
+    /// Position among the source's waves within its epoch, across channels
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source_sequence: Option<u64>,
+
+    /// Fields from newer schema versions, kept so re-serialization is lossless
+    #[serde(flatten, skip_serializing_if = "serde_json::Map::is_empty")]
+    extra: serde_json::Map<String, serde_json::Value>,
+
+    /// Set while the amplitude is left to the emitting layer's policy
+    #[serde(skip)]
+    amplitude_unset: AmplitudeUnset,
+}
+
+/// Whether a wave was built without an amplitude
+///
+/// Never leaves the process and is not part of a wave's identity, so any two
+/// compare equal.
+#[derive(Debug, Clone, Copy, Default)]
+struct AmplitudeUnset(bool);
+
+impl PartialEq for AmplitudeUnset {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
 }
 
 const DEFAULT_MIN_AMPLITUDE: f64 = 0.01;
@@ -113,6 +153,12 @@ impl Wave {
             metadata: serde_json::json!({}),
             phase: 0.0,
             propagation_count: 0,
+            sequence: None,
+            instance: None,
+            epoch: None,
+            source_sequence: None,
+            extra: serde_json::Map::new(),
+            amplitude_unset: AmplitudeUnset(true),
         }
     }
 
@@ -131,9 +177,25 @@ impl Wave {
             metadata: serde_json::json!({}),
             phase: 0.0,
             propagation_count: 0,
+            sequence: None,
+            instance: None,
+            epoch: None,
+            source_sequence: None,
+            extra: serde_json::Map::new(),
+            amplitude_unset: AmplitudeUnset(true),
         }
     }
 
+    /// Encode with the given codec
+    pub fn to_bytes(&self, codec: WaveCodec) -> crate::Result<Vec<u8>> {
+        codec.encode(self)
+    }
+
+    /// Decode with the given codec
+    pub fn from_bytes(bytes: &[u8], codec: WaveCodec) -> crate::Result<Self> {
+        codec.decode(bytes)
+    }
+
     /// Build a wave using the builder pattern
     pub fn builder(channel: impl Into<Channel>) -> WaveBuilder {
         WaveBuilder::new(channel)
@@ -164,6 +226,18 @@ impl Wave {
         self.payload_bytes.as_ref()
     }
 
+    pub(crate) fn payload_mut(&mut self) -> &mut serde_json::Value {
+        &mut self.payload
+    }
+
+    pub fn metadata(&self) -> &serde_json::Value {
+        &self.metadata
+    }
+
+    pub(crate) fn metadata_mut(&mut self) -> &mut serde_json::Value {
+        &mut self.metadata
+    }
+
     pub fn auth_token(&self) -> Option<&str> {
         self.metadata.get("auth_token").and_then(|v| v.as_str())
     }
@@ -177,14 +251,67 @@ impl Wave {
         }
     }
 
+    /// Token presented to a service's control plane, separate from the layer `auth_token`
+    pub fn control_token(&self) -> Option<&str> {
+        self.metadata.get("control_token").and_then(|v| v.as_str())
+    }
+
+    pub fn set_control_token(&mut self, token: impl Into<String>) {
+        let token = token.into();
+        if let Some(obj) = self.metadata.as_object_mut() {
+            obj.insert("control_token".to_string(), serde_json::Value::String(token));
+        } else {
+            self.metadata = serde_json::json!({ "control_token": token });
+        }
+    }
+
+    /// Tenant namespace the wave was emitted into
+    pub fn namespace(&self) -> Option<&str> {
+        self.metadata.get("namespace").and_then(|v| v.as_str())
+    }
+
+    pub fn set_namespace(&mut self, namespace: impl Into<String>) {
+        let namespace = namespace.into();
+        if let Some(obj) = self.metadata.as_object_mut() {
+            obj.insert("namespace".to_string(), serde_json::Value::String(namespace));
+        } else {
+            self.metadata = serde_json::json!({ "namespace": namespace });
+        }
+    }
+
     pub fn amplitude(&self) -> &Amplitude {
         &self.amplitude
     }
 
+    /// Multiply the amplitude by `factor`, capped at 1.0
+    pub(crate) fn amplify(&mut self, factor: f64) {
+        self.amplitude.amplify(factor);
+    }
+
+    /// Give a wave built without an amplitude `default`, once
+    pub(crate) fn default_amplitude(&mut self, default: Option<f64>) {
+        if std::mem::take(&mut self.amplitude_unset.0) {
+            if let Some(default) = default {
+                self.amplitude = Amplitude::new(default);
+            }
+        }
+    }
+
+    /// Lift the amplitude to at least `floor`
+    pub(crate) fn raise_amplitude(&mut self, floor: f64) {
+        if self.amplitude.value() < floor {
+            self.amplitude = Amplitude::new(floor);
+        }
+    }
+
     pub fn source(&self) -> Option<&str> {
         self.source.as_deref()
     }
 
+    pub(crate) fn source_arc(&self) -> Option<&Arc<str>> {
+        self.source.as_ref()
+    }
+
     pub fn timestamp(&self) -> &DateTime<Utc> {
         &self.timestamp
     }
@@ -193,6 +320,41 @@ impl Wave {
         self.propagation_count
     }
 
+    /// Start counting hops again, e.g. when replaying a recorded wave
+    pub fn reset_propagation(&mut self) {
+        self.propagation_count = 0;
+    }
+
+    /// Position on its channel, assigned in `Aether::emit`
+    pub fn sequence(&self) -> Option<u64> {
+        self.sequence
+    }
+
+    pub fn set_sequence(&mut self, sequence: u64) {
+        self.sequence = Some(sequence);
+    }
+
+    /// Emitting layer, assigned in `Aether::emit`; see [`Aether::instance_id`](crate::Aether::instance_id)
+    pub fn instance(&self) -> Option<&Uuid> {
+        self.instance.as_ref()
+    }
+
+    /// Producer start count, assigned in `Aether::emit`; see [`Aether::epoch`](crate::Aether::epoch)
+    pub fn epoch(&self) -> Option<u64> {
+        self.epoch
+    }
+
+    /// Position among the source's waves in its epoch, assigned in `Aether::emit`
+    pub fn source_sequence(&self) -> Option<u64> {
+        self.source_sequence
+    }
+
+    pub fn set_source_position(&mut self, instance: Uuid, epoch: u64, source_sequence: u64) {
+        self.instance = Some(instance);
+        self.epoch = Some(epoch);
+        self.source_sequence = Some(source_sequence);
+    }
+
     /// Schema compatibility check
     pub fn is_compatible(&self) -> bool {
         self.schema_version <= current_schema_version()
@@ -219,11 +381,22 @@ impl Wave {
 
     /// Apply attenuation over time
     pub fn apply_time_decay(&mut self) {
-        let elapsed = Utc::now().signed_duration_since(self.timestamp);
+        self.apply_time_decay_on(&SystemClock);
+    }
+
+    /// Apply attenuation for the time elapsed on the given clock
+    pub fn apply_time_decay_on(&mut self, clock: &dyn Clock) {
+        let elapsed = clock.now().signed_duration_since(self.timestamp);
         let seconds = elapsed.num_seconds() as f64;
         let decay_factor = (-seconds / 60.0).exp(); // Attenuate over 60 seconds
         self.amplitude.attenuate(decay_factor);
     }
+
+    /// Whether the wave is older than `ttl` on the given clock
+    pub fn is_expired_on(&self, clock: &dyn Clock, ttl: std::time::Duration) -> bool {
+        let age = clock.now().signed_duration_since(self.timestamp);
+        age.to_std().is_ok_and(|age| age > ttl)
+    }
 }
 
 /// Wave builder
@@ -232,10 +405,11 @@ pub struct WaveBuilder {
     payload: Option<serde_json::Value>,
     payload_bytes: Option<Bytes>,
     wave_type: WaveType,
-    amplitude: Amplitude,
-    source: Option<String>,
+    amplitude: Option<Amplitude>,
+    source: Option<Arc<str>>,
     metadata: serde_json::Value,
     schema_version: u16,
+    timestamp: Option<DateTime<Utc>>,
 }
 
 impl WaveBuilder {
@@ -245,10 +419,11 @@ impl WaveBuilder {
             payload: None,
             payload_bytes: None,
             wave_type: WaveType::Event,
-            amplitude: Amplitude::default(),
+            amplitude: None,
             source: None,
             metadata: serde_json::json!({}),
             schema_version: current_schema_version(),
+            timestamp: None,
         }
     }
 
@@ -268,11 +443,11 @@ impl WaveBuilder {
     }
 
     pub fn amplitude(mut self, amplitude: f64) -> Self {
-        self.amplitude = Amplitude::new(amplitude);
+        self.amplitude = Some(Amplitude::new(amplitude));
         self
     }
 
-    pub fn source(mut self, source: impl Into<String>) -> Self {
+    pub fn source(mut self, source: impl Into<Arc<str>>) -> Self {
         self.source = Some(source.into());
         self
     }
@@ -287,7 +462,16 @@ impl WaveBuilder {
         self
     }
 
+    /// Stamp the wave with a specific send time (defaults to now)
+    pub fn timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Build the wave; without an explicit amplitude the emitting layer's
+    /// policy gives it its type's default
     pub fn build(self) -> Wave {
+        let amplitude = self.amplitude.unwrap_or_default();
         Wave {
             schema_version: self.schema_version,
             id: Uuid::new_v4(),
@@ -301,12 +485,18 @@ impl WaveBuilder {
                 }
             }),
             payload_bytes: self.payload_bytes,
-            amplitude: self.amplitude,
+            amplitude,
             source: self.source,
-            timestamp: Utc::now(),
+            timestamp: self.timestamp.unwrap_or_else(Utc::now),
             metadata: self.metadata,
             phase: 0.0,
             propagation_count: 0,
+            sequence: None,
+            instance: None,
+            epoch: None,
+            source_sequence: None,
+            extra: serde_json::Map::new(),
+            amplitude_unset: AmplitudeUnset(self.amplitude.is_none()),
         }
     }
 }
@@ -361,4 +551,19 @@ mod tests {
         assert_eq!(wave.wave_type(), &WaveType::Command);
         assert_eq!(wave.source(), Some("service-1"));
     }
+
+    #[test]
+    fn test_time_decay_on_virtual_clock() {
+        let clock = crate::clock::VirtualClock::new();
+        let mut wave = Wave::builder("test.decay").timestamp(clock.now()).build();
+
+        wave.apply_time_decay_on(&clock);
+        assert_eq!(wave.amplitude().value(), 1.0);
+        assert!(!wave.is_expired_on(&clock, std::time::Duration::from_secs(30)));
+
+        clock.advance(std::time::Duration::from_secs(60));
+        wave.apply_time_decay_on(&clock);
+        assert!((wave.amplitude().value() - (-1.0f64).exp()).abs() < 1e-9);
+        assert!(wave.is_expired_on(&clock, std::time::Duration::from_secs(30)));
+    }
 }
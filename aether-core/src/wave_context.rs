@@ -0,0 +1,175 @@
+//! Wave context for logs: which wave the current handler is working on.
+//!
+//! The service loop runs each handler inside [`WaveContext::scope`], which sets
+//! a task-local context and enters an `aether.wave` span carrying `wave_id`,
+//! `channel`, `source` and `correlation_id`. Every line the handler logs sits
+//! inside that span, so the fmt layer prints the fields (JSON logs carry them
+//! under `spans`) and concurrent handlers' logs stay attributable without
+//! passing fields around.
+//!
+//! Waves emitted while a context is set inherit its correlation ID, so one
+//! ID follows a request across services, and name the handled wave as their
+//! causation ID, so the tree of waves a request triggered can be rebuilt.
+
+use crate::wave::Wave;
+use serde::Serialize;
+use std::future::Future;
+use tracing::{info_span, Instrument};
+use uuid::Uuid;
+
+/// Metadata key holding the correlation ID
+pub const CORRELATION_KEY: &str = "correlation_id";
+
+/// Metadata key holding the ID of the wave whose handler emitted this one
+pub const CAUSATION_KEY: &str = "causation_id";
+
+tokio::task_local! {
+    static CURRENT: WaveContext;
+}
+
+/// The wave a handler was invoked for
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WaveContext {
+    pub wave_id: Uuid,
+    pub channel: String,
+    pub source: Option<String>,
+    pub correlation_id: String,
+}
+
+impl WaveContext {
+    pub fn of(wave: &Wave) -> Self {
+        Self {
+            wave_id: *wave.id(),
+            channel: wave.channel().name().to_string(),
+            source: wave.source().map(str::to_string),
+            correlation_id: wave.correlation_id(),
+        }
+    }
+
+    /// Context of the handler running on this task, if any
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    /// Run `fut` with this context set and its fields on every log line
+    pub async fn scope<F: Future>(self, fut: F) -> F::Output {
+        let span = info_span!(
+            "aether.wave",
+            wave_id = %self.wave_id,
+            channel = %self.channel,
+            source = self.source.as_deref().unwrap_or(""),
+            correlation_id = %self.correlation_id,
+        );
+        CURRENT.scope(self, fut.instrument(span)).await
+    }
+}
+
+impl Wave {
+    /// Correlation ID from metadata; a wave that starts a flow is its own
+    pub fn correlation_id(&self) -> String {
+        self.metadata()
+            .get(CORRELATION_KEY)
+            .and_then(|id| id.as_str())
+            .map_or_else(|| self.id().to_string(), str::to_string)
+    }
+
+    /// Wave whose handler emitted this one; `None` for a wave that starts a flow
+    pub fn causation_id(&self) -> Option<Uuid> {
+        self.metadata()
+            .get(CAUSATION_KEY)
+            .and_then(|id| id.as_str())
+            .and_then(|id| id.parse().ok())
+    }
+
+    /// Take correlation and causation from the handler context, unless set
+    pub(crate) fn inherit_context(&mut self) {
+        let Some(context) = WaveContext::current() else {
+            return;
+        };
+        let metadata = self.metadata_mut();
+        if !metadata.is_object() {
+            *metadata = serde_json::json!({});
+        }
+        if let Some(metadata) = metadata.as_object_mut() {
+            metadata
+                .entry(CORRELATION_KEY)
+                .or_insert_with(|| context.correlation_id.into());
+            metadata
+                .entry(CAUSATION_KEY)
+                .or_insert_with(|| context.wave_id.to_string().into());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aether::{Aether, AetherConfig};
+    use crate::channel::Channel;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handler_logs_and_emits_carry_wave_context() {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            ..AetherConfig::default()
+        });
+        let mut downstream = aether.subscribe(&Channel::new("payments.requested")).await;
+        let wave = Wave::builder("orders.created")
+            .source("checkout")
+            .metadata(serde_json::json!({ CORRELATION_KEY: "req-42" }))
+            .build();
+        let wave_id = *wave.id();
+
+        assert_eq!(WaveContext::current(), None);
+        WaveContext::of(&wave)
+            .scope(async {
+                let context = WaveContext::current().unwrap();
+                assert_eq!(context.source.as_deref(), Some("checkout"));
+                tracing::info!("reserving stock");
+                let follow_up = Wave::new("payments.requested", serde_json::json!({}));
+                aether.emit(follow_up).await.unwrap();
+            })
+            .await;
+
+        let logs = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        let line = logs
+            .lines()
+            .find(|line| line.contains("reserving stock"))
+            .unwrap();
+        assert!(line.contains(&format!("wave_id={}", wave_id)));
+        assert!(line.contains("channel=orders.created"));
+        assert!(line.contains("source=\"checkout\""));
+        assert!(line.contains("correlation_id=req-42"));
+
+        let follow_up = downstream.recv().await.unwrap();
+        assert_eq!(follow_up.correlation_id(), "req-42");
+        assert_eq!(follow_up.causation_id(), Some(wave_id));
+        let unrelated = Wave::new("orders.created", serde_json::json!({}));
+        assert_eq!(unrelated.correlation_id(), unrelated.id().to_string());
+        assert_eq!(unrelated.causation_id(), None);
+    }
+}
@@ -0,0 +1,402 @@
+//! Wave search: a ring buffer of recent waves that can be queried and tailed.
+//!
+//! A [`WaveIndex`] (the gateway runs one) keeps the newest waves seen on `>`,
+//! redacted the way `aether-cli tail` prints them. `search_waves` on the
+//! control plane answers a [`WaveQuery`] from it, and `tail_waves` forwards new
+//! matches onto an `aether.tail.*` stream channel the caller picked. Tails are
+//! leases: they stop after `duration_ms` unless the same stream is requested
+//! again, so an abandoned `aether-cli search --follow` cleans up after itself.
+//! Layer-internal `aether.*` traffic (control commands, heartbeats, tails) is
+//! not indexed.
+
+use crate::{
+    aether::Aether,
+    channel::Channel,
+    wave::{Wave, WaveType},
+};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Channels the layer itself talks on, left out of the index
+const INTERNAL_PREFIX: &str = "aether.";
+
+/// Prefix of the channels tails are forwarded on
+pub const TAIL_CHANNEL_PREFIX: &str = "aether.tail";
+
+/// Stream channel for a tail named `name`
+pub fn tail_channel(name: &str) -> Channel {
+    Channel::new(format!("{}.{}", TAIL_CHANNEL_PREFIX, name))
+}
+
+fn is_tail_channel(channel: &Channel) -> bool {
+    channel
+        .name()
+        .strip_prefix(TAIL_CHANNEL_PREFIX)
+        .is_some_and(|rest| rest.starts_with('.'))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WaveIndexConfig {
+    /// Waves kept for search; the oldest are dropped first
+    #[serde(default = "default_capacity")]
+    pub capacity: usize,
+    /// Longest lease a single `tail_waves` command gets
+    #[serde(default = "default_max_tail_ms")]
+    pub max_tail_ms: u64,
+}
+
+impl Default for WaveIndexConfig {
+    fn default() -> Self {
+        Self {
+            capacity: default_capacity(),
+            max_tail_ms: default_max_tail_ms(),
+        }
+    }
+}
+
+fn default_capacity() -> usize {
+    10_000
+}
+
+fn default_max_tail_ms() -> u64 {
+    60_000
+}
+
+/// What to look for; unset fields match everything
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WaveQuery {
+    /// Channel name or pattern
+    #[serde(default)]
+    pub channel: Option<String>,
+    #[serde(default)]
+    pub source: Option<String>,
+    /// Substring of the JSON (or UTF-8) payload
+    #[serde(default)]
+    pub contains: Option<String>,
+    #[serde(default)]
+    pub since: Option<DateTime<Utc>>,
+    /// Keep only the newest `limit` matches
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+impl WaveQuery {
+    pub fn matches(&self, wave: &Wave) -> bool {
+        self.channel
+            .as_ref()
+            .is_none_or(|pattern| wave.channel().matches(&Channel::new(pattern.as_str())))
+            && self
+                .source
+                .as_ref()
+                .is_none_or(|source| wave.source() == Some(source.as_str()))
+            && self.since.is_none_or(|since| *wave.timestamp() >= since)
+            && self
+                .contains
+                .as_ref()
+                .is_none_or(|needle| match wave.payload_bytes() {
+                    Some(bytes) => String::from_utf8_lossy(bytes).contains(needle.as_str()),
+                    None => wave.payload().to_string().contains(needle.as_str()),
+                })
+    }
+}
+
+/// Recent waves, shared by every clone
+#[derive(Clone)]
+pub struct WaveIndex {
+    capacity: usize,
+    max_tail: Duration,
+    waves: Arc<Mutex<VecDeque<Wave>>>,
+    /// Lease per active tail stream
+    tails: Arc<Mutex<HashMap<String, TailLease>>>,
+}
+
+/// One forwarding task; renewing with another query replaces the task
+struct TailLease {
+    id: Uuid,
+    query: WaveQuery,
+    deadline: Instant,
+}
+
+impl WaveIndex {
+    pub fn new(config: &WaveIndexConfig) -> Self {
+        Self {
+            capacity: config.capacity.max(1),
+            max_tail: Duration::from_millis(config.max_tail_ms),
+            waves: Arc::default(),
+            tails: Arc::default(),
+        }
+    }
+
+    pub fn record(&self, wave: Wave) {
+        let mut waves = self.waves.lock().expect("wave index lock poisoned");
+        if waves.len() >= self.capacity {
+            waves.pop_front();
+        }
+        waves.push_back(wave);
+    }
+
+    /// Indexed waves matching `query`, oldest first
+    pub fn search(&self, query: &WaveQuery) -> Vec<Wave> {
+        let waves = self.waves.lock().expect("wave index lock poisoned");
+        let mut matches = waves
+            .iter()
+            .rev()
+            .filter(|wave| query.matches(wave))
+            .take(query.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect::<Vec<_>>();
+        matches.reverse();
+        matches
+    }
+
+    /// Index every wave on the layer until the task is aborted
+    pub async fn spawn(&self, aether: &Aether) -> tokio::task::JoinHandle<()> {
+        let stream = aether.tap(Channel::new(">")).await;
+        let redactor = Arc::clone(aether.redactor());
+        let index = self.clone();
+        tokio::spawn(async move {
+            futures::pin_mut!(stream);
+            while let Some(wave) = stream.next().await {
+                if !wave.channel().name().starts_with(INTERNAL_PREFIX) {
+                    index.record(redactor.redact(&wave).into_owned());
+                }
+            }
+        })
+    }
+
+    /// Forward new waves matching `query` onto `stream` for `duration`
+    ///
+    /// Asking again for a stream that is still open with the same query extends
+    /// its lease; a different query replaces the tail. Returns the lease
+    /// granted, capped at `max_tail_ms`.
+    pub async fn tail(
+        &self,
+        aether: &Aether,
+        query: WaveQuery,
+        stream: Channel,
+        duration: Duration,
+        source: &str,
+    ) -> Duration {
+        let duration = duration.min(self.max_tail);
+        let id = Uuid::new_v4();
+        {
+            let mut tails = self.tails.lock().expect("tail lock poisoned");
+            let deadline = Instant::now() + duration;
+            if let Some(lease) = tails.get_mut(stream.name()) {
+                if lease.query == query {
+                    lease.deadline = deadline;
+                    return duration;
+                }
+            }
+            tails.insert(
+                stream.name().to_string(),
+                TailLease {
+                    id,
+                    query: query.clone(),
+                    deadline,
+                },
+            );
+        }
+
+        let pattern = Channel::new(query.channel.as_deref().unwrap_or(">"));
+        let taps = aether.tap(pattern).await;
+        let aether = aether.clone();
+        let tails = Arc::clone(&self.tails);
+        let source: Arc<str> = source.into();
+        tokio::spawn(async move {
+            futures::pin_mut!(taps);
+            loop {
+                // Gone, or taken over by a tail with another query
+                let Some(deadline) = tails
+                    .lock()
+                    .expect("tail lock poisoned")
+                    .get(stream.name())
+                    .filter(|lease| lease.id == id)
+                    .map(|lease| lease.deadline)
+                else {
+                    break;
+                };
+                let wave = match tokio::time::timeout_at(deadline, taps.next()).await {
+                    Ok(Some(wave)) => wave,
+                    Ok(None) => {
+                        let mut tails = tails.lock().expect("tail lock poisoned");
+                        if tails.get(stream.name()).is_some_and(|lease| lease.id == id) {
+                            tails.remove(stream.name());
+                        }
+                        break;
+                    }
+                    Err(_) => {
+                        let mut tails = tails.lock().expect("tail lock poisoned");
+                        // Unless renewed while we waited
+                        if tails
+                            .get(stream.name())
+                            .is_some_and(|lease| lease.id == id && lease.deadline <= Instant::now())
+                        {
+                            tails.remove(stream.name());
+                            break;
+                        }
+                        continue;
+                    }
+                };
+                if is_tail_channel(wave.channel()) || !query.matches(&wave) {
+                    continue;
+                }
+                let payload = serde_json::to_value(aether.redactor().redact(&wave).as_ref())
+                    .unwrap_or_default();
+                let mut forwarded = Wave::builder(stream.clone())
+                    .wave_type(WaveType::Event)
+                    .payload(payload)
+                    .source(Arc::clone(&source))
+                    .build();
+                if let Some(token) = &aether.config().auth_token {
+                    forwarded.set_auth_token(token.clone());
+                }
+                if let Err(err) = aether.emit(forwarded).await {
+                    warn!("Failed to forward wave to {}: {}", stream, err);
+                }
+            }
+        });
+        duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aether::AetherConfig;
+
+    fn wave(channel: &str, source: &str, order_id: &str) -> Wave {
+        Wave::builder(channel)
+            .payload(serde_json::json!({ "order_id": order_id }))
+            .source(source)
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_search_and_tail_matching_waves() {
+        let aether = Aether::new(AetherConfig {
+            use_nats: false,
+            ..AetherConfig::default()
+        });
+        let index = WaveIndex::new(&WaveIndexConfig {
+            capacity: 3,
+            ..WaveIndexConfig::default()
+        });
+        let task = index.spawn(&aether).await;
+
+        for (channel, source, id) in [
+            ("orders.created", "checkout", "ORD-1"),
+            ("orders.created", "checkout", "ORD-2"),
+            ("payments.captured", "billing", "ORD-2"),
+            ("orders.created", "backfill", "ORD-3"),
+        ] {
+            aether.emit(wave(channel, source, id)).await.unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let ids = |waves: Vec<Wave>| {
+            waves
+                .iter()
+                .map(|wave| wave.payload()["order_id"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        // ORD-1 fell out of the ring buffer
+        let query = WaveQuery {
+            channel: Some("orders.*".into()),
+            ..WaveQuery::default()
+        };
+        assert_eq!(ids(index.search(&query)), ["ORD-2", "ORD-3"]);
+        let query = WaveQuery {
+            contains: Some("ORD-2".into()),
+            limit: Some(1),
+            ..WaveQuery::default()
+        };
+        let found = index.search(&query);
+        assert_eq!(found[0].channel().name(), "payments.captured");
+        let query = WaveQuery {
+            source: Some("backfill".into()),
+            ..WaveQuery::default()
+        };
+        assert_eq!(ids(index.search(&query)), ["ORD-3"]);
+
+        let stream = tail_channel("cli1");
+        let mut tailed = aether.subscribe(&stream).await;
+        let query = WaveQuery {
+            channel: Some("payments.>".into()),
+            ..WaveQuery::default()
+        };
+        let lease = index
+            .tail(
+                &aether,
+                query,
+                stream.clone(),
+                Duration::from_secs(600),
+                "gateway",
+            )
+            .await;
+        assert_eq!(lease, Duration::from_millis(default_max_tail_ms()));
+
+        aether
+            .emit(wave("orders.created", "checkout", "ORD-4"))
+            .await
+            .unwrap();
+        aether
+            .emit(wave("payments.captured", "billing", "ORD-5"))
+            .await
+            .unwrap();
+        let forwarded = tailed.recv().await.unwrap();
+        assert_eq!(forwarded.payload()["channel"]["name"], "payments.captured");
+        assert_eq!(forwarded.payload()["payload"]["order_id"], "ORD-5");
+        // Forwarded copies are neither indexed nor tailed again
+        assert!(index
+            .search(&WaveQuery::default())
+            .iter()
+            .all(|wave| !is_tail_channel(wave.channel())));
+
+        // Renewing the stream with another query switches what it carries
+        let query = WaveQuery {
+            channel: Some("orders.>".into()),
+            ..WaveQuery::default()
+        };
+        index
+            .tail(
+                &aether,
+                query,
+                stream.clone(),
+                Duration::from_secs(1),
+                "gateway",
+            )
+            .await;
+        aether
+            .emit(wave("payments.captured", "billing", "ORD-6"))
+            .await
+            .unwrap();
+        aether
+            .emit(wave("orders.created", "checkout", "ORD-7"))
+            .await
+            .unwrap();
+        let forwarded = tailed.recv().await.unwrap();
+        assert_eq!(forwarded.payload()["payload"]["order_id"], "ORD-7");
+
+        // Layer-internal traffic stays out of the index
+        aether
+            .emit(wave("aether.control.billing", "cli", "ORD-8"))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let query = WaveQuery {
+            contains: Some("ORD-8".into()),
+            ..WaveQuery::default()
+        };
+        assert!(index.search(&query).is_empty());
+
+        task.abort();
+    }
+}
@@ -0,0 +1,435 @@
+//! Aggregation windows: batch a channel's waves by event time or count.
+//!
+//! Time windows are aligned to the Unix epoch and keyed by each wave's
+//! timestamp. The watermark trails the newest timestamp seen (or the clock,
+//! on a quiet channel) by the allowed lateness; a window fires once the
+//! watermark passes its end, and waves arriving for fired windows are late.
+//!
+//! By default a window hands out its waves; a [`WindowContents`] other than
+//! `Vec<Wave>` folds them into running aggregates as they arrive instead.
+
+use crate::{aether::Aether, channel::Channel, wave::Wave};
+use chrono::{DateTime, TimeZone, Utc};
+use futures::StreamExt;
+use std::collections::{BTreeMap, VecDeque};
+use std::future::Future;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+/// Window shape
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Window {
+    /// Consecutive, non-overlapping spans of time
+    Tumbling(Duration),
+    /// Spans `size` wide starting every `slide`
+    Sliding { size: Duration, slide: Duration },
+    /// Every `n` waves
+    TumblingCount(usize),
+    /// The last `size` waves, every `slide` waves
+    SlidingCount { size: usize, slide: usize },
+}
+
+/// What a window keeps of the waves it collects
+pub trait WindowContents: Default + Send + 'static {
+    fn add(&mut self, wave: &Wave);
+
+    /// Contents of a count window from the waves it held
+    fn from_waves(waves: Vec<Wave>) -> Self {
+        let mut contents = Self::default();
+        for wave in &waves {
+            contents.add(wave);
+        }
+        contents
+    }
+}
+
+/// The waves themselves, in arrival order
+impl WindowContents for Vec<Wave> {
+    fn add(&mut self, wave: &Wave) {
+        self.push(wave.clone());
+    }
+
+    fn from_waves(waves: Vec<Wave>) -> Self {
+        waves
+    }
+}
+
+/// Waves collected in one window
+#[derive(Debug, Clone)]
+pub struct WindowBatch<C = Vec<Wave>> {
+    /// Window start (first wave's timestamp for count windows)
+    pub start: DateTime<Utc>,
+    /// Window end, exclusive (last wave's timestamp for count windows)
+    pub end: DateTime<Utc>,
+    /// Waves in arrival order, or what `C` keeps of them
+    pub waves: C,
+    /// Late waves dropped since the previous batch
+    pub late: u64,
+}
+
+/// Collects waves into windows and hands out the batches that close
+#[derive(Debug)]
+pub struct WaveWindow<C = Vec<Wave>> {
+    window: Window,
+    allowed_lateness: Duration,
+    /// Open time windows by start (epoch ms)
+    open: BTreeMap<i64, C>,
+    /// Pending waves of a count window
+    recent: VecDeque<Wave>,
+    since_emit: usize,
+    /// Epoch ms before which windows have fired
+    watermark: Option<i64>,
+    late: u64,
+}
+
+impl WaveWindow {
+    pub fn new(window: Window) -> Self {
+        Self::summarizing(window)
+    }
+}
+
+impl<C: WindowContents> WaveWindow<C> {
+    /// A window that folds its waves into `C` as they arrive
+    pub fn summarizing(window: Window) -> Self {
+        Self {
+            window,
+            allowed_lateness: Duration::ZERO,
+            open: BTreeMap::new(),
+            recent: VecDeque::new(),
+            since_emit: 0,
+            watermark: None,
+            late: 0,
+        }
+    }
+
+    /// How far behind the newest wave a time window waits for stragglers
+    pub fn with_allowed_lateness(mut self, lateness: Duration) -> Self {
+        self.allowed_lateness = lateness;
+        self
+    }
+
+    pub fn window(&self) -> Window {
+        self.window
+    }
+
+    /// Add a wave; returns the windows it closed
+    pub fn push(&mut self, wave: Wave) -> Vec<WindowBatch<C>> {
+        match self.window {
+            Window::Tumbling(size) => self.push_timed(wave, size, size),
+            Window::Sliding { size, slide } => self.push_timed(wave, size, slide),
+            Window::TumblingCount(n) => self.push_counted(wave, n, n),
+            Window::SlidingCount { size, slide } => self.push_counted(wave, size, slide),
+        }
+    }
+
+    /// Move the watermark to `now` minus the allowed lateness so windows close
+    /// on a quiet channel; count windows ignore it
+    pub fn advance_to(&mut self, now: DateTime<Utc>) -> Vec<WindowBatch<C>> {
+        if self.time_spans().is_none() {
+            return Vec::new();
+        }
+        self.raise_watermark(now.timestamp_millis() - millis(self.allowed_lateness));
+        self.fire_closed()
+    }
+
+    /// Emit every window still open, including partial count windows
+    pub fn flush(&mut self) -> Vec<WindowBatch<C>> {
+        if let Some((size, _)) = self.time_spans() {
+            let mut late = std::mem::take(&mut self.late);
+            return std::mem::take(&mut self.open)
+                .into_iter()
+                .map(|(start, waves)| WindowBatch {
+                    start: from_millis(start),
+                    end: from_millis(start.saturating_add(size)),
+                    waves,
+                    late: std::mem::take(&mut late),
+                })
+                .collect();
+        }
+        if self.since_emit == 0 {
+            return Vec::new();
+        }
+        self.since_emit = 0;
+        let waves: Vec<Wave> = self.recent.drain(..).collect();
+        self.counted_batch(waves).into_iter().collect()
+    }
+
+    /// Window size and slide in ms for time windows
+    fn time_spans(&self) -> Option<(i64, i64)> {
+        match self.window {
+            Window::Tumbling(size) => Some((span(size), span(size))),
+            Window::Sliding { size, slide } => Some((span(size), span(slide))),
+            _ => None,
+        }
+    }
+
+    fn push_timed(&mut self, wave: Wave, size: Duration, slide: Duration) -> Vec<WindowBatch<C>> {
+        let (size, slide) = (span(size), span(slide));
+        let ts = wave.timestamp().timestamp_millis();
+        let watermark = self.watermark.unwrap_or(i64::MIN);
+        let last_start = ts.div_euclid(slide) * slide;
+
+        if last_start.saturating_add(size) <= watermark {
+            self.late += 1;
+            metrics::counter!("aether_window_late_waves_total").increment(1);
+            debug!("Wave {} arrived after its windows closed", wave.id());
+        } else {
+            let mut start = last_start;
+            while start.saturating_add(size) > ts && start.saturating_add(size) > watermark {
+                self.open.entry(start).or_default().add(&wave);
+                start -= slide;
+            }
+        }
+
+        self.raise_watermark(ts - millis(self.allowed_lateness));
+        self.fire_closed()
+    }
+
+    fn push_counted(&mut self, wave: Wave, size: usize, slide: usize) -> Vec<WindowBatch<C>> {
+        let (size, slide) = (size.max(1), slide.max(1));
+        self.recent.push_back(wave);
+        while self.recent.len() > size {
+            self.recent.pop_front();
+        }
+        self.since_emit += 1;
+        if self.since_emit < slide || self.recent.len() < size.min(slide) {
+            return Vec::new();
+        }
+        self.since_emit = 0;
+        let waves: Vec<Wave> = if size <= slide {
+            self.recent.drain(..).collect()
+        } else {
+            self.recent.iter().cloned().collect()
+        };
+        self.counted_batch(waves).into_iter().collect()
+    }
+
+    fn counted_batch(&mut self, waves: Vec<Wave>) -> Option<WindowBatch<C>> {
+        let start = *waves.first()?.timestamp();
+        let end = *waves.last()?.timestamp();
+        Some(WindowBatch {
+            start,
+            end,
+            waves: C::from_waves(waves),
+            late: std::mem::take(&mut self.late),
+        })
+    }
+
+    fn raise_watermark(&mut self, candidate: i64) {
+        self.watermark = Some(
+            self.watermark
+                .map_or(candidate, |watermark| watermark.max(candidate)),
+        );
+    }
+
+    fn fire_closed(&mut self) -> Vec<WindowBatch<C>> {
+        let (Some((size, _)), Some(watermark)) = (self.time_spans(), self.watermark) else {
+            return Vec::new();
+        };
+        let mut batches = Vec::new();
+        while let Some(entry) = self.open.first_entry() {
+            let start = *entry.key();
+            if start.saturating_add(size) > watermark {
+                break;
+            }
+            batches.push(WindowBatch {
+                start: from_millis(start),
+                end: from_millis(start.saturating_add(size)),
+                waves: entry.remove(),
+                late: std::mem::take(&mut self.late),
+            });
+        }
+        batches
+    }
+
+    /// Feed the waves on `pattern` through the window in the background,
+    /// calling `handler` with each batch; the rest is flushed if the tap ends
+    pub async fn spawn<F, Fut>(
+        mut self,
+        aether: &Aether,
+        pattern: Channel,
+        mut handler: F,
+    ) -> JoinHandle<()>
+    where
+        F: FnMut(WindowBatch<C>) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send,
+    {
+        let stream = aether.tap(pattern).await;
+        let clock = aether.clock().clone();
+        let tick = self.time_spans().map(|(_, slide)| slide).unwrap_or(1000);
+        tokio::spawn(async move {
+            futures::pin_mut!(stream);
+            let mut ticker =
+                tokio::time::interval(Duration::from_millis(tick.clamp(10, 1000) as u64));
+            let timed = self.time_spans().is_some();
+            loop {
+                let batches = tokio::select! {
+                    wave = stream.next() => match wave {
+                        Some(wave) => self.push(wave),
+                        None => break,
+                    },
+                    _ = ticker.tick(), if timed => self.advance_to(clock.now()),
+                };
+                for batch in batches {
+                    handler(batch).await;
+                }
+            }
+            for batch in self.flush() {
+                handler(batch).await;
+            }
+        })
+    }
+}
+
+fn millis(duration: Duration) -> i64 {
+    duration.as_millis().min(i64::MAX as u128) as i64
+}
+
+/// Window size or slide in ms, at least 1
+fn span(duration: Duration) -> i64 {
+    millis(duration).max(1)
+}
+
+fn from_millis(ms: i64) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(ms)
+        .single()
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(ms: i64) -> Wave {
+        Wave::builder("metrics.cpu")
+            .payload(serde_json::json!({ "at": ms }))
+            .timestamp(from_millis(ms))
+            .build()
+    }
+
+    fn contents(batches: &[WindowBatch]) -> Vec<Vec<i64>> {
+        batches
+            .iter()
+            .map(|batch| {
+                batch
+                    .waves
+                    .iter()
+                    .map(|wave| wave.payload()["at"].as_i64().unwrap())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_time_windows_fire_on_watermark_and_drop_late_waves() {
+        let mut tumbling = WaveWindow::new(Window::Tumbling(Duration::from_millis(100)))
+            .with_allowed_lateness(Duration::from_millis(20));
+        assert!(tumbling.push(at(10)).is_empty());
+        assert!(tumbling.push(at(90)).is_empty());
+        // Within the lateness, 115 doesn't close [0, 100) yet; 50 still fits
+        assert!(tumbling.push(at(115)).is_empty());
+        assert!(tumbling.push(at(50)).is_empty());
+        let fired = tumbling.push(at(125));
+        assert_eq!(contents(&fired), vec![vec![10, 90, 50]]);
+        assert_eq!(fired[0].start, from_millis(0));
+        assert_eq!(fired[0].end, from_millis(100));
+
+        // [0, 100) has fired, so a wave for it is late
+        assert!(tumbling.push(at(60)).is_empty());
+        let fired = tumbling.advance_to(from_millis(220));
+        assert_eq!(contents(&fired), vec![vec![115, 125]]);
+        assert_eq!(fired[0].late, 1);
+
+        let mut sliding = WaveWindow::new(Window::Sliding {
+            size: Duration::from_millis(100),
+            slide: Duration::from_millis(50),
+        });
+        let mut fired: Vec<_> = [10, 60, 120]
+            .into_iter()
+            .flat_map(|ms| sliding.push(at(ms)))
+            .collect();
+        fired.extend(sliding.flush());
+        assert_eq!(
+            contents(&fired),
+            vec![vec![10], vec![10, 60], vec![60, 120], vec![120]]
+        );
+    }
+
+    #[derive(Debug, Default)]
+    struct Span {
+        count: usize,
+        latest: i64,
+    }
+
+    impl WindowContents for Span {
+        fn add(&mut self, wave: &Wave) {
+            self.count += 1;
+            self.latest = self.latest.max(wave.payload()["at"].as_i64().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_summarizing_window_folds_waves_as_they_arrive() {
+        let mut tumbling =
+            WaveWindow::<Span>::summarizing(Window::Tumbling(Duration::from_millis(100)));
+        for ms in [10, 90, 50] {
+            assert!(tumbling.push(at(ms)).is_empty());
+        }
+        let fired = tumbling.push(at(130));
+        assert_eq!(fired.len(), 1);
+        assert_eq!((fired[0].waves.count, fired[0].waves.latest), (3, 90));
+
+        let mut counted = WaveWindow::<Span>::summarizing(Window::TumblingCount(2));
+        assert!(counted.push(at(5)).is_empty());
+        let fired = counted.push(at(7));
+        assert_eq!((fired[0].waves.count, fired[0].waves.latest), (2, 7));
+    }
+
+    #[tokio::test]
+    async fn test_spawned_window_hands_batches_to_handler() {
+        let aether = Aether::new(crate::aether::AetherConfig {
+            use_nats: false,
+            ..crate::aether::AetherConfig::default()
+        });
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let task = WaveWindow::new(Window::TumblingCount(2))
+            .spawn(&aether, Channel::new("metrics.*"), move |batch| {
+                let tx = tx.clone();
+                async move {
+                    tx.send(batch.waves.len()).unwrap();
+                }
+            })
+            .await;
+        for ms in 0..4 {
+            aether.emit(at(ms)).await.unwrap();
+        }
+        aether
+            .emit(Wave::new("orders.created", serde_json::json!({})))
+            .await
+            .unwrap();
+
+        for _ in 0..2 {
+            let size = tokio::time::timeout(Duration::from_millis(200), rx.recv())
+                .await
+                .unwrap();
+            assert_eq!(size, Some(2));
+        }
+        task.abort();
+    }
+
+    #[test]
+    fn test_count_windows() {
+        let mut tumbling = WaveWindow::new(Window::TumblingCount(2));
+        let fired: Vec<_> = (0..5).flat_map(|ms| tumbling.push(at(ms))).collect();
+        assert_eq!(contents(&fired), vec![vec![0, 1], vec![2, 3]]);
+        assert_eq!(contents(&tumbling.flush()), vec![vec![4]]);
+
+        let mut sliding = WaveWindow::new(Window::SlidingCount { size: 3, slide: 2 });
+        let fired: Vec<_> = (0..7).flat_map(|ms| sliding.push(at(ms))).collect();
+        assert_eq!(
+            contents(&fired),
+            vec![vec![0, 1], vec![1, 2, 3], vec![3, 4, 5]]
+        );
+    }
+}
@@ -1,4 +1,7 @@
-use aether_core::{Aether, AetherConfig, Channel, Wave};
+use aether_core::{
+    Aether, AetherConfig, AetherError, Channel, ChaosConfig, CircuitBreaker, RateLimitConfig, Wave,
+};
+use std::time::Duration;
 
 #[tokio::test]
 async fn reject_oversized_payload() {
@@ -15,3 +18,91 @@ async fn reject_oversized_payload() {
     let result = aether.emit(wave).await;
     assert!(result.is_err());
 }
+
+fn chaotic(chaos: ChaosConfig) -> Aether {
+    Aether::new(AetherConfig {
+        use_nats: false,
+        chaos: Some(chaos),
+        ..AetherConfig::default()
+    })
+}
+
+#[tokio::test]
+async fn partition_opens_circuit_until_healed() {
+    let aether = chaotic(ChaosConfig {
+        partitioned_channels: vec!["payments.*".to_string()],
+        ..ChaosConfig::default()
+    });
+    let breaker = CircuitBreaker::new(2, Duration::from_millis(50), 1);
+    let emit = || async {
+        aether
+            .emit(Wave::new("payments.request", serde_json::json!({})))
+            .await
+            .map_err(anyhow::Error::from)
+    };
+
+    assert!(breaker.call(emit).await.is_err());
+    assert!(breaker.call(emit).await.is_err());
+    let open = breaker.call(emit).await.unwrap_err();
+    assert_eq!(open.to_string(), "circuit open");
+
+    aether.chaos().unwrap().heal();
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert!(breaker.call(emit).await.is_ok());
+}
+
+#[tokio::test]
+async fn dropped_waves_never_arrive() {
+    let aether = chaotic(ChaosConfig {
+        drop_probability: 1.0,
+        ..ChaosConfig::default()
+    });
+    let channel = Channel::new("orders.created");
+    let mut receiver = aether.subscribe(&channel).await;
+
+    aether
+        .emit(Wave::new(channel.clone(), serde_json::json!({})))
+        .await
+        .unwrap();
+    assert!(receiver.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn duplicated_waves_arrive_twice() {
+    let aether = chaotic(ChaosConfig {
+        duplicate_probability: 1.0,
+        ..ChaosConfig::default()
+    });
+    let channel = Channel::new("orders.created");
+    let mut receiver = aether.subscribe(&channel).await;
+
+    let wave = Wave::new(channel.clone(), serde_json::json!({}));
+    let id = *wave.id();
+    aether.emit(wave).await.unwrap();
+    assert_eq!(receiver.recv().await.unwrap().id(), &id);
+    assert_eq!(receiver.recv().await.unwrap().id(), &id);
+}
+
+#[tokio::test]
+async fn runaway_producer_is_rate_limited() {
+    let aether = Aether::new(AetherConfig {
+        use_nats: false,
+        rate_limit: Some(RateLimitConfig {
+            per_source_per_sec: 1.0,
+            burst: Some(2.0),
+            ..RateLimitConfig::default()
+        }),
+        ..AetherConfig::default()
+    });
+    let wave = |source: &str| {
+        Wave::builder(Channel::new("orders.created"))
+            .source(source)
+            .build()
+    };
+
+    aether.emit(wave("runaway")).await.unwrap();
+    aether.emit(wave("runaway")).await.unwrap();
+    let err = aether.emit(wave("runaway")).await.unwrap_err();
+    assert!(matches!(err, AetherError::RateLimited(_)));
+    aether.emit(wave("polite")).await.unwrap();
+}
@@ -0,0 +1,284 @@
+//! End-to-end tests against a real NATS server
+//!
+//! Ignored by default. Each test starts its own `nats-server` (from
+//! `NATS_SERVER_BIN`, otherwise `PATH`) on a free port:
+//!
+//! ```bash
+//! cargo test -p aether-core --test nats_integration -- --ignored
+//! ```
+//!
+//! The TLS test also needs the certificates from `scripts/gen_tls_certs.sh`.
+
+use aether_core::{
+    Aether, AetherConfig, AetherError, AetherShardSet, Channel, Vibrator, VibratorConfig, Wave,
+};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// How long a wave may take to come back before a test gives up
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct NatsServer {
+    process: Child,
+    port: u16,
+    args: Vec<String>,
+}
+
+impl NatsServer {
+    fn start() -> Self {
+        Self::start_with(free_port(), Vec::new())
+    }
+
+    fn start_with(port: u16, args: Vec<String>) -> Self {
+        let binary = std::env::var("NATS_SERVER_BIN").unwrap_or_else(|_| "nats-server".into());
+        let process = Command::new(&binary)
+            .args(["-a", "127.0.0.1", "-p", &port.to_string()])
+            .args(&args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap_or_else(|err| panic!("failed to start {}: {}", binary, err));
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while TcpStream::connect(("127.0.0.1", port)).is_err() {
+            assert!(
+                Instant::now() < deadline,
+                "nats-server did not listen on {}",
+                port
+            );
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        Self {
+            process,
+            port,
+            args,
+        }
+    }
+
+    fn url(&self) -> String {
+        format!("nats://127.0.0.1:{}", self.port)
+    }
+
+    fn stop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+
+    /// Start again on the same port with the same arguments
+    fn restart(&mut self) {
+        self.stop();
+        *self = Self::start_with(self.port, std::mem::take(&mut self.args));
+    }
+}
+
+impl Drop for NatsServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .expect("no free port")
+}
+
+/// A layer with its own NATS connection, as a separate service would have
+fn layer(url: &str) -> Aether {
+    Aether::new(AetherConfig {
+        nats_url: url.to_string(),
+        ..AetherConfig::default()
+    })
+}
+
+async fn vibrator(aether: &Aether, name: &str, channels: &[&str]) -> Vibrator {
+    let config = VibratorConfig::new(name)
+        .with_channels(channels.iter().copied().map(Channel::new).collect());
+    Vibrator::new(config, aether).await.unwrap()
+}
+
+async fn next_wave(vibrator: &mut Vibrator) -> Option<Wave> {
+    tokio::time::timeout(DELIVERY_TIMEOUT, vibrator.receive())
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Subscriptions reach the server asynchronously; let them land before emitting
+async fn settle() {
+    tokio::time::sleep(Duration::from_millis(250)).await;
+}
+
+#[tokio::test]
+#[ignore = "needs nats-server"]
+async fn delivers_between_vibrators_and_gateway() {
+    let server = NatsServer::start();
+    let alpha = layer(&server.url());
+    let beta = layer(&server.url());
+    let gateway = layer(&server.url());
+
+    let emitter = vibrator(&alpha, "service-alpha", &[]).await;
+    let mut inventory = vibrator(&beta, "service-beta", &["orders.created"]).await;
+    let mut observer = vibrator(&gateway, "aether-gateway", &[">"]).await;
+    settle().await;
+
+    emitter
+        .emit_wave("orders.created", serde_json::json!({"order_id": 7}))
+        .await
+        .unwrap();
+    alpha.flush().await.unwrap();
+
+    let received = next_wave(&mut inventory).await.expect("beta got nothing");
+    assert_eq!(received.payload()["order_id"], 7);
+    assert_eq!(received.source(), Some("service-alpha"));
+    let observed = next_wave(&mut observer).await.expect("gateway saw nothing");
+    assert_eq!(observed.id(), received.id());
+}
+
+#[tokio::test]
+#[ignore = "needs nats-server"]
+async fn rejects_waves_with_the_wrong_token() {
+    let server = NatsServer::start();
+    let secured = Aether::new(AetherConfig {
+        nats_url: server.url(),
+        auth_token: Some("fleet-token".to_string()),
+        ..AetherConfig::default()
+    });
+    let mut receiver = vibrator(&layer(&server.url()), "service-beta", &["payments.>"]).await;
+    settle().await;
+
+    let mut forged = Wave::new("payments.completed", serde_json::json!({"forged": true}));
+    forged.set_auth_token("guess");
+    let err = secured.emit(forged).await.unwrap_err();
+    assert!(matches!(err, AetherError::AuthorizationFailed(_)));
+
+    let mut genuine = Wave::new("payments.completed", serde_json::json!({"forged": false}));
+    genuine.set_auth_token("fleet-token");
+    secured.emit(genuine).await.unwrap();
+    secured.flush().await.unwrap();
+
+    let wave = next_wave(&mut receiver).await.expect("genuine wave lost");
+    assert_eq!(wave.payload()["forged"], false);
+}
+
+#[tokio::test]
+#[ignore = "needs nats-server"]
+async fn sharded_wildcard_delivers_each_wave_once() {
+    let server = NatsServer::start();
+    let shards = AetherShardSet::new(
+        AetherConfig {
+            nats_url: server.url(),
+            ..AetherConfig::default()
+        },
+        4,
+    );
+    let mut all_orders = shards.subscribe(&Channel::new("orders.>")).await;
+    settle().await;
+
+    for n in 0..8 {
+        shards
+            .emit(Wave::new(
+                format!("orders.{}", n),
+                serde_json::json!({ "n": n }),
+            ))
+            .await
+            .unwrap();
+    }
+    let mut seen = Vec::new();
+    while let Ok(Ok(wave)) = tokio::time::timeout(Duration::from_secs(1), all_orders.recv()).await {
+        seen.push(wave.payload()["n"].as_u64().unwrap());
+    }
+    seen.sort();
+    assert_eq!(seen, (0..8).collect::<Vec<u64>>());
+}
+
+#[tokio::test]
+#[ignore = "needs nats-server"]
+async fn resumes_delivery_after_server_restart() {
+    let mut server = NatsServer::start();
+    let alpha = layer(&server.url());
+    let mut receiver = vibrator(&layer(&server.url()), "service-beta", &["orders.>"]).await;
+    settle().await;
+
+    alpha
+        .emit(Wave::new("orders.created", serde_json::json!({"n": 1})))
+        .await
+        .unwrap();
+    assert!(next_wave(&mut receiver).await.is_some());
+
+    server.restart();
+
+    // Both clients reconnect with backoff and resubscribe; keep emitting until one arrives
+    let deadline = Instant::now() + Duration::from_secs(15);
+    loop {
+        assert!(Instant::now() < deadline, "no delivery after restart");
+        let _ = alpha
+            .emit(Wave::new("orders.created", serde_json::json!({"n": 2})))
+            .await;
+        let arrived = tokio::time::timeout(Duration::from_millis(500), receiver.receive()).await;
+        if let Ok(Some(wave)) = arrived {
+            assert_eq!(wave.payload()["n"], 2);
+            break;
+        }
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs nats-server and scripts/gen_tls_certs.sh"]
+async fn connects_over_mutual_tls() {
+    let certs = Path::new(env!("CARGO_MANIFEST_DIR")).join("../certs");
+    let cert = |name: &str| -> String {
+        let path: PathBuf = certs.join(name);
+        assert!(
+            path.exists(),
+            "{} missing; run scripts/gen_tls_certs.sh",
+            path.display()
+        );
+        path.to_string_lossy().into_owned()
+    };
+    let server = NatsServer::start_with(
+        free_port(),
+        vec![
+            "--tlsverify".to_string(),
+            "--tlscert".to_string(),
+            cert("server.pem"),
+            "--tlskey".to_string(),
+            cert("server.key"),
+            "--tlscacert".to_string(),
+            cert("ca.pem"),
+        ],
+    );
+    let tls = |client_cert: bool| {
+        Aether::new(AetherConfig {
+            nats_url: format!("tls://127.0.0.1:{}", server.port),
+            nats_tls_required: true,
+            nats_mtls_ca_path: Some(cert("ca.pem")),
+            nats_mtls_client_cert_path: client_cert.then(|| cert("client.pem")),
+            nats_mtls_client_key_path: client_cert.then(|| cert("client.key")),
+            ..AetherConfig::default()
+        })
+    };
+
+    let anonymous = tls(false);
+    let err = anonymous
+        .emit(Wave::new("secure.ping", serde_json::json!({})))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, AetherError::ConnectionFailed(_)), "{}", err);
+
+    let alpha = tls(true);
+    let mut receiver = vibrator(&tls(true), "service-beta", &["secure.ping"]).await;
+    settle().await;
+    alpha
+        .emit(Wave::new(
+            "secure.ping",
+            serde_json::json!({"over": "mtls"}),
+        ))
+        .await
+        .unwrap();
+    let wave = next_wave(&mut receiver).await.expect("no wave over TLS");
+    assert_eq!(wave.payload()["over"], "mtls");
+}
@@ -0,0 +1,60 @@
+use aether_core::{
+    Aether, AetherConfig, Channel, ChannelRegistry, ChannelSpec, RegistryMode, Vibrator, Wave,
+};
+
+fn persistent(path: &std::path::Path) -> AetherConfig {
+    AetherConfig {
+        use_nats: false,
+        persistence_enabled: true,
+        persistence_path: path.to_string_lossy().into_owned(),
+        ..AetherConfig::default()
+    }
+}
+
+#[tokio::test]
+async fn restore_continues_sequences_and_rebuilds_state() {
+    let path = std::env::temp_dir().join(format!("aether-restore-{}", uuid::Uuid::new_v4()));
+    {
+        let aether = Aether::new(persistent(&path)).with_registry(
+            ChannelRegistry::new(RegistryMode::Warn).declare(ChannelSpec::new("orders.created")),
+        );
+        let _vibrator = Vibrator::create("alpha", &aether).await.unwrap();
+        let _receiver = aether.subscribe(&Channel::new("orders.created")).await;
+        for _ in 0..3 {
+            aether
+                .emit(Wave::new("orders.created", serde_json::json!({})))
+                .await
+                .unwrap();
+        }
+        let snapshot = aether.snapshot().await.unwrap().unwrap();
+        assert_eq!(snapshot.sequences["orders.created"], 3);
+        assert_eq!(snapshot.vibrators, vec!["alpha".to_string()]);
+
+        // Logged after the snapshot; restore must account for it
+        aether
+            .emit(Wave::new("orders.created", serde_json::json!({})))
+            .await
+            .unwrap();
+        aether.flush().await.unwrap();
+    }
+
+    let mut aether = Aether::new(persistent(&path));
+    let snapshot = aether.restore_from_snapshot().await.unwrap().unwrap();
+    assert_eq!(snapshot.last_index, 2);
+    assert_eq!(aether.stats().await.total_waves, 4);
+    assert_eq!(aether.active_channels().await, vec!["orders.created".to_string()]);
+    assert!(aether
+        .registry()
+        .and_then(|registry| registry.lookup(&Channel::new("orders.created")))
+        .is_some());
+
+    let mut receiver = aether.subscribe(&Channel::new("orders.created")).await;
+    aether
+        .emit(Wave::new("orders.created", serde_json::json!({})))
+        .await
+        .unwrap();
+    assert_eq!(receiver.recv().await.unwrap().sequence(), Some(5));
+
+    drop(aether);
+    let _ = std::fs::remove_dir_all(path);
+}
@@ -1,5 +1,76 @@
-use aether_core::{Channel, Wave};
+use aether_core::{Channel, Wave, WaveCodec, WaveType};
+use bytes::Bytes;
 use proptest::prelude::*;
+use serde_json::Value;
+
+fn json_value() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        any::<i64>().prop_map(Value::from),
+        any::<u64>().prop_map(Value::from),
+        any::<f64>()
+            .prop_filter("finite", |f| f.is_finite())
+            .prop_map(Value::from),
+        ".{0,16}".prop_map(Value::String),
+    ];
+    leaf.prop_recursive(3, 32, 6, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..6).prop_map(Value::Array),
+            prop::collection::btree_map("[a-z_]{1,8}", inner, 0..6)
+                .prop_map(|map| Value::Object(map.into_iter().collect())),
+        ]
+    })
+}
+
+fn json_object() -> impl Strategy<Value = Value> {
+    prop::collection::btree_map("[a-z_]{1,8}", json_value(), 0..6)
+        .prop_map(|map| Value::Object(map.into_iter().collect()))
+}
+
+fn wave_type() -> impl Strategy<Value = WaveType> {
+    prop_oneof![
+        Just(WaveType::Event),
+        Just(WaveType::Command),
+        Just(WaveType::Query),
+        Just(WaveType::Response),
+        Just(WaveType::Broadcast),
+    ]
+}
+
+prop_compose! {
+    fn arb_wave()(
+        channel in "[a-z]{1,8}(\\.[a-z0-9]{1,8}){0,3}",
+        payload in json_value(),
+        payload_bytes in prop::option::of(prop::collection::vec(any::<u8>(), 0..64)),
+        wave_type in wave_type(),
+        amplitude in 0.0f64..=1.0,
+        source in prop::option::of("[a-z-]{1,12}"),
+        metadata in json_object(),
+        hops in 0u32..4,
+    ) -> Wave {
+        let mut builder = Wave::builder(Channel::new(channel))
+            .payload(payload)
+            .wave_type(wave_type)
+            .amplitude(amplitude)
+            .metadata(metadata);
+        if let Some(bytes) = payload_bytes {
+            builder = builder.payload_bytes(Bytes::from(bytes));
+        }
+        if let Some(source) = source {
+            builder = builder.source(source);
+        }
+        let mut wave = builder.build();
+        for _ in 0..hops {
+            wave.propagate();
+        }
+        wave
+    }
+}
+
+fn codec() -> impl Strategy<Value = WaveCodec> {
+    prop_oneof![Just(WaveCodec::Json), Just(WaveCodec::MessagePack)]
+}
 
 proptest! {
     #[test]
@@ -14,4 +85,28 @@ proptest! {
         let wave = Wave::new("test.channel", serde_json::json!({"payload": payload}));
         prop_assert!(wave.is_compatible());
     }
+
+    #[test]
+    fn wave_round_trips_through_every_codec(wave in arb_wave(), codec in codec()) {
+        let bytes = wave.to_bytes(codec).unwrap();
+        let decoded = Wave::from_bytes(&bytes, codec).unwrap();
+        prop_assert_eq!(&decoded, &wave);
+        prop_assert_eq!(decoded.to_bytes(codec).unwrap(), bytes);
+    }
+
+    #[test]
+    fn unknown_fields_are_preserved(
+        wave in arb_wave(),
+        field in "future_[a-z]{1,8}",
+        value in json_value(),
+        codec in codec(),
+    ) {
+        let mut json = serde_json::to_value(&wave).unwrap();
+        json[&field] = value.clone();
+        let wave = Wave::from_bytes(&serde_json::to_vec(&json).unwrap(), WaveCodec::Json).unwrap();
+
+        let decoded = Wave::from_bytes(&wave.to_bytes(codec).unwrap(), codec).unwrap();
+        let reencoded = serde_json::to_value(&decoded).unwrap();
+        prop_assert_eq!(&reencoded[&field], &value);
+    }
 }
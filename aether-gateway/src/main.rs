@@ -3,15 +3,14 @@
 //! Observes all waves and provides statistics
 
 use aether_core::{
-    apply_resource_limits, init_observability, init_ops, install_panic_hook, load_config,
-    shutdown_signal, start_resource_monitoring, wait_for_shutdown, watch_config, Aether, Channel,
-    OpsConfig, ResourceMonitorConfig, TaskManager, Vibrator, VibratorConfig, Wave,
+    handler, Aether, AetherApp, AetherEvent, Anomaly, AnomalyDetector, Channel, ChannelActivity,
+    ServiceContext, VibratorEmitter, Wave, WaveWindow, Window, WindowBatch, WindowContents,
+    MEMBERSHIP_CHANNEL,
 };
-use anyhow::Context;
-use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::Mutex;
-use tracing::info;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
 
 #[cfg(feature = "jemalloc")]
 #[global_allocator]
@@ -19,165 +18,136 @@ static GLOBAL: jemallocator::Jemalloc = jemallocator::Jemalloc;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Load config
-    let app_config = load_config("aether-gateway").context("failed to load service config")?;
-
-    // Panic hook & resource limits
-    install_panic_hook();
-    apply_resource_limits(
-        app_config.operations.memory_limit_bytes,
-        app_config.operations.cpu_time_limit_secs,
-    )
-    .context("failed to apply resource limits")?;
-
-    // Initialize observability (logging/metrics/tracing)
-    let _observability = init_observability(&app_config).context("failed to init observability")?;
-
-    // Operations (health check)
-    let _ops = init_ops(&OpsConfig {
-        enable_health: app_config.operations.health_enabled,
-        health_bind: app_config.operations.health_bind.clone(),
-        shutdown_grace_ms: app_config.operations.shutdown_grace_ms,
-        memory_limit_bytes: app_config.operations.memory_limit_bytes,
-        cpu_time_limit_secs: app_config.operations.cpu_time_limit_secs,
-    });
-
-    let _resource_monitor = start_resource_monitoring(ResourceMonitorConfig {
-        enabled: app_config.resource_monitoring.enabled,
-        interval_ms: app_config.resource_monitoring.interval_ms,
-        leak_detection_enabled: app_config.resource_monitoring.leak_detection_enabled,
-        leak_growth_bytes_per_min: app_config.resource_monitoring.leak_growth_bytes_per_min,
-        allocator_metrics_enabled: app_config.resource_monitoring.allocator_metrics_enabled,
-    });
-
-    info!("🌊 Starting Aether Gateway...");
-
-    // Watch config changes
-    let mut config_rx = watch_config("aether-gateway").context("failed to start config watcher")?;
-    tokio::spawn(async move {
-        while config_rx.changed().await.is_ok() {
-            let updated = config_rx.borrow().clone();
-            info!("🔄 Config reloaded for {}", updated.service.name);
-        }
-    });
-
-    // Initialize the Aether layer
-    let aether = Aether::new(app_config.aether_config());
-
-    // Vibrator that monitors all channels
-    let channels = if app_config.service.channels.is_empty() {
-        vec![Channel::new("*")]
-    } else {
-        app_config
-            .service
-            .channels
-            .iter()
-            .map(|ch| Channel::new(ch))
-            .collect()
-    };
-    let config = VibratorConfig::new(app_config.service.name.clone())
-        .with_channels(channels)
-        .with_auth_token(app_config.aether.auth_token.clone())
-        .with_noise_floor(app_config.service.noise_floor);
-
-    let mut vibrator = Vibrator::new(config, &aether).await;
-    let mut task_manager = TaskManager::new(
-        app_config.service.max_inflight,
-        app_config.service.rate_limit_per_sec,
-    );
-
-    info!("✨ Gateway connected to the Aether layer");
-    info!("👁️  Monitoring all channels...");
+    // Observes every channel unless the config narrows it
+    AetherApp::builder()
+        .config("aether-gateway")
+        .version(env!("CARGO_PKG_VERSION"))
+        .state(Gateway::start)
+        .topology()
+        .flow_traces()
+        .physics_history()
+        .wave_search()
+        .observe_sampling()
+        .handler(observe_wave)
+        .run()
+        .await
+}
 
-    // Statistics
-    let stats = Arc::new(Mutex::new(GatewayStats::new()));
+struct Gateway {
+    stats_window: JoinHandle<()>,
+    lifecycle: JoinHandle<()>,
+}
 
-    // Stats report task
-    let aether_clone = aether.clone();
-    tokio::spawn(async move {
-        loop {
-            tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
-            print_stats(&aether_clone).await;
+impl Gateway {
+    async fn start(ctx: ServiceContext) -> anyhow::Result<Self> {
+        if let Some(registry) = ctx.aether().registry() {
+            info!("📚 Channel catalog:");
+            for line in registry.render_catalog().lines() {
+                info!("   {}", line);
+            }
         }
-    });
+        info!("👁️  Monitoring all channels...");
+
+        // Stats report and anomaly check once per window of wave time
+        let config = ctx.config().anomaly.clone();
+        let window = Duration::from_millis(config.window_ms.max(1));
+        let mut detector = config.enabled.then(|| AnomalyDetector::new(config));
+        let aether = ctx.aether().clone();
+        let alerts = ctx.emitter().clone();
+        let stats_window = WaveWindow::<GatewayWindow>::summarizing(Window::Tumbling(window))
+            .with_allowed_lateness(Duration::from_secs(1))
+            .spawn(ctx.aether(), Channel::new(">"), move |batch| {
+                let stats = WindowStats::from_batch(&batch);
+                let anomalies = detector.as_mut().map(|detector| {
+                    let alert_channel = detector.config().alert_channel.clone();
+                    let activity = &batch.waves.activity;
+                    let anomalies = detector.observe_activity(batch.start, batch.end, activity);
+                    (alert_channel, anomalies)
+                });
+                let aether = aether.clone();
+                let alerts = alerts.clone();
+                async move {
+                    print_stats(&aether, window, &stats).await;
+                    if let Some((channel, anomalies)) = anomalies {
+                        report_anomalies(&alerts, &channel, anomalies).await;
+                    }
+                }
+            })
+            .await;
 
-    // Shutdown handling
-    let (shutdown_tx, shutdown_rx) = shutdown_signal();
-    tokio::spawn(async move {
-        let _ = tokio::signal::ctrl_c().await;
-        let _ = shutdown_tx.send(true);
-    });
+        let lifecycle = tokio::spawn(log_events(ctx.aether().clone()));
 
-    // Main loop: observe all waves
-    loop {
-        tokio::select! {
-            _ = wait_for_shutdown(shutdown_rx.clone()) => {
-                info!("Shutdown signal received");
-                break;
-            }
-            wave = vibrator.receive() => {
-                if let Some(wave) = wave {
-                    let stats = Arc::clone(&stats);
-                    task_manager
-                        .spawn(async move {
-                            observe_wave(stats, wave).await;
-                        })
-                        .await;
-                    task_manager.reap().await;
-                }
-            }
-        }
+        Ok(Self {
+            stats_window,
+            lifecycle,
+        })
     }
+}
+
+impl Drop for Gateway {
+    fn drop(&mut self) {
+        self.stats_window.abort();
+        self.lifecycle.abort();
+    }
+}
 
-    tokio::time::sleep(tokio::time::Duration::from_millis(
-        app_config.operations.shutdown_grace_ms,
-    ))
-    .await;
+/// Running totals of one window; the waves themselves are not kept
+#[derive(Debug, Default)]
+struct GatewayWindow {
+    activity: ChannelActivity,
+    waves_by_type: BTreeMap<String, u64>,
+}
 
-    Ok(())
+impl WindowContents for GatewayWindow {
+    fn add(&mut self, wave: &Wave) {
+        self.activity.add(wave);
+        if wave.channel().name() != MEMBERSHIP_CHANNEL {
+            *self
+                .waves_by_type
+                .entry(format!("{:?}", wave.wave_type()))
+                .or_insert(0) += 1;
+        }
+    }
 }
 
-#[derive(Debug)]
-struct GatewayStats {
+#[derive(Debug, Default)]
+struct WindowStats {
     total_waves: u64,
-    waves_by_channel: HashMap<String, u64>,
-    waves_by_type: HashMap<String, u64>,
+    late_waves: u64,
+    waves_by_channel: BTreeMap<String, u64>,
+    waves_by_type: BTreeMap<String, u64>,
     average_amplitude: f64,
 }
 
-impl GatewayStats {
-    fn new() -> Self {
-        Self {
-            total_waves: 0,
-            waves_by_channel: HashMap::new(),
-            waves_by_type: HashMap::new(),
-            average_amplitude: 0.0,
+impl WindowStats {
+    fn from_batch(batch: &WindowBatch<GatewayWindow>) -> Self {
+        let mut stats = Self {
+            late_waves: batch.late,
+            waves_by_type: batch.waves.waves_by_type.clone(),
+            ..Self::default()
+        };
+        let mut amplitude = 0.0;
+        for (channel, &(count, amplitude_sum)) in &batch.waves.activity.channels {
+            if channel == MEMBERSHIP_CHANNEL {
+                continue;
+            }
+            stats.total_waves += count;
+            amplitude += amplitude_sum;
+            stats.waves_by_channel.insert(channel.clone(), count);
         }
-    }
-
-    fn record_wave(&mut self, wave: &Wave) {
-        self.total_waves += 1;
-
-        // Count by channel
-        *self
-            .waves_by_channel
-            .entry(wave.channel().name().to_string())
-            .or_insert(0) += 1;
-
-        // Count by type
-        *self
-            .waves_by_type
-            .entry(format!("{:?}", wave.wave_type()))
-            .or_insert(0) += 1;
-
-        // Update average amplitude
-        let n = self.total_waves as f64;
-        self.average_amplitude =
-            (self.average_amplitude * (n - 1.0) + wave.amplitude().value()) / n;
+        if stats.total_waves > 0 {
+            stats.average_amplitude = amplitude / stats.total_waves as f64;
+        }
+        stats
     }
 }
 
-async fn observe_wave(stats: Arc<Mutex<GatewayStats>>, wave: Wave) {
+#[handler(channel = ">")]
+async fn observe_wave(_gateway: &Gateway, wave: Wave) {
+    // Heartbeats show up in the cluster view instead
+    if wave.channel().name() == MEMBERSHIP_CHANNEL {
+        return;
+    }
     info!(
         "👁️  [Observed] Channel: {} | Type: {:?} | Amplitude: {:.3} | Propagation: {} | Source: {:?}",
         wave.channel().name(),
@@ -186,18 +156,89 @@ async fn observe_wave(stats: Arc<Mutex<GatewayStats>>, wave: Wave) {
         wave.propagation_count(),
         wave.source()
     );
+}
+
+/// Surface layer trouble next to the stats instead of only in the emitting process's logs
+async fn log_events(aether: Aether) {
+    let mut events = aether.events();
+    loop {
+        match events.recv().await {
+            Ok(
+                event @ (AetherEvent::SubscriberLagged { .. }
+                | AetherEvent::NatsDisconnected
+                | AetherEvent::CircuitOpened { .. }),
+            ) => warn!("⚠️  {:?}", event),
+            Ok(event) => info!("🔔 {:?}", event),
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                warn!("Missed {} lifecycle events", skipped);
+            }
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
 
-    let mut stats_guard = stats.lock().await;
-    stats_guard.record_wave(&wave);
+async fn report_anomalies(alerts: &VibratorEmitter, channel: &str, anomalies: Vec<Anomaly>) {
+    for anomaly in anomalies {
+        warn!(
+            "🚨 Anomaly on {}: {} (observed {:.2}, baseline {:.2})",
+            anomaly.channel,
+            anomaly.kind.as_str(),
+            anomaly.observed,
+            anomaly.baseline
+        );
+        let payload = match serde_json::to_value(&anomaly) {
+            Ok(payload) => payload,
+            Err(err) => {
+                warn!("Failed to encode anomaly: {}", err);
+                continue;
+            }
+        };
+        if let Err(err) = alerts.emit_wave(channel, payload).await {
+            warn!("Failed to emit anomaly alert: {}", err);
+        }
+    }
 }
 
-async fn print_stats(aether: &Aether) {
+async fn print_stats(aether: &Aether, window: Duration, recent: &WindowStats) {
     let stats = aether.stats().await;
     let channels = aether.active_channels().await;
 
     info!("📊 ===== Aether Layer Stats =====");
+    let connection = aether.connection_state();
+    info!(
+        "   NATS: {} (up {}s, {} reconnects, last error: {})",
+        connection.status.as_str(),
+        connection.uptime.as_secs(),
+        connection.reconnects,
+        connection.last_error.as_deref().unwrap_or("none")
+    );
     info!("   Total waves: {}", stats.total_waves);
     info!("   Active channels: {}", stats.active_channels);
     info!("   Channel list: {:?}", channels);
+    info!("   Top channels: {:?}", stats.top_channels);
+    info!(
+        "   Distinct sources: ~{} | payload p50 {}B, p99 {}B",
+        stats.distinct_sources, stats.payload_bytes_p50, stats.payload_bytes_p99
+    );
+    info!(
+        "   Last {}s: {} waves ({} late) | avg amplitude {:.3}",
+        window.as_secs(),
+        recent.total_waves,
+        recent.late_waves,
+        recent.average_amplitude
+    );
+    info!("     by channel: {:?}", recent.waves_by_channel);
+    info!("     by type: {:?}", recent.waves_by_type);
+    info!("   Live services:");
+    for peer in aether.cluster_view().peers {
+        info!(
+            "     {} v{} [{}] load {:.0}% | {:?}",
+            peer.name,
+            peer.version,
+            peer.status.as_str(),
+            peer.load * 100.0,
+            peer.channels
+        );
+    }
     info!("=============================");
 }
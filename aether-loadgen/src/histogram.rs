@@ -0,0 +1,96 @@
+//! Fixed-size latency histogram: exact below 16µs, then 16 buckets per power
+//! of two (at most ~6% error), so long soaks use constant memory.
+
+const SUB_BUCKETS: u64 = 16;
+const SUB_BITS: u32 = 4;
+const BUCKETS: usize = (SUB_BUCKETS as usize) * (64 - SUB_BITS as usize + 1);
+
+#[derive(Debug, Clone)]
+pub struct LatencyHistogram {
+    counts: Vec<u64>,
+    total: u64,
+    max_us: u64,
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        Self {
+            counts: vec![0; BUCKETS],
+            total: 0,
+            max_us: 0,
+        }
+    }
+
+    pub fn record(&mut self, micros: u64) {
+        self.counts[bucket_of(micros)] += 1;
+        self.total += 1;
+        self.max_us = self.max_us.max(micros);
+    }
+
+    pub fn max_us(&self) -> u64 {
+        self.max_us
+    }
+
+    /// Lower bound of the bucket holding the given quantile (0.0..=1.0)
+    pub fn quantile_us(&self, quantile: f64) -> u64 {
+        if self.total == 0 {
+            return 0;
+        }
+        let rank = ((self.total as f64) * quantile.clamp(0.0, 1.0))
+            .ceil()
+            .max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return lower_bound(bucket).min(self.max_us);
+            }
+        }
+        self.max_us
+    }
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn bucket_of(micros: u64) -> usize {
+    if micros < SUB_BUCKETS {
+        return micros as usize;
+    }
+    let exponent = 63 - micros.leading_zeros();
+    let shift = exponent - SUB_BITS;
+    let sub = (micros >> shift) & (SUB_BUCKETS - 1);
+    (SUB_BUCKETS * (shift as u64 + 1) + sub) as usize
+}
+
+fn lower_bound(bucket: usize) -> u64 {
+    let bucket = bucket as u64;
+    if bucket < SUB_BUCKETS {
+        return bucket;
+    }
+    let shift = bucket / SUB_BUCKETS - 1;
+    (SUB_BUCKETS + bucket % SUB_BUCKETS) << shift
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quantiles_within_bucket_error() {
+        let mut histogram = LatencyHistogram::new();
+        for micros in 1..=10_000 {
+            histogram.record(micros);
+        }
+        assert_eq!(histogram.max_us(), 10_000);
+        for (quantile, exact) in [(0.5, 5_000.0), (0.99, 9_900.0)] {
+            let estimate = histogram.quantile_us(quantile) as f64;
+            assert!((estimate - exact).abs() / exact < 0.07, "{}", estimate);
+        }
+        assert_eq!(histogram.quantile_us(0.0), 1);
+        assert_eq!(lower_bound(bucket_of(u64::MAX)), 31 << 59);
+    }
+}
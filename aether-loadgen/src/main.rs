@@ -0,0 +1,400 @@
+//! Aether load generator - drive waves at a configured rate and report
+//! throughput, latency, drops and memory
+//!
+//! Connects like the other binaries (`config/default.toml`,
+//! `config/aether-loadgen.toml`); `--in-memory` runs against a local layer.
+//! Waves are emitted on `<prefix>.<n>` and received back through a
+//! `<prefix>.>` subscription, so latency is measured emit-to-receive.
+
+mod histogram;
+
+use aether_core::{
+    load_config, resource_monitoring::current_rss_bytes, start_resource_monitoring, Aether,
+    AetherConfig, Channel, ChaosConfig, ResourceMonitorConfig, Wave,
+};
+use anyhow::{bail, Context};
+use clap::{Parser, ValueEnum};
+use histogram::LatencyHistogram;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+/// Producers top up their budget this often
+const TICK: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "aether-loadgen",
+    about = "Drive load through Aether and report capacity"
+)]
+struct Args {
+    /// Total waves per second across all producers (0 = as fast as possible)
+    #[arg(long, default_value_t = 1000)]
+    rate: u64,
+    /// How long to generate load
+    #[arg(long, default_value_t = 30)]
+    duration_secs: u64,
+    /// Padding added to each payload
+    #[arg(long, default_value_t = 256)]
+    payload_bytes: usize,
+    /// Number of channels the waves are spread over
+    #[arg(long, default_value_t = 16)]
+    channels: usize,
+    /// Concurrent producer tasks
+    #[arg(long, default_value_t = 4)]
+    producers: usize,
+    /// Waves go to `<prefix>.<n>`
+    #[arg(long, default_value = "loadgen")]
+    channel_prefix: String,
+    /// Faults injected before transmission
+    #[arg(long, value_enum, default_value_t = FaultProfile::None)]
+    faults: FaultProfile,
+    /// Seed for the fault profile
+    #[arg(long, default_value_t = 1)]
+    seed: u64,
+    /// Seconds between progress lines
+    #[arg(long, default_value_t = 5)]
+    report_interval_secs: u64,
+    /// How long to wait for in-flight waves after the last emit
+    #[arg(long, default_value_t = 2000)]
+    drain_ms: u64,
+    /// Use an in-process layer instead of NATS
+    #[arg(long)]
+    in_memory: bool,
+    /// Print the final report as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum FaultProfile {
+    /// No injected faults
+    None,
+    /// 1% of waves lost
+    Lossy,
+    /// Up to 20ms added before transmission
+    Jittery,
+    /// Loss, duplicates, jitter and reordering together
+    Chaotic,
+}
+
+impl FaultProfile {
+    fn chaos(self, seed: u64) -> Option<ChaosConfig> {
+        let base = ChaosConfig {
+            seed,
+            ..ChaosConfig::default()
+        };
+        match self {
+            FaultProfile::None => None,
+            FaultProfile::Lossy => Some(ChaosConfig {
+                drop_probability: 0.01,
+                ..base
+            }),
+            FaultProfile::Jittery => Some(ChaosConfig {
+                latency_jitter_ms: 20,
+                ..base
+            }),
+            FaultProfile::Chaotic => Some(ChaosConfig {
+                drop_probability: 0.01,
+                duplicate_probability: 0.01,
+                latency_jitter_ms: 10,
+                reorder_window: 8,
+                ..base
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    sent: AtomicU64,
+    send_errors: AtomicU64,
+    received: AtomicU64,
+    /// Waves a slow receiver skipped
+    lagged: AtomicU64,
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    elapsed_secs: f64,
+    sent: u64,
+    send_errors: u64,
+    received: u64,
+    lagged: u64,
+    /// Sent but never received (duplicates can hide drops)
+    dropped: u64,
+    throughput_per_sec: f64,
+    latency_us: LatencySummary,
+    peak_rss_bytes: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct LatencySummary {
+    p50: u64,
+    p90: u64,
+    p99: u64,
+    p999: u64,
+    max: u64,
+}
+
+impl From<&LatencyHistogram> for LatencySummary {
+    fn from(histogram: &LatencyHistogram) -> Self {
+        Self {
+            p50: histogram.quantile_us(0.50),
+            p90: histogram.quantile_us(0.90),
+            p99: histogram.quantile_us(0.99),
+            p999: histogram.quantile_us(0.999),
+            max: histogram.max_us(),
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("warn")),
+        )
+        .init();
+
+    let args = Args::parse();
+    if args.channels == 0 || args.producers == 0 {
+        bail!("--channels and --producers must be at least 1");
+    }
+
+    let mut config = if args.in_memory {
+        AetherConfig {
+            use_nats: false,
+            ..AetherConfig::default()
+        }
+    } else {
+        load_config("aether-loadgen")
+            .context("failed to load config")?
+            .aether_config()
+    };
+    config.chaos = args.faults.chaos(args.seed);
+    let auth_token = config.auth_token.clone();
+    let aether = Aether::new(config);
+    let _monitor = start_resource_monitoring(ResourceMonitorConfig::default());
+
+    let mut receiver = aether
+        .subscribe(&Channel::new(format!("{}.>", args.channel_prefix)))
+        .await;
+    wait_for_loopback(&aether, &mut receiver, &args.channel_prefix, &auth_token).await?;
+
+    let start = Instant::now();
+    let counters = Arc::new(Counters::default());
+    let latencies = Arc::new(Mutex::new(LatencyHistogram::new()));
+    let receiving = tokio::spawn(receive(
+        receiver,
+        start,
+        Arc::clone(&counters),
+        Arc::clone(&latencies),
+    ));
+
+    let channels: Arc<[Channel]> = (0..args.channels)
+        .map(|n| Channel::new(format!("{}.{}", args.channel_prefix, n)))
+        .collect();
+    let padding = "x".repeat(args.payload_bytes);
+    let deadline = start + Duration::from_secs(args.duration_secs);
+    let producers: Vec<_> = (0..args.producers)
+        .map(|producer| {
+            let rate = share(args.rate, args.producers, producer);
+            let producer = Producer {
+                aether: aether.clone(),
+                channels: Arc::clone(&channels),
+                offset: producer,
+                padding: padding.clone(),
+                auth_token: auth_token.clone(),
+                counters: Arc::clone(&counters),
+            };
+            tokio::spawn(producer.run(rate, start, deadline))
+        })
+        .collect();
+
+    let mut peak_rss = None;
+    let mut ticker = tokio::time::interval(Duration::from_secs(args.report_interval_secs.max(1)));
+    ticker.tick().await;
+    while Instant::now() < deadline {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = tokio::time::sleep_until(deadline.into()) => break,
+        }
+        peak_rss = peak_rss.max(current_rss_bytes());
+        let summary = LatencySummary::from(&*latencies.lock().expect("latency lock poisoned"));
+        eprintln!(
+            "[{:>4}s] sent {} received {} errors {} lagged {} p99 {}us rss {}",
+            start.elapsed().as_secs(),
+            counters.sent.load(Ordering::Relaxed),
+            counters.received.load(Ordering::Relaxed),
+            counters.send_errors.load(Ordering::Relaxed),
+            counters.lagged.load(Ordering::Relaxed),
+            summary.p99,
+            format_rss(current_rss_bytes()),
+        );
+    }
+
+    for producer in producers {
+        producer.await?;
+    }
+    let emitting = start.elapsed();
+    tokio::time::sleep(Duration::from_millis(args.drain_ms)).await;
+    receiving.abort();
+    peak_rss = peak_rss.max(current_rss_bytes());
+
+    let sent = counters.sent.load(Ordering::Relaxed);
+    let received = counters.received.load(Ordering::Relaxed);
+    let report = Report {
+        elapsed_secs: emitting.as_secs_f64(),
+        sent,
+        send_errors: counters.send_errors.load(Ordering::Relaxed),
+        received,
+        lagged: counters.lagged.load(Ordering::Relaxed),
+        dropped: sent.saturating_sub(received),
+        throughput_per_sec: received as f64 / emitting.as_secs_f64(),
+        latency_us: LatencySummary::from(&*latencies.lock().expect("latency lock poisoned")),
+        peak_rss_bytes: peak_rss,
+    };
+    print_report(&report, args.json)
+}
+
+struct Producer {
+    aether: Aether,
+    channels: Arc<[Channel]>,
+    /// First channel, so producers start spread out
+    offset: usize,
+    padding: String,
+    auth_token: Option<String>,
+    counters: Arc<Counters>,
+}
+
+impl Producer {
+    /// Emit round-robin over the channels until the deadline
+    async fn run(self, rate: u64, start: Instant, deadline: Instant) {
+        let mut ticker = tokio::time::interval(TICK);
+        let mut budget = 0.0;
+        let mut next = self.offset;
+        while Instant::now() < deadline {
+            let batch = if rate == 0 {
+                tokio::task::yield_now().await;
+                64
+            } else {
+                ticker.tick().await;
+                budget += rate as f64 * TICK.as_secs_f64();
+                let batch = budget.floor();
+                budget -= batch;
+                batch as u64
+            };
+            for _ in 0..batch {
+                let channel = self.channels[next % self.channels.len()].clone();
+                next += 1;
+                let mut wave = Wave::builder(channel)
+                    .payload(serde_json::json!({
+                        "t_us": start.elapsed().as_micros() as u64,
+                        "padding": self.padding,
+                    }))
+                    .source("aether-loadgen")
+                    .build();
+                if let Some(token) = &self.auth_token {
+                    wave.set_auth_token(token.clone());
+                }
+                let counter = match self.aether.emit(wave).await {
+                    Ok(_) => &self.counters.sent,
+                    Err(_) => &self.counters.send_errors,
+                };
+                counter.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Record emit-to-receive latency for every wave that comes back
+async fn receive(
+    mut receiver: broadcast::Receiver<Wave>,
+    start: Instant,
+    counters: Arc<Counters>,
+    latencies: Arc<Mutex<LatencyHistogram>>,
+) {
+    loop {
+        match receiver.recv().await {
+            Ok(wave) => {
+                let Some(sent_us) = wave.payload()["t_us"].as_u64() else {
+                    continue;
+                };
+                let now_us = start.elapsed().as_micros() as u64;
+                counters.received.fetch_add(1, Ordering::Relaxed);
+                latencies
+                    .lock()
+                    .expect("latency lock poisoned")
+                    .record(now_us.saturating_sub(sent_us));
+            }
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                counters.lagged.fetch_add(skipped, Ordering::Relaxed);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Emit probes until one comes back, so the run does not count subscription setup as loss
+async fn wait_for_loopback(
+    aether: &Aether,
+    receiver: &mut broadcast::Receiver<Wave>,
+    prefix: &str,
+    auth_token: &Option<String>,
+) -> anyhow::Result<()> {
+    let probe = Channel::new(format!("{}.probe", prefix));
+    for _ in 0..50 {
+        let mut wave = Wave::builder(probe.clone())
+            .source("aether-loadgen")
+            .build();
+        if let Some(token) = auth_token {
+            wave.set_auth_token(token.clone());
+        }
+        aether.emit(wave).await.context("probe emit failed")?;
+        if let Ok(Ok(_)) = tokio::time::timeout(Duration::from_millis(100), receiver.recv()).await {
+            return Ok(());
+        }
+    }
+    bail!("no probe wave came back on {}; is NATS reachable?", probe)
+}
+
+/// This producer's part of the total rate
+fn share(rate: u64, producers: usize, producer: usize) -> u64 {
+    let producers = producers as u64;
+    rate / producers + u64::from((producer as u64) < rate % producers)
+}
+
+fn format_rss(bytes: Option<u64>) -> String {
+    match bytes {
+        Some(bytes) => format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0)),
+        None => "n/a".to_string(),
+    }
+}
+
+fn print_report(report: &Report, json: bool) -> anyhow::Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(report)?);
+        return Ok(());
+    }
+    println!("elapsed     {:.1}s", report.elapsed_secs);
+    println!(
+        "waves       sent {} received {} dropped {} lagged {} send errors {}",
+        report.sent, report.received, report.dropped, report.lagged, report.send_errors
+    );
+    println!("throughput  {:.0} waves/s", report.throughput_per_sec);
+    println!(
+        "latency     p50 {}us p90 {}us p99 {}us p99.9 {}us max {}us",
+        report.latency_us.p50,
+        report.latency_us.p90,
+        report.latency_us.p99,
+        report.latency_us.p999,
+        report.latency_us.max
+    );
+    println!("peak rss    {}", format_rss(report.peak_rss_bytes));
+    Ok(())
+}
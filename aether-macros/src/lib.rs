@@ -0,0 +1,197 @@
+//! Procedural macros for Aether services, re-exported from `aether_core`
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Expr, FnArg, ItemFn, Lit, Type};
+
+/// Turn an async fn into an `aether_core::WaveHandler` for a channel
+///
+/// Parameters are recognised by type: a reference is the router state, a
+/// `Wave` is the raw wave, and any other type is the payload, decoded from
+/// JSON. The fn may return `()`, `Result<_, AetherError>` or
+/// `anyhow::Result<_>`.
+///
+/// ```ignore
+/// #[aether_core::handler(channel = "orders.created")]
+/// async fn order_created(service: &Service, order: OrderCreated) -> anyhow::Result<()> {
+///     service.reserve(&order.items).await
+/// }
+///
+/// let router = WaveRouter::new(service).route(order_created);
+/// ```
+///
+/// The fn name becomes a unit struct; the original body stays callable as
+/// `order_created::call(..)`. `channel` is a string literal (checked at
+/// compile time) or a `TypedChannel` constant.
+#[proc_macro_attribute]
+pub fn handler(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut channel: Option<Expr> = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("channel") {
+            channel = Some(meta.value()?.parse()?);
+            Ok(())
+        } else {
+            Err(meta.error("expected `channel = ...`"))
+        }
+    });
+    parse_macro_input!(attr with parser);
+    let function = parse_macro_input!(item as ItemFn);
+
+    let Some(channel) = channel else {
+        return syn::Error::new(function.sig.ident.span(), "missing `channel = ...`")
+            .to_compile_error()
+            .into();
+    };
+    expand(channel, function)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+enum Param {
+    State(Type),
+    Wave,
+    Payload(Type),
+}
+
+fn classify(arg: &FnArg) -> syn::Result<Param> {
+    let FnArg::Typed(arg) = arg else {
+        return Err(syn::Error::new(arg.span(), "handlers cannot take `self`"));
+    };
+    Ok(match &*arg.ty {
+        Type::Reference(reference) if reference.mutability.is_none() => {
+            Param::State((*reference.elem).clone())
+        }
+        Type::Reference(reference) => {
+            return Err(syn::Error::new(
+                reference.span(),
+                "state must be a shared reference",
+            ))
+        }
+        Type::Path(path) if path.path.segments.last().is_some_and(|s| s.ident == "Wave") => {
+            Param::Wave
+        }
+        ty => Param::Payload(ty.clone()),
+    })
+}
+
+fn expand(channel: Expr, function: ItemFn) -> syn::Result<TokenStream2> {
+    if function.sig.asyncness.is_none() {
+        return Err(syn::Error::new(
+            function.sig.fn_token.span(),
+            "handlers must be async",
+        ));
+    }
+    if !function.sig.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            function.sig.generics.span(),
+            "handlers cannot be generic",
+        ));
+    }
+
+    let channel = match channel {
+        Expr::Lit(lit) if matches!(lit.lit, Lit::Str(_)) => quote! {
+            ::aether_core::TypedChannel::<::aether_core::__private::serde_json::Value>::new(#lit)
+                .name()
+        },
+        Expr::Path(path) => quote! { #path.name() },
+        other => {
+            return Err(syn::Error::new(
+                other.span(),
+                "channel must be a string literal or a TypedChannel constant",
+            ))
+        }
+    };
+
+    let mut state = None;
+    let mut payload = None;
+    let mut args = Vec::new();
+    let mut takes_wave = false;
+    for input in &function.sig.inputs {
+        let duplicate =
+            |what: &str| syn::Error::new(input.span(), format!("more than one {}", what));
+        match classify(input)? {
+            Param::State(ty) => {
+                if state.replace(ty).is_some() {
+                    return Err(duplicate("state parameter"));
+                }
+                args.push(quote! { state });
+            }
+            Param::Wave => {
+                if std::mem::replace(&mut takes_wave, true) {
+                    return Err(duplicate("wave parameter"));
+                }
+                args.push(quote! { wave });
+            }
+            Param::Payload(ty) => {
+                if payload.replace(ty).is_some() {
+                    return Err(duplicate("payload parameter"));
+                }
+                args.push(quote! { payload });
+            }
+        }
+    }
+
+    let decode = payload.map(|ty| {
+        quote! { let payload: #ty = ::aether_core::__private::decode_payload(&wave)?; }
+    });
+    let (impl_generics, state) = match state {
+        Some(ty) => (quote! {}, quote! { #ty }),
+        None => (quote! { <S: Sync> }, quote! { S }),
+    };
+
+    let ItemFn {
+        attrs,
+        vis,
+        mut sig,
+        block,
+    } = function;
+    let name = sig.ident.clone();
+    sig.ident = format_ident!("call");
+    let (docs, attrs): (Vec<_>, Vec<_>) = attrs.into_iter().partition(|a| a.path().is_ident("doc"));
+
+    Ok(quote! {
+        #(#docs)*
+        #[allow(non_camel_case_types)]
+        #[derive(Debug, Clone, Copy)]
+        #vis struct #name;
+
+        impl #name {
+            #(#attrs)*
+            #vis #sig #block
+        }
+
+        impl #impl_generics ::aether_core::WaveHandler<#state> for #name {
+            fn channel(&self) -> &'static str {
+                const CHANNEL: &str = #channel;
+                CHANNEL
+            }
+
+            fn name(&self) -> &'static str {
+                stringify!(#name)
+            }
+
+            fn handle<'a>(
+                &'a self,
+                state: &'a #state,
+                wave: ::aether_core::Wave,
+            ) -> ::aether_core::__private::BoxFuture<
+                'a,
+                ::aether_core::__private::anyhow::Result<()>,
+            > {
+                let _ = state;
+                ::std::boxed::Box::pin(::aether_core::__private::run_handler(
+                    stringify!(#name),
+                    wave.channel().clone(),
+                    async move {
+                        #decode
+                        ::aether_core::IntoHandlerResult::into_handler_result(
+                            Self::call(#(#args),*).await,
+                        )
+                    },
+                ))
+            }
+        }
+    })
+}
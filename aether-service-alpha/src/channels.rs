@@ -0,0 +1,11 @@
+//! Channels used by Service Alpha
+
+aether_core::channels! {
+    pub ORDERS_ALL = "orders.*";
+    pub ORDERS_CREATED = "orders.created";
+    pub ORDERS_CONFIRMED = "orders.confirmed";
+    pub ORDERS_COMPLETED = "orders.completed";
+    pub INVENTORY_CHECK = "inventory.check";
+    pub PAYMENTS_REQUEST = "payments.request";
+    pub PAYMENTS_COMPLETED = "payments.completed";
+}
@@ -0,0 +1,21 @@
+//! Channels used by Service Beta
+
+aether_core::channels! {
+    pub INVENTORY_ALL = "inventory.*";
+    pub INVENTORY_CHECK = "inventory.check" => InventoryCheck {
+        pub order_id: Option<String>,
+        pub items: Vec<String>,
+        pub total: Option<serde_json::Value>,
+    };
+    pub INVENTORY_RESERVE = "inventory.reserve" => InventoryReserve {
+        pub order_id: Option<String>,
+        #[serde(default)]
+        pub items: Vec<String>,
+    };
+    pub INVENTORY_RESERVED = "inventory.reserved";
+    pub INVENTORY_RESTOCKED = "inventory.restocked";
+    pub INVENTORY_AVAILABLE = "inventory.available";
+    pub INVENTORY_UNAVAILABLE = "inventory.unavailable";
+    pub ORDERS_CREATED = "orders.created";
+    pub ORDERS_CONFIRMED = "orders.confirmed";
+}
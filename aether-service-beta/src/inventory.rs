@@ -0,0 +1,51 @@
+//! Inventory levels derived from `inventory.*` waves
+
+use crate::channels::{INVENTORY_RESERVED, INVENTORY_RESTOCKED};
+use aether_core::{Channel, Projection, Wave};
+use std::collections::BTreeMap;
+
+/// Stock per item: restocks add quantities, each reserved item takes one
+pub struct InventoryProjection;
+
+impl Projection for InventoryProjection {
+    type State = BTreeMap<String, i32>;
+    type Delta = Vec<(String, i32)>;
+
+    fn name(&self) -> &str {
+        "inventory"
+    }
+
+    fn pattern(&self) -> Channel {
+        Channel::new("inventory.*")
+    }
+
+    fn apply(&self, wave: &Wave) -> Option<Self::Delta> {
+        let items = wave.payload().get("items")?;
+        let channel = wave.channel().name();
+        if channel == INVENTORY_RESTOCKED.name() {
+            let restocked = items.as_object()?;
+            Some(
+                restocked
+                    .iter()
+                    .filter_map(|(item, quantity)| Some((item.clone(), quantity.as_i64()? as i32)))
+                    .collect(),
+            )
+        } else if channel == INVENTORY_RESERVED.name() {
+            let reserved = items.as_array()?;
+            Some(
+                reserved
+                    .iter()
+                    .filter_map(|item| Some((item.as_str()?.to_string(), -1)))
+                    .collect(),
+            )
+        } else {
+            None
+        }
+    }
+
+    fn fold(&self, state: &mut Self::State, delta: Self::Delta) {
+        for (item, change) in delta {
+            *state.entry(item).or_default() += change;
+        }
+    }
+}